@@ -0,0 +1,75 @@
+use crate::command::BldCommand;
+use actix_web::rt::System;
+use anyhow::{Result, bail};
+use bld_config::BldConfig;
+use bld_http::HttpClient;
+use bld_models::dtos::{AccessRole, AccessSubject, AddAccessGrantRequest};
+use bld_utils::sync::IntoArc;
+use clap::{ArgGroup, Args};
+
+#[derive(Args)]
+#[command(about = "Grants a role for the pipelines of a server to a user or a group")]
+#[command(group(ArgGroup::new("subject").required(true).args(["user", "group"])))]
+pub struct AccessAddCommand {
+    #[arg(long = "verbose", help = "Sets the level of verbosity")]
+    verbose: bool,
+
+    #[arg(
+        short = 's',
+        long = "server",
+        help = "The name of the server to add the access grant to"
+    )]
+    server: String,
+
+    #[arg(
+        short = 'r',
+        long = "role",
+        required = true,
+        help = "The role to grant, one of viewer, runner, maintainer or admin"
+    )]
+    role: AccessRole,
+
+    #[arg(
+        short = 'u',
+        long = "user",
+        help = "The user that the role is granted to"
+    )]
+    user: Option<String>,
+
+    #[arg(
+        short = 'g',
+        long = "group",
+        help = "The group that the role is granted to"
+    )]
+    group: Option<String>,
+
+    #[arg(
+        short = 'p',
+        long = "pipeline",
+        default_value = "",
+        help = "The folder or pipeline that the role applies to, matched by whole path segments. If not provided the role applies to the whole server"
+    )]
+    pipeline: String,
+}
+
+impl BldCommand for AccessAddCommand {
+    fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    fn exec(self) -> Result<()> {
+        let (subject_type, subject) = match (self.user, self.group) {
+            (Some(user), _) => (AccessSubject::User, user),
+            (_, Some(group)) => (AccessSubject::Group, group),
+            (None, None) => bail!("either a user or a group is required"),
+        };
+
+        System::new().block_on(async move {
+            let config = BldConfig::load().await?.into_arc();
+            let client = HttpClient::new(config, &self.server)?;
+            let request =
+                AddAccessGrantRequest::new(self.role, subject_type, subject, self.pipeline);
+            client.access_add(&request).await
+        })
+    }
+}
@@ -0,0 +1,29 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use super::{add::AccessAddCommand, list::AccessListCommand, remove::AccessRemoveCommand};
+use crate::command::BldCommand;
+
+#[derive(Subcommand)]
+pub enum AccessCommands {
+    Ls(AccessListCommand),
+    Add(AccessAddCommand),
+    Rm(AccessRemoveCommand),
+}
+
+#[derive(Parser)]
+#[command(about = "Manage the access grants of a server")]
+pub struct AccessCommand {
+    #[command(subcommand)]
+    command: AccessCommands,
+}
+
+impl AccessCommand {
+    pub fn invoke(self) -> Result<()> {
+        match self.command {
+            AccessCommands::Ls(list) => list.invoke(),
+            AccessCommands::Add(add) => add.invoke(),
+            AccessCommands::Rm(remove) => remove.invoke(),
+        }
+    }
+}
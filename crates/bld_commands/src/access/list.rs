@@ -0,0 +1,64 @@
+use crate::command::BldCommand;
+use actix_web::rt::System;
+use anyhow::Result;
+use bld_config::BldConfig;
+use bld_http::HttpClient;
+use bld_utils::sync::IntoArc;
+use clap::Args;
+use tabled::{Table, Tabled, settings::Style};
+
+#[derive(Tabled)]
+struct AccessGrantRow<'a> {
+    pub id: &'a str,
+    pub role: String,
+    pub subject_type: String,
+    pub subject: &'a str,
+    pub pipeline: &'a str,
+    pub date_created: &'a str,
+}
+
+#[derive(Args)]
+#[command(about = "Lists the access grants of a server")]
+pub struct AccessListCommand {
+    #[arg(long = "verbose", help = "Sets the level of verbosity")]
+    verbose: bool,
+
+    #[arg(
+        short = 's',
+        long = "server",
+        help = "The name of the server to list the access grants from"
+    )]
+    server: String,
+}
+
+impl BldCommand for AccessListCommand {
+    fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    fn exec(self) -> Result<()> {
+        System::new().block_on(async move {
+            let config = BldConfig::load().await?.into_arc();
+            let client = HttpClient::new(config, &self.server)?;
+            let response = client.access_list().await?;
+
+            if !response.is_empty() {
+                let data: Vec<AccessGrantRow> = response
+                    .iter()
+                    .map(|g| AccessGrantRow {
+                        id: &g.id,
+                        role: g.role.to_string(),
+                        subject_type: g.subject_type.to_string(),
+                        subject: &g.subject,
+                        pipeline: &g.pipeline,
+                        date_created: &g.date_created,
+                    })
+                    .collect();
+                let table = Table::new(data).with(Style::modern()).to_string();
+                println!("{table}");
+            }
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,4 @@
+mod add;
+pub mod command;
+mod list;
+mod remove;
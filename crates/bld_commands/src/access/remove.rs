@@ -0,0 +1,38 @@
+use crate::command::BldCommand;
+use actix_web::rt::System;
+use anyhow::Result;
+use bld_config::BldConfig;
+use bld_http::HttpClient;
+use bld_utils::sync::IntoArc;
+use clap::Args;
+
+#[derive(Args)]
+#[command(about = "Removes an access grant from a server")]
+pub struct AccessRemoveCommand {
+    #[arg(long = "verbose", help = "Sets the level of verbosity")]
+    verbose: bool,
+
+    #[arg(required = true, help = "The id of the access grant to remove")]
+    id: String,
+
+    #[arg(
+        short = 's',
+        long = "server",
+        help = "The name of the server to remove the access grant from"
+    )]
+    server: String,
+}
+
+impl BldCommand for AccessRemoveCommand {
+    fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    fn exec(self) -> Result<()> {
+        System::new().block_on(async move {
+            let config = BldConfig::load().await?.into_arc();
+            let client = HttpClient::new(config, &self.server)?;
+            client.access_remove(&self.id).await
+        })
+    }
+}
@@ -0,0 +1,60 @@
+use crate::command::BldCommand;
+use actix_web::rt::System;
+use anyhow::{Result, bail};
+use bld_config::BldConfig;
+use bld_supervisor::agent;
+use clap::Args;
+use tracing::{debug, error};
+
+#[derive(Args)]
+#[command(
+    about = "Starts an agent that connects to the supervisor of a bld server and runs the pipelines that need its labels. The agent's workers read the pipelines and the state of the runs and write their logs directly, so the agent must run with the server's .bld directory, logs path and database, and set the same local.supervisor.agent_token as the supervisor. Agents on hosts without access to these aren't supported"
+)]
+pub struct AgentCommand {
+    #[arg(long = "verbose", help = "Sets the level of verbosity")]
+    verbose: bool,
+
+    #[arg(
+        short = 'n',
+        long = "name",
+        required = true,
+        help = "The name of the agent"
+    )]
+    name: String,
+
+    #[arg(
+        short = 'l',
+        long = "label",
+        help = "A label of the agent that pipelines can use in their runs_on section. Can be used multiple times"
+    )]
+    labels: Vec<String>,
+
+    #[arg(
+        short = 'c',
+        long = "capacity",
+        help = "The number of runs that the agent can have active at the same time. Defaults to the number of workers of the supervisor config"
+    )]
+    capacity: Option<usize>,
+}
+
+impl BldCommand for AgentCommand {
+    fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    fn exec(self) -> Result<()> {
+        System::new().block_on(async move {
+            let config = BldConfig::load().await?;
+            let capacity = match self.capacity {
+                Some(capacity) => capacity,
+                None => config.local.supervisor.workers.try_into()?,
+            };
+            debug!("starting agent {}", self.name);
+            if let Err(e) = agent::start(config, self.name, self.labels, capacity).await {
+                error!("{e}");
+                bail!("")
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,50 @@
+use crate::command::BldCommand;
+use actix_web::rt::System;
+use anyhow::Result;
+use bld_config::BldConfig;
+use bld_http::HttpClient;
+use bld_utils::sync::IntoArc;
+use clap::Args;
+
+#[derive(Args)]
+#[command(about = "Approves or rejects a run on a server that is waiting for approval")]
+pub struct ApproveCommand {
+    #[arg(long = "verbose", help = "Sets the level of verbosity")]
+    verbose: bool,
+
+    #[arg(help = "The id of a run on a server")]
+    run_id: String,
+
+    #[arg(
+        short = 's',
+        long = "server",
+        help = "The name of the server that the file is running on"
+    )]
+    server: String,
+
+    #[arg(
+        short = 'j',
+        long = "job",
+        help = "The job whose approval to decide on, needed when the run waits on the approval of more than one job"
+    )]
+    job: Option<String>,
+
+    #[arg(long = "reject", help = "Rejects the run instead of approving it")]
+    reject: bool,
+}
+
+impl BldCommand for ApproveCommand {
+    fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    fn exec(self) -> Result<()> {
+        System::new().block_on(async move {
+            let config = BldConfig::load().await?.into_arc();
+            let client = HttpClient::new(config, &self.server)?;
+            client
+                .approve(&self.run_id, self.job.as_deref(), !self.reject)
+                .await
+        })
+    }
+}
@@ -1,3 +1,6 @@
+use crate::access::command::AccessCommand;
+use crate::agent::AgentCommand;
+use crate::approve::ApproveCommand;
 use crate::auth::AuthCommand;
 use crate::cat::CatCommand;
 use crate::check::CheckCommand;
@@ -14,9 +17,11 @@ use crate::pull::PullCommand;
 use crate::push::PushCommand;
 use crate::remove::RemoveCommand;
 use crate::run::RunCommand;
+use crate::secrets::command::SecretsCommand;
 use crate::server::ServerCommand;
 use crate::stop::StopCommand;
 use crate::supervisor::SupervisorCommand;
+use crate::trigger::command::TriggerCommand;
 use crate::worker::WorkerCommand;
 use crate::{add::AddCommand, artifacts::command::ArtifactsCommand, cron::command::CronCommand};
 use anyhow::Result;
@@ -26,7 +31,10 @@ use clap::{Parser, Subcommand};
 #[derive(Subcommand)]
 enum Commands {
     Login(AuthCommand),
+    Access(AccessCommand),
+    Agent(AgentCommand),
     Artifacts(ArtifactsCommand),
+    Approve(ApproveCommand),
     Cat(CatCommand),
     Check(CheckCommand),
     Config(ConfigCommand),
@@ -43,9 +51,11 @@ enum Commands {
     Push(PushCommand),
     Rm(RemoveCommand),
     Run(RunCommand),
+    Secrets(SecretsCommand),
     Server(ServerCommand),
     Stop(StopCommand),
     Supervisor(SupervisorCommand),
+    Trigger(TriggerCommand),
     Worker(WorkerCommand),
 }
 
@@ -61,7 +71,10 @@ impl Cli {
     pub fn invoke(self) -> Result<()> {
         match self.command {
             Commands::Login(auth) => auth.invoke(),
+            Commands::Access(access) => access.invoke(),
+            Commands::Agent(agent) => agent.invoke(),
             Commands::Artifacts(artifacts) => artifacts.invoke(),
+            Commands::Approve(approve) => approve.invoke(),
             Commands::Cat(cat) => cat.invoke(),
             Commands::Check(check) => check.invoke(),
             Commands::Config(config) => config.invoke(),
@@ -78,9 +91,11 @@ impl Cli {
             Commands::Push(push) => push.invoke(),
             Commands::Rm(remove) => remove.invoke(),
             Commands::Run(run) => run.invoke(),
+            Commands::Secrets(secrets) => secrets.invoke(),
             Commands::Server(server) => server.invoke(),
             Commands::Stop(stop) => stop.invoke(),
             Commands::Supervisor(supervisor) => supervisor.invoke(),
+            Commands::Trigger(trigger) => trigger.invoke(),
             Commands::Worker(worker) => worker.invoke(),
         }
     }
@@ -43,6 +43,14 @@ pub struct CronAddCommand {
         help = "Define value for an environment variable. Can be used multiple times"
     )]
     env: Vec<String>,
+
+    #[arg(
+        short = 'p',
+        long = "priority",
+        default_value = "0",
+        help = "The priority of the runs of the cron job. Runs with a higher priority leave the queue first"
+    )]
+    priority: i32,
 }
 
 impl BldCommand for CronAddCommand {
@@ -56,7 +64,8 @@ impl BldCommand for CronAddCommand {
             let client = HttpClient::new(config, &self.server)?;
             let inputs = Some(parse_variables(&self.inputs));
             let env = Some(parse_variables(&self.env));
-            let request = AddJobRequest::new(self.schedule, self.file, inputs, env, false);
+            let request =
+                AddJobRequest::new(self.schedule, self.file, inputs, env, false, self.priority);
             client.cron_add(&request).await
         })
     }
@@ -14,6 +14,7 @@ struct JobInfoRow<'a> {
     pub schedule: &'a str,
     pub file: &'a str,
     pub is_default: bool,
+    pub priority: i32,
     pub date_created: &'a str,
     pub date_updated: &'a str,
 }
@@ -82,6 +83,7 @@ impl BldCommand for CronListCommand {
                         schedule: &j.schedule,
                         file: &j.pipeline,
                         is_default: j.is_default,
+                        priority: j.priority,
                         date_created: &j.date_created,
                         date_updated: j.date_updated.as_deref().unwrap_or(""),
                     })
@@ -43,6 +43,13 @@ pub struct CronUpdateCommand {
         help = "Define value for an environment variable. Can be used multiple times"
     )]
     env: Vec<String>,
+
+    #[arg(
+        short = 'p',
+        long = "priority",
+        help = "The priority of the runs of the cron job, kept as is when omitted. Runs with a higher priority leave the queue first"
+    )]
+    priority: Option<i32>,
 }
 
 impl BldCommand for CronUpdateCommand {
@@ -56,7 +63,8 @@ impl BldCommand for CronUpdateCommand {
             let client = HttpClient::new(config, &self.server)?;
             let inputs = Some(parse_variables(&self.inputs));
             let env = Some(parse_variables(&self.env));
-            let update_job = UpdateJobRequest::new(self.id, self.schedule, inputs, env);
+            let update_job =
+                UpdateJobRequest::new(self.id, self.schedule, inputs, env, self.priority);
             client.cron_update(&update_job).await
         })
     }
@@ -3,9 +3,10 @@ use actix_web::rt::System;
 use anyhow::Result;
 use bld_config::BldConfig;
 use bld_http::HttpClient;
-use bld_models::dtos::HistoryEntry;
+use bld_models::dtos::{HistoryEntry, HistoryEntryDetails};
 use bld_utils::sync::IntoArc;
 use clap::Args;
+use std::collections::HashMap;
 use tabled::{Table, Tabled, settings::Style};
 use tracing::debug;
 
@@ -27,13 +28,59 @@ impl HistoryEntryRow {
     }
 }
 
+#[derive(Tabled)]
+struct HistoryEntryDetailsRow {
+    pub name: String,
+    pub id: String,
+    pub state: String,
+    pub trigger: String,
+    pub inputs: String,
+    pub env: String,
+    pub outputs: String,
+}
+
+impl HistoryEntryDetailsRow {
+    fn display_map(values: &HashMap<String, String>) -> String {
+        let mut values: Vec<String> = values.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        values.sort();
+        values.join("\n")
+    }
+}
+
+impl From<HistoryEntry> for HistoryEntryDetailsRow {
+    fn from(value: HistoryEntry) -> Self {
+        let details = value.details.clone().unwrap_or_default();
+        let mut outputs: Vec<String> = details
+            .outputs
+            .iter()
+            .flat_map(|(job, outputs)| {
+                outputs
+                    .iter()
+                    .map(move |(name, value)| format!("{job}.{name}={value}"))
+            })
+            .collect();
+        outputs.sort();
+
+        let HistoryEntryDetails { inputs, env, .. } = details;
+        Self {
+            state: value.display_state(),
+            trigger: value.display_trigger(),
+            name: value.name,
+            id: value.id,
+            inputs: Self::display_map(&inputs),
+            env: Self::display_map(&env),
+            outputs: outputs.join("\n"),
+        }
+    }
+}
+
 impl From<HistoryEntry> for HistoryEntryRow {
     fn from(value: HistoryEntry) -> Self {
         Self {
+            state: value.display_state(),
             name: value.name,
             id: value.id,
             user: value.user,
-            state: value.state,
             start_date_time: value.start_date_time,
             end_date_time: value.end_date_time,
         }
@@ -57,7 +104,7 @@ pub struct HistCommand {
         short = 'x',
         long = "state",
         default_value = "running",
-        help = "Filter the history with state. Possible values are all, initial, queued, running, finished"
+        help = "Filter the history with state. Possible values are all, initial, queued, running, waiting-for-approval, finished"
     )]
     state: String,
 
@@ -71,6 +118,12 @@ pub struct HistCommand {
         help = "Limit the results"
     )]
     limit: u64,
+
+    #[arg(
+        long = "details",
+        help = "Include the trigger, inputs, environment and outputs of each run"
+    )]
+    details: bool,
 }
 
 impl BldCommand for HistCommand {
@@ -93,18 +146,24 @@ impl BldCommand for HistCommand {
                 self.server, self.limit,
             );
 
-            let history: Vec<HistoryEntryRow> = HttpClient::new(config, &self.server)?
-                .hist(state, self.file, self.limit)
-                .await?
-                .into_iter()
-                .map(From::from)
-                .collect();
+            let history = HttpClient::new(config, &self.server)?
+                .hist(state, self.file, self.limit, self.details)
+                .await?;
 
-            if !history.is_empty() {
-                let table = Table::new(history).with(Style::modern()).to_string();
-                println!("{table}");
+            if history.is_empty() {
+                return Ok(());
             }
 
+            let table = if self.details {
+                let rows: Vec<HistoryEntryDetailsRow> =
+                    history.into_iter().map(From::from).collect();
+                Table::new(rows).with(Style::modern()).to_string()
+            } else {
+                let rows: Vec<HistoryEntryRow> = history.into_iter().map(From::from).collect();
+                Table::new(rows).with(Style::modern()).to_string()
+            };
+            println!("{table}");
+
             Ok(())
         })
     }
@@ -1,4 +1,7 @@
+mod access;
 mod add;
+mod agent;
+mod approve;
 mod artifacts;
 mod auth;
 mod cat;
@@ -18,10 +21,12 @@ mod pull;
 mod push;
 mod remove;
 mod run;
+mod secrets;
 mod server;
 mod signals;
 mod stop;
 mod supervisor;
+mod trigger;
 mod worker;
 
 pub use cli::*;
@@ -40,6 +40,13 @@ pub struct MonitCommand {
         help = "Monitor the execution of the last invoked file. Takes precedence over pipeline-id and file"
     )]
     last: bool,
+
+    #[arg(
+        short = 'j',
+        long = "job",
+        help = "Monitor only the output of the job with the given name"
+    )]
+    job: Option<String>,
 }
 
 impl MonitCommand {
@@ -52,7 +59,7 @@ impl MonitCommand {
         );
         MonitClient::connect(config, logger, self.server)
             .await?
-            .run(MonitInfo::new(self.pipeline_id, self.file, self.last))
+            .run(MonitInfo::new(self.pipeline_id, self.file, self.last).with_job(self.job))
             .await
     }
 }
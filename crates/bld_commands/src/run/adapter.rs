@@ -17,6 +17,7 @@ pub struct LocalRun {
     pipeline: String,
     inputs: HashMap<String, String>,
     env: HashMap<String, String>,
+    priority: Option<i32>,
 }
 
 pub struct HttpRequest {
@@ -24,6 +25,7 @@ pub struct HttpRequest {
     pipeline: String,
     inputs: HashMap<String, String>,
     env: HashMap<String, String>,
+    priority: Option<i32>,
     server: String,
 }
 
@@ -32,6 +34,7 @@ pub struct WebSocketRequest {
     pipeline: String,
     inputs: HashMap<String, String>,
     env: HashMap<String, String>,
+    priority: Option<i32>,
     server: String,
 }
 
@@ -58,6 +61,7 @@ impl RunBuilder {
                 pipeline,
                 inputs,
                 env,
+                priority: None,
             }),
         }
     }
@@ -70,6 +74,7 @@ impl RunBuilder {
                     pipeline: local.pipeline,
                     inputs: local.inputs,
                     env: local.env,
+                    priority: local.priority,
                 }),
             },
 
@@ -79,6 +84,7 @@ impl RunBuilder {
                     pipeline: local.pipeline,
                     inputs: local.inputs,
                     env: local.env,
+                    priority: local.priority,
                     server: server.to_string(),
                 }),
             },
@@ -89,6 +95,7 @@ impl RunBuilder {
                     pipeline: socket.pipeline,
                     inputs: socket.inputs,
                     env: socket.env,
+                    priority: socket.priority,
                     server: socket.server,
                 }),
             },
@@ -99,6 +106,7 @@ impl RunBuilder {
                     pipeline: socket.pipeline,
                     inputs: socket.inputs,
                     env: socket.env,
+                    priority: socket.priority,
                     server: server.to_string(),
                 }),
             },
@@ -109,6 +117,7 @@ impl RunBuilder {
                     pipeline: http.pipeline,
                     inputs: http.inputs,
                     env: http.env,
+                    priority: http.priority,
                     server: http.server,
                 }),
             },
@@ -119,6 +128,7 @@ impl RunBuilder {
                     pipeline: http.pipeline,
                     inputs: http.inputs,
                     env: http.env,
+                    priority: http.priority,
                     server: server.to_string(),
                 }),
             },
@@ -133,6 +143,7 @@ impl RunBuilder {
                     pipeline: local.pipeline,
                     inputs: local.inputs,
                     env: local.env,
+                    priority: local.priority,
                 }),
             },
 
@@ -142,6 +153,7 @@ impl RunBuilder {
                     pipeline: socket.pipeline,
                     inputs: socket.inputs,
                     env: socket.env,
+                    priority: socket.priority,
                     server: socket.server,
                 }),
             },
@@ -152,6 +164,7 @@ impl RunBuilder {
                     pipeline: socket.pipeline,
                     inputs: socket.inputs,
                     env: socket.env,
+                    priority: socket.priority,
                     server: socket.server,
                 }),
             },
@@ -162,6 +175,7 @@ impl RunBuilder {
                     pipeline: http.pipeline,
                     inputs: http.inputs,
                     env: http.env,
+                    priority: http.priority,
                     server: http.server,
                 }),
             },
@@ -172,12 +186,24 @@ impl RunBuilder {
                     pipeline: http.pipeline,
                     inputs: http.inputs,
                     env: http.env,
+                    priority: http.priority,
                     server: http.server,
                 }),
             },
         }
     }
 
+    /// Sets the priority of the run in the queue of the server, which has no effect on
+    /// local runs.
+    pub fn priority(mut self, priority: Option<i32>) -> Self {
+        match &mut self.config {
+            RunConfiguration::Local(local) => local.priority = priority,
+            RunConfiguration::Http(http) => http.priority = priority,
+            RunConfiguration::WebSocket(socket) => socket.priority = priority,
+        }
+        self
+    }
+
     pub fn build(self) -> RunAdapter {
         RunAdapter {
             config: self.config,
@@ -189,6 +215,31 @@ pub struct RunAdapter {
     config: RunConfiguration,
 }
 
+/// Enqueues the pipeline of a previous run again on the server that the run was on.
+pub async fn rerun(
+    config: Arc<BldConfig>,
+    server: &str,
+    run_id: String,
+    detach: bool,
+) -> Result<()> {
+    if detach {
+        return HttpClient::new(config, server)?
+            .rerun(&run_id)
+            .await
+            .map(|_| println!("file has been scheduled to run"));
+    }
+
+    let client = ExecClient::connect(
+        config.clone(),
+        server.to_owned(),
+        Logger::shell().into_arc(),
+        Context::local(config).into_arc(),
+    )
+    .await?;
+
+    client.run(ExecClientMessage::Rerun { run_id }).await
+}
+
 impl RunAdapter {
     async fn run_local(mode: LocalRun) -> Result<()> {
         let (cmd_signals, signals_rx) = CommandSignals::new()?;
@@ -220,6 +271,8 @@ impl RunAdapter {
             name: mode.pipeline,
             env: Some(mode.env),
             inputs: Some(mode.inputs),
+            parent_run_id: None,
+            priority: mode.priority,
         };
 
         let client = ExecClient::connect(
@@ -235,7 +288,12 @@ impl RunAdapter {
 
     async fn run_http(mode: HttpRequest) -> Result<()> {
         HttpClient::new(mode.config, &mode.server)?
-            .run(&mode.pipeline, Some(mode.env), Some(mode.inputs))
+            .run(
+                &mode.pipeline,
+                Some(mode.env),
+                Some(mode.inputs),
+                mode.priority,
+            )
             .await
             .map(|_| println!("file has been scheduled to run"))
     }
@@ -1,5 +1,5 @@
 use crate::command::BldCommand;
-use crate::run::adapter::RunBuilder;
+use crate::run::adapter::{RunBuilder, rerun};
 use actix_web::rt::System;
 use anyhow::Result;
 use bld_config::BldConfig;
@@ -43,6 +43,22 @@ pub struct RunCommand {
         help = "Define value for an environment variable. Can be used multiple times"
     )]
     env: Vec<String>,
+
+    #[arg(
+        long = "rerun",
+        requires = "server",
+        conflicts_with_all = ["inputs", "env"],
+        help = "The id of a previous run to enqueue again with the same inputs and environment"
+    )]
+    rerun: Option<String>,
+
+    #[arg(
+        short = 'p',
+        long = "priority",
+        requires = "server",
+        help = "The priority of the run in the queue of the server. Runs with a higher priority leave the queue first, and a priority above 0 requires the maintainer role"
+    )]
+    priority: Option<i32>,
 }
 
 impl BldCommand for RunCommand {
@@ -53,11 +69,16 @@ impl BldCommand for RunCommand {
     fn exec(self) -> Result<()> {
         System::new().block_on(async move {
             let config = BldConfig::load().await?.into_arc();
+            if let (Some(run_id), Some(server)) = (self.rerun, self.server.as_ref()) {
+                return rerun(config, server, run_id, self.detach).await;
+            }
+
             let inputs = parse_variables(&self.inputs);
             let env = parse_variables(&self.env);
             let adapter = RunBuilder::new(config, self.file, inputs, env)
                 .server(self.server.as_ref())
                 .detach(self.detach)
+                .priority(self.priority)
                 .build();
 
             adapter.run().await
@@ -0,0 +1,29 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use super::{list::SecretsListCommand, remove::SecretsRemoveCommand, set::SecretsSetCommand};
+use crate::command::BldCommand;
+
+#[derive(Subcommand)]
+pub enum SecretsCommands {
+    Ls(SecretsListCommand),
+    Set(SecretsSetCommand),
+    Rm(SecretsRemoveCommand),
+}
+
+#[derive(Parser)]
+#[command(about = "Manage the encrypted secrets of a server")]
+pub struct SecretsCommand {
+    #[command(subcommand)]
+    command: SecretsCommands,
+}
+
+impl SecretsCommand {
+    pub fn invoke(self) -> Result<()> {
+        match self.command {
+            SecretsCommands::Ls(list) => list.invoke(),
+            SecretsCommands::Set(set) => set.invoke(),
+            SecretsCommands::Rm(remove) => remove.invoke(),
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod command;
+mod list;
+mod remove;
+mod set;
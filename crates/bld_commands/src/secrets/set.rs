@@ -0,0 +1,57 @@
+use crate::command::BldCommand;
+use actix_web::rt::System;
+use anyhow::Result;
+use bld_config::BldConfig;
+use bld_http::HttpClient;
+use bld_models::dtos::SecretRequest;
+use bld_utils::sync::IntoArc;
+use clap::Args;
+use std::io::stdin;
+
+#[derive(Args)]
+#[command(about = "Creates or updates a secret in a server")]
+pub struct SecretsSetCommand {
+    #[arg(long = "verbose", help = "Sets the level of verbosity")]
+    verbose: bool,
+
+    #[arg(
+        short = 's',
+        long = "server",
+        help = "The name of the server to store the secret to"
+    )]
+    server: String,
+
+    #[arg(required = true, help = "The name of the secret")]
+    name: String,
+
+    #[arg(
+        short = 'v',
+        long = "value",
+        help = "The value of the secret. If not provided it will be read from the standard input"
+    )]
+    value: Option<String>,
+}
+
+impl BldCommand for SecretsSetCommand {
+    fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    fn exec(self) -> Result<()> {
+        let value = match self.value {
+            Some(value) => value,
+            None => {
+                let mut value = String::new();
+                stdin().read_line(&mut value)?;
+                value.trim_end_matches(['\r', '\n']).to_string()
+            }
+        };
+
+        System::new().block_on(async move {
+            let config = BldConfig::load().await?.into_arc();
+            let client = HttpClient::new(config, &self.server)?;
+            let request = SecretRequest::new(self.name, value);
+            client.secrets_set(&request).await
+        })
+    }
+}
@@ -0,0 +1,95 @@
+use crate::command::BldCommand;
+use actix_web::rt::System;
+use anyhow::Result;
+use bld_config::BldConfig;
+use bld_http::HttpClient;
+use bld_models::dtos::AddTriggerRequest;
+use bld_utils::{sync::IntoArc, variables::parse_variables};
+use clap::Args;
+use std::io::stdin;
+
+#[derive(Args)]
+#[command(about = "Adds a webhook trigger for a pipeline to a server")]
+pub struct TriggerAddCommand {
+    #[arg(long = "verbose", help = "Sets the level of verbosity")]
+    verbose: bool,
+
+    #[arg(
+        required = true,
+        help = "The name of the trigger, used in the url of the webhook"
+    )]
+    name: String,
+
+    #[arg(
+        short = 's',
+        long = "server",
+        help = "The name of the server to add the trigger to"
+    )]
+    server: String,
+
+    #[arg(
+        short = 'p',
+        long = "pipeline",
+        required = true,
+        help = "The pipeline that the trigger will run"
+    )]
+    pipeline: String,
+
+    #[arg(
+        long = "secret",
+        help = "The secret used to verify the signature of the payloads. If not provided it will be read from the standard input"
+    )]
+    secret: Option<String>,
+
+    #[arg(
+        short = 'b',
+        long = "branch",
+        help = "Start runs only for pushes to branches matching one of these patterns"
+    )]
+    branches: Vec<String>,
+
+    #[arg(
+        short = 'P',
+        long = "path",
+        help = "Start runs only for pushes that change a path matching one of these patterns"
+    )]
+    paths: Vec<String>,
+
+    #[arg(
+        short = 'i',
+        long = "input",
+        help = "Map a field of the payload to an input of the pipeline in the format name=field.path"
+    )]
+    inputs: Vec<String>,
+}
+
+impl BldCommand for TriggerAddCommand {
+    fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    fn exec(self) -> Result<()> {
+        let secret = match self.secret {
+            Some(secret) => secret,
+            None => {
+                let mut secret = String::new();
+                stdin().read_line(&mut secret)?;
+                secret.trim_end_matches(['\r', '\n']).to_string()
+            }
+        };
+
+        System::new().block_on(async move {
+            let config = BldConfig::load().await?.into_arc();
+            let client = HttpClient::new(config, &self.server)?;
+            let request = AddTriggerRequest::new(
+                self.name,
+                self.pipeline,
+                secret,
+                self.branches,
+                self.paths,
+                parse_variables(&self.inputs),
+            );
+            client.triggers_add(&request).await
+        })
+    }
+}
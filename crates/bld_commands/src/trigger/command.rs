@@ -0,0 +1,34 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use super::{
+    add::TriggerAddCommand, list::TriggerListCommand, remove::TriggerRemoveCommand,
+    update::TriggerUpdateCommand,
+};
+use crate::command::BldCommand;
+
+#[derive(Subcommand)]
+pub enum TriggerCommands {
+    Ls(TriggerListCommand),
+    Add(TriggerAddCommand),
+    Update(TriggerUpdateCommand),
+    Rm(TriggerRemoveCommand),
+}
+
+#[derive(Parser)]
+#[command(about = "Manage the webhook triggers of a server")]
+pub struct TriggerCommand {
+    #[command(subcommand)]
+    command: TriggerCommands,
+}
+
+impl TriggerCommand {
+    pub fn invoke(self) -> Result<()> {
+        match self.command {
+            TriggerCommands::Ls(list) => list.invoke(),
+            TriggerCommands::Add(add) => add.invoke(),
+            TriggerCommands::Update(update) => update.invoke(),
+            TriggerCommands::Rm(remove) => remove.invoke(),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use crate::command::BldCommand;
+use actix_web::rt::System;
+use anyhow::Result;
+use bld_config::BldConfig;
+use bld_http::HttpClient;
+use bld_utils::sync::IntoArc;
+use clap::Args;
+use tabled::{Table, Tabled, settings::Style};
+
+#[derive(Tabled)]
+struct TriggerInfoRow<'a> {
+    pub name: &'a str,
+    pub pipeline: &'a str,
+    pub branches: String,
+    pub paths: String,
+    pub inputs: String,
+    pub date_created: &'a str,
+    pub date_updated: &'a str,
+}
+
+#[derive(Args)]
+#[command(about = "Lists all webhook triggers of a server")]
+pub struct TriggerListCommand {
+    #[arg(long = "verbose", help = "Sets the level of verbosity")]
+    verbose: bool,
+
+    #[arg(
+        short = 's',
+        long = "server",
+        help = "The name of the server to list the triggers from"
+    )]
+    server: String,
+}
+
+impl BldCommand for TriggerListCommand {
+    fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    fn exec(self) -> Result<()> {
+        System::new().block_on(async move {
+            let config = BldConfig::load().await?.into_arc();
+            let client = HttpClient::new(config, &self.server)?;
+            let response = client.triggers_list().await?;
+
+            if !response.is_empty() {
+                let data: Vec<TriggerInfoRow> = response
+                    .iter()
+                    .map(|t| {
+                        let mut inputs: Vec<String> = t
+                            .inputs
+                            .iter()
+                            .map(|(name, path)| format!("{name}={path}"))
+                            .collect();
+                        inputs.sort();
+                        TriggerInfoRow {
+                            name: &t.name,
+                            pipeline: &t.pipeline,
+                            branches: t.branches.join("\n"),
+                            paths: t.paths.join("\n"),
+                            inputs: inputs.join("\n"),
+                            date_created: &t.date_created,
+                            date_updated: t.date_updated.as_deref().unwrap_or(""),
+                        }
+                    })
+                    .collect();
+                let table = Table::new(data).with(Style::modern()).to_string();
+                println!("{table}");
+            }
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,5 @@
+mod add;
+pub mod command;
+mod list;
+mod remove;
+mod update;
@@ -0,0 +1,38 @@
+use crate::command::BldCommand;
+use actix_web::rt::System;
+use anyhow::Result;
+use bld_config::BldConfig;
+use bld_http::HttpClient;
+use bld_utils::sync::IntoArc;
+use clap::Args;
+
+#[derive(Args)]
+#[command(about = "Removes a webhook trigger from a server")]
+pub struct TriggerRemoveCommand {
+    #[arg(long = "verbose", help = "Sets the level of verbosity")]
+    verbose: bool,
+
+    #[arg(required = true, help = "The name of the trigger to remove")]
+    name: String,
+
+    #[arg(
+        short = 's',
+        long = "server",
+        help = "The name of the server to remove the trigger from"
+    )]
+    server: String,
+}
+
+impl BldCommand for TriggerRemoveCommand {
+    fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    fn exec(self) -> Result<()> {
+        System::new().block_on(async move {
+            let config = BldConfig::load().await?.into_arc();
+            let client = HttpClient::new(config, &self.server)?;
+            client.triggers_remove(&self.name).await
+        })
+    }
+}
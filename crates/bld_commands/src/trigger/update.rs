@@ -0,0 +1,75 @@
+use crate::command::BldCommand;
+use actix_web::rt::System;
+use anyhow::Result;
+use bld_config::BldConfig;
+use bld_http::HttpClient;
+use bld_models::dtos::UpdateTriggerRequest;
+use bld_utils::{sync::IntoArc, variables::parse_variables};
+use clap::Args;
+
+#[derive(Args)]
+#[command(
+    about = "Updates a webhook trigger of a server, replacing its filters and input mappings"
+)]
+pub struct TriggerUpdateCommand {
+    #[arg(long = "verbose", help = "Sets the level of verbosity")]
+    verbose: bool,
+
+    #[arg(required = true, help = "The name of the trigger to update")]
+    name: String,
+
+    #[arg(
+        short = 's',
+        long = "server",
+        help = "The name of the server to update the trigger to"
+    )]
+    server: String,
+
+    #[arg(
+        long = "secret",
+        help = "The new secret used to verify the signature of the payloads"
+    )]
+    secret: Option<String>,
+
+    #[arg(
+        short = 'b',
+        long = "branch",
+        help = "Start runs only for pushes to branches matching one of these patterns"
+    )]
+    branches: Vec<String>,
+
+    #[arg(
+        short = 'P',
+        long = "path",
+        help = "Start runs only for pushes that change a path matching one of these patterns"
+    )]
+    paths: Vec<String>,
+
+    #[arg(
+        short = 'i',
+        long = "input",
+        help = "Map a field of the payload to an input of the pipeline in the format name=field.path"
+    )]
+    inputs: Vec<String>,
+}
+
+impl BldCommand for TriggerUpdateCommand {
+    fn verbose(&self) -> bool {
+        self.verbose
+    }
+
+    fn exec(self) -> Result<()> {
+        System::new().block_on(async move {
+            let config = BldConfig::load().await?.into_arc();
+            let client = HttpClient::new(config, &self.server)?;
+            let request = UpdateTriggerRequest::new(
+                self.name,
+                self.secret,
+                self.branches,
+                self.paths,
+                parse_variables(&self.inputs),
+            );
+            client.triggers_update(&request).await
+        })
+    }
+}
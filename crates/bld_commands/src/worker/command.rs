@@ -2,7 +2,9 @@ use crate::{command::BldCommand, signals::CommandSignals};
 use actix_web::rt::{System, spawn};
 use anyhow::Result;
 use bld_config::BldConfig;
-use bld_core::{artifacts::ArtifactsStore, context::Context, fs::FileSystem, logger::Logger};
+use bld_core::{
+    artifacts::ArtifactsStore, context::Context, fs::FileSystem, logger::Logger, secrets,
+};
 use bld_models::{
     new_connection_pool,
     pipeline_runs::{self, PR_STATE_FAULTED},
@@ -13,7 +15,7 @@ use bld_sock::WorkerClient;
 use bld_utils::{sync::IntoArc, variables::parse_variables};
 use chrono::Utc;
 use clap::Args;
-use futures::join;
+use tokio::select;
 use tokio::sync::mpsc::channel;
 use tracing::error;
 
@@ -68,10 +70,14 @@ impl BldCommand for WorkerCommand {
 
             let pipeline = self.pipeline.into_arc();
             let run_id = self.run_id.into_arc();
+            let socket_run_id = run_id.clone();
             let inputs = parse_variables(&self.inputs).into_arc();
             let env = parse_variables(&self.env).into_arc();
 
             let conn = new_connection_pool(config.clone()).await?.into_arc();
+            let secrets = secrets::load(config.as_ref(), conn.as_ref())
+                .await?
+                .into_arc();
             let start_date = Utc::now().naive_utc();
             pipeline_runs::update_start_date(conn.as_ref(), &run_id, &start_date).await?;
             let start_date = start_date.format("%F %X").to_string();
@@ -90,8 +96,10 @@ impl BldCommand for WorkerCommand {
             let context = Context::server(config.clone(), conn.clone(), &run_id).into_arc();
             let (cmd_signals, signals_rx) = CommandSignals::new()?;
 
-            let socket_handle = spawn(async move {
-                let Ok(client) = WorkerClient::connect(socket_cfg, Logger::shell())
+            let socket_conn = conn.clone();
+            let socket_lost_run_id = run_id.clone();
+            let mut socket_handle = spawn(async move {
+                let Ok(client) = WorkerClient::connect(socket_cfg, &socket_run_id, Logger::shell())
                     .await
                     .inspect_err(|e| error!("{e}"))
                 else {
@@ -100,7 +108,7 @@ impl BldCommand for WorkerCommand {
                 let _ = client.run(worker_rx).await.inspect_err(|e| error!("{e}"));
             });
 
-            let runner_handle = spawn(async move {
+            let mut runner_handle = spawn(async move {
                 match RunnerBuilder::default()
                     .run_id(&run_id)
                     .run_start_time(&start_date)
@@ -110,6 +118,7 @@ impl BldCommand for WorkerCommand {
                     .logger(logger)
                     .env(env)
                     .inputs(inputs)
+                    .secrets(secrets)
                     .context(context)
                     .ipc(worker_tx)
                     .signals(signals_rx)
@@ -140,7 +149,31 @@ impl BldCommand for WorkerCommand {
                 let _ = cmd_signals.stop().await;
             });
 
-            let _ = join!(socket_handle, runner_handle);
+            // When the connection to the supervisor is lost the run can't be monitored or
+            // stopped, so it's faulted and the worker exits without waiting for the runner.
+            // The socket also closes once the runner drops its channel, so the state of the
+            // run is checked before faulting it.
+            select! {
+                _ = &mut socket_handle => {
+                    let conn = socket_conn.as_ref();
+                    let run_id = socket_lost_run_id.as_str();
+                    let completed = pipeline_runs::select_by_id(conn, run_id)
+                        .await
+                        .is_ok_and(|run| pipeline_runs::is_completed(&run.state));
+                    if completed {
+                        let _ = runner_handle.await;
+                    } else {
+                        error!("lost connection to the supervisor");
+                        let reason = "the worker lost its connection to the supervisor";
+                        let _ = pipeline_runs::update_as_faulted(conn, run_id, reason)
+                            .await
+                            .inspect_err(|e| error!("unable set pipeline as faulted due to {e}"));
+                    }
+                }
+                _ = &mut runner_handle => {
+                    let _ = socket_handle.await;
+                }
+            }
 
             Ok(())
         })
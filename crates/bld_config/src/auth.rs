@@ -8,6 +8,8 @@ use openidconnect::{
 };
 use serde::{Deserialize, Serialize};
 
+use crate::definitions;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum UserInfoProperty {
     #[serde(rename = "name")]
@@ -32,9 +34,21 @@ pub struct OpenIdInfo {
     pub client_secret: ClientSecret,
     pub scopes: Vec<Scope>,
     pub user_property: UserInfoProperty,
+
+    /// The claim of the user info response with the groups that the user is a member of.
+    #[serde(default = "OpenIdInfo::default_groups_claim")]
+    pub groups_claim: String,
+
+    /// The users that are admins of the server regardless of the access grants stored in it.
+    #[serde(default)]
+    pub admins: Vec<String>,
 }
 
 impl OpenIdInfo {
+    fn default_groups_claim() -> String {
+        definitions::LOCAL_AUTH_GROUPS_CLAIM.to_owned()
+    }
+
     async fn build_core_client(&self, redirect_url: RedirectUrl) -> Result<CoreClient> {
         let provider_metadata =
             CoreProviderMetadata::discover_async(self.issuer_url.clone(), async_http_client)
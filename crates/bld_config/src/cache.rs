@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+use crate::definitions;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BldCacheConfig {
+    #[serde(default = "BldCacheConfig::default_dir")]
+    pub dir: String,
+
+    /// The maximum size in megabytes of every cache entry combined, after which the
+    /// least recently used entries are evicted.
+    #[serde(default = "BldCacheConfig::default_max_size")]
+    pub max_size: u64,
+
+    /// The number of days after which an entry that hasn't been used is evicted.
+    #[serde(default = "BldCacheConfig::default_max_age")]
+    pub max_age: u64,
+}
+
+impl BldCacheConfig {
+    fn default_dir() -> String {
+        definitions::LOCAL_CACHE_DIR.to_owned()
+    }
+
+    fn default_max_size() -> u64 {
+        definitions::LOCAL_CACHE_MAX_SIZE
+    }
+
+    fn default_max_age() -> u64 {
+        definitions::LOCAL_CACHE_MAX_AGE
+    }
+}
+
+impl Default for BldCacheConfig {
+    fn default() -> Self {
+        Self {
+            dir: Self::default_dir(),
+            max_size: Self::default_max_size(),
+            max_age: Self::default_max_age(),
+        }
+    }
+}
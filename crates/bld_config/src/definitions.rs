@@ -34,9 +34,18 @@ pub const LOCAL_SERVER_CLEANUP_INTERVAL: i64 = 3600;
 pub const LOCAL_SUPERVISOR_HOST: &str = "127.0.0.1";
 pub const LOCAL_SUPERVISOR_PORT: i64 = 7080;
 pub const LOCAL_SUPERVISOR_WORKERS: i64 = 5;
+pub const LOCAL_SUPERVISOR_HEARTBEAT_TIMEOUT: u64 = 60;
+pub const WORKER_HEARTBEAT_INTERVAL: u64 = 10;
 pub const LOCAL_HA_MODE: bool = false;
+pub const LOCAL_HA_ELECTION_TIMEOUT: u64 = 1500;
+pub const LOCAL_HA_HEARTBEAT_INTERVAL: u64 = 500;
+pub const LOCAL_HA_SNAPSHOT_THRESHOLD: i32 = 1000;
+pub const LOCAL_AUTH_GROUPS_CLAIM: &str = "groups";
 pub const LOCAL_LOGS: &str = "logs";
 pub const LOCAL_ARTIFACTS: &str = "artifacts";
+pub const LOCAL_CACHE_DIR: &str = "cache";
+pub const LOCAL_CACHE_MAX_SIZE: u64 = 5120;
+pub const LOCAL_CACHE_MAX_AGE: u64 = 7;
 pub const LOCAL_DEFAULT_DB_DIR: &str = "db";
 pub const LOCAL_DEFAULT_DB_NAME: &str = "bld-server.db";
 pub const LOCAL_DOCKER_URL: &str = "tcp://127.0.0.1:2376";
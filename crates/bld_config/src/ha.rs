@@ -0,0 +1,75 @@
+use crate::definitions;
+use serde::{Deserialize, Serialize};
+
+/// The options of a server that is a member of a raft group. The members replicate
+/// the pipelines, cron jobs, secrets, triggers and runs between them and elect a leader
+/// that runs them. The secrets are replicated encrypted, so every member must have the
+/// same secrets key.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BldLocalHaConfig {
+    /// The id of the server in the raft group.
+    pub id: i32,
+
+    /// The rest of the members of the raft group.
+    #[serde(default)]
+    pub members: Vec<BldHaMemberConfig>,
+
+    /// The milliseconds without hearing from a leader after which a member starts an election.
+    #[serde(default = "BldLocalHaConfig::default_election_timeout")]
+    pub election_timeout: u64,
+
+    /// The milliseconds between the heartbeats that the leader sends to the members.
+    #[serde(default = "BldLocalHaConfig::default_heartbeat_interval")]
+    pub heartbeat_interval: u64,
+
+    /// The entries applied since the last snapshot after which a member takes a snapshot of
+    /// its state and removes the entries up to it from its log.
+    #[serde(default = "BldLocalHaConfig::default_snapshot_threshold")]
+    pub snapshot_threshold: i32,
+
+    /// A secret shared between the members in order to authenticate their requests, without
+    /// which a member rejects the requests of the rest.
+    pub secret: String,
+}
+
+impl BldLocalHaConfig {
+    fn default_election_timeout() -> u64 {
+        definitions::LOCAL_HA_ELECTION_TIMEOUT
+    }
+
+    fn default_heartbeat_interval() -> u64 {
+        definitions::LOCAL_HA_HEARTBEAT_INTERVAL
+    }
+
+    fn default_snapshot_threshold() -> i32 {
+        definitions::LOCAL_HA_SNAPSHOT_THRESHOLD
+    }
+
+    pub fn member(&self, id: i32) -> Option<&BldHaMemberConfig> {
+        self.members.iter().find(|m| m.id == id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BldHaMemberConfig {
+    pub id: i32,
+    pub host: String,
+    pub port: i64,
+
+    #[serde(default)]
+    pub tls: bool,
+}
+
+impl BldHaMemberConfig {
+    fn http_protocol(&self) -> String {
+        if self.tls {
+            "https".to_string()
+        } else {
+            "http".to_string()
+        }
+    }
+
+    pub fn base_url_http(&self) -> String {
+        format!("{}://{}:{}", self.http_protocol(), self.host, self.port)
+    }
+}
@@ -1,6 +1,8 @@
 mod auth;
+mod cache;
 pub mod definitions;
 mod docker;
+mod ha;
 mod local;
 mod packages;
 mod path;
@@ -10,7 +12,9 @@ mod supervisor;
 mod tls;
 
 pub use auth::*;
+pub use cache::*;
 pub use docker::*;
+pub use ha::*;
 pub use local::*;
 pub use packages::*;
 pub use path::*;
@@ -216,6 +220,19 @@ impl BldConfig {
         path![&self.root_dir, &self.local.server.logs, id]
     }
 
+    /// The directory that holds the separate log of every job of a run.
+    pub fn job_logs_dir(&self, id: &str) -> PathBuf {
+        path![
+            &self.root_dir,
+            &self.local.server.logs,
+            format!("{id}.jobs")
+        ]
+    }
+
+    pub fn job_log_full_path(&self, id: &str, job: &str) -> PathBuf {
+        path![self.job_logs_dir(id), job.replace(['/', '\\'], "_")]
+    }
+
     pub fn auth_full_path(&self, server: &str) -> PathBuf {
         path![&self.root_dir, REMOTE_SERVER_AUTH, server]
     }
@@ -244,6 +261,10 @@ impl BldConfig {
     pub fn artifact_full_path(&self, run_id: &str, name: &str) -> PathBuf {
         path![self.artifacts_run_dir(run_id), format!("{name}.tar.gz")]
     }
+
+    pub fn cache_dir(&self) -> PathBuf {
+        path![&self.root_dir, &self.local.cache.dir]
+    }
 }
 
 #[cfg(test)]
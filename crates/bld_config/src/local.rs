@@ -1,8 +1,8 @@
 use std::collections::HashMap;
 
 use crate::{
-    BldLocalServerConfig, BldLocalSupervisorConfig, BldPackages, DockerUrl, RegistryConfig,
-    definitions, ssh::SshConfig,
+    BldCacheConfig, BldLocalServerConfig, BldLocalSupervisorConfig, BldPackages, DockerUrl,
+    RegistryConfig, definitions, ssh::SshConfig,
 };
 use serde::{Deserialize, Serialize};
 
@@ -34,6 +34,9 @@ pub struct BldLocalConfig {
 
     #[serde(default = "BldLocalConfig::default_artifacts")]
     pub artifacts: String,
+
+    #[serde(default)]
+    pub cache: BldCacheConfig,
 }
 
 impl BldLocalConfig {
@@ -55,7 +58,13 @@ impl BldLocalConfig {
         debug!("server > pipelines: {}", self.server.pipelines);
         debug!("logs: {}", self.server.logs);
         debug!("db: {:?}", self.server.db);
+        if self.server.secrets_key.is_some() {
+            debug!("server > secrets_key: ********");
+        }
         debug!("artifacts: {}", self.artifacts);
+        debug!("cache > dir: {}", self.cache.dir);
+        debug!("cache > max_size: {}", self.cache.max_size);
+        debug!("cache > max_age: {}", self.cache.max_age);
         if let Some(Auth::OpenId(openid)) = &self.server.auth {
             debug!("auth > method: openid");
             debug!("auth > issuer_url: {:?}", openid.issuer_url);
@@ -131,6 +140,7 @@ impl Default for BldLocalConfig {
             registries: Default::default(),
             packages: Default::default(),
             artifacts: Self::default_artifacts(),
+            cache: Default::default(),
         }
     }
 }
@@ -1,4 +1,4 @@
-use crate::{Auth, BldTlsConfig, definitions};
+use crate::{Auth, BldLocalHaConfig, BldTlsConfig, definitions};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -23,6 +23,10 @@ pub struct BldLocalServerConfig {
 
     #[serde(default = "BldLocalServerConfig::default_cleanup_interval")]
     pub cleanup_interval: i64,
+
+    pub secrets_key: Option<String>,
+
+    pub ha: Option<BldLocalHaConfig>,
 }
 
 impl BldLocalServerConfig {
@@ -90,6 +94,8 @@ impl Default for BldLocalServerConfig {
             logs: Self::default_logs(),
             db: None,
             cleanup_interval: Self::default_cleanup_interval(),
+            secrets_key: None,
+            ha: None,
         }
     }
 }
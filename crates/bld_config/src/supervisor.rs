@@ -14,6 +14,14 @@ pub struct BldLocalSupervisorConfig {
 
     #[serde(default = "BldLocalSupervisorConfig::default_workers")]
     pub workers: i64,
+
+    /// The seconds after which a worker that has stopped sending heartbeats is killed.
+    #[serde(default = "BldLocalSupervisorConfig::default_heartbeat_timeout")]
+    pub heartbeat_timeout: u64,
+
+    /// The token that agents send when they register, without which the supervisor
+    /// doesn't accept agent connections.
+    pub agent_token: Option<String>,
 }
 
 impl BldLocalSupervisorConfig {
@@ -29,6 +37,10 @@ impl BldLocalSupervisorConfig {
         definitions::LOCAL_SUPERVISOR_WORKERS
     }
 
+    fn default_heartbeat_timeout() -> u64 {
+        definitions::LOCAL_SUPERVISOR_HEARTBEAT_TIMEOUT
+    }
+
     fn http_protocol(&self) -> String {
         if self.tls.is_some() {
             "https".to_string()
@@ -61,6 +73,8 @@ impl Default for BldLocalSupervisorConfig {
             port: Self::default_port(),
             tls: None,
             workers: Self::default_workers(),
+            heartbeat_timeout: Self::default_heartbeat_timeout(),
+            agent_token: None,
         }
     }
 }
@@ -226,7 +226,7 @@ pub fn validate_artifact_name(name: &str) -> Result<()> {
     }
 }
 
-fn compress_to_tar_gz(source: &Path, entry_name: &str) -> Result<Vec<u8>> {
+pub(crate) fn compress_to_tar_gz(source: &Path, entry_name: &str) -> Result<Vec<u8>> {
     let mut tar = Builder::new(Vec::new());
 
     if source.is_file() {
@@ -241,7 +241,7 @@ fn compress_to_tar_gz(source: &Path, entry_name: &str) -> Result<Vec<u8>> {
     Ok(gz.finish()?)
 }
 
-fn decompress_tar_gz(data: &[u8], dest: &Path) -> Result<()> {
+pub(crate) fn decompress_tar_gz(data: &[u8], dest: &Path) -> Result<()> {
     let gz = GzDecoder::new(data);
     let mut archive = Archive::new(gz);
     archive.unpack(dest)?;
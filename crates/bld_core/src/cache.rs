@@ -0,0 +1,628 @@
+use std::{
+    fs::{File, OpenOptions},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Result, anyhow};
+use bld_config::BldConfig;
+use ring::digest::{SHA256, digest};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs::{
+        create_dir_all, metadata, read, read_dir, read_to_string, remove_dir_all, remove_file,
+        rename, write,
+    },
+    task::spawn_blocking,
+};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use crate::{
+    artifacts::{compress_to_tar_gz, decompress_tar_gz},
+    logger::Logger,
+    platform::Platform,
+};
+
+/// The name of the root entry of an archive, under which every path is stored in a
+/// directory named after its position in the paths of the cache.
+const ARCHIVE_ROOT: &str = "paths";
+
+/// The name that a path has inside the directory of its position.
+const ARCHIVE_PATH: &str = "content";
+
+/// The file of the cache directory that is locked while entries are read, written or
+/// removed, so that runs on different workers sharing the directory don't race.
+const LOCK_FILE: &str = "cache.lock";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    key: String,
+    size: u64,
+    created_at: u64,
+    last_used: u64,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or_default()
+}
+
+/// The name of the files of an entry, which is the hash of its key so that any key can
+/// be stored regardless of the characters it has.
+fn entry_id(key: &str) -> String {
+    digest(&SHA256, key.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|x| format!("{x:02x}"))
+        .collect()
+}
+
+/// Finds the entry of the key or, if there isn't one, the most recently created entry
+/// whose key starts with one of the restore keys, checking them in order.
+fn find_entry<'a>(
+    entries: &'a [(String, CacheEntry)],
+    key: &str,
+    restore_keys: &[String],
+) -> Option<&'a (String, CacheEntry)> {
+    entries.iter().find(|(_, x)| x.key == key).or_else(|| {
+        restore_keys.iter().find_map(|prefix| {
+            entries
+                .iter()
+                .filter(|(_, x)| x.key.starts_with(prefix.as_str()))
+                .max_by_key(|(_, x)| x.created_at)
+        })
+    })
+}
+
+/// Picks the entries to evict, which are the ones not used for longer than the max age
+/// followed by the least recently used ones until the rest fit in the max size.
+fn entries_to_evict(
+    mut entries: Vec<(String, CacheEntry)>,
+    max_size: u64,
+    max_age: u64,
+    now: u64,
+) -> Vec<(String, CacheEntry)> {
+    entries.sort_by_key(|(_, x)| x.last_used);
+
+    let (mut evicted, mut kept): (Vec<_>, Vec<_>) = entries
+        .into_iter()
+        .partition(|(_, x)| now.saturating_sub(x.last_used) > max_age);
+
+    let mut total: u64 = kept.iter().map(|(_, x)| x.size).sum();
+    let mut remaining = kept.drain(..);
+    while total > max_size {
+        let Some(entry) = remaining.next() else {
+            break;
+        };
+        total -= entry.1.size;
+        evicted.push(entry);
+    }
+
+    evicted
+}
+
+/// Stores the paths of cache steps between runs, as tar.gz archives in the cache
+/// directory of the bld root, along with a file that describes every archive. The paths
+/// are copied between the platform and the host that runs the job, which for a server is
+/// always its own host since its workers and agents use its bld root, so every run of the
+/// server shares the same cache while `bld run` uses the one of the local bld root.
+pub struct Cache {
+    config: Option<Arc<BldConfig>>,
+}
+
+impl Cache {
+    pub fn new(config: Arc<BldConfig>) -> Self {
+        Self {
+            config: Some(config),
+        }
+    }
+
+    pub fn mock() -> Self {
+        Self { config: None }
+    }
+
+    async fn entries(dir: &Path) -> Result<Vec<(String, CacheEntry)>> {
+        let mut entries = vec![];
+        if !dir.is_dir() {
+            return Ok(entries);
+        }
+
+        let mut dir_entries = read_dir(dir).await?;
+        while let Some(dir_entry) = dir_entries.next_entry().await? {
+            let path = dir_entry.path();
+            if path.extension().is_none_or(|x| x != "json") {
+                continue;
+            }
+            let Some(id) = path.file_stem().map(|x| x.to_string_lossy().to_string()) else {
+                continue;
+            };
+            match read_to_string(&path)
+                .await
+                .map_err(|e| anyhow!(e))
+                .and_then(|x| serde_json::from_str(&x).map_err(|e| anyhow!(e)))
+            {
+                Ok(entry) => entries.push((id, entry)),
+                Err(e) => error!("unable to read cache entry {}: {e}", path.display()),
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Takes an exclusive lock of the cache directory, which is released once the returned
+    /// file is dropped.
+    async fn lock(dir: &Path) -> Result<File> {
+        create_dir_all(dir).await?;
+        let path = dir.join(LOCK_FILE);
+        spawn_blocking(move || {
+            let file = OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(path)?;
+            file.lock()?;
+            Ok(file)
+        })
+        .await?
+    }
+
+    /// Writes the file of an entry through a temporary one, so that a partially written
+    /// file is never read.
+    async fn write_entry(dir: &Path, id: &str, entry: &CacheEntry) -> Result<()> {
+        let tmp_path = dir.join(format!("{id}.{}.tmp", Uuid::new_v4()));
+        write(&tmp_path, serde_json::to_vec(entry)?).await?;
+        rename(&tmp_path, dir.join(format!("{id}.json"))).await?;
+        Ok(())
+    }
+
+    /// Restores the entry of the key, or the one of the first matching restore key, to
+    /// the paths and returns the key of the restored entry.
+    pub async fn restore(
+        &self,
+        platform: &Platform,
+        logger: &Logger,
+        key: &str,
+        restore_keys: &[String],
+        paths: &[String],
+    ) -> Result<Option<String>> {
+        let Some(config) = &self.config else {
+            return Ok(None);
+        };
+
+        let dir = config.cache_dir();
+        let Some((entry, compressed)) = Self::use_entry(&dir, key, restore_keys).await? else {
+            logger
+                .write_line(format!("{:<15}: no entry found for key {key}", "Cache"))
+                .await?;
+            return Ok(None);
+        };
+
+        let staging_dir = config.tmp_full_path(&Uuid::new_v4().to_string());
+        create_dir_all(&staging_dir).await?;
+
+        let result = Self::restore_inner(platform, &compressed, paths, &staging_dir).await;
+
+        if let Err(e) = remove_dir_all(&staging_dir).await {
+            error!("unable to clean up staging directory for cache {key}: {e}");
+        }
+        result?;
+
+        logger
+            .write_line(format!("{:<15}: restored from key {}", "Cache", entry.key))
+            .await?;
+
+        Ok(Some(entry.key))
+    }
+
+    /// Finds the entry to restore and reads its archive while the cache is locked, so that
+    /// it can't be evicted in the meantime, and marks the entry as used.
+    async fn use_entry(
+        dir: &Path,
+        key: &str,
+        restore_keys: &[String],
+    ) -> Result<Option<(CacheEntry, Vec<u8>)>> {
+        if !dir.is_dir() {
+            return Ok(None);
+        }
+
+        let _lock = Self::lock(dir).await?;
+        let entries = Self::entries(dir).await?;
+        let Some((id, entry)) = find_entry(&entries, key, restore_keys) else {
+            return Ok(None);
+        };
+
+        let compressed = read(dir.join(format!("{id}.tar.gz"))).await?;
+
+        let used = CacheEntry {
+            key: entry.key.clone(),
+            size: entry.size,
+            created_at: entry.created_at,
+            last_used: now(),
+        };
+        if let Err(e) = Self::write_entry(dir, id, &used).await {
+            error!("unable to update cache entry {}: {e}", entry.key);
+        }
+
+        Ok(Some((used, compressed)))
+    }
+
+    async fn restore_inner(
+        platform: &Platform,
+        compressed: &[u8],
+        paths: &[String],
+        staging_dir: &Path,
+    ) -> Result<()> {
+        decompress_tar_gz(compressed, staging_dir)?;
+
+        for (index, path) in paths.iter().enumerate() {
+            let extracted = staging_dir
+                .join(ARCHIVE_ROOT)
+                .join(index.to_string())
+                .join(ARCHIVE_PATH);
+
+            if metadata(&extracted).await.is_err() {
+                debug!("no content for path {path} in cache entry");
+                continue;
+            }
+
+            platform
+                .push_as(&extracted.display().to_string(), path)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Saves the paths under the key, unless an entry already exists for it, and evicts
+    /// entries based on the configured limits. Returns whether a new entry was created.
+    /// The paths are copied and compressed before the cache is locked, so the check for an
+    /// existing entry is repeated once it is, in case another run saved the same key.
+    pub async fn save(
+        &self,
+        platform: &Platform,
+        logger: &Logger,
+        key: &str,
+        paths: &[String],
+    ) -> Result<bool> {
+        let Some(config) = &self.config else {
+            return Ok(false);
+        };
+
+        let dir = config.cache_dir();
+        let id = entry_id(key);
+        if dir.join(format!("{id}.json")).is_file() {
+            logger
+                .write_line(format!(
+                    "{:<15}: entry for key {key} already exists, skipping save",
+                    "Cache"
+                ))
+                .await?;
+            return Ok(false);
+        }
+
+        let staging_dir = config.tmp_full_path(&Uuid::new_v4().to_string());
+        create_dir_all(&staging_dir).await?;
+
+        let result = Self::save_inner(platform, logger, &dir, &id, key, paths, &staging_dir).await;
+
+        if let Err(e) = remove_dir_all(&staging_dir).await {
+            error!("unable to clean up staging directory for cache {key}: {e}");
+        }
+
+        if matches!(result, Ok(true)) {
+            logger
+                .write_line(format!("{:<15}: saved with key {key}", "Cache"))
+                .await?;
+
+            if let Err(e) = self.evict().await {
+                error!("unable to evict cache entries: {e}");
+            }
+        }
+
+        result
+    }
+
+    async fn save_inner(
+        platform: &Platform,
+        logger: &Logger,
+        dir: &Path,
+        id: &str,
+        key: &str,
+        paths: &[String],
+        staging_dir: &Path,
+    ) -> Result<bool> {
+        let root = staging_dir.join(ARCHIVE_ROOT);
+        let mut found = false;
+
+        for (index, path) in paths.iter().enumerate() {
+            let path_dir = root.join(index.to_string());
+            create_dir_all(&path_dir).await?;
+
+            let target = path_dir.join(ARCHIVE_PATH).display().to_string();
+            match platform.get_as(path, &target).await {
+                Ok(_) if metadata(&target).await.is_ok() => found = true,
+                Ok(_) => debug!("nothing was copied for path {path}"),
+                Err(e) => {
+                    debug!("unable to copy path {path}: {e}");
+                    logger
+                        .write_line(format!(
+                            "{:<15}: path {path} was not found, it will not be cached",
+                            "Cache"
+                        ))
+                        .await?;
+                }
+            }
+        }
+
+        if !found {
+            logger
+                .write_line(format!(
+                    "{:<15}: none of the paths were found, skipping save",
+                    "Cache"
+                ))
+                .await?;
+            return Ok(false);
+        }
+
+        let compressed = compress_to_tar_gz(&root, ARCHIVE_ROOT)?;
+
+        let _lock = Self::lock(dir).await?;
+        if dir.join(format!("{id}.json")).is_file() {
+            logger
+                .write_line(format!(
+                    "{:<15}: entry for key {key} was saved by another run, skipping save",
+                    "Cache"
+                ))
+                .await?;
+            return Ok(false);
+        }
+
+        let archive_path = dir.join(format!("{id}.tar.gz"));
+        let tmp_path = dir.join(format!("{id}.{}.tmp", Uuid::new_v4()));
+        write(&tmp_path, &compressed).await?;
+        rename(&tmp_path, &archive_path).await?;
+
+        let now = now();
+        let entry = CacheEntry {
+            key: key.to_string(),
+            size: compressed.len() as u64,
+            created_at: now,
+            last_used: now,
+        };
+        Self::write_entry(dir, id, &entry).await?;
+
+        Ok(true)
+    }
+
+    /// Removes the entries that haven't been used for longer than the configured max age
+    /// and then the least recently used ones until the rest fit in the max size.
+    pub async fn evict(&self) -> Result<()> {
+        let Some(config) = &self.config else {
+            return Ok(());
+        };
+
+        let dir = config.cache_dir();
+        if !dir.is_dir() {
+            return Ok(());
+        }
+
+        let _lock = Self::lock(&dir).await?;
+        let entries = Self::entries(&dir).await?;
+        let max_size = config.local.cache.max_size.saturating_mul(1024 * 1024);
+        let max_age = config.local.cache.max_age.saturating_mul(24 * 60 * 60);
+
+        for (id, entry) in entries_to_evict(entries, max_size, max_age, now()) {
+            debug!("evicting cache entry {}", entry.key);
+            let paths: [PathBuf; 2] = [
+                dir.join(format!("{id}.json")),
+                dir.join(format!("{id}.tar.gz")),
+            ];
+            for path in paths {
+                if let Err(e) = remove_file(&path).await {
+                    error!("unable to remove {}: {e}", path.display());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        fs::{create_dir_all, read_to_string, remove_dir_all, write},
+    };
+
+    use bld_config::BldConfig;
+    use bld_utils::sync::IntoArc;
+    use uuid::Uuid;
+
+    use super::{Cache, CacheEntry, entries_to_evict, entry_id, find_entry};
+    use crate::{
+        logger::Logger,
+        platform::{Machine, Platform},
+    };
+
+    fn entry(
+        id: &str,
+        key: &str,
+        size: u64,
+        created_at: u64,
+        last_used: u64,
+    ) -> (String, CacheEntry) {
+        (
+            id.to_string(),
+            CacheEntry {
+                key: key.to_string(),
+                size,
+                created_at,
+                last_used,
+            },
+        )
+    }
+
+    #[test]
+    fn entry_id_is_stable_and_file_name_safe() {
+        let id = entry_id("cargo-linux/../abc");
+        assert_eq!(id, entry_id("cargo-linux/../abc"));
+        assert_eq!(id.len(), 64);
+        assert!(id.chars().all(|x| x.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn find_entry_prefers_exact_key() {
+        let entries = vec![
+            entry("1", "cargo-abc", 1, 10, 10),
+            entry("2", "cargo-def", 1, 20, 20),
+        ];
+        let found = find_entry(&entries, "cargo-abc", &["cargo-".to_string()]).unwrap();
+        assert_eq!(found.0, "1");
+    }
+
+    #[test]
+    fn find_entry_uses_most_recent_entry_of_first_matching_restore_key() {
+        let entries = vec![
+            entry("1", "cargo-linux-abc", 1, 10, 10),
+            entry("2", "cargo-linux-def", 1, 20, 20),
+            entry("3", "cargo-mac-ghi", 1, 30, 30),
+        ];
+        let restore_keys = vec!["cargo-linux-".to_string(), "cargo-".to_string()];
+        let found = find_entry(&entries, "cargo-linux-xyz", &restore_keys).unwrap();
+        assert_eq!(found.0, "2");
+
+        let restore_keys = vec!["npm-".to_string()];
+        assert!(find_entry(&entries, "cargo-linux-xyz", &restore_keys).is_none());
+    }
+
+    #[actix_web::test]
+    async fn save_and_restore_round_trip_on_machine() {
+        let root = std::env::temp_dir().join(format!("cache-test-{}", Uuid::new_v4()));
+        let config = BldConfig {
+            root_dir: root.display().to_string(),
+            ..Default::default()
+        }
+        .into_arc();
+        let machine = Machine::new(
+            "cache-test",
+            config.clone(),
+            &HashMap::new(),
+            HashMap::new().into_arc(),
+        )
+        .await
+        .unwrap();
+        let platform = Platform::machine(Box::new(machine));
+        let logger = Logger::mock();
+        let cache = Cache::new(config);
+
+        let dir = root.join("workspace").join("deps");
+        let file = root.join("workspace").join("lock.txt");
+        create_dir_all(dir.join("nested")).unwrap();
+        write(dir.join("nested").join("dep.txt"), "dependency").unwrap();
+        write(&file, "lock").unwrap();
+        let paths = vec![
+            dir.display().to_string(),
+            file.display().to_string(),
+            root.join("missing").display().to_string(),
+        ];
+
+        assert!(
+            cache
+                .save(&platform, &logger, "deps-1", &paths)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !cache
+                .save(&platform, &logger, "deps-1", &paths)
+                .await
+                .unwrap()
+        );
+
+        remove_dir_all(root.join("workspace")).unwrap();
+
+        let restored = cache
+            .restore(&platform, &logger, "deps-2", &["deps-".to_string()], &paths)
+            .await
+            .unwrap();
+        assert_eq!(restored.as_deref(), Some("deps-1"));
+        assert_eq!(
+            read_to_string(dir.join("nested").join("dep.txt")).unwrap(),
+            "dependency"
+        );
+        assert_eq!(read_to_string(&file).unwrap(), "lock");
+
+        let restored = cache
+            .restore(&platform, &logger, "other", &[], &paths)
+            .await
+            .unwrap();
+        assert!(restored.is_none());
+
+        let _ = remove_dir_all(&root);
+    }
+
+    #[actix_web::test]
+    async fn concurrent_saves_of_the_same_key_create_a_single_entry() {
+        let root = std::env::temp_dir().join(format!("cache-test-{}", Uuid::new_v4()));
+        let config = BldConfig {
+            root_dir: root.display().to_string(),
+            ..Default::default()
+        }
+        .into_arc();
+        let machine = Machine::new(
+            "cache-test",
+            config.clone(),
+            &HashMap::new(),
+            HashMap::new().into_arc(),
+        )
+        .await
+        .unwrap();
+        let platform = Platform::machine(Box::new(machine));
+        let logger = Logger::mock();
+        let first = Cache::new(config.clone());
+        let second = Cache::new(config.clone());
+
+        let file = root.join("workspace").join("lock.txt");
+        create_dir_all(root.join("workspace")).unwrap();
+        write(&file, "lock").unwrap();
+        let paths = vec![file.display().to_string()];
+
+        let (first, second) = tokio::join!(
+            first.save(&platform, &logger, "deps-1", &paths),
+            second.save(&platform, &logger, "deps-1", &paths)
+        );
+        assert!(first.unwrap() ^ second.unwrap());
+
+        let files: Vec<String> = std::fs::read_dir(config.cache_dir())
+            .unwrap()
+            .map(|x| x.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|x| x.ends_with(".json") || x.ends_with(".tar.gz"))
+            .collect();
+        assert_eq!(files.len(), 2, "{files:?}");
+
+        let _ = remove_dir_all(&root);
+    }
+
+    #[test]
+    fn entries_to_evict_removes_expired_then_least_recently_used() {
+        let entries = vec![
+            entry("expired", "a", 10, 0, 0),
+            entry("old", "b", 40, 50, 50),
+            entry("recent", "c", 40, 60, 90),
+            entry("newest", "d", 40, 70, 95),
+        ];
+
+        let mut evicted: Vec<String> = entries_to_evict(entries, 80, 50, 100)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        evicted.sort();
+
+        assert_eq!(evicted, vec!["expired".to_string(), "old".to_string()]);
+    }
+}
@@ -3,12 +3,15 @@ pub mod run;
 pub mod server;
 
 use crate::platform::Platform;
-use anyhow::{Result, anyhow};
+use anyhow::{Result, anyhow, bail};
 use bld_config::BldConfig;
-use bld_models::pipeline_run_containers::PipelineRunContainers;
+use bld_models::{
+    pipeline_run_approvals::PipelineRunApprovals, pipeline_run_containers::PipelineRunContainers,
+    pipeline_run_steps::InsertPipelineRunStep,
+};
 use run::RemoteRun;
 use sea_orm::DatabaseConnection;
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 use tokio::sync::mpsc::{Sender, channel};
 use tokio::sync::oneshot;
 
@@ -121,12 +124,18 @@ impl Context {
             .map_err(|e| anyhow!("{e}"))
     }
 
-    pub async fn set_pipeline_as_finished(&self, run_id: String) -> Result<()> {
+    /// Sets the run as finished and stores the outputs of its jobs, so that they can be
+    /// passed to the runs started by its completion.
+    pub async fn set_pipeline_as_finished(
+        &self,
+        run_id: String,
+        outputs: HashMap<String, HashMap<String, String>>,
+    ) -> Result<()> {
         let Self::Server { tx, .. } = self else {
             return Ok(());
         };
 
-        tx.send(ServerContextMessage::SetPipelineAsFinished(run_id))
+        tx.send(ServerContextMessage::SetPipelineAsFinished { run_id, outputs })
             .await
             .map_err(|e| anyhow!("{e}"))
     }
@@ -191,6 +200,161 @@ impl Context {
             .map_err(|e| anyhow!("{e}"))
     }
 
+    /// Records a pending approval for a job of the run and moves the run into the waiting
+    /// for approval state, returning the id of the approval. There is nobody to approve a
+    /// run outside of a server, so a local run fails while a mock one needs no approval.
+    pub async fn add_approval(
+        &self,
+        job: String,
+        message: Option<String>,
+        approvers: Vec<String>,
+    ) -> Result<Option<String>> {
+        let tx = match self {
+            Self::Server { tx, .. } => tx,
+            Self::Local(_) => bail!("approval jobs can only run on a server"),
+            Self::Mock => return Ok(None),
+        };
+
+        let (resp_tx, resp_rx) = oneshot::channel();
+
+        tx.send(ServerContextMessage::AddApproval {
+            job,
+            message,
+            approvers,
+            resp_tx,
+        })
+        .await
+        .map_err(|e| anyhow!(e.to_string()))?;
+
+        resp_rx.await.map_err(|e| anyhow!("{e}"))?.map(Some)
+    }
+
+    pub async fn approval(&self, id: String) -> Result<PipelineRunApprovals> {
+        let Self::Server { tx, .. } = self else {
+            bail!("approval jobs can only run on a server");
+        };
+
+        let (resp_tx, resp_rx) = oneshot::channel();
+
+        tx.send(ServerContextMessage::Approval { id, resp_tx })
+            .await
+            .map_err(|e| anyhow!(e.to_string()))?;
+
+        resp_rx.await.map_err(|e| anyhow!("{e}"))?
+    }
+
+    pub async fn set_approval_as_timed_out(&self, id: String) -> Result<()> {
+        let Self::Server { tx, .. } = self else {
+            return Ok(());
+        };
+
+        tx.send(ServerContextMessage::SetApprovalAsTimedOut(id))
+            .await
+            .map_err(|e| anyhow!("{e}"))
+    }
+
+    /// Records the commit that a checkout step of the job cloned, so that the run keeps
+    /// track of the exact sources it used. Only a server run stores them.
+    pub async fn add_checkout(
+        &self,
+        job: String,
+        repository: String,
+        reference: Option<String>,
+        sha: String,
+        path: String,
+    ) -> Result<()> {
+        let Self::Server { tx, .. } = self else {
+            return Ok(());
+        };
+
+        tx.send(ServerContextMessage::AddCheckout {
+            job,
+            repository,
+            reference,
+            sha,
+            path,
+        })
+        .await
+        .map_err(|e| anyhow!("{e}"))
+    }
+
+    /// Records a job of the run that has started, along with the combination of its matrix
+    /// and the platform it runs on. Only a server run stores them.
+    pub async fn add_job(
+        &self,
+        id: String,
+        name: String,
+        matrix: Option<String>,
+        platform: Option<String>,
+    ) -> Result<()> {
+        let Self::Server { tx, .. } = self else {
+            return Ok(());
+        };
+
+        tx.send(ServerContextMessage::AddJob {
+            id,
+            name,
+            matrix,
+            platform,
+        })
+        .await
+        .map_err(|e| anyhow!("{e}"))
+    }
+
+    pub async fn set_job_state(
+        &self,
+        id: String,
+        state: String,
+        error: Option<String>,
+    ) -> Result<()> {
+        let Self::Server { tx, .. } = self else {
+            return Ok(());
+        };
+
+        tx.send(ServerContextMessage::SetJobState { id, state, error })
+            .await
+            .map_err(|e| anyhow!("{e}"))
+    }
+
+    /// Records a step of a job that has started, once for each combination of its matrix.
+    pub async fn add_step(
+        &self,
+        id: String,
+        job_id: String,
+        step_id: String,
+        name: Option<String>,
+        matrix: Option<String>,
+    ) -> Result<()> {
+        let Self::Server { tx, .. } = self else {
+            return Ok(());
+        };
+
+        tx.send(ServerContextMessage::AddStep(InsertPipelineRunStep {
+            id,
+            job_id,
+            step_id,
+            name,
+            matrix,
+        }))
+        .await
+        .map_err(|e| anyhow!("{e}"))
+    }
+
+    pub async fn set_step_state(
+        &self,
+        id: String,
+        state: String,
+        error: Option<String>,
+    ) -> Result<()> {
+        let Self::Server { tx, .. } = self else {
+            return Ok(());
+        };
+
+        tx.send(ServerContextMessage::SetStepState { id, state, error })
+            .await
+            .map_err(|e| anyhow!("{e}"))
+    }
+
     pub async fn run_faulted(&self) -> Result<()> {
         if matches!(self, Self::Mock) {
             return Ok(());
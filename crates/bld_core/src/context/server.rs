@@ -5,14 +5,23 @@ use anyhow::{Result, anyhow};
 use bld_config::BldConfig;
 use bld_http::Request;
 use bld_models::{
+    pipeline_run_approvals::{
+        self, InsertPipelineRunApproval, PRA_STATE_TIMED_OUT, PipelineRunApprovals,
+    },
+    pipeline_run_checkouts::{self, InsertPipelineRunCheckout},
     pipeline_run_containers::{
         self, InsertPipelineRunContainer, PRC_STATE_FAULTED, PRC_STATE_KEEP_ALIVE,
         PRC_STATE_REMOVED, PipelineRunContainers,
     },
-    pipeline_runs::{self, PR_STATE_FAULTED, PR_STATE_FINISHED, PR_STATE_RUNNING},
+    pipeline_run_jobs::{self, InsertPipelineRunJob},
+    pipeline_run_outputs::{self, InsertPipelineRunOutput},
+    pipeline_run_steps::{self, InsertPipelineRunStep},
+    pipeline_runs::{
+        self, PR_STATE_FAULTED, PR_STATE_FINISHED, PR_STATE_RUNNING, PR_STATE_WAITING_APPROVAL,
+    },
 };
 use sea_orm::DatabaseConnection;
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 use tokio::sync::{mpsc::Receiver, oneshot};
 use tracing::{debug, error};
 use uuid::Uuid;
@@ -23,7 +32,10 @@ pub enum ServerContextMessage {
     AddPlatform(Arc<Platform>),
     RemovePlatform(String),
     SetPipelineAsRunning(String),
-    SetPipelineAsFinished(String),
+    SetPipelineAsFinished {
+        run_id: String,
+        outputs: HashMap<String, HashMap<String, String>>,
+    },
     SetPipelineAsFaulted(String),
     AddContainer {
         container_id: String,
@@ -32,6 +44,41 @@ pub enum ServerContextMessage {
     SetContainerAsRemoved(String),
     SetContainerAsFaulted(String),
     KeepAliveContainer(String),
+    AddApproval {
+        job: String,
+        message: Option<String>,
+        approvers: Vec<String>,
+        resp_tx: oneshot::Sender<Result<String>>,
+    },
+    Approval {
+        id: String,
+        resp_tx: oneshot::Sender<Result<PipelineRunApprovals>>,
+    },
+    SetApprovalAsTimedOut(String),
+    AddCheckout {
+        job: String,
+        repository: String,
+        reference: Option<String>,
+        sha: String,
+        path: String,
+    },
+    AddJob {
+        id: String,
+        name: String,
+        matrix: Option<String>,
+        platform: Option<String>,
+    },
+    SetJobState {
+        id: String,
+        state: String,
+        error: Option<String>,
+    },
+    AddStep(InsertPipelineRunStep),
+    SetStepState {
+        id: String,
+        state: String,
+        error: Option<String>,
+    },
     RunFaulted(oneshot::Sender<()>),
 }
 
@@ -100,7 +147,11 @@ impl ServerContextBackend {
                         .await?;
                 }
 
-                ServerContextMessage::SetPipelineAsFinished(run_id) => {
+                ServerContextMessage::SetPipelineAsFinished { run_id, outputs } => {
+                    let _ = self
+                        .add_outputs(&run_id, outputs)
+                        .await
+                        .map_err(|e| error!("{e}"));
                     self.update_pipeline_state(&run_id, PR_STATE_FINISHED)
                         .await?;
                 }
@@ -142,6 +193,97 @@ impl ServerContextBackend {
                     .await?;
                 }
 
+                ServerContextMessage::AddApproval {
+                    job,
+                    message,
+                    approvers,
+                    resp_tx,
+                } => {
+                    let result = self.add_approval(job, message, approvers).await;
+                    resp_tx
+                        .send(result)
+                        .map_err(|_| anyhow!("oneshot response sender dropped"))?;
+                }
+
+                ServerContextMessage::Approval { id, resp_tx } => {
+                    let result =
+                        pipeline_run_approvals::select_by_id(self.conn.as_ref(), &id).await;
+                    resp_tx
+                        .send(result)
+                        .map_err(|_| anyhow!("oneshot response sender dropped"))?;
+                }
+
+                ServerContextMessage::SetApprovalAsTimedOut(id) => {
+                    let _ = pipeline_run_approvals::update_decision(
+                        self.conn.as_ref(),
+                        &id,
+                        PRA_STATE_TIMED_OUT,
+                        None,
+                    )
+                    .await
+                    .map_err(|e| error!("{e}"));
+                }
+
+                ServerContextMessage::AddCheckout {
+                    job,
+                    repository,
+                    reference,
+                    sha,
+                    path,
+                } => {
+                    let _ = pipeline_run_checkouts::insert(
+                        self.conn.as_ref(),
+                        InsertPipelineRunCheckout {
+                            id: Uuid::new_v4().to_string(),
+                            run_id: self.run_id.to_owned(),
+                            job,
+                            repository,
+                            reference,
+                            sha,
+                            path,
+                        },
+                    )
+                    .await
+                    .map_err(|e| error!("{e}"));
+                }
+
+                ServerContextMessage::AddJob {
+                    id,
+                    name,
+                    matrix,
+                    platform,
+                } => {
+                    let model = InsertPipelineRunJob {
+                        id,
+                        run_id: self.run_id.to_owned(),
+                        name,
+                        matrix,
+                        platform,
+                    };
+                    let _ = pipeline_run_jobs::insert(self.conn.as_ref(), model)
+                        .await
+                        .map_err(|e| error!("{e}"));
+                }
+
+                ServerContextMessage::SetJobState { id, state, error } => {
+                    let _ = pipeline_run_jobs::update_state(self.conn.as_ref(), &id, &state, error)
+                        .await
+                        .map_err(|e| error!("{e}"));
+                }
+
+                ServerContextMessage::AddStep(model) => {
+                    let _ = pipeline_run_steps::insert(self.conn.as_ref(), model)
+                        .await
+                        .map_err(|e| error!("{e}"));
+                }
+
+                ServerContextMessage::SetStepState { id, state, error } => {
+                    let _ =
+                        pipeline_run_steps::update_state(self.conn.as_ref(), &id, &state, error)
+                            .await
+                            .map_err(|e| error!("{e}"));
+                }
+
                 ServerContextMessage::RunFaulted(resp_tx) => self.run_faulted(resp_tx).await?,
             }
         }
@@ -173,6 +315,52 @@ impl ServerContextBackend {
         Ok(())
     }
 
+    async fn add_outputs(
+        &self,
+        run_id: &str,
+        outputs: HashMap<String, HashMap<String, String>>,
+    ) -> Result<()> {
+        let models = outputs
+            .into_iter()
+            .flat_map(|(job, outputs)| {
+                outputs
+                    .into_iter()
+                    .map(move |(name, value)| InsertPipelineRunOutput {
+                        id: Uuid::new_v4().to_string(),
+                        run_id: run_id.to_owned(),
+                        job: job.to_owned(),
+                        name,
+                        value,
+                    })
+            })
+            .collect();
+        pipeline_run_outputs::insert_many(self.conn.as_ref(), models).await
+    }
+
+    async fn add_approval(
+        &self,
+        job: String,
+        message: Option<String>,
+        approvers: Vec<String>,
+    ) -> Result<String> {
+        let approval = pipeline_run_approvals::insert(
+            self.conn.as_ref(),
+            InsertPipelineRunApproval {
+                id: Uuid::new_v4().to_string(),
+                run_id: self.run_id.to_owned(),
+                job,
+                message,
+                approvers,
+            },
+        )
+        .await?;
+
+        self.update_pipeline_state(&self.run_id, PR_STATE_WAITING_APPROVAL)
+            .await?;
+
+        Ok(approval.id)
+    }
+
     async fn run_faulted(&mut self, resp_tx: oneshot::Sender<()>) -> Result<()> {
         self.update_pipeline_state(&self.run_id, PR_STATE_FAULTED)
             .await?;
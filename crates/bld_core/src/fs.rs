@@ -38,7 +38,7 @@ impl FileSystem {
         Self::Server { config, conn }
     }
 
-    fn config(&self) -> &BldConfig {
+    pub fn config(&self) -> &Arc<BldConfig> {
         match self {
             Self::Server { config, .. } | Self::Local { config } => config,
         }
@@ -1,9 +1,11 @@
 pub mod artifacts;
+pub mod cache;
 pub mod context;
 pub mod fs;
 pub mod logger;
 pub mod platform;
 pub mod regex;
 pub mod scanner;
+pub mod secrets;
 pub mod signals;
 pub mod workers;
@@ -1,10 +1,10 @@
 use actix_web::rt::spawn;
 use anyhow::{Result, anyhow};
 use bld_config::BldConfig;
-use std::{fmt::Write as FmtWrite, io::Write, sync::Arc};
+use std::{fmt::Write as FmtWrite, io::Write, mem::take, path::PathBuf, sync::Arc};
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 use tokio::{
-    fs::File,
+    fs::{File, OpenOptions, create_dir_all, read_to_string},
     io::{AsyncReadExt, AsyncWriteExt},
     sync::{
         mpsc::{Receiver, Sender, channel},
@@ -13,7 +13,7 @@ use tokio::{
 };
 use tracing::error;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 enum LogType {
     Write,
     WriteLine,
@@ -23,6 +23,28 @@ enum LogType {
     ErrorLine,
 }
 
+impl LogType {
+    fn is_line(self) -> bool {
+        matches!(self, Self::WriteLine | Self::InfoLine | Self::ErrorLine)
+    }
+
+    fn line(self) -> Self {
+        match self {
+            Self::Write | Self::WriteLine => Self::WriteLine,
+            Self::Info | Self::InfoLine => Self::InfoLine,
+            Self::Error | Self::ErrorLine => Self::ErrorLine,
+        }
+    }
+
+    fn partial(self) -> Self {
+        match self {
+            Self::Write | Self::WriteLine => Self::Write,
+            Self::Info | Self::InfoLine => Self::Info,
+            Self::Error | Self::ErrorLine => Self::Error,
+        }
+    }
+}
+
 #[derive(Debug)]
 enum LoggerMessage {
     Write {
@@ -33,16 +55,108 @@ enum LoggerMessage {
     TryRetrieveOutput {
         resp_tx: oneshot::Sender<String>,
     },
+    Mask {
+        values: Vec<String>,
+        resp_tx: oneshot::Sender<()>,
+    },
+    Fork {
+        job: String,
+        resp_tx: oneshot::Sender<(Vec<String>, Option<PathBuf>)>,
+    },
+    Tag {
+        tag: Option<String>,
+        resp_tx: oneshot::Sender<()>,
+    },
+    Flush {
+        resp_tx: oneshot::Sender<()>,
+    },
 }
 
+const MASK: &str = "***";
+/// The shortest line of a multi-line masked value that is masked on its own, so that lines
+/// like the braces of a json credential don't mask every brace of the output.
+const MIN_MASKED_LINE: usize = 4;
+
 enum LoggerType {
     Shell,
     File(File),
     InMemory(String),
+    Job(JobLog),
+}
+
+/// The output of a single job. Every complete line is forwarded to the parent logger
+/// prefixed with the job's name and tag, while the raw output is also kept in the
+/// job's own log file when the parent logs to a file.
+struct JobLog {
+    parent: Arc<Logger>,
+    name: String,
+    tag: Option<String>,
+    pending: String,
+    path: Option<PathBuf>,
+    file: Option<File>,
+}
+
+impl JobLog {
+    fn label(&self) -> String {
+        match &self.tag {
+            Some(tag) => format!("{} ({tag})", self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    async fn write(&mut self, text: &str, log_type: LogType) -> Result<()> {
+        let text = match log_type {
+            LogType::WriteLine | LogType::InfoLine | LogType::ErrorLine => format!("{text}\n"),
+            LogType::Write | LogType::Info | LogType::Error => text.to_owned(),
+        };
+
+        if let Some(file) = self.file.as_mut() {
+            file.write_all(text.as_bytes()).await?;
+        }
+
+        self.pending.push_str(&text);
+        while let Some(idx) = self.pending.find('\n') {
+            let line: String = self.pending.drain(..=idx).collect();
+            self.forward(line.trim_end_matches(['\n', '\r']), log_type)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn forward(&self, line: &str, log_type: LogType) -> Result<()> {
+        let text = format!("[{}] {line}", self.label());
+        match log_type {
+            LogType::Write | LogType::WriteLine => self.parent.write_line(text).await,
+            LogType::Info | LogType::InfoLine => self.parent.info_line(text).await,
+            LogType::Error | LogType::ErrorLine => self.parent.error_line(text).await,
+        }
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        if !self.pending.is_empty() {
+            let line = take(&mut self.pending);
+            self.forward(&line, LogType::Write).await?;
+        }
+        if let Some(file) = self.file.as_mut() {
+            file.flush().await?;
+        }
+        self.parent.flush().await
+    }
+
+    async fn output(&self) -> Result<String> {
+        match &self.path {
+            Some(path) if path.is_file() => Ok(read_to_string(path).await?),
+            _ => Ok(String::new()),
+        }
+    }
 }
 
 struct LoggerBackend {
     logger_type: LoggerType,
+    masked: Vec<String>,
+    pending: String,
+    pending_type: LogType,
+    job_logs: Option<(Arc<BldConfig>, String)>,
     rx: Receiver<LoggerMessage>,
 }
 
@@ -50,6 +164,10 @@ impl LoggerBackend {
     pub fn shell(rx: Receiver<LoggerMessage>) -> Self {
         Self {
             logger_type: LoggerType::Shell,
+            masked: Vec::new(),
+            pending: String::new(),
+            pending_type: LogType::Write,
+            job_logs: None,
             rx,
         }
     }
@@ -66,6 +184,10 @@ impl LoggerBackend {
             } else {
                 File::create(&path).await?
             }),
+            masked: Vec::new(),
+            pending: String::new(),
+            pending_type: LogType::Write,
+            job_logs: Some((config, run_id.to_owned())),
             rx,
         })
     }
@@ -73,54 +195,89 @@ impl LoggerBackend {
     pub fn in_memory(rx: Receiver<LoggerMessage>) -> Self {
         Self {
             logger_type: LoggerType::InMemory(String::new()),
+            masked: Vec::new(),
+            pending: String::new(),
+            pending_type: LogType::Write,
+            job_logs: None,
             rx,
         }
     }
 
+    pub async fn job(
+        parent: Arc<Logger>,
+        name: &str,
+        masked: Vec<String>,
+        path: Option<PathBuf>,
+        rx: Receiver<LoggerMessage>,
+    ) -> Result<Self> {
+        let file = match &path {
+            Some(path) => {
+                if let Some(dir) = path.parent() {
+                    create_dir_all(dir).await?;
+                }
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .await?;
+                Some(file)
+            }
+            None => None,
+        };
+        Ok(Self {
+            logger_type: LoggerType::Job(JobLog {
+                parent,
+                name: name.to_owned(),
+                tag: None,
+                pending: String::new(),
+                path,
+                file,
+            }),
+            masked,
+            pending: String::new(),
+            pending_type: LogType::Write,
+            job_logs: None,
+            rx,
+        })
+    }
+
     async fn receive_inner(mut self) -> Result<()> {
         while let Some(msg) = self.rx.recv().await {
             match msg {
                 LoggerMessage::Write {
                     text,
-                    log_type: LogType::Write,
+                    log_type,
                     resp_tx,
-                } => self.write(&text, resp_tx).await?,
-
-                LoggerMessage::Write {
-                    text,
-                    log_type: LogType::WriteLine,
-                    resp_tx,
-                } => self.write_line(&text, resp_tx).await?,
+                } => {
+                    for (text, log_type) in self.lines(text, log_type) {
+                        self.write_text(&text, log_type).await?;
+                    }
+                    resp_tx
+                        .send(())
+                        .map_err(|_| anyhow!("oneshot response sender dropped"))?;
+                }
 
-                LoggerMessage::Write {
-                    text,
-                    log_type: LogType::Info,
-                    resp_tx,
-                } => self.info(&text, resp_tx).await?,
+                LoggerMessage::TryRetrieveOutput { resp_tx } => {
+                    self.write_pending().await?;
+                    self.try_retrieve_output(resp_tx).await?
+                }
 
-                LoggerMessage::Write {
-                    text,
-                    log_type: LogType::InfoLine,
-                    resp_tx,
-                } => self.info_line(&text, resp_tx).await?,
+                LoggerMessage::Mask { values, resp_tx } => self.mask(values, resp_tx)?,
 
-                LoggerMessage::Write {
-                    text,
-                    log_type: LogType::Error,
-                    resp_tx,
-                } => self.error(&text, resp_tx).await?,
+                LoggerMessage::Fork { job, resp_tx } => self.fork(&job, resp_tx)?,
 
-                LoggerMessage::Write {
-                    text,
-                    log_type: LogType::ErrorLine,
-                    resp_tx,
-                } => self.error_line(&text, resp_tx).await?,
+                LoggerMessage::Tag { tag, resp_tx } => self.tag(tag, resp_tx)?,
 
-                LoggerMessage::TryRetrieveOutput { resp_tx } => {
-                    self.try_retrieve_output(resp_tx).await?
-                }
+                LoggerMessage::Flush { resp_tx } => self.flush(resp_tx).await?,
             }
         }
+
+        // Forwarding any output of a job that didn't end with a new line once every
+        // handle to its logger has been dropped.
+        self.write_pending().await?;
+        if let LoggerType::Job(job) = &mut self.logger_type {
+            job.flush().await?;
+        }
         Ok(())
     }
 
@@ -132,7 +289,126 @@ impl LoggerBackend {
         });
     }
 
-    pub async fn write(&mut self, text: &str, resp_tx: oneshot::Sender<()>) -> Result<()> {
+    /// Splits the text into what can be written right away. Once there are values to mask,
+    /// the text is held back until its line is complete so that a value split across
+    /// writes, such as the chunks of a container's output, is still found and replaced.
+    fn lines(&mut self, text: String, log_type: LogType) -> Vec<(String, LogType)> {
+        if self.masked.is_empty() {
+            return vec![(text, log_type)];
+        }
+
+        self.pending.push_str(&text);
+        if log_type.is_line() {
+            self.pending.push('\n');
+        }
+        self.pending_type = log_type;
+
+        let mut lines = vec![];
+        while let Some(idx) = self.pending.find('\n') {
+            let line: String = self.pending.drain(..=idx).collect();
+            let line = line.trim_end_matches('\n').to_owned();
+            lines.push((self.redact(line), log_type.line()));
+        }
+        lines
+    }
+
+    /// Writes the start of a line that is still held back for masking.
+    async fn write_pending(&mut self) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let pending = take(&mut self.pending);
+        let text = self.redact(pending);
+        self.write_text(&text, self.pending_type.partial()).await
+    }
+
+    async fn write_text(&mut self, text: &str, log_type: LogType) -> Result<()> {
+        match log_type {
+            LogType::Write => self.write(text).await,
+            LogType::WriteLine => self.write_line(text).await,
+            LogType::Info => self.info(text).await,
+            LogType::InfoLine => self.info_line(text).await,
+            LogType::Error => self.error(text).await,
+            LogType::ErrorLine => self.error_line(text).await,
+        }
+    }
+
+    /// Replaces every occurrence of a masked value in the text. Longer values are
+    /// replaced first so that a value containing another masked value is hidden entirely.
+    fn redact(&self, text: String) -> String {
+        self.masked.iter().fold(text, |acc, value| {
+            if acc.contains(value.as_str()) {
+                acc.replace(value.as_str(), MASK)
+            } else {
+                acc
+            }
+        })
+    }
+
+    /// Adds the values to mask. The output is redacted a line at a time, so a value that
+    /// spans several lines, like a private key, has each of its lines masked as well.
+    fn mask(&mut self, values: Vec<String>, resp_tx: oneshot::Sender<()>) -> Result<()> {
+        let lines: Vec<String> = values
+            .iter()
+            .filter(|value| value.contains('\n'))
+            .flat_map(|value| value.lines())
+            .map(str::trim)
+            .filter(|line| line.len() >= MIN_MASKED_LINE)
+            .map(str::to_owned)
+            .collect();
+        for value in values.into_iter().chain(lines) {
+            if !value.is_empty() && !self.masked.contains(&value) {
+                self.masked.push(value);
+            }
+        }
+        self.masked.sort_by_key(|x| std::cmp::Reverse(x.len()));
+
+        resp_tx
+            .send(())
+            .map_err(|_| anyhow!("oneshot response sender dropped"))
+    }
+
+    /// Provides what a job logger needs from its parent, which is the values masked so far
+    /// and the path of the job's own log file if the parent logs to a file.
+    fn fork(
+        &self,
+        job: &str,
+        resp_tx: oneshot::Sender<(Vec<String>, Option<PathBuf>)>,
+    ) -> Result<()> {
+        let path = self
+            .job_logs
+            .as_ref()
+            .map(|(config, run_id)| config.job_log_full_path(run_id, job));
+
+        resp_tx
+            .send((self.masked.clone(), path))
+            .map_err(|_| anyhow!("oneshot response sender dropped"))
+    }
+
+    fn tag(&mut self, tag: Option<String>, resp_tx: oneshot::Sender<()>) -> Result<()> {
+        if let LoggerType::Job(job) = &mut self.logger_type {
+            job.tag = tag;
+        }
+
+        resp_tx
+            .send(())
+            .map_err(|_| anyhow!("oneshot response sender dropped"))
+    }
+
+    async fn flush(&mut self, resp_tx: oneshot::Sender<()>) -> Result<()> {
+        self.write_pending().await?;
+        match &mut self.logger_type {
+            LoggerType::File(handle) => handle.flush().await?,
+            LoggerType::Job(job) => job.flush().await?,
+            LoggerType::Shell | LoggerType::InMemory(_) => {}
+        }
+
+        resp_tx
+            .send(())
+            .map_err(|_| anyhow!("oneshot response sender dropped"))
+    }
+
+    async fn write(&mut self, text: &str) -> Result<()> {
         match &mut self.logger_type {
             LoggerType::Shell => {
                 print!("{text}");
@@ -144,14 +420,13 @@ impl LoggerBackend {
             LoggerType::InMemory(output) => {
                 write!(output, "{text}")?;
             }
+            LoggerType::Job(job) => job.write(text, LogType::Write).await?,
         }
 
-        resp_tx
-            .send(())
-            .map_err(|_| anyhow!("oneshot response sender dropped"))
+        Ok(())
     }
 
-    pub async fn write_line(&mut self, text: &str, resp_tx: oneshot::Sender<()>) -> Result<()> {
+    async fn write_line(&mut self, text: &str) -> Result<()> {
         match &mut self.logger_type {
             LoggerType::Shell => {
                 println!("{text}");
@@ -164,14 +439,13 @@ impl LoggerBackend {
             LoggerType::InMemory(output) => {
                 writeln!(output, "{text}")?;
             }
+            LoggerType::Job(job) => job.write(text, LogType::WriteLine).await?,
         }
 
-        resp_tx
-            .send(())
-            .map_err(|_| anyhow!("oneshot response sender dropped"))
+        Ok(())
     }
 
-    pub async fn info(&mut self, text: &str, resp_tx: oneshot::Sender<()>) -> Result<()> {
+    async fn info(&mut self, text: &str) -> Result<()> {
         match &mut self.logger_type {
             LoggerType::Shell => {
                 let mut stdout = StandardStream::stdout(ColorChoice::Always);
@@ -186,14 +460,13 @@ impl LoggerBackend {
             LoggerType::InMemory(output) => {
                 write!(output, "{text}")?;
             }
+            LoggerType::Job(job) => job.write(text, LogType::Info).await?,
         }
 
-        resp_tx
-            .send(())
-            .map_err(|_| anyhow!("oneshot response sender dropped"))
+        Ok(())
     }
 
-    pub async fn info_line(&mut self, text: &str, resp_tx: oneshot::Sender<()>) -> Result<()> {
+    async fn info_line(&mut self, text: &str) -> Result<()> {
         match &mut self.logger_type {
             LoggerType::Shell => {
                 let mut stdout = StandardStream::stdout(ColorChoice::Always);
@@ -209,14 +482,13 @@ impl LoggerBackend {
             LoggerType::InMemory(output) => {
                 writeln!(output, "{text}")?;
             }
+            LoggerType::Job(job) => job.write(text, LogType::InfoLine).await?,
         }
 
-        resp_tx
-            .send(())
-            .map_err(|_| anyhow!("oneshot response sender dropped"))
+        Ok(())
     }
 
-    pub async fn error(&mut self, text: &str, resp_tx: oneshot::Sender<()>) -> Result<()> {
+    async fn error(&mut self, text: &str) -> Result<()> {
         match &mut self.logger_type {
             LoggerType::Shell => {
                 let mut stderr = StandardStream::stderr(ColorChoice::Always);
@@ -231,14 +503,13 @@ impl LoggerBackend {
             LoggerType::InMemory(output) => {
                 write!(output, "{text}")?;
             }
+            LoggerType::Job(job) => job.write(text, LogType::Error).await?,
         }
 
-        resp_tx
-            .send(())
-            .map_err(|_| anyhow!("oneshot rsponse sender dropped"))
+        Ok(())
     }
 
-    pub async fn error_line(&mut self, text: &str, resp_tx: oneshot::Sender<()>) -> Result<()> {
+    async fn error_line(&mut self, text: &str) -> Result<()> {
         match &mut self.logger_type {
             LoggerType::Shell => {
                 let mut stderr = StandardStream::stderr(ColorChoice::Always);
@@ -254,11 +525,10 @@ impl LoggerBackend {
             LoggerType::InMemory(output) => {
                 writeln!(output, "{text}")?;
             }
+            LoggerType::Job(job) => job.write(text, LogType::ErrorLine).await?,
         }
 
-        resp_tx
-            .send(())
-            .map_err(|_| anyhow!("oneshot response sender dropped"))
+        Ok(())
     }
 
     async fn try_retrieve_output(&mut self, resp_tx: oneshot::Sender<String>) -> Result<()> {
@@ -270,6 +540,7 @@ impl LoggerBackend {
                 output
             }
             LoggerType::InMemory(output) => output.clone(),
+            LoggerType::Job(job) => job.output().await?,
         };
 
         resp_tx
@@ -307,6 +578,18 @@ impl Logger {
         Self { tx: Some(tx) }
     }
 
+    /// Creates a logger for a single job that streams its output into the parent logger
+    /// line by line, each line prefixed with the job's name. If the parent logs to a file
+    /// the job's output is also kept in a separate file for the run.
+    pub async fn job(parent: Arc<Logger>, name: &str) -> Result<Self> {
+        let (masked, path) = parent.fork(name).await?;
+        let (tx, rx) = channel(4096);
+        LoggerBackend::job(parent, name, masked, path, rx)
+            .await?
+            .receive();
+        Ok(Self { tx: Some(tx) })
+    }
+
     pub fn mock() -> Self {
         Self { tx: None }
     }
@@ -409,6 +692,55 @@ impl Logger {
         resp_rx.await.map_err(|e| anyhow!(e))
     }
 
+    /// Registers values that will be replaced with a mask in everything written
+    /// through this logger from this point on.
+    pub async fn mask(&self, values: Vec<String>) -> Result<()> {
+        let Some(tx) = &self.tx else { return Ok(()) };
+        let (resp_tx, resp_rx) = oneshot::channel();
+
+        tx.send(LoggerMessage::Mask { values, resp_tx }).await?;
+
+        resp_rx.await.map_err(|e| anyhow!(e))
+    }
+
+    async fn fork(&self, job: &str) -> Result<(Vec<String>, Option<PathBuf>)> {
+        let Some(tx) = &self.tx else {
+            return Ok((vec![], None));
+        };
+        let (resp_tx, resp_rx) = oneshot::channel();
+
+        tx.send(LoggerMessage::Fork {
+            job: job.to_owned(),
+            resp_tx,
+        })
+        .await?;
+
+        resp_rx.await.map_err(|e| anyhow!(e))
+    }
+
+    /// Sets the tag, such as a matrix combination, that is added next to the name of a job
+    /// in the lines it forwards to its parent. Has no effect on any other kind of logger.
+    pub async fn tag(&self, tag: Option<String>) -> Result<()> {
+        let Some(tx) = &self.tx else { return Ok(()) };
+        let (resp_tx, resp_rx) = oneshot::channel();
+
+        tx.send(LoggerMessage::Tag { tag, resp_tx }).await?;
+
+        resp_rx.await.map_err(|e| anyhow!(e))
+    }
+
+    /// Writes out anything buffered by the logger, which for a job logger means
+    /// forwarding a last line that didn't end with a new line to its parent and then
+    /// flushing the parent as well.
+    pub async fn flush(&self) -> Result<()> {
+        let Some(tx) = &self.tx else { return Ok(()) };
+        let (resp_tx, resp_rx) = oneshot::channel();
+
+        tx.send(LoggerMessage::Flush { resp_tx }).await?;
+
+        resp_rx.await.map_err(|e| anyhow!(e))
+    }
+
     pub async fn try_retrieve_output(&self) -> Result<String> {
         let Some(tx) = &self.tx else {
             return Ok(String::new());
@@ -421,3 +753,147 @@ impl Logger {
         resp_rx.await.map_err(|e| anyhow!(e))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bld_config::BldConfig;
+    use bld_utils::sync::IntoArc;
+
+    use super::Logger;
+
+    #[actix_web::test]
+    async fn masked_values_are_redacted_from_output() {
+        let logger = Logger::in_memory();
+        logger
+            .mask(vec!["hunter2".to_string(), String::new()])
+            .await
+            .unwrap();
+
+        logger
+            .write_line("password is hunter2".to_string())
+            .await
+            .unwrap();
+        logger.error("hunter2hunter2".to_string()).await.unwrap();
+
+        let output = logger.try_retrieve_output().await.unwrap();
+        assert_eq!(output, "password is ***\n******");
+    }
+
+    #[actix_web::test]
+    async fn longer_masked_values_take_precedence() {
+        let logger = Logger::in_memory();
+        logger
+            .mask(vec!["abc".to_string(), "abcdef".to_string()])
+            .await
+            .unwrap();
+
+        logger.write("abcdef abc".to_string()).await.unwrap();
+
+        let output = logger.try_retrieve_output().await.unwrap();
+        assert_eq!(output, "*** ***");
+    }
+
+    #[actix_web::test]
+    async fn masked_values_split_across_writes_are_redacted() {
+        let logger = Logger::in_memory();
+        logger.mask(vec!["hunter2".to_string()]).await.unwrap();
+
+        logger.write("password is hun".to_string()).await.unwrap();
+        logger.write("ter2\nand hun".to_string()).await.unwrap();
+        logger.write_line("ter2 again".to_string()).await.unwrap();
+        logger.write("last hunter".to_string()).await.unwrap();
+        logger.write("2".to_string()).await.unwrap();
+
+        let output = logger.try_retrieve_output().await.unwrap();
+        assert_eq!(output, "password is ***\nand *** again\nlast ***");
+    }
+
+    #[actix_web::test]
+    async fn multi_line_masked_values_are_redacted_line_by_line() {
+        let logger = Logger::in_memory();
+        let key = "-----BEGIN KEY-----\nMIIEvQIBADANBg\r\n  kqhkiG9w0BAQEF\n}\n-----END KEY-----\n";
+        logger.mask(vec![key.to_string()]).await.unwrap();
+
+        logger.write(key.to_string()).await.unwrap();
+        logger
+            .write_line("{ \"key\": 1 }".to_string())
+            .await
+            .unwrap();
+
+        let output = logger.try_retrieve_output().await.unwrap();
+        assert_eq!(output, "***\n***\r\n  ***\n}\n***\n{ \"key\": 1 }\n");
+    }
+
+    #[actix_web::test]
+    async fn values_written_before_masking_are_kept() {
+        let logger = Logger::in_memory();
+        logger.write("token ".to_string()).await.unwrap();
+        logger.mask(vec!["token".to_string()]).await.unwrap();
+        logger.write("token".to_string()).await.unwrap();
+
+        let output = logger.try_retrieve_output().await.unwrap();
+        assert_eq!(output, "token ***");
+    }
+
+    #[actix_web::test]
+    async fn job_lines_are_forwarded_to_parent_with_prefix() {
+        let parent = Logger::in_memory().into_arc();
+        let job = Logger::job(parent.clone(), "build").await.unwrap();
+
+        job.write("compiling ".to_string()).await.unwrap();
+        job.write_line("crate".to_string()).await.unwrap();
+        job.tag(Some("os=alpine".to_string())).await.unwrap();
+        job.write("done".to_string()).await.unwrap();
+        assert_eq!(
+            parent.try_retrieve_output().await.unwrap(),
+            "[build] compiling crate\n"
+        );
+
+        job.flush().await.unwrap();
+        assert_eq!(
+            parent.try_retrieve_output().await.unwrap(),
+            "[build] compiling crate\n[build (os=alpine)] done\n"
+        );
+    }
+
+    #[actix_web::test]
+    async fn job_logger_keeps_the_masked_values_of_its_parent() {
+        let parent = Logger::in_memory().into_arc();
+        parent.mask(vec!["hunter2".to_string()]).await.unwrap();
+        let job = Logger::job(parent.clone(), "build").await.unwrap();
+
+        job.write_line("password is hunter2".to_string())
+            .await
+            .unwrap();
+
+        let output = parent.try_retrieve_output().await.unwrap();
+        assert_eq!(output, "[build] password is ***\n");
+    }
+
+    #[actix_web::test]
+    async fn job_output_is_kept_separately_for_file_loggers() {
+        let root_dir = std::env::temp_dir().join("bld_core_test_job_logs");
+        let _ = std::fs::remove_dir_all(&root_dir);
+        let config = BldConfig {
+            root_dir: root_dir.display().to_string(),
+            ..Default::default()
+        }
+        .into_arc();
+        std::fs::create_dir_all(config.log_full_path("run").parent().unwrap()).unwrap();
+
+        let parent = Logger::file(config.clone(), "run")
+            .await
+            .unwrap()
+            .into_arc();
+        let job = Logger::job(parent, "build").await.unwrap();
+        job.write_line("compiling".to_string()).await.unwrap();
+        job.flush().await.unwrap();
+
+        let output = std::fs::read_to_string(config.job_log_full_path("run", "build")).unwrap();
+        assert_eq!(output, "compiling\n");
+        let output = std::fs::read_to_string(config.log_full_path("run")).unwrap();
+        assert_eq!(output, "[build] compiling\n");
+
+        let _ = std::fs::remove_dir_all(&root_dir);
+    }
+}
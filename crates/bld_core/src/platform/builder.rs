@@ -1,13 +1,16 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
 use anyhow::{Result, anyhow};
-use bld_config::BldConfig;
+use bld_config::{BldConfig, path};
 use bld_utils::sync::IntoArc;
 use sea_orm::DatabaseConnection;
 
 use crate::{
     logger::Logger,
-    platform::{Container, Image, Machine, Platform, Ssh, SshConnectOptions, SshExecutionOptions},
+    platform::{
+        Container, ContainerService, Image, Machine, Platform, Ssh, SshConnectOptions,
+        SshExecutionOptions,
+    },
 };
 
 use super::{ContainerOptions, context::PlatformContext};
@@ -18,6 +21,7 @@ pub enum PlatformOptions<'a> {
         image: Image<'a>,
         docker_url: Option<&'a str>,
         volumes: Vec<String>,
+        services: Vec<ContainerService>,
     },
     Ssh(SshConnectOptions<'a>),
     #[default]
@@ -27,6 +31,7 @@ pub enum PlatformOptions<'a> {
 #[derive(Default)]
 pub struct PlatformBuilder<'a> {
     run_id: Option<&'a str>,
+    instance: Option<&'a str>,
     options: PlatformOptions<'a>,
     config: Option<Arc<BldConfig>>,
     pipeline_env: Option<&'a HashMap<String, String>>,
@@ -41,6 +46,13 @@ impl<'a> PlatformBuilder<'a> {
         self
     }
 
+    /// Names the instance of the job that the platform is built for, which gives a machine
+    /// platform its own directory under the one of the run.
+    pub fn instance(mut self, instance: &'a str) -> Self {
+        self.instance = Some(instance);
+        self
+    }
+
     pub fn options(mut self, options: PlatformOptions<'a>) -> Self {
         self.options = options;
         self
@@ -97,6 +109,7 @@ impl<'a> PlatformBuilder<'a> {
                 image,
                 docker_url,
                 volumes,
+                services,
             } => {
                 let context = PlatformContext::new(run_id, self.conn);
                 let options = ContainerOptions {
@@ -106,6 +119,7 @@ impl<'a> PlatformBuilder<'a> {
                     pipeline_env,
                     env,
                     volumes,
+                    services,
                     logger,
                     context,
                 };
@@ -114,13 +128,18 @@ impl<'a> PlatformBuilder<'a> {
             }
 
             PlatformOptions::Ssh(connect) => {
-                let execution = SshExecutionOptions::new(config, pipeline_env, env);
+                let execution =
+                    SshExecutionOptions::new(run_id, config, pipeline_env, env, self.conn);
                 let ssh = Ssh::new(connect, execution).await?;
                 Platform::ssh(Box::new(ssh))
             }
 
             PlatformOptions::Machine => {
-                let machine = Machine::new(run_id, config, pipeline_env, env).await?;
+                let id = match self.instance {
+                    Some(instance) => path![run_id, instance].display().to_string(),
+                    None => run_id.to_string(),
+                };
+                let machine = Machine::new(&id, config, pipeline_env, env).await?;
                 Platform::machine(Box::new(machine))
             }
         }
@@ -129,3 +148,69 @@ impl<'a> PlatformBuilder<'a> {
         Ok(platform)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, fs::remove_dir_all};
+
+    use bld_config::BldConfig;
+    use bld_utils::sync::IntoArc;
+    use uuid::Uuid;
+
+    use crate::logger::Logger;
+
+    use super::{PlatformBuilder, PlatformOptions};
+
+    #[cfg(target_family = "unix")]
+    #[tokio::test]
+    async fn concurrent_machine_instances_of_a_run_use_their_own_directories() {
+        let config = BldConfig {
+            root_dir: std::env::temp_dir().display().to_string(),
+            ..Default::default()
+        }
+        .into_arc();
+        let run_id = format!("machine-instances-test-{}", Uuid::new_v4());
+        let pipeline_env = HashMap::new();
+
+        let build = |instance| {
+            PlatformBuilder::default()
+                .run_id(&run_id)
+                .instance(instance)
+                .options(PlatformOptions::Machine)
+                .config(config.clone())
+                .pipeline_env(&pipeline_env)
+                .env(HashMap::new().into_arc())
+                .logger(Logger::mock().into_arc())
+                .build()
+        };
+        let (first, second) = tokio::join!(build("build-0"), build("build-1"));
+        let (first, second) = (first.unwrap(), second.unwrap());
+
+        let logger = Logger::mock().into_arc();
+        let env = HashMap::new();
+        let (first_sh, second_sh) = tokio::join!(
+            first.shell(logger.clone(), &None, &env, "echo first > file.txt", None),
+            second.shell(logger.clone(), &None, &env, "echo second > file.txt", None),
+        );
+        first_sh.unwrap();
+        second_sh.unwrap();
+
+        let run_dir = config.tmp_full_path(&run_id);
+        let first_file = run_dir.join("build-0").join("file.txt");
+        let second_file = run_dir.join("build-1").join("file.txt");
+        assert_eq!(std::fs::read_to_string(&first_file).unwrap(), "first\n");
+        assert_eq!(std::fs::read_to_string(&second_file).unwrap(), "second\n");
+
+        first.dispose(false).await.unwrap();
+        assert!(!run_dir.join("build-0").exists());
+        assert!(second_file.is_file());
+
+        second
+            .shell(logger, &None, &env, "cat file.txt", None)
+            .await
+            .unwrap();
+
+        second.dispose(false).await.unwrap();
+        let _ = remove_dir_all(&run_dir);
+    }
+}
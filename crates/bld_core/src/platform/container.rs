@@ -1,4 +1,12 @@
-use std::{collections::HashMap, path::Path, path::PathBuf, sync::Arc};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fs::{create_dir_all, read_dir, remove_dir_all, rename},
+    path::Path,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
 
 use anyhow::{Result, anyhow, bail};
 use bld_config::{BldConfig, definitions::BLD_OUTPUTS_ENV_VAR_V3, path};
@@ -14,12 +22,16 @@ use bollard::{
 };
 use futures::StreamExt;
 use tar::{Archive, Builder};
+use tokio::time::timeout as with_timeout;
 use tracing::{debug, error};
 use uuid::Uuid;
 
 use crate::logger::Logger;
 
-use super::{Image, context::PlatformContext, docker};
+use super::{
+    ContainerService, Image, Services, TimedOut, context::PlatformContext, docker,
+    kill_process_group_script, process_group_script,
+};
 
 pub struct ContainerOptions<'a> {
     pub config: Arc<BldConfig>,
@@ -28,6 +40,7 @@ pub struct ContainerOptions<'a> {
     pub pipeline_env: &'a HashMap<String, String>,
     pub env: Arc<HashMap<String, String>>,
     pub volumes: Vec<String>,
+    pub services: Vec<ContainerService>,
     pub logger: Arc<Logger>,
     pub context: PlatformContext,
 }
@@ -40,6 +53,7 @@ pub struct Container {
     pub context: PlatformContext,
     pub env: Vec<String>,
     pub outputs_dir: PathBuf,
+    pub services: Option<Services>,
 }
 
 impl Container {
@@ -48,17 +62,19 @@ impl Container {
         image: &str,
         env: Vec<&str>,
         volumes: Vec<String>,
+        network: Option<&str>,
     ) -> Result<(String, String)> {
         let name = Uuid::new_v4().to_string();
         let options = CreateContainerOptions {
             name: &name,
             platform: None,
         };
-        let host_config = if volumes.is_empty() {
+        let host_config = if volumes.is_empty() && network.is_none() {
             None
         } else {
             Some(HostConfig {
-                binds: Some(volumes),
+                binds: (!volumes.is_empty()).then_some(volumes),
+                network_mode: network.map(str::to_owned),
                 ..Default::default()
             })
         };
@@ -104,13 +120,42 @@ impl Container {
             .image
             .create(&client, options.logger.as_ref())
             .await?;
-        let (id, name) = Container::create(
+
+        let services = if options.services.is_empty() {
+            None
+        } else {
+            debug!("starting services for container");
+            let services = Services::start(
+                &client,
+                options.services,
+                &options.context,
+                options.logger.as_ref(),
+            )
+            .await?;
+            Some(services)
+        };
+
+        let created = Container::create(
             &client,
             options.image.name(),
             container_env,
             options.volumes,
+            services.as_ref().map(|x| x.network.as_str()),
         )
-        .await?;
+        .await;
+
+        let (id, name) = match created {
+            Ok(created) => created,
+            Err(e) => {
+                if let Some(services) = services.as_ref() {
+                    let _ = services
+                        .dispose(&client)
+                        .await
+                        .inspect_err(|e| error!("unable to remove services, {e}"));
+                }
+                bail!(e);
+            }
+        };
 
         options.context.add(&id).await?;
 
@@ -122,6 +167,7 @@ impl Container {
             context: options.context,
             env,
             outputs_dir: path!["tmp", "outputs"],
+            services,
         };
 
         instance
@@ -152,18 +198,67 @@ impl Container {
         Ok(())
     }
 
+    /// Copies a file or directory from the container so that it is created at the `to`
+    /// path of the host, instead of inside of it as with `copy_from`.
+    pub async fn copy_from_as(&self, from: &str, to: &str) -> Result<()> {
+        let to = Path::new(to);
+        let parent = to
+            .parent()
+            .ok_or_else(|| anyhow!("unable to retrieve parent for path {}", to.display()))?;
+        let staging_dir = parent.join(Uuid::new_v4().to_string());
+        create_dir_all(&staging_dir)?;
+
+        let result = self
+            .copy_from(from, &staging_dir.display().to_string())
+            .await
+            .and_then(|_| {
+                let entry = read_dir(&staging_dir)?
+                    .next()
+                    .ok_or_else(|| anyhow!("nothing was copied from path {from}"))??;
+                rename(entry.path(), to)?;
+                Ok(())
+            });
+
+        if let Err(e) = remove_dir_all(&staging_dir) {
+            error!("unable to clean up staging directory: {e}");
+        }
+
+        result
+    }
+
     pub async fn copy_into(&self, from: &str, to: &str) -> Result<()> {
-        let mut tar = Builder::new(Vec::new());
         let path = path![from];
 
         let filename = path
             .file_name()
             .ok_or_else(|| anyhow!("unable to retrieve filename for path {from}"))?;
 
-        if path.is_file() {
-            tar.append_path_with_name(from, filename)?;
+        self.upload(&path, filename, to).await
+    }
+
+    /// Copies a file or directory of the host so that it is created at the `to` path of
+    /// the container, instead of inside of it as with `copy_into`.
+    pub async fn copy_into_as(&self, from: &str, to: &str) -> Result<()> {
+        let to_path = Path::new(to);
+        let filename = to_path
+            .file_name()
+            .ok_or_else(|| anyhow!("unable to retrieve filename for path {to}"))?;
+        let parent = match to_path.parent().map(|x| x.display().to_string()) {
+            Some(parent) if !parent.is_empty() => parent,
+            _ => ".".to_string(),
+        };
+
+        self.run_internal_cmd(vec!["mkdir", "-p", &parent]).await?;
+        self.upload(Path::new(from), filename, &parent).await
+    }
+
+    async fn upload(&self, from: &Path, name: &OsStr, to: &str) -> Result<()> {
+        let mut tar = Builder::new(Vec::new());
+
+        if from.is_file() {
+            tar.append_path_with_name(from, name)?;
         } else {
-            tar.append_dir_all(filename, from)?;
+            tar.append_dir_all(name, from)?;
         }
         let content = tar.into_inner()?;
 
@@ -234,7 +329,9 @@ impl Container {
         &self,
         logger: Arc<Logger>,
         working_dir: &Option<String>,
+        env: &HashMap<String, String>,
         input: &str,
+        timeout: Option<Duration>,
     ) -> Result<HashMap<String, String>> {
         let outputs_path = path![&self.outputs_dir, Uuid::new_v4().to_string()]
             .display()
@@ -249,11 +346,20 @@ impl Container {
             .unwrap();
 
         let outputs_env = format!("{BLD_OUTPUTS_ENV_VAR_V3}={}", outputs_path);
+        let exec_env: Vec<String> = env.iter().map(|(k, v)| format!("{k}={v}")).collect();
         let mut env: Vec<&str> = self.env.iter().map(String::as_str).collect();
+        env.extend(exec_env.iter().map(String::as_str));
         env.push(&outputs_env);
 
+        let pid_file = format!("{outputs_path}.pid");
+        let script = process_group_script(&pid_file);
+        let cmd = match timeout {
+            Some(_) => vec!["bash", "-c", &script, "bld", &input],
+            None => vec!["bash", "-c", &input],
+        };
+
         let options = CreateExecOptions {
-            cmd: Some(vec!["bash", "-c", &input]),
+            cmd: Some(cmd),
             env: Some(env),
             attach_stdout: Some(true),
             attach_stderr: Some(true),
@@ -267,20 +373,38 @@ impl Container {
             return Ok(HashMap::new());
         };
 
-        while let Some(result) = output.next().await {
-            let Ok(output) = result else {
-                continue;
-            };
+        let stream_output = async {
+            while let Some(result) = output.next().await {
+                let Ok(output) = result else {
+                    continue;
+                };
 
-            let chunk: Vec<u8> = match output {
-                LogOutput::StdOut { message } => message.into(),
-                LogOutput::StdErr { message } => message.into(),
-                LogOutput::StdIn { .. } | LogOutput::Console { .. } => continue,
-            };
+                let chunk: Vec<u8> = match output {
+                    LogOutput::StdOut { message } => message.into(),
+                    LogOutput::StdErr { message } => message.into(),
+                    LogOutput::StdIn { .. } | LogOutput::Console { .. } => continue,
+                };
 
-            let chunk_str = String::from_utf8(chunk)?;
+                let chunk_str = String::from_utf8(chunk)?;
 
-            logger.write(chunk_str).await?;
+                logger.write(chunk_str).await?;
+            }
+            Ok::<(), anyhow::Error>(())
+        };
+
+        match timeout {
+            Some(duration) => match with_timeout(duration, stream_output).await {
+                Ok(result) => result?,
+                Err(_) => {
+                    let kill = kill_process_group_script(&pid_file);
+                    let _ = self
+                        .run_internal_cmd(vec!["bash", "-c", &kill])
+                        .await
+                        .inspect_err(|e| error!("unable to kill timed out command, {e}"));
+                    bail!(TimedOut::new(duration));
+                }
+            },
+            None => stream_output.await?,
         }
 
         let inspect = self.client.inspect_exec(&exec.id).await?;
@@ -304,10 +428,25 @@ impl Container {
     }
 
     pub async fn keep_alive(&self) -> Result<()> {
-        self.context.keep_alive().await
+        self.context.keep_alive().await?;
+        if let Some(services) = self.services.as_ref() {
+            services.keep_alive().await?;
+        }
+        Ok(())
     }
 
+    /// Removes the container along with any services started for it, even if removing
+    /// the container itself fails.
     pub async fn dispose(&self) -> Result<()> {
+        let result = self.dispose_container().await;
+        let Some(services) = self.services.as_ref() else {
+            return result;
+        };
+        let services_result = services.dispose(&self.client).await;
+        result.and(services_result)
+    }
+
+    async fn dispose_container(&self) -> Result<()> {
         if let Err(e) = self.client.stop_container(&self.name, None).await {
             error!("could not stop container, {e}");
             let _ = self
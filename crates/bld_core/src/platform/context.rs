@@ -32,6 +32,18 @@ impl PlatformContext {
         }
     }
 
+    /// Creates a context for another container of the same run, such as a service.
+    pub fn fork(&self) -> Self {
+        match self {
+            Self::Local => Self::Local,
+            Self::Server { conn, run_id, .. } => Self::Server {
+                conn: conn.clone(),
+                entity_id: None,
+                run_id: run_id.to_owned(),
+            },
+        }
+    }
+
     async fn update_pipeline_state(&self, state: &str) -> Result<()> {
         let Self::Server {
             conn, entity_id, ..
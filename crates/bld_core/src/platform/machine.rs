@@ -1,15 +1,21 @@
-use crate::logger::Logger;
+use crate::{logger::Logger, platform::TimedOut};
 use anyhow::{Result, anyhow, bail};
 use bld_config::{BldConfig, definitions::BLD_OUTPUTS_ENV_VAR_V3, path};
 use bld_utils::{shell::get_shell, variables::parse_variables_iter};
 use std::{
     collections::HashMap,
-    fmt::Write,
     path::{Path, PathBuf},
-    process::ExitStatus,
+    process::{ExitStatus, Stdio},
     sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    fs::{copy, create_dir_all, read_dir, read_to_string, remove_dir_all},
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    process::Child,
+    select,
+    time::timeout as with_timeout,
 };
-use tokio::fs::{copy, create_dir_all, read_dir, read_to_string, remove_dir_all};
 use tracing::debug;
 use uuid::Uuid;
 
@@ -114,7 +120,9 @@ impl Machine {
         &self,
         logger: Arc<Logger>,
         working_dir: &Option<String>,
+        env: &HashMap<String, String>,
         input: &str,
+        timeout: Option<Duration>,
     ) -> Result<HashMap<String, String>> {
         let id = Uuid::new_v4();
         let outputs_file = path![&self.tmp_dir, id.to_string()];
@@ -129,24 +137,35 @@ impl Machine {
 
         let mut shell = get_shell(&mut vec![input])?;
         shell.envs(&self.env);
+        shell.envs(env);
         shell.env(BLD_OUTPUTS_ENV_VAR_V3, &outputs_file);
         shell.current_dir(current_dir);
+        shell.stdout(Stdio::piped());
+        shell.stderr(Stdio::piped());
+        shell.kill_on_drop(true);
+
+        // The command runs in its own process group so that every process it spawns
+        // can be killed along with it when the timeout expires.
+        #[cfg(target_family = "unix")]
+        shell.process_group(0);
+
+        let mut child = shell.spawn()?;
+        let pid = child.id();
+        let status = match timeout {
+            Some(duration) => {
+                match with_timeout(duration, Self::stream_output(&mut child, &logger)).await {
+                    Ok(status) => status?,
+                    Err(_) => {
+                        Self::kill_process_group(pid);
+                        bail!(TimedOut::new(duration));
+                    }
+                }
+            }
+            None => Self::stream_output(&mut child, &logger).await?,
+        };
 
-        let process = shell.output().await?;
-        let mut shell_output = String::new();
-
-        if !process.stderr.is_empty() {
-            writeln!(shell_output, "{}", String::from_utf8_lossy(&process.stderr))?;
-        }
-
-        if !process.stdout.is_empty() {
-            writeln!(shell_output, "{}", String::from_utf8_lossy(&process.stdout))?;
-        }
-
-        logger.write(shell_output).await?;
-
-        if !ExitStatus::success(&process.status) {
-            bail!("command finished with {}", process.status);
+        if !ExitStatus::success(&status) {
+            bail!("command finished with {status}");
         }
 
         let mut outputs = HashMap::new();
@@ -162,6 +181,78 @@ impl Machine {
         Ok(outputs)
     }
 
+    /// Writes every line of the child's stdout and stderr to the logger as soon as it is
+    /// produced, so the two keep the order in which the command printed them, and then
+    /// waits for the child to exit.
+    async fn stream_output(child: &mut Child, logger: &Logger) -> Result<ExitStatus> {
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow!("unable to capture the stdout of the command"))?;
+        let stderr = child
+            .stderr
+            .take()
+            .ok_or_else(|| anyhow!("unable to capture the stderr of the command"))?;
+
+        let mut stdout = Some(Self::lines(stdout));
+        let mut stderr = Some(Self::lines(stderr));
+
+        while stdout.is_some() || stderr.is_some() {
+            let (segment, is_stdout) = select! {
+                Some(segment) = Self::next_line(&mut stdout) => (segment?, true),
+                Some(segment) = Self::next_line(&mut stderr) => (segment?, false),
+            };
+
+            match segment {
+                Some(segment) => {
+                    let line = String::from_utf8_lossy(&segment);
+                    logger
+                        .write_line(line.trim_end_matches('\r').to_owned())
+                        .await?;
+                }
+                None if is_stdout => stdout = None,
+                None => stderr = None,
+            }
+        }
+
+        Ok(child.wait().await?)
+    }
+
+    fn lines<R: AsyncRead + Unpin>(reader: R) -> tokio::io::Split<BufReader<R>> {
+        BufReader::new(reader).split(b'\n')
+    }
+
+    /// Reads the next line of an output that hasn't been closed yet. Returns nothing for
+    /// one that has, which disables its branch in the select of stream_output.
+    async fn next_line<R: AsyncRead + Unpin>(
+        lines: &mut Option<tokio::io::Split<BufReader<R>>>,
+    ) -> Option<std::io::Result<Option<Vec<u8>>>> {
+        match lines {
+            Some(lines) => Some(lines.next_segment().await),
+            None => None,
+        }
+    }
+
+    #[cfg(target_family = "unix")]
+    fn kill_process_group(pid: Option<u32>) {
+        use nix::{
+            sys::signal::{Signal, killpg},
+            unistd::Pid,
+        };
+
+        let Some(pid) = pid.and_then(|x| i32::try_from(x).ok()) else {
+            return;
+        };
+        if let Err(e) = killpg(Pid::from_raw(pid), Signal::SIGKILL) {
+            debug!("unable to kill process group {pid}, {e}");
+        }
+    }
+
+    // The child process is killed when its handle is dropped, which happens when
+    // the timeout expires, so there is nothing more to be done here.
+    #[cfg(target_family = "windows")]
+    fn kill_process_group(_pid: Option<u32>) {}
+
     pub async fn dispose(&self) -> Result<()> {
         remove_dir_all(&self.tmp_dir).await?;
         Ok(())
@@ -170,9 +261,15 @@ impl Machine {
 
 #[cfg(test)]
 mod tests {
-    use super::copy_path;
+    use super::{Machine, copy_path};
+    use crate::{logger::Logger, platform::TimedOut};
     use bld_config::BldConfig;
-    use std::fs::{create_dir_all, read_to_string, remove_dir_all, write};
+    use bld_utils::sync::IntoArc;
+    use std::{
+        collections::HashMap,
+        fs::{create_dir_all, read_to_string, remove_dir_all, write},
+        time::{Duration, Instant},
+    };
     use uuid::Uuid;
 
     #[tokio::test]
@@ -213,4 +310,162 @@ mod tests {
 
         let _ = remove_dir_all(&base);
     }
+
+    #[cfg(target_family = "unix")]
+    #[tokio::test]
+    async fn sh_with_expired_timeout_kills_command() {
+        let config = BldConfig {
+            root_dir: std::env::temp_dir().display().to_string(),
+            ..Default::default()
+        }
+        .into_arc();
+        let id = format!("machine-timeout-test-{}", Uuid::new_v4());
+        let machine = Machine::new(&id, config, &HashMap::new(), HashMap::new().into_arc())
+            .await
+            .unwrap();
+
+        let start = Instant::now();
+        let error = machine
+            .sh(
+                Logger::mock().into_arc(),
+                &None,
+                &HashMap::new(),
+                "sleep 10",
+                Some(Duration::from_secs(1)),
+            )
+            .await
+            .unwrap_err();
+
+        assert!(error.downcast_ref::<TimedOut>().is_some(), "{error}");
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let _ = machine.dispose().await;
+    }
+
+    #[cfg(target_family = "unix")]
+    #[tokio::test]
+    async fn sh_within_timeout_succeeds() {
+        let config = BldConfig {
+            root_dir: std::env::temp_dir().display().to_string(),
+            ..Default::default()
+        }
+        .into_arc();
+        let id = format!("machine-timeout-test-{}", Uuid::new_v4());
+        let machine = Machine::new(&id, config, &HashMap::new(), HashMap::new().into_arc())
+            .await
+            .unwrap();
+
+        let result = machine
+            .sh(
+                Logger::mock().into_arc(),
+                &None,
+                &HashMap::new(),
+                "echo hello",
+                Some(Duration::from_secs(10)),
+            )
+            .await;
+
+        assert!(result.is_ok());
+
+        let _ = machine.dispose().await;
+    }
+
+    #[cfg(target_family = "unix")]
+    #[actix_web::test]
+    async fn sh_streams_stdout_and_stderr_in_order() {
+        let config = BldConfig {
+            root_dir: std::env::temp_dir().display().to_string(),
+            ..Default::default()
+        }
+        .into_arc();
+        let id = format!("machine-stream-test-{}", Uuid::new_v4());
+        let machine = Machine::new(&id, config, &HashMap::new(), HashMap::new().into_arc())
+            .await
+            .unwrap();
+        let logger = Logger::in_memory().into_arc();
+
+        machine
+            .sh(
+                logger.clone(),
+                &None,
+                &HashMap::new(),
+                "echo one; sleep 0.2; echo two >&2; sleep 0.2; printf three",
+                None,
+            )
+            .await
+            .unwrap();
+
+        let output = logger.try_retrieve_output().await.unwrap();
+        assert_eq!(output, "one\ntwo\nthree\n");
+
+        let _ = machine.dispose().await;
+    }
+
+    #[cfg(target_family = "unix")]
+    #[actix_web::test]
+    async fn sh_logs_output_of_failed_command() {
+        let config = BldConfig {
+            root_dir: std::env::temp_dir().display().to_string(),
+            ..Default::default()
+        }
+        .into_arc();
+        let id = format!("machine-stream-test-{}", Uuid::new_v4());
+        let machine = Machine::new(&id, config, &HashMap::new(), HashMap::new().into_arc())
+            .await
+            .unwrap();
+        let logger = Logger::in_memory().into_arc();
+
+        let result = machine
+            .sh(
+                logger.clone(),
+                &None,
+                &HashMap::new(),
+                "echo failing >&2; exit 3",
+                None,
+            )
+            .await;
+
+        assert!(result.is_err());
+        let output = logger.try_retrieve_output().await.unwrap();
+        assert_eq!(output, "failing\n");
+
+        let _ = machine.dispose().await;
+    }
+
+    #[cfg(target_family = "unix")]
+    #[actix_web::test]
+    async fn sh_applies_env_only_to_its_command() {
+        let config = BldConfig {
+            root_dir: std::env::temp_dir().display().to_string(),
+            ..Default::default()
+        }
+        .into_arc();
+        let id = format!("machine-env-test-{}", Uuid::new_v4());
+        let platform_env: HashMap<String, String> =
+            [("STAGE".to_string(), "build".to_string())].into();
+        let machine = Machine::new(&id, config, &platform_env, HashMap::new().into_arc())
+            .await
+            .unwrap();
+        let logger = Logger::in_memory().into_arc();
+        let command = "echo \"$STAGE $TARGET\"";
+
+        let env: HashMap<String, String> = [
+            ("STAGE".to_string(), "test".to_string()),
+            ("TARGET".to_string(), "linux".to_string()),
+        ]
+        .into();
+        machine
+            .sh(logger.clone(), &None, &env, command, None)
+            .await
+            .unwrap();
+        machine
+            .sh(logger.clone(), &None, &HashMap::new(), command, None)
+            .await
+            .unwrap();
+
+        let output = logger.try_retrieve_output().await.unwrap();
+        assert_eq!(output, "test linux\nbuild \n");
+
+        let _ = machine.dispose().await;
+    }
 }
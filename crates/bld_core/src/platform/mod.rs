@@ -4,9 +4,16 @@ mod context;
 mod docker;
 mod image;
 mod machine;
+mod service;
 mod ssh;
 
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    future::Future,
+    sync::Arc,
+    time::Duration,
+};
 
 pub use container::*;
 pub use context::*;
@@ -14,6 +21,7 @@ pub use docker::*;
 use futures::channel::oneshot;
 pub use image::*;
 pub use machine::*;
+pub use service::*;
 pub use ssh::*;
 use tokio::sync::mpsc::{Receiver, Sender, channel};
 use uuid::Uuid;
@@ -24,6 +32,45 @@ use tracing::{debug, error};
 
 use crate::logger::Logger;
 
+/// The error returned by a platform when a shell command exceeded its timeout
+/// and was killed.
+#[derive(Debug)]
+pub struct TimedOut {
+    pub duration: Duration,
+}
+
+impl TimedOut {
+    pub fn new(duration: Duration) -> Self {
+        Self { duration }
+    }
+}
+
+impl Display for TimedOut {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "timed out after {}s", self.duration.as_secs())
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+/// Wraps a command with a bash script that runs it in its own process group and writes
+/// the id of the group in the provided file, so that the command along with every process
+/// it spawned can be killed if it exceeds its timeout. The command itself is expected as
+/// the first positional argument of the script.
+pub(crate) fn process_group_script(pid_file: &str) -> String {
+    format!("set -m; bash -c \"$1\" & echo $! > {pid_file}; wait $!")
+}
+
+/// Quotes a value so that it is passed as a single argument to a posix shell.
+pub fn single_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// The command that kills the process group whose id is stored in the provided file.
+pub(crate) fn kill_process_group_script(pid_file: &str) -> String {
+    format!("kill -KILL -- -$(cat {pid_file})")
+}
+
 pub enum PlatformArtifactsAction {
     Push,
     Get,
@@ -39,7 +86,9 @@ pub enum PlatformMessage {
     Shell {
         logger: Arc<Logger>,
         working_dir: Option<String>,
+        env: HashMap<String, String>,
         command: String,
+        timeout: Option<Duration>,
         resp_tx: oneshot::Sender<Result<HashMap<String, String>>>,
     },
     Dispose {
@@ -85,13 +134,20 @@ impl PlatformBackend {
                 PlatformMessage::Shell {
                     logger,
                     working_dir,
+                    env,
                     command,
-                    resp_tx,
+                    timeout,
+                    mut resp_tx,
                 } => {
-                    let res = self.shell(logger, working_dir, command).await;
-                    resp_tx
-                        .send(res)
-                        .map_err(|_| anyhow!("oneshot channel closed"))?;
+                    // the receiver is dropped once the step running the command has been
+                    // cancelled, in which case the remote command is killed.
+                    let cancelled = resp_tx.cancellation();
+                    let res = self
+                        .shell(logger, working_dir, env, command, timeout, cancelled)
+                        .await;
+                    if resp_tx.send(res).is_err() {
+                        debug!("shell command was cancelled before it finished");
+                    }
                 }
 
                 PlatformMessage::Dispose { resp_tx } => {
@@ -119,9 +175,14 @@ impl PlatformBackend {
         &self,
         logger: Arc<Logger>,
         working_dir: Option<String>,
+        env: HashMap<String, String>,
         command: String,
+        timeout: Option<Duration>,
+        cancelled: impl Future<Output = ()>,
     ) -> Result<HashMap<String, String>> {
-        self.ssh.sh(logger, &working_dir, &command).await
+        self.ssh
+            .sh(logger, &working_dir, &env, &command, timeout, cancelled)
+            .await
     }
 
     pub async fn dispose(&mut self) -> Result<()> {
@@ -131,6 +192,9 @@ impl PlatformBackend {
 
 pub struct Platform {
     id: String,
+    /// What the platform runs on, such as the id of a container or the host of an ssh
+    /// connection.
+    label: String,
     inner: PlatformType,
 }
 
@@ -139,6 +203,7 @@ impl Platform {
         let id = Uuid::new_v4().to_string();
         Self {
             id,
+            label: "machine".to_owned(),
             inner: PlatformType::Machine(machine),
         }
     }
@@ -147,12 +212,14 @@ impl Platform {
         let id = Uuid::new_v4().to_string();
         Self {
             id,
+            label: format!("container {}", container.id),
             inner: PlatformType::Container(container),
         }
     }
 
     pub fn ssh(ssh: Box<Ssh>) -> Self {
         let id = Uuid::new_v4().to_string();
+        let label = format!("ssh {}", ssh.host());
         let (tx, rx) = channel(4096);
 
         spawn(async move {
@@ -164,6 +231,7 @@ impl Platform {
 
         Self {
             id,
+            label,
             inner: PlatformType::Ssh(tx),
         }
     }
@@ -171,6 +239,7 @@ impl Platform {
     pub fn mock() -> Self {
         Self {
             id: Uuid::new_v4().to_string(),
+            label: "mock".to_owned(),
             inner: PlatformType::Mock,
         }
     }
@@ -179,6 +248,10 @@ impl Platform {
         self.id.as_str()
     }
 
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
     pub fn is(&self, pid: &str) -> bool {
         self.id == pid
     }
@@ -225,22 +298,54 @@ impl Platform {
         }
     }
 
+    /// Copies a file or directory of the host so that it is created at the `to` path of
+    /// the platform for every platform type, unlike `push` where a container copies it
+    /// inside of `to`.
+    pub async fn push_as(&self, from: &str, to: &str) -> Result<()> {
+        match &self.inner {
+            PlatformType::Container(container) => container.copy_into_as(from, to).await,
+            _ => self.push(from, to).await,
+        }
+    }
+
+    /// Copies a file or directory of the platform so that it is created at the `to` path
+    /// of the host for every platform type, unlike `get` where a container copies it
+    /// inside of `to`.
+    pub async fn get_as(&self, from: &str, to: &str) -> Result<()> {
+        match &self.inner {
+            PlatformType::Container(container) => container.copy_from_as(from, to).await,
+            _ => self.get(from, to).await,
+        }
+    }
+
+    /// Runs a shell command on the platform. The provided environment variables are
+    /// applied only to this command on top of the environment of the platform.
     pub async fn shell(
         &self,
         logger: Arc<Logger>,
         working_dir: &Option<String>,
+        env: &HashMap<String, String>,
         command: &str,
+        timeout: Option<Duration>,
     ) -> Result<HashMap<String, String>> {
         match &self.inner {
-            PlatformType::Machine(machine) => machine.sh(logger, working_dir, command).await,
-            PlatformType::Container(container) => container.sh(logger, working_dir, command).await,
+            PlatformType::Machine(machine) => {
+                machine.sh(logger, working_dir, env, command, timeout).await
+            }
+            PlatformType::Container(container) => {
+                container
+                    .sh(logger, working_dir, env, command, timeout)
+                    .await
+            }
             PlatformType::Ssh(ssh) => {
                 let (resp_tx, resp_rx) = oneshot::channel();
 
                 ssh.send(PlatformMessage::Shell {
                     logger,
                     working_dir: working_dir.clone(),
+                    env: env.clone(),
                     command: command.to_string(),
+                    timeout,
                     resp_tx,
                 })
                 .await?;
@@ -273,3 +378,18 @@ impl Platform {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::single_quote;
+
+    #[test]
+    fn single_quote_wraps_value() {
+        assert_eq!(single_quote("echo hello"), "'echo hello'");
+    }
+
+    #[test]
+    fn single_quote_escapes_quotes() {
+        assert_eq!(single_quote("echo 'hello'"), r"'echo '\''hello'\'''");
+    }
+}
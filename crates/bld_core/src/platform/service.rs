@@ -0,0 +1,326 @@
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::{Result, anyhow, bail};
+use bollard::{
+    Docker,
+    container::{
+        Config as ContainerConfig, CreateContainerOptions, NetworkingConfig,
+        RemoveContainerOptions, StartContainerOptions,
+    },
+    errors::Error as BollardError,
+    exec::{CreateExecOptions, StartExecResults},
+    network::CreateNetworkOptions,
+    service::{EndpointSettings, HostConfig, PortBinding},
+};
+use futures::StreamExt;
+use tokio::time::sleep;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use crate::logger::Logger;
+
+use super::{Image, context::PlatformContext};
+
+pub struct ServiceHealthCheck {
+    pub command: String,
+    pub interval: Duration,
+    pub retries: u32,
+}
+
+/// A container that runs next to the container of a job, reachable from it by its name.
+pub struct ContainerService {
+    pub name: String,
+    pub image: String,
+    pub env: HashMap<String, String>,
+    pub ports: Vec<String>,
+    pub health_check: Option<ServiceHealthCheck>,
+}
+
+/// Parses a port in the `[host:]container[/protocol]` format into the container port,
+/// with its protocol, and the host port it is published to if any.
+pub fn parse_port(value: &str) -> Result<(String, Option<String>)> {
+    let (ports, protocol) = match value.split_once('/') {
+        Some((ports, protocol)) if protocol == "tcp" || protocol == "udp" => (ports, protocol),
+        Some((_, protocol)) => bail!("'{protocol}' is not a valid protocol (must be tcp or udp)"),
+        None => (value, "tcp"),
+    };
+
+    let (host, container) = match ports.split_once(':') {
+        Some((host, container)) => (Some(host), container),
+        None => (None, ports),
+    };
+
+    for port in host.iter().chain(std::iter::once(&container)) {
+        if port.parse::<u16>().is_err() {
+            bail!("'{port}' is not a valid port number (must be 0-65535)");
+        }
+    }
+
+    Ok((format!("{container}/{protocol}"), host.map(str::to_owned)))
+}
+
+struct RunningService {
+    name: String,
+    container: String,
+    context: PlatformContext,
+}
+
+/// The services of a job along with the docker network that they share with the
+/// job's container.
+pub struct Services {
+    pub network: String,
+    running: Vec<RunningService>,
+}
+
+impl Services {
+    pub async fn start(
+        client: &Docker,
+        services: Vec<ContainerService>,
+        context: &PlatformContext,
+        logger: &Logger,
+    ) -> Result<Self> {
+        let network = format!("bld-{}", Uuid::new_v4());
+        debug!("creating network {network} for services");
+        client
+            .create_network(CreateNetworkOptions {
+                name: network.as_str(),
+                driver: "bridge",
+                ..Default::default()
+            })
+            .await?;
+
+        let mut instance = Self {
+            network,
+            running: vec![],
+        };
+
+        for service in services {
+            if let Err(e) = instance
+                .start_service(client, &service, context, logger)
+                .await
+            {
+                let _ = instance
+                    .dispose(client)
+                    .await
+                    .inspect_err(|e| error!("unable to remove services, {e}"));
+                bail!("service {} failed to start, {e}", service.name);
+            }
+        }
+
+        Ok(instance)
+    }
+
+    async fn start_service(
+        &mut self,
+        client: &Docker,
+        service: &ContainerService,
+        context: &PlatformContext,
+        logger: &Logger,
+    ) -> Result<()> {
+        logger
+            .write_line(format!(
+                "{:<15}: {} ({})",
+                "Service", service.name, service.image
+            ))
+            .await?;
+
+        if client.inspect_image(&service.image).await.is_err() {
+            Image::pull(&service.image, None)
+                .create(client, logger)
+                .await?;
+        }
+
+        let mut exposed_ports = HashMap::new();
+        let mut port_bindings = HashMap::new();
+        for port in &service.ports {
+            let (container_port, host_port) = parse_port(port)?;
+            exposed_ports.insert(container_port.clone(), HashMap::new());
+            port_bindings.insert(
+                container_port,
+                Some(vec![PortBinding {
+                    host_ip: None,
+                    host_port,
+                }]),
+            );
+        }
+
+        let env: Vec<String> = service
+            .env
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect();
+
+        let name = Uuid::new_v4().to_string();
+        let endpoint = EndpointSettings {
+            aliases: Some(vec![service.name.clone()]),
+            ..Default::default()
+        };
+        let config = ContainerConfig {
+            image: Some(service.image.clone()),
+            env: Some(env),
+            exposed_ports: Some(exposed_ports),
+            host_config: Some(HostConfig {
+                network_mode: Some(self.network.clone()),
+                port_bindings: Some(port_bindings),
+                ..Default::default()
+            }),
+            networking_config: Some(NetworkingConfig {
+                endpoints_config: HashMap::from([(self.network.clone(), endpoint)]),
+            }),
+            ..Default::default()
+        };
+        let options = CreateContainerOptions {
+            name: name.clone(),
+            platform: None,
+        };
+
+        let response = client.create_container(Some(options), config).await?;
+        let mut service_context = context.fork();
+        service_context.add(&response.id).await?;
+        self.running.push(RunningService {
+            name: service.name.clone(),
+            container: name.clone(),
+            context: service_context,
+        });
+
+        client
+            .start_container(&name, None::<StartContainerOptions<String>>)
+            .await?;
+
+        if let Some(health_check) = service.health_check.as_ref() {
+            Self::wait_until_healthy(client, &name, health_check, &service.name, logger).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn wait_until_healthy(
+        client: &Docker,
+        container: &str,
+        health_check: &ServiceHealthCheck,
+        name: &str,
+        logger: &Logger,
+    ) -> Result<()> {
+        for attempt in 1..=health_check.retries {
+            match Self::exec(client, container, &health_check.command).await {
+                Ok(0) => {
+                    logger
+                        .write_line(format!("{:<15}: {name} is healthy", "Service"))
+                        .await?;
+                    return Ok(());
+                }
+                Ok(code) => {
+                    debug!("health check of {name} exited with {code} on attempt {attempt}")
+                }
+                Err(e) => debug!("health check of {name} failed on attempt {attempt}, {e}"),
+            }
+            sleep(health_check.interval).await;
+        }
+
+        bail!(
+            "health check did not succeed after {} attempts",
+            health_check.retries
+        )
+    }
+
+    async fn exec(client: &Docker, container: &str, command: &str) -> Result<i64> {
+        let options = CreateExecOptions {
+            cmd: Some(vec!["sh", "-c", command]),
+            attach_stdout: Some(true),
+            attach_stderr: Some(true),
+            ..Default::default()
+        };
+
+        let exec = client.create_exec(container, options).await?;
+        if let StartExecResults::Attached { mut output, .. } =
+            client.start_exec(&exec.id, None).await?
+        {
+            while output.next().await.is_some() {}
+        }
+
+        client
+            .inspect_exec(&exec.id)
+            .await?
+            .exit_code
+            .ok_or_else(|| anyhow!("unable to confirm exit code"))
+    }
+
+    /// Removes every service container and then the network, trying all of them even if
+    /// one fails and returning the first error.
+    pub async fn dispose(&self, client: &Docker) -> Result<()> {
+        let mut result = Ok(());
+
+        for service in self.running.iter() {
+            debug!("removing service {}", service.name);
+            let options = RemoveContainerOptions {
+                force: true,
+                ..Default::default()
+            };
+            match client
+                .remove_container(&service.container, Some(options))
+                .await
+            {
+                Ok(_)
+                | Err(BollardError::DockerResponseServerError {
+                    status_code: 404, ..
+                }) => {
+                    let _ = service
+                        .context
+                        .set_as_removed()
+                        .await
+                        .inspect_err(|e| error!("could not set service as removed, {e}"));
+                }
+                Err(e) => {
+                    error!("could not remove service {}, {e}", service.name);
+                    let _ = service
+                        .context
+                        .set_as_faulted()
+                        .await
+                        .inspect_err(|e| error!("could not set service as faulted, {e}"));
+                    result = result.and(Err(anyhow!(e)));
+                }
+            }
+        }
+
+        if let Err(e) = client.remove_network(&self.network).await {
+            error!("could not remove network {}, {e}", self.network);
+            result = result.and(Err(anyhow!(e)));
+        }
+
+        result
+    }
+
+    pub async fn keep_alive(&self) -> Result<()> {
+        for service in self.running.iter() {
+            service.context.keep_alive().await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_port;
+
+    #[test]
+    fn parse_port_with_container_port_only() {
+        let (container, host) = parse_port("5432").unwrap();
+        assert_eq!(container, "5432/tcp");
+        assert_eq!(host, None);
+    }
+
+    #[test]
+    fn parse_port_with_host_port_and_protocol() {
+        let (container, host) = parse_port("8080:80/udp").unwrap();
+        assert_eq!(container, "80/udp");
+        assert_eq!(host.as_deref(), Some("8080"));
+    }
+
+    #[test]
+    fn parse_port_with_invalid_values() {
+        assert!(parse_port("http").is_err());
+        assert!(parse_port("70000").is_err());
+        assert!(parse_port("8080:80/sctp").is_err());
+        assert!(parse_port(":80").is_err());
+    }
+}
@@ -1,23 +1,36 @@
 use std::{
-    collections::HashMap, future::Future, net::SocketAddr, path::PathBuf, pin::Pin, sync::Arc,
+    collections::HashMap,
+    future::{Future, pending},
+    net::SocketAddr,
+    path::PathBuf,
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
 };
 
 use anyhow::{Result, anyhow, bail};
 use async_ssh2_lite::{AsyncSession, AsyncSftp, TokioTcpStream};
 use bld_config::{BldConfig, definitions::BLD_OUTPUTS_ENV_VAR_V3, path};
+use bld_models::pipeline_runs::{self, is_completed};
 use bld_utils::{sync::IntoArc, variables::parse_variables_iter};
 use futures_util::{
     AsyncReadExt as FuturesUtilAsyncReadExt, AsyncWriteExt as FuturesUtilAsyncWriteExt,
 };
+use sea_orm::DatabaseConnection;
 use tokio::{
     fs::{File, OpenOptions, create_dir},
     io::{AsyncReadExt, AsyncWriteExt},
+    select,
+    time::sleep,
 };
 use tracing::{debug, error};
 use uuid::Uuid;
 use walkdir::WalkDir;
 
-use crate::logger::Logger;
+use crate::{
+    logger::Logger,
+    platform::{TimedOut, kill_process_group_script, process_group_script, single_quote},
+};
 
 type RecursiveFuture = Pin<Box<dyn Future<Output = Result<()>>>>;
 
@@ -51,27 +64,38 @@ impl<'a> SshConnectOptions<'a> {
 }
 
 pub struct SshExecutionOptions<'a> {
+    pub run_id: &'a str,
     pub config: Arc<BldConfig>,
     pub pipeline_env: &'a HashMap<String, String>,
     pub env: Arc<HashMap<String, String>>,
+    pub conn: Option<Arc<DatabaseConnection>>,
 }
 
 impl<'a> SshExecutionOptions<'a> {
     pub fn new(
+        run_id: &'a str,
         config: Arc<BldConfig>,
         pipeline_env: &'a HashMap<String, String>,
         env: Arc<HashMap<String, String>>,
+        conn: Option<Arc<DatabaseConnection>>,
     ) -> Self {
         Self {
+            run_id,
             config,
             pipeline_env,
             env,
+            conn,
         }
     }
 }
 
+/// The directory on the host under which each run keeps the outputs of its steps, in a
+/// directory of its own named after the run.
+const OUTPUTS_ROOT: &str = "tmp/bld_outputs";
+
 pub struct Ssh {
     session: AsyncSession<TokioTcpStream>,
+    host: String,
     env: HashMap<String, String>,
     outputs_dir: PathBuf,
 }
@@ -87,8 +111,9 @@ impl Ssh {
 
         let mut instance = Self {
             session,
+            host: format!("{}:{}", connect.host, connect.port),
             env: HashMap::new(),
-            outputs_dir: path!["tmp", "bld_outputs"],
+            outputs_dir: path![OUTPUTS_ROOT, execution.run_id, Uuid::new_v4().to_string()],
         };
         instance.set_auth(connect.user, &connect.auth).await?;
         instance.set_env(execution.pipeline_env, execution.env);
@@ -101,9 +126,47 @@ impl Ssh {
             ])
             .await?;
 
+        if let Some(conn) = execution.conn
+            && let Err(e) = instance.remove_stale_dirs(&conn).await
+        {
+            error!(
+                "unable to remove the stale directories of ssh host {} due to: {e}",
+                instance.host
+            );
+        }
+
         Ok(instance)
     }
 
+    /// Removes the directories of the runs that are no longer in progress, which a worker
+    /// that died before disposing of its platform leaves behind. The supervisor that cleans
+    /// up after such a worker has no credentials for the host, so they are removed by the
+    /// next platform of the server that connects to it instead. Directories that don't
+    /// belong to a run of the server are left alone.
+    async fn remove_stale_dirs(&self, conn: &DatabaseConnection) -> Result<()> {
+        let listing = self
+            .run_internal_cmd(vec!["ls", "-1", OUTPUTS_ROOT])
+            .await?;
+        for run_id in listing.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let Ok(run) = pipeline_runs::select_by_id(conn, run_id).await else {
+                continue;
+            };
+            if is_completed(&run.state) {
+                debug!(
+                    "removing the stale directory of run {run_id} from ssh host {}",
+                    self.host
+                );
+                let dir = single_quote(&path![OUTPUTS_ROOT, run_id].display().to_string());
+                self.run_internal_cmd(vec!["rm", "-rf", &dir]).await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
     async fn set_auth<'a>(&mut self, user: &'a str, auth: &SshAuthOptions<'a>) -> Result<()> {
         match auth {
             SshAuthOptions::Agent => {
@@ -354,11 +417,17 @@ impl Ssh {
         Ok(stdout)
     }
 
+    /// Runs a command on the remote host, whose process group is killed if it doesn't
+    /// finish within the timeout or once the provided future resolves, which is when the
+    /// caller has stopped waiting for it.
     pub async fn sh(
         &self,
         logger: Arc<Logger>,
         working_dir: &Option<String>,
+        env: &HashMap<String, String>,
         input: &str,
+        timeout: Option<Duration>,
+        cancelled: impl Future<Output = ()>,
     ) -> Result<HashMap<String, String>> {
         let mut command = String::new();
         if let Some(wd) = working_dir {
@@ -371,26 +440,67 @@ impl Ssh {
         let outputs_file = path![&self.outputs_dir, Uuid::new_v4().to_string()]
             .display()
             .to_string();
+        let pid_file = format!("{outputs_file}.pid");
 
-        for (k, v) in self.env.iter() {
+        for (k, v) in self.env.iter().chain(env.iter()) {
             channel.setenv(k, v).await?;
         }
         channel
             .setenv(BLD_OUTPUTS_ENV_VAR_V3, &outputs_file)
             .await?;
 
+        let script = process_group_script(&pid_file);
+        command = format!(
+            "bash -c {} bld {}",
+            single_quote(&script),
+            single_quote(&command)
+        );
+
         channel.exec(&command).await?;
 
-        let mut output = String::new();
+        let read_output = async {
+            let mut output = String::new();
 
-        let mut stdout = String::new();
-        FuturesUtilAsyncReadExt::read_to_string(&mut channel, &mut stdout).await?;
-        output.push_str(&stdout);
+            let mut stdout = String::new();
+            FuturesUtilAsyncReadExt::read_to_string(&mut channel, &mut stdout).await?;
+            output.push_str(&stdout);
 
-        let mut stderr = String::new();
-        let mut channel_stderr = channel.stderr();
-        FuturesUtilAsyncReadExt::read_to_string(&mut channel_stderr, &mut stderr).await?;
-        output.push_str(&stderr);
+            let mut stderr = String::new();
+            let mut channel_stderr = channel.stderr();
+            FuturesUtilAsyncReadExt::read_to_string(&mut channel_stderr, &mut stderr).await?;
+            output.push_str(&stderr);
+
+            Ok::<String, anyhow::Error>(output)
+        };
+
+        let timed_out = async {
+            match timeout {
+                Some(duration) => {
+                    sleep(duration).await;
+                    duration
+                }
+                None => pending().await,
+            }
+        };
+
+        let output = select! {
+            output = read_output => output,
+            duration = timed_out => Err(anyhow!(TimedOut::new(duration))),
+            _ = cancelled => Err(anyhow!("command was cancelled")),
+        };
+
+        let output = match output {
+            Ok(output) => output,
+            Err(e) => {
+                let kill = kill_process_group_script(&pid_file);
+                let _ = self
+                    .run_internal_cmd(vec![&kill])
+                    .await
+                    .inspect_err(|e| error!("unable to kill command, {e}"));
+                let _ = channel.close().await;
+                return Err(e);
+            }
+        };
 
         logger.write(output).await?;
 
@@ -414,6 +524,13 @@ impl Ssh {
         let _ = self
             .run_internal_cmd(vec!["rm", "-r", &self.outputs_dir.display().to_string()])
             .await;
+        // The directory of the run is only removed once its last platform on the host is
+        // disposed, since rmdir refuses to remove it while other ones are still in it.
+        if let Some(run_dir) = self.outputs_dir.parent() {
+            let _ = self
+                .run_internal_cmd(vec!["rmdir", &run_dir.display().to_string()])
+                .await;
+        }
         self.session.disconnect(None, "", None).await?;
         Ok(())
     }
@@ -88,7 +88,15 @@ pub struct FileScanner {
 
 impl FileScanner {
     pub fn new(config: &BldConfig, run_id: &str) -> Self {
-        let path = config.log_full_path(run_id);
+        Self::from_path(config.log_full_path(run_id))
+    }
+
+    /// Creates a scanner for the separate log of a single job of a run.
+    pub fn job(config: &BldConfig, run_id: &str, job: &str) -> Self {
+        Self::from_path(config.job_log_full_path(run_id, job))
+    }
+
+    fn from_path(path: PathBuf) -> Self {
         let (tx, rx) = channel(4096);
         FileScannerBackend::new(path, rx).receive();
         Self { tx }
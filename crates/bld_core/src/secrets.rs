@@ -0,0 +1,193 @@
+use anyhow::{Result, anyhow, bail};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use bld_config::BldConfig;
+use bld_models::secrets;
+use ring::{
+    aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey},
+    pbkdf2::{self, PBKDF2_HMAC_SHA256},
+    rand::{SecureRandom, SystemRandom},
+};
+use sea_orm::{ConnectionTrait, TransactionTrait};
+use std::{collections::HashMap, num::NonZeroU32};
+use tracing::debug;
+
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+const PBKDF2_ITERATIONS: NonZeroU32 = NonZeroU32::new(100_000).unwrap();
+
+/// Encrypts and decrypts secret values using AES-256-GCM with a key derived with
+/// PBKDF2 from the `secrets_key` entry of the server configuration and a random salt
+/// for every value. The encrypted value is the base64 encoding of the salt, the
+/// random nonce and the ciphertext.
+pub struct SecretsCipher {
+    passphrase: String,
+    rng: SystemRandom,
+}
+
+impl SecretsCipher {
+    pub fn new(config: &BldConfig) -> Result<Self> {
+        let Some(passphrase) = &config.local.server.secrets_key else {
+            bail!("no secrets_key has been configured for the server");
+        };
+        Ok(Self::from_passphrase(passphrase))
+    }
+
+    pub fn from_passphrase(passphrase: &str) -> Self {
+        Self {
+            passphrase: passphrase.to_owned(),
+            rng: SystemRandom::new(),
+        }
+    }
+
+    fn key(&self, salt: &[u8]) -> Result<LessSafeKey> {
+        let mut key = [0u8; KEY_LEN];
+        pbkdf2::derive(
+            PBKDF2_HMAC_SHA256,
+            PBKDF2_ITERATIONS,
+            salt,
+            self.passphrase.as_bytes(),
+            &mut key,
+        );
+        let key = UnboundKey::new(&AES_256_GCM, &key)
+            .map_err(|_| anyhow!("unable to create the secrets encryption key"))?;
+        Ok(LessSafeKey::new(key))
+    }
+
+    pub fn encrypt(&self, value: &str) -> Result<String> {
+        let mut salt = [0u8; SALT_LEN];
+        self.rng
+            .fill(&mut salt)
+            .map_err(|_| anyhow!("unable to generate salt for secret"))?;
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| anyhow!("unable to generate nonce for secret"))?;
+
+        let mut data = value.as_bytes().to_vec();
+        self.key(&salt)?
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+            .map_err(|_| anyhow!("unable to encrypt secret"))?;
+
+        let mut output = salt.to_vec();
+        output.extend_from_slice(&nonce);
+        output.append(&mut data);
+        Ok(STANDARD.encode(output))
+    }
+
+    pub fn decrypt(&self, value: &str) -> Result<String> {
+        let data = STANDARD.decode(value)?;
+        if data.len() < SALT_LEN + NONCE_LEN {
+            bail!("invalid encrypted secret");
+        }
+
+        let (salt, data) = data.split_at(SALT_LEN);
+        let (nonce, data) = data.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_| anyhow!("invalid encrypted secret"))?;
+        let mut data = data.to_vec();
+        let plain = self
+            .key(salt)?
+            .open_in_place(nonce, Aad::empty(), &mut data)
+            .map_err(|_| anyhow!("unable to decrypt secret"))?;
+
+        Ok(String::from_utf8(plain.to_vec())?)
+    }
+}
+
+/// Secret names are used as part of an expression object path so they are
+/// limited to the characters that are valid there.
+pub fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        bail!("secret name cannot be empty");
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        bail!("secret name can only contain alphanumeric characters, '_' and '-'");
+    }
+    Ok(())
+}
+
+/// Loads and decrypts all stored secrets. When the server has no secrets key
+/// configured an empty collection is returned since no secrets could have been stored.
+pub async fn load<C: ConnectionTrait + TransactionTrait>(
+    config: &BldConfig,
+    conn: &C,
+) -> Result<HashMap<String, String>> {
+    if config.local.server.secrets_key.is_none() {
+        debug!("no secrets_key configured, skipping loading of secrets");
+        return Ok(HashMap::new());
+    }
+
+    let cipher = SecretsCipher::new(config)?;
+    let mut values = HashMap::new();
+    for secret in secrets::select_all(conn).await? {
+        let value = cipher.decrypt(&secret.value)?;
+        values.insert(secret.name, value);
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SALT_LEN, SecretsCipher, validate_name};
+    use base64::{Engine, engine::general_purpose::STANDARD};
+
+    #[test]
+    fn validate_name_success() {
+        assert!(validate_name("GITHUB_TOKEN").is_ok());
+        assert!(validate_name("docker-password-2").is_ok());
+    }
+
+    #[test]
+    fn validate_name_failure() {
+        assert!(validate_name("").is_err());
+        assert!(validate_name("my.secret").is_err());
+        assert!(validate_name("with space").is_err());
+    }
+
+    #[test]
+    fn encrypt_then_decrypt_returns_original_value() {
+        let cipher = SecretsCipher::from_passphrase("passphrase");
+        let encrypted = cipher.encrypt("my secret value").unwrap();
+
+        assert_ne!(encrypted, "my secret value");
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "my secret value");
+    }
+
+    #[test]
+    fn encrypt_same_value_produces_different_output() {
+        let cipher = SecretsCipher::from_passphrase("passphrase");
+        let first = cipher.encrypt("value").unwrap();
+        let second = cipher.encrypt("value").unwrap();
+
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn decrypt_with_different_passphrase_fails() {
+        let cipher = SecretsCipher::from_passphrase("passphrase");
+        let other = SecretsCipher::from_passphrase("other");
+        let encrypted = cipher.encrypt("value").unwrap();
+
+        assert!(other.decrypt(&encrypted).is_err());
+    }
+
+    #[test]
+    fn decrypt_invalid_payload_fails() {
+        let cipher = SecretsCipher::from_passphrase("passphrase");
+
+        assert!(cipher.decrypt("not base64!").is_err());
+        assert!(cipher.decrypt("AAAA").is_err());
+    }
+
+    #[test]
+    fn encrypt_uses_a_different_salt_for_every_value() {
+        let cipher = SecretsCipher::from_passphrase("passphrase");
+        let first = STANDARD.decode(cipher.encrypt("value").unwrap()).unwrap();
+        let second = STANDARD.decode(cipher.encrypt("value").unwrap()).unwrap();
+
+        assert_ne!(first[..SALT_LEN], second[..SALT_LEN]);
+    }
+}
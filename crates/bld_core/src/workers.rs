@@ -1,8 +1,13 @@
-use std::{process::ExitStatus, time::Duration};
+use std::{
+    process::ExitStatus,
+    time::{Duration, Instant},
+};
 
 use anyhow::{Result, anyhow};
+use bld_models::dtos::{ConcurrencyGroup, ServerMessages};
 use tokio::{
     process::{Child, Command},
+    sync::mpsc::Sender,
     time::timeout,
 };
 
@@ -12,32 +17,156 @@ use nix::{
     unistd::Pid,
 };
 
+/// Where the worker process of a run is spawned. A local worker is a child process of
+/// the supervisor, while a remote one is spawned by the agent that the run is routed to.
+#[derive(Debug)]
+enum WorkerProcess {
+    Local {
+        cmd: Command,
+        child: Option<Child>,
+    },
+    Remote {
+        message: ServerMessages,
+        agent: Option<(String, Sender<ServerMessages>)>,
+    },
+}
+
 #[derive(Debug)]
 pub struct Worker {
     run_id: String,
-    cmd: Command,
-    child: Option<Child>,
+    concurrency: Option<ConcurrencyGroup>,
+    labels: Vec<String>,
+    pipeline: String,
+    user: String,
+    priority: i32,
+    passed_over: i64,
+    last_heartbeat: Instant,
+    process: WorkerProcess,
 }
 
 impl Worker {
-    pub fn new(run_id: String, cmd: Command) -> Self {
+    pub fn new(run_id: String, concurrency: Option<ConcurrencyGroup>, cmd: Command) -> Self {
+        Self {
+            run_id,
+            concurrency,
+            labels: vec![],
+            pipeline: String::new(),
+            user: String::new(),
+            priority: 0,
+            passed_over: 0,
+            last_heartbeat: Instant::now(),
+            process: WorkerProcess::Local { cmd, child: None },
+        }
+    }
+
+    /// Creates a worker that is spawned by an agent with every one of the labels, by
+    /// forwarding the enqueue message to it.
+    pub fn remote(
+        run_id: String,
+        concurrency: Option<ConcurrencyGroup>,
+        labels: Vec<String>,
+        message: ServerMessages,
+    ) -> Self {
         Self {
             run_id,
-            cmd,
-            child: None,
+            concurrency,
+            labels,
+            pipeline: String::new(),
+            user: String::new(),
+            priority: 0,
+            passed_over: 0,
+            last_heartbeat: Instant::now(),
+            process: WorkerProcess::Remote {
+                message,
+                agent: None,
+            },
         }
     }
 
+    /// Sets what the worker queue orders its backlog by, which is the priority of the run
+    /// along with the pipeline and the user that it belongs to.
+    pub fn queued_as(mut self, pipeline: &str, user: &str, priority: i32) -> Self {
+        self.pipeline = pipeline.to_owned();
+        self.user = user.to_owned();
+        self.priority = priority;
+        self
+    }
+
     pub fn get_run_id(&self) -> &str {
         &self.run_id
     }
 
+    pub fn get_concurrency(&self) -> Option<&ConcurrencyGroup> {
+        self.concurrency.as_ref()
+    }
+
+    pub fn get_labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    pub fn get_pipeline(&self) -> &str {
+        &self.pipeline
+    }
+
+    pub fn get_user(&self) -> &str {
+        &self.user
+    }
+
+    pub fn get_priority(&self) -> i32 {
+        self.priority
+    }
+
+    /// The priority of the worker raised by one for every other worker that has left the
+    /// backlog ahead of it, so that runs of a lower priority aren't starved by runs of a
+    /// higher one that keep being enqueued.
+    pub fn get_aged_priority(&self) -> i64 {
+        i64::from(self.priority) + self.passed_over
+    }
+
+    /// Records that another worker has left the backlog ahead of this one.
+    pub fn pass_over(&mut self) {
+        self.passed_over += 1;
+    }
+
+    /// The id of the agent that the worker has been spawned on.
+    pub fn get_agent(&self) -> Option<&str> {
+        match &self.process {
+            WorkerProcess::Remote {
+                agent: Some((id, _)),
+                ..
+            } => Some(id),
+            _ => None,
+        }
+    }
+
+    pub fn is_remote(&self) -> bool {
+        matches!(self.process, WorkerProcess::Remote { .. })
+    }
+
+    pub fn in_group(&self, group: &str) -> bool {
+        self.concurrency.as_ref().is_some_and(|c| c.name == group)
+    }
+
     pub fn has_run_id(&self, run_id: &str) -> bool {
         self.run_id == run_id
     }
 
     pub fn get_pid(&self) -> Option<u32> {
-        self.child.as_ref().map(|c| c.id()).unwrap_or(None)
+        match &self.process {
+            WorkerProcess::Local { child, .. } => child.as_ref().and_then(|c| c.id()),
+            WorkerProcess::Remote { .. } => None,
+        }
+    }
+
+    /// Records that the worker process is still alive.
+    pub fn heartbeat(&mut self) {
+        self.last_heartbeat = Instant::now();
+    }
+
+    /// Whether the worker has sent no heartbeat for longer than the timeout, counting
+    /// from when it was spawned if it never sent one.
+    pub fn is_hung(&self, timeout: Duration) -> bool {
+        self.last_heartbeat.elapsed() > timeout
     }
 
     pub fn has_pid(&self, pid: u32) -> bool {
@@ -45,42 +174,97 @@ impl Worker {
     }
 
     fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
-        self.child
-            .as_mut()
-            .ok_or_else(|| anyhow!("worker has not spawned"))
-            .and_then(|c| c.try_wait().map_err(|e| anyhow!(e)))
+        match &mut self.process {
+            WorkerProcess::Local { child, .. } => child
+                .as_mut()
+                .ok_or_else(|| anyhow!("worker has not spawned"))
+                .and_then(|c| c.try_wait().map_err(|e| anyhow!(e))),
+            WorkerProcess::Remote { .. } => Err(anyhow!("worker has been spawned by an agent")),
+        }
     }
 
     pub fn spawn(&mut self) -> Result<()> {
-        self.child = Some(self.cmd.spawn().map_err(|e| anyhow!(e))?);
+        let WorkerProcess::Local { cmd, child } = &mut self.process else {
+            return Err(anyhow!("worker has to be spawned by an agent"));
+        };
+        *child = Some(cmd.spawn().map_err(|e| anyhow!(e))?);
+        self.last_heartbeat = Instant::now();
         Ok(())
     }
 
+    /// Forwards the enqueue message of the worker to the agent so that it spawns it.
+    pub async fn spawn_on(&mut self, agent_id: &str, tx: Sender<ServerMessages>) -> Result<()> {
+        let WorkerProcess::Remote { message, agent } = &mut self.process else {
+            return Err(anyhow!("worker has to be spawned locally"));
+        };
+        tx.send(message.clone()).await.map_err(|e| anyhow!(e))?;
+        *agent = Some((agent_id.to_owned(), tx));
+        self.last_heartbeat = Instant::now();
+        Ok(())
+    }
+
+    /// Whether the local process of the worker has exited.
     pub fn completed(&mut self) -> bool {
-        self.try_wait().is_ok()
+        matches!(self.try_wait(), Ok(Some(_)))
     }
 
-    pub async fn cleanup(&mut self) -> Result<ExitStatus> {
-        let child = self
-            .child
+    pub async fn cleanup(&mut self) -> Result<()> {
+        let WorkerProcess::Local { child, .. } = &mut self.process else {
+            // the agent cleans up the processes that it has spawned.
+            return Ok(());
+        };
+        let child = child
             .as_mut()
             .ok_or_else(|| anyhow!("worker has not spawned"))?;
         match child.try_wait()? {
-            Some(status) => Ok(status),
+            Some(_) => Ok(()),
             // give the process some time to exit gracefully but don't block
             // the worker queue indefinitely on a process that never exits
             None => match timeout(Duration::from_secs(30), child.wait()).await {
-                Ok(status) => status.map_err(|e| anyhow!(e)),
+                Ok(status) => status.map(|_| ()).map_err(|e| anyhow!(e)),
                 Err(_) => {
                     child.kill().await?;
-                    child.wait().await.map_err(|e| anyhow!(e))
+                    child.wait().await.map(|_| ()).map_err(|e| anyhow!(e))
                 }
             },
         }
     }
 
-    #[cfg(target_family = "unix")]
     pub async fn stop(&mut self) -> Result<()> {
+        match &self.process {
+            WorkerProcess::Local { .. } => self.stop_local().await,
+            WorkerProcess::Remote {
+                agent: Some((_, tx)),
+                ..
+            } => {
+                let message = ServerMessages::Stop {
+                    run_id: self.run_id.to_owned(),
+                };
+                tx.send(message).await.map_err(|e| anyhow!(e))
+            }
+            WorkerProcess::Remote { agent: None, .. } => Ok(()),
+        }
+    }
+
+    /// Kills the worker process without letting it exit gracefully, which for a worker
+    /// spawned by an agent is up to the agent.
+    pub async fn kill(&mut self) -> Result<()> {
+        if self.is_remote() {
+            return self.stop().await;
+        }
+        let WorkerProcess::Local { child, .. } = &mut self.process else {
+            return Ok(());
+        };
+        child
+            .as_mut()
+            .ok_or_else(|| anyhow!("worker has not spawned"))?
+            .kill()
+            .await
+            .map_err(|e| anyhow!(e))
+    }
+
+    #[cfg(target_family = "unix")]
+    async fn stop_local(&mut self) -> Result<()> {
         let pid = self
             .get_pid()
             .ok_or_else(|| anyhow!("child instance doesnt have a pid"))?;
@@ -89,8 +273,11 @@ impl Worker {
     }
 
     #[cfg(target_family = "windows")]
-    pub async fn stop(&mut self) -> Result<()> {
-        self.child
+    async fn stop_local(&mut self) -> Result<()> {
+        let WorkerProcess::Local { child, .. } = &mut self.process else {
+            return Ok(());
+        };
+        child
             .as_mut()
             .ok_or_else(|| anyhow!("unable to get instance of worker process"))?
             .kill()
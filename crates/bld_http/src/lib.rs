@@ -10,15 +10,17 @@ use actix_codec::Framed;
 use anyhow::{Result, anyhow, bail};
 use awc::{
     BoxedSocket, Client, ClientRequest, Connector, SendClientRequest,
-    http::StatusCode,
+    error::WsClientError,
+    http::{StatusCode, header::LOCATION},
     ws::{Codec, Frame, Message},
 };
 use bld_config::BldConfig;
 use bld_models::dtos::{
-    AddJobRequest, ArtifactResponse, ArtifactsQueryParams, AuthTokens, CronJobResponse,
-    ExecClientMessage, HistQueryParams, HistoryEntry, JobFiltersParams, PipelineInfoQueryParams,
-    PipelinePathRequest, PipelineQueryParams, PullResponse, PushInfo, RefreshTokenParams,
-    UpdateJobRequest,
+    AccessGrantResponse, AddAccessGrantRequest, AddJobRequest, AddTriggerRequest, ApprovalRequest,
+    ArtifactResponse, ArtifactsQueryParams, AuthTokens, CronJobResponse, ExecClientMessage,
+    HistQueryParams, HistoryEntry, JobFiltersParams, PipelineInfoQueryParams, PipelinePathRequest,
+    PipelineQueryParams, PullResponse, PushInfo, RefreshTokenParams, SecretRequest, SecretResponse,
+    TriggerResponse, UpdateJobRequest, UpdateTriggerRequest,
 };
 use bld_utils::{
     fs::{read_tokens, write_tokens},
@@ -52,26 +54,38 @@ impl Display for RequestError {
 
 impl Error for RequestError {}
 
+/// The redirects that a request follows, which are sent by the members of a raft group
+/// to its leader.
+const MAX_REDIRECTS: usize = 5;
+
+type SendResponse = <SendClientRequest as Future>::Output;
+
 pub struct Request {
     request: ClientRequest,
 }
 
 impl Request {
+    fn client() -> Client {
+        Client::builder().disable_redirects().finish()
+    }
+
     pub fn get(url: &str) -> Self {
         Self {
-            request: Client::new().get(url).insert_header(("User-Agent", "bld")),
+            request: Self::client().get(url).insert_header(("User-Agent", "bld")),
         }
     }
 
     pub fn post(url: &str) -> Self {
         Self {
-            request: Client::new().post(url).insert_header(("User-Agent", "bld")),
+            request: Self::client()
+                .post(url)
+                .insert_header(("User-Agent", "bld")),
         }
     }
 
     pub fn patch(url: &str) -> Self {
         Self {
-            request: Client::new()
+            request: Self::client()
                 .patch(url)
                 .insert_header(("User-Agent", "bld")),
         }
@@ -79,7 +93,7 @@ impl Request {
 
     pub fn delete(url: &str) -> Self {
         Self {
-            request: Client::new()
+            request: Self::client()
                 .delete(url)
                 .insert_header(("User-Agent", "bld")),
         }
@@ -105,23 +119,23 @@ impl Request {
     }
 
     pub async fn text(self) -> Result<String> {
-        let send_request = self.request.send();
-        Self::request_with_text(send_request).await
+        let response = self.send(None).await;
+        Self::request_with_text(response).await
     }
 
     pub async fn text_with_data<T: Serialize>(self, data: &T) -> Result<String> {
-        let send_request = self.request.send_json(data);
-        Self::request_with_text(send_request).await
+        let response = self.send(Some(serde_json::to_vec(data)?)).await;
+        Self::request_with_text(response).await
     }
 
     pub async fn bytes(self) -> Result<Vec<u8>> {
-        let send_request = self.request.send();
-        Self::request_with_bytes(send_request).await
+        let response = self.send(None).await;
+        Self::request_with_bytes(response).await
     }
 
     pub async fn json<T: DeserializeOwned>(self) -> Result<T> {
-        let send_request = self.request.send();
-        Self::request_with_json::<T>(send_request).await
+        let response = self.send(None).await;
+        Self::request_with_json::<T>(response).await
     }
 
     pub async fn json_with_data<T, V>(self, data: &T) -> Result<V>
@@ -129,12 +143,49 @@ impl Request {
         T: 'static + Serialize,
         V: DeserializeOwned,
     {
-        let send_request = self.request.send_json(&data);
-        Self::request_with_json::<V>(send_request).await
+        let response = self.send(Some(serde_json::to_vec(data)?)).await;
+        Self::request_with_json::<V>(response).await
     }
 
-    async fn request_with_text(send_request: SendClientRequest) -> Result<String> {
-        let mut response = send_request.await.map_err(|e| anyhow!(e.to_string()))?;
+    /// Sends the request and follows the temporary and permanent redirects of the server
+    /// with the same method, headers and body. The redirects aren't followed by the client
+    /// since it drops the authorization header when the host changes.
+    async fn send(self, body: Option<Vec<u8>>) -> SendResponse {
+        let mut request = match body {
+            Some(_) => self.request.content_type("application/json"),
+            None => self.request,
+        };
+        let mut redirects = 0;
+        loop {
+            let method = request.get_method().clone();
+            let headers = request.headers().clone();
+            let response = match &body {
+                Some(body) => request.send_body(body.clone()).await?,
+                None => request.send().await?,
+            };
+
+            let status = response.status();
+            let redirect = status == StatusCode::TEMPORARY_REDIRECT
+                || status == StatusCode::PERMANENT_REDIRECT;
+            let location = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_owned());
+            let location = match location {
+                Some(location) if redirect && redirects < MAX_REDIRECTS => location,
+                _ => return Ok(response),
+            };
+
+            debug!("following redirect to {location}");
+            request = Self::client().request(method, location);
+            *request.headers_mut() = headers;
+            redirects += 1;
+        }
+    }
+
+    async fn request_with_text(response: SendResponse) -> Result<String> {
+        let mut response = response.map_err(|e| anyhow!(e.to_string()))?;
         let status = response.status();
 
         match status {
@@ -165,8 +216,8 @@ impl Request {
         }
     }
 
-    async fn request_with_bytes(send_request: SendClientRequest) -> Result<Vec<u8>> {
-        let mut response = send_request.await.map_err(|e| anyhow!(e.to_string()))?;
+    async fn request_with_bytes(response: SendResponse) -> Result<Vec<u8>> {
+        let mut response = response.map_err(|e| anyhow!(e.to_string()))?;
         let status = response.status();
 
         match status {
@@ -197,8 +248,8 @@ impl Request {
         }
     }
 
-    async fn request_with_json<T: DeserializeOwned>(send_request: SendClientRequest) -> Result<T> {
-        let mut response = send_request.await.map_err(|e| anyhow!(e.to_string()))?;
+    async fn request_with_json<T: DeserializeOwned>(response: SendResponse) -> Result<T> {
+        let mut response = response.map_err(|e| anyhow!(e.to_string()))?;
         let status = response.status();
 
         match status {
@@ -240,7 +291,33 @@ impl WebSock {
             .with_no_client_auth();
 
         let connector = Connector::new().rustls_0_23(Arc::new(rustls_config));
-        let mut request = Client::builder().connector(connector).finish().ws(url);
+        let client = Client::builder()
+            .connector(connector)
+            .disable_redirects()
+            .finish();
+
+        let connection = match Self::handshake(&client, url, auth_path).await {
+            Err(WsClientError::InvalidResponseStatus(status)) if status.is_redirection() => {
+                let url = Self::leader_url(&client, url).await?;
+                debug!("following redirect to {url}");
+                Self::handshake(&client, &url, auth_path).await
+            }
+            connection => connection,
+        }
+        .map_err(|e| anyhow!("{e}"))?;
+
+        Ok(Self {
+            connection,
+            pong_pending: false,
+        })
+    }
+
+    async fn handshake(
+        client: &Client,
+        url: &str,
+        auth_path: Option<&Path>,
+    ) -> Result<Framed<BoxedSocket, Codec>, WsClientError> {
+        let mut request = client.ws(url);
 
         if let Some(path) = auth_path
             && let Ok(tokens) = read_tokens::<AuthTokens>(path).await
@@ -248,12 +325,25 @@ impl WebSock {
             request = request.header("Authorization", format!("Bearer {}", tokens.access_token));
         }
 
-        let (_, connection) = request.connect().await.map_err(|e| anyhow!("{e}"))?;
+        let (_, connection) = request.connect().await?;
+        Ok(connection)
+    }
 
-        Ok(Self {
-            connection,
-            pong_pending: false,
-        })
+    /// The handshake error doesn't keep the location of a redirect, so it's requested again
+    /// as a plain http request in order to find the leader of the raft group.
+    async fn leader_url(client: &Client, url: &str) -> Result<String> {
+        let http_url = url.replacen("ws", "http", 1);
+        let response = client
+            .get(&http_url)
+            .send()
+            .await
+            .map_err(|e| anyhow!(e.to_string()))?;
+        response
+            .headers()
+            .get(LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.replacen("http", "ws", 1))
+            .ok_or_else(|| anyhow!("redirect without a location from {http_url}"))
     }
 
     pub async fn text<T: Serialize>(&mut self, value: &T) -> Result<()> {
@@ -388,8 +478,14 @@ impl HttpClient {
         state: Option<String>,
         name: Option<String>,
         limit: u64,
+        details: bool,
     ) -> Result<Vec<HistoryEntry>> {
-        let params = HistQueryParams { state, name, limit };
+        let params = HistQueryParams {
+            state,
+            name,
+            limit,
+            details,
+        };
         let response = self.hist_inner(&params).await;
 
         if Self::unauthorized(&response) {
@@ -526,11 +622,28 @@ impl HttpClient {
         pipeline: &str,
         env: Option<HashMap<String, String>>,
         vars: Option<HashMap<String, String>>,
+        priority: Option<i32>,
     ) -> Result<()> {
         let json = ExecClientMessage::EnqueueRun {
             name: pipeline.to_owned(),
             env,
             inputs: vars,
+            parent_run_id: None,
+            priority,
+        };
+        let response = self.run_inner(&json).await;
+
+        if Self::unauthorized(&response) {
+            self.refresh().await?;
+            self.run_inner(&json).await
+        } else {
+            response
+        }
+    }
+
+    pub async fn rerun(&self, run_id: &str) -> Result<()> {
+        let json = ExecClientMessage::Rerun {
+            run_id: run_id.to_owned(),
         };
         let response = self.run_inner(&json).await;
 
@@ -564,6 +677,28 @@ impl HttpClient {
         }
     }
 
+    async fn approve_inner(&self, body: &ApprovalRequest) -> Result<()> {
+        let url = format!("{}/v1/approve", self.base_url);
+        Request::post(&url)
+            .auth(&self.auth_path)
+            .await
+            .json_with_data(body)
+            .await
+            .map(|_: String| ())
+    }
+
+    pub async fn approve(&self, run_id: &str, job: Option<&str>, approved: bool) -> Result<()> {
+        let body = ApprovalRequest::new(run_id.to_owned(), job.map(str::to_owned), approved);
+        let response = self.approve_inner(&body).await;
+
+        if Self::unauthorized(&response) {
+            self.refresh().await?;
+            self.approve_inner(&body).await
+        } else {
+            response
+        }
+    }
+
     async fn cron_list_inner(&self, filters: &JobFiltersParams) -> Result<Vec<CronJobResponse>> {
         let url = format!("{}/v1/cron", self.base_url);
         Request::get(&url)
@@ -709,6 +844,201 @@ impl HttpClient {
         }
     }
 
+    async fn secrets_list_inner(&self) -> Result<Vec<SecretResponse>> {
+        let url = format!("{}/v1/secrets", self.base_url);
+        Request::get(&url).auth(&self.auth_path).await.json().await
+    }
+
+    pub async fn secrets_list(&self) -> Result<Vec<SecretResponse>> {
+        let response = self.secrets_list_inner().await;
+
+        if Self::unauthorized(&response) {
+            self.refresh().await?;
+            self.secrets_list_inner().await
+        } else {
+            response
+        }
+    }
+
+    async fn secrets_set_inner(&self, body: &SecretRequest) -> Result<()> {
+        let url = format!("{}/v1/secrets", self.base_url);
+        Request::post(&url)
+            .auth(&self.auth_path)
+            .await
+            .json_with_data(body)
+            .await
+            .map(|_: String| ())
+    }
+
+    pub async fn secrets_set(&self, body: &SecretRequest) -> Result<()> {
+        let response = self.secrets_set_inner(body).await;
+
+        if Self::unauthorized(&response) {
+            self.refresh().await?;
+            self.secrets_set_inner(body).await
+        } else {
+            response
+        }
+    }
+
+    async fn secrets_remove_inner(&self, name: &str) -> Result<()> {
+        let url = format!("{}/v1/secrets/{name}", self.base_url);
+        Request::delete(&url)
+            .auth(&self.auth_path)
+            .await
+            .json()
+            .await
+            .map(|_: String| ())
+    }
+
+    pub async fn secrets_remove(&self, name: &str) -> Result<()> {
+        let response = self.secrets_remove_inner(name).await;
+
+        if Self::unauthorized(&response) {
+            self.refresh().await?;
+            self.secrets_remove_inner(name).await
+        } else {
+            response
+        }
+    }
+
+    async fn access_list_inner(&self) -> Result<Vec<AccessGrantResponse>> {
+        let url = format!("{}/v1/access", self.base_url);
+        Request::get(&url).auth(&self.auth_path).await.json().await
+    }
+
+    pub async fn access_list(&self) -> Result<Vec<AccessGrantResponse>> {
+        let response = self.access_list_inner().await;
+
+        if Self::unauthorized(&response) {
+            self.refresh().await?;
+            self.access_list_inner().await
+        } else {
+            response
+        }
+    }
+
+    async fn access_add_inner(&self, body: &AddAccessGrantRequest) -> Result<()> {
+        let url = format!("{}/v1/access", self.base_url);
+        Request::post(&url)
+            .auth(&self.auth_path)
+            .await
+            .json_with_data(body)
+            .await
+            .map(|_: String| ())
+    }
+
+    pub async fn access_add(&self, body: &AddAccessGrantRequest) -> Result<()> {
+        let response = self.access_add_inner(body).await;
+
+        if Self::unauthorized(&response) {
+            self.refresh().await?;
+            self.access_add_inner(body).await
+        } else {
+            response
+        }
+    }
+
+    async fn access_remove_inner(&self, id: &str) -> Result<()> {
+        let url = format!("{}/v1/access/{id}", self.base_url);
+        Request::delete(&url)
+            .auth(&self.auth_path)
+            .await
+            .json()
+            .await
+            .map(|_: String| ())
+    }
+
+    pub async fn access_remove(&self, id: &str) -> Result<()> {
+        let response = self.access_remove_inner(id).await;
+
+        if Self::unauthorized(&response) {
+            self.refresh().await?;
+            self.access_remove_inner(id).await
+        } else {
+            response
+        }
+    }
+
+    async fn triggers_list_inner(&self) -> Result<Vec<TriggerResponse>> {
+        let url = format!("{}/v1/triggers", self.base_url);
+        Request::get(&url).auth(&self.auth_path).await.json().await
+    }
+
+    pub async fn triggers_list(&self) -> Result<Vec<TriggerResponse>> {
+        let response = self.triggers_list_inner().await;
+
+        if Self::unauthorized(&response) {
+            self.refresh().await?;
+            self.triggers_list_inner().await
+        } else {
+            response
+        }
+    }
+
+    async fn triggers_add_inner(&self, body: &AddTriggerRequest) -> Result<()> {
+        let url = format!("{}/v1/triggers", self.base_url);
+        Request::post(&url)
+            .auth(&self.auth_path)
+            .await
+            .json_with_data(body)
+            .await
+            .map(|_: String| ())
+    }
+
+    pub async fn triggers_add(&self, body: &AddTriggerRequest) -> Result<()> {
+        let response = self.triggers_add_inner(body).await;
+
+        if Self::unauthorized(&response) {
+            self.refresh().await?;
+            self.triggers_add_inner(body).await
+        } else {
+            response
+        }
+    }
+
+    async fn triggers_update_inner(&self, body: &UpdateTriggerRequest) -> Result<()> {
+        let url = format!("{}/v1/triggers", self.base_url);
+        Request::patch(&url)
+            .auth(&self.auth_path)
+            .await
+            .json_with_data(body)
+            .await
+            .map(|_: String| ())
+    }
+
+    pub async fn triggers_update(&self, body: &UpdateTriggerRequest) -> Result<()> {
+        let response = self.triggers_update_inner(body).await;
+
+        if Self::unauthorized(&response) {
+            self.refresh().await?;
+            self.triggers_update_inner(body).await
+        } else {
+            response
+        }
+    }
+
+    async fn triggers_remove_inner(&self, name: &str) -> Result<()> {
+        let url = format!("{}/v1/triggers/{name}", self.base_url);
+        Request::delete(&url)
+            .auth(&self.auth_path)
+            .await
+            .json()
+            .await
+            .map(|_: String| ())
+    }
+
+    pub async fn triggers_remove(&self, name: &str) -> Result<()> {
+        let response = self.triggers_remove_inner(name).await;
+
+        if Self::unauthorized(&response) {
+            self.refresh().await?;
+            self.triggers_remove_inner(name).await
+        } else {
+            response
+        }
+    }
+
     async fn copy_inner(&self, data: &PipelinePathRequest) -> Result<()> {
         let url = format!("{}/v1/copy", self.base_url);
         Request::post(&url)
@@ -16,6 +16,25 @@ mod m20230907_190403_create_cron_job_variables_table;
 mod m20230907_190709_create_cron_job_environment_variables_table;
 mod m20240630_162930_login_attempts;
 mod m20260705_163911_add_artifacts;
+mod m20261018_093215_create_secrets_table;
+mod m20261018_141207_add_pipeline_runs_waiting_on_group;
+mod m20261018_163524_create_pipeline_run_approvals_table;
+mod m20261018_180412_create_pipeline_run_checkouts_table;
+mod m20261018_190128_create_webhook_triggers_table;
+mod m20261018_190245_create_webhook_trigger_inputs_table;
+mod m20261018_190402_add_pipeline_runs_trigger_id;
+mod m20261018_203114_create_pipeline_run_outputs_table;
+mod m20261018_203327_create_completion_triggers_table;
+mod m20261018_203541_create_completion_trigger_events_table;
+mod m20261018_212041_add_pipeline_runs_trigger_type;
+mod m20261018_212203_create_pipeline_run_inputs_table;
+mod m20261018_212318_create_pipeline_run_environment_variables_table;
+mod m20261018_220105_create_pipeline_run_jobs_table;
+mod m20261018_220247_create_pipeline_run_steps_table;
+mod m20261018_230112_create_pipeline_run_queue_table;
+mod m20261018_230340_add_cron_jobs_priority;
+mod m20261018_233015_add_pipeline_runs_fault_reason;
+mod m20261019_010215_create_access_grants_table;
 
 pub struct Migrator;
 
@@ -39,6 +58,25 @@ impl MigratorTrait for Migrator {
             Box::new(m20230907_190709_create_cron_job_environment_variables_table::Migration),
             Box::new(m20240630_162930_login_attempts::Migration),
             Box::new(m20260705_163911_add_artifacts::Migration),
+            Box::new(m20261018_093215_create_secrets_table::Migration),
+            Box::new(m20261018_141207_add_pipeline_runs_waiting_on_group::Migration),
+            Box::new(m20261018_163524_create_pipeline_run_approvals_table::Migration),
+            Box::new(m20261018_180412_create_pipeline_run_checkouts_table::Migration),
+            Box::new(m20261018_190128_create_webhook_triggers_table::Migration),
+            Box::new(m20261018_190245_create_webhook_trigger_inputs_table::Migration),
+            Box::new(m20261018_190402_add_pipeline_runs_trigger_id::Migration),
+            Box::new(m20261018_203114_create_pipeline_run_outputs_table::Migration),
+            Box::new(m20261018_203327_create_completion_triggers_table::Migration),
+            Box::new(m20261018_203541_create_completion_trigger_events_table::Migration),
+            Box::new(m20261018_212041_add_pipeline_runs_trigger_type::Migration),
+            Box::new(m20261018_212203_create_pipeline_run_inputs_table::Migration),
+            Box::new(m20261018_212318_create_pipeline_run_environment_variables_table::Migration),
+            Box::new(m20261018_220105_create_pipeline_run_jobs_table::Migration),
+            Box::new(m20261018_220247_create_pipeline_run_steps_table::Migration),
+            Box::new(m20261018_230112_create_pipeline_run_queue_table::Migration),
+            Box::new(m20261018_230340_add_cron_jobs_priority::Migration),
+            Box::new(m20261018_233015_add_pipeline_runs_fault_reason::Migration),
+            Box::new(m20261019_010215_create_access_grants_table::Migration),
         ]
     }
 }
@@ -0,0 +1,81 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230907_182138_create_pipeline_runs_table::PipelineRuns;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PipelineRunApprovals::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PipelineRunApprovals::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(PipelineRunApprovals::RunId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PipelineRunApprovals::Job)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PipelineRunApprovals::Message).text())
+                    .col(
+                        ColumnDef::new(PipelineRunApprovals::Approvers)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PipelineRunApprovals::State)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PipelineRunApprovals::DecidedBy).string())
+                    .col(
+                        ColumnDef::new(PipelineRunApprovals::DateCreated)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PipelineRunApprovals::DateUpdated).date_time())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(PipelineRunApprovals::Table)
+                            .from_col(PipelineRunApprovals::RunId)
+                            .to_tbl(PipelineRuns::Table)
+                            .to_col(PipelineRuns::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PipelineRunApprovals::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PipelineRunApprovals {
+    Table,
+    Id,
+    RunId,
+    Job,
+    Message,
+    Approvers,
+    State,
+    DecidedBy,
+    DateCreated,
+    DateUpdated,
+}
@@ -0,0 +1,79 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230907_182138_create_pipeline_runs_table::PipelineRuns;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PipelineRunCheckouts::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PipelineRunCheckouts::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(PipelineRunCheckouts::RunId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PipelineRunCheckouts::Job)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PipelineRunCheckouts::Repository)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PipelineRunCheckouts::Reference).string())
+                    .col(
+                        ColumnDef::new(PipelineRunCheckouts::Sha)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PipelineRunCheckouts::Path).text().not_null())
+                    .col(
+                        ColumnDef::new(PipelineRunCheckouts::DateCreated)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(PipelineRunCheckouts::Table)
+                            .from_col(PipelineRunCheckouts::RunId)
+                            .to_tbl(PipelineRuns::Table)
+                            .to_col(PipelineRuns::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PipelineRunCheckouts::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PipelineRunCheckouts {
+    Table,
+    Id,
+    RunId,
+    Job,
+    Repository,
+    Reference,
+    Sha,
+    Path,
+    DateCreated,
+}
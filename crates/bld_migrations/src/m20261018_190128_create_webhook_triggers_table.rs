@@ -0,0 +1,72 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230907_181924_create_pipeline_table::Pipeline;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebhookTriggers::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WebhookTriggers::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookTriggers::Name)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookTriggers::PipelineId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(WebhookTriggers::Secret).text().not_null())
+                    .col(ColumnDef::new(WebhookTriggers::Branches).text())
+                    .col(ColumnDef::new(WebhookTriggers::Paths).text())
+                    .col(
+                        ColumnDef::new(WebhookTriggers::DateCreated)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(WebhookTriggers::DateUpdated).date_time())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(WebhookTriggers::Table)
+                            .from_col(WebhookTriggers::PipelineId)
+                            .to_tbl(Pipeline::Table)
+                            .to_col(Pipeline::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WebhookTriggers::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum WebhookTriggers {
+    Table,
+    Id,
+    Name,
+    PipelineId,
+    Secret,
+    Branches,
+    Paths,
+    DateCreated,
+    DateUpdated,
+}
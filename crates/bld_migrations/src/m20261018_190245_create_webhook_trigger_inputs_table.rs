@@ -0,0 +1,69 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20261018_190128_create_webhook_triggers_table::WebhookTriggers;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebhookTriggerInputs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WebhookTriggerInputs::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookTriggerInputs::TriggerId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookTriggerInputs::Name)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookTriggerInputs::Path)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookTriggerInputs::DateCreated)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(WebhookTriggerInputs::Table)
+                            .from_col(WebhookTriggerInputs::TriggerId)
+                            .to_tbl(WebhookTriggers::Table)
+                            .to_col(WebhookTriggers::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WebhookTriggerInputs::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum WebhookTriggerInputs {
+    Table,
+    Id,
+    TriggerId,
+    Name,
+    Path,
+    DateCreated,
+}
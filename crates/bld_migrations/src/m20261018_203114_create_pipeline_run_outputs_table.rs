@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230907_182138_create_pipeline_runs_table::PipelineRuns;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PipelineRunOutputs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PipelineRunOutputs::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(PipelineRunOutputs::RunId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PipelineRunOutputs::Job).string().not_null())
+                    .col(ColumnDef::new(PipelineRunOutputs::Name).string().not_null())
+                    .col(ColumnDef::new(PipelineRunOutputs::Value).text().not_null())
+                    .col(
+                        ColumnDef::new(PipelineRunOutputs::DateCreated)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(PipelineRunOutputs::Table)
+                            .from_col(PipelineRunOutputs::RunId)
+                            .to_tbl(PipelineRuns::Table)
+                            .to_col(PipelineRuns::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PipelineRunOutputs::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PipelineRunOutputs {
+    Table,
+    Id,
+    RunId,
+    Job,
+    Name,
+    Value,
+    DateCreated,
+}
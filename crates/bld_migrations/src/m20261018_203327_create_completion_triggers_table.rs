@@ -0,0 +1,72 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230907_181924_create_pipeline_table::Pipeline;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CompletionTriggers::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CompletionTriggers::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(CompletionTriggers::PipelineId)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(CompletionTriggers::Upstream)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CompletionTriggers::States)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CompletionTriggers::DateCreated)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(CompletionTriggers::DateUpdated).date_time())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(CompletionTriggers::Table)
+                            .from_col(CompletionTriggers::PipelineId)
+                            .to_tbl(Pipeline::Table)
+                            .to_col(Pipeline::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CompletionTriggers::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum CompletionTriggers {
+    Table,
+    Id,
+    PipelineId,
+    Upstream,
+    States,
+    DateCreated,
+    DateUpdated,
+}
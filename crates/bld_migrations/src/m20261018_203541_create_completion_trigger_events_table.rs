@@ -0,0 +1,83 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{
+    m20230907_182138_create_pipeline_runs_table::PipelineRuns,
+    m20261018_203327_create_completion_triggers_table::CompletionTriggers,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CompletionTriggerEvents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CompletionTriggerEvents::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(CompletionTriggerEvents::TriggerId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CompletionTriggerEvents::RunId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CompletionTriggerEvents::State)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CompletionTriggerEvents::DateCreated)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(CompletionTriggerEvents::Table)
+                            .from_col(CompletionTriggerEvents::TriggerId)
+                            .to_tbl(CompletionTriggers::Table)
+                            .to_col(CompletionTriggers::Id),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(CompletionTriggerEvents::Table)
+                            .from_col(CompletionTriggerEvents::RunId)
+                            .to_tbl(PipelineRuns::Table)
+                            .to_col(PipelineRuns::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(CompletionTriggerEvents::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum CompletionTriggerEvents {
+    Table,
+    Id,
+    TriggerId,
+    RunId,
+    State,
+    DateCreated,
+}
@@ -0,0 +1,57 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230907_182138_create_pipeline_runs_table::PipelineRuns;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PipelineRunInputs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PipelineRunInputs::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PipelineRunInputs::RunId).string().not_null())
+                    .col(ColumnDef::new(PipelineRunInputs::Name).string().not_null())
+                    .col(ColumnDef::new(PipelineRunInputs::Value).text().not_null())
+                    .col(
+                        ColumnDef::new(PipelineRunInputs::DateCreated)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(PipelineRunInputs::Table)
+                            .from_col(PipelineRunInputs::RunId)
+                            .to_tbl(PipelineRuns::Table)
+                            .to_col(PipelineRuns::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PipelineRunInputs::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PipelineRunInputs {
+    Table,
+    Id,
+    RunId,
+    Name,
+    Value,
+    DateCreated,
+}
@@ -0,0 +1,77 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230907_182138_create_pipeline_runs_table::PipelineRuns;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PipelineRunJobs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PipelineRunJobs::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PipelineRunJobs::RunId).string().not_null())
+                    .col(ColumnDef::new(PipelineRunJobs::Name).string().not_null())
+                    .col(ColumnDef::new(PipelineRunJobs::Matrix).string().null())
+                    .col(ColumnDef::new(PipelineRunJobs::Platform).string().null())
+                    .col(ColumnDef::new(PipelineRunJobs::State).string().not_null())
+                    .col(ColumnDef::new(PipelineRunJobs::Error).text().null())
+                    .col(
+                        ColumnDef::new(PipelineRunJobs::StartDate)
+                            .date_time()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(PipelineRunJobs::EndDate).date_time().null())
+                    .col(
+                        ColumnDef::new(PipelineRunJobs::DateCreated)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PipelineRunJobs::DateUpdated)
+                            .date_time()
+                            .null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(PipelineRunJobs::Table)
+                            .from_col(PipelineRunJobs::RunId)
+                            .to_tbl(PipelineRuns::Table)
+                            .to_col(PipelineRuns::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PipelineRunJobs::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum PipelineRunJobs {
+    Table,
+    Id,
+    RunId,
+    Name,
+    Matrix,
+    Platform,
+    State,
+    Error,
+    StartDate,
+    EndDate,
+    DateCreated,
+    DateUpdated,
+}
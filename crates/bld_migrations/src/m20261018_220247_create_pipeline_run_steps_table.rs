@@ -0,0 +1,77 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20261018_220105_create_pipeline_run_jobs_table::PipelineRunJobs;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PipelineRunSteps::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PipelineRunSteps::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PipelineRunSteps::JobId).string().not_null())
+                    .col(ColumnDef::new(PipelineRunSteps::StepId).string().not_null())
+                    .col(ColumnDef::new(PipelineRunSteps::Name).string().null())
+                    .col(ColumnDef::new(PipelineRunSteps::Matrix).string().null())
+                    .col(ColumnDef::new(PipelineRunSteps::State).string().not_null())
+                    .col(ColumnDef::new(PipelineRunSteps::Error).text().null())
+                    .col(
+                        ColumnDef::new(PipelineRunSteps::StartDate)
+                            .date_time()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(PipelineRunSteps::EndDate).date_time().null())
+                    .col(
+                        ColumnDef::new(PipelineRunSteps::DateCreated)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PipelineRunSteps::DateUpdated)
+                            .date_time()
+                            .null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(PipelineRunSteps::Table)
+                            .from_col(PipelineRunSteps::JobId)
+                            .to_tbl(PipelineRunJobs::Table)
+                            .to_col(PipelineRunJobs::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PipelineRunSteps::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PipelineRunSteps {
+    Table,
+    Id,
+    JobId,
+    StepId,
+    Name,
+    Matrix,
+    State,
+    Error,
+    StartDate,
+    EndDate,
+    DateCreated,
+    DateUpdated,
+}
@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230907_182138_create_pipeline_runs_table::PipelineRuns;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PipelineRunQueue::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PipelineRunQueue::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PipelineRunQueue::RunId).string().not_null())
+                    .col(
+                        ColumnDef::new(PipelineRunQueue::Priority)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PipelineRunQueue::Message).text().not_null())
+                    .col(
+                        ColumnDef::new(PipelineRunQueue::DateCreated)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(PipelineRunQueue::Table)
+                            .from_col(PipelineRunQueue::RunId)
+                            .to_tbl(PipelineRuns::Table)
+                            .to_col(PipelineRuns::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PipelineRunQueue::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum PipelineRunQueue {
+    Table,
+    Id,
+    RunId,
+    Priority,
+    Message,
+    DateCreated,
+}
@@ -0,0 +1,54 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AccessGrants::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AccessGrants::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AccessGrants::Role).string().not_null())
+                    .col(
+                        ColumnDef::new(AccessGrants::SubjectType)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(AccessGrants::Subject).string().not_null())
+                    .col(ColumnDef::new(AccessGrants::Pipeline).string().not_null())
+                    .col(
+                        ColumnDef::new(AccessGrants::DateCreated)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AccessGrants::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AccessGrants {
+    Table,
+    Id,
+    Role,
+    SubjectType,
+    Subject,
+    Pipeline,
+    DateCreated,
+}
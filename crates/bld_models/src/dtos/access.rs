@@ -0,0 +1,134 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// The roles that can be granted to the users of a server. Each role is allowed to do
+/// everything that the roles before it are allowed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessRole {
+    /// Reads pipelines, runs, logs and artifacts.
+    Viewer,
+    /// Starts, stops and approves runs.
+    Runner,
+    /// Edits, moves and removes pipelines along with their cron jobs and triggers.
+    Maintainer,
+    /// Manages the secrets and the access grants of the server.
+    Admin,
+}
+
+impl AccessRole {
+    pub fn all() -> [Self; 4] {
+        [Self::Viewer, Self::Runner, Self::Maintainer, Self::Admin]
+    }
+}
+
+impl Display for AccessRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Viewer => write!(f, "viewer"),
+            Self::Runner => write!(f, "runner"),
+            Self::Maintainer => write!(f, "maintainer"),
+            Self::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+impl FromStr for AccessRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viewer" => Ok(Self::Viewer),
+            "runner" => Ok(Self::Runner),
+            "maintainer" => Ok(Self::Maintainer),
+            "admin" => Ok(Self::Admin),
+            _ => Err(format!("unknown access role {s}")),
+        }
+    }
+}
+
+/// Whether a grant is given to a single user or to every member of a group claim.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessSubject {
+    User,
+    Group,
+}
+
+impl Display for AccessSubject {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::User => write!(f, "user"),
+            Self::Group => write!(f, "group"),
+        }
+    }
+}
+
+impl FromStr for AccessSubject {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "user" => Ok(Self::User),
+            "group" => Ok(Self::Group),
+            _ => Err(format!("unknown access subject {s}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddAccessGrantRequest {
+    pub role: AccessRole,
+    pub subject_type: AccessSubject,
+    pub subject: String,
+    /// The prefix of the pipeline paths that the grant applies to, matched by whole path
+    /// segments, empty for all of them.
+    #[serde(default)]
+    pub pipeline: String,
+}
+
+impl AddAccessGrantRequest {
+    pub fn new(
+        role: AccessRole,
+        subject_type: AccessSubject,
+        subject: String,
+        pipeline: String,
+    ) -> Self {
+        Self {
+            role,
+            subject_type,
+            subject,
+            pipeline,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessGrantResponse {
+    pub id: String,
+    pub role: AccessRole,
+    pub subject_type: AccessSubject,
+    pub subject: String,
+    pub pipeline: String,
+    pub date_created: String,
+}
+
+#[cfg(feature = "database")]
+impl TryFrom<crate::access_grants::AccessGrant> for AccessGrantResponse {
+    type Error = anyhow::Error;
+
+    fn try_from(value: crate::access_grants::AccessGrant) -> Result<Self, Self::Error> {
+        Ok(Self {
+            role: value.role.parse().map_err(anyhow::Error::msg)?,
+            subject_type: value.subject_type.parse().map_err(anyhow::Error::msg)?,
+            id: value.id,
+            subject: value.subject,
+            pipeline: value.pipeline,
+            date_created: value.date_created.format("%F %X").to_string(),
+        })
+    }
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// The decision on the pending approval of a run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalRequest {
+    pub run_id: String,
+    /// The job whose approval is decided on, which can be omitted while the run waits on
+    /// the approval of a single job.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job: Option<String>,
+    pub approved: bool,
+}
+
+impl ApprovalRequest {
+    pub fn new(run_id: String, job: Option<String>, approved: bool) -> Self {
+        Self {
+            run_id,
+            job,
+            approved,
+        }
+    }
+}
@@ -20,7 +20,7 @@ impl PipelineQueryParams {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PipelinePathRequest {
     pub pipeline: String,
     pub target: String,
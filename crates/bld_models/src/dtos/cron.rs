@@ -2,13 +2,15 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AddJobRequest {
     pub schedule: String,
     pub pipeline: String,
     pub inputs: Option<HashMap<String, String>>,
     pub env: Option<HashMap<String, String>>,
     pub is_default: bool,
+    #[serde(default)]
+    pub priority: i32,
 }
 
 impl AddJobRequest {
@@ -18,6 +20,7 @@ impl AddJobRequest {
         inputs: Option<HashMap<String, String>>,
         env: Option<HashMap<String, String>>,
         is_default: bool,
+        priority: i32,
     ) -> Self {
         Self {
             schedule,
@@ -25,16 +28,20 @@ impl AddJobRequest {
             inputs,
             env,
             is_default,
+            priority,
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateJobRequest {
     pub id: String,
     pub schedule: String,
     pub inputs: Option<HashMap<String, String>>,
     pub env: Option<HashMap<String, String>>,
+    /// The new priority of the runs of the cron job, which is kept when omitted.
+    #[serde(default)]
+    pub priority: Option<i32>,
 }
 
 impl UpdateJobRequest {
@@ -43,12 +50,14 @@ impl UpdateJobRequest {
         schedule: String,
         inputs: Option<HashMap<String, String>>,
         env: Option<HashMap<String, String>>,
+        priority: Option<i32>,
     ) -> Self {
         Self {
             id,
             schedule,
             inputs,
             env,
+            priority,
         }
     }
 }
@@ -88,6 +97,8 @@ pub struct CronJobResponse {
     pub inputs: Option<HashMap<String, String>>,
     pub env: Option<HashMap<String, String>>,
     pub is_default: bool,
+    #[serde(default)]
+    pub priority: i32,
     pub date_created: String,
     pub date_updated: Option<String>,
 }
@@ -9,7 +9,15 @@ pub enum ExecClientMessage {
         name: String,
         env: Option<HashMap<String, String>>,
         inputs: Option<HashMap<String, String>>,
+        /// The run whose external job enqueued this one.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        parent_run_id: Option<String>,
+        /// Runs with a higher priority leave the queue of the supervisor first.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        priority: Option<i32>,
     },
+    /// Enqueues the pipeline of a previous run again with the same inputs and environment.
+    Rerun { run_id: String },
 }
 
 #[cfg(feature = "web_socket")]
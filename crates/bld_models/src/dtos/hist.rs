@@ -1,12 +1,16 @@
 #[cfg(feature = "database")]
 use crate::pipeline_runs::PipelineRuns;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct HistQueryParams {
     pub state: Option<String>,
     pub name: Option<String>,
     pub limit: u64,
+    /// Whether to include the trigger, inputs, environment and outputs of the runs.
+    #[serde(default)]
+    pub details: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -17,12 +21,53 @@ pub struct HistoryEntry {
     pub state: String,
     pub start_date_time: Option<String>,
     pub end_date_time: Option<String>,
+    /// The concurrency group that a queued run waits on.
+    #[serde(default)]
+    pub waiting_on_group: Option<String>,
+    /// Why the run faulted when it wasn't the pipeline itself that failed, such as its
+    /// worker being gone.
+    #[serde(default)]
+    pub fault_reason: Option<String>,
+    /// What started the run, such as a user, a cron job or a webhook.
+    #[serde(default)]
+    pub trigger_type: Option<String>,
+    #[serde(default)]
+    pub trigger_id: Option<String>,
+    #[serde(default)]
+    pub details: Option<HistoryEntryDetails>,
+}
+
+/// The parameters and outputs of a run, with the outputs grouped per job.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct HistoryEntryDetails {
+    pub inputs: HashMap<String, String>,
+    pub env: HashMap<String, String>,
+    pub outputs: HashMap<String, HashMap<String, String>>,
 }
 
 impl HistoryEntry {
     pub fn display_option(value: &Option<String>) -> String {
         value.as_deref().unwrap_or("").to_string()
     }
+
+    pub fn display_trigger(&self) -> String {
+        match (self.trigger_type.as_deref(), self.trigger_id.as_deref()) {
+            (Some(trigger_type), Some(id)) => format!("{trigger_type} ({id})"),
+            (Some(trigger_type), None) => trigger_type.to_owned(),
+            _ => String::new(),
+        }
+    }
+
+    pub fn display_state(&self) -> String {
+        match (
+            self.waiting_on_group.as_deref(),
+            self.fault_reason.as_deref(),
+        ) {
+            (Some(group), _) if self.state == "queued" => format!("waiting on group {group}"),
+            (_, Some(reason)) if self.state == "faulted" => format!("faulted ({reason})"),
+            _ => self.state.to_owned(),
+        }
+    }
 }
 
 #[cfg(feature = "database")]
@@ -35,6 +80,11 @@ impl From<PipelineRuns> for HistoryEntry {
             state: value.state,
             start_date_time: value.start_date.map(|x| x.format("%F %X").to_string()),
             end_date_time: value.end_date.map(|x| x.format("%F %X").to_string()),
+            waiting_on_group: value.waiting_on_group,
+            fault_reason: value.fault_reason,
+            trigger_type: value.trigger_type,
+            trigger_id: value.trigger_id,
+            details: None,
         }
     }
 }
@@ -1,3 +1,5 @@
+mod access;
+mod approval;
 mod artifacts;
 mod auth;
 mod common;
@@ -8,6 +10,9 @@ mod list;
 mod login;
 mod pull;
 mod push;
+mod secrets;
+mod timeline;
+mod triggers;
 
 #[cfg(feature = "web_socket")]
 mod exec;
@@ -18,6 +23,8 @@ mod monit;
 #[cfg(feature = "web_socket")]
 mod supervisor;
 
+pub use access::*;
+pub use approval::*;
 pub use artifacts::*;
 pub use auth::*;
 pub use common::*;
@@ -28,6 +35,9 @@ pub use list::*;
 pub use login::*;
 pub use pull::*;
 pub use push::*;
+pub use secrets::*;
+pub use timeline::*;
+pub use triggers::*;
 
 #[cfg(feature = "web_socket")]
 pub use exec::*;
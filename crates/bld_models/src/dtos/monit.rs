@@ -5,10 +5,22 @@ pub struct MonitInfo {
     pub id: Option<String>,
     pub name: Option<String>,
     pub last: bool,
+    #[serde(default)]
+    pub job: Option<String>,
 }
 
 impl MonitInfo {
     pub fn new(id: Option<String>, name: Option<String>, last: bool) -> Self {
-        Self { id, name, last }
+        Self {
+            id,
+            name,
+            last,
+            job: None,
+        }
+    }
+
+    pub fn with_job(mut self, job: Option<String>) -> Self {
+        self.job = job;
+        self
     }
 }
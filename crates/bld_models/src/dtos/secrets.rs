@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretRequest {
+    pub name: String,
+    pub value: String,
+}
+
+impl SecretRequest {
+    pub fn new(name: String, value: String) -> Self {
+        Self { name, value }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretResponse {
+    pub name: String,
+    pub date_created: String,
+    pub date_updated: Option<String>,
+}
+
+#[cfg(feature = "database")]
+impl From<crate::secrets::Secret> for SecretResponse {
+    fn from(value: crate::secrets::Secret) -> Self {
+        Self {
+            name: value.name,
+            date_created: value.date_created.format("%F %X").to_string(),
+            date_updated: value.date_updated.map(|x| x.format("%F %X").to_string()),
+        }
+    }
+}
@@ -3,7 +3,16 @@ use serde::{Deserialize, Serialize};
 pub static SERVER: &str = "server";
 pub static WORKER: &str = "worker";
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The concurrency group of a run, resolved by the server when the run is enqueued.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConcurrencyGroup {
+    pub name: String,
+    pub cancel_in_progress: bool,
+}
+
+/// The messages that the server sends to the supervisor, which the supervisor also forwards
+/// to the agents that it routes runs to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ServerMessages {
     Ack,
     Enqueue {
@@ -11,6 +20,15 @@ pub enum ServerMessages {
         run_id: String,
         inputs: Option<Vec<String>>,
         env: Option<Vec<String>>,
+        concurrency: Option<ConcurrencyGroup>,
+        #[serde(default)]
+        labels: Vec<String>,
+        /// The user that enqueued the run, so that the supervisor shares its capacity fairly.
+        #[serde(default)]
+        user: String,
+        /// Runs with a higher priority leave the queue of the supervisor first.
+        #[serde(default)]
+        priority: i32,
     },
     Stop {
         run_id: String,
@@ -20,6 +38,23 @@ pub enum ServerMessages {
 #[derive(Debug, Serialize, Deserialize)]
 pub enum WorkerMessages {
     Ack,
-    WhoAmI { pid: u32 },
+    WhoAmI {
+        pid: u32,
+        run_id: String,
+    },
     Completed,
+    /// Sent by a worker periodically so that the supervisor knows that it hasn't hung.
+    Heartbeat,
+    /// Sent by an agent when it connects, advertising its labels and how many runs it can
+    /// have active at the same time along with the agent token of the supervisor.
+    Register {
+        name: String,
+        token: String,
+        labels: Vec<String>,
+        capacity: usize,
+    },
+    /// Sent by an agent when the worker process of a run has exited.
+    Exited {
+        run_id: String,
+    },
 }
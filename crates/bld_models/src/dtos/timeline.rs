@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimelineQueryParams {
+    pub run_id: String,
+}
+
+/// A job of a run, with one entry for each combination of its matrix. The dates are
+/// also given as milliseconds since the epoch so that they can be placed on a timeline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineJobResponse {
+    pub id: String,
+    pub name: String,
+    pub matrix: Option<String>,
+    pub platform: Option<String>,
+    pub state: String,
+    pub error: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub start_millis: Option<i64>,
+    pub end_millis: Option<i64>,
+    pub steps: Vec<TimelineStepResponse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineStepResponse {
+    pub id: String,
+    pub step_id: String,
+    pub name: Option<String>,
+    pub matrix: Option<String>,
+    pub state: String,
+    pub error: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub start_millis: Option<i64>,
+    pub end_millis: Option<i64>,
+}
+
+#[cfg(feature = "database")]
+impl From<crate::pipeline_run_jobs::PipelineRunJobs> for TimelineJobResponse {
+    fn from(value: crate::pipeline_run_jobs::PipelineRunJobs) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            matrix: value.matrix,
+            platform: value.platform,
+            state: value.state,
+            error: value.error,
+            start_date: value.start_date.map(|x| x.format("%F %X").to_string()),
+            end_date: value.end_date.map(|x| x.format("%F %X").to_string()),
+            start_millis: value.start_date.map(|x| x.and_utc().timestamp_millis()),
+            end_millis: value.end_date.map(|x| x.and_utc().timestamp_millis()),
+            steps: vec![],
+        }
+    }
+}
+
+#[cfg(feature = "database")]
+impl From<crate::pipeline_run_steps::PipelineRunSteps> for TimelineStepResponse {
+    fn from(value: crate::pipeline_run_steps::PipelineRunSteps) -> Self {
+        Self {
+            id: value.id,
+            step_id: value.step_id,
+            name: value.name,
+            matrix: value.matrix,
+            state: value.state,
+            error: value.error,
+            start_date: value.start_date.map(|x| x.format("%F %X").to_string()),
+            end_date: value.end_date.map(|x| x.format("%F %X").to_string()),
+            start_millis: value.start_date.map(|x| x.and_utc().timestamp_millis()),
+            end_millis: value.end_date.map(|x| x.and_utc().timestamp_millis()),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddTriggerRequest {
+    pub name: String,
+    pub pipeline: String,
+    pub secret: String,
+    pub branches: Vec<String>,
+    pub paths: Vec<String>,
+    pub inputs: HashMap<String, String>,
+}
+
+impl AddTriggerRequest {
+    pub fn new(
+        name: String,
+        pipeline: String,
+        secret: String,
+        branches: Vec<String>,
+        paths: Vec<String>,
+        inputs: HashMap<String, String>,
+    ) -> Self {
+        Self {
+            name,
+            pipeline,
+            secret,
+            branches,
+            paths,
+            inputs,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateTriggerRequest {
+    pub name: String,
+    pub secret: Option<String>,
+    pub branches: Vec<String>,
+    pub paths: Vec<String>,
+    pub inputs: HashMap<String, String>,
+}
+
+impl UpdateTriggerRequest {
+    pub fn new(
+        name: String,
+        secret: Option<String>,
+        branches: Vec<String>,
+        paths: Vec<String>,
+        inputs: HashMap<String, String>,
+    ) -> Self {
+        Self {
+            name,
+            secret,
+            branches,
+            paths,
+            inputs,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TriggerResponse {
+    pub name: String,
+    pub pipeline: String,
+    pub branches: Vec<String>,
+    pub paths: Vec<String>,
+    pub inputs: HashMap<String, String>,
+    pub date_created: String,
+    pub date_updated: Option<String>,
+}
@@ -10,6 +10,7 @@ pub struct Model {
     pub pipeline_id: String,
     pub schedule: String,
     pub is_default: bool,
+    pub priority: i32,
     pub date_created: DateTime,
     pub date_updated: Option<DateTime>,
 }
@@ -2,7 +2,10 @@
 
 pub mod prelude;
 
+pub mod access_grants;
 pub mod artifacts;
+pub mod completion_trigger_events;
+pub mod completion_triggers;
 pub mod cron_job_environment_variables;
 pub mod cron_job_variables;
 pub mod cron_jobs;
@@ -16,5 +19,16 @@ pub mod high_availability_snapshot;
 pub mod high_availability_state_machine;
 pub mod login_attempts;
 pub mod pipeline;
+pub mod pipeline_run_approvals;
+pub mod pipeline_run_checkouts;
 pub mod pipeline_run_containers;
+pub mod pipeline_run_environment_variables;
+pub mod pipeline_run_inputs;
+pub mod pipeline_run_jobs;
+pub mod pipeline_run_outputs;
+pub mod pipeline_run_queue;
+pub mod pipeline_run_steps;
 pub mod pipeline_runs;
+pub mod secrets;
+pub mod webhook_trigger_inputs;
+pub mod webhook_triggers;
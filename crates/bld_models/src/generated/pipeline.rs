@@ -13,8 +13,18 @@ pub struct Model {
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::completion_triggers::Entity")]
+    CompletionTriggers,
     #[sea_orm(has_many = "super::cron_jobs::Entity")]
     CronJobs,
+    #[sea_orm(has_many = "super::webhook_triggers::Entity")]
+    WebhookTriggers,
+}
+
+impl Related<super::completion_triggers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CompletionTriggers.def()
+    }
 }
 
 impl Related<super::cron_jobs::Entity> for Entity {
@@ -23,4 +33,10 @@ impl Related<super::cron_jobs::Entity> for Entity {
     }
 }
 
+impl Related<super::webhook_triggers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WebhookTriggers.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -14,12 +14,51 @@ pub struct Model {
     pub end_date: Option<DateTime>,
     pub date_created: DateTime,
     pub date_updated: Option<DateTime>,
+    pub waiting_on_group: Option<String>,
+    pub trigger_id: Option<String>,
+    pub trigger_type: Option<String>,
+    pub fault_reason: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+#[allow(clippy::enum_variant_names)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::completion_trigger_events::Entity")]
+    CompletionTriggerEvents,
+    #[sea_orm(has_many = "super::pipeline_run_approvals::Entity")]
+    PipelineRunApprovals,
+    #[sea_orm(has_many = "super::pipeline_run_checkouts::Entity")]
+    PipelineRunCheckouts,
     #[sea_orm(has_many = "super::pipeline_run_containers::Entity")]
     PipelineRunContainers,
+    #[sea_orm(has_many = "super::pipeline_run_environment_variables::Entity")]
+    PipelineRunEnvironmentVariables,
+    #[sea_orm(has_many = "super::pipeline_run_inputs::Entity")]
+    PipelineRunInputs,
+    #[sea_orm(has_many = "super::pipeline_run_jobs::Entity")]
+    PipelineRunJobs,
+    #[sea_orm(has_many = "super::pipeline_run_outputs::Entity")]
+    PipelineRunOutputs,
+    #[sea_orm(has_many = "super::pipeline_run_queue::Entity")]
+    PipelineRunQueue,
+}
+
+impl Related<super::completion_trigger_events::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CompletionTriggerEvents.def()
+    }
+}
+
+impl Related<super::pipeline_run_approvals::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PipelineRunApprovals.def()
+    }
+}
+
+impl Related<super::pipeline_run_checkouts::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PipelineRunCheckouts.def()
+    }
 }
 
 impl Related<super::pipeline_run_containers::Entity> for Entity {
@@ -28,4 +67,34 @@ impl Related<super::pipeline_run_containers::Entity> for Entity {
     }
 }
 
+impl Related<super::pipeline_run_environment_variables::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PipelineRunEnvironmentVariables.def()
+    }
+}
+
+impl Related<super::pipeline_run_inputs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PipelineRunInputs.def()
+    }
+}
+
+impl Related<super::pipeline_run_jobs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PipelineRunJobs.def()
+    }
+}
+
+impl Related<super::pipeline_run_outputs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PipelineRunOutputs.def()
+    }
+}
+
+impl Related<super::pipeline_run_queue::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PipelineRunQueue.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -1,7 +1,10 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.1
 #![allow(unused_imports)]
 
+pub use super::access_grants::Entity as AccessGrants;
 pub use super::artifacts::Entity as Artifacts;
+pub use super::completion_trigger_events::Entity as CompletionTriggerEvents;
+pub use super::completion_triggers::Entity as CompletionTriggers;
 pub use super::cron_job_environment_variables::Entity as CronJobEnvironmentVariables;
 pub use super::cron_job_variables::Entity as CronJobVariables;
 pub use super::cron_jobs::Entity as CronJobs;
@@ -15,5 +18,16 @@ pub use super::high_availability_snapshot::Entity as HighAvailabilitySnapshot;
 pub use super::high_availability_state_machine::Entity as HighAvailabilityStateMachine;
 pub use super::login_attempts::Entity as LoginAttempts;
 pub use super::pipeline::Entity as Pipeline;
+pub use super::pipeline_run_approvals::Entity as PipelineRunApprovals;
+pub use super::pipeline_run_checkouts::Entity as PipelineRunCheckouts;
 pub use super::pipeline_run_containers::Entity as PipelineRunContainers;
+pub use super::pipeline_run_environment_variables::Entity as PipelineRunEnvironmentVariables;
+pub use super::pipeline_run_inputs::Entity as PipelineRunInputs;
+pub use super::pipeline_run_jobs::Entity as PipelineRunJobs;
+pub use super::pipeline_run_outputs::Entity as PipelineRunOutputs;
+pub use super::pipeline_run_queue::Entity as PipelineRunQueue;
+pub use super::pipeline_run_steps::Entity as PipelineRunSteps;
 pub use super::pipeline_runs::Entity as PipelineRuns;
+pub use super::secrets::Entity as Secrets;
+pub use super::webhook_trigger_inputs::Entity as WebhookTriggerInputs;
+pub use super::webhook_triggers::Entity as WebhookTriggers;
@@ -0,0 +1,114 @@
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, Condition, ConnectionTrait, EntityTrait,
+    QueryFilter, QueryOrder, TransactionTrait,
+};
+use tracing::{debug, error};
+
+pub use crate::generated::access_grants::Model as AccessGrant;
+use crate::generated::access_grants::{self, Entity as AccessGrantsEntity};
+
+pub const AG_SUBJECT_USER: &str = "user";
+pub const AG_SUBJECT_GROUP: &str = "group";
+
+#[derive(Debug)]
+pub struct InsertAccessGrant {
+    pub id: String,
+    pub role: String,
+    pub subject_type: String,
+    pub subject: String,
+    pub pipeline: String,
+}
+
+pub async fn select_all<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+) -> Result<Vec<AccessGrant>> {
+    debug!("loading all access grants");
+
+    AccessGrantsEntity::find()
+        .order_by_asc(access_grants::Column::Subject)
+        .order_by_asc(access_grants::Column::Pipeline)
+        .all(conn)
+        .await
+        .inspect(|_| debug!("loaded all access grants successfully"))
+        .map_err(|e| {
+            error!("could not load access grants due to: {e}");
+            anyhow!(e)
+        })
+}
+
+/// Loads the grants given either to the user or to any of the groups that the user is a
+/// member of.
+pub async fn select_by_subjects<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    user: &str,
+    groups: &[String],
+) -> Result<Vec<AccessGrant>> {
+    debug!("loading access grants for user: {user}");
+
+    let by_user = Condition::all()
+        .add(access_grants::Column::SubjectType.eq(AG_SUBJECT_USER))
+        .add(access_grants::Column::Subject.eq(user));
+    let by_group = Condition::all()
+        .add(access_grants::Column::SubjectType.eq(AG_SUBJECT_GROUP))
+        .add(access_grants::Column::Subject.is_in(groups));
+
+    AccessGrantsEntity::find()
+        .filter(Condition::any().add(by_user).add(by_group))
+        .all(conn)
+        .await
+        .inspect(|_| debug!("loaded access grants for user successfully"))
+        .map_err(|e| {
+            error!("could not load access grants for user due to: {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn insert<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    model: InsertAccessGrant,
+) -> Result<()> {
+    debug!(
+        "inserting {} access grant for {} {}",
+        model.role, model.subject_type, model.subject
+    );
+
+    let model = access_grants::ActiveModel {
+        id: Set(model.id),
+        role: Set(model.role),
+        subject_type: Set(model.subject_type),
+        subject: Set(model.subject),
+        pipeline: Set(model.pipeline),
+        date_created: Set(Utc::now().naive_utc()),
+    };
+
+    model
+        .insert(conn)
+        .await
+        .map(|_| debug!("inserted access grant successfully"))
+        .map_err(|e| {
+            error!("could not insert access grant due to: {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn delete_by_id<C: ConnectionTrait + TransactionTrait>(conn: &C, id: &str) -> Result<()> {
+    debug!("deleting access grant with id: {id}");
+
+    let result = AccessGrantsEntity::delete_by_id(id)
+        .exec(conn)
+        .await
+        .map_err(|e| {
+            error!("could not delete access grant due to: {e}");
+            anyhow!(e)
+        })?;
+
+    if result.rows_affected == 0 {
+        error!("couldn't delete access grant. Not found");
+        return Err(anyhow!("access grant not found"));
+    }
+
+    debug!("deleted access grant successfully");
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder,
+    TransactionTrait,
+};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use crate::generated::completion_trigger_events::{self, Entity as CompletionTriggerEventEntity};
+
+use super::completion_triggers;
+
+pub use crate::generated::completion_trigger_events::Model as CompletionTriggerEvent;
+
+pub async fn select_all<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+) -> Result<Vec<CompletionTriggerEvent>> {
+    debug!("loading all pending completion trigger events");
+    CompletionTriggerEventEntity::find()
+        .order_by_asc(completion_trigger_events::Column::DateCreated)
+        .all(conn)
+        .await
+        .inspect(|_| debug!("loaded completion trigger events successfully"))
+        .map_err(|e| {
+            error!("couldn't load completion trigger events due to {e}");
+            anyhow!(e)
+        })
+}
+
+/// Records an event for every completion trigger whose upstream pipeline is the one of
+/// the run and that fires on the state the run completed with.
+pub async fn insert_for_run<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    pipeline: &str,
+    run_id: &str,
+    state: &str,
+) -> Result<()> {
+    let models: Vec<completion_trigger_events::ActiveModel> =
+        completion_triggers::select_by_upstream(conn, pipeline)
+            .await?
+            .into_iter()
+            .filter(|trigger| {
+                completion_triggers::states(trigger)
+                    .iter()
+                    .any(|s| s == state)
+            })
+            .map(|trigger| completion_trigger_events::ActiveModel {
+                id: Set(Uuid::new_v4().to_string()),
+                trigger_id: Set(trigger.id),
+                run_id: Set(run_id.to_owned()),
+                state: Set(state.to_owned()),
+                date_created: Set(Utc::now().naive_utc()),
+            })
+            .collect();
+
+    if models.is_empty() {
+        return Ok(());
+    }
+
+    debug!(
+        "inserting {} completion trigger events for run {run_id} of pipeline {pipeline}",
+        models.len()
+    );
+
+    CompletionTriggerEventEntity::insert_many(models)
+        .exec(conn)
+        .await
+        .map(|_| debug!("inserted completion trigger events successfully"))
+        .map_err(|e| {
+            error!("couldn't insert completion trigger events due to {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn delete_by_id<C: ConnectionTrait + TransactionTrait>(conn: &C, id: &str) -> Result<()> {
+    debug!("deleting completion trigger event with id: {id}");
+    CompletionTriggerEventEntity::delete_by_id(id)
+        .exec(conn)
+        .await
+        .map(|_| debug!("deleted completion trigger event successfully"))
+        .map_err(|e| {
+            error!("couldn't delete completion trigger event due to {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn delete_by_trigger_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    trigger_id: &str,
+) -> Result<()> {
+    debug!("deleting completion trigger events of trigger with id: {trigger_id}");
+    CompletionTriggerEventEntity::delete_many()
+        .filter(completion_trigger_events::Column::TriggerId.eq(trigger_id))
+        .exec(conn)
+        .await
+        .map(|_| debug!("deleted completion trigger events successfully"))
+        .map_err(|e| {
+            error!("couldn't delete completion trigger events due to {e}");
+            anyhow!(e)
+        })
+}
@@ -0,0 +1,159 @@
+use anyhow::{Result, anyhow};
+use bld_migrations::Expr;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    TransactionTrait,
+};
+use tracing::{debug, error};
+
+use crate::generated::completion_triggers::{self, Entity as CompletionTriggerEntity};
+
+use super::completion_trigger_events;
+
+pub use crate::generated::completion_triggers::Model as CompletionTrigger;
+
+const STATES_SEPARATOR: &str = ",";
+
+pub struct InsertCompletionTrigger {
+    pub id: String,
+    pub pipeline_id: String,
+    pub upstream: String,
+    pub states: Vec<String>,
+}
+
+/// The final states of the upstream pipeline's runs that start a run of the trigger's
+/// pipeline.
+pub fn states(trigger: &CompletionTrigger) -> Vec<String> {
+    trigger
+        .states
+        .split(STATES_SEPARATOR)
+        .map(str::to_owned)
+        .collect()
+}
+
+pub async fn select_by_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    id: &str,
+) -> Result<CompletionTrigger> {
+    debug!("loading completion trigger with id: {id}");
+    CompletionTriggerEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|e| {
+            error!("couldn't load completion trigger due to {e}");
+            anyhow!(e)
+        })?
+        .ok_or_else(|| {
+            error!("couldn't load completion trigger due to not found");
+            anyhow!("completion trigger not found")
+        })
+        .inspect(|_| debug!("loaded completion trigger successfully"))
+}
+
+pub async fn select_by_pipeline<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    pipeline_id: &str,
+) -> Result<Option<CompletionTrigger>> {
+    debug!("loading completion trigger associated with pipeline: {pipeline_id}");
+    CompletionTriggerEntity::find()
+        .filter(completion_triggers::Column::PipelineId.eq(pipeline_id))
+        .one(conn)
+        .await
+        .inspect(|_| debug!("loaded completion trigger successfully"))
+        .map_err(|e| {
+            error!("couldn't load completion trigger due to {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn select_by_upstream<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    upstream: &str,
+) -> Result<Vec<CompletionTrigger>> {
+    debug!("loading completion triggers for upstream pipeline: {upstream}");
+    CompletionTriggerEntity::find()
+        .filter(completion_triggers::Column::Upstream.eq(upstream))
+        .all(conn)
+        .await
+        .inspect(|_| debug!("loaded completion triggers successfully"))
+        .map_err(|e| {
+            error!("couldn't load completion triggers due to {e}");
+            anyhow!(e)
+        })
+}
+
+/// Creates the completion trigger of a pipeline or replaces the upstream pipeline and
+/// states of the existing one, since a pipeline declares at most one.
+pub async fn upsert<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    model: &InsertCompletionTrigger,
+) -> Result<()> {
+    debug!(
+        "upserting completion trigger of pipeline_id: {} for upstream pipeline: {}",
+        model.pipeline_id, model.upstream
+    );
+    let states = model.states.join(STATES_SEPARATOR);
+
+    if let Some(trigger) = select_by_pipeline(conn, &model.pipeline_id).await? {
+        CompletionTriggerEntity::update_many()
+            .col_expr(
+                completion_triggers::Column::Upstream,
+                Expr::value(model.upstream.to_owned()),
+            )
+            .col_expr(completion_triggers::Column::States, Expr::value(states))
+            .col_expr(
+                completion_triggers::Column::DateUpdated,
+                Expr::value(Utc::now().naive_utc()),
+            )
+            .filter(completion_triggers::Column::Id.eq(&trigger.id))
+            .exec(conn)
+            .await
+            .map_err(|e| {
+                error!("couldn't update completion trigger due to {e}");
+                anyhow!(e)
+            })?;
+        debug!("updated completion trigger successfully");
+        return Ok(());
+    }
+
+    let active_model = completion_triggers::ActiveModel {
+        id: Set(model.id.to_owned()),
+        pipeline_id: Set(model.pipeline_id.to_owned()),
+        upstream: Set(model.upstream.to_owned()),
+        states: Set(states),
+        date_created: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    };
+
+    active_model.insert(conn).await.map_err(|e| {
+        error!("couldn't insert completion trigger due to {e}");
+        anyhow!(e)
+    })?;
+
+    debug!("created completion trigger successfully");
+    Ok(())
+}
+
+pub async fn delete_by_pipeline<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    pipeline_id: &str,
+) -> Result<()> {
+    debug!("deleting completion trigger associated with pipeline id: {pipeline_id}");
+    let txn = conn.begin().await?;
+
+    if let Some(model) = select_by_pipeline(&txn, pipeline_id).await? {
+        completion_trigger_events::delete_by_trigger_id(&txn, &model.id).await?;
+        CompletionTriggerEntity::delete_by_id(model.id)
+            .exec(&txn)
+            .await
+            .map_err(|e| {
+                error!("couldn't delete completion trigger due to {e}");
+                anyhow!(e)
+            })?;
+    }
+
+    txn.commit().await?;
+    debug!("deleted completion trigger successfully");
+    Ok(())
+}
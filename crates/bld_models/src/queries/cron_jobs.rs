@@ -24,11 +24,13 @@ pub struct InsertCronJob {
     pub pipeline_id: String,
     pub schedule: String,
     pub is_default: bool,
+    pub priority: i32,
 }
 
 pub struct UpdateCronJob {
     pub id: String,
     pub schedule: String,
+    pub priority: i32,
 }
 
 pub async fn select_all<C: ConnectionTrait + TransactionTrait>(conn: &C) -> Result<Vec<CronJob>> {
@@ -185,6 +187,7 @@ pub async fn insert<C: ConnectionTrait + TransactionTrait>(
         pipeline_id: Set(cj_model.pipeline_id.to_owned()),
         schedule: Set(cj_model.schedule.to_owned()),
         is_default: Set(cj_model.is_default),
+        priority: Set(cj_model.priority),
         date_created: Set(Utc::now().naive_utc()),
         ..Default::default()
     };
@@ -220,6 +223,7 @@ pub async fn update<C: ConnectionTrait + TransactionTrait>(
 
     CronJobEntity::update_many()
         .col_expr(cron_jobs::Column::Schedule, Expr::value(&cj_model.schedule))
+        .col_expr(cron_jobs::Column::Priority, Expr::value(cj_model.priority))
         .col_expr(cron_jobs::Column::DateUpdated, Expr::value(date_updated))
         .filter(cron_jobs::Column::Id.eq(&cj_model.id))
         .exec(conn)
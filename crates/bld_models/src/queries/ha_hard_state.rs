@@ -12,13 +12,15 @@ use crate::generated::high_availability_hard_state::{self, Entity as HighAvailHa
 
 #[derive(Debug)]
 pub struct InsertHighAvailHardState {
+    pub id: i32,
     pub current_term: i32,
     pub voted_for: Option<i32>,
 }
 
 impl InsertHighAvailHardState {
-    pub fn new(hs_current_term: i32, hs_voted_for: Option<i32>) -> Self {
+    pub fn new(hs_id: i32, hs_current_term: i32, hs_voted_for: Option<i32>) -> Self {
         Self {
+            id: hs_id,
             current_term: hs_current_term,
             voted_for: hs_voted_for,
         }
@@ -105,6 +107,7 @@ pub async fn insert<C: ConnectionTrait + TransactionTrait>(
     debug!("inserting new high availability hard state: {:?}", model);
 
     let active_model = high_availability_hard_state::ActiveModel {
+        id: Set(model.id),
         current_term: Set(model.current_term),
         voted_for: Set(model.voted_for),
         date_created: Set(Utc::now().naive_utc()),
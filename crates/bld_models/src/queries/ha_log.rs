@@ -36,6 +36,47 @@ impl InsertHighAvailLog {
     }
 }
 
+pub async fn select_all<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+) -> Result<Vec<HighAvailLog>> {
+    debug!("loading all entries of high availability log");
+
+    HighAvailLogEntity::find()
+        .order_by_asc(high_availability_log::Column::Id)
+        .all(conn)
+        .await
+        .inspect(|_| {
+            debug!("loaded high availability logs successfully");
+        })
+        .map_err(|e| {
+            error!("could not load high availability logs due to: {}", e);
+            anyhow!(e)
+        })
+}
+
+pub async fn select_after_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    lg_id: i32,
+) -> Result<Vec<HighAvailLog>> {
+    debug!(
+        "loading entries of high availability log greater than: {}",
+        lg_id
+    );
+
+    HighAvailLogEntity::find()
+        .filter(high_availability_log::Column::Id.gt(lg_id))
+        .order_by_asc(high_availability_log::Column::Id)
+        .all(conn)
+        .await
+        .inspect(|_| {
+            debug!("loaded high availability logs successfully");
+        })
+        .map_err(|e| {
+            error!("could not load high availability logs due to: {}", e);
+            anyhow!(e)
+        })
+}
+
 pub async fn select_last<C: ConnectionTrait + TransactionTrait>(conn: &C) -> Result<HighAvailLog> {
     debug!("loading the last entry of high availability log");
 
@@ -244,6 +285,7 @@ pub async fn insert_many<C: ConnectionTrait + TransactionTrait>(
             term: Set(m.term),
             payload: Set(m.payload),
             payload_type: Set(m.payload_type),
+            date_created: Set(Utc::now().naive_utc()),
             ..Default::default()
         })
         .collect();
@@ -309,12 +351,16 @@ pub async fn delete_from_id<C: ConnectionTrait + TransactionTrait>(
         "deleting high availability log entries starting from id: {}",
         lg_id
     );
-    HighAvailLogEntity::delete_by_id(lg_id)
+    HighAvailLogEntity::delete_many()
+        .filter(high_availability_log::Column::Id.gte(lg_id))
         .exec(conn)
         .await
-        .map(|_| debug!("deleted high availability log entry successfully"))
+        .map(|_| debug!("deleted high availability log entries successfully"))
         .map_err(|e| {
-            error!("could not delete high availability log entry due to: {}", e);
+            error!(
+                "could not delete high availability log entries due to: {}",
+                e
+            );
             anyhow!(e)
         })
 }
@@ -1,7 +1,8 @@
 use anyhow::{Result, anyhow};
 use chrono::Utc;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue::Set, ConnectionTrait, EntityTrait, QueryOrder, TransactionTrait,
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, TransactionTrait,
 };
 use tracing::{debug, error};
 
@@ -53,7 +54,7 @@ pub async fn insert<C: ConnectionTrait + TransactionTrait>(
     conn: &C,
     model: InsertHighAvailSnapshot,
 ) -> Result<()> {
-    debug!("inserting high availability snapshot: {:?}", model);
+    debug!("inserting high availability snapshot with id: {}", model.id);
 
     let model = high_availability_snapshot::ActiveModel {
         id: Set(model.id),
@@ -71,3 +72,19 @@ pub async fn insert<C: ConnectionTrait + TransactionTrait>(
     debug!("inserted high availability snapshot successfully");
     Ok(())
 }
+
+pub async fn delete_until_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    sn_id: i32,
+) -> Result<()> {
+    debug!("deleting high availability snapshots less than: {}", sn_id);
+    HighAvailSnapshotEntity::delete_many()
+        .filter(high_availability_snapshot::Column::Id.lt(sn_id))
+        .exec(conn)
+        .await
+        .map(|_| debug!("deleted high availability snapshots successfully"))
+        .map_err(|e| {
+            error!("could not delete high availability snapshots due to: {}", e);
+            anyhow!(e)
+        })
+}
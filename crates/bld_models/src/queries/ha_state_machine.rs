@@ -94,6 +94,7 @@ pub async fn select_by_id<C: ConnectionTrait + TransactionTrait>(
 
 pub async fn insert<C: ConnectionTrait + TransactionTrait>(
     conn: &C,
+    sm_id: i32,
     sm_last_applied_log: i32,
 ) -> Result<()> {
     debug!(
@@ -102,6 +103,7 @@ pub async fn insert<C: ConnectionTrait + TransactionTrait>(
     );
 
     let model = high_availability_state_machine::ActiveModel {
+        id: Set(sm_id),
         last_applied_log: Set(sm_last_applied_log),
         date_created: Set(Utc::now().naive_utc()),
         ..Default::default()
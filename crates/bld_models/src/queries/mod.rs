@@ -1,4 +1,7 @@
+pub mod access_grants;
 pub mod artifacts;
+pub mod completion_trigger_events;
+pub mod completion_triggers;
 pub mod cron_job_environment_variables;
 pub mod cron_job_variables;
 pub mod cron_jobs;
@@ -12,8 +15,19 @@ pub mod ha_snapshot;
 pub mod ha_state_machine;
 pub mod login_attempts;
 pub mod pipeline;
+pub mod pipeline_run_approvals;
+pub mod pipeline_run_checkouts;
 pub mod pipeline_run_containers;
+pub mod pipeline_run_environment_variables;
+pub mod pipeline_run_inputs;
+pub mod pipeline_run_jobs;
+pub mod pipeline_run_outputs;
+pub mod pipeline_run_queue;
+pub mod pipeline_run_steps;
 pub mod pipeline_runs;
+pub mod secrets;
+pub mod webhook_trigger_inputs;
+pub mod webhook_triggers;
 
 use anyhow::{Result, bail};
 use bld_config::BldConfig;
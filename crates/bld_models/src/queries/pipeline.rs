@@ -8,7 +8,7 @@ use sea_orm::{
 };
 use tracing::{debug, error};
 
-use super::cron_jobs;
+use super::{completion_triggers, cron_jobs, webhook_triggers};
 
 pub use crate::generated::pipeline::Model as Pipeline;
 
@@ -131,6 +131,8 @@ pub async fn delete_by_name<C: ConnectionTrait + TransactionTrait>(
     let txn = conn.begin().await?;
     let model = select_by_name(&txn, pip_name).await?;
     cron_jobs::delete_by_pipeline(&txn, &model.id).await?;
+    webhook_triggers::delete_by_pipeline(&txn, &model.id).await?;
+    completion_triggers::delete_by_pipeline(&txn, &model.id).await?;
     model
         .delete(&txn)
         .await
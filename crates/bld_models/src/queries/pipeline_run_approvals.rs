@@ -0,0 +1,145 @@
+use anyhow::{Result, anyhow, bail};
+use bld_migrations::Expr;
+use chrono::Utc;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder,
+    TransactionTrait,
+};
+use tracing::{debug, error};
+
+pub use crate::generated::pipeline_run_approvals::Model as PipelineRunApprovals;
+use crate::generated::pipeline_run_approvals::{self, Entity as PipelineRunApprovalsEntity};
+
+pub const PRA_STATE_PENDING: &str = "pending";
+pub const PRA_STATE_APPROVED: &str = "approved";
+pub const PRA_STATE_REJECTED: &str = "rejected";
+pub const PRA_STATE_TIMED_OUT: &str = "timed-out";
+
+const APPROVERS_SEPARATOR: &str = ",";
+
+#[derive(Debug)]
+pub struct InsertPipelineRunApproval {
+    pub id: String,
+    pub run_id: String,
+    pub job: String,
+    pub message: Option<String>,
+    pub approvers: Vec<String>,
+}
+
+/// Checks whether the user is allowed to decide on the approval, which is the case for
+/// every user when the approval doesn't name any approvers.
+pub fn is_approver(approval: &PipelineRunApprovals, user: &str) -> bool {
+    approval.approvers.is_empty()
+        || approval
+            .approvers
+            .split(APPROVERS_SEPARATOR)
+            .any(|approver| approver == user)
+}
+
+pub async fn select_by_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    id: &str,
+) -> Result<PipelineRunApprovals> {
+    debug!("loading pipeline run approval with id: {id}");
+
+    PipelineRunApprovalsEntity::find_by_id(id)
+        .one(conn)
+        .await
+        .map_err(|e| {
+            error!("could not load pipeline run approval. {e}");
+            anyhow!(e)
+        })?
+        .ok_or_else(|| {
+            error!("couldn't load pipeline run approval. Not found");
+            anyhow!("pipeline run approval not found")
+        })
+        .inspect(|_| debug!("loaded pipeline run approval successfully"))
+}
+
+/// Loads the pending approvals of a run, oldest first.
+pub async fn select_pending_by_run_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    run_id: &str,
+) -> Result<Vec<PipelineRunApprovals>> {
+    debug!("loading pending approvals of pipeline run with id: {run_id}");
+
+    PipelineRunApprovalsEntity::find()
+        .filter(pipeline_run_approvals::Column::RunId.eq(run_id))
+        .filter(pipeline_run_approvals::Column::State.eq(PRA_STATE_PENDING))
+        .order_by_asc(pipeline_run_approvals::Column::DateCreated)
+        .all(conn)
+        .await
+        .map_err(|e| {
+            error!("could not load pending pipeline run approvals. {e}");
+            anyhow!(e)
+        })
+        .inspect(|_| debug!("loaded pending pipeline run approvals successfully"))
+}
+
+pub async fn insert<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    model: InsertPipelineRunApproval,
+) -> Result<PipelineRunApprovals> {
+    debug!("inserting pipeline run approval for job {}", model.job);
+
+    let id = model.id;
+    let model = pipeline_run_approvals::ActiveModel {
+        id: Set(id.to_owned()),
+        run_id: Set(model.run_id),
+        job: Set(model.job),
+        message: Set(model.message),
+        approvers: Set(model.approvers.join(APPROVERS_SEPARATOR)),
+        state: Set(PRA_STATE_PENDING.to_owned()),
+        date_created: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    };
+
+    PipelineRunApprovalsEntity::insert(model)
+        .exec(conn)
+        .await
+        .map_err(|e| {
+            error!("could not insert pipeline run approval. {e}");
+            anyhow!(e)
+        })?;
+
+    debug!("inserted pipeline run approval successfully");
+    select_by_id(conn, &id).await
+}
+
+/// Records the decision on an approval that is still pending, failing when it has
+/// already been decided on or has timed out.
+pub async fn update_decision<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    id: &str,
+    state: &str,
+    decided_by: Option<&str>,
+) -> Result<()> {
+    debug!("updating pipeline run approval with id: {id} with new state: {state}");
+
+    let result = PipelineRunApprovalsEntity::update_many()
+        .col_expr(pipeline_run_approvals::Column::State, Expr::value(state))
+        .col_expr(
+            pipeline_run_approvals::Column::DecidedBy,
+            Expr::value(decided_by.map(str::to_owned)),
+        )
+        .col_expr(
+            pipeline_run_approvals::Column::DateUpdated,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(pipeline_run_approvals::Column::Id.eq(id))
+        .filter(pipeline_run_approvals::Column::State.eq(PRA_STATE_PENDING))
+        .exec(conn)
+        .await
+        .map_err(|e| {
+            error!("could not update pipeline run approval. {e}");
+            anyhow!(e)
+        })?;
+
+    if result.rows_affected == 0 {
+        error!("couldn't update pipeline run approval. Not pending");
+        bail!("pipeline run approval is no longer pending");
+    }
+
+    debug!("updated pipeline run approval successfully");
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder,
+    TransactionTrait,
+};
+use tracing::{debug, error};
+
+pub use crate::generated::pipeline_run_checkouts::Model as PipelineRunCheckouts;
+use crate::generated::pipeline_run_checkouts::{self, Entity as PipelineRunCheckoutsEntity};
+
+#[derive(Debug)]
+pub struct InsertPipelineRunCheckout {
+    pub id: String,
+    pub run_id: String,
+    pub job: String,
+    pub repository: String,
+    pub reference: Option<String>,
+    pub sha: String,
+    pub path: String,
+}
+
+pub async fn select_by_run_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    run_id: &str,
+) -> Result<Vec<PipelineRunCheckouts>> {
+    debug!("loading checkouts of pipeline run with id: {run_id}");
+
+    PipelineRunCheckoutsEntity::find()
+        .filter(pipeline_run_checkouts::Column::RunId.eq(run_id))
+        .order_by_asc(pipeline_run_checkouts::Column::DateCreated)
+        .all(conn)
+        .await
+        .map_err(|e| {
+            error!("could not load pipeline run checkouts. {e}");
+            anyhow!(e)
+        })
+        .inspect(|_| debug!("loaded pipeline run checkouts successfully"))
+}
+
+pub async fn insert<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    model: InsertPipelineRunCheckout,
+) -> Result<()> {
+    debug!(
+        "inserting checkout of {} at {} for pipeline run with id: {}",
+        model.repository, model.sha, model.run_id
+    );
+
+    let model = pipeline_run_checkouts::ActiveModel {
+        id: Set(model.id),
+        run_id: Set(model.run_id),
+        job: Set(model.job),
+        repository: Set(model.repository),
+        reference: Set(model.reference),
+        sha: Set(model.sha),
+        path: Set(model.path),
+        date_created: Set(Utc::now().naive_utc()),
+    };
+
+    PipelineRunCheckoutsEntity::insert(model)
+        .exec(conn)
+        .await
+        .map_err(|e| {
+            error!("could not insert pipeline run checkout. {e}");
+            anyhow!(e)
+        })?;
+
+    debug!("inserted pipeline run checkout successfully");
+    Ok(())
+}
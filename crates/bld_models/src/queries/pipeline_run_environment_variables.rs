@@ -0,0 +1,97 @@
+use crate::generated::pipeline_run_environment_variables::{
+    self, Entity as PipelineRunEnvironmentVariableEntity,
+};
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, TransactionTrait,
+};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+pub use crate::generated::pipeline_run_environment_variables::Model as PipelineRunEnvironmentVariable;
+
+const SENSITIVE_NAME_PARTS: [&str; 7] = [
+    "SECRET",
+    "TOKEN",
+    "PASSWORD",
+    "PASSWD",
+    "KEY",
+    "CREDENTIAL",
+    "AUTH",
+];
+
+/// Whether the name of an environment variable suggests that it holds a secret, in which
+/// case its value is never persisted with the run.
+pub fn is_sensitive(name: &str) -> bool {
+    let name = name.to_uppercase();
+    SENSITIVE_NAME_PARTS.iter().any(|part| name.contains(part))
+}
+
+pub struct InsertPipelineRunEnvironmentVariable {
+    pub id: String,
+    pub run_id: String,
+    pub name: String,
+    pub value: String,
+}
+
+impl InsertPipelineRunEnvironmentVariable {
+    pub fn new(kv: (&String, &String), run_id: &str) -> Self {
+        let (name, value) = kv;
+        Self {
+            id: Uuid::new_v4().to_string(),
+            run_id: run_id.to_owned(),
+            name: name.to_owned(),
+            value: value.to_owned(),
+        }
+    }
+}
+
+pub async fn select_by_run_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    run_id: &str,
+) -> Result<Vec<PipelineRunEnvironmentVariable>> {
+    debug!("loading all environment variables for pipeline run with id: {run_id}");
+    PipelineRunEnvironmentVariableEntity::find()
+        .filter(pipeline_run_environment_variables::Column::RunId.eq(run_id))
+        .all(conn)
+        .await
+        .inspect(|_| {
+            debug!("loaded pipeline run environment variables successfully");
+        })
+        .map_err(|e| {
+            error!("couldn't load pipeline run environment variables due to {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn insert_many<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    models: &[InsertPipelineRunEnvironmentVariable],
+) -> Result<()> {
+    if models.is_empty() {
+        return Ok(());
+    }
+
+    let models: Vec<pipeline_run_environment_variables::ActiveModel> = models
+        .iter()
+        .map(|m| pipeline_run_environment_variables::ActiveModel {
+            id: Set(m.id.to_owned()),
+            run_id: Set(m.run_id.to_owned()),
+            name: Set(m.name.to_owned()),
+            value: Set(m.value.to_owned()),
+            date_created: Set(Utc::now().naive_utc()),
+        })
+        .collect();
+
+    PipelineRunEnvironmentVariableEntity::insert_many(models)
+        .exec(conn)
+        .await
+        .map(|_| {
+            debug!("created new pipeline run environment variables successfully");
+        })
+        .map_err(|e| {
+            error!("couldn't insert pipeline run environment variables due to {e}");
+            anyhow!(e)
+        })
+}
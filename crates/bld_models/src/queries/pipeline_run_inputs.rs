@@ -0,0 +1,78 @@
+use crate::generated::pipeline_run_inputs::{self, Entity as PipelineRunInputEntity};
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, TransactionTrait,
+};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+pub use crate::generated::pipeline_run_inputs::Model as PipelineRunInput;
+
+pub struct InsertPipelineRunInput {
+    pub id: String,
+    pub run_id: String,
+    pub name: String,
+    pub value: String,
+}
+
+impl InsertPipelineRunInput {
+    pub fn new(kv: (&String, &String), run_id: &str) -> Self {
+        let (name, value) = kv;
+        Self {
+            id: Uuid::new_v4().to_string(),
+            run_id: run_id.to_owned(),
+            name: name.to_owned(),
+            value: value.to_owned(),
+        }
+    }
+}
+
+pub async fn select_by_run_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    run_id: &str,
+) -> Result<Vec<PipelineRunInput>> {
+    debug!("loading all inputs for pipeline run with id: {run_id}");
+    PipelineRunInputEntity::find()
+        .filter(pipeline_run_inputs::Column::RunId.eq(run_id))
+        .all(conn)
+        .await
+        .inspect(|_| {
+            debug!("loaded pipeline run inputs successfully");
+        })
+        .map_err(|e| {
+            error!("couldn't load pipeline run inputs due to {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn insert_many<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    models: &[InsertPipelineRunInput],
+) -> Result<()> {
+    if models.is_empty() {
+        return Ok(());
+    }
+
+    let models: Vec<pipeline_run_inputs::ActiveModel> = models
+        .iter()
+        .map(|m| pipeline_run_inputs::ActiveModel {
+            id: Set(m.id.to_owned()),
+            run_id: Set(m.run_id.to_owned()),
+            name: Set(m.name.to_owned()),
+            value: Set(m.value.to_owned()),
+            date_created: Set(Utc::now().naive_utc()),
+        })
+        .collect();
+
+    PipelineRunInputEntity::insert_many(models)
+        .exec(conn)
+        .await
+        .map(|_| {
+            debug!("created new pipeline run inputs successfully");
+        })
+        .map_err(|e| {
+            error!("couldn't insert pipeline run inputs due to {e}");
+            anyhow!(e)
+        })
+}
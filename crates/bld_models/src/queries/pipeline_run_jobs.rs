@@ -0,0 +1,112 @@
+use anyhow::{Result, anyhow};
+use bld_migrations::Expr;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, TransactionTrait,
+};
+use tracing::{debug, error};
+
+pub use crate::generated::pipeline_run_jobs::Model as PipelineRunJobs;
+use crate::generated::pipeline_run_jobs::{self, Entity as PipelineRunJobsEntity};
+
+pub const PRJ_STATE_RUNNING: &str = "running";
+pub const PRJ_STATE_COMPLETED: &str = "completed";
+pub const PRJ_STATE_CONTINUED_ON_ERROR: &str = "continued_on_error";
+pub const PRJ_STATE_FAILED: &str = "failed";
+pub const PRJ_STATE_CANCELLED: &str = "cancelled";
+
+/// Whether the state is one of the final states of a job or a step, which are the same.
+pub fn is_completed(state: &str) -> bool {
+    state != PRJ_STATE_RUNNING
+}
+
+#[derive(Debug)]
+pub struct InsertPipelineRunJob {
+    pub id: String,
+    pub run_id: String,
+    pub name: String,
+    pub matrix: Option<String>,
+    pub platform: Option<String>,
+}
+
+pub async fn select_by_run_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    run_id: &str,
+) -> Result<Vec<PipelineRunJobs>> {
+    debug!("loading jobs of pipeline run with id: {run_id}");
+
+    PipelineRunJobsEntity::find()
+        .filter(pipeline_run_jobs::Column::RunId.eq(run_id))
+        .order_by_asc(pipeline_run_jobs::Column::StartDate)
+        .all(conn)
+        .await
+        .map_err(|e| {
+            error!("could not load pipeline run jobs. {e}");
+            anyhow!(e)
+        })
+        .inspect(|_| debug!("loaded pipeline run jobs successfully"))
+}
+
+/// Inserts a job of a run that has just started running.
+pub async fn insert<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    model: InsertPipelineRunJob,
+) -> Result<()> {
+    debug!(
+        "inserting job {} for pipeline run with id: {}",
+        model.name, model.run_id
+    );
+
+    let now = Utc::now().naive_utc();
+    let model = pipeline_run_jobs::ActiveModel {
+        id: Set(model.id),
+        run_id: Set(model.run_id),
+        name: Set(model.name),
+        matrix: Set(model.matrix),
+        platform: Set(model.platform),
+        state: Set(PRJ_STATE_RUNNING.to_owned()),
+        start_date: Set(Some(now)),
+        date_created: Set(now),
+        ..Default::default()
+    };
+
+    model
+        .insert(conn)
+        .await
+        .map(|_| debug!("inserted pipeline run job successfully"))
+        .map_err(|e| {
+            error!("could not insert pipeline run job due to: {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn update_state<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    id: &str,
+    state: &str,
+    job_error: Option<String>,
+) -> Result<()> {
+    debug!("updating pipeline run job {id} with state: {state}");
+
+    let now = Utc::now().naive_utc();
+    let mut update_statement = PipelineRunJobsEntity::update_many()
+        .col_expr(pipeline_run_jobs::Column::State, Expr::value(state))
+        .col_expr(pipeline_run_jobs::Column::Error, Expr::value(job_error))
+        .col_expr(pipeline_run_jobs::Column::DateUpdated, Expr::value(now));
+
+    if is_completed(state) {
+        update_statement =
+            update_statement.col_expr(pipeline_run_jobs::Column::EndDate, Expr::value(now));
+    }
+
+    update_statement
+        .filter(pipeline_run_jobs::Column::Id.eq(id))
+        .exec(conn)
+        .await
+        .map(|_| debug!("updated pipeline run job successfully"))
+        .map_err(|e| {
+            error!("could not update pipeline run job due to: {e}");
+            anyhow!(e)
+        })
+}
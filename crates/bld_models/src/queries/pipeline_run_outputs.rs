@@ -0,0 +1,69 @@
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, TransactionTrait,
+};
+use tracing::{debug, error};
+
+pub use crate::generated::pipeline_run_outputs::Model as PipelineRunOutputs;
+use crate::generated::pipeline_run_outputs::{self, Entity as PipelineRunOutputsEntity};
+
+#[derive(Debug)]
+pub struct InsertPipelineRunOutput {
+    pub id: String,
+    pub run_id: String,
+    pub job: String,
+    pub name: String,
+    pub value: String,
+}
+
+pub async fn select_by_run_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    run_id: &str,
+) -> Result<Vec<PipelineRunOutputs>> {
+    debug!("loading outputs of pipeline run with id: {run_id}");
+
+    PipelineRunOutputsEntity::find()
+        .filter(pipeline_run_outputs::Column::RunId.eq(run_id))
+        .all(conn)
+        .await
+        .map_err(|e| {
+            error!("could not load pipeline run outputs. {e}");
+            anyhow!(e)
+        })
+        .inspect(|_| debug!("loaded pipeline run outputs successfully"))
+}
+
+pub async fn insert_many<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    models: Vec<InsertPipelineRunOutput>,
+) -> Result<()> {
+    if models.is_empty() {
+        return Ok(());
+    }
+
+    debug!("inserting {} outputs of pipeline run", models.len());
+
+    let models: Vec<pipeline_run_outputs::ActiveModel> = models
+        .into_iter()
+        .map(|m| pipeline_run_outputs::ActiveModel {
+            id: Set(m.id),
+            run_id: Set(m.run_id),
+            job: Set(m.job),
+            name: Set(m.name),
+            value: Set(m.value),
+            date_created: Set(Utc::now().naive_utc()),
+        })
+        .collect();
+
+    PipelineRunOutputsEntity::insert_many(models)
+        .exec(conn)
+        .await
+        .map_err(|e| {
+            error!("could not insert pipeline run outputs. {e}");
+            anyhow!(e)
+        })?;
+
+    debug!("inserted pipeline run outputs successfully");
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, TransactionTrait,
+};
+use tracing::{debug, error};
+
+pub use crate::generated::pipeline_run_queue::Model as PipelineRunQueue;
+use crate::generated::pipeline_run_queue::{self, Entity as PipelineRunQueueEntity};
+
+#[derive(Debug)]
+pub struct InsertPipelineRunQueue {
+    pub id: String,
+    pub run_id: String,
+    pub priority: i32,
+    pub message: String,
+}
+
+/// Loads every run waiting in the queue of the supervisor, in the order that they were
+/// added to it.
+pub async fn select_all<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+) -> Result<Vec<PipelineRunQueue>> {
+    debug!("loading all queued pipeline runs");
+
+    PipelineRunQueueEntity::find()
+        .order_by_asc(pipeline_run_queue::Column::DateCreated)
+        .all(conn)
+        .await
+        .map_err(|e| {
+            error!("could not load queued pipeline runs. {e}");
+            anyhow!(e)
+        })
+        .inspect(|_| debug!("loaded queued pipeline runs successfully"))
+}
+
+pub async fn insert<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    model: InsertPipelineRunQueue,
+) -> Result<()> {
+    debug!(
+        "inserting pipeline run with id: {} to the queue",
+        model.run_id
+    );
+
+    let model = pipeline_run_queue::ActiveModel {
+        id: Set(model.id),
+        run_id: Set(model.run_id),
+        priority: Set(model.priority),
+        message: Set(model.message),
+        date_created: Set(Utc::now().naive_utc()),
+    };
+
+    model
+        .insert(conn)
+        .await
+        .map(|_| debug!("inserted pipeline run to the queue successfully"))
+        .map_err(|e| {
+            error!("could not insert pipeline run to the queue due to: {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn delete_by_run_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    run_id: &str,
+) -> Result<()> {
+    debug!("deleting pipeline run with id: {run_id} from the queue");
+
+    PipelineRunQueueEntity::delete_many()
+        .filter(pipeline_run_queue::Column::RunId.eq(run_id))
+        .exec(conn)
+        .await
+        .map(|_| debug!("deleted pipeline run from the queue successfully"))
+        .map_err(|e| {
+            error!("could not delete pipeline run from the queue due to: {e}");
+            anyhow!(e)
+        })
+}
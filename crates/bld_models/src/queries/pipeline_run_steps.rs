@@ -0,0 +1,103 @@
+use anyhow::{Result, anyhow};
+use bld_migrations::Expr;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, TransactionTrait,
+};
+use tracing::{debug, error};
+
+pub use crate::generated::pipeline_run_steps::Model as PipelineRunSteps;
+use crate::generated::pipeline_run_steps::{self, Entity as PipelineRunStepsEntity};
+
+use super::pipeline_run_jobs::{PRJ_STATE_RUNNING, is_completed};
+
+#[derive(Debug)]
+pub struct InsertPipelineRunStep {
+    pub id: String,
+    pub job_id: String,
+    pub step_id: String,
+    pub name: Option<String>,
+    pub matrix: Option<String>,
+}
+
+pub async fn select_by_job_ids<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    job_ids: &[String],
+) -> Result<Vec<PipelineRunSteps>> {
+    debug!("loading steps of pipeline run jobs with ids: {job_ids:?}");
+
+    PipelineRunStepsEntity::find()
+        .filter(pipeline_run_steps::Column::JobId.is_in(job_ids.iter().cloned()))
+        .order_by_asc(pipeline_run_steps::Column::StartDate)
+        .all(conn)
+        .await
+        .map_err(|e| {
+            error!("could not load pipeline run steps. {e}");
+            anyhow!(e)
+        })
+        .inspect(|_| debug!("loaded pipeline run steps successfully"))
+}
+
+/// Inserts a step of a job that has just started running.
+pub async fn insert<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    model: InsertPipelineRunStep,
+) -> Result<()> {
+    debug!(
+        "inserting step {} for pipeline run job with id: {}",
+        model.step_id, model.job_id
+    );
+
+    let now = Utc::now().naive_utc();
+    let model = pipeline_run_steps::ActiveModel {
+        id: Set(model.id),
+        job_id: Set(model.job_id),
+        step_id: Set(model.step_id),
+        name: Set(model.name),
+        matrix: Set(model.matrix),
+        state: Set(PRJ_STATE_RUNNING.to_owned()),
+        start_date: Set(Some(now)),
+        date_created: Set(now),
+        ..Default::default()
+    };
+
+    model
+        .insert(conn)
+        .await
+        .map(|_| debug!("inserted pipeline run step successfully"))
+        .map_err(|e| {
+            error!("could not insert pipeline run step due to: {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn update_state<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    id: &str,
+    state: &str,
+    step_error: Option<String>,
+) -> Result<()> {
+    debug!("updating pipeline run step {id} with state: {state}");
+
+    let now = Utc::now().naive_utc();
+    let mut update_statement = PipelineRunStepsEntity::update_many()
+        .col_expr(pipeline_run_steps::Column::State, Expr::value(state))
+        .col_expr(pipeline_run_steps::Column::Error, Expr::value(step_error))
+        .col_expr(pipeline_run_steps::Column::DateUpdated, Expr::value(now));
+
+    if is_completed(state) {
+        update_statement =
+            update_statement.col_expr(pipeline_run_steps::Column::EndDate, Expr::value(now));
+    }
+
+    update_statement
+        .filter(pipeline_run_steps::Column::Id.eq(id))
+        .exec(conn)
+        .await
+        .map(|_| debug!("updated pipeline run step successfully"))
+        .map_err(|e| {
+            error!("could not update pipeline run step due to: {e}");
+            anyhow!(e)
+        })
+}
@@ -6,21 +6,91 @@ use sea_orm::{
     DatabaseConnection, EntityTrait, FromQueryResult, PaginatorTrait, QueryFilter, QueryOrder,
     QuerySelect, Statement, TransactionTrait, prelude::DateTime,
 };
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use tracing::{debug, error};
 
 pub use crate::generated::pipeline_runs::Model as PipelineRuns;
 use crate::generated::pipeline_runs::{self, Entity as PipelineRunsEntity};
 
+use super::{
+    completion_trigger_events,
+    pipeline_run_environment_variables::{self, InsertPipelineRunEnvironmentVariable},
+    pipeline_run_inputs::{self, InsertPipelineRunInput},
+};
+
 pub const PR_STATE_INITIAL: &str = "initial";
 pub const PR_STATE_QUEUED: &str = "queued";
 pub const PR_STATE_RUNNING: &str = "running";
+pub const PR_STATE_WAITING_APPROVAL: &str = "waiting-for-approval";
 pub const PR_STATE_FINISHED: &str = "finished";
 pub const PR_STATE_FAULTED: &str = "faulted";
 
+pub const PR_TRIGGER_USER: &str = "user";
+pub const PR_TRIGGER_CRON: &str = "cron";
+pub const PR_TRIGGER_WEBHOOK: &str = "webhook";
+pub const PR_TRIGGER_PIPELINE_COMPLETED: &str = "pipeline_completed";
+pub const PR_TRIGGER_PARENT_RUN: &str = "parent_run";
+pub const PR_TRIGGER_RERUN: &str = "rerun";
+
+/// Whether the state is one of the final states of a run.
+pub fn is_completed(state: &str) -> bool {
+    state == PR_STATE_FINISHED || state == PR_STATE_FAULTED
+}
+
+/// What started a run, along with the id of the cron job, webhook trigger, upstream
+/// run, parent run or re-run run depending on the type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunTrigger {
+    pub trigger_type: String,
+    pub trigger_id: Option<String>,
+}
+
+impl RunTrigger {
+    pub fn user() -> Self {
+        Self {
+            trigger_type: PR_TRIGGER_USER.to_owned(),
+            trigger_id: None,
+        }
+    }
+
+    pub fn cron(job_id: &str) -> Self {
+        Self::with_id(PR_TRIGGER_CRON, job_id)
+    }
+
+    pub fn webhook(trigger_id: &str) -> Self {
+        Self::with_id(PR_TRIGGER_WEBHOOK, trigger_id)
+    }
+
+    pub fn pipeline_completed(upstream_run_id: &str) -> Self {
+        Self::with_id(PR_TRIGGER_PIPELINE_COMPLETED, upstream_run_id)
+    }
+
+    pub fn parent_run(run_id: &str) -> Self {
+        Self::with_id(PR_TRIGGER_PARENT_RUN, run_id)
+    }
+
+    pub fn rerun(run_id: &str) -> Self {
+        Self::with_id(PR_TRIGGER_RERUN, run_id)
+    }
+
+    fn with_id(trigger_type: &str, id: &str) -> Self {
+        Self {
+            trigger_type: trigger_type.to_owned(),
+            trigger_id: Some(id.to_owned()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InsertPipelineRun {
     pub id: String,
     pub name: String,
     pub app_user: String,
+    pub trigger: RunTrigger,
+    pub inputs: HashMap<String, String>,
+    /// The environment variables of the run, sensitive ones are not persisted.
+    pub env: HashMap<String, String>,
 }
 
 #[derive(Debug, FromQueryResult)]
@@ -97,6 +167,43 @@ pub async fn select_by_name<C: ConnectionTrait + TransactionTrait>(
     Ok(model)
 }
 
+/// Loads the runs that are in any of the states, such as the ones in progress when
+/// reconciling runs whose worker is gone.
+pub async fn select_by_states<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    states: &[&str],
+) -> Result<Vec<PipelineRuns>> {
+    debug!("loading pipeline runs with states: {states:?} from the database");
+
+    PipelineRunsEntity::find()
+        .filter(pipeline_runs::Column::State.is_in(states.iter().copied()))
+        .all(conn)
+        .await
+        .inspect(|_| debug!("loaded pipeline runs successfully"))
+        .map_err(|e| {
+            error!("could not load pipeline runs due to: {e}");
+            anyhow!(e)
+        })
+}
+
+/// Loads the runs whose state has been updated since the date.
+pub async fn select_updated_since<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    date: DateTime,
+) -> Result<Vec<PipelineRuns>> {
+    debug!("loading pipeline runs updated since: {date} from the database");
+
+    PipelineRunsEntity::find()
+        .filter(pipeline_runs::Column::DateUpdated.gt(date))
+        .all(conn)
+        .await
+        .inspect(|_| debug!("loaded pipeline runs successfully"))
+        .map_err(|e| {
+            error!("could not load pipeline runs due to: {e}");
+            anyhow!(e)
+        })
+}
+
 pub async fn select_last<C: ConnectionTrait + TransactionTrait>(conn: &C) -> Result<PipelineRuns> {
     debug!("loading the last invoked pipeline from the database");
 
@@ -481,20 +588,40 @@ pub async fn insert<C: ConnectionTrait + TransactionTrait>(
 ) -> Result<()> {
     debug!("inserting new pipeline to the database");
 
+    let txn = conn.begin().await?;
     let active_model = pipeline_runs::ActiveModel {
         id: Set(model.id.to_owned()),
         name: Set(model.name.to_owned()),
         app_user: Set(model.app_user.to_owned()),
+        trigger_id: Set(model.trigger.trigger_id.to_owned()),
+        trigger_type: Set(Some(model.trigger.trigger_type.to_owned())),
         state: Set(PR_STATE_INITIAL.to_owned()),
         date_created: Set(Utc::now().naive_utc()),
         ..Default::default()
     };
 
-    active_model.insert(conn).await.map_err(|e| {
+    active_model.insert(&txn).await.map_err(|e| {
         error!("could not insert pipeline due to: {e}");
         anyhow!(e)
     })?;
 
+    let inputs: Vec<InsertPipelineRunInput> = model
+        .inputs
+        .iter()
+        .map(|kv| InsertPipelineRunInput::new(kv, &model.id))
+        .collect();
+    pipeline_run_inputs::insert_many(&txn, &inputs).await?;
+
+    let env: Vec<InsertPipelineRunEnvironmentVariable> = model
+        .env
+        .iter()
+        .filter(|(name, _)| !pipeline_run_environment_variables::is_sensitive(name))
+        .map(|kv| InsertPipelineRunEnvironmentVariable::new(kv, &model.id))
+        .collect();
+    pipeline_run_environment_variables::insert_many(&txn, &env).await?;
+
+    txn.commit().await?;
+
     debug!(
         "created new pipeline run entry for id: {}, name: {}, user: {}",
         model.id, model.name, model.app_user
@@ -508,6 +635,8 @@ pub async fn update_state<C: ConnectionTrait + TransactionTrait>(
     state: &str,
 ) -> Result<PipelineRuns> {
     debug!("updating pipeline id: {id} with values state: {state}");
+    let txn = conn.begin().await?;
+    let previous = select_by_id(&txn, id).await?;
     let current_date = Utc::now().naive_utc();
     let mut update_statement = PipelineRunsEntity::update_many()
         .col_expr(pipeline_runs::Column::State, Expr::value(state))
@@ -516,14 +645,14 @@ pub async fn update_state<C: ConnectionTrait + TransactionTrait>(
             Expr::value(current_date),
         );
 
-    if state == PR_STATE_FINISHED || state == PR_STATE_FAULTED {
+    if is_completed(state) {
         update_statement =
             update_statement.col_expr(pipeline_runs::Column::EndDate, Expr::value(current_date));
     }
 
     update_statement
         .filter(pipeline_runs::Column::Id.eq(id))
-        .exec(conn)
+        .exec(&txn)
         .await
         .map(|_| {
             debug!("updated pipeline successfully");
@@ -533,9 +662,51 @@ pub async fn update_state<C: ConnectionTrait + TransactionTrait>(
             anyhow!(e)
         })?;
 
+    // the completion triggers are evaluated only once, when the run first reaches a
+    // final state, so that marking an already completed run as faulted is a no-op.
+    if is_completed(state) && !is_completed(&previous.state) {
+        completion_trigger_events::insert_for_run(&txn, &previous.name, id, state).await?;
+    }
+
+    txn.commit().await?;
     select_by_id(conn, id).await
 }
 
+/// Updates the state of a run to the one that it has on the leader of a raft group. Unlike
+/// `update_state` it doesn't record the completion trigger events of the run, since they
+/// are dispatched by the leader.
+pub async fn update_replicated_state<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    id: &str,
+    state: &str,
+) -> Result<()> {
+    debug!("updating pipeline id: {id} with replicated state: {state}");
+    let current_date = Utc::now().naive_utc();
+    let mut update_statement = PipelineRunsEntity::update_many()
+        .col_expr(pipeline_runs::Column::State, Expr::value(state))
+        .col_expr(
+            pipeline_runs::Column::DateUpdated,
+            Expr::value(current_date),
+        );
+
+    if is_completed(state) {
+        update_statement =
+            update_statement.col_expr(pipeline_runs::Column::EndDate, Expr::value(current_date));
+    }
+
+    update_statement
+        .filter(pipeline_runs::Column::Id.eq(id))
+        .exec(conn)
+        .await
+        .map(|_| {
+            debug!("updated pipeline successfully");
+        })
+        .map_err(|e| {
+            error!("could not update pipeline run due to: {e}");
+            anyhow!(e)
+        })
+}
+
 pub async fn update_start_date<C: ConnectionTrait + TransactionTrait>(
     conn: &C,
     id: &str,
@@ -555,3 +726,51 @@ pub async fn update_start_date<C: ConnectionTrait + TransactionTrait>(
             anyhow!(e)
         })
 }
+
+pub async fn update_waiting_on_group<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    id: &str,
+    group: Option<&str>,
+) -> Result<()> {
+    debug!("updating pipeline run {id} with waiting on group {group:?}");
+    PipelineRunsEntity::update_many()
+        .col_expr(
+            pipeline_runs::Column::WaitingOnGroup,
+            Expr::value(group.map(str::to_owned)),
+        )
+        .filter(pipeline_runs::Column::Id.eq(id))
+        .exec(conn)
+        .await
+        .map(|_| {
+            debug!("updated pipeline run waiting on group successfully");
+        })
+        .map_err(|e| {
+            error!("couldn't update pipeline run's waiting on group due to {e}");
+            anyhow!(e)
+        })
+}
+
+/// Marks the run as faulted along with the reason, unless it has already completed.
+pub async fn update_as_faulted<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    id: &str,
+    reason: &str,
+) -> Result<()> {
+    debug!("updating pipeline run {id} as faulted due to: {reason}");
+    let run = select_by_id(conn, id).await?;
+    if is_completed(&run.state) {
+        return Ok(());
+    }
+
+    PipelineRunsEntity::update_many()
+        .col_expr(pipeline_runs::Column::FaultReason, Expr::value(reason))
+        .filter(pipeline_runs::Column::Id.eq(id))
+        .exec(conn)
+        .await
+        .map_err(|e| {
+            error!("couldn't update pipeline run's fault reason due to {e}");
+            anyhow!(e)
+        })?;
+
+    update_state(conn, id, PR_STATE_FAULTED).await.map(|_| ())
+}
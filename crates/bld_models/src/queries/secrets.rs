@@ -0,0 +1,117 @@
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel,
+    QueryFilter, QueryOrder, TransactionTrait,
+};
+use tracing::{debug, error};
+
+pub use crate::generated::secrets::Model as Secret;
+use crate::generated::secrets::{self, Entity as SecretsEntity};
+
+pub struct InsertSecret {
+    pub name: String,
+    pub value: String,
+}
+
+pub async fn select_all<C: ConnectionTrait + TransactionTrait>(conn: &C) -> Result<Vec<Secret>> {
+    debug!("loading all secrets from the database");
+
+    SecretsEntity::find()
+        .order_by_asc(secrets::Column::Name)
+        .all(conn)
+        .await
+        .inspect(|_| debug!("loaded all secrets successfully"))
+        .map_err(|e| {
+            error!("could not load secrets due to: {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn select_by_name<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    name: &str,
+) -> Result<Secret> {
+    debug!("loading secret with name: {name}");
+
+    SecretsEntity::find()
+        .filter(secrets::Column::Name.eq(name))
+        .one(conn)
+        .await
+        .map_err(|e| {
+            error!("could not load secret due to: {e}");
+            anyhow!(e)
+        })?
+        .ok_or_else(|| {
+            error!("couldn't load secret. Not found");
+            anyhow!("secret not found")
+        })
+}
+
+/// Inserts a new secret or replaces the value of an existing one with the same name.
+/// The value is expected to already be encrypted.
+pub async fn upsert<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    model: InsertSecret,
+) -> Result<Secret> {
+    debug!("upserting secret with name: {}", model.name);
+
+    let existing = SecretsEntity::find()
+        .filter(secrets::Column::Name.eq(&model.name))
+        .one(conn)
+        .await
+        .map_err(|e| {
+            error!("could not load secret due to: {e}");
+            anyhow!(e)
+        })?;
+
+    let result = match existing {
+        Some(secret) => {
+            let mut active_model = secret.into_active_model();
+            active_model.value = Set(model.value);
+            active_model.date_updated = Set(Some(Utc::now().naive_utc()));
+            active_model.update(conn).await
+        }
+        None => {
+            let active_model = secrets::ActiveModel {
+                id: Set(uuid::Uuid::new_v4().to_string()),
+                name: Set(model.name),
+                value: Set(model.value),
+                date_created: Set(Utc::now().naive_utc()),
+                ..Default::default()
+            };
+            active_model.insert(conn).await
+        }
+    };
+
+    result
+        .inspect(|_| debug!("upserted secret successfully"))
+        .map_err(|e| {
+            error!("could not upsert secret due to: {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn delete_by_name<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    name: &str,
+) -> Result<()> {
+    debug!("deleting secret with name: {name}");
+
+    let result = SecretsEntity::delete_many()
+        .filter(secrets::Column::Name.eq(name))
+        .exec(conn)
+        .await
+        .map_err(|e| {
+            error!("could not delete secret due to: {e}");
+            anyhow!(e)
+        })?;
+
+    if result.rows_affected == 0 {
+        error!("couldn't delete secret. Not found");
+        return Err(anyhow!("secret not found"));
+    }
+
+    debug!("deleted secret successfully");
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use crate::generated::webhook_trigger_inputs::{self, Entity as WebhookTriggerInputEntity};
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, TransactionTrait,
+};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+pub use crate::generated::webhook_trigger_inputs::Model as WebhookTriggerInput;
+
+pub struct InsertWebhookTriggerInput {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    pub trigger_id: String,
+}
+
+impl InsertWebhookTriggerInput {
+    pub fn new(kv: (&String, &String), trigger_id: &str) -> Self {
+        let id = Uuid::new_v4().to_string();
+        let (name, path) = kv;
+        Self {
+            id,
+            name: name.to_owned(),
+            path: path.to_owned(),
+            trigger_id: trigger_id.to_owned(),
+        }
+    }
+}
+
+pub async fn select_by_trigger_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    trigger_id: &str,
+) -> Result<Vec<WebhookTriggerInput>> {
+    debug!("loading all inputs for webhook trigger with id: {trigger_id}");
+    WebhookTriggerInputEntity::find()
+        .filter(webhook_trigger_inputs::Column::TriggerId.eq(trigger_id))
+        .all(conn)
+        .await
+        .inspect(|_| {
+            debug!("loaded webhook trigger inputs successfully");
+        })
+        .map_err(|e| {
+            error!("couldn't load webhook trigger inputs due to {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn insert_many<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    models: &[InsertWebhookTriggerInput],
+) -> Result<()> {
+    if models.is_empty() {
+        return Ok(());
+    }
+
+    let models: Vec<webhook_trigger_inputs::ActiveModel> = models
+        .iter()
+        .map(|m| webhook_trigger_inputs::ActiveModel {
+            id: Set(m.id.to_owned()),
+            name: Set(m.name.to_owned()),
+            path: Set(m.path.to_owned()),
+            trigger_id: Set(m.trigger_id.to_owned()),
+            date_created: Set(Utc::now().naive_utc()),
+        })
+        .collect();
+
+    WebhookTriggerInputEntity::insert_many(models)
+        .exec(conn)
+        .await
+        .map(|_| {
+            debug!("created new webhook trigger input successfully");
+        })
+        .map_err(|e| {
+            error!("couldn't insert webhook trigger input due to {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn delete_by_trigger_id<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    trigger_id: &str,
+) -> Result<()> {
+    debug!("deleting webhook trigger inputs associated with webhook trigger id: {trigger_id}");
+    WebhookTriggerInputEntity::delete_many()
+        .filter(webhook_trigger_inputs::Column::TriggerId.eq(trigger_id))
+        .exec(conn)
+        .await
+        .map(|_| {
+            debug!("deleted all webhook trigger inputs successfully");
+        })
+        .map_err(|e| {
+            error!("couldn't delete webhook trigger inputs due to {e}");
+            anyhow!(e)
+        })
+}
@@ -0,0 +1,235 @@
+use anyhow::{Result, anyhow};
+use bld_migrations::Expr;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, TransactionTrait,
+};
+use tracing::{debug, error};
+
+use crate::generated::webhook_triggers::{self, Entity as WebhookTriggerEntity};
+
+use super::webhook_trigger_inputs::{self, InsertWebhookTriggerInput};
+
+pub use crate::generated::webhook_triggers::Model as WebhookTrigger;
+
+const FILTERS_SEPARATOR: &str = ",";
+
+pub struct InsertWebhookTrigger {
+    pub id: String,
+    pub name: String,
+    pub pipeline_id: String,
+    pub secret: String,
+    pub branches: Vec<String>,
+    pub paths: Vec<String>,
+}
+
+pub struct UpdateWebhookTrigger {
+    pub id: String,
+    pub secret: Option<String>,
+    pub branches: Vec<String>,
+    pub paths: Vec<String>,
+}
+
+fn join_filters(filters: &[String]) -> Option<String> {
+    if filters.is_empty() {
+        None
+    } else {
+        Some(filters.join(FILTERS_SEPARATOR))
+    }
+}
+
+fn split_filters(filters: Option<&str>) -> Vec<String> {
+    filters
+        .map(|x| x.split(FILTERS_SEPARATOR).map(str::to_owned).collect())
+        .unwrap_or_default()
+}
+
+/// The branch patterns that a push must match for the trigger to start a run.
+pub fn branches(trigger: &WebhookTrigger) -> Vec<String> {
+    split_filters(trigger.branches.as_deref())
+}
+
+/// The path patterns that at least one changed file must match for the trigger to
+/// start a run.
+pub fn paths(trigger: &WebhookTrigger) -> Vec<String> {
+    split_filters(trigger.paths.as_deref())
+}
+
+pub async fn select_all<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+) -> Result<Vec<WebhookTrigger>> {
+    debug!("loading all webhook triggers from the database");
+    WebhookTriggerEntity::find()
+        .order_by_asc(webhook_triggers::Column::Name)
+        .all(conn)
+        .await
+        .inspect(|_| debug!("loaded all webhook triggers successfully"))
+        .map_err(|e| {
+            error!("couldn't load webhook triggers due to {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn select_by_name<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    name: &str,
+) -> Result<WebhookTrigger> {
+    debug!("loading webhook trigger with name: {name}");
+    WebhookTriggerEntity::find()
+        .filter(webhook_triggers::Column::Name.eq(name))
+        .one(conn)
+        .await
+        .map_err(|e| {
+            error!("couldn't load webhook trigger due to {e}");
+            anyhow!(e)
+        })?
+        .ok_or_else(|| {
+            error!("couldn't load webhook trigger due to not found");
+            anyhow!("webhook trigger not found")
+        })
+        .inspect(|_| debug!("loaded webhook trigger successfully"))
+}
+
+pub async fn select_by_pipeline<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    pipeline_id: &str,
+) -> Result<Vec<WebhookTrigger>> {
+    debug!("loading webhook triggers associated with pipeline: {pipeline_id}");
+    WebhookTriggerEntity::find()
+        .filter(webhook_triggers::Column::PipelineId.eq(pipeline_id))
+        .all(conn)
+        .await
+        .inspect(|_| debug!("loaded webhook triggers successfully"))
+        .map_err(|e| {
+            error!("couldn't load webhook triggers due to {e}");
+            anyhow!(e)
+        })
+}
+
+pub async fn insert<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    model: &InsertWebhookTrigger,
+    inputs: &[InsertWebhookTriggerInput],
+) -> Result<()> {
+    debug!(
+        "inserting new webhook trigger with name: {} for pipeline_id: {}",
+        model.name, model.pipeline_id
+    );
+    let txn = conn.begin().await?;
+
+    let active_model = webhook_triggers::ActiveModel {
+        id: Set(model.id.to_owned()),
+        name: Set(model.name.to_owned()),
+        pipeline_id: Set(model.pipeline_id.to_owned()),
+        secret: Set(model.secret.to_owned()),
+        branches: Set(join_filters(&model.branches)),
+        paths: Set(join_filters(&model.paths)),
+        date_created: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    };
+
+    active_model.insert(&txn).await.map_err(|e| {
+        error!("couldn't insert webhook trigger due to {e}");
+        anyhow!(e)
+    })?;
+
+    webhook_trigger_inputs::insert_many(&txn, inputs).await?;
+
+    txn.commit().await?;
+    debug!("created webhook trigger successfully");
+    Ok(())
+}
+
+/// Updates the filters and input mappings of a trigger, along with its secret when a new
+/// one is provided.
+pub async fn update<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    model: &UpdateWebhookTrigger,
+    inputs: &[InsertWebhookTriggerInput],
+) -> Result<()> {
+    debug!("updating webhook trigger with id: {}", model.id);
+    let txn = conn.begin().await?;
+
+    let mut update_statement = WebhookTriggerEntity::update_many()
+        .col_expr(
+            webhook_triggers::Column::Branches,
+            Expr::value(join_filters(&model.branches)),
+        )
+        .col_expr(
+            webhook_triggers::Column::Paths,
+            Expr::value(join_filters(&model.paths)),
+        )
+        .col_expr(
+            webhook_triggers::Column::DateUpdated,
+            Expr::value(Utc::now().naive_utc()),
+        );
+
+    if let Some(secret) = model.secret.as_deref() {
+        update_statement = update_statement.col_expr(
+            webhook_triggers::Column::Secret,
+            Expr::value(secret.to_owned()),
+        );
+    }
+
+    update_statement
+        .filter(webhook_triggers::Column::Id.eq(&model.id))
+        .exec(&txn)
+        .await
+        .map_err(|e| {
+            error!("couldn't update webhook trigger due to {e}");
+            anyhow!(e)
+        })?;
+
+    webhook_trigger_inputs::delete_by_trigger_id(&txn, &model.id).await?;
+    webhook_trigger_inputs::insert_many(&txn, inputs).await?;
+
+    txn.commit().await?;
+    debug!("updated webhook trigger successfully");
+    Ok(())
+}
+
+pub async fn delete_by_name<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    name: &str,
+) -> Result<()> {
+    debug!("deleting webhook trigger with name: {name}");
+    let txn = conn.begin().await?;
+    let model = select_by_name(&txn, name).await?;
+    webhook_trigger_inputs::delete_by_trigger_id(&txn, &model.id).await?;
+
+    WebhookTriggerEntity::delete_by_id(model.id)
+        .exec(&txn)
+        .await
+        .map_err(|e| {
+            error!("couldn't delete webhook trigger due to {e}");
+            anyhow!(e)
+        })?;
+
+    txn.commit().await?;
+    debug!("deleted webhook trigger successfully");
+    Ok(())
+}
+
+pub async fn delete_by_pipeline<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    pipeline_id: &str,
+) -> Result<()> {
+    debug!("deleting webhook triggers associated with pipeline id: {pipeline_id}");
+    let txn = conn.begin().await?;
+
+    for model in select_by_pipeline(&txn, pipeline_id).await? {
+        webhook_trigger_inputs::delete_by_trigger_id(&txn, &model.id).await?;
+        WebhookTriggerEntity::delete_by_id(model.id)
+            .exec(&txn)
+            .await
+            .map_err(|e| {
+                error!("couldn't delete webhook trigger due to {e}");
+                anyhow!(e)
+            })?;
+    }
+
+    txn.commit().await?;
+    debug!("deleted webhook triggers successfully");
+    Ok(())
+}
@@ -0,0 +1,258 @@
+use std::{path::Path, sync::Arc};
+
+use anyhow::{Result, anyhow, bail};
+use bld_config::BldConfig;
+use git2::{BranchType, Commit, Repository, SubmoduleUpdateOptions, build::RepoBuilder};
+use tokio::{process::Command, task::spawn_blocking};
+
+use crate::{PackageManager, manager::RepositoryUrl};
+
+/// The options for checking out a git repository to a local path.
+#[derive(Debug, Clone, Default)]
+pub struct GitCheckout {
+    pub repository: String,
+    pub reference: Option<String>,
+    pub depth: Option<u32>,
+    pub submodules: bool,
+    pub lfs: bool,
+}
+
+impl GitCheckout {
+    /// The directory name of the repository, meaning the last segment of its url without the `.git` suffix.
+    pub fn name(&self) -> Result<String> {
+        let name = self
+            .repository
+            .trim_end_matches('/')
+            .rsplit(['/', ':'])
+            .next()
+            .map(|x| x.trim_end_matches(".git"))
+            .unwrap_or_default();
+        if name.is_empty() || name == "." || name == ".." {
+            bail!("unable to deduce repository name for {}", self.repository);
+        }
+        Ok(name.to_string())
+    }
+}
+
+fn resolve_reference<'a>(
+    repository: &'a Repository,
+    reference: &str,
+) -> Result<(Commit<'a>, bool)> {
+    if let Ok(obj) = repository.revparse_single(&format!("refs/remotes/origin/{reference}")) {
+        return Ok((obj.peel_to_commit()?, true));
+    }
+
+    repository
+        .revparse_single(&format!("refs/tags/{reference}"))
+        .or_else(|_| repository.revparse_single(reference))
+        .and_then(|obj| obj.peel_to_commit())
+        .map(|commit| (commit, false))
+        .map_err(|_| anyhow!("Unable to find branch, tag or commit '{reference}' in repository"))
+}
+
+fn update_submodules(config: &Arc<BldConfig>, repository: &Repository) -> Result<()> {
+    for mut submodule in repository.submodules()? {
+        let url = submodule
+            .url()
+            .ok_or_else(|| anyhow!("submodule {} has no valid url", submodule.path().display()))?;
+        let url = RepositoryUrl::new(url.to_string())?;
+        let mut options = SubmoduleUpdateOptions::new();
+        options.fetch(PackageManager::repo_fetch_options(config.clone(), &url));
+        submodule.update(true, Some(&mut options))?;
+        update_submodules(config, &submodule.open()?)?;
+    }
+    Ok(())
+}
+
+fn checkout_blocking(
+    config: Arc<BldConfig>,
+    checkout: &GitCheckout,
+    path: &Path,
+) -> Result<String> {
+    let url = RepositoryUrl::new(checkout.repository.clone())?;
+    let mut fetch_options = PackageManager::repo_fetch_options(config.clone(), &url);
+    if let Some(depth) = checkout.depth {
+        fetch_options.depth(i32::try_from(depth)?);
+    }
+
+    let mut builder = RepoBuilder::new();
+    builder.fetch_options(fetch_options);
+    let repository = builder.clone(url.raw(), path)?;
+
+    let commit_id = match checkout.reference.as_deref() {
+        Some(reference) => {
+            let (commit, is_branch) = resolve_reference(&repository, reference)?;
+            repository.checkout_tree(commit.as_object(), None)?;
+
+            if is_branch {
+                if repository
+                    .find_branch(reference, BranchType::Local)
+                    .is_err()
+                {
+                    repository.branch(reference, &commit, false)?;
+                }
+                repository.set_head(&format!("refs/heads/{reference}"))?;
+            } else {
+                repository.set_head_detached(commit.id())?;
+            }
+
+            commit.id()
+        }
+        None => repository.head()?.peel_to_commit()?.id(),
+    };
+
+    if checkout.submodules {
+        update_submodules(&config, &repository)?;
+    }
+
+    Ok(commit_id.to_string())
+}
+
+async fn lfs_pull(path: &Path) -> Result<()> {
+    for args in [["lfs", "install", "--local"], ["lfs", "pull", "origin"]] {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(path)
+            .output()
+            .await
+            .map_err(|e| {
+                anyhow!(
+                    "unable to run git {}, is git-lfs installed? {e}",
+                    args.join(" ")
+                )
+            })?;
+
+        if !output.status.success() {
+            bail!(
+                "git {} failed due to {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+    }
+    Ok(())
+}
+
+impl PackageManager {
+    /// Clones the repository to the provided path using the same credentials
+    /// as packages and checks out the requested branch, tag or commit. LFS objects
+    /// are pulled using the git-lfs executable of the host. Returns the SHA of the
+    /// checked out commit.
+    pub async fn checkout(&self, checkout: &GitCheckout, path: &Path) -> Result<String> {
+        let config = self.config.clone();
+        let checkout_clone = checkout.clone();
+        let target = path.to_path_buf();
+        let sha =
+            spawn_blocking(move || checkout_blocking(config, &checkout_clone, &target)).await??;
+
+        if checkout.lfs {
+            lfs_pull(path).await?;
+        }
+
+        Ok(sha)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+    use std::{fs, path::PathBuf};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bld_pkg_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn commit_file(repository: &Repository, content: &str) -> git2::Oid {
+        let workdir = repository.workdir().unwrap();
+        fs::write(workdir.join("file.txt"), content).unwrap();
+        let mut index = repository.index().unwrap();
+        index.add_path(Path::new("file.txt")).unwrap();
+        index.write().unwrap();
+        let tree = repository.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("bld", "bld@example.com").unwrap();
+        let parent = repository.head().ok().and_then(|x| x.peel_to_commit().ok());
+        let parents: Vec<&Commit> = parent.iter().collect();
+        repository
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                content,
+                &tree,
+                &parents,
+            )
+            .unwrap()
+    }
+
+    fn test_manager() -> PackageManager {
+        PackageManager::new(Arc::new(BldConfig::default()))
+    }
+
+    #[test]
+    fn name_strips_git_suffix_from_https_and_ssh_urls() {
+        let https = GitCheckout {
+            repository: "https://example.com/org/repo.git".to_string(),
+            ..Default::default()
+        };
+        let ssh = GitCheckout {
+            repository: "git@example.com:org/other.git".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(https.name().unwrap(), "repo");
+        assert_eq!(ssh.name().unwrap(), "other");
+    }
+
+    #[test]
+    fn name_rejects_urls_without_a_repository_name() {
+        let checkout = GitCheckout {
+            repository: "..".to_string(),
+            ..Default::default()
+        };
+        assert!(checkout.name().is_err());
+    }
+
+    #[tokio::test]
+    async fn checkout_resolves_tags_and_default_branch() {
+        let dir = temp_dir("checkout_tags");
+        let origin = Repository::init(dir.join("origin")).unwrap();
+        let first = commit_file(&origin, "first");
+        origin
+            .tag_lightweight("v1", &origin.find_object(first, None).unwrap(), false)
+            .unwrap();
+        let second = commit_file(&origin, "second");
+        let manager = test_manager();
+
+        let mut checkout = GitCheckout {
+            repository: dir.join("origin").display().to_string(),
+            reference: Some("v1".to_string()),
+            ..Default::default()
+        };
+        let sha = manager.checkout(&checkout, &dir.join("tag")).await.unwrap();
+        assert_eq!(sha, first.to_string());
+        assert_eq!(
+            fs::read_to_string(dir.join("tag").join("file.txt")).unwrap(),
+            "first"
+        );
+
+        checkout.reference = None;
+        let sha = manager
+            .checkout(&checkout, &dir.join("head"))
+            .await
+            .unwrap();
+        assert_eq!(sha, second.to_string());
+
+        checkout.reference = Some("missing".to_string());
+        assert!(
+            manager
+                .checkout(&checkout, &dir.join("missing"))
+                .await
+                .is_err()
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,3 +1,5 @@
+mod checkout;
 mod manager;
 
+pub use checkout::*;
 pub use manager::*;
@@ -19,13 +19,27 @@ struct RepositoryBranch {
 }
 
 #[derive(Clone)]
-enum RepositoryUrl {
+pub(crate) enum RepositoryUrl {
     Ssh { raw: String, host: String },
     Http { raw: String },
 }
 
 impl RepositoryUrl {
-    fn raw(&self) -> &str {
+    pub(crate) fn new(url: String) -> Result<Self> {
+        if url.starts_with("git@") {
+            let host = url
+                .replace("git@", "")
+                .rsplit_once(":")
+                .ok_or_else(|| anyhow!("unable to deduce host"))?
+                .0
+                .to_string();
+            Ok(Self::Ssh { raw: url, host })
+        } else {
+            Ok(Self::Http { raw: url })
+        }
+    }
+
+    pub(crate) fn raw(&self) -> &str {
         match self {
             Self::Ssh { raw, .. } | Self::Http { raw } => raw,
         }
@@ -40,7 +54,7 @@ struct RepositoryInfo {
 }
 
 pub struct PackageManager {
-    config: Arc<BldConfig>,
+    pub(crate) config: Arc<BldConfig>,
 }
 
 impl PackageManager {
@@ -83,20 +97,8 @@ impl PackageManager {
         let name = name.to_string();
         Self::validate_path_segment(&name)?;
 
-        let repo_url = if url.starts_with("git@") {
-            let host = url
-                .replace("git@", "")
-                .rsplit_once(":")
-                .ok_or_else(|| anyhow!("unable to deduce host"))?
-                .0
-                .to_string();
-            RepositoryUrl::Ssh { raw: url, host }
-        } else {
-            RepositoryUrl::Http { raw: url }
-        };
-
         Ok(RepositoryInfo {
-            url: repo_url,
+            url: RepositoryUrl::new(url)?,
             name,
             branch,
         })
@@ -127,16 +129,16 @@ impl PackageManager {
         }
     }
 
-    fn repo_fetch_options<'a>(
+    pub(crate) fn repo_fetch_options<'a>(
         config: Arc<BldConfig>,
-        info: &'a RepositoryInfo,
+        url: &'a RepositoryUrl,
     ) -> FetchOptions<'a> {
         let mut callbacks = RemoteCallbacks::new();
 
         callbacks.credentials(move |_url, username_from_url, _allowed_types| {
             let user = username_from_url.unwrap_or("git");
 
-            if let RepositoryUrl::Ssh { host: ssh_host, .. } = &info.url {
+            if let RepositoryUrl::Ssh { host: ssh_host, .. } = url {
                 let ssh = &config.local.ssh;
 
                 let Some(ssh_config) = ssh.iter().find(|x| &x.1.host == ssh_host).map(|x| x.1)
@@ -158,7 +160,7 @@ impl PackageManager {
 
     fn repo_builder<'a>(config: Arc<BldConfig>, info: &'a RepositoryInfo) -> RepoBuilder<'a> {
         let mut builder = RepoBuilder::new();
-        builder.fetch_options(Self::repo_fetch_options(config, info));
+        builder.fetch_options(Self::repo_fetch_options(config, &info.url));
         builder
     }
 
@@ -171,7 +173,7 @@ impl PackageManager {
         let repo = Repository::open(path)?;
         {
             let mut remote = repo.find_remote("origin")?;
-            let mut fetch_options = Self::repo_fetch_options(config, &info);
+            let mut fetch_options = Self::repo_fetch_options(config, &info.url);
             remote.fetch::<&str>(&[], Some(&mut fetch_options), None)?;
         }
         Ok(repo)
@@ -496,7 +498,7 @@ mod tests {
             userauth: SshUserAuth::Agent,
         };
         let cred = PackageManager::ssh_credentials(&ssh_config, "git").unwrap();
-        assert!(git2::CredentialType::from_bits_truncate(cred.credtype() as u32).is_ssh_key());
+        assert!(git2::CredentialType::from_bits_truncate(cred.credtype()).is_ssh_key());
     }
 
     #[test]
@@ -510,10 +512,7 @@ mod tests {
             },
         };
         let cred = PackageManager::ssh_credentials(&ssh_config, "git").unwrap();
-        assert!(
-            git2::CredentialType::from_bits_truncate(cred.credtype() as u32)
-                .is_user_pass_plaintext()
-        );
+        assert!(git2::CredentialType::from_bits_truncate(cred.credtype()).is_user_pass_plaintext());
     }
 
     #[test]
@@ -528,6 +527,6 @@ mod tests {
             },
         };
         let cred = PackageManager::ssh_credentials(&ssh_config, "git").unwrap();
-        assert!(git2::CredentialType::from_bits_truncate(cred.credtype() as u32).is_ssh_key());
+        assert!(git2::CredentialType::from_bits_truncate(cred.credtype()).is_ssh_key());
     }
 }
@@ -47,6 +47,10 @@ pub struct Action {
     #[serde(default)]
     pub steps: Vec<Step>,
 
+    /// Steps that run after the main ones whatever their result, for any teardown.
+    #[serde(default, alias = "finally")]
+    pub post: Vec<Step>,
+
     #[serde(default)]
     pub outputs: HashMap<String, Output>,
 }
@@ -81,6 +85,11 @@ impl Action {
         }
     }
 
+    /// The main steps of the action followed by its post steps.
+    pub fn all_steps(&self) -> impl Iterator<Item = &Step> {
+        self.steps.iter().chain(self.post.iter())
+    }
+
     pub fn outputs_map(&self) -> HashMap<String, String> {
         self.outputs
             .iter()
@@ -110,7 +119,7 @@ impl IntoVariables for Action {
 impl<'a> Dependencies<'a> for Action {
     async fn local_deps(&'a self, fs: &FileSystem) -> Vec<Dependency<'a>> {
         let mut dependecies = vec![];
-        for step in &self.steps {
+        for step in self.all_steps() {
             dependecies.append(&mut step.local_deps(fs).await);
         }
         dependecies
@@ -118,7 +127,7 @@ impl<'a> Dependencies<'a> for Action {
 
     async fn remote_deps(&'a self, manager: &PackageManager) -> Vec<Dependency<'a>> {
         let mut dependecies = vec![];
-        for step in &self.steps {
+        for step in self.all_steps() {
             dependecies.append(&mut step.remote_deps(manager).await);
         }
         dependecies
@@ -164,6 +173,13 @@ impl<'a> EvalObject<'a> for Action {
                     .map(|x| ExprValue::Text(ExprText::Ref(x)))
             }
 
+            "secrets" => {
+                let Some(part) = object_parts.next() else {
+                    bail!("expected name of secret in object path");
+                };
+                rctx.get_secret(part.as_span().as_str())
+            }
+
             "matrix" => {
                 let Some(part) = object_parts.next() else {
                     bail!("expected name of matrix variable in object path");
@@ -179,7 +195,7 @@ impl<'a> EvalObject<'a> for Action {
                 };
 
                 let step_id = step_id.as_span().as_str();
-                let Some(step) = self.steps.iter().find(|x| x.is(step_id)) else {
+                let Some(step) = self.all_steps().find(|x| x.is(step_id)) else {
                     bail!("step with id {step_id} not defined");
                 };
 
@@ -247,6 +263,22 @@ impl<'a> Validate<'a> for Action {
         }
         ctx.pop_section();
 
+        if !self.post.is_empty() {
+            debug!("Validating action's post steps");
+            ctx.push_section("post");
+            for step in &self.post {
+                let step_id = step.id();
+                if !step_ids.insert(step_id) {
+                    ctx.push_section(step_id);
+                    ctx.append_error(&format!("Duplicate step id '{step_id}' found in action"));
+                    ctx.pop_section();
+                }
+                step.validate(ctx).await;
+                step.validate_matrix(ctx, None).await;
+            }
+            ctx.pop_section();
+        }
+
         debug!("Validating action's outputs section");
         ctx.push_section("outputs");
         for (name, output) in self.outputs.iter() {
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "all")]
+use {
+    crate::{
+        timeout::v3::validate_timeout,
+        validator::v3::{ExprScope, Validate, ValidatorContext},
+    },
+    tracing::debug,
+};
+
+/// Pauses the run until someone approves or rejects it, in place of the steps of a job.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Approval {
+    /// The users allowed to decide on the approval, or anyone if none are set.
+    #[serde(default)]
+    pub approvers: Vec<String>,
+    pub timeout: Option<String>,
+    pub message: Option<String>,
+}
+
+#[cfg(feature = "all")]
+impl<'a> Validate<'a> for Approval {
+    async fn validate<C: ValidatorContext<'a>>(&'a self, ctx: &mut C) {
+        debug!("Validating approval approvers");
+        ctx.push_section("approvers");
+        if self.approvers.iter().any(|x| x.trim().is_empty()) {
+            ctx.append_error("Approvers must not be empty");
+        }
+        ctx.pop_section();
+
+        if let Some(timeout) = self.timeout.as_deref() {
+            debug!("Validating approval timeout");
+            validate_timeout(ctx, timeout);
+        }
+
+        if let Some(message) = self.message.as_deref() {
+            debug!("Validating approval message");
+            ctx.push_section("message");
+            ctx.validate_expressions(message, ExprScope::Runtime);
+            ctx.pop_section();
+        }
+    }
+}
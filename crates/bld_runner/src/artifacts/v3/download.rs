@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::{retry::v3::Retry, step::v3::ContinueOnErrorValue};
+
 #[cfg(feature = "all")]
 use {
     crate::validator::v3::{ExprScope, Validate, ValidatorContext},
@@ -16,6 +18,8 @@ pub struct DownloadArtifact {
     pub to: String,
     #[serde(rename = "if")]
     pub condition: Option<String>,
+    pub continue_on_error: Option<ContinueOnErrorValue>,
+    pub retry: Option<Retry>,
 }
 
 impl DownloadArtifact {
@@ -31,6 +35,8 @@ impl Default for DownloadArtifact {
             download: String::new(),
             to: String::new(),
             condition: None,
+            continue_on_error: None,
+            retry: None,
         }
     }
 }
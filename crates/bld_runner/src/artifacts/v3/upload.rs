@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::{retry::v3::Retry, step::v3::ContinueOnErrorValue};
+
 #[cfg(feature = "all")]
 use {
     crate::validator::v3::{ExprScope, Validate, ValidatorContext},
@@ -16,6 +18,8 @@ pub struct UploadArtifact {
     pub name: String,
     #[serde(rename = "if")]
     pub condition: Option<String>,
+    pub continue_on_error: Option<ContinueOnErrorValue>,
+    pub retry: Option<Retry>,
 }
 
 impl UploadArtifact {
@@ -31,6 +35,8 @@ impl Default for UploadArtifact {
             upload: String::new(),
             name: String::new(),
             condition: None,
+            continue_on_error: None,
+            retry: None,
         }
     }
 }
@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{retry::v3::Retry, step::v3::ContinueOnErrorValue};
+
+#[cfg(feature = "all")]
+use {
+    crate::validator::v3::{ExprScope, Validate, ValidatorContext},
+    anyhow::Result,
+    tracing::debug,
+};
+
+/// The output of a cache step that tells whether an entry for its exact key was found.
+pub const CACHE_HIT_OUTPUT: &str = "cache_hit";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheOptions {
+    pub key: String,
+    pub paths: Vec<String>,
+    #[serde(default)]
+    pub restore_keys: Vec<String>,
+}
+
+/// A step that restores the paths from the entry of its key, or of the first matching
+/// restore key, and saves them under its key once every step of the job has completed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheStep {
+    #[serde(default = "CacheStep::default_id")]
+    pub id: String,
+    pub name: Option<String>,
+    pub cache: CacheOptions,
+    #[serde(rename = "if")]
+    pub condition: Option<String>,
+    pub continue_on_error: Option<ContinueOnErrorValue>,
+    pub retry: Option<Retry>,
+}
+
+#[cfg(feature = "all")]
+impl CacheOptions {
+    /// Creates the options with every expression replaced by its value.
+    pub fn resolve<F: FnMut(&str) -> Result<String>>(&self, mut eval: F) -> Result<Self> {
+        let key = eval(&self.key)?;
+        let paths = self
+            .paths
+            .iter()
+            .map(|x| eval(x))
+            .collect::<Result<Vec<String>>>()?;
+        let restore_keys = self
+            .restore_keys
+            .iter()
+            .map(|x| eval(x))
+            .collect::<Result<Vec<String>>>()?;
+
+        Ok(Self {
+            key,
+            paths,
+            restore_keys,
+        })
+    }
+}
+
+impl CacheStep {
+    fn default_id() -> String {
+        Uuid::new_v4().to_string()
+    }
+}
+
+impl Default for CacheStep {
+    fn default() -> Self {
+        Self {
+            id: Self::default_id(),
+            name: None,
+            cache: CacheOptions::default(),
+            condition: None,
+            continue_on_error: None,
+            retry: None,
+        }
+    }
+}
+
+#[cfg(feature = "all")]
+impl<'a> Validate<'a> for CacheStep {
+    async fn validate<C: ValidatorContext<'a>>(&'a self, ctx: &mut C) {
+        debug!("Validating cache step {}", self.id);
+
+        if let Some(name) = self.name.as_ref() {
+            debug!("Validating cache's name");
+            ctx.push_section("name");
+            ctx.validate_expressions(name, ExprScope::Runtime);
+            ctx.pop_section();
+        }
+
+        ctx.push_section("cache");
+
+        debug!("Validating cache's key");
+        ctx.push_section("key");
+        if self.cache.key.trim().is_empty() {
+            ctx.append_error("Cache key must not be empty");
+        }
+        ctx.validate_expressions(&self.cache.key, ExprScope::Runtime);
+        ctx.pop_section();
+
+        debug!("Validating cache's paths");
+        ctx.push_section("paths");
+        if self.cache.paths.is_empty() {
+            ctx.append_error("At least one path must be provided");
+        }
+        for path in &self.cache.paths {
+            if path.trim().is_empty() {
+                ctx.append_error("Cache path must not be empty");
+            }
+            ctx.validate_expressions(path, ExprScope::Runtime);
+        }
+        ctx.pop_section();
+
+        debug!("Validating cache's restore keys");
+        ctx.push_section("restore_keys");
+        for restore_key in &self.cache.restore_keys {
+            ctx.validate_expressions(restore_key, ExprScope::Runtime);
+        }
+        ctx.pop_section();
+
+        ctx.pop_section();
+
+        if let Some(condition) = &self.condition {
+            debug!("Validating cache's if condition");
+            ctx.push_section("if");
+            ctx.validate_condition(condition, ExprScope::Runtime);
+            ctx.pop_section();
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{retry::v3::Retry, step::v3::ContinueOnErrorValue};
+
+#[cfg(feature = "all")]
+use {
+    crate::validator::v3::{ExprScope, Validate, ValidatorContext},
+    anyhow::Result,
+    bld_pkg::GitCheckout,
+    tracing::debug,
+};
+
+/// The output of a checkout step with the SHA of the checked out commit.
+pub const CHECKOUT_SHA_OUTPUT: &str = "sha";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CheckoutOptions {
+    pub repository: String,
+    #[serde(rename = "ref")]
+    pub reference: Option<String>,
+    pub depth: Option<u32>,
+    #[serde(default)]
+    pub submodules: bool,
+    #[serde(default)]
+    pub lfs: bool,
+    pub path: Option<String>,
+}
+
+/// A step that clones a git repository using the credentials configured for packages
+/// and checks out a branch, tag or commit of it to a path of the job's platform.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckoutStep {
+    #[serde(default = "CheckoutStep::default_id")]
+    pub id: String,
+    pub name: Option<String>,
+    pub checkout: CheckoutOptions,
+    #[serde(rename = "if")]
+    pub condition: Option<String>,
+    pub continue_on_error: Option<ContinueOnErrorValue>,
+    pub retry: Option<Retry>,
+}
+
+#[cfg(feature = "all")]
+impl CheckoutOptions {
+    /// Creates the checkout with every expression replaced by its value, along with the
+    /// path of the platform to check it out to, which defaults to the repository's name.
+    pub fn resolve<F: FnMut(&str) -> Result<String>>(
+        &self,
+        mut eval: F,
+    ) -> Result<(GitCheckout, String)> {
+        let checkout = GitCheckout {
+            repository: eval(&self.repository)?,
+            reference: self.reference.as_deref().map(&mut eval).transpose()?,
+            depth: self.depth,
+            submodules: self.submodules,
+            lfs: self.lfs,
+        };
+        let path = match self.path.as_deref() {
+            Some(path) => eval(path)?,
+            None => checkout.name()?,
+        };
+        Ok((checkout, path))
+    }
+}
+
+impl CheckoutStep {
+    fn default_id() -> String {
+        Uuid::new_v4().to_string()
+    }
+}
+
+impl Default for CheckoutStep {
+    fn default() -> Self {
+        Self {
+            id: Self::default_id(),
+            name: None,
+            checkout: CheckoutOptions::default(),
+            condition: None,
+            continue_on_error: None,
+            retry: None,
+        }
+    }
+}
+
+#[cfg(feature = "all")]
+impl<'a> Validate<'a> for CheckoutStep {
+    async fn validate<C: ValidatorContext<'a>>(&'a self, ctx: &mut C) {
+        debug!("Validating checkout step {}", self.id);
+
+        if let Some(name) = self.name.as_ref() {
+            debug!("Validating checkout's name");
+            ctx.push_section("name");
+            ctx.validate_expressions(name, ExprScope::Runtime);
+            ctx.pop_section();
+        }
+
+        ctx.push_section("checkout");
+
+        debug!("Validating checkout's repository");
+        ctx.push_section("repository");
+        if self.checkout.repository.trim().is_empty() {
+            ctx.append_error("Checkout repository must not be empty");
+        }
+        ctx.validate_expressions(&self.checkout.repository, ExprScope::Runtime);
+        ctx.pop_section();
+
+        if let Some(reference) = self.checkout.reference.as_deref() {
+            debug!("Validating checkout's ref");
+            ctx.push_section("ref");
+            if reference.trim().is_empty() {
+                ctx.append_error("Checkout ref must not be empty");
+            }
+            ctx.validate_expressions(reference, ExprScope::Runtime);
+            ctx.pop_section();
+        }
+
+        if self.checkout.depth == Some(0) {
+            debug!("Validating checkout's depth");
+            ctx.push_section("depth");
+            ctx.append_error("Checkout depth must be greater than 0");
+            ctx.pop_section();
+        }
+
+        if let Some(path) = self.checkout.path.as_deref() {
+            debug!("Validating checkout's path");
+            ctx.push_section("path");
+            if path.trim().is_empty() {
+                ctx.append_error("Checkout path must not be empty");
+            }
+            ctx.validate_expressions(path, ExprScope::Runtime);
+            ctx.pop_section();
+        }
+
+        ctx.pop_section();
+
+        if let Some(condition) = &self.condition {
+            debug!("Validating checkout's if condition");
+            ctx.push_section("if");
+            ctx.validate_condition(condition, ExprScope::Runtime);
+            ctx.pop_section();
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "all")]
+use {
+    crate::{
+        expr::v3::{
+            context::{CommonReadonlyRuntimeExprContext, START_OF_RUN_WCTX},
+            exec::{CommonExprExecutor, eval_all_expressions},
+            parser,
+        },
+        pipeline::v3::Pipeline,
+        validator::v3::{ExprScope, Validate, ValidatorContext},
+    },
+    anyhow::Result,
+    bld_models::dtos::ConcurrencyGroup,
+};
+
+/// Limits the runs of a pipeline that share the same group to one at a time, with the
+/// rest either waiting for the active run or cancelling it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Concurrency {
+    pub group: String,
+    #[serde(default)]
+    pub cancel_in_progress: bool,
+}
+
+#[cfg(feature = "all")]
+impl Concurrency {
+    /// Evaluates the group of the run, which like every other start of run value can
+    /// only use the inputs, env and properties of the run.
+    pub fn resolve(
+        &self,
+        pipeline: &Pipeline,
+        rctx: &CommonReadonlyRuntimeExprContext,
+    ) -> Result<ConcurrencyGroup> {
+        let regex = parser::new_regex()?;
+        let exec = CommonExprExecutor::new(pipeline, rctx, &START_OF_RUN_WCTX);
+        let name = eval_all_expressions(&exec, &regex, &self.group)?;
+        Ok(ConcurrencyGroup {
+            name,
+            cancel_in_progress: self.cancel_in_progress,
+        })
+    }
+}
+
+#[cfg(feature = "all")]
+impl<'a> Validate<'a> for Concurrency {
+    async fn validate<C: ValidatorContext<'a>>(&'a self, ctx: &mut C) {
+        ctx.push_section("group");
+        if self.group.trim().is_empty() {
+            ctx.append_error("Concurrency group must not be empty");
+        }
+        ctx.validate_expressions(&self.group, ExprScope::StartOfRun);
+        ctx.pop_section();
+    }
+}
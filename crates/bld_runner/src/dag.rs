@@ -68,8 +68,8 @@ impl Dag {
         order
     }
 
-    /// Groups the jobs into execution layers. All jobs within a layer can run in
-    /// parallel and every layer must complete before the next one is started.
+    /// Groups the jobs into layers by their depth in the graph. All jobs within a layer
+    /// are independent of each other and only depend on jobs of previous layers.
     pub fn layers(&self) -> Vec<Vec<String>> {
         let mut in_degree = self.in_degrees();
         let mut current: Vec<usize> = (0..self.nodes.len())
@@ -118,6 +118,21 @@ impl Dag {
         self.edges = reduced;
     }
 
+    /// Creates a scheduler that hands out the jobs of the graph as soon as every job
+    /// they need has completed.
+    #[cfg(feature = "all")]
+    pub fn scheduler(&self) -> DagScheduler<'_> {
+        let in_degree = self.in_degrees();
+        let ready = (0..self.nodes.len())
+            .filter(|&i| in_degree[i] == 0)
+            .collect();
+        DagScheduler {
+            dag: self,
+            in_degree,
+            ready,
+        }
+    }
+
     fn in_degrees(&self) -> Vec<usize> {
         let mut in_degree = vec![0usize; self.nodes.len()];
         for dependents in &self.edges {
@@ -143,6 +158,37 @@ impl Dag {
     }
 }
 
+#[cfg(feature = "all")]
+pub struct DagScheduler<'a> {
+    dag: &'a Dag,
+    in_degree: Vec<usize>,
+    ready: VecDeque<usize>,
+}
+
+#[cfg(feature = "all")]
+impl<'a> DagScheduler<'a> {
+    /// Returns the next job whose needs have all completed, if any.
+    pub fn next_ready(&mut self) -> Option<&'a str> {
+        self.ready
+            .pop_front()
+            .map(|idx| self.dag.nodes[idx].name.as_str())
+    }
+
+    /// Marks a job as completed, making every dependent job that has no other
+    /// pending needs ready to run.
+    pub fn complete(&mut self, name: &str) {
+        let Some(idx) = self.dag.nodes.iter().position(|n| n.name == name) else {
+            return;
+        };
+        for &dependent in &self.dag.edges[idx] {
+            self.in_degree[dependent] -= 1;
+            if self.in_degree[dependent] == 0 {
+                self.ready.push_back(dependent);
+            }
+        }
+    }
+}
+
 #[cfg(feature = "all")]
 impl TryFrom<&Pipeline> for Dag {
     type Error = anyhow::Error;
@@ -484,6 +530,44 @@ mod tests {
         assert_eq!(sorted_layers(&dag), before);
     }
 
+    #[test]
+    fn scheduler_releases_a_job_once_all_of_its_needs_complete() {
+        let dag = Dag::try_from(&pipeline(&[
+            ("a", job(&[])),
+            ("b", job(&[])),
+            ("c", job(&["a"])),
+            ("d", job(&["a", "b"])),
+        ]))
+        .expect("valid");
+
+        let mut scheduler = dag.scheduler();
+        let mut roots = vec![
+            scheduler.next_ready().unwrap(),
+            scheduler.next_ready().unwrap(),
+        ];
+        roots.sort();
+        assert_eq!(roots, vec!["a", "b"]);
+        assert_eq!(scheduler.next_ready(), None);
+
+        // "c" only needs "a" so it is released without waiting for "b".
+        scheduler.complete("a");
+        assert_eq!(scheduler.next_ready(), Some("c"));
+        assert_eq!(scheduler.next_ready(), None);
+
+        scheduler.complete("b");
+        assert_eq!(scheduler.next_ready(), Some("d"));
+        assert_eq!(scheduler.next_ready(), None);
+    }
+
+    #[test]
+    fn scheduler_never_releases_dependents_of_an_incomplete_job() {
+        let dag = Dag::try_from(&pipeline(&[("a", job(&[])), ("b", job(&["a"]))])).expect("valid");
+
+        let mut scheduler = dag.scheduler();
+        assert_eq!(scheduler.next_ready(), Some("a"));
+        assert_eq!(scheduler.next_ready(), None);
+    }
+
     #[test]
     fn reduce_is_idempotent() {
         let mut once = Dag::try_from(&pipeline(&[
@@ -4,16 +4,17 @@ use crate::expr::v3::traits::EvalObject;
 use crate::inputs::v3::Input;
 
 use super::traits::{
-    ExprValue, OutputScope, ReadonlyRuntimeExprContext, WritableRuntimeExprContext,
+    ExprText, ExprValue, OutputScope, ReadonlyRuntimeExprContext, RunStatus,
+    WritableRuntimeExprContext,
 };
-use anyhow::{Context, Error, Result, anyhow};
+use anyhow::{Context, Error, Result, anyhow, bail};
 use bld_config::BldConfig;
 use bld_config::definitions::{
     KEYWORD_BLD_DIR_V3, KEYWORD_PROJECT_DIR_V3, KEYWORD_RUN_PROPS_ID_V3,
     KEYWORD_RUN_PROPS_START_TIME_V3,
 };
 use bld_utils::sync::IntoArc;
-use std::{collections::HashMap, sync::Arc};
+use std::{cell::RefCell, collections::HashMap, sync::Arc};
 
 pub struct StartOfRunWritableExprContext;
 
@@ -21,7 +22,7 @@ pub static START_OF_RUN_WCTX: StartOfRunWritableExprContext = StartOfRunWritable
 
 pub fn out_of_scope(symbol: &str) -> Error {
     anyhow!(
-        "'{symbol}' is not available at the start of a run, only inputs, env, {KEYWORD_BLD_DIR_V3}, {KEYWORD_PROJECT_DIR_V3}, {KEYWORD_RUN_PROPS_ID_V3} and {KEYWORD_RUN_PROPS_START_TIME_V3} can be used here"
+        "'{symbol}' is not available at the start of a run, only inputs, env, secrets, {KEYWORD_BLD_DIR_V3}, {KEYWORD_PROJECT_DIR_V3}, {KEYWORD_RUN_PROPS_ID_V3} and {KEYWORD_RUN_PROPS_START_TIME_V3} can be used here"
     )
 }
 
@@ -48,6 +49,62 @@ impl WritableRuntimeExprContext for StartOfRunWritableExprContext {
     fn get_matrix_value<'a>(&'a self, name: &str) -> Result<&'a str> {
         Err(out_of_scope(&format!("matrix.{name}")))
     }
+
+    fn get_step_outcome<'a>(&'a self, id: &str) -> Result<ExprValue<'a>> {
+        Err(out_of_scope(&format!("steps.{id}.outcome")))
+    }
+
+    fn get_run_status(&self) -> Result<RunStatus> {
+        bail!("status check functions can only be used in the if condition of a job or a step")
+    }
+}
+
+/// The context of a job's condition, which is evaluated before any of the job's steps
+/// have run, so on top of the start of run expressions it only knows the status of the
+/// jobs that it needs.
+pub struct JobConditionWritableExprContext {
+    status: RunStatus,
+}
+
+impl JobConditionWritableExprContext {
+    pub const fn new(status: RunStatus) -> Self {
+        Self { status }
+    }
+}
+
+/// Used to validate job conditions, since the status of the needed jobs is only known
+/// once they have run.
+pub static JOB_CONDITION_VALIDATION_WCTX: JobConditionWritableExprContext =
+    JobConditionWritableExprContext::new(RunStatus::Unknown);
+
+impl WritableRuntimeExprContext for JobConditionWritableExprContext {
+    fn get_exec_id(&self) -> Option<&str> {
+        START_OF_RUN_WCTX.get_exec_id()
+    }
+
+    fn get_output<'a>(&'a self, scope: OutputScope, id: &str, name: &str) -> Result<ExprValue<'a>> {
+        START_OF_RUN_WCTX.get_output(scope, id, name)
+    }
+
+    fn set_output(&mut self, _id: &str, name: String, _value: String) -> Result<()> {
+        Err(out_of_scope(&format!("outputs.{name}")))
+    }
+
+    fn set_outputs(&mut self, _id: &str, _outputs: HashMap<String, String>) -> Result<()> {
+        Err(out_of_scope("outputs"))
+    }
+
+    fn get_matrix_value<'a>(&'a self, name: &str) -> Result<&'a str> {
+        START_OF_RUN_WCTX.get_matrix_value(name)
+    }
+
+    fn get_step_outcome<'a>(&'a self, id: &str) -> Result<ExprValue<'a>> {
+        START_OF_RUN_WCTX.get_step_outcome(id)
+    }
+
+    fn get_run_status(&self) -> Result<RunStatus> {
+        Ok(self.status)
+    }
 }
 
 #[derive(Debug, Default)]
@@ -55,6 +112,9 @@ pub struct CommonReadonlyRuntimeExprContext {
     pub config: Arc<BldConfig>,
     pub inputs: Arc<HashMap<String, String>>,
     pub env: Arc<HashMap<String, String>>,
+    /// The secret values available to the run. A `None` value means the secrets
+    /// aren't known, for example during validation, and any secret evaluates to unknown.
+    pub secrets: Option<Arc<HashMap<String, String>>>,
     pub run_id: String,
     pub run_start_time: String,
 }
@@ -64,6 +124,7 @@ impl CommonReadonlyRuntimeExprContext {
         config: Arc<BldConfig>,
         inputs: Arc<HashMap<String, String>>,
         env: Arc<HashMap<String, String>>,
+        secrets: Option<Arc<HashMap<String, String>>>,
         run_id: String,
         run_start_time: String,
     ) -> Self {
@@ -71,6 +132,7 @@ impl CommonReadonlyRuntimeExprContext {
             config,
             inputs,
             env,
+            secrets,
             run_id,
             run_start_time,
         }
@@ -100,6 +162,16 @@ impl<'a> ReadonlyRuntimeExprContext<'a> for CommonReadonlyRuntimeExprContext {
             .ok_or_else(|| anyhow!("env variable '{name}' not found"))
     }
 
+    fn get_secret(&'a self, name: &'a str) -> Result<ExprValue<'a>> {
+        let Some(secrets) = &self.secrets else {
+            return Ok(ExprValue::Unknown);
+        };
+        secrets
+            .get(name)
+            .map(|x| ExprValue::Text(ExprText::Ref(x.as_str())))
+            .ok_or_else(|| anyhow!("secret '{name}' not found"))
+    }
+
     fn get_run_id(&'a self) -> &'a str {
         &self.run_id
     }
@@ -107,6 +179,91 @@ impl<'a> ReadonlyRuntimeExprContext<'a> for CommonReadonlyRuntimeExprContext {
     fn get_run_start_time(&'a self) -> &'a str {
         &self.run_start_time
     }
+
+    /// The files are only hashed on the platform of a cache step, through a
+    /// FileHashesExprContext, so everywhere else the hash is unknown during validation and
+    /// an error during a run.
+    fn get_file_hash(&'a self, _patterns: &[String]) -> Result<ExprValue<'a>> {
+        if self.secrets.is_none() {
+            return Ok(ExprValue::Unknown);
+        }
+        bail!("hash_files can only be used in the key and restore_keys of a cache step")
+    }
+}
+
+/// The hashes of the files of a platform for the patterns of `hash_files` calls, along
+/// with the patterns that were requested without their hash being known. The files of
+/// a platform can only be read asynchronously, so the expressions are evaluated once to
+/// find the patterns and again after they have been hashed.
+#[derive(Default)]
+pub struct FileHashes {
+    hashes: HashMap<Vec<String>, String>,
+    missing: RefCell<Vec<Vec<String>>>,
+}
+
+impl FileHashes {
+    pub fn insert(&mut self, patterns: Vec<String>, hash: String) {
+        self.hashes.insert(patterns, hash);
+    }
+
+    pub fn take_missing(&self) -> Vec<Vec<String>> {
+        self.missing.take()
+    }
+}
+
+/// A context that evaluates `hash_files` calls with the hashes of the files of a platform
+/// and everything else with the inner context.
+pub struct FileHashesExprContext<'b, R> {
+    inner: &'b R,
+    hashes: &'b FileHashes,
+}
+
+impl<'b, R> FileHashesExprContext<'b, R> {
+    pub fn new(inner: &'b R, hashes: &'b FileHashes) -> Self {
+        Self { inner, hashes }
+    }
+}
+
+impl<'a, 'b: 'a, R: ReadonlyRuntimeExprContext<'a>> ReadonlyRuntimeExprContext<'a>
+    for FileHashesExprContext<'b, R>
+{
+    fn get_root_dir(&'a self) -> &'a str {
+        self.inner.get_root_dir()
+    }
+
+    fn get_project_dir(&'a self) -> &'a str {
+        self.inner.get_project_dir()
+    }
+
+    fn get_input(&'a self, name: &'a str) -> Result<&'a str> {
+        self.inner.get_input(name)
+    }
+
+    fn get_env(&'a self, name: &'a str) -> Result<&'a str> {
+        self.inner.get_env(name)
+    }
+
+    fn get_secret(&'a self, name: &'a str) -> Result<ExprValue<'a>> {
+        self.inner.get_secret(name)
+    }
+
+    fn get_run_id(&'a self) -> &'a str {
+        self.inner.get_run_id()
+    }
+
+    fn get_run_start_time(&'a self) -> &'a str {
+        self.inner.get_run_start_time()
+    }
+
+    fn get_file_hash(&'a self, patterns: &[String]) -> Result<ExprValue<'a>> {
+        match self.hashes.hashes.get(patterns) {
+            Some(hash) => Ok(ExprValue::Text(ExprText::Ref(hash))),
+            None => {
+                self.hashes.missing.borrow_mut().push(patterns.to_vec());
+                Ok(ExprValue::Unknown)
+            }
+        }
+    }
 }
 
 pub struct CommonReadonlyRuntimeExprContextOptions<'a, T: for<'x> EvalObject<'x>> {
@@ -116,6 +273,7 @@ pub struct CommonReadonlyRuntimeExprContextOptions<'a, T: for<'x> EvalObject<'x>
     pub declared_inputs: &'a HashMap<String, Input>,
     pub env: Arc<HashMap<String, String>>,
     pub declared_env: &'a HashMap<String, String>,
+    pub secrets: Arc<HashMap<String, String>>,
     pub run_id: String,
     pub run_start_time: String,
 }
@@ -132,6 +290,7 @@ pub fn expr_rctx<'a, T: for<'x> EvalObject<'x>>(
         options.config.clone(),
         options.inputs.clone(),
         options.env.clone(),
+        Some(options.secrets.clone()),
         options.run_id.clone(),
         options.run_start_time.clone(),
     );
@@ -149,6 +308,7 @@ pub fn expr_rctx<'a, T: for<'x> EvalObject<'x>>(
         options.config.clone(),
         inputs.clone().into_arc(),
         env.clone().into_arc(),
+        Some(options.secrets.clone()),
         options.run_id.clone(),
         options.run_start_time.clone(),
     );
@@ -166,6 +326,7 @@ pub fn expr_rctx<'a, T: for<'x> EvalObject<'x>>(
         options.config,
         inputs.into_arc(),
         env.into_arc(),
+        Some(options.secrets),
         options.run_id,
         options.run_start_time,
     ))
@@ -207,6 +368,7 @@ mod tests {
             declared_inputs: &pipeline.inputs,
             env: HashMap::new().into_arc(),
             declared_env: &pipeline.env,
+            secrets: HashMap::from([("TOKEN".to_string(), "s3cr3t".to_string())]).into_arc(),
             run_id: "run-id".to_string(),
             run_start_time: "start-time".to_string(),
         })
@@ -268,6 +430,31 @@ mod tests {
         assert!(error.contains("unable to resolve inputs.second"), "{error}");
     }
 
+    #[test]
+    pub fn env_referencing_secret_resolve_success() {
+        let mut pipeline = Pipeline::default();
+        pipeline.env.insert(
+            "AUTH".to_string(),
+            "Bearer ${{ secrets.TOKEN }}".to_string(),
+        );
+
+        let rctx = resolve(&pipeline, vec![]).unwrap();
+
+        assert_eq!(rctx.get_env("AUTH").unwrap(), "Bearer s3cr3t");
+    }
+
+    #[test]
+    pub fn missing_secret_resolve_failure() {
+        let mut pipeline = Pipeline::default();
+        pipeline
+            .env
+            .insert("AUTH".to_string(), "${{ secrets.MISSING }}".to_string());
+
+        let error = format!("{:#}", resolve(&pipeline, vec![]).unwrap_err());
+
+        assert!(error.contains("secret 'MISSING' not found"), "{error}");
+    }
+
     #[test]
     pub fn supplied_input_overrides_default_resolve_success() {
         let mut pipeline = Pipeline::default();
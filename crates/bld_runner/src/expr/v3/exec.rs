@@ -1,6 +1,9 @@
 use std::collections::HashMap;
 
-use crate::expr::v3::parser::{ExprParser, Rule};
+use crate::expr::v3::{
+    functions,
+    parser::{ExprParser, Rule},
+};
 
 use super::traits::{
     EvalExpr, EvalObject, ExprText, ExprValue, ReadonlyRuntimeExprContext,
@@ -91,6 +94,44 @@ impl<'a, T: EvalObject<'a>, RCtx: ReadonlyRuntimeExprContext<'a>, WCtx: Writable
         Ok(ExprValue::Array(items))
     }
 
+    fn eval_function(&self, expr: Pair<'a, Rule>) -> Result<ExprValue<'a>> {
+        let Rule::Function = expr.as_rule() else {
+            bail!("expected function rule, found {:?}", expr.as_rule());
+        };
+
+        let mut inner = expr.into_inner();
+        let name = inner
+            .next()
+            .ok_or_else(|| anyhow!("no function name found"))?
+            .as_str();
+
+        let args = inner
+            .map(|arg| self.eval_symbol(arg))
+            .collect::<Result<Vec<ExprValue<'a>>>>()?;
+
+        functions::call(name, args, self.rctx, self.wctx)
+    }
+
+    fn eval_not(&self, expr: Pair<'a, Rule>) -> Result<ExprValue<'a>> {
+        let Rule::Not = expr.as_rule() else {
+            bail!("expected not rule, found {:?}", expr.as_rule());
+        };
+
+        let inner = expr
+            .into_inner()
+            .next()
+            .ok_or_else(|| anyhow!("no value found to negate"))?;
+
+        let value = match inner.as_rule() {
+            Rule::LogicalExpression => self.eval_logical_expr(inner)?,
+            Rule::Expression => self.eval_expr(inner)?,
+            Rule::Symbol => self.eval_symbol(inner)?,
+            _ => bail!("unexpected rule: {:?}", inner.as_rule()),
+        };
+
+        value.try_not()
+    }
+
     fn eval_index(&self, object: Pair<'a, Rule>, value: ExprValue<'a>) -> Result<ExprValue<'a>> {
         let Some(index) = object
             .into_inner()
@@ -210,6 +251,18 @@ impl<'a, T: EvalObject<'a>, RCtx: ReadonlyRuntimeExprContext<'a>, WCtx: Writable
                     .ok_or_else(|| anyhow!("no array found in expression"))?;
                 self.eval_array(array)
             }
+            Rule::Not => {
+                let not = symbol
+                    .next()
+                    .ok_or_else(|| anyhow!("no negation found in expression"))?;
+                self.eval_not(not)
+            }
+            Rule::Function => {
+                let function = symbol
+                    .next()
+                    .ok_or_else(|| anyhow!("no function found in expression"))?;
+                self.eval_function(function)
+            }
             Rule::Object => {
                 let value = self
                     .obj_executor
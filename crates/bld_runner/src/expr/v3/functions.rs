@@ -0,0 +1,555 @@
+use anyhow::{Result, anyhow, bail};
+use pest::Parser;
+use serde_json::{Number as JsonNumber, Value as JsonValue};
+
+use super::{
+    parser::{ExprParser, Rule},
+    traits::{
+        ExprText, ExprValue, ReadonlyRuntimeExprContext, RunStatus, WritableRuntimeExprContext,
+    },
+};
+
+/// The functions that check the status of the jobs or steps that came before.
+const STATUS_FUNCTIONS: [&str; 4] = ["success", "failure", "always", "cancelled"];
+
+/// The kind of value that a function parameter accepts. Numbers and booleans are
+/// accepted as text, using the same text they would be written with.
+#[derive(Clone, Copy)]
+enum Param {
+    Text,
+    Any,
+}
+
+/// The parameters of a function, where the first `required` ones must always be
+/// provided and, for a variadic function, the last one can be repeated.
+struct Signature {
+    params: &'static [Param],
+    required: usize,
+    variadic: bool,
+}
+
+impl Signature {
+    const fn new(params: &'static [Param], required: usize) -> Self {
+        Self {
+            params,
+            required,
+            variadic: false,
+        }
+    }
+
+    const fn variadic(params: &'static [Param], required: usize) -> Self {
+        Self {
+            params,
+            required,
+            variadic: true,
+        }
+    }
+
+    fn check(&self, name: &str, args: &[ExprValue<'_>]) -> Result<()> {
+        let found = args.len();
+        let max = self.params.len();
+        if found < self.required || (!self.variadic && found > max) {
+            let expected = if self.variadic {
+                format!("at least {}", self.required)
+            } else if self.required == max {
+                max.to_string()
+            } else {
+                format!("{} to {max}", self.required)
+            };
+            let last = if self.variadic { self.required } else { max };
+            let suffix = if last == 1 { "" } else { "s" };
+            bail!("{name} expects {expected} argument{suffix}, found {found}");
+        }
+
+        for (i, arg) in args.iter().enumerate() {
+            let param = self.params[i.min(max - 1)];
+            if let (Param::Text, ExprValue::Array(_)) = (param, arg) {
+                bail!(
+                    "{name} expects text for argument {}, found {}",
+                    i + 1,
+                    arg.type_as_string()
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn signature(name: &str) -> Option<Signature> {
+    use Param::*;
+
+    let signature = match name {
+        "contains" => Signature::new(&[Any, Any], 2),
+        "starts_with" | "ends_with" => Signature::new(&[Text, Text], 2),
+        "format" => Signature::variadic(&[Text, Any], 1),
+        "join" => Signature::new(&[Any, Text], 1),
+        "to_json" => Signature::new(&[Any], 1),
+        "from_json" | "lower" | "upper" => Signature::new(&[Text], 1),
+        "replace" => Signature::new(&[Text, Text, Text], 3),
+        "default" => Signature::new(&[Any, Any], 2),
+        "hash_files" => Signature::variadic(&[Text], 1),
+        "success" | "failure" | "always" | "cancelled" => Signature::new(&[], 0),
+        _ => return None,
+    };
+
+    Some(signature)
+}
+
+/// Calls the builtin function with the provided name and already evaluated arguments.
+///
+/// The number and the types of the arguments are checked before anything else, so that
+/// the validator reports invalid calls even when some of the arguments are unknown until
+/// the run has started, in which case the result is unknown as well.
+pub fn call<'a, RCtx: ReadonlyRuntimeExprContext<'a>, WCtx: WritableRuntimeExprContext>(
+    name: &str,
+    args: Vec<ExprValue<'a>>,
+    rctx: &'a RCtx,
+    wctx: &'a WCtx,
+) -> Result<ExprValue<'a>> {
+    let signature = signature(name).ok_or_else(|| anyhow!("unknown function '{name}'"))?;
+    signature.check(name, &args)?;
+
+    if STATUS_FUNCTIONS.contains(&name) {
+        return status(name, wctx.get_run_status()?);
+    }
+
+    if args.iter().any(|x| matches!(x, ExprValue::Unknown)) {
+        return Ok(ExprValue::Unknown);
+    }
+
+    match name {
+        "contains" => contains(&args[0], &args[1]),
+        "starts_with" => Ok(ExprValue::Boolean(
+            text(&args[0]).starts_with(&text(&args[1])),
+        )),
+        "ends_with" => Ok(ExprValue::Boolean(
+            text(&args[0]).ends_with(&text(&args[1])),
+        )),
+        "format" => format_text(&args),
+        "join" => Ok(owned_text(join(&args))),
+        "to_json" => Ok(owned_text(serde_json::to_string(&to_json(&args[0]))?)),
+        "from_json" => from_json(serde_json::from_str(&text(&args[0]))?),
+        "lower" => Ok(owned_text(text(&args[0]).to_lowercase())),
+        "upper" => Ok(owned_text(text(&args[0]).to_uppercase())),
+        "replace" => Ok(owned_text(
+            text(&args[0]).replace(&text(&args[1]), &text(&args[2])),
+        )),
+        "default" => Ok(default(args)),
+        "hash_files" => rctx.get_file_hash(&args.iter().map(text).collect::<Vec<String>>()),
+        _ => bail!("unknown function '{name}'"),
+    }
+}
+
+/// Checks whether a condition calls any of the status functions, in which case it
+/// decides on its own whether its job or step runs after an earlier one has failed.
+pub fn uses_status_check(condition: &str) -> bool {
+    let Ok(pairs) = ExprParser::parse(Rule::Full, condition.trim()) else {
+        return false;
+    };
+    pairs
+        .flatten()
+        .any(|x| x.as_rule() == Rule::FunctionName && STATUS_FUNCTIONS.contains(&x.as_str()))
+}
+
+fn status<'a>(name: &str, status: RunStatus) -> Result<ExprValue<'a>> {
+    let value = match (name, status) {
+        (_, RunStatus::Unknown) => return Ok(ExprValue::Unknown),
+        ("always", _) => true,
+        ("success", status) => status == RunStatus::Success,
+        ("failure", status) => status == RunStatus::Failure,
+        ("cancelled", status) => status == RunStatus::Cancelled,
+        _ => bail!("unknown function '{name}'"),
+    };
+    Ok(ExprValue::Boolean(value))
+}
+
+fn owned_text<'a>(value: String) -> ExprValue<'a> {
+    ExprValue::Text(ExprText::Owned(value))
+}
+
+fn text(value: &ExprValue<'_>) -> String {
+    match value {
+        ExprValue::Text(text) => text.inner().to_owned(),
+        other => other.to_string(),
+    }
+}
+
+fn same_value(left: &ExprValue<'_>, right: &ExprValue<'_>) -> bool {
+    match (left, right) {
+        (ExprValue::Boolean(l), ExprValue::Boolean(r)) => l == r,
+        (ExprValue::Number { value: l, .. }, ExprValue::Number { value: r, .. }) => l == r,
+        (ExprValue::Text(l), ExprValue::Text(r)) => l.inner() == r.inner(),
+        (ExprValue::Array(l), ExprValue::Array(r)) => {
+            l.len() == r.len() && l.iter().zip(r.iter()).all(|(l, r)| same_value(l, r))
+        }
+        _ => false,
+    }
+}
+
+/// Checks whether an array has an item equal to the value, or whether a text has the
+/// value as a substring.
+fn contains<'a>(haystack: &ExprValue<'_>, needle: &ExprValue<'_>) -> Result<ExprValue<'a>> {
+    let found = match (haystack, needle) {
+        (ExprValue::Array(items), _) => items.iter().any(|x| same_value(x, needle)),
+        (_, ExprValue::Array(_)) => bail!("contains cannot search for an array in a text"),
+        _ => text(haystack).contains(&text(needle)),
+    };
+    Ok(ExprValue::Boolean(found))
+}
+
+/// Replaces every `{N}` placeholder of the first argument with the text of the argument
+/// at index N of the rest.
+fn format_text<'a>(args: &[ExprValue<'_>]) -> Result<ExprValue<'a>> {
+    let template = text(&args[0]);
+    let values = &args[1..];
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template.as_str();
+
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let placeholder = after
+            .find('}')
+            .map(|end| (&after[..end], &after[end + 1..]))
+            .filter(|(index, _)| !index.is_empty() && index.chars().all(|c| c.is_ascii_digit()));
+
+        let Some((index, remaining)) = placeholder else {
+            result.push('{');
+            rest = after;
+            continue;
+        };
+
+        let value = index
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| values.get(i))
+            .ok_or_else(|| {
+                anyhow!(
+                    "format has no value for placeholder {{{index}}}, found {} values",
+                    values.len()
+                )
+            })?;
+        result.push_str(&text(value));
+        rest = remaining;
+    }
+
+    result.push_str(rest);
+    Ok(owned_text(result))
+}
+
+/// Joins the items of an array with the separator, which is a comma by default.
+fn join(args: &[ExprValue<'_>]) -> String {
+    let separator = args.get(1).map(text).unwrap_or_else(|| ",".to_owned());
+    match &args[0] {
+        ExprValue::Array(items) => items.iter().map(text).collect::<Vec<_>>().join(&separator),
+        other => text(other),
+    }
+}
+
+fn to_json(value: &ExprValue<'_>) -> JsonValue {
+    match value {
+        ExprValue::Boolean(value) => JsonValue::Bool(*value),
+        ExprValue::Number { value, raw } => raw
+            .inner()
+            .parse::<i64>()
+            .map(JsonNumber::from)
+            .ok()
+            .or_else(|| JsonNumber::from_f64(*value))
+            .map(JsonValue::Number)
+            .unwrap_or(JsonValue::Null),
+        ExprValue::Text(text) => JsonValue::String(text.inner().to_owned()),
+        ExprValue::Array(items) => JsonValue::Array(items.iter().map(to_json).collect()),
+        ExprValue::Unknown => JsonValue::Null,
+    }
+}
+
+fn from_json<'a>(value: JsonValue) -> Result<ExprValue<'a>> {
+    let value = match value {
+        JsonValue::Bool(value) => ExprValue::Boolean(value),
+        JsonValue::Number(number) => ExprValue::Number {
+            value: number
+                .as_f64()
+                .ok_or_else(|| anyhow!("from_json cannot convert number {number}"))?,
+            raw: ExprText::Owned(number.to_string()),
+        },
+        JsonValue::String(value) => owned_text(value),
+        JsonValue::Array(items) => ExprValue::Array(
+            items
+                .into_iter()
+                .map(from_json)
+                .collect::<Result<Vec<ExprValue<'a>>>>()?,
+        ),
+        JsonValue::Null => bail!("from_json does not support null values"),
+        JsonValue::Object(_) => bail!("from_json does not support objects"),
+    };
+    Ok(value)
+}
+
+/// Gives the first argument unless it is an empty text, in which case the second one is
+/// given instead.
+fn default<'a>(args: Vec<ExprValue<'a>>) -> ExprValue<'a> {
+    let mut args = args.into_iter();
+    match (args.next(), args.next()) {
+        (Some(ExprValue::Text(text)), Some(fallback)) if text.inner().is_empty() => fallback,
+        (value, _) => value.unwrap_or(ExprValue::Unknown),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use super::uses_status_check;
+
+    use crate::{
+        expr::v3::{
+            context::{CommonReadonlyRuntimeExprContext, FileHashes, FileHashesExprContext},
+            exec::CommonExprExecutor,
+            traits::{EvalExpr, ExprValue, MockWritableRuntimeExprContext, RunStatus},
+        },
+        pipeline::v3::Pipeline,
+    };
+
+    fn eval_with_hashes(hashes: &FileHashes, expr: &str) -> anyhow::Result<String> {
+        let inner = CommonReadonlyRuntimeExprContext {
+            secrets: Some(Arc::new(HashMap::new())),
+            ..Default::default()
+        };
+        let rctx = FileHashesExprContext::new(&inner, hashes);
+        let wctx = MockWritableRuntimeExprContext::new();
+        let pipeline = Pipeline::default();
+        let exec = CommonExprExecutor::new(&pipeline, &rctx, &wctx);
+        exec.eval(expr).map(|x| x.to_string())
+    }
+
+    #[test]
+    pub fn hash_files_uses_the_hash_of_its_patterns() {
+        let mut hashes = FileHashes::default();
+        hashes.insert(vec!["**/Cargo.lock".to_string()], "abc".to_string());
+        hashes.insert(
+            vec!["**/Cargo.lock".to_string(), "*.toml".to_string()],
+            "def".to_string(),
+        );
+
+        let value = eval_with_hashes(&hashes, "${{ hash_files(\"**/Cargo.lock\") }}").unwrap();
+        assert_eq!(value, "abc");
+        let value = eval_with_hashes(&hashes, "${{ hash_files('**/Cargo.lock', '*.toml') }}");
+        assert_eq!(value.unwrap(), "def");
+        let value = eval_with_hashes(&hashes, "${{ hash_files('**/Cargo.lock') == 'abc' }}");
+        assert_eq!(value.unwrap(), ExprValue::Boolean(true).to_string());
+        assert!(hashes.take_missing().is_empty());
+    }
+
+    #[test]
+    pub fn hash_files_without_a_hash_requests_its_patterns() {
+        let hashes = FileHashes::default();
+
+        let value = eval_with_hashes(&hashes, "${{ hash_files('*.lock') }}").unwrap();
+        assert_eq!(value, ExprValue::Unknown.to_string());
+        assert_eq!(hashes.take_missing(), vec![vec!["*.lock".to_string()]]);
+    }
+
+    #[test]
+    pub fn hash_files_outside_of_a_cache_step_fails() {
+        let rctx = CommonReadonlyRuntimeExprContext {
+            secrets: Some(Arc::new(HashMap::new())),
+            ..Default::default()
+        };
+        let wctx = MockWritableRuntimeExprContext::new();
+        let pipeline = Pipeline::default();
+        let exec = CommonExprExecutor::new(&pipeline, &rctx, &wctx);
+        assert!(exec.eval("${{ hash_files('*.lock') }}").is_err());
+
+        let value = eval("${{ hash_files('*.lock') }}").unwrap();
+        assert_eq!(value, ExprValue::Unknown.to_string());
+    }
+
+    #[test]
+    pub fn function_calls_are_validated() {
+        assert!(eval("${{ hash_files() }}").is_err());
+        assert!(eval("${{ hash_files(['a', 'b']) }}").is_err());
+        assert!(eval("${{ unknown_function('a') }}").is_err());
+    }
+
+    fn eval(expr: &str) -> anyhow::Result<String> {
+        let rctx = CommonReadonlyRuntimeExprContext::default();
+        let wctx = MockWritableRuntimeExprContext::new();
+        let pipeline = Pipeline::default();
+        let exec = CommonExprExecutor::new(&pipeline, &rctx, &wctx);
+        exec.eval(expr).map(|x| x.to_string())
+    }
+
+    #[test]
+    pub fn text_functions_success() {
+        let data = [
+            ("${{ contains('release/1.0', 'release') }}", "true"),
+            ("${{ contains(['alpine', 'debian'], 'ubuntu') }}", "false"),
+            ("${{ contains([1, 2, 3], 2.0) }}", "true"),
+            ("${{ starts_with('release/1.0', 'release/') }}", "true"),
+            ("${{ ends_with('release/1.0', '.1') }}", "false"),
+            ("${{ starts_with(1.80, '1.8') }}", "true"),
+            ("${{ lower('MiXeD') }}", "mixed"),
+            ("${{ upper('MiXeD') }}", "MIXED"),
+            ("${{ replace('a-b-c', '-', '_') }}", "a_b_c"),
+            ("${{ join(['a', 'b', 'c']) }}", "a,b,c"),
+            ("${{ join(['a', 'b', 'c'], ' | ') }}", "a | b | c"),
+            ("${{ join('single', '-') }}", "single"),
+            ("${{ default('', 'fallback') }}", "fallback"),
+            ("${{ default('value', 'fallback') }}", "value"),
+        ];
+
+        for (expr, expected) in data {
+            assert_eq!(eval(expr).unwrap(), expected, "{expr}");
+        }
+    }
+
+    #[test]
+    pub fn format_function_success() {
+        let data = [
+            ("${{ format('{0}-{1}', 'build', 42) }}", "build-42"),
+            ("${{ format('{1}{0}{1}', 'a', 'b') }}", "bab"),
+            ("${{ format('{ {x} {0}', true) }}", "{ {x} true"),
+            ("${{ format('plain') }}", "plain"),
+        ];
+
+        for (expr, expected) in data {
+            assert_eq!(eval(expr).unwrap(), expected, "{expr}");
+        }
+
+        assert!(eval("${{ format('{0} {1}', 'a') }}").is_err());
+    }
+
+    #[test]
+    pub fn json_functions_success() {
+        assert_eq!(eval("${{ to_json(['a', 'b']) }}").unwrap(), r#"["a","b"]"#);
+        assert_eq!(eval("${{ to_json(5) }}").unwrap(), "5");
+        assert_eq!(eval("${{ to_json(1.5) }}").unwrap(), "1.5");
+        assert_eq!(eval("${{ to_json('text') }}").unwrap(), r#""text""#);
+        assert_eq!(
+            eval("${{ contains(from_json('[\"x\", \"y\"]'), 'y') }}").unwrap(),
+            "true"
+        );
+        assert_eq!(eval("${{ from_json('true') }}").unwrap(), "true");
+        assert_eq!(eval("${{ from_json('3') == 3 }}").unwrap(), "true");
+        assert!(eval("${{ from_json('{\"a\": 1}') }}").is_err());
+        assert!(eval("${{ from_json('null') }}").is_err());
+        assert!(eval("${{ from_json('not json') }}").is_err());
+    }
+
+    #[test]
+    pub fn nested_function_calls_success() {
+        assert_eq!(
+            eval("${{ starts_with(lower(format('{0}/{1}', 'Release', '1.0')), 'release/') }}")
+                .unwrap(),
+            "true"
+        );
+    }
+
+    #[test]
+    pub fn not_operator_success() {
+        let data = [
+            ("${{ !true }}", "false"),
+            ("${{ !false }}", "true"),
+            ("${{ !starts_with('main', 'release/') }}", "true"),
+            ("${{ !(1 == 1) }}", "false"),
+            ("${{ !(true && false) }}", "true"),
+            ("${{ !false == true }}", "true"),
+            ("${{ !!true }}", "true"),
+            ("${{ 'a' != 'b' && !false }}", "true"),
+        ];
+
+        for (expr, expected) in data {
+            assert_eq!(eval(expr).unwrap(), expected, "{expr}");
+        }
+
+        assert!(eval("${{ !'text' }}").is_err());
+        assert!(eval("${{ ![true] }}").is_err());
+    }
+
+    #[test]
+    pub fn function_arity_and_types_are_checked() {
+        let data = [
+            (
+                "${{ contains('a') }}",
+                "contains expects 2 arguments, found 1",
+            ),
+            (
+                "${{ lower('a', 'b') }}",
+                "lower expects 1 argument, found 2",
+            ),
+            ("${{ join() }}", "join expects 1 to 2 arguments, found 0"),
+            (
+                "${{ format() }}",
+                "format expects at least 1 argument, found 0",
+            ),
+            (
+                "${{ replace(['a'], 'a', 'b') }}",
+                "replace expects text for argument 1, found array",
+            ),
+            (
+                "${{ join(['a'], ['b']) }}",
+                "join expects text for argument 2, found array",
+            ),
+            (
+                "${{ contains('abc', ['a']) }}",
+                "contains cannot search for an array in a text",
+            ),
+        ];
+
+        for (expr, expected) in data {
+            let error = eval(expr).unwrap_err().to_string();
+            assert_eq!(error, expected, "{expr}");
+        }
+    }
+
+    fn eval_with_status(expr: &str, status: RunStatus) -> anyhow::Result<String> {
+        let rctx = CommonReadonlyRuntimeExprContext::default();
+        let mut wctx = MockWritableRuntimeExprContext::new();
+        wctx.expect_get_run_status().returning(move || Ok(status));
+        let pipeline = Pipeline::default();
+        let exec = CommonExprExecutor::new(&pipeline, &rctx, &wctx);
+        exec.eval(expr).map(|x| x.to_string())
+    }
+
+    #[test]
+    pub fn status_functions_success() {
+        let data = [
+            (RunStatus::Success, ["true", "false", "true", "false"]),
+            (RunStatus::Failure, ["false", "true", "true", "false"]),
+            (RunStatus::Cancelled, ["false", "false", "true", "true"]),
+        ];
+
+        for (status, expected) in data {
+            let actual = ["success", "failure", "always", "cancelled"]
+                .map(|name| eval_with_status(&format!("${{{{ {name}() }}}}"), status).unwrap());
+            assert_eq!(actual, expected, "{status:?}");
+        }
+
+        assert_eq!(
+            eval_with_status("${{ failure() || cancelled() }}", RunStatus::Cancelled).unwrap(),
+            "true"
+        );
+        assert!(eval_with_status("${{ success('a') }}", RunStatus::Success).is_err());
+    }
+
+    #[test]
+    pub fn status_functions_with_unknown_status_are_unknown() {
+        for expr in ["${{ success() }}", "${{ !failure() }}"] {
+            let value = eval_with_status(expr, RunStatus::Unknown).unwrap();
+            assert_eq!(value, ExprValue::Unknown.to_string(), "{expr}");
+        }
+    }
+
+    #[test]
+    pub fn uses_status_check_success() {
+        assert!(uses_status_check("${{ failure() }}"));
+        assert!(uses_status_check(
+            "${{ always() && steps.build.outcome == 'failed' }}"
+        ));
+        assert!(uses_status_check("${{ !cancelled() }}"));
+        assert!(!uses_status_check("${{ true }}"));
+        assert!(!uses_status_check("${{ contains('failure()', 'a') }}"));
+        assert!(!uses_status_check("${{ inputs.failure }}"));
+    }
+}
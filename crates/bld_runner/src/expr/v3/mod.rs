@@ -1,4 +1,5 @@
 pub mod context;
 pub mod exec;
+pub mod functions;
 pub mod parser;
 pub mod traits;
@@ -176,6 +176,16 @@ impl<'a, 'b> ExprValue<'a> {
         Ok(ExprValue::<'b>::Boolean(value))
     }
 
+    pub fn try_not(&self) -> Result<ExprValue<'b>> {
+        match self {
+            Self::Boolean(value) => Ok(ExprValue::<'b>::Boolean(!value)),
+            Self::Text(text) if text.inner() == "true" => Ok(ExprValue::<'b>::Boolean(false)),
+            Self::Text(text) if text.inner() == "false" => Ok(ExprValue::<'b>::Boolean(true)),
+            Self::Unknown => Ok(ExprValue::<'b>::Unknown),
+            _ => bail!("cannot use logical NOT on type {}", self.type_as_string()),
+        }
+    }
+
     pub fn try_or(&self, other: &'a Self) -> Result<ExprValue<'b>> {
         if matches!(self, Self::Unknown) || matches!(other, Self::Unknown) {
             return Ok(ExprValue::<'b>::Boolean(true));
@@ -290,13 +300,28 @@ pub enum OutputScope {
     Job,
 }
 
+/// The combined result of the jobs or steps that came before the one whose condition is
+/// evaluated, as checked by the `success()`, `failure()` and `cancelled()` functions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RunStatus {
+    #[default]
+    Success,
+    Failure,
+    Cancelled,
+    /// Placeholder used only during validation, when nothing has run yet.
+    Unknown,
+}
+
 pub trait ReadonlyRuntimeExprContext<'a> {
     fn get_root_dir(&'a self) -> &'a str;
     fn get_project_dir(&'a self) -> &'a str;
     fn get_input(&'a self, name: &'a str) -> Result<&'a str>;
     fn get_env(&'a self, name: &'a str) -> Result<&'a str>;
+    fn get_secret(&'a self, name: &'a str) -> Result<ExprValue<'a>>;
     fn get_run_id(&'a self) -> &'a str;
     fn get_run_start_time(&'a self) -> &'a str;
+    /// The hash of the files of the platform that match the patterns of a `hash_files` call.
+    fn get_file_hash(&'a self, patterns: &[String]) -> Result<ExprValue<'a>>;
 }
 
 #[automock]
@@ -308,6 +333,8 @@ pub trait WritableRuntimeExprContext {
     fn set_outputs(&mut self, id: &str, outputs: HashMap<String, String>) -> Result<()>;
     #[allow(clippy::needless_lifetimes)]
     fn get_matrix_value<'a>(&'a self, name: &str) -> Result<&'a str>;
+    fn get_step_outcome<'a>(&'a self, id: &str) -> Result<ExprValue<'a>>;
+    fn get_run_status(&self) -> Result<RunStatus>;
 }
 
 pub trait EvalObject<'a> {
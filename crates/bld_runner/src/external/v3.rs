@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 
-use crate::strategy::v3::Strategy;
+use crate::{retry::v3::Retry, step::v3::ContinueOnErrorValue, strategy::v3::Strategy};
 
 #[cfg(feature = "all")]
 use {
@@ -14,6 +14,7 @@ use {
                 WritableRuntimeExprContext,
             },
         },
+        timeout::v3::validate_timeout,
         validator::v3::{ExprScope, Validate, ValidatorContext},
     },
     anyhow::{Result, bail},
@@ -40,6 +41,12 @@ pub struct External {
 
     #[serde(rename = "if")]
     pub condition: Option<String>,
+
+    pub timeout: Option<String>,
+
+    pub continue_on_error: Option<ContinueOnErrorValue>,
+
+    pub retry: Option<Retry>,
 }
 
 impl External {
@@ -140,6 +147,11 @@ impl<'a> Validate<'a> for External {
             ctx.validate_condition(condition, ExprScope::Runtime);
             ctx.pop_section();
         }
+
+        if let Some(timeout) = self.timeout.as_deref() {
+            debug!("Validating external's timeout");
+            validate_timeout(ctx, timeout);
+        }
     }
 }
 
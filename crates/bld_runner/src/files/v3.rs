@@ -4,14 +4,19 @@ use crate::{
     action::v3::Action,
     pipeline::v3::Pipeline,
     traits::{IntoVariables, Variables},
+    triggers::v3::PipelineCompleted,
 };
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "all")]
 use {
     crate::deps::v3::{Dependencies, Dependency},
+    anyhow::Result,
+    bld_config::BldConfig,
     bld_core::fs::FileSystem,
+    bld_models::dtos::ConcurrencyGroup,
     bld_pkg::PackageManager,
+    std::sync::Arc,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +43,13 @@ impl RunnerFile {
         }
     }
 
+    pub fn labels(&self) -> Vec<String> {
+        match self {
+            Self::PipelineFileType(pipeline) => pipeline.labels(),
+            Self::ActionFileType(_) => vec![],
+        }
+    }
+
     pub fn required_inputs(&self) -> Option<HashSet<&str>> {
         match self {
             Self::PipelineFileType(pipeline) => pipeline.required_inputs(),
@@ -51,6 +63,27 @@ impl RunnerFile {
             Self::ActionFileType(_) => None,
         }
     }
+
+    pub fn pipeline_completed(&self) -> Option<&PipelineCompleted> {
+        match self {
+            Self::PipelineFileType(pip) => pip.pipeline_completed(),
+            Self::ActionFileType(_) => None,
+        }
+    }
+
+    #[cfg(feature = "all")]
+    pub fn concurrency_group(
+        &self,
+        config: Arc<BldConfig>,
+        inputs: HashMap<String, String>,
+        env: HashMap<String, String>,
+        run_id: &str,
+    ) -> Result<Option<ConcurrencyGroup>> {
+        match self {
+            Self::PipelineFileType(pip) => pip.concurrency_group(config, inputs, env, run_id),
+            Self::ActionFileType(_) => Ok(None),
+        }
+    }
 }
 
 impl IntoVariables for RunnerFile {
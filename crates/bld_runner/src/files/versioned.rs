@@ -1,7 +1,8 @@
 use crate::pipeline::{v1, v2};
 use crate::traits::{IntoVariables, Variables};
+use crate::triggers::v3::PipelineCompleted;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use super::v3 as files_v3;
 
@@ -17,9 +18,9 @@ use {
     anyhow::{Result, anyhow},
     bld_config::BldConfig,
     bld_core::fs::FileSystem,
+    bld_models::dtos::ConcurrencyGroup,
     bld_pkg::PackageManager,
     futures::Future,
-    std::collections::HashMap,
     std::{fmt::Write, pin::Pin, sync::Arc},
     tracing::debug,
 };
@@ -208,6 +209,46 @@ impl VersionedFile {
         }
     }
 
+    /// The pipeline whose completed runs start runs of this one, which only pipelines of
+    /// version 3 support.
+    pub fn pipeline_completed(&self) -> Option<&PipelineCompleted> {
+        match self {
+            Self::Version1(_) | Self::Version2(_) => None,
+            Self::Version3(file) => file.pipeline_completed(),
+        }
+    }
+
+    /// Resolves the concurrency group of a run, which only pipelines of version 3 support.
+    #[cfg(feature = "all")]
+    pub fn concurrency_group(
+        &self,
+        config: Arc<BldConfig>,
+        inputs: HashMap<String, String>,
+        env: HashMap<String, String>,
+        run_id: &str,
+    ) -> Result<Option<ConcurrencyGroup>> {
+        match self {
+            Self::Version1(_) | Self::Version2(_) => Ok(None),
+            Self::Version3(file) => file.concurrency_group(config, inputs, env, run_id),
+        }
+    }
+
+    /// The declared inputs with their default values, which only files of version 3 have.
+    pub fn inputs_map(&self) -> HashMap<String, String> {
+        match self {
+            Self::Version1(_) | Self::Version2(_) => HashMap::new(),
+            Self::Version3(file) => file.inputs_map(),
+        }
+    }
+
+    /// The labels of the agent that the run is routed to, which only files of version 3 have.
+    pub fn labels(&self) -> Vec<String> {
+        match self {
+            Self::Version1(_) | Self::Version2(_) => vec![],
+            Self::Version3(file) => file.labels(),
+        }
+    }
+
     pub fn required_inputs(&self) -> Option<HashSet<&str>> {
         match self {
             Self::Version1(_) | Self::Version2(_) => None,
@@ -1,6 +1,9 @@
 #[cfg(feature = "all")]
 use crate::expr::v3::traits::ExprText;
-use crate::{outputs::v3::Output, runs_on::v3::RunsOn, step::v3::Step, strategy::v3::Strategy};
+use crate::{
+    approval::v3::Approval, outputs::v3::Output, runs_on::v3::RunsOn, services::v3::Service,
+    step::v3::Step, strategy::v3::Strategy,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
@@ -15,7 +18,9 @@ use {
                 EvalObject, ExprValue, ReadonlyRuntimeExprContext, WritableRuntimeExprContext,
             },
         },
+        services::v3::validate_service_name,
         strategy::v3::validate_matrix_refs,
+        timeout::v3::validate_timeout,
         validator::v3::{ExprScope, Validate, ValidatorContext},
     },
     anyhow::{Result, bail},
@@ -37,6 +42,7 @@ pub enum Needs {
 pub struct Job {
     #[serde(default = "Job::default_id")]
     pub id: String,
+    #[serde(default)]
     pub runs_on: RunsOn,
     #[serde(rename = "if")]
     pub condition: Option<String>,
@@ -45,7 +51,20 @@ pub struct Job {
     pub dispose: bool,
     pub strategy: Option<Strategy>,
     pub working_dir: Option<String>,
+    /// Environment variables applied to every shell step of the job, resolved when
+    /// each step runs.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    pub timeout: Option<String>,
+    #[serde(default)]
+    pub services: HashMap<String, Service>,
+    /// Makes the job wait for someone to approve the run instead of running any steps.
+    pub approval: Option<Approval>,
+    #[serde(default)]
     pub steps: Vec<Step>,
+    /// Steps that run after the main ones whatever their result, for any teardown.
+    #[serde(default, alias = "finally")]
+    pub post: Vec<Step>,
     #[serde(default)]
     pub outputs: HashMap<String, Output>,
 }
@@ -67,6 +86,11 @@ impl Job {
         }
     }
 
+    /// The main steps of the job followed by its post steps.
+    pub fn all_steps(&self) -> impl Iterator<Item = &Step> {
+        self.steps.iter().chain(self.post.iter())
+    }
+
     pub fn outputs_map(&self) -> HashMap<String, String> {
         self.outputs
             .iter()
@@ -85,7 +109,12 @@ impl Default for Job {
             dispose: Self::default_dispose(),
             strategy: None,
             working_dir: None,
+            env: HashMap::new(),
+            timeout: None,
+            services: HashMap::new(),
+            approval: None,
             steps: vec![],
+            post: vec![],
             outputs: HashMap::new(),
         }
     }
@@ -95,14 +124,14 @@ impl Default for Job {
 impl<'a> Dependencies<'a> for Job {
     async fn local_deps(&'a self, fs: &FileSystem) -> Vec<Dependency<'a>> {
         let mut deps = vec![];
-        for step in &self.steps {
+        for step in self.all_steps() {
             deps.append(&mut step.local_deps(fs).await);
         }
         deps
     }
     async fn remote_deps(&'a self, manager: &PackageManager) -> Vec<Dependency<'a>> {
         let mut deps = vec![];
-        for step in &self.steps {
+        for step in self.all_steps() {
             deps.append(&mut step.remote_deps(manager).await);
         }
         deps
@@ -157,7 +186,7 @@ impl<'a> EvalObject<'a> for Job {
 
                 let step_id = step_id.as_span().as_str();
 
-                let Some(step) = self.steps.iter().find(|x| x.is(step_id)) else {
+                let Some(step) = self.all_steps().find(|x| x.is(step_id)) else {
                     bail!("step with id {step_id} not defined");
                 };
 
@@ -209,9 +238,53 @@ impl<'a> Validate<'a> for Job {
             ctx.pop_section();
         }
 
+        debug!("Validating job's {} env section", self.id);
+        ctx.push_section("env");
+        ctx.validate_env(&self.env, ExprScope::Runtime);
+        for value in self.env.values() {
+            validate_matrix_refs(ctx, value, &job_matrix_keys);
+        }
+        ctx.pop_section();
+
+        if let Some(timeout) = self.timeout.as_deref() {
+            debug!("Validating job's {} timeout", self.id);
+            validate_timeout(ctx, timeout);
+        }
+
+        if !self.services.is_empty() {
+            debug!("Validating job's {} services", self.id);
+            ctx.push_section("services");
+            if !self.runs_on.is_container() {
+                ctx.append_error("Services are only supported for jobs that run on a container");
+            }
+            for (name, service) in self.services.iter() {
+                ctx.push_section(name);
+                validate_service_name(ctx, name);
+                service.validate(ctx).await;
+                ctx.pop_section();
+            }
+            ctx.pop_section();
+        }
+
+        if let Some(approval) = self.approval.as_ref() {
+            debug!("Validating job's {} approval section", self.id);
+            ctx.push_section("approval");
+            approval.validate(ctx).await;
+            let fields = [
+                ("steps", !self.steps.is_empty()),
+                ("post steps", !self.post.is_empty()),
+                ("services", !self.services.is_empty()),
+                ("a strategy", self.strategy.is_some()),
+            ];
+            for (field, _) in fields.iter().filter(|(_, defined)| *defined) {
+                ctx.append_error(&format!("Approval jobs must not have {field}"));
+            }
+            ctx.pop_section();
+        }
+
         debug!("Validating job's {} steps", self.id);
         ctx.push_section("steps");
-        if self.steps.is_empty() {
+        if self.steps.is_empty() && self.approval.is_none() {
             ctx.append_error("Job must have at least one step defined");
         }
 
@@ -228,6 +301,22 @@ impl<'a> Validate<'a> for Job {
         }
         ctx.pop_section();
 
+        if !self.post.is_empty() {
+            debug!("Validating job's {} post steps", self.id);
+            ctx.push_section("post");
+            for step in &self.post {
+                let step_id = step.id();
+                if !step_ids.insert(step_id) {
+                    ctx.push_section(step_id);
+                    ctx.append_error(&format!("Duplicate step id '{step_id}' found in job"));
+                    ctx.pop_section();
+                }
+                step.validate(ctx).await;
+                step.validate_matrix(ctx, Some(&job_matrix_keys)).await;
+            }
+            ctx.pop_section();
+        }
+
         debug!("Validating job's {} outputs section", self.id);
         ctx.push_section("outputs");
         for (name, output) in self.outputs.iter() {
@@ -251,8 +340,11 @@ mod tests {
     use bld_utils::sync::IntoArc;
 
     use crate::{
+        approval::v3::Approval,
         expr::v3::context::CommonReadonlyRuntimeExprContext,
         pipeline::v3::Pipeline,
+        runs_on::v3::RunsOn,
+        services::v3::Service,
         step::v3::{ShellCommand, Step},
         strategy::v3::{FailFastValue, MatrixValue, Strategy},
         validator::v3::{CommonValidator, ConsumeValidator, ValidatorWritableRuntimeExprContext},
@@ -302,6 +394,9 @@ mod tests {
             strategy: Some(Strategy {
                 matrix: matrix_of(vec![("os", vec!["linux", "windows"])]),
                 fail_fast: None,
+                include: vec![],
+                exclude: vec![],
+                max_parallel: None,
             }),
             steps: vec![Step::ComplexSh(Box::new(ShellCommand {
                 id: "build".to_string(),
@@ -309,6 +404,9 @@ mod tests {
                 strategy: Some(Strategy {
                     matrix: matrix_of(vec![("version", vec!["v2", "v3"])]),
                     fail_fast: None,
+                    include: vec![],
+                    exclude: vec![],
+                    max_parallel: None,
                 }),
                 ..Default::default()
             }))],
@@ -319,6 +417,30 @@ mod tests {
         assert!(result.is_ok(), "unexpected error: {:?}", result.err());
     }
 
+    #[tokio::test]
+    pub async fn strategy_max_parallel_of_zero_failure() {
+        let job = Job {
+            strategy: Some(Strategy {
+                matrix: matrix_of(vec![("os", vec!["linux", "windows"])]),
+                fail_fast: None,
+                include: vec![],
+                exclude: vec![],
+                max_parallel: Some(0),
+            }),
+            steps: vec![Step::ComplexSh(Box::new(ShellCommand {
+                run: "echo hello".to_string(),
+                ..Default::default()
+            }))],
+            ..Default::default()
+        };
+
+        let error = validate_job(job).await.unwrap_err().to_string();
+        assert!(
+            error.contains("max_parallel must be greater than zero"),
+            "unexpected error: {error}"
+        );
+    }
+
     #[tokio::test]
     pub async fn matrix_ref_undefined_key_failure() {
         let job = Job {
@@ -340,6 +462,9 @@ mod tests {
             strategy: Some(Strategy {
                 matrix: matrix_of(vec![("os", vec!["linux", "windows"])]),
                 fail_fast: None,
+                include: vec![],
+                exclude: vec![],
+                max_parallel: None,
             }),
             steps: vec![Step::ComplexSh(Box::new(ShellCommand {
                 id: "build".to_string(),
@@ -347,6 +472,9 @@ mod tests {
                 strategy: Some(Strategy {
                     matrix: matrix_of(vec![("os", vec!["mac"])]),
                     fail_fast: None,
+                    include: vec![],
+                    exclude: vec![],
+                    max_parallel: None,
                 }),
                 ..Default::default()
             }))],
@@ -364,6 +492,9 @@ mod tests {
             strategy: Some(Strategy {
                 matrix: matrix_of(vec![("os", vec!["linux"])]),
                 fail_fast: Some(FailFastValue::Expr("${{ true }}".to_string())),
+                include: vec![],
+                exclude: vec![],
+                max_parallel: None,
             }),
             steps: vec![Step::ComplexSh(Box::new(ShellCommand {
                 id: "build".to_string(),
@@ -400,6 +531,9 @@ mod tests {
                 strategy: Some(Strategy {
                     matrix,
                     fail_fast: None,
+                    include: vec![],
+                    exclude: vec![],
+                    max_parallel: None,
                 }),
                 steps: vec![Step::ComplexSh(Box::new(ShellCommand {
                     id: "build".to_string(),
@@ -414,6 +548,9 @@ mod tests {
                     fail_fast: Some(FailFastValue::Expr(
                         "${{ steps.build.outputs.ff == \"yes\" }}".to_string(),
                     )),
+                    include: vec![],
+                    exclude: vec![],
+                    max_parallel: None,
                 }),
                 steps: vec![Step::ComplexSh(Box::new(ShellCommand {
                     id: "build".to_string(),
@@ -471,6 +608,124 @@ mod tests {
         assert!(e.to_string().contains("working_dir"), "{e}");
     }
 
+    #[tokio::test]
+    pub async fn valid_job_and_step_timeouts_success() {
+        let job = Job {
+            timeout: Some("1h".to_string()),
+            steps: vec![Step::ComplexSh(Box::new(ShellCommand {
+                id: "build".to_string(),
+                run: "npm install".to_string(),
+                timeout: Some("10m30s".to_string()),
+                ..Default::default()
+            }))],
+            ..Default::default()
+        };
+
+        let result = validate_job(job).await;
+        assert!(result.is_ok(), "unexpected error: {:?}", result.err());
+    }
+
+    #[tokio::test]
+    pub async fn invalid_job_timeout_failure() {
+        let job = Job {
+            timeout: Some("ten minutes".to_string()),
+            steps: vec![Step::ComplexSh(Box::new(ShellCommand {
+                id: "build".to_string(),
+                run: "npm install".to_string(),
+                ..Default::default()
+            }))],
+            ..Default::default()
+        };
+
+        let Err(e) = validate_job(job).await else {
+            panic!("expected an error for an invalid timeout");
+        };
+        assert!(e.to_string().contains("timeout"), "{e}");
+    }
+
+    fn job_with_services(runs_on: &str, services: Vec<(&str, Service)>) -> Job {
+        Job {
+            runs_on: RunsOn::ContainerOrMachine(runs_on.to_string()),
+            services: services
+                .into_iter()
+                .map(|(name, service)| (name.to_string(), service))
+                .collect(),
+            steps: vec![Step::ComplexSh(Box::new(ShellCommand {
+                id: "test".to_string(),
+                run: "cargo test".to_string(),
+                ..Default::default()
+            }))],
+            ..Default::default()
+        }
+    }
+
+    fn service(image: &str, ports: Vec<&str>) -> Service {
+        Service {
+            image: image.to_string(),
+            env: HashMap::new(),
+            ports: ports.into_iter().map(|x| x.to_string()).collect(),
+            health_check: None,
+        }
+    }
+
+    #[tokio::test]
+    pub async fn services_on_container_job_success() {
+        let job = job_with_services(
+            "rust:latest",
+            vec![("db", service("postgres:16", vec!["5432:5432"]))],
+        );
+
+        let result = validate_job(job).await;
+        assert!(result.is_ok(), "unexpected error: {:?}", result.err());
+    }
+
+    #[tokio::test]
+    pub async fn services_on_machine_job_failure() {
+        let job = job_with_services("machine", vec![("db", service("postgres:16", vec![]))]);
+
+        let Err(e) = validate_job(job).await else {
+            panic!("expected an error for services on a machine job");
+        };
+        assert!(
+            e.to_string()
+                .contains("Services are only supported for jobs that run on a container"),
+            "{e}"
+        );
+    }
+
+    #[tokio::test]
+    pub async fn invalid_service_name_and_port_failure() {
+        let job = job_with_services(
+            "rust:latest",
+            vec![("my db", service("postgres:16", vec!["postgres"]))],
+        );
+
+        let Err(e) = validate_job(job).await else {
+            panic!("expected an error for an invalid service");
+        };
+        let e = e.to_string();
+        assert!(e.contains("'my db' is not a valid service name"), "{e}");
+        assert!(e.contains("'postgres' is not a valid port number"), "{e}");
+    }
+
+    #[tokio::test]
+    pub async fn invalid_step_timeout_failure() {
+        let job = Job {
+            steps: vec![Step::ComplexSh(Box::new(ShellCommand {
+                id: "build".to_string(),
+                run: "npm install".to_string(),
+                timeout: Some("0s".to_string()),
+                ..Default::default()
+            }))],
+            ..Default::default()
+        };
+
+        let Err(e) = validate_job(job).await else {
+            panic!("expected an error for a zero timeout");
+        };
+        assert!(e.to_string().contains("greater than zero"), "{e}");
+    }
+
     #[tokio::test]
     pub async fn matrix_non_array_value_failure() {
         let mut matrix = HashMap::new();
@@ -480,6 +735,9 @@ mod tests {
             strategy: Some(Strategy {
                 matrix,
                 fail_fast: None,
+                include: vec![],
+                exclude: vec![],
+                max_parallel: None,
             }),
             steps: vec![Step::ComplexSh(Box::new(ShellCommand {
                 id: "build".to_string(),
@@ -524,4 +782,124 @@ mod tests {
         needs.sort();
         assert_eq!(needs, vec!["a", "b", "c"]);
     }
+
+    fn sh_step(id: &str, run: &str) -> Step {
+        Step::ComplexSh(Box::new(ShellCommand {
+            id: id.to_string(),
+            run: run.to_string(),
+            ..Default::default()
+        }))
+    }
+
+    #[tokio::test]
+    pub async fn post_steps_reading_main_step_outputs_success() {
+        let job = Job {
+            steps: vec![sh_step("build", "echo \"image=app\" >> $BLD_OUTPUTS")],
+            post: vec![sh_step(
+                "cleanup",
+                "docker rmi ${{ steps.build.outputs.image }}",
+            )],
+            ..Default::default()
+        };
+        let result = validate_job(job).await;
+        assert!(result.is_ok(), "unexpected error: {:?}", result.err());
+    }
+
+    #[tokio::test]
+    pub async fn post_step_with_duplicate_id_failure() {
+        let job = Job {
+            steps: vec![sh_step("build", "echo build")],
+            post: vec![sh_step("build", "echo cleanup")],
+            ..Default::default()
+        };
+        let error = validate_job(job).await.unwrap_err().to_string();
+        assert!(
+            error.contains("Duplicate step id 'build' found in job"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    pub fn post_steps_deserialize() {
+        let yaml = r#"
+runs_on: machine
+steps:
+  - run: echo build
+post:
+  - id: cleanup
+    if: ${{ failure() }}
+    run: echo cleanup
+"#;
+        let job: Job = serde_yaml_ng::from_str(yaml).unwrap();
+        assert_eq!(job.steps.len(), 1);
+        assert_eq!(job.post.len(), 1);
+        assert_eq!(job.all_steps().last().map(|x| x.id()), Some("cleanup"));
+
+        let job: Job = serde_yaml_ng::from_str(&yaml.replace("post:", "finally:")).unwrap();
+        assert_eq!(job.post.len(), 1);
+    }
+
+    #[tokio::test]
+    pub async fn env_reading_step_outputs_success() {
+        let mut build = sh_step("build", "echo build");
+        if let Step::ComplexSh(cmd) = &mut build {
+            cmd.env.insert(
+                "VERSION".to_string(),
+                "${{ steps.version.outputs.tag }}".to_string(),
+            );
+        }
+        let job = Job {
+            env: [("STAGE".to_string(), "build".to_string())].into(),
+            steps: vec![sh_step("version", "echo version"), build],
+            ..Default::default()
+        };
+        let result = validate_job(job).await;
+        assert!(result.is_ok(), "unexpected error: {:?}", result.err());
+    }
+
+    #[tokio::test]
+    pub async fn env_with_undefined_matrix_key_failure() {
+        let job = Job {
+            env: [("OS".to_string(), "${{ matrix.os }}".to_string())].into(),
+            steps: vec![sh_step("build", "echo build")],
+            ..Default::default()
+        };
+        let error = validate_job(job).await.unwrap_err().to_string();
+        assert!(error.contains("matrix key 'os' is not defined"), "{error}");
+    }
+
+    #[tokio::test]
+    pub async fn approval_job_deserialized_without_steps_success() {
+        let yaml = r#"
+approval:
+  approvers:
+    - alice
+  timeout: 1h
+  message: Deploy to production?
+"#;
+        let job: Job = serde_yaml_ng::from_str(yaml).unwrap();
+        assert!(job.steps.is_empty());
+        assert_eq!(job.approval.as_ref().unwrap().approvers, vec!["alice"]);
+
+        let result = validate_job(job).await;
+        assert!(result.is_ok(), "unexpected error: {:?}", result.err());
+    }
+
+    #[tokio::test]
+    pub async fn approval_job_with_steps_and_invalid_timeout_failure() {
+        let job = Job {
+            approval: Some(Approval {
+                timeout: Some("soon".to_string()),
+                ..Default::default()
+            }),
+            steps: vec![sh_step("build", "echo build")],
+            ..Default::default()
+        };
+        let error = validate_job(job).await.unwrap_err().to_string();
+        assert!(
+            error.contains("Approval jobs must not have steps"),
+            "{error}"
+        );
+        assert!(error.contains("invalid timeout 'soon'"), "{error}");
+    }
 }
@@ -1,5 +1,9 @@
 pub mod action;
+pub mod approval;
 pub mod artifacts;
+pub mod cache;
+pub mod checkout;
+pub mod concurrency;
 pub mod dag;
 pub mod deps;
 pub mod external;
@@ -9,10 +13,14 @@ pub mod job;
 pub mod outputs;
 pub mod pipeline;
 pub mod registry;
+pub mod retry;
 pub mod runs_on;
+pub mod services;
 pub mod step;
 pub mod strategy;
+pub mod timeout;
 pub mod traits;
+pub mod triggers;
 
 #[cfg(feature = "all")]
 mod runner;
@@ -1,7 +1,9 @@
 use crate::{
+    concurrency::v3::Concurrency,
     inputs::v3::Input,
     job::v3::Job,
     traits::{IntoVariables, Variables},
+    triggers::v3::{On, PipelineCompleted},
 };
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
@@ -11,25 +13,30 @@ use {
     crate::{
         deps::v3::{Dependencies, Dependency},
         expr::v3::{
-            context::out_of_scope,
+            context::{CommonReadonlyRuntimeExprContextOptions, expr_rctx, out_of_scope},
             parser::Rule,
             traits::{
                 EvalObject, ExprText, ExprValue, OutputScope, ReadonlyRuntimeExprContext,
                 WritableRuntimeExprContext,
             },
         },
+        timeout::v3::validate_timeout,
         validator::v3::{ExprScope, Validate, ValidatorContext},
     },
     anyhow::{Result, bail},
+    bld_config::BldConfig,
     bld_config::definitions::{
         KEYWORD_BLD_DIR_V3, KEYWORD_PROJECT_DIR_V3, KEYWORD_RUN_PROPS_ID_V3,
         KEYWORD_RUN_PROPS_START_TIME_V3,
     },
     bld_core::fs::FileSystem,
+    bld_models::dtos::ConcurrencyGroup,
     bld_pkg::PackageManager,
+    bld_utils::sync::IntoArc,
+    chrono::Utc,
     cron::Schedule,
     pest::iterators::Pairs,
-    std::{iter::Peekable, str::FromStr},
+    std::{iter::Peekable, str::FromStr, sync::Arc},
     tracing::debug,
 };
 
@@ -39,6 +46,18 @@ pub struct Pipeline {
 
     pub cron: Option<String>,
 
+    /// The default timeout for every step that doesn't declare its own.
+    pub timeout: Option<String>,
+
+    /// The maximum number of jobs that run at the same time.
+    pub max_parallel: Option<usize>,
+
+    /// The group that limits the runs of the pipeline that can be active at the same time.
+    pub concurrency: Option<Concurrency>,
+
+    /// The events that start a run of the pipeline.
+    pub on: Option<On>,
+
     #[serde(default)]
     pub env: HashMap<String, String>,
 
@@ -65,6 +84,23 @@ impl Pipeline {
         inputs
     }
 
+    /// The labels that an agent needs to have in order to run the pipeline, which are
+    /// the labels of every job that runs on one.
+    pub fn labels(&self) -> Vec<String> {
+        let mut labels: Vec<String> = self
+            .jobs
+            .values()
+            .flat_map(|job| job.runs_on.labels().iter().cloned())
+            .collect();
+        labels.sort();
+        labels.dedup();
+        labels
+    }
+
+    pub fn pipeline_completed(&self) -> Option<&PipelineCompleted> {
+        self.on.as_ref()?.pipeline_completed.as_ref()
+    }
+
     pub fn required_inputs(&self) -> Option<HashSet<&str>> {
         if !self.inputs.is_empty() {
             let inputs = self
@@ -79,6 +115,35 @@ impl Pipeline {
         }
     }
 
+    /// Resolves the concurrency group of a run with the provided inputs and env. The
+    /// secrets of the run aren't available at this point so the group can't use them.
+    #[cfg(feature = "all")]
+    pub fn concurrency_group(
+        &self,
+        config: Arc<BldConfig>,
+        inputs: HashMap<String, String>,
+        env: HashMap<String, String>,
+        run_id: &str,
+    ) -> Result<Option<ConcurrencyGroup>> {
+        let Some(concurrency) = self.concurrency.as_ref() else {
+            return Ok(None);
+        };
+
+        let rctx = expr_rctx(CommonReadonlyRuntimeExprContextOptions {
+            obj: self,
+            config,
+            inputs: inputs.into_arc(),
+            declared_inputs: &self.inputs,
+            env: env.into_arc(),
+            declared_env: &self.env,
+            secrets: HashMap::new().into_arc(),
+            run_id: run_id.to_owned(),
+            run_start_time: Utc::now().naive_utc().format("%F %X").to_string(),
+        })?;
+
+        concurrency.resolve(self, &rctx).map(Some)
+    }
+
     #[cfg(feature = "all")]
     fn validate_cron<'a, C: ValidatorContext<'a>>(&'a self, ctx: &mut C) {
         let Some(cron) = self.cron.as_ref() else {
@@ -239,6 +304,13 @@ impl<'a> EvalObject<'a> for Pipeline {
                     .map(|x| ExprValue::Text(ExprText::Ref(x)))
             }
 
+            "secrets" => {
+                let Some(part) = object_parts.nth(1) else {
+                    bail!("expected name of secret in object path");
+                };
+                rctx.get_secret(part.as_span().as_str())
+            }
+
             // Keywords section
             value if value == KEYWORD_BLD_DIR_V3 => {
                 Ok(ExprValue::Text(ExprText::Ref(rctx.get_root_dir())))
@@ -306,6 +378,32 @@ impl<'a> Validate<'a> for Pipeline {
         debug!("Validating pipeline's cron value");
         self.validate_cron(ctx);
 
+        if let Some(timeout) = self.timeout.as_deref() {
+            debug!("Validating pipeline's timeout value");
+            validate_timeout(ctx, timeout);
+        }
+
+        if self.max_parallel == Some(0) {
+            debug!("Validating pipeline's max_parallel value");
+            ctx.push_section("max_parallel");
+            ctx.append_error("max_parallel must be greater than zero");
+            ctx.pop_section();
+        }
+
+        if let Some(concurrency) = self.concurrency.as_ref() {
+            debug!("Validating pipeline's concurrency section");
+            ctx.push_section("concurrency");
+            concurrency.validate(ctx).await;
+            ctx.pop_section();
+        }
+
+        if let Some(on) = self.on.as_ref() {
+            debug!("Validating pipeline's on section");
+            ctx.push_section("on");
+            on.validate(ctx).await;
+            ctx.pop_section();
+        }
+
         debug!("Validating pipeline's inputs section");
         ctx.push_section("inputs");
         for (name, input) in self.inputs.iter() {
@@ -336,6 +434,7 @@ mod tests {
     use bld_utils::sync::IntoArc;
 
     use crate::{
+        concurrency::v3::Concurrency,
         expr::v3::{
             context::CommonReadonlyRuntimeExprContext,
             exec::CommonExprExecutor,
@@ -343,7 +442,9 @@ mod tests {
         },
         inputs::v3::Input,
         job::v3::{Job, Needs},
+        runs_on::v3::RunsOn,
         step::v3::{ShellCommand, Step},
+        triggers::v3::{On, PipelineCompleted},
         validator::v3::{ExprScope, RunnerFileValidator, Validate, ValidatorContext},
     };
     use crate::{files::v3::RunnerFile, validator::v3::ConsumeValidator};
@@ -568,6 +669,145 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn labels_are_collected_from_every_job() {
+        let mut pipeline = Pipeline::default();
+        let labels = [vec!["linux", "docker"], vec!["linux"], vec![]];
+        for (i, labels) in labels.into_iter().enumerate() {
+            let runs_on = match labels.is_empty() {
+                true => RunsOn::default(),
+                false => RunsOn::Labels {
+                    labels: labels.into_iter().map(String::from).collect(),
+                },
+            };
+            let job = Job {
+                runs_on,
+                ..Default::default()
+            };
+            pipeline.jobs.insert(format!("job_{i}"), job);
+        }
+        assert_eq!(pipeline.labels(), vec!["docker", "linux"]);
+    }
+
+    #[test]
+    pub fn concurrency_group_resolves_with_the_inputs_of_the_run() {
+        let mut pipeline = Pipeline {
+            concurrency: Some(Concurrency {
+                group: "deploy-${{ inputs.environment }}".to_string(),
+                cancel_in_progress: true,
+            }),
+            ..Default::default()
+        };
+        pipeline
+            .inputs
+            .insert("environment".to_string(), complex_input("staging"));
+        let config = BldConfig::default().into_arc();
+
+        let group = pipeline
+            .concurrency_group(config.clone(), HashMap::new(), HashMap::new(), "run")
+            .unwrap()
+            .unwrap();
+        assert_eq!(group.name, "deploy-staging");
+        assert!(group.cancel_in_progress);
+
+        let inputs = HashMap::from([("environment".to_string(), "production".to_string())]);
+        let group = pipeline
+            .concurrency_group(config.clone(), inputs, HashMap::new(), "run")
+            .unwrap()
+            .unwrap();
+        assert_eq!(group.name, "deploy-production");
+
+        pipeline.concurrency = None;
+        let group = pipeline
+            .concurrency_group(config, HashMap::new(), HashMap::new(), "run")
+            .unwrap();
+        assert!(group.is_none());
+    }
+
+    #[tokio::test]
+    pub async fn concurrency_group_validation_failure() {
+        let pipeline = Pipeline {
+            concurrency: Some(Concurrency {
+                group: "${{ steps.build.outputs.image }}".to_string(),
+                cancel_in_progress: false,
+            }),
+            ..Default::default()
+        };
+        let error = validate_pipeline(pipeline).await.unwrap_err().to_string();
+        assert!(
+            error.contains(
+                "[concurrency > group] 'steps.build.outputs.image' is not available at the start of a run"
+            ),
+            "{error}"
+        );
+
+        let pipeline = Pipeline {
+            concurrency: Some(Concurrency {
+                group: " ".to_string(),
+                cancel_in_progress: false,
+            }),
+            ..Default::default()
+        };
+        let error = validate_pipeline(pipeline).await.unwrap_err().to_string();
+        assert!(
+            error.contains("Concurrency group must not be empty"),
+            "{error}"
+        );
+    }
+
+    #[tokio::test]
+    pub async fn max_parallel_of_zero_validation_failure() {
+        let pipeline = Pipeline {
+            max_parallel: Some(0),
+            ..Default::default()
+        };
+        let error = validate_pipeline(pipeline).await.unwrap_err().to_string();
+        assert!(
+            error.contains("[max_parallel] max_parallel must be greater than zero"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    pub fn on_pipeline_completed_defaults_to_finished_state() {
+        let yaml = r"
+on:
+  pipeline_completed:
+    name: build.yaml
+";
+        let pipeline: Pipeline = serde_yaml_ng::from_str(yaml).unwrap();
+        let completed = pipeline.pipeline_completed().unwrap();
+        assert_eq!(completed.name, "build.yaml");
+        assert_eq!(completed.states, vec!["finished".to_string()]);
+
+        assert!(Pipeline::default().pipeline_completed().is_none());
+    }
+
+    #[tokio::test]
+    pub async fn on_pipeline_completed_validation_failure() {
+        let pipeline = Pipeline {
+            on: Some(On {
+                pipeline_completed: Some(PipelineCompleted {
+                    name: " ".to_string(),
+                    states: vec!["faulted".to_string(), "running".to_string()],
+                }),
+            }),
+            ..Default::default()
+        };
+        let error = validate_pipeline(pipeline).await.unwrap_err().to_string();
+        assert!(
+            error.contains(
+                "[on > pipeline_completed > name] Completed pipeline name must not be empty"
+            ),
+            "{error}"
+        );
+        assert!(
+            error.contains("Invalid state 'running', expected one of finished, faulted"),
+            "{error}"
+        );
+        assert!(!error.contains("'faulted'"), "{error}");
+    }
+
     #[test]
     pub fn name_expr_eval_success() {
         let wctx = MockWritableRuntimeExprContext::new();
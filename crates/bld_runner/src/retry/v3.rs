@@ -0,0 +1,303 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "all")]
+use {
+    crate::{
+        timeout::v3::{Cancelled, parse as parse_duration},
+        validator::v3::{Validate, ValidatorContext},
+    },
+    anyhow::{Result, bail},
+    bld_core::{logger::Logger, platform::TimedOut},
+    std::time::{Duration, Instant},
+    tokio::{select, time::sleep},
+    tokio_util::sync::CancellationToken,
+    tracing::debug,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backoff {
+    #[default]
+    Fixed,
+    Exponential,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Retry {
+    pub attempts: u32,
+    pub delay: Option<String>,
+    #[serde(default)]
+    pub backoff: Backoff,
+}
+
+#[cfg(feature = "all")]
+impl Retry {
+    /// The time to wait after the provided attempt, starting from 1, has failed.
+    pub fn delay_after(&self, attempt: u32) -> Result<Duration> {
+        let Some(delay) = self.delay.as_deref() else {
+            return Ok(Duration::ZERO);
+        };
+        let delay = parse_duration(delay)?;
+        let delay = match self.backoff {
+            Backoff::Fixed => delay,
+            Backoff::Exponential => {
+                let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+                delay.saturating_mul(factor)
+            }
+        };
+        Ok(delay)
+    }
+}
+
+/// Runs the provided operation until it succeeds or the attempts of the retry policy
+/// are exhausted, logging each attempt. Without a retry policy the operation runs once,
+/// and an operation that was cancelled isn't attempted again. The wait between attempts
+/// stops early with a [`Cancelled`] error once the token is cancelled, or with a
+/// [`TimedOut`] error once the deadline, along with the timeout that set it, has passed.
+#[cfg(feature = "all")]
+pub async fn run_with_retry<F>(
+    retry: Option<&Retry>,
+    logger: &Logger,
+    cancel: &CancellationToken,
+    deadline: Option<(Instant, Duration)>,
+    mut operation: F,
+) -> Result<()>
+where
+    F: AsyncFnMut() -> Result<()>,
+{
+    let Some(retry) = retry.filter(|x| x.attempts > 1) else {
+        return operation().await;
+    };
+
+    let mut attempt = 1;
+    loop {
+        logger
+            .write_line(format!("{:<15}: {attempt}/{}", "Attempt", retry.attempts))
+            .await?;
+
+        let Err(e) = operation().await else {
+            return Ok(());
+        };
+
+        if attempt >= retry.attempts || e.is::<Cancelled>() {
+            return Err(e);
+        }
+
+        let delay = retry.delay_after(attempt)?;
+        logger
+            .write_line(format!(
+                "attempt {attempt} failed with: {e}, retrying in {}s",
+                delay.as_secs()
+            ))
+            .await?;
+        debug!("waiting {delay:?} before the next attempt");
+        wait(delay, cancel, deadline).await?;
+
+        attempt += 1;
+    }
+}
+
+/// Waits for the delay between two attempts, unless the token is cancelled or the
+/// deadline passes first.
+#[cfg(feature = "all")]
+async fn wait(
+    delay: Duration,
+    cancel: &CancellationToken,
+    deadline: Option<(Instant, Duration)>,
+) -> Result<()> {
+    let remaining =
+        deadline.map(|(deadline, _)| deadline.saturating_duration_since(Instant::now()));
+    select! {
+        biased;
+        _ = cancel.cancelled() => bail!(Cancelled),
+        _ = sleep(remaining.map_or(delay, |remaining| remaining.min(delay))) => {}
+    }
+    match deadline {
+        Some((deadline, timeout)) if Instant::now() >= deadline => bail!(TimedOut::new(timeout)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(feature = "all")]
+impl<'a> Validate<'a> for Retry {
+    async fn validate<C: ValidatorContext<'a>>(&'a self, ctx: &mut C) {
+        ctx.push_section("attempts");
+        if self.attempts == 0 {
+            ctx.append_error("Retry attempts must be greater than zero");
+        }
+        ctx.pop_section();
+
+        if let Some(delay) = self.delay.as_deref() {
+            ctx.push_section("delay");
+            if let Err(e) = parse_duration(delay) {
+                ctx.append_error(&e.to_string());
+            }
+            ctx.pop_section();
+        }
+    }
+}
+
+#[cfg(all(test, feature = "all"))]
+mod tests {
+    use std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    use anyhow::{Result, bail};
+    use bld_core::{logger::Logger, platform::TimedOut};
+    use tokio_util::sync::CancellationToken;
+
+    use super::{Backoff, Retry, run_with_retry};
+    use crate::timeout::v3::Cancelled;
+
+    #[test]
+    pub fn retry_deserializes_with_default_backoff() {
+        let yaml = "attempts: 3\ndelay: 5s\n";
+        let retry: Retry = serde_yaml_ng::from_str(yaml).unwrap();
+        assert_eq!(retry.attempts, 3);
+        assert_eq!(retry.delay.as_deref(), Some("5s"));
+        assert_eq!(retry.backoff, Backoff::Fixed);
+    }
+
+    #[test]
+    pub fn fixed_backoff_keeps_the_same_delay() {
+        let retry = Retry {
+            attempts: 3,
+            delay: Some("5s".to_string()),
+            backoff: Backoff::Fixed,
+        };
+        assert_eq!(retry.delay_after(1).unwrap(), Duration::from_secs(5));
+        assert_eq!(retry.delay_after(3).unwrap(), Duration::from_secs(5));
+    }
+
+    #[test]
+    pub fn exponential_backoff_doubles_the_delay() {
+        let retry = Retry {
+            attempts: 4,
+            delay: Some("5s".to_string()),
+            backoff: Backoff::Exponential,
+        };
+        assert_eq!(retry.delay_after(1).unwrap(), Duration::from_secs(5));
+        assert_eq!(retry.delay_after(2).unwrap(), Duration::from_secs(10));
+        assert_eq!(retry.delay_after(3).unwrap(), Duration::from_secs(20));
+    }
+
+    #[tokio::test]
+    pub async fn run_with_retry_stops_at_first_success() {
+        let logger = Arc::new(Logger::mock());
+        let retry = Retry {
+            attempts: 5,
+            delay: None,
+            backoff: Backoff::Fixed,
+        };
+
+        let mut calls = 0;
+        let cancel = CancellationToken::new();
+        let result = run_with_retry(
+            Some(&retry),
+            &logger,
+            &cancel,
+            None,
+            async || -> Result<()> {
+                calls += 1;
+                if calls < 3 {
+                    bail!("transient failure");
+                }
+                Ok(())
+            },
+        )
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(calls, 3);
+    }
+
+    #[tokio::test]
+    pub async fn run_with_retry_returns_last_error_when_attempts_are_exhausted() {
+        let logger = Arc::new(Logger::mock());
+        let retry = Retry {
+            attempts: 2,
+            delay: None,
+            backoff: Backoff::Fixed,
+        };
+
+        let mut calls = 0;
+        let cancel = CancellationToken::new();
+        let result = run_with_retry(
+            Some(&retry),
+            &logger,
+            &cancel,
+            None,
+            async || -> Result<()> {
+                calls += 1;
+                bail!("failure {calls}")
+            },
+        )
+        .await;
+
+        assert_eq!(result.unwrap_err().to_string(), "failure 2");
+        assert_eq!(calls, 2);
+    }
+
+    #[tokio::test]
+    pub async fn run_with_retry_stops_waiting_once_cancelled() {
+        let logger = Arc::new(Logger::mock());
+        let retry = Retry {
+            attempts: 3,
+            delay: Some("1h".to_string()),
+            backoff: Backoff::Fixed,
+        };
+        let cancel = CancellationToken::new();
+
+        let mut calls = 0;
+        let started = Instant::now();
+        let result = run_with_retry(
+            Some(&retry),
+            &logger,
+            &cancel,
+            None,
+            async || -> Result<()> {
+                calls += 1;
+                cancel.cancel();
+                bail!("failure")
+            },
+        )
+        .await;
+
+        assert!(result.unwrap_err().is::<Cancelled>());
+        assert_eq!(calls, 1);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    pub async fn run_with_retry_stops_waiting_once_the_deadline_passes() {
+        let logger = Arc::new(Logger::mock());
+        let retry = Retry {
+            attempts: 3,
+            delay: Some("1h".to_string()),
+            backoff: Backoff::Fixed,
+        };
+        let cancel = CancellationToken::new();
+        let timeout = Duration::from_millis(100);
+        let deadline = Some((Instant::now() + timeout, timeout));
+
+        let mut calls = 0;
+        let started = Instant::now();
+        let result = run_with_retry(
+            Some(&retry),
+            &logger,
+            &cancel,
+            deadline,
+            async || -> Result<()> {
+                calls += 1;
+                bail!("failure")
+            },
+        )
+        .await;
+
+        assert!(result.unwrap_err().is::<TimedOut>());
+        assert_eq!(calls, 1);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -2,6 +2,7 @@ use anyhow::{Result, anyhow, bail};
 use bld_config::BldConfig;
 use bld_core::{
     artifacts::{Artifacts, ArtifactsStore},
+    cache::Cache,
     context::Context,
     fs::FileSystem,
     logger::Logger,
@@ -20,6 +21,7 @@ use regex::Regex;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::mpsc::Sender;
+use tokio_util::sync::CancellationToken;
 use tracing::debug;
 use uuid::Uuid;
 
@@ -49,11 +51,15 @@ pub struct RunnerBuilder<'a> {
     ipc: Arc<Option<Sender<WorkerMessages>>>,
     env: Option<Arc<HashMap<String, String>>>,
     inputs: Option<Arc<HashMap<String, String>>>,
+    secrets: Option<Arc<HashMap<String, String>>>,
     context: Option<Arc<Context>>,
     platform: Option<Arc<Platform>>,
     package_manager: Option<Arc<PackageManager>>,
     artifacts_store: Option<ArtifactsStore>,
     artifacts: Option<Arc<Artifacts>>,
+    cache: Option<Arc<Cache>>,
+    default_timeout: Option<String>,
+    cancel: CancellationToken,
     is_child: bool,
 }
 
@@ -71,11 +77,15 @@ impl Default for RunnerBuilder<'_> {
             ipc: None.into_arc(),
             env: None,
             inputs: None,
+            secrets: None,
             context: None,
             platform: None,
             package_manager: None,
             artifacts_store: None,
             artifacts: None,
+            cache: None,
+            default_timeout: None,
+            cancel: CancellationToken::new(),
             is_child: false,
         }
     }
@@ -137,6 +147,11 @@ impl<'a> RunnerBuilder<'a> {
         self
     }
 
+    pub fn secrets(mut self, secrets: Arc<HashMap<String, String>>) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
     pub fn context(mut self, context: Arc<Context>) -> Self {
         self.context = Some(context);
         self
@@ -162,6 +177,25 @@ impl<'a> RunnerBuilder<'a> {
         self
     }
 
+    pub fn cache(mut self, cache: Arc<Cache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// The timeout of the steps of an action that don't set their own, which is the
+    /// default timeout of the pipeline that uses the action.
+    pub fn default_timeout(mut self, timeout: Option<String>) -> Self {
+        self.default_timeout = timeout;
+        self
+    }
+
+    /// The token that stops the runner once cancelled, where a child runner is given a
+    /// child token of its parent step.
+    pub fn cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
     pub fn is_child(mut self, is_child: bool) -> Self {
         self.is_child = is_child;
         self
@@ -198,6 +232,11 @@ impl<'a> RunnerBuilder<'a> {
             .context
             .ok_or_else(|| anyhow!("no context instance provided"))?;
 
+        let secrets = self.secrets.unwrap_or_default();
+        self.logger
+            .mask(secrets.values().cloned().collect())
+            .await?;
+
         let artifacts = self
             .artifacts
             .or_else(|| {
@@ -206,6 +245,10 @@ impl<'a> RunnerBuilder<'a> {
             })
             .ok_or_else(|| anyhow!("no artifacts or artifacts store was provided"))?;
 
+        let cache = self
+            .cache
+            .unwrap_or_else(|| Cache::new(config.clone()).into_arc());
+
         let runner = match metadata.file {
             VersionedFile::Version1(pipeline) => {
                 let options = match pipeline.runs_on.as_str() {
@@ -214,6 +257,7 @@ impl<'a> RunnerBuilder<'a> {
                         image: Image::Use(image),
                         docker_url: None,
                         volumes: Vec::new(),
+                        services: vec![],
                     },
                 };
 
@@ -299,6 +343,7 @@ impl<'a> RunnerBuilder<'a> {
                     declared_inputs: &pipeline.inputs,
                     env,
                     declared_env: &pipeline.env,
+                    secrets,
                     run_id: self.run_id,
                     run_start_time: self.run_start_time,
                 })?
@@ -319,7 +364,9 @@ impl<'a> RunnerBuilder<'a> {
                     logger: self.logger,
                     package_manager,
                     artifacts,
+                    cache,
                     ipc: self.ipc,
+                    cancel: self.cancel,
                     is_child: self.is_child,
                     has_faulted: false,
                 });
@@ -340,6 +387,7 @@ impl<'a> RunnerBuilder<'a> {
                     declared_inputs: &action.inputs,
                     env,
                     declared_env: &HashMap::new(),
+                    secrets,
                     run_id: self.run_id,
                     run_start_time: self.run_start_time,
                 })?;
@@ -353,6 +401,7 @@ impl<'a> RunnerBuilder<'a> {
                     *action,
                     platform,
                     artifacts,
+                    cache,
                     expr_regex,
                     expr_rctx,
                     config,
@@ -360,6 +409,8 @@ impl<'a> RunnerBuilder<'a> {
                     context,
                     self.regex_cache,
                     package_manager,
+                    self.default_timeout,
+                    self.cancel,
                 ));
                 VersionedRunner::V3(FileRunner::Action(runner))
             }
@@ -69,7 +69,7 @@ impl Runner {
                     .await?;
             } else {
                 self.context
-                    .set_pipeline_as_finished(self.run_id.to_owned())
+                    .set_pipeline_as_finished(self.run_id.to_owned(), HashMap::new())
                     .await?;
             }
         }
@@ -309,6 +309,8 @@ impl Runner {
                 name: details.pipeline.to_owned(),
                 env: Some(environment),
                 inputs: Some(variables),
+                parent_run_id: Some(self.run_id.to_owned()),
+                priority: None,
             })
             .await
     }
@@ -320,7 +322,13 @@ impl Runner {
 
         debug!("executing shell command {}", command);
         self.platform
-            .shell(self.logger.clone(), &working_dir, &command)
+            .shell(
+                self.logger.clone(),
+                &working_dir,
+                &HashMap::new(),
+                &command,
+                None,
+            )
             .await?;
 
         Ok(())
@@ -215,6 +215,8 @@ impl Job {
                 name: details.pipeline.to_owned(),
                 env: Some(environment),
                 inputs: Some(variables),
+                parent_run_id: Some(self.run_id.to_owned()),
+                priority: None,
             })
             .await
     }
@@ -227,7 +229,13 @@ impl Job {
 
         debug!("executing shell command {}", command);
         platform
-            .shell(self.logger.clone(), working_dir, command)
+            .shell(
+                self.logger.clone(),
+                working_dir,
+                &HashMap::new(),
+                command,
+                None,
+            )
             .await?;
 
         Ok(())
@@ -289,7 +297,7 @@ impl Runner {
                     .await?;
             } else {
                 self.context
-                    .set_pipeline_as_finished(self.run_id.to_owned())
+                    .set_pipeline_as_finished(self.run_id.to_owned(), HashMap::new())
                     .await?;
             }
         }
@@ -304,6 +312,7 @@ impl Runner {
                 image: Image::Use(image),
                 docker_url: None,
                 volumes: Vec::new(),
+                services: vec![],
             },
 
             RunsOn::Pull {
@@ -327,6 +336,7 @@ impl Runner {
                     docker_url: docker_url.as_deref(),
                     image,
                     volumes: Vec::new(),
+                    services: vec![],
                 }
             }
 
@@ -339,6 +349,7 @@ impl Runner {
                 image: Image::build(name, dockerfile, tag),
                 docker_url: docker_url.as_deref(),
                 volumes: Vec::new(),
+                services: vec![],
             },
 
             RunsOn::SshFromGlobalConfig { ssh_config } => {
@@ -478,7 +489,7 @@ impl Runner {
             self.logger
                 .write_line(format!("{:<15}: {}", "Running job", name))
                 .await?;
-            let logger = Logger::in_memory().into_arc();
+            let logger = Logger::job(self.logger.clone(), name).await?.into_arc();
             let job = self.create_job(name, logger.clone());
             let handle = spawn(job.run());
             jobs.push(Some(RunningJob::new(name, handle, logger)));
@@ -514,6 +525,7 @@ impl Runner {
                     };
 
                     let handle_result = running_job.handle.await.map_err(|e| anyhow!(e))?;
+                    running_job.logger.flush().await?;
 
                     let message = if handle_result.is_ok() {
                         format!("{:<15}: {}", "Completed job", running_job.name)
@@ -523,10 +535,6 @@ impl Runner {
 
                     self.logger.write_line(message).await?;
 
-                    self.logger
-                        .write_line(running_job.logger.try_retrieve_output().await?)
-                        .await?;
-
                     result = result.and(handle_result.map(|_| ()));
                 }
             }
@@ -1,40 +1,53 @@
-use std::{collections::HashMap, fmt::Write, sync::Arc};
+use std::{collections::HashMap, fmt::Write, sync::Arc, time::Duration};
 
 use anyhow::{Result, anyhow, bail};
 use bld_config::BldConfig;
 use bld_core::{
-    artifacts::Artifacts, context::Context, fs::FileSystem, logger::Logger, platform::Platform,
-    regex::RegexCache,
+    artifacts::Artifacts, cache::Cache, context::Context, fs::FileSystem, logger::Logger,
+    platform::Platform, regex::RegexCache,
 };
 use bld_models::dtos::ExecClientMessage;
 use bld_pkg::PackageManager;
 use bld_sock::ExecClient;
 use bld_utils::sync::IntoArc;
 use regex::Regex;
+use tokio_util::sync::CancellationToken;
 use tracing::debug;
 
 use crate::{
     RunnerBuilder,
     action::v3::Action,
     artifacts::v3::{DownloadArtifact, UploadArtifact},
+    cache::v3::{CACHE_HIT_OUTPUT, CacheOptions, CacheStep},
+    checkout::v3::{CHECKOUT_SHA_OUTPUT, CheckoutStep},
     expr::v3::{
-        context::CommonReadonlyRuntimeExprContext,
+        context::{CommonReadonlyRuntimeExprContext, FileHashesExprContext},
         exec::{CommonExprExecutor, eval_all_expressions, eval_all_expressions_map},
-        traits::EvalExpr,
+        functions::uses_status_check,
+        traits::{EvalExpr, RunStatus},
     },
     external::v3::External,
+    retry::v3::run_with_retry,
     runner::v3::state::{ActionState, RootState, State},
     step::v3::{ShellCommand, Step},
     strategy::v3::Strategy,
+    timeout::v3::{
+        Cancelled, POST_STEPS_TIMEOUT, parse as parse_timeout, post_steps_token,
+        run_child_with_timeout, run_with_cancel, run_with_timeout,
+    },
 };
 
-use super::common::RecursiveFuture;
+use super::common::{RecursiveFuture, checkout, resolve_cache, save_caches};
 
 pub struct ActionRunner<S: RootState> {
     pub logger: Arc<Logger>,
     pub action: Action,
     pub platform: Arc<Platform>,
     pub artifacts: Arc<Artifacts>,
+    pub cache: Arc<Cache>,
+    /// The caches restored by the action's steps that will be saved once all of them
+    /// have run.
+    pub caches: Vec<CacheOptions>,
     pub expr_regex: Regex,
     pub expr_rctx: CommonReadonlyRuntimeExprContext,
     pub state: S,
@@ -43,6 +56,12 @@ pub struct ActionRunner<S: RootState> {
     pub run_ctx: Arc<Context>,
     pub regex_cache: Arc<RegexCache>,
     pub package_manager: Arc<PackageManager>,
+    /// The default timeout of the pipeline that uses the action, applied to the steps
+    /// that don't set their own.
+    pub default_timeout: Option<String>,
+    /// Cancelled to stop the step that is running, such as when the step of the parent
+    /// runner that uses the action times out.
+    pub cancel: CancellationToken,
 }
 
 impl<S: RootState> ActionRunner<S> {
@@ -87,20 +106,28 @@ impl<S: RootState> ActionRunner<S> {
             .transpose()
     }
 
+    fn resolve_env(&mut self, env: &HashMap<String, String>) -> Result<HashMap<String, String>> {
+        let expr_exec = CommonExprExecutor::new(&self.action, &self.expr_rctx, &self.state);
+        eval_all_expressions_map(&expr_exec, &self.expr_regex, env)
+    }
+
     async fn shell(
         &mut self,
         step_id: &str,
         working_dir: &Option<String>,
+        env: &HashMap<String, String>,
         command: &str,
+        timeout: Option<Duration>,
     ) -> Result<()> {
         debug!("start execution of exec section for step");
         debug!("executing shell command {}", command);
 
         let cmd = self.eval_all_expr(command)?;
         let working_dir = self.resolve_working_dir(working_dir)?;
+        let env = self.resolve_env(env)?;
         let outputs = self
             .platform
-            .shell(self.logger.clone(), &working_dir, &cmd)
+            .shell(self.logger.clone(), &working_dir, &env, &cmd, timeout)
             .await?;
 
         self.state.set_outputs(step_id, outputs)?;
@@ -108,17 +135,62 @@ impl<S: RootState> ActionRunner<S> {
         Ok(())
     }
 
+    /// Runs the steps in order, where once one of them fails the rest are skipped unless
+    /// their condition checks the status of the action. The first error is returned once
+    /// all of them have been through.
     async fn steps(&mut self) -> Result<()> {
         debug!("starting execution of action steps");
         let action = self.action.clone();
+        let mut result = Ok(());
         for step in &action.steps {
-            let Some(strategy) = step.strategy() else {
-                self.run_step(step).await?;
+            if self.cancel.is_cancelled() {
+                debug!("skipping the remaining steps since the action was cancelled");
+                return result.and(Err(anyhow!(Cancelled)));
+            }
+
+            let status_check = step.condition().is_some_and(uses_status_check);
+            if !status_check && self.state.get_run_status()? != RunStatus::Success {
+                debug!("skipping step {} since an earlier step failed", step.id());
                 continue;
-            };
-            self.run_step_with_strategy(step, strategy).await?;
+            }
+
+            if let Err(e) = self.run_any_step(step).await
+                && result.is_ok()
+            {
+                result = Err(e);
+            }
+        }
+        result
+    }
+
+    /// Runs every post step, whether the main steps failed or not, returning the first
+    /// error. They still run once the action has been cancelled so that they can clean up,
+    /// bounded by [`POST_STEPS_TIMEOUT`].
+    async fn post_steps(&mut self) -> Result<()> {
+        debug!("starting execution of action post steps");
+        self.cancel = post_steps_token(&self.cancel);
+        let cancel = self.cancel.clone();
+        let action = self.action.clone();
+        // boxed since the steps of an action may call other actions.
+        let post = Box::pin(async {
+            let mut result = Ok(());
+            for step in &action.post {
+                if let Err(e) = self.run_any_step(step).await
+                    && result.is_ok()
+                {
+                    result = Err(e);
+                }
+            }
+            result
+        });
+        run_child_with_timeout(Some(POST_STEPS_TIMEOUT), &cancel, post).await
+    }
+
+    async fn run_any_step(&mut self, step: &Step) -> Result<()> {
+        match step.strategy() {
+            Some(strategy) => self.run_step_with_strategy(step, strategy).await,
+            None => self.run_step(step).await,
         }
-        Ok(())
     }
 
     async fn run_step_with_strategy(&mut self, step: &Step, strategy: &Strategy) -> Result<()> {
@@ -148,12 +220,17 @@ impl<S: RootState> ActionRunner<S> {
         let result = match self.condition(step.condition()) {
             Ok(true) => {
                 self.state.update_node_state(step.id(), State::Running);
-                match step {
-                    Step::ComplexSh(complex) => self.complex_shell(complex).await,
-                    Step::ExternalFile(external) => self.external(external).await,
-                    Step::DownloadArtifact(download) => self.download_artifact(download).await,
-                    Step::UploadArtifact(upload) => self.upload_artifact(upload).await,
-                }
+                let logger = self.logger.clone();
+                let cancel = self.cancel.clone();
+                run_with_retry(step.retry(), &logger, &cancel, None, async || {
+                    let timeout = step
+                        .timeout()
+                        .or(self.default_timeout.as_deref())
+                        .map(parse_timeout)
+                        .transpose()?;
+                    self.step(step, timeout).await
+                })
+                .await
             }
             Ok(false) => {
                 debug!("condition failed, skiping step");
@@ -162,30 +239,121 @@ impl<S: RootState> ActionRunner<S> {
             Err(e) => Err(e),
         };
 
-        result
-            .inspect(|_| self.state.update_node_state(step.id(), State::Completed))
-            .inspect_err(|e| {
+        match result {
+            Ok(()) => {
+                self.state.update_node_state(step.id(), State::Completed);
+                Ok(())
+            }
+            Err(e) => self.step_failed(step, e).await,
+        }
+    }
+
+    /// Records the failure of a step, swallowing it if the step is allowed to fail. A step
+    /// that stopped because the action was cancelled is cancelled instead.
+    async fn step_failed(&mut self, step: &Step, error: anyhow::Error) -> Result<()> {
+        if error.is::<Cancelled>() {
+            self.state.update_node_state(
+                step.id(),
+                State::Cancelled {
+                    error: error.to_string(),
+                },
+            );
+            return Err(error);
+        }
+
+        self.state.update_node_state(
+            step.id(),
+            State::Failed {
+                error: error.to_string(),
+            },
+        );
+
+        let continue_on_error = {
+            let exec = CommonExprExecutor::new(&self.action, &self.expr_rctx, &self.state);
+            step.resolve_continue_on_error(&exec)
+        };
+
+        match continue_on_error {
+            Ok(true) => {
+                self.state.update_node_state(
+                    step.id(),
+                    State::ContinuedOnError {
+                        error: error.to_string(),
+                    },
+                );
+                self.logger
+                    .write_line(format!(
+                        "step {} failed with: {error}, continuing since the step is allowed to fail",
+                        step.id()
+                    ))
+                    .await?;
+                Ok(())
+            }
+            Ok(false) => Err(error),
+            Err(e) => {
                 self.state.update_node_state(
                     step.id(),
                     State::Failed {
                         error: e.to_string(),
                     },
-                )
-            })
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// Runs a step until it finishes, times out or the action is cancelled. An external
+    /// step isn't dropped in the last two cases, its child runner is cancelled instead and
+    /// awaited so that it cleans up after itself.
+    async fn step(&mut self, step: &Step, timeout: Option<Duration>) -> Result<()> {
+        let cancel = self.cancel.clone();
+        match step {
+            Step::ComplexSh(complex) => {
+                run_with_cancel(&cancel, self.complex_shell(complex, timeout)).await
+            }
+            Step::ExternalFile(external) => {
+                let child = cancel.child_token();
+                run_child_with_timeout(timeout, &child, self.external(external, &child)).await
+            }
+            Step::DownloadArtifact(download) => {
+                let future = run_with_timeout(timeout, self.download_artifact(download));
+                run_with_cancel(&cancel, future).await
+            }
+            Step::UploadArtifact(upload) => {
+                let future = run_with_timeout(timeout, self.upload_artifact(upload));
+                run_with_cancel(&cancel, future).await
+            }
+            Step::Cache(cache) => {
+                run_with_cancel(&cancel, run_with_timeout(timeout, self.cache(cache))).await
+            }
+            Step::Checkout(step) => {
+                run_with_cancel(&cancel, run_with_timeout(timeout, self.checkout(step))).await
+            }
+        }
     }
 
-    async fn complex_shell(&mut self, complex: &ShellCommand) -> Result<()> {
+    async fn complex_shell(
+        &mut self,
+        complex: &ShellCommand,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
         if let Some(name) = complex.name.as_ref() {
             let mut message = String::new();
             writeln!(message, "{:<15}: {name}", "Step")?;
             self.logger.write_line(message).await?;
         }
-        self.shell(&complex.id, &complex.working_dir, &complex.run)
-            .await?;
+        self.shell(
+            &complex.id,
+            &complex.working_dir,
+            &complex.env,
+            &complex.run,
+            timeout,
+        )
+        .await?;
         Ok(())
     }
 
-    async fn external(&mut self, external: &External) -> Result<()> {
+    async fn external(&mut self, external: &External, cancel: &CancellationToken) -> Result<()> {
         if let Some(name) = external.name.as_ref() {
             let mut message = String::new();
             writeln!(message, "{:<15}: {name}", "Step")?;
@@ -196,13 +364,17 @@ impl<S: RootState> ActionRunner<S> {
 
         match external.server.as_ref() {
             Some(server) => self.server_external(server, external).await?,
-            None => self.local_external(external).await?,
+            None => self.local_external(external, cancel).await?,
         };
 
         Ok(())
     }
 
-    async fn local_external(&mut self, details: &External) -> Result<()> {
+    async fn local_external(
+        &mut self,
+        details: &External,
+        cancel: &CancellationToken,
+    ) -> Result<()> {
         debug!("building runner for child file");
 
         let inputs = self.variables_external(&details.with)?;
@@ -217,11 +389,15 @@ impl<S: RootState> ActionRunner<S> {
             .logger(self.logger.clone())
             .env(env.into_arc())
             .inputs(inputs.into_arc())
+            .secrets(self.expr_rctx.secrets.clone().unwrap_or_default())
             .context(self.run_ctx.clone())
             .platform(self.platform.clone())
             .regex_cache(self.regex_cache.clone())
             .package_manager(self.package_manager.clone())
             .artifacts(self.artifacts.clone())
+            .cache(self.cache.clone())
+            .default_timeout(self.default_timeout.clone())
+            .cancel(cancel.clone())
             .is_child(true)
             .build()
             .await?;
@@ -259,6 +435,8 @@ impl<S: RootState> ActionRunner<S> {
                 name: details.uses.to_owned(),
                 env: Some(env),
                 inputs: Some(inputs),
+                parent_run_id: Some(self.expr_rctx.run_id.to_owned()),
+                priority: None,
             })
             .await
     }
@@ -291,6 +469,77 @@ impl<S: RootState> ActionRunner<S> {
             .await
     }
 
+    async fn cache(&mut self, step: &CacheStep) -> Result<()> {
+        if let Some(name) = step.name.as_ref() {
+            let mut message = String::new();
+            writeln!(message, "{:<15}: {name}", "Step")?;
+            self.logger.write_line(message).await?;
+        }
+
+        let options = resolve_cache(&self.platform, &step.cache, |value, hashes| {
+            let rctx = FileHashesExprContext::new(&self.expr_rctx, hashes);
+            let expr_exec = CommonExprExecutor::new(&self.action, &rctx, &self.state);
+            eval_all_expressions(&expr_exec, &self.expr_regex, value)
+        })
+        .await?;
+        let restored = self
+            .cache
+            .restore(
+                &self.platform,
+                &self.logger,
+                &options.key,
+                &options.restore_keys,
+                &options.paths,
+            )
+            .await?;
+
+        let hit = restored.as_deref() == Some(options.key.as_str());
+        self.state.set_outputs(
+            &step.id,
+            HashMap::from([(CACHE_HIT_OUTPUT.to_string(), hit.to_string())]),
+        )?;
+
+        if !hit {
+            self.caches.push(options);
+        }
+
+        Ok(())
+    }
+
+    async fn checkout(&mut self, step: &CheckoutStep) -> Result<()> {
+        if let Some(name) = step.name.as_ref() {
+            let mut message = String::new();
+            writeln!(message, "{:<15}: {name}", "Step")?;
+            self.logger.write_line(message).await?;
+        }
+
+        let (options, path) = step.checkout.resolve(|value| self.eval_all_expr(value))?;
+        let sha = checkout(
+            &self.package_manager,
+            &self.config,
+            &self.platform,
+            &self.logger,
+            &options,
+            &path,
+        )
+        .await?;
+
+        self.run_ctx
+            .add_checkout(
+                self.action.name.clone(),
+                options.repository,
+                options.reference,
+                sha.clone(),
+                path,
+            )
+            .await?;
+
+        self.state.set_outputs(
+            &step.id,
+            HashMap::from([(CHECKOUT_SHA_OUTPUT.to_string(), sha)]),
+        )
+    }
+
     async fn execute(mut self) -> Result<HashMap<String, String>> {
         self.state.update_state(State::Running);
         self.info().await.inspect_err(|e| {
@@ -298,19 +547,30 @@ impl<S: RootState> ActionRunner<S> {
                 error: e.to_string(),
             })
         })?;
-        self.steps().await.inspect_err(|e| {
+
+        let result = self.execute_main().await.inspect_err(|e| {
             self.state.update_state(State::Failed {
                 error: e.to_string(),
             })
-        })?;
-        let outputs = self.resolve_outputs().inspect_err(|e| {
+        });
+        let post = self.post_steps().await.inspect_err(|e| {
             self.state.update_state(State::Failed {
                 error: e.to_string(),
             })
-        })?;
+        });
+
+        let outputs = result?;
+        post?;
         self.state.update_state(State::Completed);
         Ok(outputs)
     }
+
+    async fn execute_main(&mut self) -> Result<HashMap<String, String>> {
+        self.steps().await?;
+        let caches = std::mem::take(&mut self.caches);
+        save_caches(&self.cache, &self.platform, &self.logger, caches).await?;
+        self.resolve_outputs()
+    }
 }
 
 impl ActionRunner<ActionState> {
@@ -320,6 +580,7 @@ impl ActionRunner<ActionState> {
         action: Action,
         platform: Arc<Platform>,
         artifacts: Arc<Artifacts>,
+        cache: Arc<Cache>,
         expr_regex: Regex,
         expr_rctx: CommonReadonlyRuntimeExprContext,
         config: Arc<BldConfig>,
@@ -327,9 +588,11 @@ impl ActionRunner<ActionState> {
         run_ctx: Arc<Context>,
         regex_cache: Arc<RegexCache>,
         package_manager: Arc<PackageManager>,
+        default_timeout: Option<String>,
+        cancel: CancellationToken,
     ) -> Self {
         let mut state = ActionState::default();
-        for step in &action.steps {
+        for step in action.all_steps() {
             state.add_node(step.id());
         }
         Self {
@@ -337,6 +600,8 @@ impl ActionRunner<ActionState> {
             action,
             platform,
             artifacts,
+            cache,
+            caches: vec![],
             expr_regex,
             expr_rctx,
             state,
@@ -345,6 +610,8 @@ impl ActionRunner<ActionState> {
             run_ctx,
             regex_cache,
             package_manager,
+            default_timeout,
+            cancel,
         }
     }
 
@@ -359,26 +626,29 @@ mod tests {
 
     use bld_config::BldConfig;
     use bld_core::{
-        artifacts::Artifacts, context::Context, fs::FileSystem, logger::Logger, platform::Platform,
-        regex::RegexCache,
+        artifacts::Artifacts, cache::Cache, context::Context, fs::FileSystem, logger::Logger,
+        platform::Platform, regex::RegexCache,
     };
     use bld_pkg::PackageManager;
     use bld_utils::sync::IntoArc;
     use regex::Regex;
+    use tokio_util::sync::CancellationToken;
 
     use crate::{
         action::v3::Action,
         artifacts::v3::{DownloadArtifact, UploadArtifact},
         expr::v3::{
-            context::CommonReadonlyRuntimeExprContext, parser::EXPR_REGEX,
-            traits::WritableRuntimeExprContext,
+            context::CommonReadonlyRuntimeExprContext,
+            parser::EXPR_REGEX,
+            traits::{RunStatus, WritableRuntimeExprContext},
         },
         external::v3::External,
         outputs::v3::Output,
+        retry::v3::{Backoff, Retry},
         runner::v3::{
             ActionRunner, ActionState, RootState, State, state::MockRootState, test_utils::TempDir,
         },
-        step::v3::{ShellCommand, Step},
+        step::v3::{ContinueOnErrorValue, ShellCommand, Step},
         strategy::v3::{FailFastValue, MatrixValue, Strategy},
     };
 
@@ -410,6 +680,10 @@ mod tests {
             run_ctx,
             regex_cache,
             package_manager,
+            cache: Cache::mock().into_arc(),
+            caches: vec![],
+            default_timeout: None,
+            cancel: CancellationToken::new(),
         };
 
         assert!(matches!(runner.condition(None), Ok(true)));
@@ -465,6 +739,10 @@ mod tests {
             run_ctx,
             regex_cache,
             package_manager,
+            cache: Cache::mock().into_arc(),
+            caches: vec![],
+            default_timeout: None,
+            cancel: CancellationToken::new(),
         };
 
         assert_eq!(runner.resolve_working_dir(&None).unwrap(), None);
@@ -488,6 +766,9 @@ mod tests {
         let regex = Regex::new(EXPR_REGEX).unwrap();
         let rctx = CommonReadonlyRuntimeExprContext::default();
         let mut state = MockRootState::new();
+        state
+            .expect_get_run_status()
+            .returning(|| Ok(RunStatus::Success));
         let config = BldConfig::default().into_arc();
         let fs = FileSystem::local(config.clone()).into_arc();
         let run_ctx = Context::mock().into_arc();
@@ -526,7 +807,11 @@ mod tests {
                 run: "echo hello".to_string(),
                 condition: None,
                 working_dir: None,
+                env: HashMap::new(),
                 strategy: None,
+                timeout: None,
+                continue_on_error: None,
+                retry: None,
             })));
 
             state
@@ -548,6 +833,10 @@ mod tests {
             run_ctx,
             regex_cache,
             package_manager,
+            cache: Cache::mock().into_arc(),
+            caches: vec![],
+            default_timeout: None,
+            cancel: CancellationToken::new(),
         };
 
         // Act
@@ -582,6 +871,8 @@ mod tests {
                 download: "artifact-name".to_string(),
                 to: "${{ inputs.region }}/artifact".to_string(),
                 condition: None,
+                continue_on_error: None,
+                retry: None,
             })));
         action
             .steps
@@ -590,6 +881,8 @@ mod tests {
                 upload: "${{ inputs.region }}/artifact".to_string(),
                 name: "artifact-name".to_string(),
                 condition: None,
+                continue_on_error: None,
+                retry: None,
             })));
 
         let mut state = ActionState::default();
@@ -610,6 +903,10 @@ mod tests {
             run_ctx,
             regex_cache,
             package_manager,
+            cache: Cache::mock().into_arc(),
+            caches: vec![],
+            default_timeout: None,
+            cancel: CancellationToken::new(),
         };
 
         let result = runner.execute().await;
@@ -639,7 +936,11 @@ mod tests {
             run: "echo skipped".to_string(),
             condition: Some("${{ false }}".to_string()),
             working_dir: None,
+            env: HashMap::new(),
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
         action.steps.push(Step::ComplexSh(Box::new(ShellCommand {
             id: "executed".to_string(),
@@ -647,7 +948,11 @@ mod tests {
             run: "echo executed".to_string(),
             condition: Some("${{ true }}".to_string()),
             working_dir: None,
+            env: HashMap::new(),
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
 
         let mut state = ActionState::default();
@@ -668,6 +973,10 @@ mod tests {
             run_ctx,
             regex_cache,
             package_manager,
+            cache: Cache::mock().into_arc(),
+            caches: vec![],
+            default_timeout: None,
+            cancel: CancellationToken::new(),
         };
 
         // Act
@@ -703,6 +1012,8 @@ mod tests {
                 download: "artifact-name".to_string(),
                 to: "some/path".to_string(),
                 condition: Some("${{ false }}".to_string()),
+                continue_on_error: None,
+                retry: None,
             })));
         action
             .steps
@@ -711,6 +1022,8 @@ mod tests {
                 upload: "some/path".to_string(),
                 name: "artifact-name".to_string(),
                 condition: Some("${{ true }}".to_string()),
+                continue_on_error: None,
+                retry: None,
             })));
 
         let mut state = ActionState::default();
@@ -731,6 +1044,10 @@ mod tests {
             run_ctx,
             regex_cache,
             package_manager,
+            cache: Cache::mock().into_arc(),
+            caches: vec![],
+            default_timeout: None,
+            cancel: CancellationToken::new(),
         };
 
         // Act
@@ -779,7 +1096,14 @@ mod tests {
             strategy: Some(Strategy {
                 matrix,
                 fail_fast: None,
+                include: vec![],
+                exclude: vec![],
+                max_parallel: None,
             }),
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
+            env: HashMap::new(),
         })));
 
         let runner = ActionRunner {
@@ -795,6 +1119,10 @@ mod tests {
             run_ctx,
             regex_cache,
             package_manager,
+            cache: Cache::mock().into_arc(),
+            caches: vec![],
+            default_timeout: None,
+            cancel: CancellationToken::new(),
         };
 
         let result = runner.execute().await;
@@ -816,6 +1144,9 @@ mod tests {
         let package_manager = PackageManager::new(config.clone()).into_arc();
 
         let mut state = MockRootState::new();
+        state
+            .expect_get_run_status()
+            .returning(|| Ok(RunStatus::Success));
         state.expect_update_state().returning(|_| ());
         state.expect_set_matrix().returning(|_| ());
         state
@@ -839,7 +1170,14 @@ mod tests {
             strategy: Some(Strategy {
                 matrix,
                 fail_fast: None,
+                include: vec![],
+                exclude: vec![],
+                max_parallel: None,
             }),
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
+            env: HashMap::new(),
         })));
 
         let runner = ActionRunner {
@@ -855,6 +1193,10 @@ mod tests {
             run_ctx,
             regex_cache,
             package_manager,
+            cache: Cache::mock().into_arc(),
+            caches: vec![],
+            default_timeout: None,
+            cancel: CancellationToken::new(),
         };
 
         let result = runner.execute().await;
@@ -876,6 +1218,9 @@ mod tests {
         let package_manager = PackageManager::new(config.clone()).into_arc();
 
         let mut state = MockRootState::new();
+        state
+            .expect_get_run_status()
+            .returning(|| Ok(RunStatus::Success));
         state.expect_update_state().returning(|_| ());
         state.expect_set_matrix().returning(|_| ());
         state
@@ -899,7 +1244,14 @@ mod tests {
             strategy: Some(Strategy {
                 matrix,
                 fail_fast: Some(FailFastValue::Bool(false)),
+                include: vec![],
+                exclude: vec![],
+                max_parallel: None,
             }),
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
+            env: HashMap::new(),
         })));
 
         let runner = ActionRunner {
@@ -915,12 +1267,140 @@ mod tests {
             run_ctx,
             regex_cache,
             package_manager,
+            cache: Cache::mock().into_arc(),
+            caches: vec![],
+            default_timeout: None,
+            cancel: CancellationToken::new(),
         };
 
         let result = runner.execute().await;
         assert!(result.is_err());
     }
 
+    fn action_runner_with_steps(steps: Vec<Step>) -> ActionRunner<ActionState> {
+        let config = BldConfig::default().into_arc();
+        let action = Action {
+            steps,
+            ..Default::default()
+        };
+
+        let mut state = ActionState::default();
+        for step in &action.steps {
+            state.add_node(step.id());
+        }
+
+        ActionRunner {
+            logger: Logger::mock().into_arc(),
+            action,
+            platform: Platform::mock().into_arc(),
+            artifacts: Artifacts::mock().into_arc(),
+            expr_regex: Regex::new(EXPR_REGEX).unwrap(),
+            expr_rctx: CommonReadonlyRuntimeExprContext::default(),
+            state,
+            fs: FileSystem::local(config.clone()).into_arc(),
+            run_ctx: Context::mock().into_arc(),
+            regex_cache: RegexCache::mock().into_arc(),
+            package_manager: PackageManager::new(config.clone()).into_arc(),
+            config,
+            cache: Cache::mock().into_arc(),
+            caches: vec![],
+            default_timeout: None,
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    #[tokio::test]
+    pub async fn step_allowed_to_fail_is_recorded_and_later_steps_run() {
+        let mut runner = action_runner_with_steps(vec![
+            Step::UploadArtifact(Box::new(UploadArtifact {
+                id: "flaky".to_string(),
+                upload: "some/path".to_string(),
+                name: "invalid/name".to_string(),
+                continue_on_error: Some(ContinueOnErrorValue::Bool(true)),
+                ..Default::default()
+            })),
+            Step::ComplexSh(Box::new(ShellCommand {
+                id: "after".to_string(),
+                run: "echo after".to_string(),
+                condition: Some("${{ steps.flaky.outcome == \"continued_on_error\" }}".to_string()),
+                ..Default::default()
+            })),
+        ]);
+
+        let result = runner.steps().await;
+
+        assert!(result.is_ok(), "unexpected error: {:?}", result.err());
+        assert!(matches!(
+            runner.state.get_node_state("flaky"),
+            Some(State::ContinuedOnError { .. })
+        ));
+        assert!(matches!(
+            runner.state.get_node_state("after"),
+            Some(State::Completed)
+        ));
+    }
+
+    #[tokio::test]
+    pub async fn step_with_continue_on_error_expression_false_fails() {
+        let mut runner =
+            action_runner_with_steps(vec![Step::UploadArtifact(Box::new(UploadArtifact {
+                id: "flaky".to_string(),
+                upload: "some/path".to_string(),
+                name: "invalid/name".to_string(),
+                continue_on_error: Some(ContinueOnErrorValue::Expr("${{ false }}".to_string())),
+                ..Default::default()
+            }))]);
+
+        let result = runner.steps().await;
+
+        assert!(result.is_err());
+        assert!(matches!(
+            runner.state.get_node_state("flaky"),
+            Some(State::Failed { .. })
+        ));
+    }
+
+    #[tokio::test]
+    pub async fn step_failing_every_retry_attempt_fails() {
+        let mut runner =
+            action_runner_with_steps(vec![Step::UploadArtifact(Box::new(UploadArtifact {
+                id: "flaky".to_string(),
+                upload: "some/path".to_string(),
+                name: "invalid/name".to_string(),
+                retry: Some(Retry {
+                    attempts: 3,
+                    delay: None,
+                    backoff: Backoff::Fixed,
+                }),
+                ..Default::default()
+            }))]);
+
+        let result = runner.steps().await;
+
+        assert!(result.is_err());
+        assert!(matches!(
+            runner.state.get_node_state("flaky"),
+            Some(State::Failed { .. })
+        ));
+    }
+
+    #[tokio::test]
+    pub async fn step_without_a_timeout_uses_the_default_timeout() {
+        let mut runner = action_runner_with_steps(vec![Step::ComplexSh(Box::new(ShellCommand {
+            id: "build".to_string(),
+            run: "echo build".to_string(),
+            ..Default::default()
+        }))]);
+        runner.default_timeout = Some("10x".to_string());
+
+        let error = runner.steps().await.unwrap_err();
+
+        assert!(
+            error.to_string().contains("invalid timeout '10x'"),
+            "expected the default timeout to be parsed, got: {error}"
+        );
+    }
+
     #[tokio::test]
     pub async fn action_with_no_outputs_key_gives_empty_map_success() {
         let logger = Logger::mock().into_arc();
@@ -941,7 +1421,11 @@ mod tests {
             run: "echo hello".to_string(),
             condition: None,
             working_dir: None,
+            env: HashMap::new(),
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
 
         let mut state = ActionState::default();
@@ -962,6 +1446,10 @@ mod tests {
             run_ctx,
             regex_cache,
             package_manager,
+            cache: Cache::mock().into_arc(),
+            caches: vec![],
+            default_timeout: None,
+            cancel: CancellationToken::new(),
         };
 
         let result = runner.execute().await;
@@ -994,7 +1482,11 @@ mod tests {
             run: "echo hello".to_string(),
             condition: None,
             working_dir: None,
+            env: HashMap::new(),
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
         action.outputs.insert(
             "image".to_string(),
@@ -1026,6 +1518,10 @@ mod tests {
             run_ctx,
             regex_cache,
             package_manager,
+            cache: Cache::mock().into_arc(),
+            caches: vec![],
+            default_timeout: None,
+            cancel: CancellationToken::new(),
         };
 
         let outputs = runner.resolve_outputs().unwrap();
@@ -1077,6 +1573,10 @@ mod tests {
             run_ctx,
             regex_cache,
             package_manager,
+            cache: Cache::mock().into_arc(),
+            caches: vec![],
+            default_timeout: None,
+            cancel: CancellationToken::new(),
         };
 
         let outputs = runner.resolve_outputs().unwrap();
@@ -1162,6 +1662,10 @@ steps:
             run_ctx,
             regex_cache,
             package_manager,
+            cache: Cache::mock().into_arc(),
+            caches: vec![],
+            default_timeout: None,
+            cancel: CancellationToken::new(),
         };
 
         let result = runner.execute().await;
@@ -1226,6 +1730,9 @@ steps:
             strategy: Some(Strategy {
                 matrix,
                 fail_fast: None,
+                include: vec![],
+                exclude: vec![],
+                max_parallel: None,
             }),
             ..Default::default()
         })));
@@ -1255,9 +1762,61 @@ steps:
             run_ctx,
             regex_cache,
             package_manager,
+            cache: Cache::mock().into_arc(),
+            caches: vec![],
+            default_timeout: None,
+            cancel: CancellationToken::new(),
         };
 
         let result = runner.execute().await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    pub async fn post_steps_run_after_a_failed_step() {
+        let sh_step = |id: &str, condition: Option<&str>| {
+            Step::ComplexSh(Box::new(ShellCommand {
+                id: id.to_string(),
+                run: "echo hello".to_string(),
+                condition: condition.map(str::to_string),
+                ..Default::default()
+            }))
+        };
+        let action = Action {
+            steps: vec![sh_step("build", Some("${{ 1 }} ${{ 2 }}"))],
+            post: vec![
+                sh_step("cleanup", None),
+                sh_step("notify", Some("${{ success() }}")),
+            ],
+            ..Default::default()
+        };
+        let config = BldConfig::default().into_arc();
+        let mut runner = ActionRunner::new(
+            Logger::mock().into_arc(),
+            action,
+            Platform::mock().into_arc(),
+            Artifacts::mock().into_arc(),
+            Cache::mock().into_arc(),
+            Regex::new(EXPR_REGEX).unwrap(),
+            CommonReadonlyRuntimeExprContext::default(),
+            config.clone(),
+            FileSystem::local(config.clone()).into_arc(),
+            Context::mock().into_arc(),
+            RegexCache::mock().into_arc(),
+            PackageManager::new(config).into_arc(),
+            None,
+            CancellationToken::new(),
+        );
+
+        assert!(runner.steps().await.is_err());
+        assert!(runner.post_steps().await.is_ok());
+        assert!(matches!(
+            runner.state.get_node_state("cleanup"),
+            Some(State::Completed)
+        ));
+        assert!(matches!(
+            runner.state.get_node_state("notify"),
+            Some(State::Default)
+        ));
+    }
 }
@@ -1,5 +1,264 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
+use bld_config::BldConfig;
+use bld_core::{
+    cache::Cache,
+    logger::Logger,
+    platform::{Platform, single_quote},
+};
+use bld_pkg::{GitCheckout, PackageManager};
+use bld_utils::sync::IntoArc;
 use futures::Future;
-use std::{collections::HashMap, pin::Pin};
+use glob::{MatchOptions, Pattern};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeSet, HashMap},
+    pin::Pin,
+};
+use tokio::fs::remove_dir_all;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{cache::v3::CacheOptions, expr::v3::context::FileHashes};
 
 pub type RecursiveFuture = Pin<Box<dyn Future<Output = Result<HashMap<String, String>>>>>;
+
+/// The most files that a single command hashes, so that its arguments stay within the
+/// limits of the shell.
+const HASH_FILES_BATCH: usize = 200;
+
+/// Resolves the options of a cache step, hashing the files of the platform for the
+/// `hash_files` calls of its expressions and then evaluating them with the hashes.
+pub async fn resolve_cache<F>(
+    platform: &Platform,
+    options: &CacheOptions,
+    mut eval: F,
+) -> Result<CacheOptions>
+where
+    F: FnMut(&str, &FileHashes) -> Result<String>,
+{
+    let mut hashes = FileHashes::default();
+    loop {
+        let resolved = options.resolve(|value| eval(value, &hashes));
+        let mut missing = hashes.take_missing();
+        if missing.is_empty() {
+            return resolved;
+        }
+        missing.sort();
+        missing.dedup();
+        for patterns in missing {
+            let hash = hash_files(platform, &patterns).await?;
+            hashes.insert(patterns, hash);
+        }
+    }
+}
+
+/// Gives the sha256 of the sha256 of every file of the platform that matches one of the
+/// glob patterns, relative to the directory that its steps run in, or an empty text if no
+/// file matched. The files are listed and hashed by commands on the platform, since they
+/// aren't on the host for most platforms.
+pub async fn hash_files(platform: &Platform, patterns: &[String]) -> Result<String> {
+    let listing = shell_output(platform, "find . -type f 2>/dev/null || true").await?;
+    let files = matching_files(&listing, patterns)?;
+    if files.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut hasher = Sha256::new();
+    for batch in files.chunks(HASH_FILES_BATCH) {
+        let args = batch
+            .iter()
+            .map(|x| single_quote(x))
+            .collect::<Vec<String>>()
+            .join(" ");
+        let command = format!(
+            "if command -v sha256sum >/dev/null 2>&1; then sha256sum -- {args}; else shasum -a 256 -- {args}; fi"
+        );
+        let output = shell_output(platform, &command).await?;
+        let digests: Vec<&str> = output
+            .lines()
+            .filter_map(|x| x.split_whitespace().next())
+            .map(|x| x.trim_start_matches('\\'))
+            .collect();
+        if digests.len() != batch.len() {
+            bail!("unable to hash the files matching {}", patterns.join(", "));
+        }
+        for digest in digests {
+            hasher.update(digest.as_bytes());
+        }
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Runs a command on the platform and gives its output instead of logging it.
+async fn shell_output(platform: &Platform, command: &str) -> Result<String> {
+    let logger = Logger::in_memory().into_arc();
+    platform
+        .shell(logger.clone(), &None, &HashMap::new(), command, None)
+        .await?;
+    logger.flush().await?;
+    logger.try_retrieve_output().await
+}
+
+/// The files of a `find` listing that match one of the glob patterns, without their
+/// leading `./` and sorted so that the hash doesn't depend on the order of the listing.
+fn matching_files(listing: &str, patterns: &[String]) -> Result<Vec<String>> {
+    let patterns = patterns
+        .iter()
+        .map(|x| Pattern::new(x.trim_start_matches("./")))
+        .collect::<Result<Vec<Pattern>, _>>()?;
+    let options = MatchOptions {
+        require_literal_separator: true,
+        ..MatchOptions::new()
+    };
+
+    let files: BTreeSet<&str> = listing
+        .lines()
+        .map(|x| x.strip_prefix("./").unwrap_or(x))
+        .filter(|x| patterns.iter().any(|p| p.matches_with(x, options)))
+        .collect();
+
+    Ok(files.into_iter().map(str::to_owned).collect())
+}
+
+/// Saves the caches restored during a job or action, once all of its steps have run. A
+/// cache that couldn't be saved is only logged, since it doesn't affect the result.
+pub async fn save_caches(
+    cache: &Cache,
+    platform: &Platform,
+    logger: &Logger,
+    pending: Vec<CacheOptions>,
+) -> Result<()> {
+    for options in pending {
+        if let Err(e) = cache
+            .save(platform, logger, &options.key, &options.paths)
+            .await
+        {
+            logger
+                .write_line(format!(
+                    "{:<15}: unable to save key {}, {e}",
+                    "Cache", options.key
+                ))
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks out the repository to a staging directory of the host and copies it to the
+/// path of the platform, returning the SHA of the checked out commit.
+pub async fn checkout(
+    package_manager: &PackageManager,
+    config: &BldConfig,
+    platform: &Platform,
+    logger: &Logger,
+    checkout: &GitCheckout,
+    path: &str,
+) -> Result<String> {
+    let staging_dir = config.tmp_full_path(&Uuid::new_v4().to_string());
+
+    let result: Result<String> = async {
+        let sha = package_manager.checkout(checkout, &staging_dir).await?;
+        platform
+            .push_as(&staging_dir.display().to_string(), path)
+            .await?;
+        Ok(sha)
+    }
+    .await;
+
+    if let Err(e) = remove_dir_all(&staging_dir).await {
+        error!(
+            "unable to clean up staging directory for checkout of {}: {e}",
+            checkout.repository
+        );
+    }
+
+    let sha = result?;
+    let reference = checkout.reference.as_deref().unwrap_or("HEAD");
+    logger
+        .write_line(format!(
+            "{:<15}: {}@{reference} ({sha}) to {path}",
+            "Checkout", checkout.repository
+        ))
+        .await?;
+
+    Ok(sha)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use bld_config::BldConfig;
+    use bld_core::{
+        logger::Logger,
+        platform::{Machine, Platform},
+    };
+    use bld_utils::sync::IntoArc;
+
+    use super::{hash_files, matching_files};
+    use crate::runner::v3::test_utils::TempDir;
+
+    #[test]
+    fn matching_files_follow_the_glob_patterns() {
+        let listing = "./Cargo.lock\n./crates/core/Cargo.lock\n./src/main.rs\n./src/lib/mod.rs\n";
+
+        let files = matching_files(listing, &["**/Cargo.lock".to_string()]).unwrap();
+        assert_eq!(files, vec!["Cargo.lock", "crates/core/Cargo.lock"]);
+
+        let files = matching_files(listing, &["src/*.rs".to_string()]).unwrap();
+        assert_eq!(files, vec!["src/main.rs"]);
+
+        let patterns = ["./src/**/*.rs".to_string(), "*.lock".to_string()];
+        let files = matching_files(listing, &patterns).unwrap();
+        assert_eq!(files, vec!["Cargo.lock", "src/lib/mod.rs", "src/main.rs"]);
+
+        assert!(
+            matching_files(listing, &["*.json".to_string()])
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[actix_web::test]
+    pub async fn hash_files_hashes_the_files_of_the_platform() {
+        let dir = TempDir::new("hash_files_hashes_the_files_of_the_platform");
+        let config = BldConfig {
+            root_dir: dir.root_dir(),
+            ..Default::default()
+        }
+        .into_arc();
+        let machine = Machine::new(
+            "hash-files",
+            config,
+            &HashMap::new(),
+            HashMap::new().into_arc(),
+        )
+        .await
+        .unwrap();
+        let platform = Platform::machine(Box::new(machine));
+        let write = |content: &str| {
+            let command = format!("mkdir -p nested && printf {content} > nested/Cargo.lock");
+            let platform = &platform;
+            async move {
+                let logger = Logger::mock().into_arc();
+                platform
+                    .shell(logger, &None, &HashMap::new(), &command, None)
+                    .await
+                    .unwrap();
+            }
+        };
+        let patterns = ["**/Cargo.lock".to_string()];
+
+        assert_eq!(hash_files(&platform, &patterns).await.unwrap(), "");
+
+        write("first").await;
+        let first = hash_files(&platform, &patterns).await.unwrap();
+        assert_eq!(first.len(), 64);
+        assert_eq!(first, hash_files(&platform, &patterns).await.unwrap());
+
+        write("second").await;
+        let second = hash_files(&platform, &patterns).await.unwrap();
+        assert_ne!(first, second);
+    }
+}
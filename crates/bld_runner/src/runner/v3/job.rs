@@ -1,43 +1,72 @@
-use std::{collections::HashMap, fmt::Write, sync::Arc};
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::{Result, anyhow, bail};
 use bld_config::{BldConfig, SshUserAuth};
 use bld_core::{
     artifacts::Artifacts,
+    cache::Cache,
     context::Context,
     fs::FileSystem,
     logger::Logger,
     platform::{
-        Image, Platform, SshAuthOptions, SshConnectOptions,
+        ContainerService, Image, Platform, SshAuthOptions, SshConnectOptions, TimedOut,
         builder::{PlatformBuilder, PlatformOptions},
     },
     regex::RegexCache,
 };
-use bld_models::dtos::ExecClientMessage;
+use bld_models::{
+    dtos::{ExecClientMessage, WorkerMessages},
+    pipeline_run_approvals::{PRA_STATE_APPROVED, PRA_STATE_PENDING, PipelineRunApprovals},
+};
 use bld_pkg::PackageManager;
 use bld_sock::ExecClient;
 use bld_utils::sync::IntoArc;
 use regex::Regex;
-use tokio::task::JoinHandle;
-use tracing::debug;
+use tokio::{sync::mpsc::Sender, task::JoinHandle, time::sleep};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error};
+use uuid::Uuid;
 
 use crate::{
     RunnerBuilder,
+    approval::v3::Approval,
     artifacts::v3::{DownloadArtifact, UploadArtifact},
+    cache::v3::{CACHE_HIT_OUTPUT, CacheOptions, CacheStep},
+    checkout::v3::{CHECKOUT_SHA_OUTPUT, CheckoutStep},
     expr::v3::{
-        context::{CommonReadonlyRuntimeExprContext, START_OF_RUN_WCTX},
+        context::{
+            CommonReadonlyRuntimeExprContext, FileHashesExprContext,
+            JobConditionWritableExprContext, START_OF_RUN_WCTX,
+        },
         exec::{CommonExprExecutor, eval_all_expressions, eval_all_expressions_map},
-        traits::EvalExpr,
+        functions::uses_status_check,
+        traits::{EvalExpr, RunStatus},
     },
     external::v3::External,
     job::v3::Job,
     pipeline::v3::Pipeline,
     registry::v3::Registry,
-    runner::v3::state::{JobState, RootState, State},
+    retry::v3::run_with_retry,
+    runner::v3::{
+        common::{checkout, resolve_cache, save_caches},
+        state::{JobState, RootState, State},
+    },
     runs_on::v3::RunsOn,
     step::v3::{ShellCommand, Step},
+    strategy::v3::combination_label,
+    timeout::v3::{
+        Cancelled, POST_STEPS_TIMEOUT, parse as parse_timeout, post_steps_token,
+        run_child_with_timeout, run_with_cancel, run_with_timeout,
+    },
 };
 
+const APPROVAL_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 pub struct JobRunnerOptions<S: RootState> {
     pub job_name: String,
     pub logger: Arc<Logger>,
@@ -50,8 +79,43 @@ pub struct JobRunnerOptions<S: RootState> {
     pub expr_rctx: Arc<CommonReadonlyRuntimeExprContext>,
     pub package_manager: Arc<PackageManager>,
     pub artifacts: Arc<Artifacts>,
+    pub cache: Arc<Cache>,
+    pub ipc: Arc<Option<Sender<WorkerMessages>>>,
+    /// Cancelled to stop the step that is running, where external steps run their child
+    /// runner with a child token of it.
+    pub cancel: CancellationToken,
     pub is_child: bool,
     pub state: S,
+    /// The combined status of the jobs that this one needs, checked by its condition.
+    pub needs_status: RunStatus,
+    /// The index of this instance among the ones of the job's matrix, which is 0 for a job
+    /// without one.
+    pub instance: usize,
+    /// The combination of the job's matrix that this instance of the job runs with.
+    pub matrix: Option<HashMap<String, String>>,
+}
+
+/// The ids of the records that a server keeps for a job and for the steps it runs, which
+/// the job updates as their states change.
+pub struct JobRecords {
+    pub job_id: String,
+    /// The platform the job runs on, which approval jobs don't have.
+    pub platform: Option<String>,
+    /// The record of the latest run of each step.
+    pub steps: HashMap<String, String>,
+    /// The combination of the matrix of the step that is about to run.
+    pub step_matrix: Option<HashMap<String, String>>,
+}
+
+impl Default for JobRecords {
+    fn default() -> Self {
+        Self {
+            job_id: Uuid::new_v4().to_string(),
+            platform: None,
+            steps: HashMap::new(),
+            step_matrix: None,
+        }
+    }
 }
 
 pub struct JobRunner<S: RootState> {
@@ -59,7 +123,14 @@ pub struct JobRunner<S: RootState> {
     pub platform: Arc<Platform>,
     pub runs_on: RunsOn,
     pub working_dir: Option<String>,
+    /// The environment variables of the job applied to each of its shell steps.
+    pub env: HashMap<String, String>,
     pub outputs: HashMap<String, String>,
+    /// The instant the job's timeout expires, along with the timeout itself.
+    pub deadline: Option<(Instant, Duration)>,
+    /// The caches restored by the job's steps that will be saved once all of them have run.
+    pub caches: Vec<CacheOptions>,
+    pub records: JobRecords,
 }
 
 impl<S: RootState> JobRunner<S> {
@@ -79,27 +150,62 @@ impl<S: RootState> JobRunner<S> {
         // Every runs_on field is consumed when building the platform, before any step has
         // run, so its expressions are limited to the start of run context.
         let runs_on = Self::resolve_runs_on(job, &options)?;
+        let services = Self::resolve_services(job, &options)?;
 
-        let platform = build_platform(
-            &runs_on,
-            options.config.clone(),
-            options.logger.clone(),
-            options.run_ctx.clone(),
-            options.expr_rctx.clone(),
-        )
-        .await?;
+        // An approval job doesn't run any steps so there is nothing to build a platform for.
+        let platform = if job.approval.is_some() {
+            Platform::mock().into_arc()
+        } else {
+            let instance = format!("{}-{}", options.job_name, options.instance);
+            build_platform(
+                &runs_on,
+                services,
+                &instance,
+                options.config.clone(),
+                options.logger.clone(),
+                options.run_ctx.clone(),
+                options.expr_rctx.clone(),
+            )
+            .await?
+        };
 
         let working_dir = job.working_dir.clone();
+        let env = job.env.clone();
+        let records = JobRecords {
+            platform: job.approval.is_none().then(|| platform.label().to_owned()),
+            ..Default::default()
+        };
 
         Ok(JobRunner {
             options,
             platform,
             runs_on,
             working_dir,
+            env,
             outputs: HashMap::new(),
+            deadline: None,
+            caches: vec![],
+            records,
         })
     }
 
+    fn resolve_services(job: &Job, options: &JobRunnerOptions<S>) -> Result<Vec<ContainerService>> {
+        let exec = CommonExprExecutor::new(
+            options.pipeline.as_ref(),
+            options.expr_rctx.as_ref(),
+            &START_OF_RUN_WCTX,
+        );
+
+        job.services
+            .iter()
+            .map(|(name, service)| {
+                service.resolve(name, |value| {
+                    eval_all_expressions(&exec, &options.expr_regex, value)
+                })
+            })
+            .collect()
+    }
+
     fn resolve_runs_on(job: &Job, options: &JobRunnerOptions<S>) -> Result<RunsOn> {
         let exec = CommonExprExecutor::new(
             options.pipeline.as_ref(),
@@ -111,6 +217,10 @@ impl<S: RootState> JobRunner<S> {
             .resolve(|value| eval_all_expressions(&exec, &options.expr_regex, value))
     }
 
+    /// Runs the job and then disposes of its platform, whether its steps failed or the job
+    /// was cancelled through its token, since the post steps still run in that case. The
+    /// platform is only disposed if this future is driven to completion, so a job that has
+    /// to stop early is cancelled and awaited rather than dropped.
     pub async fn run(mut self) -> Result<Self> {
         let pipeline = self.options.pipeline.clone();
         let (_, job) = pipeline
@@ -124,33 +234,243 @@ impl<S: RootState> JobRunner<S> {
                 })
             })?;
 
-        self.info().await?;
+        let result = self.run_job(job).await;
+        let disposed = self
+            .dispose_platform(job)
+            .await
+            .inspect_err(|e| error!("unable to dispose platform of job, {e}"));
+
+        result?;
+        disposed?;
+        Ok(self)
+    }
+
+    async fn run_job(&mut self, job: &Job) -> Result<()> {
+        if job.approval.is_none() {
+            self.info().await?;
+        }
 
         if !self.job_condition(job.condition.as_deref())? {
             debug!("condition failed, skiping step");
-            return Ok(self);
+            return Ok(());
         }
 
-        self.options.state.update_state(State::Running);
+        self.set_state(State::Running).await;
 
-        debug!("starting execution of pipeline steps");
-        self.run_job_steps(job).await.inspect_err(|e| {
-            self.options.state.update_state(State::Failed {
-                error: e.to_string(),
-            })
-        })?;
+        let result = self.run_main(job).await;
+        match result.as_ref() {
+            Err(e) if e.is::<Cancelled>() => {
+                self.set_state(State::Cancelled {
+                    error: e.to_string(),
+                })
+                .await;
+            }
+            Err(e) => {
+                self.set_state(State::Failed {
+                    error: e.to_string(),
+                })
+                .await;
+            }
+            Ok(()) => {}
+        }
 
-        let outputs = self.resolve_outputs(job).inspect_err(|e| {
-            self.options.state.update_state(State::Failed {
+        let post = self.run_post_steps(job).await;
+        if let Err(e) = post.as_ref() {
+            self.set_state(State::Failed {
                 error: e.to_string(),
             })
-        })?;
-        self.outputs = outputs;
+            .await;
+        }
 
-        self.options.state.update_state(State::Completed);
+        result.and(post)?;
+        self.set_state(State::Completed).await;
+        Ok(())
+    }
 
-        self.dispose_platform(job).await?;
-        Ok(self)
+    /// Updates the state of the job and records it, where the record of the job is added
+    /// once it starts running. Jobs of a child runner are part of a step of their parent so
+    /// they aren't recorded.
+    async fn set_state(&mut self, state: State) {
+        if !self.options.is_child {
+            let id = self.records.job_id.to_owned();
+            let result = match &state {
+                State::Running => {
+                    self.options
+                        .run_ctx
+                        .add_job(
+                            id,
+                            self.options.job_name.to_owned(),
+                            self.options.matrix.as_ref().map(combination_label),
+                            self.records.platform.to_owned(),
+                        )
+                        .await
+                }
+                state => {
+                    self.options
+                        .run_ctx
+                        .set_job_state(id, state.outcome().to_owned(), state.error())
+                        .await
+                }
+            };
+            let _ = result.inspect_err(|e| error!("unable to record state of job, {e}"));
+        }
+        self.options.state.update_state(state);
+    }
+
+    /// Updates the state of a step and records it, where a new record is added each time
+    /// the step starts running, such as once for every combination of its matrix.
+    async fn set_step_state(&mut self, step: &Step, state: State) {
+        if !self.options.is_child {
+            let result = match &state {
+                State::Running => {
+                    let id = Uuid::new_v4().to_string();
+                    self.records
+                        .steps
+                        .insert(step.id().to_owned(), id.to_owned());
+                    self.options
+                        .run_ctx
+                        .add_step(
+                            id,
+                            self.records.job_id.to_owned(),
+                            step.id().to_owned(),
+                            step.name().map(str::to_owned),
+                            self.records.step_matrix.as_ref().map(combination_label),
+                        )
+                        .await
+                }
+                state => match self.records.steps.get(step.id()) {
+                    Some(id) => {
+                        self.options
+                            .run_ctx
+                            .set_step_state(
+                                id.to_owned(),
+                                state.outcome().to_owned(),
+                                state.error(),
+                            )
+                            .await
+                    }
+                    None => Ok(()),
+                },
+            };
+            let _ = result.inspect_err(|e| error!("unable to record state of step, {e}"));
+        }
+        self.options.state.update_node_state(step.id(), state);
+    }
+
+    async fn run_main(&mut self, job: &Job) -> Result<()> {
+        if let Some(approval) = job.approval.as_ref() {
+            return self.wait_for_approval(approval).await;
+        }
+
+        debug!("starting execution of pipeline steps");
+        self.run_job_steps(job).await?;
+
+        let caches = std::mem::take(&mut self.caches);
+        save_caches(
+            &self.options.cache,
+            &self.platform,
+            &self.options.logger,
+            caches,
+        )
+        .await?;
+
+        self.outputs = self.resolve_outputs(job)?;
+        Ok(())
+    }
+
+    /// Waits for someone to approve the run, letting the supervisor know that the worker
+    /// is waiting, and fails the job when the run is rejected or the approval times out.
+    async fn wait_for_approval(&mut self, approval: &Approval) -> Result<()> {
+        let message = approval
+            .message
+            .as_deref()
+            .map(|message| self.eval_all_expr(message))
+            .transpose()?;
+        let timeout = approval.timeout.as_deref().map(parse_timeout).transpose()?;
+
+        let mut info = format!(
+            "{:<15}: {}",
+            "Approval",
+            message.as_deref().unwrap_or("waiting for approval")
+        );
+        if !approval.approvers.is_empty() {
+            write!(
+                info,
+                "\n{:<15}: {}",
+                "Approvers",
+                approval.approvers.join(", ")
+            )?;
+        }
+        self.options.logger.write_line(info).await?;
+
+        let run_ctx = self.options.run_ctx.clone();
+        let Some(id) = run_ctx
+            .add_approval(
+                self.options.job_name.clone(),
+                message,
+                approval.approvers.clone(),
+            )
+            .await?
+        else {
+            debug!("no approval needed for the current context");
+            return Ok(());
+        };
+
+        // the worker keeps its slot on the supervisor while it polls for the decision.
+        let cancel = self.options.cancel.clone();
+        let result = run_with_cancel(
+            &cancel,
+            run_with_timeout(timeout, poll_approval(&run_ctx, &id)),
+        )
+        .await;
+        if result
+            .as_ref()
+            .is_err_and(|e| e.downcast_ref::<TimedOut>().is_some())
+        {
+            run_ctx.set_approval_as_timed_out(id).await?;
+        }
+        run_ctx
+            .set_pipeline_as_running(self.options.expr_rctx.run_id.to_owned())
+            .await?;
+
+        let approval = result?;
+        let decided_by = approval.decided_by.unwrap_or_default();
+        if approval.state != PRA_STATE_APPROVED {
+            bail!("run was {} by {decided_by}", approval.state);
+        }
+
+        self.options
+            .logger
+            .write_line(format!("{:<15}: {decided_by}", "Approved by"))
+            .await
+    }
+
+    /// Runs every post step, whether the main steps failed or not, returning the first
+    /// error. They aren't bound by the job's timeout but by [`POST_STEPS_TIMEOUT`], and
+    /// they still run once the job has been cancelled so that they can clean up.
+    async fn run_post_steps(&mut self, job: &Job) -> Result<()> {
+        if job.post.is_empty() {
+            return Ok(());
+        }
+
+        debug!("starting execution of post steps");
+        self.deadline = None;
+        self.options.cancel = post_steps_token(&self.options.cancel);
+        let cancel = self.options.cancel.clone();
+        let matrix = self.options.matrix.clone();
+        // boxed to keep the future of the job small, since its steps may call actions.
+        let post = Box::pin(async {
+            let mut result = Ok(());
+            for step in job.post.iter() {
+                if let Err(e) = self.run_step(step, matrix.as_ref()).await
+                    && result.is_ok()
+                {
+                    result = Err(e);
+                }
+            }
+            result
+        });
+        run_child_with_timeout(Some(POST_STEPS_TIMEOUT), &cancel, post).await
     }
 
     fn resolve_outputs(&mut self, job: &Job) -> Result<HashMap<String, String>> {
@@ -164,41 +484,48 @@ impl<S: RootState> JobRunner<S> {
     }
 
     async fn run_job_steps(&mut self, job: &Job) -> Result<()> {
-        let Some(strategy) = job.strategy.as_ref() else {
-            for step in job.steps.iter() {
-                self.run_step(step, None).await?;
-            }
-            return Ok(());
-        };
+        self.deadline = job
+            .timeout
+            .as_deref()
+            .map(parse_timeout)
+            .transpose()?
+            .map(|timeout| (Instant::now() + timeout, timeout));
 
-        // The job's strategy is resolved before any of its steps have run, so the same
-        // start of run limits as runs_on apply to it.
-        let exec = CommonExprExecutor::new(
-            self.options.pipeline.as_ref(),
-            self.options.expr_rctx.as_ref(),
-            &START_OF_RUN_WCTX,
-        );
-        let combinations = strategy.combinations(&exec)?;
-        let fail_fast = strategy.resolve_fail_fast(&exec)?;
+        let matrix = self.options.matrix.clone();
+        if let Some(matrix) = matrix.as_ref() {
+            self.options.state.set_matrix(matrix.clone());
+        }
+        self.run_steps(&job.steps, matrix.as_ref()).await
+    }
 
-        let mut errors: Vec<String> = Vec::new();
-        for combination in combinations {
-            for step in job.steps.iter() {
-                if let Err(e) = self.run_step(step, Some(&combination)).await {
-                    if fail_fast {
-                        return Err(e);
-                    }
-                    errors.push(e.to_string());
-                    break;
-                }
+    /// Runs the steps in order, where once one of them fails the rest are skipped unless
+    /// their condition checks the status of the job. The first error is returned once all
+    /// of them have been through.
+    async fn run_steps(
+        &mut self,
+        steps: &[Step],
+        job_matrix: Option<&HashMap<String, String>>,
+    ) -> Result<()> {
+        let mut result = Ok(());
+        for step in steps {
+            if self.options.cancel.is_cancelled() {
+                debug!("skipping the remaining steps since the job was cancelled");
+                return result.and(Err(anyhow!(Cancelled)));
             }
-        }
 
-        if errors.is_empty() {
-            Ok(())
-        } else {
-            Err(anyhow!(errors.join("\n")))
+            let status_check = step.condition().is_some_and(uses_status_check);
+            if !status_check && self.options.state.get_run_status()? != RunStatus::Success {
+                debug!("skipping step {} since an earlier step failed", step.id());
+                continue;
+            }
+
+            if let Err(e) = self.run_step(step, job_matrix).await
+                && result.is_ok()
+            {
+                result = Err(e);
+            }
         }
+        result
     }
 
     async fn run_step(
@@ -207,6 +534,7 @@ impl<S: RootState> JobRunner<S> {
         job_matrix: Option<&HashMap<String, String>>,
     ) -> Result<()> {
         let Some(strategy) = step.strategy() else {
+            self.records.step_matrix = None;
             if let Some(job_matrix) = job_matrix {
                 self.options.state.set_matrix(job_matrix.clone());
             }
@@ -223,6 +551,8 @@ impl<S: RootState> JobRunner<S> {
 
         let mut errors: Vec<String> = Vec::new();
         for combination in combinations {
+            self.tag_logger(Some(&combination)).await?;
+            self.records.step_matrix = Some(combination.clone());
             let mut merged = job_matrix.cloned().unwrap_or_default();
             merged.extend(combination);
             self.options.state.set_matrix(merged);
@@ -233,6 +563,7 @@ impl<S: RootState> JobRunner<S> {
                 errors.push(e.to_string());
             }
         }
+        self.tag_logger(None).await?;
 
         if errors.is_empty() {
             Ok(())
@@ -241,6 +572,12 @@ impl<S: RootState> JobRunner<S> {
         }
     }
 
+    /// Tags the lines of a step with the combination of its matrix that they belong to,
+    /// since the job's own combination is already part of the name of its logger.
+    async fn tag_logger(&self, matrix: Option<&HashMap<String, String>>) -> Result<()> {
+        self.options.logger.tag(matrix.map(combination_label)).await
+    }
+
     async fn info(&self) -> Result<()> {
         debug!("printing job informantion");
         self.options
@@ -250,17 +587,18 @@ impl<S: RootState> JobRunner<S> {
     }
 
     async fn step(&mut self, step: &Step) -> Result<()> {
+        self.records.steps.remove(step.id());
         let result = match self.condition(step.condition()) {
             Ok(true) => {
-                self.options
-                    .state
-                    .update_node_state(step.id(), State::Running);
-                match step {
-                    Step::ComplexSh(complex) => self.complex_shell(complex).await,
-                    Step::ExternalFile(external) => self.external(external).await,
-                    Step::DownloadArtifact(download) => self.download_artifact(download).await,
-                    Step::UploadArtifact(upload) => self.upload_artifact(upload).await,
-                }
+                self.set_step_state(step, State::Running).await;
+                let logger = self.options.logger.clone();
+                let cancel = self.options.cancel.clone();
+                let deadline = self.deadline;
+                run_with_retry(step.retry(), &logger, &cancel, deadline, async || {
+                    let timeout = self.step_timeout(step)?;
+                    self.run_step_inner(step, timeout).await
+                })
+                .await
             }
             Ok(false) => {
                 debug!("condition failed, skiping step");
@@ -269,34 +607,162 @@ impl<S: RootState> JobRunner<S> {
             Err(e) => Err(e),
         };
 
-        result
-            .inspect(|_| {
+        match result {
+            Ok(()) => {
+                self.set_step_state(step, State::Completed).await;
+                Ok(())
+            }
+            Err(e) => self.step_failed(step, e).await,
+        }
+    }
+
+    /// Records the failure of a step, swallowing it if the step is allowed to fail. A step
+    /// that timed out because the job's deadline passed, or that stopped because the job
+    /// was cancelled, is cancelled instead.
+    async fn step_failed(&mut self, step: &Step, error: anyhow::Error) -> Result<()> {
+        let deadline_passed = self
+            .deadline
+            .is_some_and(|(deadline, _)| Instant::now() >= deadline);
+        if (deadline_passed && error.is::<TimedOut>()) || error.is::<Cancelled>() {
+            self.set_step_state(
+                step,
+                State::Cancelled {
+                    error: error.to_string(),
+                },
+            )
+            .await;
+            return Err(error);
+        }
+
+        self.set_step_state(
+            step,
+            State::Failed {
+                error: error.to_string(),
+            },
+        )
+        .await;
+
+        let continue_on_error = {
+            let exec = CommonExprExecutor::new(
+                self.options.pipeline.as_ref(),
+                self.options.expr_rctx.as_ref(),
+                &self.options.state,
+            );
+            step.resolve_continue_on_error(&exec)
+        };
+
+        match continue_on_error {
+            Ok(true) => {
+                self.set_step_state(
+                    step,
+                    State::ContinuedOnError {
+                        error: error.to_string(),
+                    },
+                )
+                .await;
                 self.options
-                    .state
-                    .update_node_state(step.id(), State::Completed)
-            })
-            .inspect_err(|e| {
-                self.options.state.update_node_state(
-                    step.id(),
+                    .logger
+                    .write_line(format!(
+                        "step {} failed with: {error}, continuing since the step is allowed to fail",
+                        step.id()
+                    ))
+                    .await?;
+                Ok(())
+            }
+            Ok(false) => Err(error),
+            Err(e) => {
+                self.set_step_state(
+                    step,
                     State::Failed {
                         error: e.to_string(),
                     },
                 )
-            })
+                .await;
+                Err(e)
+            }
+        }
     }
 
-    async fn complex_shell(&mut self, complex: &ShellCommand) -> Result<()> {
+    /// Runs a step until it finishes, times out or the job is cancelled. An external step
+    /// isn't dropped in the last two cases, its child runner is cancelled instead and
+    /// awaited so that it cleans up after itself.
+    async fn run_step_inner(&mut self, step: &Step, timeout: Option<Duration>) -> Result<()> {
+        let cancel = self.options.cancel.clone();
+        match step {
+            Step::ComplexSh(complex) => {
+                run_with_cancel(&cancel, self.complex_shell(complex, timeout)).await
+            }
+            Step::ExternalFile(external) => {
+                let child = cancel.child_token();
+                run_child_with_timeout(timeout, &child, self.external(external, &child)).await
+            }
+            Step::DownloadArtifact(download) => {
+                let future = run_with_timeout(timeout, self.download_artifact(download));
+                run_with_cancel(&cancel, future).await
+            }
+            Step::UploadArtifact(upload) => {
+                let future = run_with_timeout(timeout, self.upload_artifact(upload));
+                run_with_cancel(&cancel, future).await
+            }
+            Step::Cache(cache) => {
+                run_with_cancel(&cancel, run_with_timeout(timeout, self.cache(cache))).await
+            }
+            Step::Checkout(step) => {
+                run_with_cancel(&cancel, run_with_timeout(timeout, self.checkout(step))).await
+            }
+        }
+    }
+
+    /// The step's own timeout, or the pipeline's default one, capped by the time left
+    /// until the job's deadline. Once the deadline has passed only the steps that check
+    /// the status of the job can run, limited by their own timeout, so that they are able
+    /// to clean up.
+    fn step_timeout(&self, step: &Step) -> Result<Option<Duration>> {
+        let timeout = step
+            .timeout()
+            .or(self.options.pipeline.timeout.as_deref())
+            .map(parse_timeout)
+            .transpose()?;
+
+        let Some((deadline, job_timeout)) = self.deadline else {
+            return Ok(timeout);
+        };
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            if step.condition().is_some_and(uses_status_check) {
+                return Ok(timeout);
+            }
+            bail!(TimedOut::new(job_timeout));
+        }
+
+        Ok(Some(
+            timeout.map_or(remaining, |timeout| timeout.min(remaining)),
+        ))
+    }
+
+    async fn complex_shell(
+        &mut self,
+        complex: &ShellCommand,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
         if let Some(name) = complex.name.as_ref() {
             let mut message = String::new();
             writeln!(message, "{:<15}: {name}", "Step")?;
             self.options.logger.write_line(message).await?;
         }
-        self.shell(&complex.id, &complex.working_dir, &complex.run)
-            .await?;
+        self.shell(
+            &complex.id,
+            &complex.working_dir,
+            &complex.env,
+            &complex.run,
+            timeout,
+        )
+        .await?;
         Ok(())
     }
 
-    async fn external(&mut self, external: &External) -> Result<()> {
+    async fn external(&mut self, external: &External, cancel: &CancellationToken) -> Result<()> {
         if let Some(name) = external.name.as_ref() {
             let mut message = String::new();
             writeln!(message, "{:<15}: {name}", "Step")?;
@@ -307,7 +773,7 @@ impl<S: RootState> JobRunner<S> {
 
         match external.server.as_ref() {
             Some(server) => self.server_external(server, external).await?,
-            None => self.local_external(external).await?,
+            None => self.local_external(external, cancel).await?,
         };
 
         Ok(())
@@ -329,7 +795,85 @@ impl<S: RootState> JobRunner<S> {
             .await
     }
 
-    async fn local_external(&mut self, details: &External) -> Result<()> {
+    async fn cache(&mut self, step: &CacheStep) -> Result<()> {
+        if let Some(name) = step.name.as_ref() {
+            let mut message = String::new();
+            writeln!(message, "{:<15}: {name}", "Step")?;
+            self.options.logger.write_line(message).await?;
+        }
+
+        let options = resolve_cache(&self.platform, &step.cache, |value, hashes| {
+            let rctx = FileHashesExprContext::new(self.options.expr_rctx.as_ref(), hashes);
+            let expr_exec =
+                CommonExprExecutor::new(self.options.pipeline.as_ref(), &rctx, &self.options.state);
+            eval_all_expressions(&expr_exec, &self.options.expr_regex, value)
+        })
+        .await?;
+        let restored = self
+            .options
+            .cache
+            .restore(
+                &self.platform,
+                &self.options.logger,
+                &options.key,
+                &options.restore_keys,
+                &options.paths,
+            )
+            .await?;
+
+        let hit = restored.as_deref() == Some(options.key.as_str());
+        self.options.state.set_outputs(
+            &step.id,
+            HashMap::from([(CACHE_HIT_OUTPUT.to_string(), hit.to_string())]),
+        )?;
+
+        if !hit {
+            self.caches.push(options);
+        }
+
+        Ok(())
+    }
+
+    async fn checkout(&mut self, step: &CheckoutStep) -> Result<()> {
+        if let Some(name) = step.name.as_ref() {
+            let mut message = String::new();
+            writeln!(message, "{:<15}: {name}", "Step")?;
+            self.options.logger.write_line(message).await?;
+        }
+
+        let (options, path) = step.checkout.resolve(|value| self.eval_all_expr(value))?;
+        let sha = checkout(
+            &self.options.package_manager,
+            &self.options.config,
+            &self.platform,
+            &self.options.logger,
+            &options,
+            &path,
+        )
+        .await?;
+
+        self.options
+            .run_ctx
+            .add_checkout(
+                self.options.job_name.clone(),
+                options.repository,
+                options.reference,
+                sha.clone(),
+                path,
+            )
+            .await?;
+
+        self.options.state.set_outputs(
+            &step.id,
+            HashMap::from([(CHECKOUT_SHA_OUTPUT.to_string(), sha)]),
+        )
+    }
+
+    async fn local_external(
+        &mut self,
+        details: &External,
+        cancel: &CancellationToken,
+    ) -> Result<()> {
         debug!("building runner for child file");
 
         let inputs = self.variables_external(&details.with)?;
@@ -344,11 +888,16 @@ impl<S: RootState> JobRunner<S> {
             .logger(self.options.logger.clone())
             .env(env.into_arc())
             .inputs(inputs.into_arc())
+            .secrets(self.options.expr_rctx.secrets.clone().unwrap_or_default())
             .context(self.options.run_ctx.clone())
             .platform(self.platform.clone())
             .regex_cache(self.options.regex_cache.clone())
             .package_manager(self.options.package_manager.clone())
             .artifacts(self.options.artifacts.clone())
+            .cache(self.options.cache.clone())
+            .ipc(self.options.ipc.clone())
+            .default_timeout(self.options.pipeline.timeout.clone())
+            .cancel(cancel.clone())
             .is_child(true)
             .build()
             .await?;
@@ -386,6 +935,8 @@ impl<S: RootState> JobRunner<S> {
                 name: details.uses.to_owned(),
                 env: Some(env),
                 inputs: Some(inputs),
+                parent_run_id: Some(self.options.expr_rctx.run_id.to_owned()),
+                priority: None,
             })
             .await
     }
@@ -410,22 +961,45 @@ impl<S: RootState> JobRunner<S> {
         working_dir.map(|wd| self.eval_all_expr(&wd)).transpose()
     }
 
+    /// Merges the environment of the job with the one of the step, with the step taking
+    /// precedence, and evaluates its values against the current state of the job.
+    fn resolve_env(&self, env: &HashMap<String, String>) -> Result<HashMap<String, String>> {
+        let mut merged = self.env.clone();
+        merged.extend(env.iter().map(|(k, v)| (k.to_owned(), v.to_owned())));
+
+        let expr_exec = CommonExprExecutor::new(
+            self.options.pipeline.as_ref(),
+            self.options.expr_rctx.as_ref(),
+            &self.options.state,
+        );
+        eval_all_expressions_map(&expr_exec, &self.options.expr_regex, &merged)
+    }
+
     async fn shell(
         &mut self,
         step_id: &str,
         working_dir: &Option<String>,
+        env: &HashMap<String, String>,
         command: &str,
+        timeout: Option<Duration>,
     ) -> Result<()> {
         debug!("start execution of exec section for step");
         debug!("executing shell command {}", command);
 
         let command = self.eval_all_expr(command)?;
         let working_dir = self.resolve_working_dir(working_dir)?;
+        let env = self.resolve_env(env)?;
 
         debug!("sending command to platform");
         let outputs = self
             .platform
-            .shell(self.options.logger.clone(), &working_dir, &command)
+            .shell(
+                self.options.logger.clone(),
+                &working_dir,
+                &env,
+                &command,
+                timeout,
+            )
             .await?;
 
         self.options.state.set_outputs(step_id, outputs)?;
@@ -434,12 +1008,13 @@ impl<S: RootState> JobRunner<S> {
     }
 
     /// A job's condition is evaluated before any of its steps have run, so it can only use
-    /// the start of run expressions.
+    /// the start of run expressions along with the status of the jobs it needs.
     fn job_condition(&self, condition: Option<&str>) -> Result<bool> {
+        let wctx = JobConditionWritableExprContext::new(self.options.needs_status);
         let expr_exec = CommonExprExecutor::new(
             self.options.pipeline.as_ref(),
             self.options.expr_rctx.as_ref(),
-            &START_OF_RUN_WCTX,
+            &wctx,
         );
         self.eval_condition(&expr_exec, condition)
     }
@@ -499,6 +1074,16 @@ impl<S: RootState> JobRunner<S> {
     }
 }
 
+async fn poll_approval(run_ctx: &Context, id: &str) -> Result<PipelineRunApprovals> {
+    loop {
+        let approval = run_ctx.approval(id.to_owned()).await?;
+        if approval.state != PRA_STATE_PENDING {
+            return Ok(approval);
+        }
+        sleep(APPROVAL_POLL_INTERVAL).await;
+    }
+}
+
 pub struct RunningJob {
     pub name: String,
     pub handle: JoinHandle<Result<JobRunner<JobState>>>,
@@ -521,6 +1106,8 @@ impl RunningJob {
 
 pub async fn build_platform(
     runs_on: &RunsOn,
+    services: Vec<ContainerService>,
+    instance: &str,
     config: Arc<BldConfig>,
     logger: Arc<Logger>,
     run_ctx: Arc<Context>,
@@ -528,13 +1115,21 @@ pub async fn build_platform(
 ) -> Result<Arc<Platform>> {
     let volumes = runs_on.volumes().to_vec();
 
+    if !services.is_empty() && !runs_on.is_container() {
+        bail!("services are only supported for jobs that run on a container");
+    }
+
     let options = match runs_on {
         RunsOn::ContainerOrMachine(image) if image == "machine" => PlatformOptions::Machine,
 
+        // the run has already been routed to an agent with the labels.
+        RunsOn::Labels { .. } => PlatformOptions::Machine,
+
         RunsOn::ContainerOrMachine(image) => PlatformOptions::Container {
             image: Image::Use(image),
             docker_url: None,
             volumes,
+            services,
         },
 
         RunsOn::Pull {
@@ -557,6 +1152,7 @@ pub async fn build_platform(
                 docker_url: docker_url.as_deref(),
                 image,
                 volumes,
+                services,
             }
         }
 
@@ -570,6 +1166,7 @@ pub async fn build_platform(
             image: Image::build(name, dockerfile, tag),
             docker_url: docker_url.as_deref(),
             volumes,
+            services,
         },
 
         RunsOn::SshFromGlobalConfig { ssh_config } => {
@@ -619,6 +1216,7 @@ pub async fn build_platform(
     let conn = run_ctx.get_conn();
     let platform = PlatformBuilder::default()
         .run_id(&expr_rctx.run_id)
+        .instance(instance)
         .config(config.clone())
         .options(options)
         .pipeline_env(expr_rctx.env.as_ref())
@@ -636,23 +1234,32 @@ pub async fn build_platform(
 mod tests {
     use bld_config::BldConfig;
     use bld_core::{
-        artifacts::Artifacts, context::Context, fs::FileSystem, logger::Logger, platform::Platform,
+        artifacts::Artifacts,
+        cache::Cache,
+        context::Context,
+        fs::FileSystem,
+        logger::Logger,
+        platform::{Machine, Platform, TimedOut},
         regex::RegexCache,
     };
     use bld_pkg::PackageManager;
     use bld_utils::sync::IntoArc;
     use regex::Regex;
+    use tokio_util::sync::CancellationToken;
 
     use anyhow::Result;
     use bld_config::{SshConfig, SshUserAuth};
-    use std::collections::HashMap;
+    use std::{
+        collections::HashMap,
+        time::{Duration, Instant},
+    };
 
     use crate::{
         artifacts::v3::DownloadArtifact,
         expr::v3::{
             context::CommonReadonlyRuntimeExprContext,
             parser::EXPR_REGEX,
-            traits::{ExprText, ExprValue, OutputScope, WritableRuntimeExprContext},
+            traits::{ExprText, ExprValue, OutputScope, RunStatus, WritableRuntimeExprContext},
         },
         external::v3::External,
         job::v3::Job,
@@ -661,10 +1268,11 @@ mod tests {
         runner::v3::{MockRootState, RootState, State, state::JobState, test_utils::TempDir},
         runs_on::v3::RunsOn,
         step::v3::{ShellCommand, Step},
-        strategy::v3::{FailFastValue, MatrixValue, Strategy},
+        strategy::v3::{MatrixValue, Strategy},
+        timeout::v3::Cancelled,
     };
 
-    use super::{JobRunner, JobRunnerOptions};
+    use super::{JobRecords, JobRunner, JobRunnerOptions};
 
     #[test]
     pub fn condition_eval_success() {
@@ -696,13 +1304,23 @@ mod tests {
             artifacts,
             is_child: false,
             state,
+            cache: Cache::mock().into_arc(),
+            ipc: None.into_arc(),
+            needs_status: RunStatus::Success,
+            cancel: CancellationToken::new(),
+            instance: 0,
+            matrix: None,
         };
         let job = JobRunner {
             options,
             platform,
             runs_on: RunsOn::default(),
             working_dir: None,
+            env: HashMap::new(),
             outputs: HashMap::new(),
+            deadline: None,
+            caches: vec![],
+            records: JobRecords::default(),
         };
 
         assert!(matches!(job.condition(None), Ok(true)));
@@ -752,13 +1370,23 @@ mod tests {
             artifacts,
             is_child: false,
             state,
+            cache: Cache::mock().into_arc(),
+            ipc: None.into_arc(),
+            needs_status: RunStatus::Success,
+            cancel: CancellationToken::new(),
+            instance: 0,
+            matrix: None,
         };
         let job = JobRunner {
             options,
             platform,
             runs_on: RunsOn::default(),
             working_dir: None,
+            env: HashMap::new(),
             outputs: HashMap::new(),
+            deadline: None,
+            caches: vec![],
+            records: JobRecords::default(),
         };
 
         // A text value of "true" starts the step, mirroring an input whose value is "true".
@@ -813,13 +1441,23 @@ mod tests {
             artifacts,
             is_child: false,
             state,
+            cache: Cache::mock().into_arc(),
+            ipc: None.into_arc(),
+            needs_status: RunStatus::Success,
+            cancel: CancellationToken::new(),
+            instance: 0,
+            matrix: None,
         };
         let mut job = JobRunner {
             options,
             platform,
             runs_on: RunsOn::default(),
             working_dir: None,
+            env: HashMap::new(),
             outputs: HashMap::new(),
+            deadline: None,
+            caches: vec![],
+            records: JobRecords::default(),
         };
 
         // Neither the step nor the job has a value: there is no result.
@@ -855,6 +1493,87 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn step_timeout_uses_pipeline_default_and_job_deadline() {
+        let job_name = "main".to_string();
+        let config = BldConfig::default().into_arc();
+        let logger = Logger::mock().into_arc();
+        let fs = FileSystem::local(config.clone()).into_arc();
+        let run_ctx = Context::mock().into_arc();
+        let platform = Platform::mock().into_arc();
+        let artifacts = Artifacts::mock().into_arc();
+        let regex_cache = RegexCache::mock().into_arc();
+        let expr_regex = Regex::new(EXPR_REGEX).unwrap().into_arc();
+        let expr_rctx = CommonReadonlyRuntimeExprContext::default().into_arc();
+        let state = JobState::default();
+        let package_manager = PackageManager::new(config.clone()).into_arc();
+        let pipeline = Pipeline {
+            timeout: Some("10m".to_string()),
+            ..Default::default()
+        }
+        .into_arc();
+
+        let options = JobRunnerOptions {
+            job_name,
+            logger,
+            config,
+            fs,
+            run_ctx,
+            pipeline,
+            regex_cache,
+            expr_regex,
+            expr_rctx,
+            package_manager,
+            artifacts,
+            is_child: false,
+            state,
+            cache: Cache::mock().into_arc(),
+            ipc: None.into_arc(),
+            needs_status: RunStatus::Success,
+            cancel: CancellationToken::new(),
+            instance: 0,
+            matrix: None,
+        };
+        let mut job = JobRunner {
+            options,
+            platform,
+            runs_on: RunsOn::default(),
+            working_dir: None,
+            env: HashMap::new(),
+            outputs: HashMap::new(),
+            deadline: None,
+            caches: vec![],
+            records: JobRecords::default(),
+        };
+
+        let default_step = Step::ComplexSh(Box::default());
+        let step = Step::ComplexSh(Box::new(ShellCommand {
+            timeout: Some("30s".to_string()),
+            ..Default::default()
+        }));
+
+        // A step without a timeout falls back to the pipeline's default one.
+        let timeout = job.step_timeout(&default_step).unwrap();
+        assert_eq!(timeout, Some(Duration::from_secs(600)));
+
+        // The step's own timeout takes precedence over the pipeline's default one.
+        let timeout = job.step_timeout(&step).unwrap();
+        assert_eq!(timeout, Some(Duration::from_secs(30)));
+
+        // A job deadline closer than the step's timeout caps it.
+        job.deadline = Some((
+            Instant::now() + Duration::from_secs(5),
+            Duration::from_secs(5),
+        ));
+        let timeout = job.step_timeout(&step).unwrap().unwrap();
+        assert!(timeout <= Duration::from_secs(5));
+
+        // A job deadline that has already passed fails the step as timed out.
+        job.deadline = Some((Instant::now(), Duration::from_secs(5)));
+        let error = job.step_timeout(&step).unwrap_err();
+        assert!(error.downcast_ref::<TimedOut>().is_some(), "{error}");
+    }
+
     fn resolve_runs_on(runs_on: RunsOn, inputs: Vec<(&str, &str)>) -> Result<RunsOn> {
         let job_name = "main".to_string();
         let config = BldConfig::default().into_arc();
@@ -903,6 +1622,12 @@ mod tests {
             artifacts,
             is_child: false,
             state,
+            cache: Cache::mock().into_arc(),
+            ipc: None.into_arc(),
+            needs_status: RunStatus::Success,
+            cancel: CancellationToken::new(),
+            instance: 0,
+            matrix: None,
         };
 
         JobRunner::resolve_runs_on(&job, &options)
@@ -1048,6 +1773,9 @@ mod tests {
         let expr_rctx = CommonReadonlyRuntimeExprContext::default().into_arc();
         let package_manager = PackageManager::new(config.clone()).into_arc();
         let mut state = MockRootState::new();
+        state
+            .expect_get_run_status()
+            .returning(|| Ok(RunStatus::Success));
 
         let mut steps = vec![];
 
@@ -1113,13 +1841,23 @@ mod tests {
             artifacts,
             is_child: false,
             state,
+            cache: Cache::mock().into_arc(),
+            ipc: None.into_arc(),
+            needs_status: RunStatus::Success,
+            cancel: CancellationToken::new(),
+            instance: 0,
+            matrix: None,
         };
         let runner = JobRunner {
             options,
             platform,
             runs_on: RunsOn::default(),
             working_dir: None,
+            env: HashMap::new(),
             outputs: HashMap::new(),
+            deadline: None,
+            caches: vec![],
+            records: JobRecords::default(),
         };
 
         // Act
@@ -1127,7 +1865,7 @@ mod tests {
     }
 
     #[tokio::test]
-    pub async fn job_level_matrix_expansion_runs_all_combinations_success() {
+    pub async fn job_instance_runs_with_its_matrix_combination_success() {
         let job_name = "main".to_string();
         let config = BldConfig::default().into_arc();
         let logger = Logger::mock().into_arc();
@@ -1159,6 +1897,9 @@ mod tests {
                 strategy: Some(Strategy {
                     matrix,
                     fail_fast: None,
+                    include: vec![],
+                    exclude: vec![],
+                    max_parallel: None,
                 }),
                 steps: vec![Step::ComplexSh(Box::new(ShellCommand {
                     id: "build".to_string(),
@@ -1183,13 +1924,26 @@ mod tests {
             artifacts,
             is_child: false,
             state,
+            cache: Cache::mock().into_arc(),
+            ipc: None.into_arc(),
+            needs_status: RunStatus::Success,
+            cancel: CancellationToken::new(),
+            instance: 0,
+            matrix: Some(HashMap::from([
+                ("os".to_string(), "linux".to_string()),
+                ("version".to_string(), "v2".to_string()),
+            ])),
         };
         let runner = JobRunner {
             options,
             platform,
             runs_on: RunsOn::default(),
             working_dir: None,
+            env: HashMap::new(),
             outputs: HashMap::new(),
+            deadline: None,
+            caches: vec![],
+            records: JobRecords::default(),
         };
 
         let result = runner.run().await;
@@ -1228,6 +1982,9 @@ mod tests {
                     strategy: Some(Strategy {
                         matrix,
                         fail_fast: None,
+                        include: vec![],
+                        exclude: vec![],
+                        max_parallel: None,
                     }),
                     ..Default::default()
                 }))],
@@ -1249,13 +2006,23 @@ mod tests {
             artifacts,
             is_child: false,
             state,
+            cache: Cache::mock().into_arc(),
+            ipc: None.into_arc(),
+            needs_status: RunStatus::Success,
+            cancel: CancellationToken::new(),
+            instance: 0,
+            matrix: None,
         };
         let runner = JobRunner {
             options,
             platform,
             runs_on: RunsOn::default(),
             working_dir: None,
+            env: HashMap::new(),
             outputs: HashMap::new(),
+            deadline: None,
+            caches: vec![],
+            records: JobRecords::default(),
         };
 
         let result = runner.run().await;
@@ -1263,7 +2030,7 @@ mod tests {
     }
 
     #[tokio::test]
-    pub async fn combined_job_and_step_matrix_full_cartesian_success() {
+    pub async fn combined_job_and_step_matrix_success() {
         let job_name = "main".to_string();
         let config = BldConfig::default().into_arc();
         let logger = Logger::mock().into_arc();
@@ -1297,6 +2064,9 @@ mod tests {
                 strategy: Some(Strategy {
                     matrix: job_matrix,
                     fail_fast: None,
+                    include: vec![],
+                    exclude: vec![],
+                    max_parallel: None,
                 }),
                 steps: vec![Step::ComplexSh(Box::new(ShellCommand {
                     id: "build".to_string(),
@@ -1304,6 +2074,9 @@ mod tests {
                     strategy: Some(Strategy {
                         matrix: step_matrix,
                         fail_fast: None,
+                        include: vec![],
+                        exclude: vec![],
+                        max_parallel: None,
                     }),
                     ..Default::default()
                 }))],
@@ -1325,167 +2098,29 @@ mod tests {
             artifacts,
             is_child: false,
             state,
+            cache: Cache::mock().into_arc(),
+            ipc: None.into_arc(),
+            needs_status: RunStatus::Success,
+            cancel: CancellationToken::new(),
+            instance: 0,
+            matrix: Some(HashMap::from([("os".to_string(), "linux".to_string())])),
         };
         let runner = JobRunner {
             options,
             platform,
             runs_on: RunsOn::default(),
             working_dir: None,
+            env: HashMap::new(),
             outputs: HashMap::new(),
+            deadline: None,
+            caches: vec![],
+            records: JobRecords::default(),
         };
 
         let result = runner.run().await;
         assert!(result.is_ok());
     }
 
-    #[tokio::test]
-    pub async fn job_matrix_fail_fast_true_stops_at_first_failure() {
-        let job_name = "main".to_string();
-        let config = BldConfig::default().into_arc();
-        let logger = Logger::mock().into_arc();
-        let fs = FileSystem::local(config.clone()).into_arc();
-        let run_ctx = Context::mock().into_arc();
-        let platform = Platform::mock().into_arc();
-        let artifacts = Artifacts::mock().into_arc();
-        let regex_cache = RegexCache::mock().into_arc();
-        let expr_regex = Regex::new(EXPR_REGEX).unwrap().into_arc();
-        let expr_rctx = CommonReadonlyRuntimeExprContext::default().into_arc();
-        let package_manager = PackageManager::new(config.clone()).into_arc();
-
-        let mut state = MockRootState::new();
-        state.expect_update_state().returning(|_| ());
-        state.expect_set_matrix().returning(|_| ());
-        state
-            .expect_update_node_state()
-            .withf(|_, state| matches!(state, State::Failed { .. }))
-            .times(1)
-            .returning(|_, _| ());
-
-        let mut matrix = HashMap::new();
-        matrix.insert(
-            "n".to_string(),
-            MatrixValue::Array(vec!["1".to_string(), "2".to_string(), "3".to_string()]),
-        );
-
-        let mut pipeline = Pipeline::default();
-        pipeline.jobs.insert(
-            job_name.clone(),
-            Job {
-                strategy: Some(Strategy {
-                    matrix,
-                    fail_fast: None,
-                }),
-                steps: vec![Step::ComplexSh(Box::new(ShellCommand {
-                    id: "build".to_string(),
-                    run: "echo hello".to_string(),
-                    condition: Some("${{ 1 }} ${{ 2 }}".to_string()),
-                    ..Default::default()
-                }))],
-                ..Default::default()
-            },
-        );
-
-        let options = JobRunnerOptions {
-            job_name,
-            logger,
-            config,
-            fs,
-            run_ctx,
-            pipeline: pipeline.into_arc(),
-            regex_cache,
-            expr_regex,
-            expr_rctx,
-            package_manager,
-            artifacts,
-            is_child: false,
-            state,
-        };
-        let runner = JobRunner {
-            options,
-            platform,
-            runs_on: RunsOn::default(),
-            working_dir: None,
-            outputs: HashMap::new(),
-        };
-
-        let result = runner.run().await;
-        assert!(result.is_err());
-    }
-
-    #[tokio::test]
-    pub async fn job_matrix_fail_fast_false_runs_all_and_aggregates() {
-        let job_name = "main".to_string();
-        let config = BldConfig::default().into_arc();
-        let logger = Logger::mock().into_arc();
-        let fs = FileSystem::local(config.clone()).into_arc();
-        let run_ctx = Context::mock().into_arc();
-        let platform = Platform::mock().into_arc();
-        let artifacts = Artifacts::mock().into_arc();
-        let regex_cache = RegexCache::mock().into_arc();
-        let expr_regex = Regex::new(EXPR_REGEX).unwrap().into_arc();
-        let expr_rctx = CommonReadonlyRuntimeExprContext::default().into_arc();
-        let package_manager = PackageManager::new(config.clone()).into_arc();
-
-        let mut state = MockRootState::new();
-        state.expect_update_state().returning(|_| ());
-        state.expect_set_matrix().returning(|_| ());
-        state
-            .expect_update_node_state()
-            .withf(|_, state| matches!(state, State::Failed { .. }))
-            .times(3)
-            .returning(|_, _| ());
-
-        let mut matrix = HashMap::new();
-        matrix.insert(
-            "n".to_string(),
-            MatrixValue::Array(vec!["1".to_string(), "2".to_string(), "3".to_string()]),
-        );
-
-        let mut pipeline = Pipeline::default();
-        pipeline.jobs.insert(
-            job_name.clone(),
-            Job {
-                strategy: Some(Strategy {
-                    matrix,
-                    fail_fast: Some(FailFastValue::Bool(false)),
-                }),
-                steps: vec![Step::ComplexSh(Box::new(ShellCommand {
-                    id: "build".to_string(),
-                    run: "echo hello".to_string(),
-                    condition: Some("${{ 1 }} ${{ 2 }}".to_string()),
-                    ..Default::default()
-                }))],
-                ..Default::default()
-            },
-        );
-
-        let options = JobRunnerOptions {
-            job_name,
-            logger,
-            config,
-            fs,
-            run_ctx,
-            pipeline: pipeline.into_arc(),
-            regex_cache,
-            expr_regex,
-            expr_rctx,
-            package_manager,
-            artifacts,
-            is_child: false,
-            state,
-        };
-        let runner = JobRunner {
-            options,
-            platform,
-            runs_on: RunsOn::default(),
-            working_dir: None,
-            outputs: HashMap::new(),
-        };
-
-        let result = runner.run().await;
-        assert!(result.is_err());
-    }
-
     const ACTION_WITH_OUTPUT: &str = r#"
 version: 3
 type: action
@@ -1546,13 +2181,23 @@ steps:
             artifacts,
             is_child: false,
             state,
+            cache: Cache::mock().into_arc(),
+            ipc: None.into_arc(),
+            needs_status: RunStatus::Success,
+            cancel: CancellationToken::new(),
+            instance: 0,
+            matrix: None,
         };
         JobRunner {
             options,
             platform,
             runs_on: RunsOn::default(),
             working_dir: None,
+            env: HashMap::new(),
             outputs: HashMap::new(),
+            deadline: None,
+            caches: vec![],
+            records: JobRecords::default(),
         }
     }
 
@@ -1581,6 +2226,8 @@ steps:
                     download: "artifact-name".to_string(),
                     to: "some/path".to_string(),
                     condition: Some("${{ false }}".to_string()),
+                    continue_on_error: None,
+                    retry: None,
                 }))],
                 ..Default::default()
             },
@@ -1600,13 +2247,23 @@ steps:
             artifacts,
             is_child: false,
             state,
+            cache: Cache::mock().into_arc(),
+            ipc: None.into_arc(),
+            needs_status: RunStatus::Success,
+            cancel: CancellationToken::new(),
+            instance: 0,
+            matrix: None,
         };
         let runner = JobRunner {
             options,
             platform,
             runs_on: RunsOn::default(),
             working_dir: None,
+            env: HashMap::new(),
             outputs: HashMap::new(),
+            deadline: None,
+            caches: vec![],
+            records: JobRecords::default(),
         };
 
         let result = runner.run().await;
@@ -1644,6 +2301,8 @@ steps:
                     download: "artifact-name".to_string(),
                     to: "some/path".to_string(),
                     condition: Some("${{ true }}".to_string()),
+                    continue_on_error: None,
+                    retry: None,
                 }))],
                 ..Default::default()
             },
@@ -1663,13 +2322,23 @@ steps:
             artifacts,
             is_child: false,
             state,
+            cache: Cache::mock().into_arc(),
+            ipc: None.into_arc(),
+            needs_status: RunStatus::Success,
+            cancel: CancellationToken::new(),
+            instance: 0,
+            matrix: None,
         };
         let runner = JobRunner {
             options,
             platform,
             runs_on: RunsOn::default(),
             working_dir: None,
+            env: HashMap::new(),
             outputs: HashMap::new(),
+            deadline: None,
+            caches: vec![],
+            records: JobRecords::default(),
         };
 
         let result = runner.run().await;
@@ -1737,6 +2406,9 @@ steps:
                 strategy: Some(Strategy {
                     matrix,
                     fail_fast: None,
+                    include: vec![],
+                    exclude: vec![],
+                    max_parallel: None,
                 }),
                 ..Default::default()
             },
@@ -1810,13 +2482,313 @@ steps:
             artifacts,
             is_child: false,
             state,
+            cache: Cache::mock().into_arc(),
+            ipc: None.into_arc(),
+            needs_status: RunStatus::Success,
+            cancel: CancellationToken::new(),
+            instance: 0,
+            matrix: None,
         };
         JobRunner {
             options,
             platform,
             runs_on: RunsOn::default(),
             working_dir: None,
+            env: HashMap::new(),
             outputs: HashMap::new(),
+            deadline: None,
+            caches: vec![],
+            records: JobRecords::default(),
+        }
+    }
+
+    fn job_runner_with_steps(
+        dir: &TempDir,
+        steps: Vec<Step>,
+        needs_status: RunStatus,
+    ) -> (Job, JobRunner<JobState>) {
+        let job = Job {
+            steps,
+            ..Default::default()
+        };
+        job_runner_for(dir, job, needs_status)
+    }
+
+    fn job_runner_for(
+        dir: &TempDir,
+        job: Job,
+        needs_status: RunStatus,
+    ) -> (Job, JobRunner<JobState>) {
+        let config = BldConfig {
+            root_dir: dir.root_dir(),
+            ..Default::default()
+        }
+        .into_arc();
+
+        let mut state = JobState::new("main");
+        for step in job.all_steps() {
+            state.add_node(step.id());
+        }
+
+        let mut pipeline = Pipeline::default();
+        pipeline.jobs.insert("main".to_string(), job.clone());
+
+        let options = JobRunnerOptions {
+            job_name: "main".to_string(),
+            logger: Logger::mock().into_arc(),
+            fs: FileSystem::local(config.clone()).into_arc(),
+            run_ctx: Context::mock().into_arc(),
+            pipeline: pipeline.into_arc(),
+            regex_cache: RegexCache::mock().into_arc(),
+            expr_regex: Regex::new(EXPR_REGEX).unwrap().into_arc(),
+            expr_rctx: CommonReadonlyRuntimeExprContext::default().into_arc(),
+            package_manager: PackageManager::new(config.clone()).into_arc(),
+            artifacts: Artifacts::mock().into_arc(),
+            is_child: false,
+            state,
+            cache: Cache::mock().into_arc(),
+            ipc: None.into_arc(),
+            needs_status,
+            cancel: CancellationToken::new(),
+            config,
+            instance: 0,
+            matrix: None,
+        };
+        let runner = JobRunner {
+            options,
+            platform: Platform::mock().into_arc(),
+            runs_on: RunsOn::default(),
+            working_dir: None,
+            env: job.env.clone(),
+            outputs: HashMap::new(),
+            deadline: None,
+            caches: vec![],
+            records: JobRecords::default(),
+        };
+        (job, runner)
+    }
+
+    fn sh_step(id: &str, condition: Option<&str>) -> Step {
+        Step::ComplexSh(Box::new(ShellCommand {
+            id: id.to_string(),
+            run: "echo hello".to_string(),
+            condition: condition.map(str::to_string),
+            ..Default::default()
+        }))
+    }
+
+    /// Once a step fails the rest of the steps are skipped, apart from the ones whose
+    /// condition checks the status of the job. The conditions that would fail to evaluate
+    /// reveal which of the steps ran.
+    #[actix_web::test]
+    pub async fn steps_after_a_failure_run_only_when_checking_the_status() {
+        let dir = TempDir::new("steps_after_a_failure_run_only_when_checking_the_status");
+        let steps = vec![
+            sh_step("build", Some("${{ 1 }} ${{ 2 }}")),
+            sh_step("test", Some("${{ true == \"James\" }}")),
+            sh_step("report", Some("${{ success() }}")),
+            sh_step("cleanup", Some("${{ failure() && true == \"James\" }}")),
+            sh_step("notify", Some("${{ always() }}")),
+        ];
+        let (job, mut runner) = job_runner_with_steps(&dir, steps, RunStatus::Success);
+
+        let error = runner.run_job_steps(&job).await.unwrap_err().to_string();
+        assert!(error.contains("more than one condition"), "{error}");
+
+        let state = &runner.options.state;
+        assert!(matches!(
+            state.get_node_state("build"),
+            Some(State::Failed { .. })
+        ));
+        assert_eq!(state.get_node_state("test"), Some(&State::Default));
+        assert_eq!(state.get_node_state("report"), Some(&State::Default));
+        let Some(State::Failed { error }) = state.get_node_state("cleanup") else {
+            panic!("expected the cleanup step to have failed");
+        };
+        assert!(error.contains("cannot compare"), "{error}");
+        assert_eq!(state.get_node_state("notify"), Some(&State::Completed));
+    }
+
+    /// Steps that time out once the job's deadline has passed are cancelled, which is
+    /// what the `cancelled()` check of the steps after them sees.
+    #[actix_web::test]
+    pub async fn steps_after_the_job_deadline_are_cancelled() {
+        let dir = TempDir::new("steps_after_the_job_deadline_are_cancelled");
+        let steps = vec![
+            sh_step("build", None),
+            sh_step("test", Some("${{ failure() }}")),
+            sh_step("cleanup", Some("${{ cancelled() }}")),
+        ];
+        let (job, mut runner) = job_runner_with_steps(&dir, steps, RunStatus::Success);
+        runner.deadline = Some((
+            Instant::now() - Duration::from_millis(10),
+            Duration::from_secs(1),
+        ));
+
+        let result = runner.run_steps(&job.steps, None).await;
+        assert!(result.unwrap_err().is::<TimedOut>());
+
+        let state = &runner.options.state;
+        assert!(matches!(
+            state.get_node_state("build"),
+            Some(State::Cancelled { .. })
+        ));
+        assert_eq!(state.get_node_state("test"), Some(&State::Default));
+        assert_eq!(state.get_node_state("cleanup"), Some(&State::Completed));
+    }
+
+    /// The post steps run after a failed step, with the ones checking the status seeing
+    /// that the job failed.
+    #[actix_web::test]
+    pub async fn post_steps_run_after_a_failed_step() {
+        let dir = TempDir::new("post_steps_run_after_a_failed_step");
+        let job = Job {
+            steps: vec![
+                sh_step("build", Some("${{ 1 }} ${{ 2 }}")),
+                sh_step("test", None),
+            ],
+            post: vec![
+                sh_step("cleanup", None),
+                sh_step("report", Some("${{ success() }}")),
+                sh_step("notify", Some("${{ failure() && true == \"James\" }}")),
+            ],
+            ..Default::default()
+        };
+        let (job, mut runner) = job_runner_for(&dir, job, RunStatus::Success);
+
+        let error = runner.run_job(&job).await.unwrap_err().to_string();
+        assert!(error.contains("more than one condition"), "{error}");
+
+        let state = &runner.options.state;
+        assert!(matches!(state.get_state(), State::Failed { .. }));
+        assert_eq!(state.get_node_state("test"), Some(&State::Default));
+        assert_eq!(state.get_node_state("cleanup"), Some(&State::Completed));
+        assert_eq!(state.get_node_state("report"), Some(&State::Default));
+        let Some(State::Failed { error }) = state.get_node_state("notify") else {
+            panic!("expected the notify step to have failed");
+        };
+        assert!(error.contains("cannot compare"), "{error}");
+    }
+
+    /// Stopping the run while its post steps are running stops them as well.
+    #[cfg(target_family = "unix")]
+    #[actix_web::test]
+    pub async fn post_steps_stop_once_the_run_is_cancelled() {
+        let dir = TempDir::new("post_steps_stop_once_the_run_is_cancelled");
+        let job = Job {
+            steps: vec![sh_step("build", None)],
+            post: vec![Step::ComplexSh(Box::new(ShellCommand {
+                id: "cleanup".to_string(),
+                run: "sleep 5".to_string(),
+                ..Default::default()
+            }))],
+            ..Default::default()
+        };
+        let (job, mut runner) = job_runner_for(&dir, job, RunStatus::Success);
+        let machine = Machine::new(
+            "post-steps",
+            runner.options.config.clone(),
+            &HashMap::new(),
+            HashMap::new().into_arc(),
+        )
+        .await
+        .unwrap();
+        runner.platform = Platform::machine(Box::new(machine)).into_arc();
+
+        let cancel = runner.options.cancel.clone();
+        actix_web::rt::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            cancel.cancel();
+        });
+
+        let start = Instant::now();
+        let error = runner.run_job(&job).await.unwrap_err();
+        assert!(error.is::<Cancelled>(), "{error}");
+        assert!(start.elapsed() < Duration::from_secs(4));
+
+        let state = &runner.options.state;
+        assert_eq!(state.get_node_state("build"), Some(&State::Completed));
+        assert!(matches!(
+            state.get_node_state("cleanup"),
+            Some(State::Cancelled { .. })
+        ));
+    }
+
+    #[actix_web::test]
+    pub async fn failing_post_step_fails_the_job() {
+        let dir = TempDir::new("failing_post_step_fails_the_job");
+        let job = Job {
+            steps: vec![sh_step("build", None)],
+            post: vec![
+                sh_step("cleanup", Some("${{ 1 }} ${{ 2 }}")),
+                sh_step("notify", None),
+            ],
+            ..Default::default()
+        };
+        let (job, mut runner) = job_runner_for(&dir, job, RunStatus::Success);
+
+        let error = runner.run_job(&job).await.unwrap_err().to_string();
+        assert!(error.contains("more than one condition"), "{error}");
+
+        let state = &runner.options.state;
+        assert!(matches!(state.get_state(), State::Failed { .. }));
+        assert_eq!(state.get_node_state("build"), Some(&State::Completed));
+        assert_eq!(state.get_node_state("notify"), Some(&State::Completed));
+    }
+
+    #[test]
+    pub fn resolve_env_merges_the_job_and_step_env() {
+        let dir = TempDir::new("resolve_env_merges_the_job_and_step_env");
+        let env_of = |values: &[(&str, &str)]| -> HashMap<String, String> {
+            values
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        let job = Job {
+            env: env_of(&[("STAGE", "build"), ("TARGET", "linux")]),
+            steps: vec![sh_step("version", None), sh_step("publish", None)],
+            ..Default::default()
+        };
+        let (_, mut runner) = job_runner_for(&dir, job, RunStatus::Success);
+        runner
+            .options
+            .state
+            .set_outputs("version", env_of(&[("tag", "v1.2.0")]))
+            .unwrap();
+
+        // The step overrides the values of the job and can export the outputs of the
+        // steps that ran before it.
+        let step_env = env_of(&[
+            ("TARGET", "windows"),
+            ("TAG", "${{ steps.version.outputs.tag }}"),
+        ]);
+        let actual = runner.resolve_env(&step_env).unwrap();
+        let expected = env_of(&[("STAGE", "build"), ("TARGET", "windows"), ("TAG", "v1.2.0")]);
+        assert_eq!(actual, expected);
+
+        // Without any env on the step only the one of the job applies.
+        let actual = runner.resolve_env(&HashMap::new()).unwrap();
+        assert_eq!(actual, env_of(&[("STAGE", "build"), ("TARGET", "linux")]));
+    }
+
+    #[test]
+    pub fn job_condition_checks_the_status_of_its_needs() {
+        let dir = TempDir::new("job_condition_checks_the_status_of_its_needs");
+        let data = [
+            (RunStatus::Success, [true, false, true, false]),
+            (RunStatus::Failure, [false, true, true, false]),
+            (RunStatus::Cancelled, [false, false, true, true]),
+        ];
+
+        for (status, expected) in data {
+            let (_, runner) = job_runner_with_steps(&dir, vec![], status);
+            let actual = ["success", "failure", "always", "cancelled"].map(|name| {
+                runner
+                    .job_condition(Some(&format!("${{{{ {name}() }}}}")))
+                    .unwrap()
+            });
+            assert_eq!(actual, expected, "{status:?}");
         }
     }
 
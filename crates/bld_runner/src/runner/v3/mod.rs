@@ -4,7 +4,7 @@ mod job;
 mod pipeline;
 mod state;
 #[cfg(test)]
-mod test_utils;
+pub(crate) mod test_utils;
 
 pub use action::*;
 pub use pipeline::*;
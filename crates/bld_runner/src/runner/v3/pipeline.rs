@@ -1,10 +1,16 @@
-use std::{collections::HashMap, fmt::Write, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write,
+    sync::Arc,
+    time::Duration,
+};
 
 use actix_web::rt::spawn;
 use anyhow::{Result, anyhow, bail};
 use bld_config::BldConfig;
 use bld_core::{
     artifacts::Artifacts,
+    cache::Cache,
     context::Context,
     fs::FileSystem,
     logger::Logger,
@@ -14,18 +20,27 @@ use bld_core::{
 use bld_models::dtos::WorkerMessages;
 use bld_pkg::PackageManager;
 use bld_utils::sync::IntoArc;
+use futures::{StreamExt, stream::FuturesUnordered};
 use regex::Regex;
 use tokio::{sync::mpsc::Sender, time::sleep};
-use tracing::debug;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error};
 
 use crate::{
     dag::Dag,
-    expr::v3::context::CommonReadonlyRuntimeExprContext,
+    expr::v3::{
+        context::{CommonReadonlyRuntimeExprContext, START_OF_RUN_WCTX},
+        exec::CommonExprExecutor,
+        functions::uses_status_check,
+        traits::RunStatus,
+    },
     pipeline::v3::Pipeline,
     runner::v3::{
         job::JobRunnerOptions,
-        state::{JobState, RootState},
+        state::{JobState, RootState, State, run_status},
     },
+    strategy::v3::combination_label,
+    timeout::v3::Cancelled,
 };
 
 use super::{
@@ -33,6 +48,86 @@ use super::{
     job::{JobRunner, RunningJob},
 };
 
+/// A single run of a job, one for each combination of its matrix or just the one for a
+/// job without a strategy.
+struct JobInstance {
+    name: String,
+    index: usize,
+    matrix: Option<HashMap<String, String>>,
+}
+
+/// The instances of a job that has been started, tracked until every one of them has
+/// finished so that the job can be completed.
+struct JobInstances {
+    name: String,
+    pending: VecDeque<JobInstance>,
+    running: usize,
+    started: usize,
+    max_parallel: usize,
+    fail_fast: bool,
+    needs_status: RunStatus,
+    states: Vec<State>,
+    outputs: Option<HashMap<String, String>>,
+}
+
+impl JobInstances {
+    fn new(
+        name: &str,
+        instances: Vec<JobInstance>,
+        max_parallel: usize,
+        fail_fast: bool,
+        needs_status: RunStatus,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            pending: instances.into(),
+            running: 0,
+            started: 0,
+            max_parallel,
+            fail_fast,
+            needs_status,
+            states: vec![],
+            outputs: None,
+        }
+    }
+
+    fn next(&mut self) -> Option<JobInstance> {
+        if self.running >= self.max_parallel {
+            return None;
+        }
+        let instance = self.pending.pop_front()?;
+        self.running += 1;
+        self.started += 1;
+        Some(instance)
+    }
+
+    /// Records the result of an instance, where the outputs of every instance that
+    /// succeeded are merged into the outputs of the job.
+    fn finish(&mut self, state: State, outputs: Option<HashMap<String, String>>) {
+        self.running -= 1;
+        if let Some(outputs) = outputs {
+            self.outputs.get_or_insert_default().extend(outputs);
+        }
+        let failed = matches!(state, State::Failed { .. });
+        self.states.push(state);
+        if failed && self.fail_fast {
+            self.cancel("cancelled since another combination of the job failed");
+        }
+    }
+
+    fn cancel(&mut self, error: &str) {
+        for _ in self.pending.drain(..) {
+            self.states.push(State::Cancelled {
+                error: error.to_string(),
+            });
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.pending.is_empty() && self.running == 0
+    }
+}
+
 pub struct PipelineRunner {
     pub config: Arc<BldConfig>,
     pub fs: Arc<FileSystem>,
@@ -46,7 +141,11 @@ pub struct PipelineRunner {
     pub signals: Option<UnixSignalsBackend>,
     pub package_manager: Arc<PackageManager>,
     pub artifacts: Arc<Artifacts>,
+    pub cache: Arc<Cache>,
     pub ipc: Arc<Option<Sender<WorkerMessages>>>,
+    /// Cancelled to stop the jobs of the run, such as when the step of a parent runner
+    /// that runs this pipeline times out.
+    pub cancel: CancellationToken,
     pub is_child: bool,
     pub has_faulted: bool,
 }
@@ -62,7 +161,12 @@ impl PipelineRunner {
         Ok(())
     }
 
-    async fn register_completion(&self) -> Result<()> {
+    /// Sets the state of the root pipeline, along with the outputs of its jobs once it has
+    /// finished successfully.
+    async fn register_completion(
+        &self,
+        job_outputs: HashMap<String, HashMap<String, String>>,
+    ) -> Result<()> {
         if !self.is_child {
             debug!("setting state of root pipeline");
             if self.has_faulted {
@@ -71,7 +175,7 @@ impl PipelineRunner {
                     .await?;
             } else {
                 self.run_ctx
-                    .set_pipeline_as_finished(self.expr_rctx.run_id.to_owned())
+                    .set_pipeline_as_finished(self.expr_rctx.run_id.to_owned(), job_outputs)
                     .await?;
             }
         }
@@ -107,20 +211,23 @@ impl PipelineRunner {
         Ok(())
     }
 
-    async fn stop(&self) -> Result<()> {
+    async fn stop(&self, job_outputs: HashMap<String, HashMap<String, String>>) -> Result<()> {
         debug!("starting cleanup operations for runner");
-        self.register_completion().await?;
+        self.register_completion(job_outputs).await?;
         self.ipc_send_completed().await?;
         Ok(())
     }
 
-    async fn create_job(
+    fn job_options(
         &self,
         name: &str,
         logger: Arc<Logger>,
         state: JobState,
-    ) -> Result<JobRunner<JobState>> {
-        let options = JobRunnerOptions {
+        needs_status: RunStatus,
+        instance: usize,
+        matrix: Option<HashMap<String, String>>,
+    ) -> JobRunnerOptions<JobState> {
+        JobRunnerOptions {
             job_name: name.to_string(),
             logger: logger.clone(),
             config: self.config.clone(),
@@ -132,10 +239,15 @@ impl PipelineRunner {
             expr_rctx: self.expr_rctx.clone(),
             package_manager: self.package_manager.clone(),
             artifacts: self.artifacts.clone(),
+            cache: self.cache.clone(),
+            ipc: self.ipc.clone(),
+            cancel: self.cancel.clone(),
             is_child: self.is_child,
             state,
-        };
-        JobRunner::new(options).await
+            needs_status,
+            instance,
+            matrix,
+        }
     }
 
     fn create_job_state(
@@ -147,108 +259,271 @@ impl PipelineRunner {
         let Some(job) = self.pipeline.jobs.get(name) else {
             bail!("job with name {name} not found");
         };
-        for step in &job.steps {
+        for step in job.all_steps() {
             state.add_node(step.id());
         }
         state.set_job_outputs(job_outputs.clone())?;
         Ok(state)
     }
 
-    async fn prepare_jobs(
+    /// Spawns an instance of a job. The job runner is created in the spawned task, since
+    /// building its platform pulls images and starts services, so that the instances that
+    /// run in parallel also start in parallel.
+    async fn start_job(
         &self,
-        names: &[String],
+        name: &str,
+        instance: JobInstance,
         job_outputs: &HashMap<String, HashMap<String, String>>,
-    ) -> Result<Vec<Option<RunningJob>>> {
-        let mut jobs = Vec::new();
-        for name in names {
-            self.logger
-                .write_line(format!("{:<15}: {}", "Running job", name))
-                .await?;
-            let logger = Logger::in_memory().into_arc();
-            let state = self.create_job_state(name, job_outputs)?;
-            let job = self.create_job(name, logger.clone(), state).await?;
-            let handle = spawn(job.run());
-            jobs.push(Some(RunningJob::new(name, handle, logger)));
-        }
-        Ok(jobs)
+        needs_status: RunStatus,
+    ) -> Result<RunningJob> {
+        self.logger
+            .write_line(format!("{:<15}: {}", "Running job", instance.name))
+            .await?;
+        let logger = Logger::job(self.logger.clone(), &instance.name)
+            .await?
+            .into_arc();
+        let state = self.create_job_state(name, job_outputs)?;
+        let options = self.job_options(
+            name,
+            logger.clone(),
+            state,
+            needs_status,
+            instance.index,
+            instance.matrix,
+        );
+        let handle = spawn(async move { JobRunner::new(options).await?.run().await });
+        Ok(RunningJob::new(&instance.name, handle, logger))
     }
 
-    async fn run_first_job(&self) -> Result<()> {
+    /// Creates an instance for every combination of a job's matrix, named after the job
+    /// and the values of the combination, or a single instance for a job without one.
+    fn job_instances(&self, name: &str, needs_status: RunStatus) -> Result<JobInstances> {
+        let Some(job) = self.pipeline.jobs.get(name) else {
+            bail!("job with name {name} not found");
+        };
+
+        let Some(strategy) = job.strategy.as_ref() else {
+            let instance = JobInstance {
+                name: name.to_string(),
+                index: 0,
+                matrix: None,
+            };
+            return Ok(JobInstances::new(
+                name,
+                vec![instance],
+                1,
+                true,
+                needs_status,
+            ));
+        };
+
+        // The job's strategy is resolved before any of its steps have run, so the same
+        // start of run limits as runs_on apply to it.
+        let exec = CommonExprExecutor::new(
+            self.pipeline.as_ref(),
+            self.expr_rctx.as_ref(),
+            &START_OF_RUN_WCTX,
+        );
+        let instances = strategy
+            .combinations(&exec)?
+            .into_iter()
+            .enumerate()
+            .map(|(index, combination)| JobInstance {
+                name: format!("{name} ({})", combination_label(&combination)),
+                index,
+                matrix: Some(combination),
+            })
+            .collect();
+
+        Ok(JobInstances::new(
+            name,
+            instances,
+            strategy.resolve_max_parallel(),
+            strategy.resolve_fail_fast(&exec)?,
+            needs_status,
+        ))
+    }
+
+    async fn run_first_job(&self) -> Result<HashMap<String, HashMap<String, String>>> {
         let Some(name) = self.pipeline.jobs.keys().next() else {
             bail!("unable to retrieve job");
         };
         debug!("found only one job so running it in the current context");
         let state = self.create_job_state(name, &HashMap::new())?;
-        self.create_job(name, self.logger.clone(), state)
+        let options = self.job_options(
+            name,
+            self.logger.clone(),
+            state,
+            RunStatus::Success,
+            0,
+            None,
+        );
+        JobRunner::new(options)
             .await?
             .run()
             .await
-            .map(|_| ())
+            .map(|runner| HashMap::from([(name.to_owned(), runner.outputs)]))
     }
 
-    async fn run_layer(
-        &self,
-        names: &[String],
-        job_outputs: &HashMap<String, HashMap<String, String>>,
-    ) -> Result<HashMap<String, HashMap<String, String>>> {
+    /// Checks whether the condition of a job decides on its own if the job runs after
+    /// another one has failed.
+    fn checks_status(&self, name: &str) -> bool {
+        self.pipeline
+            .jobs
+            .get(name)
+            .and_then(|job| job.condition.as_deref())
+            .is_some_and(uses_status_check)
+    }
+
+    fn needs_status(&self, name: &str, job_states: &HashMap<String, Vec<State>>) -> RunStatus {
+        let Some(job) = self.pipeline.jobs.get(name) else {
+            return RunStatus::Success;
+        };
+        run_status(
+            job.needs_iter()
+                .filter_map(|need| job_states.get(need))
+                .flatten(),
+        )
+    }
+
+    /// Starts every job as soon as all of the jobs it needs have finished, with an
+    /// instance for each combination of its matrix, up to the pipeline's and the job's
+    /// max_parallel limits. Once a job fails the jobs that haven't started yet are
+    /// cancelled, unless their condition checks the status of the jobs they need, and the
+    /// ones already running are awaited before the errors are returned. Once the run is
+    /// cancelled no more jobs are started, and if scheduling fails unexpectedly the run is
+    /// cancelled, so that in every case the running jobs get to dispose of their platforms
+    /// before this returns.
+    async fn run_all_jobs(&self) -> Result<HashMap<String, HashMap<String, String>>> {
+        let max_parallel = self.pipeline.max_parallel.unwrap_or(usize::MAX);
+        let mut scheduler = self.dag.scheduler();
+        let mut job_outputs: HashMap<String, HashMap<String, String>> = HashMap::new();
+        let mut job_states: HashMap<String, Vec<State>> = HashMap::new();
         let mut errors: Vec<String> = Vec::new();
-        let mut collected: HashMap<String, HashMap<String, String>> = HashMap::new();
-        let mut running_jobs = self.prepare_jobs(names, job_outputs).await?;
-
-        while running_jobs.iter().any(|x| x.is_some()) {
-            for job in running_jobs.iter_mut() {
-                let is_finished = job
-                    .as_ref()
-                    .map(|x| x.handle.is_finished())
-                    .unwrap_or_default();
-
-                if is_finished {
-                    let Some(running_job) = job.take() else {
-                        continue;
-                    };
-
-                    let handle_result = running_job.handle.await.map_err(|e| anyhow!(e))?;
-
-                    let message = match &handle_result {
-                        Ok(runner) => {
-                            collected.insert(running_job.name.clone(), runner.outputs.clone());
-                            format!("{:<15}: {}", "Completed job", running_job.name)
-                        }
+        let mut started_jobs: Vec<JobInstances> = Vec::new();
+        let mut running_jobs = FuturesUnordered::new();
+
+        let result: Result<()> = async {
+            loop {
+                while let Some(name) = scheduler.next_ready() {
+                    let needs_status = self.needs_status(name, &job_states);
+                    match self.job_instances(name, needs_status) {
+                        Ok(instances) => started_jobs.push(instances),
                         Err(e) => {
-                            errors.push(format!("[{}] {e}", running_job.name));
-                            format!("{:<15}: {} ({e})", "Erroneous job", running_job.name)
+                            let error = e.to_string();
+                            errors.push(format!("[{name}] {error}"));
+                            job_states.insert(name.to_string(), vec![State::Failed { error }]);
+                            scheduler.complete(name);
                         }
-                    };
+                    }
+                }
 
-                    self.logger.write_line(message).await?;
+                for job in started_jobs.iter_mut() {
+                    if self.cancel.is_cancelled() {
+                        debug!("cancelling job {} since the run was cancelled", job.name);
+                        job.cancel("cancelled since the run was cancelled");
+                    } else if job.started == 0
+                        && !errors.is_empty()
+                        && !self.checks_status(&job.name)
+                    {
+                        debug!("cancelling job {} since an earlier job failed", job.name);
+                        job.cancel("cancelled since an earlier job failed");
+                    }
 
-                    self.logger
-                        .write_line(running_job.logger.try_retrieve_output().await?)
-                        .await?;
+                    while running_jobs.len() < max_parallel
+                        && let Some(instance) = job.next()
+                    {
+                        let instance_name = instance.name.clone();
+                        match self
+                            .start_job(&job.name, instance, &job_outputs, job.needs_status)
+                            .await
+                        {
+                            Ok(running_job) => {
+                                let name = job.name.clone();
+                                running_jobs.push(async move {
+                                    let result = running_job.handle.await;
+                                    (name, running_job.name, running_job.logger, result)
+                                })
+                            }
+                            Err(e) => {
+                                let error = e.to_string();
+                                errors.push(format!("[{instance_name}] {error}"));
+                                job.finish(State::Failed { error }, None);
+                            }
+                        }
+                    }
+                }
+
+                let mut completed = false;
+                while let Some(index) = started_jobs.iter().position(JobInstances::is_finished) {
+                    let job = started_jobs.remove(index);
+                    if let Some(outputs) = job.outputs {
+                        job_outputs.insert(job.name.clone(), outputs);
+                    }
+                    scheduler.complete(&job.name);
+                    job_states.insert(job.name, job.states);
+                    completed = true;
                 }
+                if completed {
+                    continue;
+                }
+
+                let Some((name, instance_name, logger, result)) = running_jobs.next().await else {
+                    break;
+                };
+
+                logger.flush().await?;
+                let Some(job) = started_jobs.iter_mut().find(|x| x.name == name) else {
+                    bail!("unable to find the running job {name}");
+                };
+                let message = match result.map_err(|e| anyhow!(e))? {
+                    Ok(runner) => {
+                        job.finish(
+                            runner.options.state.get_state().clone(),
+                            Some(runner.outputs),
+                        );
+                        format!("{:<15}: {}", "Completed job", instance_name)
+                    }
+                    Err(e) => {
+                        errors.push(format!("[{instance_name}] {e}"));
+                        let error = e.to_string();
+                        let state = if e.is::<Cancelled>() {
+                            State::Cancelled { error }
+                        } else {
+                            State::Failed { error }
+                        };
+                        job.finish(state, None);
+                        format!("{:<15}: {} ({e})", "Erroneous job", instance_name)
+                    }
+                };
+
+                self.logger.write_line(message).await?;
             }
+            Ok(())
+        }
+        .await;
 
-            sleep(Duration::from_millis(200)).await;
+        if result.is_err() {
+            self.cancel.cancel();
         }
+        while running_jobs.next().await.is_some() {}
+        result?;
 
         if errors.is_empty() {
-            Ok(collected)
+            Ok(job_outputs)
         } else {
             Err(anyhow!(errors.join("\n")))
         }
     }
 
-    async fn run_all_jobs(&self) -> Result<()> {
-        let mut job_outputs: HashMap<String, HashMap<String, String>> = HashMap::new();
-        for layer in self.dag.layers() {
-            let layer_outputs = self.run_layer(&layer, &job_outputs).await?;
-            job_outputs.extend(layer_outputs);
-        }
-        Ok(())
-    }
-
-    async fn jobs(&self) -> Result<()> {
-        if self.pipeline.jobs.len() == 1 {
+    async fn jobs(&self) -> Result<HashMap<String, HashMap<String, String>>> {
+        let single_job = self.pipeline.jobs.len() == 1
+            && self
+                .pipeline
+                .jobs
+                .values()
+                .all(|job| job.strategy.is_none());
+        if single_job {
             self.run_first_job().await
         } else {
             self.run_all_jobs().await
@@ -258,18 +533,20 @@ impl PipelineRunner {
     async fn execute(mut self) -> Result<HashMap<String, String>> {
         self.start().await?;
 
-        // using let expression to log the errors and let an empty string be used
+        // logging the errors here and letting an empty string be used
         // by the final print_error of main.
-
-        let Err(e) = self.jobs().await else {
-            self.stop().await?;
-            return Ok(HashMap::new());
-        };
-
-        self.logger.write(e.to_string()).await?;
-        self.has_faulted = true;
-        self.stop().await?;
-        bail!("")
+        match self.jobs().await {
+            Ok(job_outputs) => {
+                self.stop(job_outputs).await?;
+                Ok(HashMap::new())
+            }
+            Err(e) => {
+                self.logger.write(e.to_string()).await?;
+                self.has_faulted = true;
+                self.stop(HashMap::new()).await?;
+                bail!("")
+            }
+        }
     }
 
     pub async fn run(mut self) -> RecursiveFuture {
@@ -285,6 +562,7 @@ impl PipelineRunner {
 
             let context = self.run_ctx.clone();
             let logger = self.logger.clone();
+            let cancel = self.cancel.clone();
             let mut signals = signals.unwrap();
             let runner_handle = spawn(self.execute());
 
@@ -309,14 +587,20 @@ impl PipelineRunner {
                             signal: UnixSignal::SIGQUIT,
                             resp_tx,
                         } => {
-                            runner_handle.abort();
-
                             logger
                                 .write_line(
                                     "Runner interruped. Starting graceful shutdown...".to_owned(),
                                 )
                                 .await?;
 
+                            // The jobs are cancelled rather than the runner being aborted,
+                            // so that they run their post steps and dispose of their
+                            // platforms before the run is set as faulted.
+                            cancel.cancel();
+                            if let Err(e) = runner_handle.await {
+                                error!("runner stopped unexpectedly during shutdown, {e}");
+                            }
+
                             context.run_faulted().await?;
 
                             break resp_tx
@@ -333,32 +617,57 @@ impl PipelineRunner {
 
 #[cfg(test)]
 mod tests {
-    use std::sync::Arc;
+    use std::{
+        collections::HashMap,
+        fs::read_to_string,
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    use actix_web::rt::{spawn, time::sleep};
 
     use bld_config::BldConfig;
     use bld_core::{
-        artifacts::Artifacts, context::Context, fs::FileSystem, logger::Logger, regex::RegexCache,
+        artifacts::Artifacts, cache::Cache, context::Context, fs::FileSystem, logger::Logger,
+        regex::RegexCache,
     };
     use bld_pkg::PackageManager;
     use bld_utils::sync::IntoArc;
     use regex::Regex;
+    use tokio_util::sync::CancellationToken;
 
     use crate::{
+        approval::v3::Approval,
         dag::Dag,
         expr::v3::{context::CommonReadonlyRuntimeExprContext, parser::EXPR_REGEX},
         job::v3::{Job, Needs},
         outputs::v3::Output,
         pipeline::v3::Pipeline,
+        runner::v3::test_utils::TempDir,
+        step::v3::{ShellCommand, Step},
+        strategy::v3::{FailFastValue, MatrixValue, Strategy},
     };
 
     use super::PipelineRunner;
 
     fn create_runner(jobs: Vec<(&str, Job)>, logger: Arc<Logger>) -> PipelineRunner {
+        create_runner_with_max_parallel(jobs, None, logger)
+    }
+
+    fn create_runner_with_max_parallel(
+        jobs: Vec<(&str, Job)>,
+        max_parallel: Option<usize>,
+        logger: Arc<Logger>,
+    ) -> PipelineRunner {
         let config = BldConfig::default().into_arc();
-        let mut pipeline = Pipeline::default();
+        let mut pipeline = Pipeline {
+            max_parallel,
+            ..Default::default()
+        };
         for (name, job) in jobs {
             pipeline.jobs.insert(name.to_string(), job);
         }
+        let dag = Dag::try_from(&pipeline).unwrap();
 
         PipelineRunner {
             fs: FileSystem::local(config.clone()).into_arc(),
@@ -368,14 +677,16 @@ mod tests {
             expr_regex: Regex::new(EXPR_REGEX).unwrap().into_arc(),
             expr_rctx: CommonReadonlyRuntimeExprContext::default().into_arc(),
             pipeline: pipeline.into_arc(),
-            dag: Dag::default(),
+            dag,
             signals: None,
             package_manager: PackageManager::new(config.clone()).into_arc(),
             artifacts: Artifacts::mock().into_arc(),
             ipc: None.into_arc(),
+            cancel: CancellationToken::new(),
             is_child: true,
             has_faulted: false,
             config,
+            cache: Cache::mock().into_arc(),
         }
     }
 
@@ -389,7 +700,7 @@ mod tests {
     }
 
     #[actix_web::test]
-    async fn run_layer_collects_error_message_of_failing_job() {
+    async fn run_all_jobs_collects_error_message_of_failing_job() {
         let logger = Logger::in_memory().into_arc();
         let runner = create_runner(
             vec![
@@ -399,10 +710,7 @@ mod tests {
             logger.clone(),
         );
 
-        let names = vec!["producer".to_string(), "consumer".to_string()];
-        let result = runner
-            .run_layer(&names, &std::collections::HashMap::new())
-            .await;
+        let result = runner.run_all_jobs().await;
 
         let error = result.expect_err("expected the failing job to produce an error");
         let message = error.to_string();
@@ -432,7 +740,7 @@ mod tests {
     }
 
     #[actix_web::test]
-    async fn run_layer_collects_error_message_of_every_failing_job() {
+    async fn run_all_jobs_collects_error_message_of_every_failing_job() {
         let logger = Logger::in_memory().into_arc();
         let runner = create_runner(
             vec![
@@ -442,10 +750,7 @@ mod tests {
             logger.clone(),
         );
 
-        let names = vec!["producer".to_string(), "consumer".to_string()];
-        let result = runner
-            .run_layer(&names, &std::collections::HashMap::new())
-            .await;
+        let result = runner.run_all_jobs().await;
 
         let error = result.expect_err("expected the failing jobs to produce an error");
         let message = error.to_string();
@@ -461,21 +766,36 @@ mod tests {
     }
 
     #[actix_web::test]
-    async fn run_layer_of_completed_jobs_returns_ok() {
+    async fn run_all_jobs_of_completed_jobs_returns_ok() {
         let logger = Logger::in_memory().into_arc();
         let runner = create_runner(
             vec![("producer", Job::default()), ("consumer", Job::default())],
             logger.clone(),
         );
 
-        let names = vec!["producer".to_string(), "consumer".to_string()];
+        assert!(runner.run_all_jobs().await.is_ok());
+    }
 
-        assert!(
-            runner
-                .run_layer(&names, &std::collections::HashMap::new())
-                .await
-                .is_ok()
+    #[actix_web::test]
+    async fn run_all_jobs_streams_job_output_prefixed_with_job_name() {
+        let logger = Logger::in_memory().into_arc();
+        let runner = create_runner(
+            vec![("producer", Job::default()), ("consumer", Job::default())],
+            logger.clone(),
         );
+
+        runner.run_all_jobs().await.unwrap();
+
+        let output = logger.try_retrieve_output().await.unwrap();
+        for name in ["producer", "consumer"] {
+            let runs_on = output
+                .find(&format!("[{name}] Runs on"))
+                .unwrap_or_else(|| panic!("expected prefixed output of {name}, got: {output}"));
+            let completed = output
+                .find(&format!("Completed job  : {name}"))
+                .unwrap_or_else(|| panic!("expected completion of {name}, got: {output}"));
+            assert!(runs_on < completed, "{output}");
+        }
     }
 
     fn job_with_outputs(needs: Option<Needs>, outputs: Vec<(&str, &str)>) -> Job {
@@ -489,11 +809,11 @@ mod tests {
         }
     }
 
-    /// Three layers: build (layer 1), publish (layer 2, needs build) and deploy (layer 3,
-    /// needs both build and publish). Deploy reads a value straight from build even though
-    /// it is two layers away, because build is listed directly in its own needs.
+    /// A chain of three jobs: build, publish (needs build) and deploy (needs both build and
+    /// publish). Deploy reads a value straight from build even though it is two jobs away,
+    /// because build is listed directly in its own needs.
     #[actix_web::test]
-    async fn three_layers_collect_and_forward_job_outputs() {
+    async fn chained_jobs_collect_and_forward_job_outputs() {
         let logger = Logger::in_memory().into_arc();
         let runner = create_runner(
             vec![
@@ -518,33 +838,16 @@ mod tests {
             logger.clone(),
         );
 
-        let mut job_outputs = std::collections::HashMap::new();
+        let job_outputs = runner.run_all_jobs().await.unwrap();
 
-        let layer1 = runner
-            .run_layer(&["build".to_string()], &job_outputs)
-            .await
-            .unwrap();
-        job_outputs.extend(layer1);
         assert_eq!(
             job_outputs.get("build").and_then(|m| m.get("version")),
             Some(&"1.2.3".to_string())
         );
-
-        let layer2 = runner
-            .run_layer(&["publish".to_string()], &job_outputs)
-            .await
-            .unwrap();
-        job_outputs.extend(layer2);
         assert_eq!(
             job_outputs.get("publish").and_then(|m| m.get("got")),
             Some(&"1.2.3".to_string())
         );
-
-        let layer3 = runner
-            .run_layer(&["deploy".to_string()], &job_outputs)
-            .await
-            .unwrap();
-        job_outputs.extend(layer3);
         assert_eq!(
             job_outputs.get("deploy").and_then(|m| m.get("final")),
             Some(&"1.2.3".to_string())
@@ -552,10 +855,10 @@ mod tests {
     }
 
     /// A job that is skipped because its condition fails still shows up in the outputs map
-    /// as an empty entry, so a job in the next layer that reads one of its values gets a
-    /// clear error naming the job and the missing output, instead of an order violation.
+    /// as an empty entry, so a job that needs it and reads one of its values gets a clear
+    /// error naming the job and the missing output, instead of an order violation.
     #[actix_web::test]
-    async fn job_skipped_by_condition_gives_empty_outputs_to_the_next_layer() {
+    async fn job_skipped_by_condition_gives_empty_outputs_to_its_dependents() {
         let logger = Logger::in_memory().into_arc();
         let runner = create_runner(
             vec![
@@ -572,14 +875,8 @@ mod tests {
         );
 
         // The condition of "build" doesn't fail the run, it just evaluates to false, so the
-        // job is skipped rather than erroring.
-        let layer1 = runner
-            .run_layer(&["build".to_string()], &std::collections::HashMap::new())
-            .await
-            .unwrap();
-        assert_eq!(layer1.get("build"), Some(&std::collections::HashMap::new()));
-
-        let result = runner.run_layer(&["publish".to_string()], &layer1).await;
+        // job is skipped rather than erroring and the job that needs it still starts.
+        let result = runner.run_all_jobs().await;
         let error = result
             .expect_err("expected an error reading the output of a skipped job")
             .to_string();
@@ -587,5 +884,381 @@ mod tests {
             error.contains("version") && error.contains("build"),
             "{error}"
         );
+        assert!(error.contains("[publish]"), "{error}");
+    }
+
+    #[actix_web::test]
+    async fn failing_job_prevents_its_dependents_from_starting() {
+        let logger = Logger::in_memory().into_arc();
+        let runner = create_runner(
+            vec![
+                ("build", failing_job("${{ true == \"James\" }}")),
+                (
+                    "publish",
+                    job_with_outputs(Some(Needs::Single("build".to_string())), vec![]),
+                ),
+            ],
+            logger.clone(),
+        );
+
+        let error = runner
+            .run_all_jobs()
+            .await
+            .expect_err("expected the failing job to produce an error")
+            .to_string();
+        assert!(error.contains("[build]"), "{error}");
+        assert!(!error.contains("publish"), "{error}");
+
+        let output = logger.try_retrieve_output().await.unwrap();
+        assert!(!output.contains("publish"), "{output}");
+    }
+
+    /// A job with a step that fails to evaluate its condition, so the error reveals
+    /// whether the job ran.
+    fn failing_step_job() -> Job {
+        Job {
+            steps: vec![Step::ComplexSh(Box::new(ShellCommand {
+                condition: Some("${{ 1 }} ${{ 2 }}".to_string()),
+                ..Default::default()
+            }))],
+            ..Default::default()
+        }
+    }
+
+    fn dependent_job(need: &str, condition: Option<&str>) -> Job {
+        Job {
+            needs: Some(Needs::Single(need.to_string())),
+            condition: condition.map(str::to_string),
+            ..failing_step_job()
+        }
+    }
+
+    #[actix_web::test]
+    async fn job_checking_failure_runs_after_its_need_fails() {
+        let runner = create_runner(
+            vec![
+                ("build", failing_job("${{ true == \"James\" }}")),
+                ("report", dependent_job("build", Some("${{ failure() }}"))),
+                ("publish", dependent_job("build", None)),
+            ],
+            Logger::in_memory().into_arc(),
+        );
+
+        let error = runner.run_all_jobs().await.unwrap_err().to_string();
+        assert!(error.contains("[build]"), "{error}");
+        assert!(error.contains("[report]"), "{error}");
+        assert!(error.contains("more than one condition"), "{error}");
+        assert!(!error.contains("publish"), "{error}");
+    }
+
+    #[actix_web::test]
+    async fn job_checking_failure_is_skipped_after_its_need_succeeds() {
+        let runner = create_runner(
+            vec![
+                ("build", Job::default()),
+                ("report", dependent_job("build", Some("${{ failure() }}"))),
+            ],
+            Logger::in_memory().into_arc(),
+        );
+
+        let job_outputs = runner.run_all_jobs().await.unwrap();
+        assert!(job_outputs.contains_key("report"));
+    }
+
+    #[actix_web::test]
+    async fn job_checking_cancelled_runs_after_its_need_is_cancelled() {
+        let runner = create_runner(
+            vec![
+                ("build", failing_job("${{ true == \"James\" }}")),
+                ("publish", dependent_job("build", None)),
+                (
+                    "cleanup",
+                    dependent_job("publish", Some("${{ cancelled() }}")),
+                ),
+                ("notify", dependent_job("publish", Some("${{ failure() }}"))),
+            ],
+            Logger::in_memory().into_arc(),
+        );
+
+        let error = runner.run_all_jobs().await.unwrap_err().to_string();
+        assert!(error.contains("[cleanup]"), "{error}");
+        assert!(!error.contains("publish"), "{error}");
+        assert!(!error.contains("notify"), "{error}");
+    }
+
+    fn matrix_job(job: Job, oses: &[&str], fail_fast: bool, max_parallel: Option<usize>) -> Job {
+        let values = oses.iter().map(|x| x.to_string()).collect();
+        Job {
+            strategy: Some(Strategy {
+                matrix: HashMap::from([("os".to_string(), MatrixValue::Array(values))]),
+                include: vec![],
+                exclude: vec![],
+                fail_fast: Some(FailFastValue::Bool(fail_fast)),
+                max_parallel,
+            }),
+            ..job
+        }
+    }
+
+    #[actix_web::test]
+    async fn matrix_job_runs_an_instance_per_combination() {
+        let logger = Logger::in_memory().into_arc();
+        let runner = create_runner(
+            vec![(
+                "build",
+                matrix_job(failing_step_job(), &["alpine", "debian"], false, None),
+            )],
+            logger.clone(),
+        );
+
+        let error = runner.run_all_jobs().await.unwrap_err().to_string();
+        assert!(error.contains("[build (os=alpine)]"), "{error}");
+        assert!(error.contains("[build (os=debian)]"), "{error}");
+
+        let output = logger.try_retrieve_output().await.unwrap();
+        assert!(
+            output.contains("Running job    : build (os=alpine)"),
+            "{output}"
+        );
+        assert!(
+            output.contains("Running job    : build (os=debian)"),
+            "{output}"
+        );
+    }
+
+    #[actix_web::test]
+    async fn matrix_job_with_fail_fast_cancels_the_remaining_combinations() {
+        let runner = create_runner(
+            vec![(
+                "build",
+                matrix_job(failing_step_job(), &["alpine", "debian"], true, Some(1)),
+            )],
+            Logger::in_memory().into_arc(),
+        );
+
+        let error = runner.run_all_jobs().await.unwrap_err().to_string();
+        assert!(error.contains("[build (os=alpine)]"), "{error}");
+        assert!(!error.contains("debian"), "{error}");
+    }
+
+    #[actix_web::test]
+    async fn matrix_job_merges_the_outputs_of_its_combinations() {
+        let build = matrix_job(
+            job_with_outputs(None, vec![("os", "${{ matrix.os }}")]),
+            &["alpine", "debian"],
+            true,
+            Some(1),
+        );
+        let runner = create_runner(
+            vec![
+                ("build", build),
+                (
+                    "publish",
+                    job_with_outputs(
+                        Some(Needs::Single("build".to_string())),
+                        vec![("got", "${{ jobs.build.outputs.os }}")],
+                    ),
+                ),
+            ],
+            Logger::in_memory().into_arc(),
+        );
+
+        let job_outputs = runner.run_all_jobs().await.unwrap();
+        assert_eq!(
+            job_outputs.get("publish").and_then(|m| m.get("got")),
+            Some(&"debian".to_string())
+        );
+    }
+
+    #[actix_web::test]
+    async fn job_needing_a_failed_matrix_job_is_cancelled() {
+        let build = matrix_job(
+            failing_job("${{ true == \"James\" }}"),
+            &["alpine", "debian"],
+            false,
+            None,
+        );
+        let runner = create_runner(
+            vec![("build", build), ("publish", dependent_job("build", None))],
+            Logger::in_memory().into_arc(),
+        );
+
+        let error = runner.run_all_jobs().await.unwrap_err().to_string();
+        assert!(
+            error.contains("[build (os=debian)] cannot compare"),
+            "{error}"
+        );
+        assert!(!error.contains("publish"), "{error}");
+    }
+
+    /// Adds a step to the job that appends a line to the provided file when it starts and
+    /// another when it ends, sleeping in between so that jobs running in parallel overlap.
+    fn recording_job(job: Job, file: &str) -> Job {
+        let mut job = job;
+        job.steps.push(Step::ComplexSh(Box::new(ShellCommand {
+            id: "record".to_string(),
+            run: format!("echo start >> {file} && sleep 0.2 && echo end >> {file}"),
+            ..Default::default()
+        })));
+        job
+    }
+
+    /// The highest number of jobs that were running at the same time, according to the
+    /// lines appended to the provided file by recording jobs.
+    fn peak_concurrency(file: &str) -> usize {
+        let content = std::fs::read_to_string(file).unwrap();
+        let mut running: usize = 0;
+        let mut peak = 0;
+        for line in content.lines() {
+            match line {
+                "start" => running += 1,
+                "end" => running -= 1,
+                _ => {}
+            }
+            peak = peak.max(running);
+        }
+        peak
+    }
+
+    #[actix_web::test]
+    async fn max_parallel_of_one_still_runs_every_job() {
+        let dir = TempDir::new("max_parallel_of_one_still_runs_every_job");
+        let file = format!("{}/jobs.log", dir.root_dir());
+        let logger = Logger::in_memory().into_arc();
+        let mut runner = create_runner_with_max_parallel(
+            vec![
+                (
+                    "build",
+                    recording_job(job_with_outputs(None, vec![("version", "1.2.3")]), &file),
+                ),
+                (
+                    "lint",
+                    recording_job(job_with_outputs(None, vec![("result", "ok")]), &file),
+                ),
+                (
+                    "test",
+                    recording_job(job_with_outputs(None, vec![("result", "ok")]), &file),
+                ),
+                (
+                    "publish",
+                    job_with_outputs(
+                        Some(Needs::Single("build".to_string())),
+                        vec![("got", "${{ jobs.build.outputs.version }}")],
+                    ),
+                ),
+            ],
+            Some(1),
+            logger.clone(),
+        );
+        runner.config = BldConfig {
+            root_dir: dir.root_dir(),
+            ..Default::default()
+        }
+        .into_arc();
+
+        let job_outputs = runner.run_all_jobs().await.unwrap();
+
+        assert_eq!(job_outputs.len(), 4);
+        assert_eq!(
+            job_outputs.get("publish").and_then(|m| m.get("got")),
+            Some(&"1.2.3".to_string())
+        );
+        assert_eq!(peak_concurrency(&file), 1);
+    }
+
+    #[actix_web::test]
+    async fn jobs_without_max_parallel_run_at_the_same_time() {
+        let dir = TempDir::new("jobs_without_max_parallel_run_at_the_same_time");
+        let file = format!("{}/jobs.log", dir.root_dir());
+        let logger = Logger::in_memory().into_arc();
+        let mut runner = create_runner(
+            vec![
+                ("build", recording_job(Job::default(), &file)),
+                ("lint", recording_job(Job::default(), &file)),
+            ],
+            logger.clone(),
+        );
+        runner.config = BldConfig {
+            root_dir: dir.root_dir(),
+            ..Default::default()
+        }
+        .into_arc();
+
+        runner.run_all_jobs().await.unwrap();
+
+        assert_eq!(peak_concurrency(&file), 2);
+    }
+
+    #[actix_web::test]
+    async fn cancelled_run_runs_post_steps_and_starts_no_more_jobs() {
+        let dir = TempDir::new("cancelled_run_runs_post_steps_and_starts_no_more_jobs");
+        let file = format!("{}/steps.log", dir.root_dir());
+        let sh_step = |id: &str, run: String| {
+            Step::ComplexSh(Box::new(ShellCommand {
+                id: id.to_string(),
+                run,
+                ..Default::default()
+            }))
+        };
+        let build = Job {
+            steps: vec![sh_step("wait", "sleep 10".to_string())],
+            post: vec![sh_step("cleanup", format!("echo cleanup >> {file}"))],
+            ..Default::default()
+        };
+        let publish = Job {
+            needs: Some(Needs::Single("build".to_string())),
+            steps: vec![sh_step("publish", format!("echo publish >> {file}"))],
+            ..Default::default()
+        };
+        let logger = Logger::in_memory().into_arc();
+        let mut runner = create_runner(vec![("build", build), ("publish", publish)], logger);
+        runner.config = BldConfig {
+            root_dir: dir.root_dir(),
+            ..Default::default()
+        }
+        .into_arc();
+
+        let cancel = runner.cancel.clone();
+        spawn(async move {
+            sleep(Duration::from_millis(300)).await;
+            cancel.cancel();
+        });
+
+        let started = Instant::now();
+        let error = runner.run_all_jobs().await.unwrap_err().to_string();
+
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert!(error.contains("[build] cancelled"), "{error}");
+        assert_eq!(read_to_string(&file).unwrap(), "cleanup\n");
+    }
+
+    #[actix_web::test]
+    async fn approval_job_lets_its_dependents_run_once_approved() {
+        let logger = Logger::in_memory().into_arc();
+        let approval = Approval {
+            message: Some("Deploy to production?".to_string()),
+            ..Default::default()
+        };
+        let runner = create_runner(
+            vec![
+                (
+                    "approve",
+                    Job {
+                        approval: Some(approval),
+                        ..Default::default()
+                    },
+                ),
+                ("deploy", dependent_job("approve", None)),
+            ],
+            logger.clone(),
+        );
+
+        let error = runner.run_all_jobs().await.unwrap_err().to_string();
+        assert!(!error.contains("[approve]"), "{error}");
+        assert!(error.contains("[deploy]"), "{error}");
+
+        let output = logger.try_retrieve_output().await.unwrap();
+        assert!(output.contains("Deploy to production?"), "{output}");
+        assert!(!output.contains("[approve] Runs on"), "{output}");
     }
 }
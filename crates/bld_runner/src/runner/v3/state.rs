@@ -4,7 +4,9 @@ use anyhow::{Result, anyhow, bail};
 use mockall::{automock, mock};
 use uuid::Uuid;
 
-use crate::expr::v3::traits::{ExprValue, OutputScope, WritableRuntimeExprContext};
+use crate::expr::v3::traits::{
+    ExprText, ExprValue, OutputScope, RunStatus, WritableRuntimeExprContext,
+};
 
 #[automock]
 pub trait NodeState {
@@ -28,9 +30,54 @@ pub enum State {
     Default,
     Running,
     Completed,
+    /// The node failed but was allowed to, so its parent carried on running.
+    ContinuedOnError {
+        error: String,
+    },
     Failed {
         error: String,
     },
+    /// The node was stopped, or never started, since its run was cut short.
+    Cancelled {
+        error: String,
+    },
+}
+
+impl State {
+    pub fn error(&self) -> Option<String> {
+        match self {
+            Self::Default | Self::Running | Self::Completed => None,
+            Self::ContinuedOnError { error }
+            | Self::Failed { error }
+            | Self::Cancelled { error } => Some(error.to_owned()),
+        }
+    }
+
+    /// The value of the state as exposed to expressions through `steps.<id>.outcome`.
+    pub fn outcome(&self) -> &'static str {
+        match self {
+            Self::Default => "pending",
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::ContinuedOnError { .. } => "continued_on_error",
+            Self::Failed { .. } => "failed",
+            Self::Cancelled { .. } => "cancelled",
+        }
+    }
+}
+
+/// The combined status of a set of nodes, where a cancelled node takes precedence over a
+/// failed one. A node that was allowed to fail counts as a success.
+pub fn run_status<'a>(states: impl Iterator<Item = &'a State>) -> RunStatus {
+    let mut status = RunStatus::Success;
+    for state in states {
+        match state {
+            State::Cancelled { .. } => return RunStatus::Cancelled,
+            State::Failed { .. } => status = RunStatus::Failure,
+            _ => {}
+        }
+    }
+    status
 }
 
 #[derive(Debug, Default, PartialEq)]
@@ -102,6 +149,17 @@ impl WritableRuntimeExprContext for StepState {
     fn get_matrix_value<'a>(&'a self, _name: &str) -> Result<&'a str> {
         bail!("matrix values are not accessible from step state")
     }
+
+    fn get_step_outcome<'a>(&'a self, id: &str) -> Result<ExprValue<'a>> {
+        if self.id != id {
+            bail!("id {id} has no outcome");
+        }
+        Ok(ExprValue::Text(ExprText::Ref(self.state.outcome())))
+    }
+
+    fn get_run_status(&self) -> Result<RunStatus> {
+        Ok(run_status(std::iter::once(&self.state)))
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -229,6 +287,20 @@ impl WritableRuntimeExprContext for JobState {
             .map(|x| x.as_str())
             .ok_or_else(|| anyhow!("matrix value '{name}' not found"))
     }
+
+    fn get_step_outcome<'a>(&'a self, id: &str) -> Result<ExprValue<'a>> {
+        let Some(step_state) = self.steps.get(id) else {
+            bail!("state for step {id} wasn't found");
+        };
+        step_state.get_step_outcome(id)
+    }
+
+    // The job's own state fails once something other than a step does, such as saving its
+    // caches, which its post steps get to see.
+    fn get_run_status(&self) -> Result<RunStatus> {
+        let steps = self.steps.values().map(|x| &x.state);
+        Ok(run_status(steps.chain(std::iter::once(&self.state))))
+    }
 }
 
 pub struct ActionState {
@@ -314,6 +386,18 @@ impl WritableRuntimeExprContext for ActionState {
             .map(|x| x.as_str())
             .ok_or_else(|| anyhow!("matrix value '{name}' not found"))
     }
+
+    fn get_step_outcome<'a>(&'a self, id: &str) -> Result<ExprValue<'a>> {
+        let Some(step_state) = self.steps.get(id) else {
+            bail!("state for step {id} wasn't found");
+        };
+        step_state.get_step_outcome(id)
+    }
+
+    fn get_run_status(&self) -> Result<RunStatus> {
+        let steps = self.steps.values().map(|x| &x.state);
+        Ok(run_status(steps.chain(std::iter::once(&self.state))))
+    }
 }
 
 mock! {
@@ -334,6 +418,8 @@ mock! {
         fn set_output(&mut self, id: &str, name: String, value: String) -> Result<()>;
         fn set_outputs(&mut self, id: &str, outputs: HashMap<String, String>) -> Result<()>;
         fn get_matrix_value<'a>(&'a self, name: &str) -> Result<&'a str>;
+        fn get_step_outcome<'a>(&'a self, id: &str) -> Result<ExprValue<'a>>;
+        fn get_run_status(&self) -> Result<RunStatus>;
     }
 }
 
@@ -344,8 +430,12 @@ mod tests {
     use uuid::Uuid;
 
     use crate::{
-        expr::v3::traits::{ExprText, ExprValue, OutputScope, WritableRuntimeExprContext},
-        runner::v3::state::{ActionState, JobState, NodeState, RootState, State, StepState},
+        expr::v3::traits::{
+            ExprText, ExprValue, OutputScope, RunStatus, WritableRuntimeExprContext,
+        },
+        runner::v3::state::{
+            ActionState, JobState, NodeState, RootState, State, StepState, run_status,
+        },
     };
 
     #[test]
@@ -792,4 +882,49 @@ mod tests {
         let result = state.set_outputs(&step_id, outputs);
         assert!(result.is_ok())
     }
+
+    #[test]
+    pub fn run_status_success() {
+        let failed = State::Failed {
+            error: "error".to_string(),
+        };
+        let cancelled = State::Cancelled {
+            error: "error".to_string(),
+        };
+        let continued = State::ContinuedOnError {
+            error: "error".to_string(),
+        };
+
+        let data = [
+            (vec![], RunStatus::Success),
+            (vec![&State::Default, &State::Completed], RunStatus::Success),
+            (vec![&State::Completed, &continued], RunStatus::Success),
+            (vec![&State::Completed, &failed], RunStatus::Failure),
+            (vec![&failed, &cancelled], RunStatus::Cancelled),
+            (vec![&cancelled, &failed], RunStatus::Cancelled),
+        ];
+
+        for (states, expected) in data {
+            assert_eq!(run_status(states.into_iter()), expected);
+        }
+    }
+
+    #[test]
+    pub fn job_state_get_run_status_success() {
+        let mut state = JobState::new("main");
+        state.add_node("first");
+        state.add_node("second");
+        assert_eq!(state.get_run_status().unwrap(), RunStatus::Success);
+
+        state.update_node_state("first", State::Completed);
+        assert_eq!(state.get_run_status().unwrap(), RunStatus::Success);
+
+        state.update_node_state(
+            "second",
+            State::Failed {
+                error: "error".to_string(),
+            },
+        );
+        assert_eq!(state.get_run_status().unwrap(), RunStatus::Failure);
+    }
 }
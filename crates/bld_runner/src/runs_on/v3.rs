@@ -50,6 +50,11 @@ pub enum RunsOn {
     SshFromGlobalConfig {
         ssh_config: String,
     },
+    /// Runs on the machine of an agent that has every one of the labels. The labels are
+    /// used to route the run before it starts, so they can't contain expressions.
+    Labels {
+        labels: Vec<String>,
+    },
 }
 
 impl Default for RunsOn {
@@ -67,6 +72,7 @@ impl Display for RunsOn {
             Self::Build { name, tag, .. } => write!(f, "{name}:{tag}"),
             Self::SshFromGlobalConfig { ssh_config } => write!(f, "{ssh_config}"),
             Self::Ssh(config) => write!(f, "{}:{}", config.host, config.port),
+            Self::Labels { labels } => write!(f, "agent [{}]", labels.join(", ")),
         }
     }
 }
@@ -96,6 +102,23 @@ impl RunsOn {
         }
     }
 
+    /// Whether the job runs on a container, which for an expression is assumed until it
+    /// has been evaluated.
+    pub fn is_container(&self) -> bool {
+        match self {
+            Self::ContainerOrMachine(image) => image != "machine",
+            Self::Pull { .. } | Self::Build { .. } => true,
+            Self::Ssh(_) | Self::SshFromGlobalConfig { .. } | Self::Labels { .. } => false,
+        }
+    }
+
+    pub fn labels(&self) -> &[String] {
+        match self {
+            Self::Labels { labels } => labels,
+            _ => &[],
+        }
+    }
+
     pub fn volumes(&self) -> &[String] {
         match self {
             RunsOn::Pull { volumes, .. } | RunsOn::Build { volumes, .. } => volumes,
@@ -165,6 +188,10 @@ impl RunsOn {
             Self::SshFromGlobalConfig { ssh_config } => Self::SshFromGlobalConfig {
                 ssh_config: eval(ssh_config)?,
             },
+
+            Self::Labels { labels } => Self::Labels {
+                labels: labels.clone(),
+            },
         };
 
         Ok(value)
@@ -267,6 +294,16 @@ impl<'a> EvalObject<'a> for RunsOn {
                     value => bail!("invalid runs_on field: {value}"),
                 }
             }
+
+            Self::Labels { labels } => {
+                let Some(next) = path.next() else {
+                    bail!("expected a path for evaluating runs_on",);
+                };
+                match next.as_span().as_str() {
+                    "labels" => ExprValue::Text(ExprText::Owned(labels.join(","))),
+                    value => bail!("invalid runs_on field: {value}"),
+                }
+            }
         };
 
         if path.peek().is_some() {
@@ -436,6 +473,21 @@ impl<'a> Validate<'a> for RunsOn {
                 validate_global_ssh_config(ctx, ssh_config);
             }
 
+            RunsOn::Labels { labels } => {
+                ctx.push_section("labels");
+                if labels.is_empty() {
+                    ctx.append_error("At least one label is required");
+                }
+                for label in labels {
+                    if ctx.contains_expressions(label) {
+                        ctx.append_error(&format!(
+                            "'{label}' can't contain expressions since labels are used before the run starts"
+                        ));
+                    }
+                }
+                ctx.pop_section();
+            }
+
             RunsOn::Ssh(config) => {
                 ctx.push_section("host");
                 ctx.validate_expressions(&config.host, ExprScope::StartOfRun);
@@ -712,6 +764,34 @@ volumes:
         }
     }
 
+    #[test]
+    pub fn runs_on_labels_deserializes() {
+        let runs_on: RunsOn = serde_yaml_ng::from_str("labels: [linux, docker]").unwrap();
+        match runs_on {
+            RunsOn::Labels { labels } => assert_eq!(labels, vec!["linux", "docker"]),
+            other => panic!("expected a Labels runs_on, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    pub async fn runs_on_labels_validation_failure() {
+        let data = vec![
+            RunsOn::Labels { labels: vec![] },
+            RunsOn::Labels {
+                labels: vec!["linux".to_string(), "${{ inputs.os }}".to_string()],
+            },
+        ];
+
+        for runs_on in data {
+            assert!(validate_runs_on(runs_on).await.is_err());
+        }
+
+        let runs_on = RunsOn::Labels {
+            labels: vec!["linux".to_string()],
+        };
+        assert!(validate_runs_on(runs_on).await.is_ok());
+    }
+
     #[test]
     pub fn runs_on_pull_volumes_default_empty() {
         let runs_on: RunsOn = serde_yaml_ng::from_str("image: my-image:latest").unwrap();
@@ -0,0 +1,216 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[cfg(feature = "all")]
+use {
+    crate::{
+        timeout::v3::parse as parse_duration,
+        validator::v3::{ExprScope, Validate, ValidatorContext},
+    },
+    anyhow::Result,
+    bld_core::platform::{ContainerService, ServiceHealthCheck, parse_port},
+    std::time::Duration,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub command: String,
+    pub interval: Option<String>,
+    pub retries: Option<u32>,
+}
+
+impl HealthCheck {
+    pub fn default_retries() -> u32 {
+        30
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Service {
+    pub image: String,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub ports: Vec<String>,
+    pub health_check: Option<HealthCheck>,
+}
+
+#[cfg(feature = "all")]
+impl HealthCheck {
+    pub fn default_interval() -> Duration {
+        Duration::from_secs(2)
+    }
+}
+
+#[cfg(feature = "all")]
+impl Service {
+    /// Creates the options for starting the service with every expression replaced by
+    /// its value.
+    pub fn resolve<F: Fn(&str) -> Result<String>>(
+        &self,
+        name: &str,
+        eval: F,
+    ) -> Result<ContainerService> {
+        let env = self
+            .env
+            .iter()
+            .map(|(k, v)| Ok((k.to_owned(), eval(v)?)))
+            .collect::<Result<HashMap<String, String>>>()?;
+
+        let health_check = self
+            .health_check
+            .as_ref()
+            .map(|x| {
+                Ok::<_, anyhow::Error>(ServiceHealthCheck {
+                    command: eval(&x.command)?,
+                    interval: x
+                        .interval
+                        .as_deref()
+                        .map(parse_duration)
+                        .transpose()?
+                        .unwrap_or_else(HealthCheck::default_interval),
+                    retries: x.retries.unwrap_or_else(HealthCheck::default_retries),
+                })
+            })
+            .transpose()?;
+
+        Ok(ContainerService {
+            name: name.to_owned(),
+            image: eval(&self.image)?,
+            env,
+            ports: self.ports.clone(),
+            health_check,
+        })
+    }
+}
+
+/// A service is reachable through its name from the job's container, so it is limited
+/// to the characters of a network alias.
+#[cfg(feature = "all")]
+pub fn validate_service_name<'a, C: ValidatorContext<'a>>(ctx: &mut C, name: &str) {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+    if !valid {
+        ctx.append_error(&format!(
+            "'{name}' is not a valid service name (use letters, digits, '-', '_' or '.')"
+        ));
+    }
+}
+
+// Services are started along with the job's platform, before any step has run, so
+// their expressions are limited to the start of run context.
+#[cfg(feature = "all")]
+impl<'a> Validate<'a> for Service {
+    async fn validate<C: ValidatorContext<'a>>(&'a self, ctx: &mut C) {
+        ctx.push_section("image");
+        ctx.validate_expressions(&self.image, ExprScope::StartOfRun);
+        ctx.pop_section();
+
+        ctx.push_section("env");
+        ctx.validate_env(&self.env, ExprScope::StartOfRun);
+        ctx.pop_section();
+
+        ctx.push_section("ports");
+        for port in &self.ports {
+            if let Err(e) = parse_port(port) {
+                ctx.append_error(&e.to_string());
+            }
+        }
+        ctx.pop_section();
+
+        if let Some(health_check) = self.health_check.as_ref() {
+            ctx.push_section("health_check");
+            health_check.validate(ctx).await;
+            ctx.pop_section();
+        }
+    }
+}
+
+#[cfg(feature = "all")]
+impl<'a> Validate<'a> for HealthCheck {
+    async fn validate<C: ValidatorContext<'a>>(&'a self, ctx: &mut C) {
+        ctx.push_section("command");
+        ctx.validate_expressions(&self.command, ExprScope::StartOfRun);
+        ctx.pop_section();
+
+        if let Some(interval) = self.interval.as_deref() {
+            ctx.push_section("interval");
+            if let Err(e) = parse_duration(interval) {
+                ctx.append_error(&e.to_string());
+            }
+            ctx.pop_section();
+        }
+
+        if self.retries == Some(0) {
+            ctx.push_section("retries");
+            ctx.append_error("Health check retries must be greater than zero");
+            ctx.pop_section();
+        }
+    }
+}
+
+#[cfg(all(test, feature = "all"))]
+mod tests {
+    use std::time::Duration;
+
+    use super::Service;
+
+    #[test]
+    pub fn service_deserializes_with_defaults() {
+        let yaml = r#"
+image: postgres:16
+env:
+  POSTGRES_PASSWORD: secret
+ports:
+  - 5432:5432
+health_check:
+  command: pg_isready
+"#;
+        let service: Service = serde_yaml_ng::from_str(yaml).unwrap();
+        let resolved = service.resolve("db", |x| Ok(x.to_owned())).unwrap();
+
+        assert_eq!(resolved.name, "db");
+        assert_eq!(resolved.image, "postgres:16");
+        assert_eq!(
+            resolved.env.get("POSTGRES_PASSWORD").map(String::as_str),
+            Some("secret")
+        );
+        assert_eq!(resolved.ports, vec!["5432:5432".to_string()]);
+
+        let health_check = resolved.health_check.unwrap();
+        assert_eq!(health_check.command, "pg_isready");
+        assert_eq!(health_check.interval, Duration::from_secs(2));
+        assert_eq!(health_check.retries, 30);
+    }
+
+    #[test]
+    pub fn service_resolve_evaluates_expressions() {
+        let yaml = r#"
+image: ${{ inputs.image }}
+env:
+  PASSWORD: ${{ inputs.password }}
+health_check:
+  command: check
+  interval: 5s
+  retries: 3
+"#;
+        let service: Service = serde_yaml_ng::from_str(yaml).unwrap();
+        let resolved = service
+            .resolve("cache", |x| {
+                Ok(x.replace("${{ inputs.image }}", "redis")
+                    .replace("${{ inputs.password }}", "pass"))
+            })
+            .unwrap();
+
+        assert_eq!(resolved.image, "redis");
+        assert_eq!(
+            resolved.env.get("PASSWORD").map(String::as_str),
+            Some("pass")
+        );
+        let health_check = resolved.health_check.unwrap();
+        assert_eq!(health_check.interval, Duration::from_secs(5));
+        assert_eq!(health_check.retries, 3);
+    }
+}
@@ -1,9 +1,13 @@
 use crate::{
     artifacts::v3::{DownloadArtifact, UploadArtifact},
+    cache::v3::CacheStep,
+    checkout::v3::CheckoutStep,
     external::v3::External,
+    retry::v3::Retry,
     strategy::v3::Strategy,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 #[cfg(feature = "all")]
@@ -11,13 +15,15 @@ use {
     crate::{
         deps::v3::{Dependencies, Dependency, RemoteDependency},
         expr::v3::{
+            exec::CommonExprExecutor,
             parser::Rule,
             traits::{
-                EvalObject, ExprText, ExprValue, OutputScope, ReadonlyRuntimeExprContext,
+                EvalExpr, EvalObject, ExprText, ExprValue, OutputScope, ReadonlyRuntimeExprContext,
                 WritableRuntimeExprContext,
             },
         },
         strategy::v3::validate_matrix_refs,
+        timeout::v3::validate_timeout,
         validator::v3::{ExprScope, Validate, ValidatorContext},
     },
     anyhow::{Result, bail},
@@ -30,16 +36,28 @@ use {
     tracing::debug,
 };
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ContinueOnErrorValue {
+    Bool(bool),
+    Expr(String),
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShellCommand {
     #[serde(default = "ShellCommand::default_id")]
     pub id: String,
     pub name: Option<String>,
     pub working_dir: Option<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
     pub run: String,
     #[serde(rename = "if")]
     pub condition: Option<String>,
     pub strategy: Option<Strategy>,
+    pub timeout: Option<String>,
+    pub continue_on_error: Option<ContinueOnErrorValue>,
+    pub retry: Option<Retry>,
 }
 
 impl ShellCommand {
@@ -54,9 +72,13 @@ impl Default for ShellCommand {
             id: Self::default_id(),
             name: None,
             working_dir: None,
+            env: HashMap::new(),
             run: String::new(),
             condition: None,
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         }
     }
 }
@@ -68,6 +90,8 @@ pub enum Step {
     ExternalFile(Box<External>),
     DownloadArtifact(Box<DownloadArtifact>),
     UploadArtifact(Box<UploadArtifact>),
+    Cache(Box<CacheStep>),
+    Checkout(Box<CheckoutStep>),
 }
 
 impl Step {
@@ -81,6 +105,19 @@ impl Step {
             Self::ExternalFile(ext) => &ext.id,
             Self::DownloadArtifact(value) => &value.id,
             Self::UploadArtifact(value) => &value.id,
+            Self::Cache(value) => &value.id,
+            Self::Checkout(value) => &value.id,
+        }
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Self::ComplexSh(cmd) => cmd.name.as_deref(),
+            Self::ExternalFile(ext) => ext.name.as_deref(),
+            Self::DownloadArtifact(_) => None,
+            Self::UploadArtifact(_) => None,
+            Self::Cache(value) => value.name.as_deref(),
+            Self::Checkout(value) => value.name.as_deref(),
         }
     }
 
@@ -90,6 +127,41 @@ impl Step {
             Self::ExternalFile(ext) => ext.strategy.as_ref(),
             Self::DownloadArtifact(_) => None,
             Self::UploadArtifact(_) => None,
+            Self::Cache(_) => None,
+            Self::Checkout(_) => None,
+        }
+    }
+
+    pub fn timeout(&self) -> Option<&str> {
+        match self {
+            Self::ComplexSh(cmd) => cmd.timeout.as_deref(),
+            Self::ExternalFile(ext) => ext.timeout.as_deref(),
+            Self::DownloadArtifact(_) => None,
+            Self::UploadArtifact(_) => None,
+            Self::Cache(_) => None,
+            Self::Checkout(_) => None,
+        }
+    }
+
+    pub fn continue_on_error(&self) -> Option<&ContinueOnErrorValue> {
+        match self {
+            Self::ComplexSh(cmd) => cmd.continue_on_error.as_ref(),
+            Self::ExternalFile(ext) => ext.continue_on_error.as_ref(),
+            Self::DownloadArtifact(download) => download.continue_on_error.as_ref(),
+            Self::UploadArtifact(upload) => upload.continue_on_error.as_ref(),
+            Self::Cache(cache) => cache.continue_on_error.as_ref(),
+            Self::Checkout(checkout) => checkout.continue_on_error.as_ref(),
+        }
+    }
+
+    pub fn retry(&self) -> Option<&Retry> {
+        match self {
+            Self::ComplexSh(cmd) => cmd.retry.as_ref(),
+            Self::ExternalFile(ext) => ext.retry.as_ref(),
+            Self::DownloadArtifact(download) => download.retry.as_ref(),
+            Self::UploadArtifact(upload) => upload.retry.as_ref(),
+            Self::Cache(cache) => cache.retry.as_ref(),
+            Self::Checkout(checkout) => checkout.retry.as_ref(),
         }
     }
 
@@ -99,6 +171,44 @@ impl Step {
             Self::ExternalFile(ext) => ext.condition.as_deref(),
             Self::DownloadArtifact(download) => download.condition.as_deref(),
             Self::UploadArtifact(upload) => upload.condition.as_deref(),
+            Self::Cache(cache) => cache.condition.as_deref(),
+            Self::Checkout(checkout) => checkout.condition.as_deref(),
+        }
+    }
+
+    #[cfg(feature = "all")]
+    pub fn resolve_continue_on_error<'a, T, RCtx, WCtx>(
+        &'a self,
+        exec: &CommonExprExecutor<'a, T, RCtx, WCtx>,
+    ) -> Result<bool>
+    where
+        T: EvalObject<'a>,
+        RCtx: ReadonlyRuntimeExprContext<'a>,
+        WCtx: WritableRuntimeExprContext,
+    {
+        match self.continue_on_error() {
+            None => Ok(false),
+            Some(ContinueOnErrorValue::Bool(value)) => Ok(*value),
+            Some(ContinueOnErrorValue::Expr(expr)) => exec.eval(expr)?.try_into(),
+        }
+    }
+
+    /// Validates the fields that control how a failure of the step is handled, which
+    /// are shared by every kind of step.
+    #[cfg(feature = "all")]
+    async fn validate_error_handling<'a, C: ValidatorContext<'a>>(&'a self, ctx: &mut C) {
+        if let Some(ContinueOnErrorValue::Expr(expr)) = self.continue_on_error() {
+            debug!("Validating step's continue_on_error value");
+            ctx.push_section("continue_on_error");
+            ctx.validate_condition(expr, ExprScope::Runtime);
+            ctx.pop_section();
+        }
+
+        if let Some(retry) = self.retry() {
+            debug!("Validating step's retry section");
+            ctx.push_section("retry");
+            retry.validate(ctx).await;
+            ctx.pop_section();
         }
     }
 
@@ -157,6 +267,7 @@ impl Step {
                 if let Some(wd) = cmd.working_dir.as_deref() {
                     values.push(wd);
                 }
+                values.extend(cmd.env.values().map(|x| x.as_str()));
                 if let Some(cond) = cmd.condition.as_deref() {
                     values.push(cond);
                 }
@@ -194,6 +305,32 @@ impl Step {
                 }
                 values
             }
+
+            Step::Cache(cache) => {
+                let mut values = vec![cache.cache.key.as_str()];
+                values.extend(cache.cache.paths.iter().map(|x| x.as_str()));
+                values.extend(cache.cache.restore_keys.iter().map(|x| x.as_str()));
+                if let Some(name) = cache.name.as_deref() {
+                    values.push(name);
+                }
+                if let Some(cond) = cache.condition.as_deref() {
+                    values.push(cond);
+                }
+                values
+            }
+
+            Step::Checkout(checkout) => {
+                let mut values = vec![checkout.checkout.repository.as_str()];
+                values.extend(checkout.checkout.reference.as_deref());
+                values.extend(checkout.checkout.path.as_deref());
+                if let Some(name) = checkout.name.as_deref() {
+                    values.push(name);
+                }
+                if let Some(cond) = checkout.condition.as_deref() {
+                    values.push(cond);
+                }
+                values
+            }
         }
     }
 }
@@ -247,6 +384,10 @@ impl<'a> EvalObject<'a> for Step {
 
         let key = object.as_span().as_str();
 
+        if key == "outcome" {
+            return wctx.get_step_outcome(self.id());
+        }
+
         let value = match self {
             Self::ComplexSh(command) => match key {
                 "name" => ExprValue::Text(ExprText::Ref(command.name.as_deref().unwrap_or(""))),
@@ -282,6 +423,28 @@ impl<'a> EvalObject<'a> for Step {
             Self::UploadArtifact(_) => {
                 bail!("invalid expression for step");
             }
+
+            Self::Cache(cache) => match key {
+                "outputs" => {
+                    let Some(object) = path.next() else {
+                        bail!("no output variable name provided");
+                    };
+                    let name = object.as_span().as_str();
+                    wctx.get_output(OutputScope::Step, &cache.id, name)?
+                }
+                value => bail!("invalid expression for step: {value}"),
+            },
+
+            Self::Checkout(checkout) => match key {
+                "outputs" => {
+                    let Some(object) = path.next() else {
+                        bail!("no output variable name provided");
+                    };
+                    let name = object.as_span().as_str();
+                    wctx.get_output(OutputScope::Step, &checkout.id, name)?
+                }
+                value => bail!("invalid expression for step: {value}"),
+            },
         };
 
         Ok(value)
@@ -310,6 +473,11 @@ impl<'a> Validate<'a> for Step {
                     ctx.pop_section();
                 }
 
+                debug!("Validating step's env section");
+                ctx.push_section("env");
+                ctx.validate_env(&complex.env, ExprScope::Runtime);
+                ctx.pop_section();
+
                 if let Some(condition) = &complex.condition {
                     debug!("Validating step's if condition");
                     ctx.push_section("if");
@@ -317,6 +485,11 @@ impl<'a> Validate<'a> for Step {
                     ctx.pop_section();
                 }
 
+                if let Some(timeout) = complex.timeout.as_deref() {
+                    debug!("Validating step's timeout");
+                    validate_timeout(ctx, timeout);
+                }
+
                 debug!("Validating step's run command");
                 ctx.push_section("run");
                 if complex.run.trim().is_empty() {
@@ -325,6 +498,7 @@ impl<'a> Validate<'a> for Step {
                 ctx.validate_expressions(&complex.run, ExprScope::Runtime);
                 ctx.pop_section();
 
+                self.validate_error_handling(ctx).await;
                 ctx.pop_section();
             }
 
@@ -332,6 +506,7 @@ impl<'a> Validate<'a> for Step {
                 debug!("Step is an external file");
                 ctx.push_section(&external.id);
                 external.validate(ctx).await;
+                self.validate_error_handling(ctx).await;
                 ctx.pop_section();
             }
 
@@ -339,6 +514,7 @@ impl<'a> Validate<'a> for Step {
                 debug!("Step is an artifact download");
                 ctx.push_section(&download.id);
                 download.validate(ctx).await;
+                self.validate_error_handling(ctx).await;
                 ctx.pop_section();
             }
 
@@ -346,6 +522,23 @@ impl<'a> Validate<'a> for Step {
                 debug!("Step is an artifact upload");
                 ctx.push_section(&upload.id);
                 upload.validate(ctx).await;
+                self.validate_error_handling(ctx).await;
+                ctx.pop_section();
+            }
+
+            Step::Cache(cache) => {
+                debug!("Step is a cache");
+                ctx.push_section(&cache.id);
+                cache.validate(ctx).await;
+                self.validate_error_handling(ctx).await;
+                ctx.pop_section();
+            }
+
+            Step::Checkout(checkout) => {
+                debug!("Step is a checkout");
+                ctx.push_section(&checkout.id);
+                checkout.validate(ctx).await;
+                self.validate_error_handling(ctx).await;
                 ctx.pop_section();
             }
         }
@@ -364,6 +557,8 @@ mod tests {
     use crate::{
         action::v3::Action,
         artifacts::v3::{DownloadArtifact, UploadArtifact},
+        cache::v3::{CacheOptions, CacheStep},
+        checkout::v3::{CheckoutOptions, CheckoutStep},
         expr::v3::{
             context::CommonReadonlyRuntimeExprContext,
             exec::CommonExprExecutor,
@@ -372,7 +567,8 @@ mod tests {
         job::v3::Job,
         outputs::v3::Output,
         pipeline::v3::Pipeline,
-        step::v3::{ShellCommand, Step},
+        retry::v3::{Backoff, Retry},
+        step::v3::{ContinueOnErrorValue, ShellCommand, Step},
         strategy::v3::{MatrixValue, Strategy},
         validator::v3::{CommonValidator, ConsumeValidator, ValidatorWritableRuntimeExprContext},
     };
@@ -390,17 +586,25 @@ mod tests {
                         id: "second".to_string(),
                         name: Some("second_name".to_string()),
                         working_dir: Some("some_second_working_directory".to_string()),
+                        env: HashMap::new(),
                         run: "second_run_command".to_string(),
                         condition: Some("second_condition".to_string()),
                         strategy: None,
+                        timeout: None,
+                        continue_on_error: None,
+                        retry: None,
                     })),
                     Step::ComplexSh(Box::new(ShellCommand {
                         id: "third".to_string(),
                         name: Some("third_name".to_string()),
                         working_dir: Some("some_third_working_directory".to_string()),
+                        env: HashMap::new(),
                         run: "third_run_command".to_string(),
                         condition: Some("third_condition".to_string()),
                         strategy: None,
+                        timeout: None,
+                        continue_on_error: None,
+                        retry: None,
                     })),
                 ],
                 ..Default::default()
@@ -413,9 +617,13 @@ mod tests {
                     id: "first".to_string(),
                     name: Some("first_name".to_string()),
                     working_dir: Some("some_first_working_directory".to_string()),
+                    env: HashMap::new(),
                     run: "first_run_command".to_string(),
                     condition: Some("first_condition".to_string()),
                     strategy: None,
+                    timeout: None,
+                    continue_on_error: None,
+                    retry: None,
                 }))],
                 ..Default::default()
             },
@@ -507,25 +715,37 @@ mod tests {
             id: "second".to_string(),
             name: Some("second_name".to_string()),
             working_dir: Some("some_second_working_directory".to_string()),
+            env: HashMap::new(),
             run: "second_run_command".to_string(),
             condition: Some("second_condition".to_string()),
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
         action.steps.push(Step::ComplexSh(Box::new(ShellCommand {
             id: "third".to_string(),
             name: Some("third_name".to_string()),
             working_dir: Some("some_third_working_directory".to_string()),
+            env: HashMap::new(),
             run: "third_run_command".to_string(),
             condition: Some("third_condition".to_string()),
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
         action.steps.push(Step::ComplexSh(Box::new(ShellCommand {
             id: "first".to_string(),
             name: Some("first_name".to_string()),
             working_dir: Some("some_first_working_directory".to_string()),
+            env: HashMap::new(),
             run: "first_run_command".to_string(),
             condition: Some("first_condition".to_string()),
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
 
         let exec = CommonExprExecutor::new(&action, &rctx, &wctx);
@@ -710,7 +930,11 @@ mod tests {
                         run: String::new(),
                         condition: None,
                         working_dir: None,
+                        env: HashMap::new(),
                         strategy: None,
+                        timeout: None,
+                        continue_on_error: None,
+                        retry: None,
                     })));
                 }
 
@@ -785,7 +1009,11 @@ mod tests {
                     run: String::new(),
                     condition: None,
                     working_dir: None,
+                    env: HashMap::new(),
                     strategy: None,
+                    timeout: None,
+                    continue_on_error: None,
+                    retry: None,
                 })));
             }
             for (name, value) in outputs.iter() {
@@ -867,6 +1095,52 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn cache_step_deserializes() {
+        let yaml = r#"
+cache:
+  key: cargo-${{ hash_files('**/Cargo.lock') }}
+  paths:
+    - target
+  restore_keys:
+    - cargo-
+"#;
+        let step: Step = serde_yaml_ng::from_str(yaml).unwrap();
+
+        let Step::Cache(cache) = step else {
+            panic!("expected a cache step, got {step:?}");
+        };
+        assert_eq!(cache.cache.key, "cargo-${{ hash_files('**/Cargo.lock') }}");
+        assert_eq!(cache.cache.paths, vec!["target".to_string()]);
+        assert_eq!(cache.cache.restore_keys, vec!["cargo-".to_string()]);
+    }
+
+    #[test]
+    pub fn checkout_step_deserializes() {
+        let yaml = r#"
+checkout:
+  repository: https://github.com/Kani-Maki-Gang/bld.git
+  ref: v0.5.0
+  depth: 1
+  submodules: true
+  path: sources
+"#;
+        let step: Step = serde_yaml_ng::from_str(yaml).unwrap();
+
+        let Step::Checkout(checkout) = step else {
+            panic!("expected a checkout step, got {step:?}");
+        };
+        assert_eq!(
+            checkout.checkout.repository,
+            "https://github.com/Kani-Maki-Gang/bld.git"
+        );
+        assert_eq!(checkout.checkout.reference.as_deref(), Some("v0.5.0"));
+        assert_eq!(checkout.checkout.depth, Some(1));
+        assert!(checkout.checkout.submodules);
+        assert!(!checkout.checkout.lfs);
+        assert_eq!(checkout.checkout.path.as_deref(), Some("sources"));
+    }
+
     async fn validate_action(action: &Action) -> anyhow::Result<()> {
         let config = BldConfig::default().into_arc();
         let fs = FileSystem::local(config.clone()).into_arc();
@@ -879,6 +1153,75 @@ mod tests {
             .await
     }
 
+    #[tokio::test]
+    pub async fn continue_on_error_and_retry_pass_validation() {
+        let mut action = Action::default();
+        action.steps.push(Step::ComplexSh(Box::new(ShellCommand {
+            id: "first".to_string(),
+            run: "echo hello".to_string(),
+            ..Default::default()
+        })));
+        action.steps.push(Step::ComplexSh(Box::new(ShellCommand {
+            id: "second".to_string(),
+            run: "echo hello".to_string(),
+            continue_on_error: Some(ContinueOnErrorValue::Expr(
+                "${{ steps.first.outcome == \"completed\" }}".to_string(),
+            )),
+            retry: Some(Retry {
+                attempts: 3,
+                delay: Some("10s".to_string()),
+                backoff: Backoff::Exponential,
+            }),
+            ..Default::default()
+        })));
+
+        let result = validate_action(&action).await;
+
+        assert!(result.is_ok(), "unexpected error: {:?}", result.err());
+    }
+
+    #[tokio::test]
+    pub async fn retry_with_zero_attempts_and_invalid_delay_fails_validation() {
+        let mut action = Action::default();
+        action
+            .steps
+            .push(Step::UploadArtifact(Box::new(UploadArtifact {
+                id: "upload".to_string(),
+                upload: "some/path".to_string(),
+                name: "artifact".to_string(),
+                retry: Some(Retry {
+                    attempts: 0,
+                    delay: Some("soon".to_string()),
+                    backoff: Backoff::Fixed,
+                }),
+                ..Default::default()
+            })));
+
+        let Err(e) = validate_action(&action).await else {
+            panic!("expected an error for an invalid retry section");
+        };
+
+        assert!(e.to_string().contains("attempts"), "{e}");
+        assert!(e.to_string().contains("delay"), "{e}");
+    }
+
+    #[tokio::test]
+    pub async fn continue_on_error_with_multiple_expressions_fails_validation() {
+        let mut action = Action::default();
+        action.steps.push(Step::ComplexSh(Box::new(ShellCommand {
+            id: "first".to_string(),
+            run: "echo hello".to_string(),
+            continue_on_error: Some(ContinueOnErrorValue::Expr(
+                "${{ true }} ${{ false }}".to_string(),
+            )),
+            ..Default::default()
+        })));
+
+        let result = validate_action(&action).await;
+
+        assert!(result.is_err());
+    }
+
     #[tokio::test]
     pub async fn condition_without_expression_wrapper_fails_validation() {
         let mut action = Action::default();
@@ -886,9 +1229,13 @@ mod tests {
             id: "first".to_string(),
             name: None,
             working_dir: None,
+            env: HashMap::new(),
             run: "echo hello".to_string(),
             condition: Some("true".to_string()),
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
 
         let result = validate_action(&action).await;
@@ -903,9 +1250,13 @@ mod tests {
             id: "first".to_string(),
             name: None,
             working_dir: None,
+            env: HashMap::new(),
             run: "echo hello".to_string(),
             condition: Some("${{ true }} ${{ false }}".to_string()),
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
 
         let result = validate_action(&action).await;
@@ -920,9 +1271,13 @@ mod tests {
             id: "first".to_string(),
             name: None,
             working_dir: None,
+            env: HashMap::new(),
             run: "echo hello".to_string(),
             condition: Some("${{ true }}".to_string()),
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
 
         let result = validate_action(&action).await;
@@ -940,6 +1295,8 @@ mod tests {
                 upload: "report.xml".to_string(),
                 name: "test-report".to_string(),
                 condition: Some("${{ true }} ${{ false }}".to_string()),
+                continue_on_error: None,
+                retry: None,
             })));
 
         let result = validate_action(&action).await;
@@ -957,6 +1314,8 @@ mod tests {
                 download: "test-report".to_string(),
                 to: "reports".to_string(),
                 condition: Some("${{ steps.missing.outputs.value }}".to_string()),
+                continue_on_error: None,
+                retry: None,
             })));
 
         let result = validate_action(&action).await;
@@ -974,6 +1333,8 @@ mod tests {
                 download: "test-report".to_string(),
                 to: "reports".to_string(),
                 condition: Some("${{ true }}".to_string()),
+                continue_on_error: None,
+                retry: None,
             })));
 
         let result = validate_action(&action).await;
@@ -981,6 +1342,158 @@ mod tests {
         assert!(result.is_ok(), "unexpected error: {:?}", result.err());
     }
 
+    #[tokio::test]
+    pub async fn cache_step_with_hash_files_key_passes_validation() {
+        let mut action = Action::default();
+        action.steps.push(Step::Cache(Box::new(CacheStep {
+            id: "cache".to_string(),
+            cache: CacheOptions {
+                key: "cargo-${{ hash_files('**/Cargo.lock') }}".to_string(),
+                paths: vec!["target".to_string()],
+                restore_keys: vec!["cargo-".to_string()],
+            },
+            ..Default::default()
+        })));
+        action.steps.push(Step::ComplexSh(Box::new(ShellCommand {
+            id: "build".to_string(),
+            run: "cargo build".to_string(),
+            condition: Some("${{ steps.cache.outputs.cache_hit != \"true\" }}".to_string()),
+            ..Default::default()
+        })));
+
+        let result = validate_action(&action).await;
+
+        assert!(result.is_ok(), "unexpected error: {:?}", result.err());
+    }
+
+    #[tokio::test]
+    pub async fn cache_step_without_key_or_paths_fails_validation() {
+        let mut action = Action::default();
+        action.steps.push(Step::Cache(Box::new(CacheStep {
+            id: "cache".to_string(),
+            cache: CacheOptions::default(),
+            ..Default::default()
+        })));
+
+        let result = validate_action(&action).await;
+
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("Cache key must not be empty"), "{error}");
+        assert!(
+            error.contains("At least one path must be provided"),
+            "{error}"
+        );
+    }
+
+    #[tokio::test]
+    pub async fn checkout_step_sha_output_passes_validation() {
+        let mut action = Action::default();
+        action.steps.push(Step::Checkout(Box::new(CheckoutStep {
+            id: "sources".to_string(),
+            checkout: CheckoutOptions {
+                repository: "git@github.com:Kani-Maki-Gang/bld.git".to_string(),
+                reference: Some("main".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        })));
+        action.steps.push(Step::ComplexSh(Box::new(ShellCommand {
+            id: "build".to_string(),
+            run: "echo ${{ steps.sources.outputs.sha }}".to_string(),
+            ..Default::default()
+        })));
+
+        let result = validate_action(&action).await;
+
+        assert!(result.is_ok(), "unexpected error: {:?}", result.err());
+    }
+
+    #[tokio::test]
+    pub async fn checkout_step_without_repository_or_with_zero_depth_fails_validation() {
+        let mut action = Action::default();
+        action.steps.push(Step::Checkout(Box::new(CheckoutStep {
+            id: "sources".to_string(),
+            checkout: CheckoutOptions {
+                depth: Some(0),
+                ..Default::default()
+            },
+            ..Default::default()
+        })));
+
+        let result = validate_action(&action).await;
+
+        let error = result.unwrap_err().to_string();
+        assert!(
+            error.contains("Checkout repository must not be empty"),
+            "{error}"
+        );
+        assert!(
+            error.contains("Checkout depth must be greater than 0"),
+            "{error}"
+        );
+    }
+
+    #[tokio::test]
+    pub async fn condition_with_functions_on_step_outputs_passes_validation() {
+        let mut action = Action::default();
+        action.steps.push(Step::ComplexSh(Box::new(ShellCommand {
+            id: "build".to_string(),
+            run: "echo \"branch=main\" >> $BLD_OUTPUTS".to_string(),
+            ..Default::default()
+        })));
+        action.steps.push(Step::ComplexSh(Box::new(ShellCommand {
+            id: "after".to_string(),
+            run: "echo done".to_string(),
+            condition: Some(
+                "${{ !starts_with(lower(steps.build.outputs.branch), 'release/') }}".to_string(),
+            ),
+            ..Default::default()
+        })));
+
+        let result = validate_action(&action).await;
+
+        assert!(result.is_ok(), "unexpected error: {:?}", result.err());
+    }
+
+    #[tokio::test]
+    pub async fn condition_with_status_check_passes_validation() {
+        let mut action = Action::default();
+        action.steps.push(Step::ComplexSh(Box::new(ShellCommand {
+            id: "cleanup".to_string(),
+            run: "echo done".to_string(),
+            condition: Some("${{ failure() || cancelled() }}".to_string()),
+            ..Default::default()
+        })));
+
+        let result = validate_action(&action).await;
+
+        assert!(result.is_ok(), "unexpected error: {:?}", result.err());
+    }
+
+    #[tokio::test]
+    pub async fn condition_with_invalid_function_call_fails_validation() {
+        let mut action = Action::default();
+        action.steps.push(Step::ComplexSh(Box::new(ShellCommand {
+            id: "build".to_string(),
+            run: "echo \"branch=main\" >> $BLD_OUTPUTS".to_string(),
+            ..Default::default()
+        })));
+        action.steps.push(Step::ComplexSh(Box::new(ShellCommand {
+            id: "after".to_string(),
+            run: "echo done".to_string(),
+            condition: Some("${{ starts_with(steps.build.outputs.branch) }}".to_string()),
+            ..Default::default()
+        })));
+
+        let result = validate_action(&action).await;
+
+        let error = result.unwrap_err().to_string();
+        assert!(
+            error.contains("starts_with expects 2 arguments, found 1"),
+            "{error}"
+        );
+    }
+
     #[tokio::test]
     pub async fn condition_with_step_output_text_comparison_passes_validation() {
         let mut action = Action::default();
@@ -988,17 +1501,25 @@ mod tests {
             id: "build".to_string(),
             name: None,
             working_dir: None,
+            env: HashMap::new(),
             run: "echo \"value=ok\" >> $BLD_OUTPUTS".to_string(),
             condition: None,
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
         action.steps.push(Step::ComplexSh(Box::new(ShellCommand {
             id: "after".to_string(),
             name: None,
             working_dir: None,
+            env: HashMap::new(),
             run: "echo done".to_string(),
             condition: Some(r#"${{ steps.build.outputs.value == "ok" }}"#.to_string()),
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
 
         let result = validate_action(&action).await;
@@ -1013,17 +1534,25 @@ mod tests {
             id: "build".to_string(),
             name: None,
             working_dir: None,
+            env: HashMap::new(),
             run: "echo \"count=5\" >> $BLD_OUTPUTS".to_string(),
             condition: None,
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
         action.steps.push(Step::ComplexSh(Box::new(ShellCommand {
             id: "after".to_string(),
             name: None,
             working_dir: None,
+            env: HashMap::new(),
             run: "echo done".to_string(),
             condition: Some("${{ steps.build.outputs.count > 3 }}".to_string()),
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
 
         let result = validate_action(&action).await;
@@ -1038,17 +1567,25 @@ mod tests {
             id: "first".to_string(),
             name: None,
             working_dir: None,
+            env: HashMap::new(),
             run: "echo hello".to_string(),
             condition: Some("${{ \"true\" }}".to_string()),
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
         action.steps.push(Step::ComplexSh(Box::new(ShellCommand {
             id: "second".to_string(),
             name: None,
             working_dir: None,
+            env: HashMap::new(),
             run: "echo hello".to_string(),
             condition: Some("${{ \"false\" }}".to_string()),
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
 
         let result = validate_action(&action).await;
@@ -1063,9 +1600,13 @@ mod tests {
             id: "first".to_string(),
             name: None,
             working_dir: None,
+            env: HashMap::new(),
             run: "echo hello".to_string(),
             condition: Some("${{ 1 }}".to_string()),
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
 
         let result = validate_action(&action).await;
@@ -1081,9 +1622,13 @@ mod tests {
             id: "first".to_string(),
             name: None,
             working_dir: None,
+            env: HashMap::new(),
             run: "echo hello".to_string(),
             condition: Some("${{ [1, 2] }}".to_string()),
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
 
         let result = validate_action(&action).await;
@@ -1104,9 +1649,13 @@ mod tests {
             id: "build".to_string(),
             name: None,
             working_dir: None,
+            env: HashMap::new(),
             run: "echo \"oses=[linux, windows]\" >> $BLD_OUTPUTS".to_string(),
             condition: None,
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
         action.steps.push(Step::ComplexSh(Box::new(ShellCommand {
             id: "after".to_string(),
@@ -1117,7 +1666,14 @@ mod tests {
             strategy: Some(Strategy {
                 matrix,
                 fail_fast: None,
+                include: vec![],
+                exclude: vec![],
+                max_parallel: None,
             }),
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
+            env: HashMap::new(),
         })));
 
         let result = validate_action(&action).await;
@@ -1136,9 +1692,13 @@ mod tests {
             id: "build".to_string(),
             name: None,
             working_dir: None,
+            env: HashMap::new(),
             run: "echo \"digest=abc\" >> $BLD_OUTPUTS".to_string(),
             condition: None,
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
         action.outputs.insert(
             "image".to_string(),
@@ -1179,9 +1739,13 @@ mod tests {
             id: "build".to_string(),
             name: None,
             working_dir: None,
+            env: HashMap::new(),
             run: "echo hello".to_string(),
             condition: None,
             strategy: None,
+            timeout: None,
+            continue_on_error: None,
+            retry: None,
         })));
         action.outputs.insert(
             "digest".to_string(),
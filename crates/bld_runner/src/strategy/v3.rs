@@ -34,7 +34,16 @@ pub enum FailFastValue {
 pub struct Strategy {
     #[serde(default)]
     pub matrix: HashMap<String, MatrixValue>,
+    /// Extra values added to the matching combinations, or new combinations when they
+    /// match none of them.
+    #[serde(default)]
+    pub include: Vec<HashMap<String, String>>,
+    /// Partial combinations whose matches are removed from the matrix.
+    #[serde(default)]
+    pub exclude: Vec<HashMap<String, String>>,
     pub fail_fast: Option<FailFastValue>,
+    /// The maximum number of a job's combinations that run at the same time.
+    pub max_parallel: Option<usize>,
 }
 
 impl Strategy {
@@ -43,7 +52,11 @@ impl Strategy {
     }
 
     pub fn matrix_keys(&self) -> HashSet<&str> {
-        self.matrix.keys().map(|k| k.as_str()).collect()
+        self.matrix
+            .keys()
+            .chain(self.include.iter().flat_map(|x| x.keys()))
+            .map(|k| k.as_str())
+            .collect()
     }
 }
 
@@ -98,9 +111,58 @@ impl Strategy {
             combinations = next;
         }
 
+        if self.matrix.is_empty() {
+            // Without a matrix every include entry is a combination of its own.
+            combinations = self.include.clone();
+        } else {
+            combinations.retain(|combination| {
+                !self
+                    .exclude
+                    .iter()
+                    .any(|exclude| is_match(combination, exclude))
+            });
+
+            for include in self.include.iter() {
+                self.apply_include(&mut combinations, include);
+            }
+        }
+
+        if combinations.is_empty() {
+            bail!("matrix has no combinations left to run");
+        }
+
         Ok(combinations)
     }
 
+    /// Adds the values of an include entry to every combination where they don't
+    /// overwrite one of the matrix's own values, or appends the entry as a combination
+    /// of its own when no such combination exists.
+    fn apply_include(
+        &self,
+        combinations: &mut Vec<HashMap<String, String>>,
+        include: &HashMap<String, String>,
+    ) {
+        let mut extended = false;
+        for combination in combinations.iter_mut() {
+            let overwrites = include.iter().any(|(key, value)| {
+                self.matrix.contains_key(key)
+                    && combination.get(key).is_some_and(|current| current != value)
+            });
+            if !overwrites {
+                combination.extend(include.clone());
+                extended = true;
+            }
+        }
+
+        if !extended {
+            combinations.push(include.clone());
+        }
+    }
+
+    pub fn resolve_max_parallel(&self) -> usize {
+        self.max_parallel.unwrap_or(usize::MAX)
+    }
+
     pub fn resolve_fail_fast<'a, T, RCtx, WCtx>(
         &'a self,
         exec: &CommonExprExecutor<'a, T, RCtx, WCtx>,
@@ -128,6 +190,13 @@ impl Strategy {
 impl<'a> Validate<'a> for Strategy {
     async fn validate<C: ValidatorContext<'a>>(&'a self, ctx: &mut C) {
         self.validate_in_scope(ctx, ExprScope::Runtime).await;
+
+        // The combinations of a step run one after the other within its job.
+        if self.max_parallel.is_some() {
+            ctx.push_section("max_parallel");
+            ctx.append_error("max_parallel is only supported in the strategy of a job");
+            ctx.pop_section();
+        }
     }
 }
 
@@ -141,7 +210,7 @@ impl Strategy {
         scope: ExprScope,
     ) {
         ctx.push_section("matrix");
-        if self.matrix.is_empty() {
+        if self.matrix.is_empty() && self.include.is_empty() {
             ctx.append_error("Strategy matrix must define at least one key");
         }
 
@@ -176,9 +245,43 @@ impl Strategy {
             }
             ctx.pop_section();
         }
+
+        for (section, entries) in [("include", &self.include), ("exclude", &self.exclude)] {
+            ctx.push_section(section);
+            if entries.iter().any(|x| x.is_empty()) {
+                ctx.append_error(&format!("{section} entries must define at least one key"));
+            }
+            ctx.pop_section();
+        }
+
+        if self.max_parallel == Some(0) {
+            ctx.push_section("max_parallel");
+            ctx.append_error("max_parallel must be greater than zero");
+            ctx.pop_section();
+        }
     }
 }
 
+/// Checks whether a combination has every value of a partial one.
+#[cfg(feature = "all")]
+fn is_match(combination: &HashMap<String, String>, partial: &HashMap<String, String>) -> bool {
+    partial
+        .iter()
+        .all(|(key, value)| combination.get(key) == Some(value))
+}
+
+/// Formats a matrix combination as `key=value` pairs sorted by key, for example
+/// `os=alpine, rust=1.80`.
+pub fn combination_label(combination: &HashMap<String, String>) -> String {
+    let mut pairs: Vec<(&String, &String)> = combination.iter().collect();
+    pairs.sort();
+    pairs
+        .into_iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
 #[cfg(feature = "all")]
 pub fn validate_matrix_refs<'a, C: ValidatorContext<'a>>(
     ctx: &mut C,
@@ -269,9 +372,18 @@ mod exec_tests {
         },
         inputs::v3::Input,
         pipeline::v3::Pipeline,
-        strategy::v3::{FailFastValue, MatrixValue, Strategy},
+        strategy::v3::{FailFastValue, MatrixValue, Strategy, combination_label},
     };
 
+    #[test]
+    pub fn combination_label_sorts_by_key() {
+        let combination = HashMap::from([
+            ("rust".to_string(), "1.80".to_string()),
+            ("os".to_string(), "alpine".to_string()),
+        ]);
+        assert_eq!(combination_label(&combination), "os=alpine, rust=1.80");
+    }
+
     #[test]
     pub fn combinations_literal_array_success() {
         let wctx = MockWritableRuntimeExprContext::new();
@@ -291,6 +403,9 @@ mod exec_tests {
         let strategy = Strategy {
             matrix,
             fail_fast: None,
+            include: vec![],
+            exclude: vec![],
+            max_parallel: None,
         };
 
         let combinations = strategy.combinations(&exec).unwrap();
@@ -317,6 +432,9 @@ mod exec_tests {
         let strategy = Strategy {
             matrix,
             fail_fast: None,
+            include: vec![],
+            exclude: vec![],
+            max_parallel: None,
         };
 
         let combinations = strategy.combinations(&exec).unwrap();
@@ -341,6 +459,9 @@ mod exec_tests {
         let strategy = Strategy {
             matrix,
             fail_fast: None,
+            include: vec![],
+            exclude: vec![],
+            max_parallel: None,
         };
 
         assert!(strategy.combinations(&exec).is_err());
@@ -356,6 +477,9 @@ mod exec_tests {
         let strategy = Strategy {
             matrix: HashMap::new(),
             fail_fast: None,
+            include: vec![],
+            exclude: vec![],
+            max_parallel: None,
         };
 
         assert!(strategy.resolve_fail_fast(&exec).unwrap());
@@ -371,6 +495,9 @@ mod exec_tests {
         let strategy = Strategy {
             matrix: HashMap::new(),
             fail_fast: Some(FailFastValue::Bool(false)),
+            include: vec![],
+            exclude: vec![],
+            max_parallel: None,
         };
 
         assert!(!strategy.resolve_fail_fast(&exec).unwrap());
@@ -386,8 +513,104 @@ mod exec_tests {
         let strategy = Strategy {
             matrix: HashMap::new(),
             fail_fast: Some(FailFastValue::Expr("${{ false }}".to_string())),
+            include: vec![],
+            exclude: vec![],
+            max_parallel: None,
         };
 
         assert!(!strategy.resolve_fail_fast(&exec).unwrap());
     }
+
+    fn combinations_of(yaml: &str) -> anyhow::Result<Vec<String>> {
+        let wctx = MockWritableRuntimeExprContext::new();
+        let rctx = CommonReadonlyRuntimeExprContext::default();
+        let pipeline = Pipeline::default();
+        let exec = CommonExprExecutor::new(&pipeline, &rctx, &wctx);
+        let strategy: Strategy = serde_yaml_ng::from_str(yaml).unwrap();
+        let combinations = strategy.combinations(&exec)?;
+        Ok(combinations.iter().map(combination_label).collect())
+    }
+
+    #[test]
+    pub fn combinations_with_exclude_success() {
+        let yaml = r#"
+matrix:
+  os: [alpine, debian]
+  rust: ["1.80", "1.81"]
+exclude:
+  - os: alpine
+    rust: "1.80"
+  - os: windows
+"#;
+        assert_eq!(
+            combinations_of(yaml).unwrap(),
+            vec![
+                "os=alpine, rust=1.81",
+                "os=debian, rust=1.80",
+                "os=debian, rust=1.81"
+            ]
+        );
+    }
+
+    #[test]
+    pub fn combinations_with_include_success() {
+        let yaml = r#"
+matrix:
+  os: [alpine, debian]
+  rust: ["1.80"]
+include:
+  - os: debian
+    features: full
+  - os: windows
+    rust: "1.81"
+  - experimental: "true"
+"#;
+        assert_eq!(
+            combinations_of(yaml).unwrap(),
+            vec![
+                "experimental=true, os=alpine, rust=1.80",
+                "experimental=true, features=full, os=debian, rust=1.80",
+                "experimental=true, os=windows, rust=1.81"
+            ]
+        );
+    }
+
+    #[test]
+    pub fn combinations_with_only_include_success() {
+        let yaml = r#"
+include:
+  - os: alpine
+  - os: debian
+"#;
+        assert_eq!(
+            combinations_of(yaml).unwrap(),
+            vec!["os=alpine", "os=debian"]
+        );
+    }
+
+    #[test]
+    pub fn combinations_all_excluded_failure() {
+        let yaml = r#"
+matrix:
+  os: [alpine]
+exclude:
+  - os: alpine
+"#;
+        assert!(combinations_of(yaml).is_err());
+    }
+
+    #[test]
+    pub fn resolve_max_parallel_success() {
+        let data = [(None, usize::MAX), (Some(1), 1), (Some(2), 2)];
+        for (max_parallel, expected) in data {
+            let strategy = Strategy {
+                matrix: HashMap::new(),
+                include: vec![],
+                exclude: vec![],
+                fail_fast: None,
+                max_parallel,
+            };
+            assert_eq!(strategy.resolve_max_parallel(), expected);
+        }
+    }
 }
@@ -0,0 +1,246 @@
+use anyhow::{Result, anyhow, bail};
+use std::time::Duration;
+
+#[cfg(feature = "all")]
+use {
+    crate::validator::v3::ValidatorContext,
+    bld_core::platform::TimedOut,
+    std::{
+        fmt::{Display, Formatter},
+        future::Future,
+    },
+    tokio::{pin, select, time::sleep},
+    tokio_util::sync::CancellationToken,
+};
+
+/// The error of a step that stopped before finishing because its run was cancelled.
+#[cfg(feature = "all")]
+#[derive(Debug)]
+pub struct Cancelled;
+
+#[cfg(feature = "all")]
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cancelled")
+    }
+}
+
+#[cfg(feature = "all")]
+impl std::error::Error for Cancelled {}
+
+/// How long the post steps of a job or an action have to clean up, after which the ones
+/// still running are cancelled.
+#[cfg(feature = "all")]
+pub const POST_STEPS_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// The token that post steps run with, a child of the provided one so that stopping the
+/// run stops them as well. If the run was stopped before they started they get a new
+/// token instead, which only [`POST_STEPS_TIMEOUT`] cancels.
+#[cfg(feature = "all")]
+pub fn post_steps_token(cancel: &CancellationToken) -> CancellationToken {
+    if cancel.is_cancelled() {
+        CancellationToken::new()
+    } else {
+        cancel.child_token()
+    }
+}
+
+/// Parses a timeout value made up of one or more `<number><unit>` segments
+/// where the unit is one of `s`, `m` or `h`, for example `30s`, `10m` or `1h30m`.
+pub fn parse(value: &str) -> Result<Duration> {
+    let mut total: u64 = 0;
+    let mut digits = String::new();
+    let mut segments = 0;
+
+    for c in value.trim().chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+
+        let multiplier = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            _ => bail!("invalid timeout '{value}', unknown unit '{c}'"),
+        };
+
+        if digits.is_empty() {
+            bail!("invalid timeout '{value}', expected a number before '{c}'");
+        }
+
+        let amount: u64 = digits
+            .parse()
+            .map_err(|_| anyhow!("invalid timeout '{value}', number is too large"))?;
+
+        total = amount
+            .checked_mul(multiplier)
+            .and_then(|x| total.checked_add(x))
+            .ok_or_else(|| anyhow!("invalid timeout '{value}', number is too large"))?;
+
+        digits.clear();
+        segments += 1;
+    }
+
+    if !digits.is_empty() {
+        bail!("invalid timeout '{value}', missing unit after '{digits}' (use s, m or h)");
+    }
+
+    if segments == 0 {
+        bail!("invalid timeout '{value}', expected a value such as 30s, 10m or 1h30m");
+    }
+
+    if total == 0 {
+        bail!("invalid timeout '{value}', must be greater than zero");
+    }
+
+    Ok(Duration::from_secs(total))
+}
+
+#[cfg(feature = "all")]
+pub fn validate_timeout<'a, C: ValidatorContext<'a>>(ctx: &mut C, timeout: &'a str) {
+    ctx.push_section("timeout");
+    if let Err(e) = parse(timeout) {
+        ctx.append_error(&e.to_string());
+    }
+    ctx.pop_section();
+}
+
+/// Runs the provided future to completion, failing with a [`TimedOut`] error if it
+/// doesn't finish within the timeout.
+#[cfg(feature = "all")]
+pub async fn run_with_timeout<T, F>(timeout: Option<Duration>, future: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    let Some(duration) = timeout else {
+        return future.await;
+    };
+    tokio::time::timeout(duration, future)
+        .await
+        .map_err(|_| anyhow!(TimedOut::new(duration)))?
+}
+
+/// Runs the provided future until it finishes, failing with a [`Cancelled`] error if the
+/// token is cancelled first, in which case the future is dropped.
+#[cfg(feature = "all")]
+pub async fn run_with_cancel<T, F>(cancel: &CancellationToken, future: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    select! {
+        biased;
+        _ = cancel.cancelled() => bail!(Cancelled),
+        result = future => result,
+    }
+}
+
+/// Runs the future of a child runner that winds down once the provided token is
+/// cancelled. When the timeout expires the token is cancelled and the future is still
+/// awaited, so that the child runner disposes of its platforms before the step fails
+/// with a [`TimedOut`] error.
+#[cfg(feature = "all")]
+pub async fn run_child_with_timeout<T, F>(
+    timeout: Option<Duration>,
+    child: &CancellationToken,
+    future: F,
+) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    let Some(duration) = timeout else {
+        return future.await;
+    };
+    pin!(future);
+    select! {
+        result = &mut future => return result,
+        _ = sleep(duration) => {}
+    }
+    child.cancel();
+    let _ = future.await;
+    bail!(TimedOut::new(duration))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::parse;
+
+    #[cfg(feature = "all")]
+    use {
+        super::{post_steps_token, run_child_with_timeout},
+        bld_core::platform::TimedOut,
+        std::sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+        tokio_util::sync::CancellationToken,
+    };
+
+    #[test]
+    pub fn parse_single_unit_success() {
+        assert_eq!(parse("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse("10m").unwrap(), Duration::from_secs(600));
+        assert_eq!(parse("2h").unwrap(), Duration::from_secs(7200));
+    }
+
+    #[test]
+    pub fn parse_multiple_units_success() {
+        assert_eq!(parse("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse("2m5s").unwrap(), Duration::from_secs(125));
+    }
+
+    #[test]
+    pub fn parse_invalid_values_failure() {
+        for value in ["", "10", "m", "10d", "0s", "-5m", "1.5h", "1m 5s"] {
+            assert!(
+                parse(value).is_err(),
+                "{value} should not be a valid timeout"
+            );
+        }
+    }
+
+    #[cfg(feature = "all")]
+    #[tokio::test]
+    pub async fn run_child_with_timeout_waits_for_the_child_to_clean_up() {
+        let child = CancellationToken::new();
+        let cleaned_up = Arc::new(AtomicBool::new(false));
+
+        let token = child.clone();
+        let flag = cleaned_up.clone();
+        let result: anyhow::Result<()> =
+            run_child_with_timeout(Some(Duration::from_millis(50)), &child, async move {
+                token.cancelled().await;
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                flag.store(true, Ordering::SeqCst);
+                Ok(())
+            })
+            .await;
+
+        assert!(result.unwrap_err().is::<TimedOut>());
+        assert!(child.is_cancelled());
+        assert!(cleaned_up.load(Ordering::SeqCst));
+    }
+
+    #[cfg(feature = "all")]
+    #[test]
+    pub fn post_steps_token_follows_a_run_that_is_still_running() {
+        let cancel = CancellationToken::new();
+        let post = post_steps_token(&cancel);
+        assert!(!post.is_cancelled());
+
+        cancel.cancel();
+        assert!(post.is_cancelled());
+    }
+
+    #[cfg(feature = "all")]
+    #[test]
+    pub fn post_steps_token_of_a_cancelled_run_is_not_cancelled() {
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+
+        let post = post_steps_token(&cancel);
+        assert!(!post.is_cancelled());
+    }
+}
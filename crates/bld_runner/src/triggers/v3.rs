@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "all")]
+use crate::validator::v3::{Validate, ValidatorContext};
+
+/// The final states of a run that can start the runs of other pipelines.
+pub const COMPLETED_STATES: [&str; 2] = ["finished", "faulted"];
+
+/// The events that start a run of the pipeline, other than running it directly.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct On {
+    pub pipeline_completed: Option<PipelineCompleted>,
+}
+
+/// Starts a run of the pipeline whenever a run of another pipeline reaches one of the
+/// provided states. The id of the upstream run and the outputs of its jobs are passed
+/// to the new run as inputs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineCompleted {
+    pub name: String,
+    #[serde(default = "PipelineCompleted::default_states")]
+    pub states: Vec<String>,
+}
+
+impl PipelineCompleted {
+    fn default_states() -> Vec<String> {
+        vec![COMPLETED_STATES[0].to_string()]
+    }
+}
+
+impl Default for PipelineCompleted {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            states: Self::default_states(),
+        }
+    }
+}
+
+#[cfg(feature = "all")]
+impl<'a> Validate<'a> for On {
+    async fn validate<C: ValidatorContext<'a>>(&'a self, ctx: &mut C) {
+        let Some(completed) = self.pipeline_completed.as_ref() else {
+            return;
+        };
+
+        ctx.push_section("pipeline_completed");
+
+        ctx.push_section("name");
+        if completed.name.trim().is_empty() {
+            ctx.append_error("Completed pipeline name must not be empty");
+        }
+        ctx.pop_section();
+
+        ctx.push_section("states");
+        if completed.states.is_empty() {
+            ctx.append_error("At least one state must be provided");
+        }
+        for state in completed.states.iter() {
+            if !COMPLETED_STATES.contains(&state.as_str()) {
+                ctx.append_error(&format!(
+                    "Invalid state '{state}', expected one of {}",
+                    COMPLETED_STATES.join(", ")
+                ));
+            }
+        }
+        ctx.pop_section();
+
+        ctx.pop_section();
+    }
+}
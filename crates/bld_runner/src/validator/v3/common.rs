@@ -13,10 +13,10 @@ use regex::Regex;
 use tracing::debug;
 
 use crate::expr::v3::{
-    context::{CommonReadonlyRuntimeExprContext, START_OF_RUN_WCTX},
+    context::{CommonReadonlyRuntimeExprContext, JOB_CONDITION_VALIDATION_WCTX, START_OF_RUN_WCTX},
     exec::CommonExprExecutor,
     parser,
-    traits::{EvalExpr, EvalObject, ExprValue, OutputScope, WritableRuntimeExprContext},
+    traits::{EvalExpr, EvalObject, ExprValue, OutputScope, RunStatus, WritableRuntimeExprContext},
 };
 
 use super::{ConsumeValidator, ExprScope, Validate, ValidatorContext};
@@ -75,6 +75,14 @@ impl<'a> WritableRuntimeExprContext for ValidatorWritableRuntimeExprContext<'a>
     fn get_matrix_value<'b>(&'b self, _name: &str) -> Result<&'b str> {
         Ok("")
     }
+
+    fn get_step_outcome<'b>(&'b self, _id: &str) -> Result<ExprValue<'b>> {
+        Ok(ExprValue::Unknown)
+    }
+
+    fn get_run_status(&self) -> Result<RunStatus> {
+        Ok(RunStatus::Unknown)
+    }
 }
 
 pub struct CommonValidator<'a, V: Validate<'a> + for<'x> EvalObject<'x>> {
@@ -367,8 +375,10 @@ impl<'a, V: Validate<'a> + for<'x> EvalObject<'x>> ValidatorContext<'a> for Comm
             self.append_error("Condition must contain at most one expression");
         } else {
             match scope {
+                // Only a job's condition is evaluated at the start of the run, which can
+                // also check the status of the jobs it needs.
                 ExprScope::StartOfRun => {
-                    self.eval_condition_expression(condition, &START_OF_RUN_WCTX)
+                    self.eval_condition_expression(condition, &JOB_CONDITION_VALIDATION_WCTX)
                 }
                 ExprScope::Runtime => {
                     let Some(expr_wctx) = self.runtime_wctx() else {
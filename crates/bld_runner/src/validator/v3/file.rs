@@ -52,6 +52,7 @@ impl ConsumeValidator for RunnerFileValidator<'_> {
                     self.config.clone(),
                     with_blank_values(pip.inputs.keys().collect()).into_arc(),
                     with_blank_values(pip.env.keys().collect()).into_arc(),
+                    None,
                     String::new(),
                     String::new(),
                 );
@@ -82,6 +83,7 @@ impl ConsumeValidator for RunnerFileValidator<'_> {
                     self.config.clone(),
                     with_blank_values(action.inputs.keys().collect()).into_arc(),
                     HashMap::new().into_arc(),
+                    None,
                     String::new(),
                     String::new(),
                 );
@@ -0,0 +1,272 @@
+use actix_web::HttpResponse;
+use anyhow::{Result, anyhow};
+use bld_config::OpenIdInfo;
+use bld_models::{
+    access_grants, cron_jobs,
+    dtos::{AccessRole, ExecClientMessage},
+    pipeline, pipeline_runs, webhook_triggers,
+};
+use sea_orm::DatabaseConnection;
+
+use crate::extractors::User;
+
+/// The roles of a user for the pipelines of the server, resolved from the grants given to
+/// the user or to the groups of the user and from the admins of the auth config.
+#[derive(Debug, Default)]
+pub enum UserAccess {
+    /// Servers without auth don't restrict their users.
+    #[default]
+    Unrestricted,
+    Grants(Vec<(AccessRole, String)>),
+}
+
+impl UserAccess {
+    pub async fn load(
+        conn: &DatabaseConnection,
+        openid: &OpenIdInfo,
+        name: &str,
+        groups: &[String],
+    ) -> Result<Self> {
+        let mut grants = vec![];
+        if openid.admins.iter().any(|admin| admin == name) {
+            grants.push((AccessRole::Admin, String::new()));
+        }
+        for grant in access_grants::select_by_subjects(conn, name, groups).await? {
+            let role = grant.role.parse().map_err(anyhow::Error::msg)?;
+            grants.push((role, grant.pipeline));
+        }
+        Ok(Self::Grants(grants))
+    }
+
+    /// The highest role of the user among the grants whose prefix matches the pipeline. Only
+    /// the grants without a prefix match the empty pipeline, which stands for the server.
+    pub fn role(&self, pipeline: &str) -> Option<AccessRole> {
+        match self {
+            Self::Unrestricted => Some(AccessRole::Admin),
+            Self::Grants(grants) => grants
+                .iter()
+                .filter(|(_, prefix)| matches_prefix(prefix, pipeline))
+                .map(|(role, _)| *role)
+                .max(),
+        }
+    }
+
+    /// The highest role of the user for any of the pipelines.
+    pub fn highest_role(&self) -> Option<AccessRole> {
+        match self {
+            Self::Unrestricted => Some(AccessRole::Admin),
+            Self::Grants(grants) => grants.iter().map(|(role, _)| *role).max(),
+        }
+    }
+}
+
+/// Whether the pipeline is the prefix itself or lies under it, comparing whole path
+/// segments so that a grant for `deploy` doesn't match `deployment.yaml` or `deploy2/`.
+fn matches_prefix(prefix: &str, pipeline: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    if prefix.is_empty() {
+        return true;
+    }
+    pipeline
+        .strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+fn forbidden(e: anyhow::Error) -> HttpResponse {
+    HttpResponse::Forbidden().body(e.to_string())
+}
+
+fn bad_request(e: anyhow::Error) -> HttpResponse {
+    HttpResponse::BadRequest().body(e.to_string())
+}
+
+pub fn authorize(user: &User, role: AccessRole, pipeline: &str) -> Result<(), HttpResponse> {
+    user.authorize(role, pipeline).map_err(forbidden)
+}
+
+pub fn authorize_server(user: &User, role: AccessRole) -> Result<(), HttpResponse> {
+    user.authorize_server(role).map_err(forbidden)
+}
+
+pub fn authorize_any(user: &User, role: AccessRole) -> Result<(), HttpResponse> {
+    user.authorize_any(role).map_err(forbidden)
+}
+
+pub async fn authorize_run(
+    user: &User,
+    conn: &DatabaseConnection,
+    role: AccessRole,
+    run_id: &str,
+) -> Result<(), HttpResponse> {
+    let run = pipeline_runs::select_by_id(conn, run_id)
+        .await
+        .map_err(bad_request)?;
+    authorize(user, role, &run.name)
+}
+
+pub async fn authorize_pipeline_id(
+    user: &User,
+    conn: &DatabaseConnection,
+    role: AccessRole,
+    pipeline_id: &str,
+) -> Result<(), HttpResponse> {
+    let pipeline = pipeline::select_by_id(conn, pipeline_id)
+        .await
+        .map_err(bad_request)?;
+    authorize(user, role, &pipeline.name)
+}
+
+pub async fn authorize_cron_job(
+    user: &User,
+    conn: &DatabaseConnection,
+    role: AccessRole,
+    cron_job_id: &str,
+) -> Result<(), HttpResponse> {
+    let job = cron_jobs::select_by_id(conn, cron_job_id)
+        .await
+        .map_err(bad_request)?;
+    authorize_pipeline_id(user, conn, role, &job.pipeline_id).await
+}
+
+pub async fn authorize_trigger(
+    user: &User,
+    conn: &DatabaseConnection,
+    role: AccessRole,
+    trigger: &str,
+) -> Result<(), HttpResponse> {
+    let trigger = webhook_triggers::select_by_name(conn, trigger)
+        .await
+        .map_err(bad_request)?;
+    authorize_pipeline_id(user, conn, role, &trigger.pipeline_id).await
+}
+
+/// The pipeline that a message of a client enqueues, which for a re-run is the pipeline of
+/// the previous run.
+pub async fn exec_pipeline(
+    conn: &DatabaseConnection,
+    message: &ExecClientMessage,
+) -> Result<String> {
+    match message {
+        ExecClientMessage::EnqueueRun { name, .. } => Ok(name.to_owned()),
+        ExecClientMessage::Rerun { run_id } => pipeline_runs::select_by_id(conn, run_id)
+            .await
+            .map(|run| run.name)
+            .map_err(|_| anyhow!("run {run_id} not found")),
+    }
+}
+
+/// The role that a message of a client requires. A priority above the default lets a run
+/// leave the queue before the runs of other users, so it takes the same role as setting
+/// the priority of a cron job.
+pub fn exec_role(message: &ExecClientMessage) -> AccessRole {
+    match message {
+        ExecClientMessage::EnqueueRun {
+            priority: Some(priority),
+            ..
+        } if *priority > 0 => AccessRole::Maintainer,
+        _ => AccessRole::Runner,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn access(grants: &[(AccessRole, &str)]) -> UserAccess {
+        let grants = grants
+            .iter()
+            .map(|(role, prefix)| (*role, prefix.to_string()))
+            .collect();
+        UserAccess::Grants(grants)
+    }
+
+    #[test]
+    fn role_is_the_highest_among_the_grants_matching_the_pipeline() {
+        let access = access(&[
+            (AccessRole::Viewer, ""),
+            (AccessRole::Runner, "deploy/"),
+            (AccessRole::Maintainer, "deploy/staging/"),
+        ]);
+
+        assert_eq!(access.role("build.yaml"), Some(AccessRole::Viewer));
+        assert_eq!(access.role("deploy/prod.yaml"), Some(AccessRole::Runner));
+        assert_eq!(
+            access.role("deploy/staging/app.yaml"),
+            Some(AccessRole::Maintainer)
+        );
+        assert_eq!(access.highest_role(), Some(AccessRole::Maintainer));
+    }
+
+    #[test]
+    fn server_role_only_comes_from_grants_without_a_prefix() {
+        let access = access(&[(AccessRole::Admin, "deploy/")]);
+
+        assert_eq!(access.role(""), None);
+        assert_eq!(access.role("build.yaml"), None);
+        assert_eq!(access.role("deploy/prod.yaml"), Some(AccessRole::Admin));
+    }
+
+    #[test]
+    fn prefix_only_matches_whole_path_segments() {
+        let access = access(&[(AccessRole::Admin, "deploy")]);
+
+        assert_eq!(access.role("deploy"), Some(AccessRole::Admin));
+        assert_eq!(access.role("deploy/prod.yaml"), Some(AccessRole::Admin));
+        assert_eq!(access.role("deployment-secrets.yaml"), None);
+        assert_eq!(access.role("deploy2/prod.yaml"), None);
+        assert_eq!(access.role("deploy.yaml"), None);
+    }
+
+    #[test]
+    fn prefix_with_a_trailing_slash_matches_the_same_pipelines() {
+        let access = access(&[(AccessRole::Runner, "deploy/")]);
+
+        assert_eq!(access.role("deploy"), Some(AccessRole::Runner));
+        assert_eq!(access.role("deploy/prod.yaml"), Some(AccessRole::Runner));
+        assert_eq!(access.role("deployment-secrets.yaml"), None);
+        assert_eq!(access.role("deploy2/prod.yaml"), None);
+    }
+
+    #[test]
+    fn prefix_of_a_pipeline_file_only_matches_that_pipeline() {
+        let access = access(&[(AccessRole::Maintainer, "deploy.yaml")]);
+
+        assert_eq!(access.role("deploy.yaml"), Some(AccessRole::Maintainer));
+        assert_eq!(access.role("deploy.yaml.bak"), None);
+    }
+
+    #[test]
+    fn unrestricted_access_is_admin_everywhere() {
+        let access = UserAccess::Unrestricted;
+
+        assert_eq!(access.role(""), Some(AccessRole::Admin));
+        assert_eq!(access.role("build.yaml"), Some(AccessRole::Admin));
+    }
+
+    #[test]
+    fn raising_the_priority_of_a_run_requires_the_maintainer_role() {
+        let enqueue = |priority| ExecClientMessage::EnqueueRun {
+            name: "build.yaml".to_string(),
+            env: None,
+            inputs: None,
+            parent_run_id: None,
+            priority,
+        };
+
+        assert_eq!(exec_role(&enqueue(None)), AccessRole::Runner);
+        assert_eq!(exec_role(&enqueue(Some(0))), AccessRole::Runner);
+        assert_eq!(exec_role(&enqueue(Some(-5))), AccessRole::Runner);
+        assert_eq!(exec_role(&enqueue(Some(1))), AccessRole::Maintainer);
+        let rerun = ExecClientMessage::Rerun {
+            run_id: "id".to_string(),
+        };
+        assert_eq!(exec_role(&rerun), AccessRole::Runner);
+    }
+
+    #[test]
+    fn roles_are_ordered_by_what_they_allow() {
+        assert!(AccessRole::Viewer < AccessRole::Runner);
+        assert!(AccessRole::Runner < AccessRole::Maintainer);
+        assert!(AccessRole::Maintainer < AccessRole::Admin);
+    }
+}
@@ -3,6 +3,7 @@ use std::{sync::Arc, time::Duration};
 use actix_web::rt::spawn;
 use anyhow::Result;
 use bld_config::BldConfig;
+use bld_core::cache::Cache;
 use bld_models::{artifacts, login_attempts};
 use sea_orm::DatabaseConnection;
 use tokio::{task::JoinHandle, time::sleep};
@@ -16,6 +17,8 @@ impl CleanupWorker {
     pub fn new(conn: Arc<DatabaseConnection>, config: Arc<BldConfig>) -> Self {
         let interval = Duration::from_secs(config.local.server.cleanup_interval.max(1) as u64);
 
+        let cache = Cache::new(config.clone());
+
         let task = spawn(async move {
             loop {
                 if let Err(e) = login_attempts::delete_expired(&conn).await {
@@ -24,6 +27,9 @@ impl CleanupWorker {
                 if let Err(e) = cleanup_expired_artifacts(&conn, &config).await {
                     error!("artifacts cleanup run failed due to: {e}");
                 }
+                if let Err(e) = cache.evict().await {
+                    error!("cache eviction run failed due to: {e}");
+                }
                 sleep(interval).await;
             }
         });
@@ -0,0 +1,196 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use actix_web::rt::spawn;
+use anyhow::Result;
+use bld_core::fs::FileSystem;
+use bld_models::{
+    completion_trigger_events::{self, CompletionTriggerEvent},
+    completion_triggers::{self, InsertCompletionTrigger},
+    dtos::ExecClientMessage,
+    pipeline,
+    pipeline_run_outputs::{self, PipelineRunOutputs},
+    pipeline_runs::RunTrigger,
+};
+use bld_runner::triggers::v3::PipelineCompleted;
+use sea_orm::DatabaseConnection;
+use tokio::{task::JoinHandle, time::sleep};
+use tracing::{error, info};
+
+use crate::{
+    high_availability::HighAvailability,
+    supervisor::{channel::SupervisorMessageSender, helpers::enqueue_worker},
+};
+
+/// The input of a run started by a completion trigger with the id of the upstream run.
+pub const UPSTREAM_RUN_ID_INPUT: &str = "upstream_run_id";
+
+const COMPLETION_USER: &str = "Pipeline completion";
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Stores the completion trigger declared by a pipeline, or removes the existing one when
+/// the pipeline no longer declares it. The id is used for the trigger if the pipeline
+/// doesn't have one already.
+pub async fn sync(
+    conn: &DatabaseConnection,
+    pipeline: &str,
+    completed: Option<&PipelineCompleted>,
+    id: &str,
+) -> Result<()> {
+    let pipeline = pipeline::select_by_name(conn, pipeline).await?;
+    match completed {
+        Some(completed) => {
+            let model = InsertCompletionTrigger {
+                id: id.to_owned(),
+                pipeline_id: pipeline.id,
+                upstream: completed.name.to_owned(),
+                states: completed.states.to_owned(),
+            };
+            completion_triggers::upsert(conn, &model).await
+        }
+        None => completion_triggers::delete_by_pipeline(conn, &pipeline.id).await,
+    }
+}
+
+/// The inputs of a run started by the completion of an upstream run, meaning its id and
+/// the outputs of its jobs named as `<job>_<output>`.
+fn inputs(run_id: &str, outputs: Vec<PipelineRunOutputs>) -> HashMap<String, String> {
+    let mut inputs: HashMap<String, String> = outputs
+        .into_iter()
+        .map(|output| (format!("{}_{}", output.job, output.name), output.value))
+        .collect();
+    inputs.insert(UPSTREAM_RUN_ID_INPUT.to_owned(), run_id.to_owned());
+    inputs
+}
+
+/// Polls the events recorded when runs reach a final state and starts a run of the
+/// pipeline of each event's completion trigger. Only the leader of a raft group polls
+/// them, since the events are recorded by its workers.
+pub struct CompletionTriggerWorker {
+    _task: JoinHandle<()>,
+}
+
+impl CompletionTriggerWorker {
+    pub fn new(
+        fs: Arc<FileSystem>,
+        conn: Arc<DatabaseConnection>,
+        supervisor: Arc<SupervisorMessageSender>,
+        ha: Arc<HighAvailability>,
+    ) -> Self {
+        let task = spawn(async move {
+            loop {
+                if !ha.is_leader() {
+                    sleep(POLL_INTERVAL).await;
+                    continue;
+                }
+                match completion_trigger_events::select_all(conn.as_ref()).await {
+                    Ok(events) => {
+                        for event in events {
+                            dispatch(&fs, &conn, &supervisor, &ha, event).await;
+                        }
+                    }
+                    Err(e) => error!("unable to load completion trigger events due to: {e}"),
+                }
+                sleep(POLL_INTERVAL).await;
+            }
+        });
+
+        Self { _task: task }
+    }
+}
+
+/// Starts the run for an event and removes it, even if the run couldn't be started, so
+/// that a broken trigger doesn't get retried forever.
+async fn dispatch(
+    fs: &Arc<FileSystem>,
+    conn: &Arc<DatabaseConnection>,
+    supervisor: &Arc<SupervisorMessageSender>,
+    ha: &Arc<HighAvailability>,
+    event: CompletionTriggerEvent,
+) {
+    match start_run(fs, conn, supervisor, ha, &event).await {
+        Ok(run_id) => info!(
+            "started run {run_id} after run {} reached state {}",
+            event.run_id, event.state
+        ),
+        Err(e) => error!(
+            "unable to start run for completion of run {} due to: {e}",
+            event.run_id
+        ),
+    }
+
+    if let Err(e) = completion_trigger_events::delete_by_id(conn.as_ref(), &event.id).await {
+        error!("unable to remove completion trigger event due to: {e}");
+    }
+}
+
+async fn start_run(
+    fs: &Arc<FileSystem>,
+    conn: &Arc<DatabaseConnection>,
+    supervisor: &Arc<SupervisorMessageSender>,
+    ha: &Arc<HighAvailability>,
+    event: &CompletionTriggerEvent,
+) -> Result<String> {
+    let trigger = completion_triggers::select_by_id(conn.as_ref(), &event.trigger_id).await?;
+    let pipeline = pipeline::select_by_id(conn.as_ref(), &trigger.pipeline_id).await?;
+    let outputs = pipeline_run_outputs::select_by_run_id(conn.as_ref(), &event.run_id).await?;
+    let message = ExecClientMessage::EnqueueRun {
+        name: pipeline.name,
+        env: None,
+        inputs: Some(inputs(&event.run_id, outputs)),
+        parent_run_id: None,
+        priority: None,
+    };
+
+    enqueue_worker(
+        COMPLETION_USER,
+        Arc::clone(fs),
+        Arc::clone(conn),
+        Arc::clone(supervisor),
+        Arc::clone(ha),
+        message,
+        RunTrigger::pipeline_completed(&event.run_id),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn output(job: &str, name: &str, value: &str) -> PipelineRunOutputs {
+        PipelineRunOutputs {
+            id: Uuid::new_v4().to_string(),
+            run_id: "upstream".to_owned(),
+            job: job.to_owned(),
+            name: name.to_owned(),
+            value: value.to_owned(),
+            date_created: Utc::now().naive_utc(),
+        }
+    }
+
+    #[test]
+    fn inputs_contain_upstream_run_id_and_job_outputs() {
+        let outputs = vec![
+            output("build", "version", "1.2.3"),
+            output("package", "image", "bld:1.2.3"),
+        ];
+
+        let inputs = inputs("upstream", outputs);
+
+        assert_eq!(inputs.len(), 3);
+        assert_eq!(
+            inputs.get(UPSTREAM_RUN_ID_INPUT).map(String::as_str),
+            Some("upstream")
+        );
+        assert_eq!(
+            inputs.get("build_version").map(String::as_str),
+            Some("1.2.3")
+        );
+        assert_eq!(
+            inputs.get("package_image").map(String::as_str),
+            Some("bld:1.2.3")
+        );
+    }
+}
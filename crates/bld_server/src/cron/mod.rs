@@ -1,4 +1,8 @@
-use std::{collections::HashMap, str::FromStr, sync::Arc};
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, OnceLock},
+};
 
 use anyhow::{Result, anyhow, bail};
 use bld_core::fs::FileSystem;
@@ -10,18 +14,29 @@ use bld_models::{
     cron_jobs::{self, CronJob, InsertCronJob, UpdateCronJob},
     dtos::{AddJobRequest, CronJobResponse, ExecClientMessage, JobFiltersParams, UpdateJobRequest},
     pipeline::{self, Pipeline},
+    pipeline_runs::RunTrigger,
 };
 use sea_orm::DatabaseConnection;
+use tokio::sync::watch;
 use tokio_cron_scheduler::{Job, JobScheduler};
 use tracing::error;
 use uuid::Uuid;
 
-use crate::supervisor::{channel::SupervisorMessageSender, helpers::enqueue_worker};
+use crate::{
+    high_availability::{HighAvailability, RaftStatus},
+    supervisor::{channel::SupervisorMessageSender, helpers::enqueue_worker},
+};
 
+/// Schedules the cron jobs of the server. Every member of a raft group schedules them
+/// since they are replicated, but only the leader starts their runs. The runs are
+/// recorded through the raft group, which is set once it has been created since the state
+/// machine of the group needs the scheduler as well.
 pub struct CronScheduler {
     fs: Arc<FileSystem>,
     conn: Arc<DatabaseConnection>,
     supervisor: Arc<SupervisorMessageSender>,
+    status: watch::Receiver<RaftStatus>,
+    ha: Arc<OnceLock<Arc<HighAvailability>>>,
     scheduler: JobScheduler,
 }
 
@@ -30,6 +45,7 @@ impl CronScheduler {
         fs: Arc<FileSystem>,
         conn: Arc<DatabaseConnection>,
         supervisor: Arc<SupervisorMessageSender>,
+        status: watch::Receiver<RaftStatus>,
     ) -> Result<Self> {
         let scheduler = JobScheduler::new().await?;
         scheduler.start().await?;
@@ -37,12 +53,18 @@ impl CronScheduler {
             fs,
             conn,
             supervisor,
+            status,
+            ha: Arc::new(OnceLock::new()),
             scheduler,
         };
         instance.load_jobs().await?;
         Ok(instance)
     }
 
+    pub fn set_high_availability(&self, ha: Arc<HighAvailability>) {
+        let _ = self.ha.set(ha);
+    }
+
     fn inputs_into_hash_map(inputs: Vec<CronJobVariable>) -> Option<HashMap<String, String>> {
         let inputs: HashMap<String, String> =
             inputs.into_iter().map(|v| (v.name, v.value)).collect();
@@ -91,6 +113,7 @@ impl CronScheduler {
                 &pipeline.id,
                 inputs,
                 environment,
+                job.priority,
             )?;
 
             self.scheduler.add(scheduled_job).await?;
@@ -112,6 +135,7 @@ impl CronScheduler {
             pipeline_id: pipeline_id.to_owned(),
             schedule: add_job.schedule.to_owned(),
             is_default: add_job.is_default,
+            priority: add_job.priority,
         };
 
         let vars: Option<Vec<_>> = add_job.inputs.as_ref().map(|vars| {
@@ -134,11 +158,13 @@ impl CronScheduler {
         conn: &DatabaseConnection,
         job_id: &Uuid,
         update_job: &UpdateJobRequest,
+        priority: i32,
     ) -> Result<CronJob> {
         let job_id_str = job_id.to_string();
         let job = UpdateCronJob {
             id: job_id_str.to_owned(),
             schedule: update_job.schedule.to_owned(),
+            priority,
         };
 
         let vars: Option<Vec<_>> = update_job.inputs.as_ref().map(|vars| {
@@ -164,24 +190,38 @@ impl CronScheduler {
         pipeline_id: &str,
         inputs: Option<HashMap<String, String>>,
         env: Option<HashMap<String, String>>,
+        priority: i32,
     ) -> Result<Job> {
         // Compiler complaints about FnMut if parameters are directly used inside the closure
         // so this is the only workaround that works atm.
         let fs = self.fs.clone();
         let conn = self.conn.clone();
         let supervisor = self.supervisor.clone();
+        let status = self.status.clone();
+        let ha = self.ha.clone();
         let pipeline_id = pipeline_id.to_owned();
         let inputs = inputs.clone();
         let env = env.clone();
+        let cron_job_id = job_id.to_string();
 
         let mut job = Job::new_cron_job_async(schedule, move |_uuid, _l| {
             let fs = fs.clone();
             let conn = conn.clone();
             let supervisor = supervisor.clone();
+            let is_leader = status.borrow().is_leader();
+            let ha = ha.get().cloned();
             let pipeline_id = pipeline_id.to_owned();
             let inputs = inputs.clone();
             let env = env.clone();
+            let cron_job_id = cron_job_id.clone();
             Box::pin(async move {
+                if !is_leader {
+                    return;
+                }
+                let Some(ha) = ha else {
+                    error!("unable to enqueue cron run before the raft group is created");
+                    return;
+                };
                 let Ok(pipeline) = pipeline::select_by_id(conn.as_ref(), &pipeline_id).await else {
                     error!("unable to find pipeline with id: {pipeline_id}");
                     return;
@@ -190,8 +230,12 @@ impl CronScheduler {
                     name: pipeline.name.to_owned(),
                     env,
                     inputs,
+                    parent_run_id: None,
+                    priority: Some(priority),
                 };
-                if let Err(e) = enqueue_worker("Cron", fs, conn, supervisor, data).await {
+                let trigger = RunTrigger::cron(&cron_job_id);
+                let result = enqueue_worker("Cron", fs, conn, supervisor, ha, data, trigger).await;
+                if let Err(e) = result {
                     error!("unable to enqueue cron run due to: {e}");
                 }
             })
@@ -207,16 +251,23 @@ impl CronScheduler {
     async fn add_inner(
         &self,
         conn: &DatabaseConnection,
+        job_id: &str,
         add_job: &AddJobRequest,
         pipeline: &Pipeline,
     ) -> Result<()> {
-        let job_id = Uuid::new_v4();
+        let job_id = Uuid::from_str(job_id)?;
 
         let inputs = add_job.inputs.as_ref().cloned();
         let env = add_job.env.as_ref().cloned();
 
-        let scheduled_job =
-            self.create_scheduled_job(&job_id, &add_job.schedule, &pipeline.id, inputs, env)?;
+        let scheduled_job = self.create_scheduled_job(
+            &job_id,
+            &add_job.schedule,
+            &pipeline.id,
+            inputs,
+            env,
+            add_job.priority,
+        )?;
         let scheduled_job_id = scheduled_job.guid();
         self.scheduler.add(scheduled_job).await?;
 
@@ -243,6 +294,7 @@ impl CronScheduler {
 
         let inputs = update_job.inputs.as_ref().cloned();
         let environment = update_job.env.as_ref().cloned();
+        let priority = update_job.priority.unwrap_or(job.priority);
 
         let scheduled_job = self.create_scheduled_job(
             &job_id,
@@ -250,15 +302,19 @@ impl CronScheduler {
             &pipeline.id,
             inputs,
             environment,
+            priority,
         )?;
 
-        self.update_database_job(conn, &job_id, update_job).await?;
+        self.update_database_job(conn, &job_id, update_job, priority)
+            .await?;
         self.scheduler.add(scheduled_job).await?;
 
         Ok(())
     }
 
-    pub async fn add(&self, add_job: &AddJobRequest) -> Result<()> {
+    /// Adds a cron job with the provided id, which is generated before the job is
+    /// replicated so that it's the same for every member of a raft group.
+    pub async fn add(&self, id: &str, add_job: &AddJobRequest) -> Result<()> {
         let conn = self.conn.as_ref();
         let pipeline = pipeline::select_by_name(conn, &add_job.pipeline).await?;
         let job_exists = add_job.is_default
@@ -270,7 +326,7 @@ impl CronScheduler {
             bail!("cron job already exists");
         }
 
-        self.add_inner(conn, add_job, &pipeline).await
+        self.add_inner(conn, id, add_job, &pipeline).await
     }
 
     pub async fn update(&self, update_job: &UpdateJobRequest) -> Result<()> {
@@ -280,7 +336,8 @@ impl CronScheduler {
         self.update_inner(conn, update_job, &job, &pipeline).await
     }
 
-    pub async fn upsert_default(&self, schedule: &str, pipeline: &str) -> Result<()> {
+    /// Updates the default cron job of the pipeline or adds it with the provided id.
+    pub async fn upsert_default(&self, id: &str, schedule: &str, pipeline: &str) -> Result<()> {
         let job = {
             let conn = self.conn.as_ref();
             let pipeline = pipeline::select_by_name(conn, pipeline).await?;
@@ -288,13 +345,20 @@ impl CronScheduler {
         };
         match job {
             Ok(job) => {
-                let update_job = UpdateJobRequest::new(job.id, schedule.to_owned(), None, None);
+                let update_job =
+                    UpdateJobRequest::new(job.id, schedule.to_owned(), None, None, None);
                 self.update(&update_job).await
             }
             Err(_) => {
-                let add_job =
-                    AddJobRequest::new(schedule.to_owned(), pipeline.to_owned(), None, None, true);
-                self.add(&add_job).await
+                let add_job = AddJobRequest::new(
+                    schedule.to_owned(),
+                    pipeline.to_owned(),
+                    None,
+                    None,
+                    true,
+                    0,
+                );
+                self.add(id, &add_job).await
             }
         }
     }
@@ -361,6 +425,7 @@ impl CronScheduler {
                 inputs,
                 env: environment,
                 is_default: job.is_default,
+                priority: job.priority,
                 date_created: job.date_created.to_string(),
                 date_updated: job.date_updated.map(|x| x.to_string()),
             });
@@ -0,0 +1,73 @@
+use actix_web::{
+    HttpResponse, Responder, delete, get, post,
+    web::{Data, Json, Path},
+};
+use anyhow::Result;
+use bld_models::{
+    access_grants,
+    dtos::{AccessGrantResponse, AccessRole, AddAccessGrantRequest},
+};
+use sea_orm::DatabaseConnection;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    access,
+    extractors::User,
+    high_availability::{HaCommand, HighAvailability},
+};
+
+#[get("/v1/access")]
+pub async fn get(user: User, conn: Data<DatabaseConnection>) -> impl Responder {
+    info!("Reached handler for GET /access route");
+    if let Err(response) = access::authorize_server(&user, AccessRole::Admin) {
+        return response;
+    }
+    match do_get(conn.get_ref()).await {
+        Ok(grants) => HttpResponse::Ok().json(grants),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+async fn do_get(conn: &DatabaseConnection) -> Result<Vec<AccessGrantResponse>> {
+    access_grants::select_all(conn)
+        .await?
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect()
+}
+
+#[post("/v1/access")]
+pub async fn post(
+    user: User,
+    ha: Data<HighAvailability>,
+    body: Json<AddAccessGrantRequest>,
+) -> impl Responder {
+    info!("Reached handler for POST /access route");
+    if let Err(response) = access::authorize_server(&user, AccessRole::Admin) {
+        return response;
+    }
+    let command = HaCommand::AddAccessGrant {
+        id: Uuid::new_v4().to_string(),
+        grant: body.into_inner(),
+    };
+    match ha.apply(command).await {
+        Ok(_) => HttpResponse::Ok().json(""),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+#[delete("/v1/access/{id}")]
+pub async fn delete(user: User, ha: Data<HighAvailability>, path: Path<String>) -> impl Responder {
+    info!("Reached handler for DELETE /access route");
+    if let Err(response) = access::authorize_server(&user, AccessRole::Admin) {
+        return response;
+    }
+    let command = HaCommand::RemoveAccessGrant {
+        id: path.into_inner(),
+    };
+    match ha.apply(command).await {
+        Ok(_) => HttpResponse::Ok().json(""),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
@@ -0,0 +1,150 @@
+use actix_web::{
+    HttpResponse, Responder, post,
+    web::{Data, Json},
+};
+use anyhow::{Result, anyhow, bail};
+use bld_models::{
+    dtos::{AccessRole, ApprovalRequest},
+    pipeline_run_approvals::{self, PRA_STATE_APPROVED, PRA_STATE_REJECTED, PipelineRunApprovals},
+};
+use sea_orm::DatabaseConnection;
+use tracing::info;
+
+use crate::{access, extractors::User};
+
+#[post("/v1/approve")]
+pub async fn post(
+    user: User,
+    conn: Data<DatabaseConnection>,
+    body: Json<ApprovalRequest>,
+) -> impl Responder {
+    info!("Reached handler for /approve route");
+    if let Err(response) =
+        access::authorize_run(&user, &conn, AccessRole::Runner, &body.run_id).await
+    {
+        return response;
+    }
+    match do_decide(conn.get_ref(), &user, body.into_inner()).await {
+        Ok(_) => HttpResponse::Ok().json(""),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+/// Picks the pending approval of the requested job, or the only job that the run waits on
+/// when no job is requested. The approvals of the instances of a job's matrix are decided
+/// on one at a time, oldest first.
+fn pending_approval(
+    pending: Vec<PipelineRunApprovals>,
+    body: &ApprovalRequest,
+) -> Result<PipelineRunApprovals> {
+    let run_id = &body.run_id;
+    if let Some(job) = body.job.as_deref() {
+        return pending
+            .into_iter()
+            .find(|approval| approval.job == job)
+            .ok_or_else(|| anyhow!("no pending approval found for job {job} of run {run_id}"));
+    }
+
+    let mut jobs: Vec<&str> = pending
+        .iter()
+        .map(|approval| approval.job.as_str())
+        .collect();
+    jobs.sort_unstable();
+    jobs.dedup();
+    if jobs.len() > 1 {
+        bail!(
+            "run {run_id} is waiting on the approval of jobs {}, choose the job to decide on",
+            jobs.join(", ")
+        );
+    }
+
+    pending
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("no pending approval found for run {run_id}"))
+}
+
+async fn do_decide(conn: &DatabaseConnection, user: &User, body: ApprovalRequest) -> Result<()> {
+    let pending = pipeline_run_approvals::select_pending_by_run_id(conn, &body.run_id).await?;
+    let approval = pending_approval(pending, &body)?;
+    if !pipeline_run_approvals::is_approver(&approval, &user.name) {
+        bail!(
+            "user {} is not allowed to approve run {}",
+            user.name,
+            body.run_id
+        );
+    }
+
+    let state = if body.approved {
+        PRA_STATE_APPROVED
+    } else {
+        PRA_STATE_REJECTED
+    };
+    info!("run {} was {state} by {}", body.run_id, user.name);
+    pipeline_run_approvals::update_decision(conn, &approval.id, state, Some(&user.name)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use bld_models::{
+        dtos::ApprovalRequest,
+        pipeline_run_approvals::{PRA_STATE_PENDING, PipelineRunApprovals},
+    };
+    use chrono::Utc;
+
+    use super::pending_approval;
+
+    fn approval(id: &str, job: &str) -> PipelineRunApprovals {
+        PipelineRunApprovals {
+            id: id.to_string(),
+            run_id: "run".to_string(),
+            job: job.to_string(),
+            message: None,
+            approvers: String::new(),
+            state: PRA_STATE_PENDING.to_string(),
+            decided_by: None,
+            date_created: Utc::now().naive_utc(),
+            date_updated: None,
+        }
+    }
+
+    fn request(job: Option<&str>) -> ApprovalRequest {
+        ApprovalRequest::new("run".to_string(), job.map(str::to_string), true)
+    }
+
+    #[test]
+    fn pending_approval_of_the_requested_job() {
+        let pending = vec![approval("1", "staging"), approval("2", "production")];
+        let selected = pending_approval(pending, &request(Some("production"))).unwrap();
+        assert_eq!(selected.id, "2");
+    }
+
+    #[test]
+    fn pending_approval_of_a_job_that_isnt_waiting_fails() {
+        let pending = vec![approval("1", "staging")];
+        let error = pending_approval(pending, &request(Some("production"))).unwrap_err();
+        assert!(error.to_string().contains("job production"), "{error}");
+    }
+
+    #[test]
+    fn pending_approval_without_a_job_picks_the_only_job_waiting() {
+        let pending = vec![approval("1", "deploy"), approval("2", "deploy")];
+        let selected = pending_approval(pending, &request(None)).unwrap();
+        assert_eq!(selected.id, "1");
+    }
+
+    #[test]
+    fn pending_approval_without_a_job_fails_when_more_than_one_is_waiting() {
+        let pending = vec![approval("1", "staging"), approval("2", "production")];
+        let error = pending_approval(pending, &request(None)).unwrap_err();
+        assert!(
+            error.to_string().contains("jobs production, staging"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn pending_approval_without_any_waiting_fails() {
+        assert!(pending_approval(vec![], &request(None)).is_err());
+    }
+}
@@ -6,20 +6,25 @@ use actix_web::{
 use bld_config::BldConfig;
 use bld_models::{
     artifacts::{delete_by_id, select_by_id, select_by_run_id},
-    dtos::{ArtifactResponse, ArtifactsQueryParams},
+    dtos::{AccessRole, ArtifactResponse, ArtifactsQueryParams},
 };
 use sea_orm::DatabaseConnection;
 use tracing::{info, warn};
 
-use crate::extractors::User;
+use crate::{access, extractors::User};
 
 #[get("/v1/artifacts")]
 pub async fn get(
-    _: User,
+    user: User,
     conn: Data<DatabaseConnection>,
     params: Query<ArtifactsQueryParams>,
 ) -> impl Responder {
     info!("Reached handler for GET /artifacts route");
+    if let Err(response) =
+        access::authorize_run(&user, &conn, AccessRole::Viewer, &params.run_id).await
+    {
+        return response;
+    }
     match select_by_run_id(conn.get_ref(), &params.run_id).await {
         Ok(artifacts) => {
             let response: Vec<ArtifactResponse> = artifacts.into_iter().map(Into::into).collect();
@@ -31,7 +36,7 @@ pub async fn get(
 
 #[get("/v1/artifacts/{id}/download")]
 pub async fn download(
-    _: User,
+    user: User,
     conn: Data<DatabaseConnection>,
     config: Data<BldConfig>,
     path: Path<String>,
@@ -44,6 +49,12 @@ pub async fn download(
         Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
     };
 
+    if let Err(response) =
+        access::authorize_run(&user, &conn, AccessRole::Viewer, &artifact.run_id).await
+    {
+        return response;
+    }
+
     let artifact_path = config.artifact_full_path(&artifact.run_id, &artifact.id);
     match tokio::fs::read(&artifact_path).await {
         Ok(bytes) => HttpResponse::Ok()
@@ -95,7 +106,7 @@ mod tests {
 
 #[delete("/v1/artifacts/{id}")]
 pub async fn delete(
-    _: User,
+    user: User,
     conn: Data<DatabaseConnection>,
     config: Data<BldConfig>,
     path: Path<String>,
@@ -108,6 +119,12 @@ pub async fn delete(
         Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
     };
 
+    if let Err(response) =
+        access::authorize_run(&user, &conn, AccessRole::Maintainer, &artifact.run_id).await
+    {
+        return response;
+    }
+
     if let Err(e) = delete_by_id(conn.get_ref(), &id).await {
         return HttpResponse::BadRequest().body(e.to_string());
     }
@@ -2,20 +2,28 @@ use actix_web::{
     HttpResponse, Responder, post,
     web::{Data, Json},
 };
-use bld_core::fs::FileSystem;
-use bld_models::dtos::PipelinePathRequest;
+use bld_models::dtos::{AccessRole, PipelinePathRequest};
 use tracing::info;
 
-use crate::extractors::User;
+use crate::{
+    access,
+    extractors::User,
+    high_availability::{HaCommand, HighAvailability},
+};
 
 #[post("/v1/copy")]
 pub async fn post(
-    _user: User,
-    fs: Data<FileSystem>,
+    user: User,
+    ha: Data<HighAvailability>,
     body: Json<PipelinePathRequest>,
 ) -> impl Responder {
     info!("Reached handler for /copy route");
-    match fs.copy(&body.pipeline, &body.target).await {
+    let authorized = access::authorize(&user, AccessRole::Viewer, &body.pipeline)
+        .and_then(|_| access::authorize(&user, AccessRole::Maintainer, &body.target));
+    if let Err(response) = authorized {
+        return response;
+    }
+    match ha.apply(HaCommand::Copy(body.into_inner())).await {
         Ok(_) => HttpResponse::Ok().json(""),
         Err(e) => HttpResponse::BadRequest().body(e.to_string()),
     }
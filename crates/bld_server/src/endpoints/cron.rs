@@ -2,28 +2,49 @@ use actix_web::{
     HttpResponse, Responder, delete, get, patch, post,
     web::{Data, Json, Path, Query},
 };
-use bld_models::dtos::{AddJobRequest, JobFiltersParams, UpdateJobRequest};
+use bld_models::dtos::{AccessRole, AddJobRequest, JobFiltersParams, UpdateJobRequest};
+use sea_orm::DatabaseConnection;
 use tracing::info;
+use uuid::Uuid;
 
-use crate::{cron::CronScheduler, extractors::User};
+use crate::{
+    access,
+    cron::CronScheduler,
+    extractors::User,
+    high_availability::{HaCommand, HighAvailability},
+};
 
 #[get("/v1/cron")]
 pub async fn get(
-    _: User,
+    user: User,
     cron: Data<CronScheduler>,
     query: Query<JobFiltersParams>,
 ) -> impl Responder {
     info!("Reached handler for GET /cron route");
     match cron.get(&query).await {
-        Ok(res) => HttpResponse::Ok().json(res),
+        Ok(mut res) => {
+            res.retain(|job| user.can_view(&job.pipeline));
+            HttpResponse::Ok().json(res)
+        }
         Err(e) => HttpResponse::BadRequest().body(e.to_string()),
     }
 }
 
 #[post("/v1/cron")]
-pub async fn post(_: User, cron: Data<CronScheduler>, body: Json<AddJobRequest>) -> impl Responder {
+pub async fn post(
+    user: User,
+    ha: Data<HighAvailability>,
+    body: Json<AddJobRequest>,
+) -> impl Responder {
     info!("Reached handler for POST /cron route");
-    match cron.add(&body).await {
+    if let Err(response) = access::authorize(&user, AccessRole::Maintainer, &body.pipeline) {
+        return response;
+    }
+    let command = HaCommand::AddCronJob {
+        id: Uuid::new_v4().to_string(),
+        job: body.into_inner(),
+    };
+    match ha.apply(command).await {
         Ok(_) => HttpResponse::Ok().json(""),
         Err(e) => HttpResponse::BadRequest().body(e.to_string()),
     }
@@ -31,22 +52,39 @@ pub async fn post(_: User, cron: Data<CronScheduler>, body: Json<AddJobRequest>)
 
 #[patch("/v1/cron")]
 pub async fn patch(
-    _: User,
-    cron: Data<CronScheduler>,
+    user: User,
+    conn: Data<DatabaseConnection>,
+    ha: Data<HighAvailability>,
     body: Json<UpdateJobRequest>,
 ) -> impl Responder {
     info!("Reached handler for PATCH /cron route");
-    match cron.update(&body).await {
+    if let Err(response) =
+        access::authorize_cron_job(&user, &conn, AccessRole::Maintainer, &body.id).await
+    {
+        return response;
+    }
+    match ha.apply(HaCommand::UpdateCronJob(body.into_inner())).await {
         Ok(_) => HttpResponse::Ok().json(""),
         Err(e) => HttpResponse::BadRequest().body(e.to_string()),
     }
 }
 
 #[delete("/v1/cron/{cron_job_id}")]
-pub async fn delete(_: User, cron: Data<CronScheduler>, path: Path<String>) -> impl Responder {
+pub async fn delete(
+    user: User,
+    conn: Data<DatabaseConnection>,
+    ha: Data<HighAvailability>,
+    path: Path<String>,
+) -> impl Responder {
     info!("Reached handler for DELETE /cron route");
-    let cron_job_id = path.into_inner();
-    match cron.remove(&cron_job_id).await {
+    let id = path.into_inner();
+    if let Err(response) =
+        access::authorize_cron_job(&user, &conn, AccessRole::Maintainer, &id).await
+    {
+        return response;
+    }
+    let command = HaCommand::RemoveCronJob { id };
+    match ha.apply(command).await {
         Ok(_) => HttpResponse::Ok().json(""),
         Err(e) => HttpResponse::BadRequest().body(e.to_string()),
     }
@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use crate::access;
 use crate::extractors::User;
 use actix_web::{
     HttpResponse, Responder, get,
@@ -8,20 +9,23 @@ use actix_web::{
 use anyhow::Result;
 use bld_config::BldConfig;
 use bld_core::fs::FileSystem;
-use bld_models::dtos::PipelineQueryParams;
+use bld_models::dtos::{AccessRole, PipelineQueryParams};
 use bld_pkg::PackageManager;
 use bld_runner::VersionedFile;
 use tracing::info;
 
 #[get("/v1/deps")]
 pub async fn get(
-    _user: User,
+    user: User,
     config: Data<BldConfig>,
     fs: Data<FileSystem>,
     package_manager: Data<PackageManager>,
     params: Query<PipelineQueryParams>,
 ) -> impl Responder {
     info!("Reached handler for /deps route");
+    if let Err(response) = access::authorize(&user, AccessRole::Viewer, &params.pipeline) {
+        return response;
+    }
     match do_deps(config, fs, package_manager, params.into_inner()).await {
         Ok(r) => HttpResponse::Ok().json(r),
         Err(e) => HttpResponse::BadRequest().body(e.to_string()),
@@ -0,0 +1,67 @@
+use actix_web::{
+    HttpRequest, HttpResponse, Responder, post,
+    web::{Data, Json},
+};
+use tracing::debug;
+
+use crate::high_availability::{
+    AppendEntriesRequest, HA_SECRET_HEADER, HighAvailability, InstallSnapshotRequest, VoteRequest,
+};
+
+/// Rejects the request unless it carries the secret of the raft group.
+fn authorize(req: &HttpRequest, ha: &HighAvailability) -> Result<(), HttpResponse> {
+    let secret = req
+        .headers()
+        .get(HA_SECRET_HEADER)
+        .and_then(|v| v.to_str().ok());
+    ha.verify_secret(secret)
+        .map_err(|e| HttpResponse::Unauthorized().body(e.to_string()))
+}
+
+#[post("/v1/ha/append-entries")]
+pub async fn append_entries(
+    req: HttpRequest,
+    ha: Data<HighAvailability>,
+    body: Json<AppendEntriesRequest>,
+) -> impl Responder {
+    debug!("Reached handler for /ha/append-entries route");
+    if let Err(response) = authorize(&req, &ha) {
+        return response;
+    }
+    match ha.append_entries(body.into_inner()).await {
+        Ok(res) => HttpResponse::Ok().json(res),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+#[post("/v1/ha/request-vote")]
+pub async fn request_vote(
+    req: HttpRequest,
+    ha: Data<HighAvailability>,
+    body: Json<VoteRequest>,
+) -> impl Responder {
+    debug!("Reached handler for /ha/request-vote route");
+    if let Err(response) = authorize(&req, &ha) {
+        return response;
+    }
+    match ha.request_vote(body.into_inner()).await {
+        Ok(res) => HttpResponse::Ok().json(res),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+#[post("/v1/ha/install-snapshot")]
+pub async fn install_snapshot(
+    req: HttpRequest,
+    ha: Data<HighAvailability>,
+    body: Json<InstallSnapshotRequest>,
+) -> impl Responder {
+    debug!("Reached handler for /ha/install-snapshot route");
+    if let Err(response) = authorize(&req, &ha) {
+        return response;
+    }
+    match ha.install_snapshot(body.into_inner()).await {
+        Ok(res) => HttpResponse::Ok().json(res),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
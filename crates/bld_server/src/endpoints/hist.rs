@@ -1,21 +1,24 @@
-use crate::extractors::User;
+use crate::{access, extractors::User};
 use actix_web::{HttpResponse, Responder, get, web::Data, web::Query};
 use anyhow::Result;
 use bld_models::{
-    dtos::{HistQueryParams, HistoryEntry},
-    pipeline_runs,
+    dtos::{AccessRole, HistQueryParams, HistoryEntry, HistoryEntryDetails},
+    pipeline_run_environment_variables, pipeline_run_inputs, pipeline_run_outputs, pipeline_runs,
 };
 use sea_orm::DatabaseConnection;
 use tracing::info;
 
 #[get("/v1/hist")]
 pub async fn get(
-    _user: User,
+    user: User,
     conn: Data<DatabaseConnection>,
     params: Query<HistQueryParams>,
 ) -> impl Responder {
     info!("Reached handler for /hist route");
-    match history_info(conn.get_ref(), params.into_inner()).await {
+    if let Err(response) = access::authorize_any(&user, AccessRole::Viewer) {
+        return response;
+    }
+    match history_info(conn.get_ref(), &user, params.into_inner()).await {
         Ok(ls) => HttpResponse::Ok().json(ls),
         Err(_) => HttpResponse::BadRequest().body(""),
     }
@@ -23,12 +26,48 @@ pub async fn get(
 
 async fn history_info(
     conn: &DatabaseConnection,
+    user: &User,
     params: HistQueryParams,
 ) -> Result<Vec<HistoryEntry>> {
     let history =
         pipeline_runs::select_with_filters(conn, &params.state, &params.name, params.limit).await;
-    let entries = history
-        .map(|entries| entries.into_iter().map(|p| p.into()).collect())
+    let mut entries: Vec<HistoryEntry> = history
+        .map(|entries| {
+            entries
+                .into_iter()
+                .filter(|run| user.can_view(&run.name))
+                .map(|run| run.into())
+                .collect()
+        })
         .unwrap_or_else(|_| vec![]);
+
+    if params.details {
+        for entry in entries.iter_mut() {
+            entry.details = Some(history_details(conn, &entry.id).await?);
+        }
+    }
+
     Ok(entries)
 }
+
+async fn history_details(conn: &DatabaseConnection, run_id: &str) -> Result<HistoryEntryDetails> {
+    let mut details = HistoryEntryDetails::default();
+
+    for input in pipeline_run_inputs::select_by_run_id(conn, run_id).await? {
+        details.inputs.insert(input.name, input.value);
+    }
+
+    for env in pipeline_run_environment_variables::select_by_run_id(conn, run_id).await? {
+        details.env.insert(env.name, env.value);
+    }
+
+    for output in pipeline_run_outputs::select_by_run_id(conn, run_id).await? {
+        details
+            .outputs
+            .entry(output.job)
+            .or_default()
+            .insert(output.name, output.value);
+    }
+
+    Ok(details)
+}
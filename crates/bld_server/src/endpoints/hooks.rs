@@ -0,0 +1,137 @@
+use std::{collections::HashMap, sync::Arc};
+
+use actix_web::{
+    HttpRequest, HttpResponse, Responder, post,
+    web::{Bytes, Data, Path},
+};
+use anyhow::{Result, anyhow};
+use bld_config::BldConfig;
+use bld_core::{fs::FileSystem, secrets::SecretsCipher};
+use bld_models::{
+    dtos::ExecClientMessage,
+    pipeline,
+    pipeline_runs::RunTrigger,
+    webhook_trigger_inputs,
+    webhook_triggers::{self, WebhookTrigger},
+};
+use sea_orm::DatabaseConnection;
+use serde_json::Value;
+use tracing::{debug, info};
+
+use crate::{
+    high_availability::HighAvailability,
+    supervisor::{channel::SupervisorMessageSender, helpers::enqueue_worker},
+    webhooks::{GITHUB_EVENT_HEADER, SIGNATURE_HEADER, filter, lookup, verify_signature},
+};
+
+const WEBHOOK_USER: &str = "Webhook";
+
+/// Receives the payload of a webhook, which authenticates using the HMAC signature of
+/// the payload instead of a user token, and starts a run of the trigger's pipeline
+/// unless the payload is filtered out.
+#[post("/v1/hooks/{name}")]
+#[allow(clippy::too_many_arguments)]
+pub async fn post(
+    request: HttpRequest,
+    config: Data<BldConfig>,
+    fs: Data<FileSystem>,
+    conn: Data<DatabaseConnection>,
+    supervisor: Data<SupervisorMessageSender>,
+    ha: Data<HighAvailability>,
+    path: Path<String>,
+    body: Bytes,
+) -> impl Responder {
+    info!("Reached handler for POST /hooks route");
+    let name = path.into_inner();
+
+    let trigger = match verify(&request, config.get_ref(), conn.get_ref(), &name, &body).await {
+        Ok(trigger) => trigger,
+        Err(e) => return HttpResponse::Unauthorized().body(e.to_string()),
+    };
+
+    let is_ping = request
+        .headers()
+        .get(GITHUB_EVENT_HEADER)
+        .is_some_and(|event| event == "ping");
+    if is_ping {
+        return HttpResponse::Ok().body("ping received, no run started");
+    }
+
+    match do_trigger(trigger, fs, conn, supervisor, ha, &body).await {
+        Ok(Ok(run_id)) => HttpResponse::Ok().json(run_id),
+        Ok(Err(reason)) => HttpResponse::Ok().body(format!("{reason}, no run started")),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+async fn verify(
+    request: &HttpRequest,
+    config: &BldConfig,
+    conn: &DatabaseConnection,
+    name: &str,
+    body: &[u8],
+) -> Result<WebhookTrigger> {
+    let signature = request
+        .headers()
+        .get(SIGNATURE_HEADER)
+        .and_then(|x| x.to_str().ok())
+        .ok_or_else(|| anyhow!("missing {SIGNATURE_HEADER} header"))?;
+    let trigger = webhook_triggers::select_by_name(conn, name).await?;
+    let secret = SecretsCipher::new(config)?.decrypt(&trigger.secret)?;
+    verify_signature(&secret, body, signature)?;
+    Ok(trigger)
+}
+
+/// Starts a run of the trigger's pipeline with the inputs mapped from the payload,
+/// returning its id, or the reason that the payload was filtered out.
+async fn do_trigger(
+    trigger: WebhookTrigger,
+    fs: Data<FileSystem>,
+    conn: Data<DatabaseConnection>,
+    supervisor: Data<SupervisorMessageSender>,
+    ha: Data<HighAvailability>,
+    body: &[u8],
+) -> Result<Result<String, String>> {
+    let payload: Value = serde_json::from_slice(body)?;
+
+    let branches = webhook_triggers::branches(&trigger);
+    let paths = webhook_triggers::paths(&trigger);
+    if let Some(reason) = filter(&branches, &paths, &payload) {
+        debug!(
+            "webhook trigger {} filtered out payload, {reason}",
+            trigger.name
+        );
+        return Ok(Err(reason));
+    }
+
+    let mut inputs = HashMap::new();
+    for input in webhook_trigger_inputs::select_by_trigger_id(conn.get_ref(), &trigger.id).await? {
+        match lookup(&payload, &input.path) {
+            Some(value) => {
+                inputs.insert(input.name, value);
+            }
+            None => debug!("no value at path {} for input {}", input.path, input.name),
+        }
+    }
+
+    let pipeline = pipeline::select_by_id(conn.get_ref(), &trigger.pipeline_id).await?;
+    let message = ExecClientMessage::EnqueueRun {
+        name: pipeline.name,
+        env: None,
+        inputs: Some(inputs),
+        parent_run_id: None,
+        priority: None,
+    };
+
+    enqueue_worker(
+        WEBHOOK_USER,
+        Arc::clone(&fs),
+        Arc::clone(&conn),
+        Arc::clone(&supervisor),
+        ha.into_inner(),
+        message,
+        RunTrigger::webhook(&trigger.id),
+    )
+    .await
+    .map(Ok)
+}
@@ -1,24 +1,31 @@
-use crate::extractors::User;
+use crate::{access, extractors::User};
 use actix_web::{
     HttpResponse, get,
     http::header,
     web::{Data, Header},
 };
-use bld_models::{dtos::ListResponse, pipeline};
+use bld_models::{
+    dtos::{AccessRole, ListResponse},
+    pipeline,
+};
 use sea_orm::DatabaseConnection;
 use tracing::info;
 
 #[get("/v1/list")]
 pub async fn get(
-    _: User,
+    user: User,
     conn: Data<DatabaseConnection>,
     accept: Header<header::Accept>,
 ) -> HttpResponse {
     info!("Reached handler for /list route");
+    if let Err(response) = access::authorize_any(&user, AccessRole::Viewer) {
+        return response;
+    }
 
-    let Ok(pips) = pipeline::select_all(conn.as_ref()).await else {
+    let Ok(mut pips) = pipeline::select_all(conn.as_ref()).await else {
         return HttpResponse::BadRequest().body("no pipelines found");
     };
+    pips.retain(|x| user.can_view(&x.name));
 
     let accept = accept.to_string();
 
@@ -1,11 +1,15 @@
+pub mod access;
+pub mod approve;
 pub mod artifacts;
 pub mod auth;
 pub mod check;
 pub mod copy;
 pub mod cron;
 pub mod deps;
+pub mod ha;
 pub mod hist;
 pub mod home;
+pub mod hooks;
 pub mod list;
 pub mod r#move;
 pub mod print;
@@ -13,5 +17,8 @@ pub mod pull;
 pub mod push;
 pub mod remove;
 pub mod run;
+pub mod secrets;
 pub mod stop;
+pub mod timeline;
+pub mod triggers;
 pub mod ui;
@@ -1,16 +1,19 @@
+use crate::access;
 use crate::extractors::User;
 use actix_web::http::header;
 use actix_web::web::{Data, Header, Query};
 use actix_web::{HttpResponse, Responder, get};
 use bld_core::fs::FileSystem;
-use bld_models::dtos::PipelineInfoQueryParams;
+use bld_models::dtos::{AccessRole, PipelineInfoQueryParams};
 use bld_pkg::PackageManager;
 use bld_runner::VersionedFileLoader;
+use sea_orm::DatabaseConnection;
 use tracing::{debug, info};
 
 #[get("/v1/print")]
 pub async fn get(
-    _: User,
+    user: User,
+    conn: Data<DatabaseConnection>,
     fs: Data<FileSystem>,
     package_manager: Data<PackageManager>,
     params: Query<PipelineInfoQueryParams>,
@@ -18,6 +21,18 @@ pub async fn get(
 ) -> impl Responder {
     info!("Reached handler for /print route");
 
+    let authorized = match &*params {
+        PipelineInfoQueryParams::Id { id } => {
+            access::authorize_pipeline_id(&user, &conn, AccessRole::Viewer, id).await
+        }
+        PipelineInfoQueryParams::Name { name } => {
+            access::authorize(&user, AccessRole::Viewer, name)
+        }
+    };
+    if let Err(response) = authorized {
+        return response;
+    }
+
     let content = match params.into_inner() {
         PipelineInfoQueryParams::Id { id } => fs.read_by_id(&id).await,
         PipelineInfoQueryParams::Name { name } => fs.read(&name).await,
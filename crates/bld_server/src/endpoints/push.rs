@@ -1,44 +1,67 @@
+use crate::access;
+use crate::completions;
 use crate::cron::CronScheduler;
 use crate::extractors::User;
+use crate::high_availability::{HaCommand, HighAvailability};
 use actix_web::web::{Data, Json};
 use actix_web::{HttpResponse, Responder, post};
 use anyhow::Result;
 use bld_core::fs::FileSystem;
-use bld_models::dtos::PushInfo;
+use bld_models::dtos::{AccessRole, PushInfo};
 use bld_pkg::PackageManager;
 use bld_runner::VersionedFileLoader;
+use sea_orm::DatabaseConnection;
 use tracing::{error, info};
+use uuid::Uuid;
 
 #[post("/v1/push")]
-pub async fn post(
-    _: User,
-    fs: Data<FileSystem>,
-    package_manager: Data<PackageManager>,
-    cron: Data<CronScheduler>,
-    info: Json<PushInfo>,
-) -> impl Responder {
+pub async fn post(user: User, ha: Data<HighAvailability>, info: Json<PushInfo>) -> impl Responder {
     info!("Reached handler for /push route");
-    match do_push(&fs, &package_manager, &cron, &info).await {
+    if let Err(response) = access::authorize(&user, AccessRole::Maintainer, &info.name) {
+        return response;
+    }
+    let command = HaCommand::Push {
+        info: info.into_inner(),
+        cron_job_id: Uuid::new_v4().to_string(),
+        completion_trigger_id: Uuid::new_v4().to_string(),
+    };
+    match ha.apply(command).await {
         Ok(()) => HttpResponse::Ok().json(""),
         Err(e) => HttpResponse::BadRequest().body(e.to_string()),
     }
 }
 
-async fn do_push(
+/// Creates the pipeline and syncs its cron job and completion triggers. The ids are used
+/// for the default cron job and the completion trigger of the pipeline if it doesn't have
+/// them already.
+pub(crate) async fn do_push(
     fs: &FileSystem,
     package_manager: &PackageManager,
     cron: &CronScheduler,
+    conn: &DatabaseConnection,
     info: &PushInfo,
+    cron_job_id: &str,
+    completion_trigger_id: &str,
 ) -> Result<()> {
     fs.create(&info.name, &info.content, true).await?;
     let loader = VersionedFileLoader::new(package_manager, fs, false);
     let metadata = loader.load(&info.name).await?;
     let remove_res = match metadata.file.cron() {
-        Some(schedule) => cron.upsert_default(schedule, &info.name).await,
+        Some(schedule) => cron.upsert_default(cron_job_id, schedule, &info.name).await,
         None => cron.remove_by_pipeline(&info.name).await,
     };
-    remove_res.map_err(|e| {
-        error!("{e}");
-        e
-    })
+    remove_res
+        .and(
+            completions::sync(
+                conn,
+                &info.name,
+                metadata.file.pipeline_completed(),
+                completion_trigger_id,
+            )
+            .await,
+        )
+        .map_err(|e| {
+            error!("{e}");
+            e
+        })
 }
@@ -1,32 +1,35 @@
+use crate::access;
 use crate::cron::CronScheduler;
 use crate::extractors::User;
+use crate::high_availability::{HaCommand, HighAvailability};
 use actix_web::web::{Data, Query};
 use actix_web::{HttpResponse, delete};
 use anyhow::Result;
 use bld_core::fs::FileSystem;
-use bld_models::dtos::PipelineQueryParams;
+use bld_models::dtos::{AccessRole, PipelineQueryParams};
 use tracing::info;
 
 #[delete("/v1/remove")]
 pub async fn delete(
-    _: User,
-    fs: Data<FileSystem>,
-    cron: Data<CronScheduler>,
+    user: User,
+    ha: Data<HighAvailability>,
     params: Query<PipelineQueryParams>,
 ) -> HttpResponse {
     info!("Reached handler for /remove route");
-    match do_remove(&fs, &cron, &params).await {
+    if let Err(response) = access::authorize(&user, AccessRole::Maintainer, &params.pipeline) {
+        return response;
+    }
+    let command = HaCommand::Remove {
+        pipeline: params.into_inner().pipeline,
+    };
+    match ha.apply(command).await {
         Ok(_) => HttpResponse::Ok().json(""),
         Err(e) => HttpResponse::BadRequest().body(e.to_string()),
     }
 }
 
-async fn do_remove(
-    fs: &FileSystem,
-    cron: &CronScheduler,
-    params: &PipelineQueryParams,
-) -> Result<()> {
-    cron.remove_scheduled_jobs(&params.pipeline).await?;
-    fs.remove(&params.pipeline).await?;
+pub(crate) async fn do_remove(fs: &FileSystem, cron: &CronScheduler, pipeline: &str) -> Result<()> {
+    cron.remove_scheduled_jobs(pipeline).await?;
+    fs.remove(pipeline).await?;
     Ok(())
 }
@@ -1,7 +1,9 @@
 use std::sync::Arc;
 
 use crate::{
+    access,
     extractors::User,
+    high_availability::HighAvailability,
     supervisor::{channel::SupervisorMessageSender, helpers::enqueue_worker},
 };
 use actix_web::{
@@ -9,7 +11,10 @@ use actix_web::{
     web::{Data, Json},
 };
 use bld_core::fs::FileSystem;
-use bld_models::dtos::ExecClientMessage;
+use bld_models::{
+    dtos::{AccessRole, ExecClientMessage},
+    pipeline_runs::RunTrigger,
+};
 use sea_orm::DatabaseConnection;
 use tracing::info;
 
@@ -19,16 +24,27 @@ pub async fn post(
     fs: Data<FileSystem>,
     conn: Data<DatabaseConnection>,
     supervisor: Data<SupervisorMessageSender>,
+    ha: Data<HighAvailability>,
     data: Json<ExecClientMessage>,
 ) -> impl Responder {
     info!("reached handler for /run route");
 
+    let pipeline = match access::exec_pipeline(&conn, &data).await {
+        Ok(pipeline) => pipeline,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+    if let Err(response) = access::authorize(&user, AccessRole::Runner, &pipeline) {
+        return response;
+    }
+
     let result = enqueue_worker(
         &user.name,
         Arc::clone(&fs),
         Arc::clone(&conn),
         Arc::clone(&supervisor),
+        ha.into_inner(),
         data.into_inner(),
+        RunTrigger::user(),
     )
     .await;
 
@@ -0,0 +1,88 @@
+use actix_web::{
+    HttpResponse, Responder, delete, get, post,
+    web::{Data, Json, Path},
+};
+use anyhow::Result;
+use bld_config::BldConfig;
+use bld_core::secrets::{SecretsCipher, validate_name};
+use bld_models::{
+    dtos::{AccessRole, SecretRequest, SecretResponse},
+    secrets::{self, InsertSecret},
+};
+use sea_orm::DatabaseConnection;
+use tracing::info;
+
+use crate::{
+    access,
+    extractors::User,
+    high_availability::{HaCommand, HighAvailability},
+};
+
+#[get("/v1/secrets")]
+pub async fn get(user: User, conn: Data<DatabaseConnection>) -> impl Responder {
+    info!("Reached handler for GET /secrets route");
+    if let Err(response) = access::authorize_server(&user, AccessRole::Admin) {
+        return response;
+    }
+    match secrets::select_all(conn.get_ref()).await {
+        Ok(secrets) => {
+            let response: Vec<SecretResponse> = secrets.into_iter().map(Into::into).collect();
+            HttpResponse::Ok().json(response)
+        }
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+#[post("/v1/secrets")]
+pub async fn post(
+    user: User,
+    config: Data<BldConfig>,
+    ha: Data<HighAvailability>,
+    body: Json<SecretRequest>,
+) -> impl Responder {
+    info!("Reached handler for POST /secrets route");
+    if let Err(response) = access::authorize_server(&user, AccessRole::Admin) {
+        return response;
+    }
+    let result = match encrypt(config.get_ref(), body.into_inner()) {
+        Ok(secret) => ha.apply(HaCommand::UpsertSecret(secret)).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(_) => HttpResponse::Ok().json(""),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+#[delete("/v1/secrets/{name}")]
+pub async fn delete(user: User, ha: Data<HighAvailability>, path: Path<String>) -> impl Responder {
+    info!("Reached handler for DELETE /secrets route");
+    if let Err(response) = access::authorize_server(&user, AccessRole::Admin) {
+        return response;
+    }
+    let command = HaCommand::RemoveSecret {
+        name: path.into_inner(),
+    };
+    match ha.apply(command).await {
+        Ok(_) => HttpResponse::Ok().json(""),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+/// Validates the name of the secret and encrypts its value, which happens before the
+/// secret is replicated so that the raft log never holds it in plain text.
+fn encrypt(config: &BldConfig, body: SecretRequest) -> Result<SecretRequest> {
+    validate_name(&body.name)?;
+    let cipher = SecretsCipher::new(config)?;
+    let value = cipher.encrypt(&body.value)?;
+    Ok(SecretRequest::new(body.name, value))
+}
+
+/// Stores the secret, whose value has already been encrypted.
+pub(crate) async fn do_upsert(conn: &DatabaseConnection, secret: SecretRequest) -> Result<()> {
+    let model = InsertSecret {
+        name: secret.name,
+        value: secret.value,
+    };
+    secrets::upsert(conn, model).await.map(|_| ())
+}
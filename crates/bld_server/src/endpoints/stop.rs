@@ -1,16 +1,23 @@
+use crate::access;
 use crate::extractors::User;
 use crate::supervisor::channel::SupervisorMessageSender;
 use actix_web::web::{Data, Json};
 use actix_web::{HttpResponse, Responder, post};
+use bld_models::dtos::AccessRole;
+use sea_orm::DatabaseConnection;
 use tracing::info;
 
 #[post("/v1/stop")]
 pub async fn post(
-    _: User,
+    user: User,
+    conn: Data<DatabaseConnection>,
     req: Json<String>,
     supervisor_sender: Data<SupervisorMessageSender>,
 ) -> impl Responder {
     info!("Reached handler for /stop route");
+    if let Err(response) = access::authorize_run(&user, &conn, AccessRole::Runner, &req).await {
+        return response;
+    }
     match supervisor_sender.stop(&req).await {
         Ok(_) => HttpResponse::Ok().json(""),
         Err(_) => HttpResponse::BadRequest().body("File not found"),
@@ -0,0 +1,46 @@
+use actix_web::{
+    HttpResponse, Responder, get,
+    web::{Data, Query},
+};
+use anyhow::Result;
+use bld_models::{
+    dtos::{AccessRole, TimelineJobResponse, TimelineQueryParams},
+    pipeline_run_jobs, pipeline_run_steps,
+};
+use sea_orm::DatabaseConnection;
+use tracing::info;
+
+use crate::{access, extractors::User};
+
+#[get("/v1/timeline")]
+pub async fn get(
+    user: User,
+    conn: Data<DatabaseConnection>,
+    params: Query<TimelineQueryParams>,
+) -> impl Responder {
+    info!("Reached handler for GET /timeline route");
+    if let Err(response) =
+        access::authorize_run(&user, &conn, AccessRole::Viewer, &params.run_id).await
+    {
+        return response;
+    }
+    match do_get(conn.get_ref(), &params.run_id).await {
+        Ok(jobs) => HttpResponse::Ok().json(jobs),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+/// Loads the jobs of a run along with their steps, both ordered by the time they started.
+async fn do_get(conn: &DatabaseConnection, run_id: &str) -> Result<Vec<TimelineJobResponse>> {
+    let jobs = pipeline_run_jobs::select_by_run_id(conn, run_id).await?;
+    let job_ids: Vec<String> = jobs.iter().map(|x| x.id.to_owned()).collect();
+    let steps = pipeline_run_steps::select_by_job_ids(conn, &job_ids).await?;
+
+    let mut jobs: Vec<TimelineJobResponse> = jobs.into_iter().map(Into::into).collect();
+    for step in steps {
+        if let Some(job) = jobs.iter_mut().find(|x| x.id == step.job_id) {
+            job.steps.push(step.into());
+        }
+    }
+    Ok(jobs)
+}
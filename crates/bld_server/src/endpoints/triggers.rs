@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+
+use actix_web::{
+    HttpResponse, Responder, delete, get, patch, post,
+    web::{Data, Json, Path},
+};
+use anyhow::Result;
+use bld_config::BldConfig;
+use bld_core::secrets::SecretsCipher;
+use bld_models::{
+    dtos::{AccessRole, AddTriggerRequest, TriggerResponse, UpdateTriggerRequest},
+    pipeline,
+    webhook_trigger_inputs::{self, InsertWebhookTriggerInput},
+    webhook_triggers::{self, InsertWebhookTrigger, UpdateWebhookTrigger},
+};
+use sea_orm::DatabaseConnection;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    access,
+    extractors::User,
+    high_availability::{HaCommand, HighAvailability},
+    webhooks::{validate_name, validate_patterns},
+};
+
+#[get("/v1/triggers")]
+pub async fn get(user: User, conn: Data<DatabaseConnection>) -> impl Responder {
+    info!("Reached handler for GET /triggers route");
+    match do_list(conn.get_ref()).await {
+        Ok(mut triggers) => {
+            triggers.retain(|trigger| user.can_view(&trigger.pipeline));
+            HttpResponse::Ok().json(triggers)
+        }
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+#[post("/v1/triggers")]
+pub async fn post(
+    user: User,
+    config: Data<BldConfig>,
+    ha: Data<HighAvailability>,
+    body: Json<AddTriggerRequest>,
+) -> impl Responder {
+    info!("Reached handler for POST /triggers route");
+    if let Err(response) = access::authorize(&user, AccessRole::Maintainer, &body.pipeline) {
+        return response;
+    }
+    let result = match prepare_add(config.get_ref(), body.into_inner()) {
+        Ok(trigger) => {
+            let id = Uuid::new_v4().to_string();
+            ha.apply(HaCommand::AddTrigger { id, trigger }).await
+        }
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(_) => HttpResponse::Ok().json(""),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+#[patch("/v1/triggers")]
+pub async fn patch(
+    user: User,
+    config: Data<BldConfig>,
+    conn: Data<DatabaseConnection>,
+    ha: Data<HighAvailability>,
+    body: Json<UpdateTriggerRequest>,
+) -> impl Responder {
+    info!("Reached handler for PATCH /triggers route");
+    if let Err(response) =
+        access::authorize_trigger(&user, &conn, AccessRole::Maintainer, &body.name).await
+    {
+        return response;
+    }
+    let result = match prepare_update(config.get_ref(), body.into_inner()) {
+        Ok(trigger) => ha.apply(HaCommand::UpdateTrigger(trigger)).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(_) => HttpResponse::Ok().json(""),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+#[delete("/v1/triggers/{name}")]
+pub async fn delete(
+    user: User,
+    conn: Data<DatabaseConnection>,
+    ha: Data<HighAvailability>,
+    path: Path<String>,
+) -> impl Responder {
+    info!("Reached handler for DELETE /triggers route");
+    let name = path.into_inner();
+    if let Err(response) =
+        access::authorize_trigger(&user, &conn, AccessRole::Maintainer, &name).await
+    {
+        return response;
+    }
+    match ha.apply(HaCommand::RemoveTrigger { name }).await {
+        Ok(_) => HttpResponse::Ok().json(""),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+async fn do_list(conn: &DatabaseConnection) -> Result<Vec<TriggerResponse>> {
+    let mut response = vec![];
+    for trigger in webhook_triggers::select_all(conn).await? {
+        let pipeline = pipeline::select_by_id(conn, &trigger.pipeline_id).await?;
+        let inputs: HashMap<String, String> =
+            webhook_trigger_inputs::select_by_trigger_id(conn, &trigger.id)
+                .await?
+                .into_iter()
+                .map(|input| (input.name, input.path))
+                .collect();
+
+        response.push(TriggerResponse {
+            branches: webhook_triggers::branches(&trigger),
+            paths: webhook_triggers::paths(&trigger),
+            name: trigger.name,
+            pipeline: pipeline.name,
+            inputs,
+            date_created: trigger.date_created.format("%F %X").to_string(),
+            date_updated: trigger.date_updated.map(|x| x.format("%F %X").to_string()),
+        });
+    }
+    Ok(response)
+}
+
+/// Loads every trigger as the request that adds it again, along with its id and its
+/// encrypted secret.
+pub(crate) async fn do_export(
+    conn: &DatabaseConnection,
+) -> Result<Vec<(String, AddTriggerRequest)>> {
+    let mut triggers = vec![];
+    for trigger in webhook_triggers::select_all(conn).await? {
+        let pipeline = pipeline::select_by_id(conn, &trigger.pipeline_id).await?;
+        let inputs: HashMap<String, String> =
+            webhook_trigger_inputs::select_by_trigger_id(conn, &trigger.id)
+                .await?
+                .into_iter()
+                .map(|input| (input.name, input.path))
+                .collect();
+        let request = AddTriggerRequest {
+            branches: webhook_triggers::branches(&trigger),
+            paths: webhook_triggers::paths(&trigger),
+            name: trigger.name,
+            pipeline: pipeline.name,
+            secret: trigger.secret,
+            inputs,
+        };
+        triggers.push((trigger.id, request));
+    }
+    Ok(triggers)
+}
+
+fn inputs_into_models(
+    inputs: &HashMap<String, String>,
+    trigger_id: &str,
+) -> Vec<InsertWebhookTriggerInput> {
+    inputs
+        .iter()
+        .map(|kv| InsertWebhookTriggerInput::new(kv, trigger_id))
+        .collect()
+}
+
+/// Validates the trigger and encrypts its secret, which happens before the trigger is
+/// replicated so that the raft log never holds the secret in plain text.
+fn prepare_add(config: &BldConfig, mut body: AddTriggerRequest) -> Result<AddTriggerRequest> {
+    validate_name(&body.name)?;
+    validate_patterns(&body.branches)?;
+    validate_patterns(&body.paths)?;
+    body.secret = SecretsCipher::new(config)?.encrypt(&body.secret)?;
+    Ok(body)
+}
+
+fn prepare_update(
+    config: &BldConfig,
+    mut body: UpdateTriggerRequest,
+) -> Result<UpdateTriggerRequest> {
+    validate_patterns(&body.branches)?;
+    validate_patterns(&body.paths)?;
+    body.secret = match body.secret.as_deref() {
+        Some(secret) => Some(SecretsCipher::new(config)?.encrypt(secret)?),
+        None => None,
+    };
+    Ok(body)
+}
+
+/// Stores the trigger with the provided id, whose secret has already been encrypted.
+pub(crate) async fn do_add(
+    conn: &DatabaseConnection,
+    id: &str,
+    trigger: &AddTriggerRequest,
+) -> Result<()> {
+    let pipeline = pipeline::select_by_name(conn, &trigger.pipeline).await?;
+    let inputs = inputs_into_models(&trigger.inputs, id);
+    let model = InsertWebhookTrigger {
+        id: id.to_owned(),
+        name: trigger.name.to_owned(),
+        pipeline_id: pipeline.id,
+        secret: trigger.secret.to_owned(),
+        branches: trigger.branches.to_owned(),
+        paths: trigger.paths.to_owned(),
+    };
+    webhook_triggers::insert(conn, &model, &inputs).await
+}
+
+pub(crate) async fn do_update(conn: &DatabaseConnection, body: UpdateTriggerRequest) -> Result<()> {
+    let trigger = webhook_triggers::select_by_name(conn, &body.name).await?;
+    let inputs = inputs_into_models(&body.inputs, &trigger.id);
+    let model = UpdateWebhookTrigger {
+        id: trigger.id,
+        secret: body.secret,
+        branches: body.branches,
+        paths: body.paths,
+    };
+    webhook_triggers::update(conn, &model, &inputs).await
+}
@@ -3,7 +3,7 @@ use anyhow::{Result, anyhow};
 use bld_config::BldConfig;
 use bld_models::{
     dtos::{
-        CompletedPipelinesKpi, PipelinePerCompletedStateKpi, PipelineRunsPerMonthKpi,
+        AccessRole, CompletedPipelinesKpi, PipelinePerCompletedStateKpi, PipelineRunsPerMonthKpi,
         QueuedPipelinesKpi, RunningPipelinesKpi, RunsPerUserKpi,
     },
     pipeline_runs,
@@ -11,6 +11,8 @@ use bld_models::{
 use sea_orm::DatabaseConnection;
 use tracing::info;
 
+use crate::{access, extractors::User};
+
 async fn get_count_of_queued_pipelines(conn: &DatabaseConnection) -> Result<QueuedPipelinesKpi> {
     pipeline_runs::count_queued(conn)
         .await
@@ -18,8 +20,11 @@ async fn get_count_of_queued_pipelines(conn: &DatabaseConnection) -> Result<Queu
 }
 
 #[get("/v1/ui/kpis/queued-pipelines")]
-pub async fn queued_pipelines(conn: Data<DatabaseConnection>) -> impl Responder {
+pub async fn queued_pipelines(user: User, conn: Data<DatabaseConnection>) -> impl Responder {
     info!("Reached handler for /v1/ui/kpis/queued-pipelines route");
+    if let Err(response) = access::authorize_any(&user, AccessRole::Viewer) {
+        return response;
+    }
     match get_count_of_queued_pipelines(&conn).await {
         Ok(kpi) => HttpResponse::Ok().json(kpi),
         Err(e) => {
@@ -47,10 +52,14 @@ async fn get_count_of_running_pipelines(
 
 #[get("/v1/ui/kpis/running-pipelines")]
 pub async fn running_pipelines(
+    user: User,
     config: Data<BldConfig>,
     conn: Data<DatabaseConnection>,
 ) -> impl Responder {
     info!("Reached handler for /v1/ui/kpis/running-pipelines route");
+    if let Err(response) = access::authorize_any(&user, AccessRole::Viewer) {
+        return response;
+    }
     match get_count_of_running_pipelines(&config, &conn).await {
         Ok(kpi) => HttpResponse::Ok().json(kpi),
         Err(e) => {
@@ -82,8 +91,11 @@ async fn get_completed_pipelines(conn: &DatabaseConnection) -> Result<CompletedP
 }
 
 #[get("/v1/ui/kpis/completed-pipelines")]
-pub async fn completed_pipelines(conn: Data<DatabaseConnection>) -> impl Responder {
+pub async fn completed_pipelines(user: User, conn: Data<DatabaseConnection>) -> impl Responder {
     info!("Reached handler for /v1/ui/kpis/completed-pipelines route");
+    if let Err(response) = access::authorize_any(&user, AccessRole::Viewer) {
+        return response;
+    }
     match get_completed_pipelines(&conn).await {
         Ok(kpi) => HttpResponse::Ok().json(kpi),
         Err(e) => {
@@ -105,8 +117,11 @@ async fn get_most_runs_per_user(conn: &DatabaseConnection) -> Result<Vec<RunsPer
 }
 
 #[get("/v1/ui/kpis/most-runs-per-user")]
-pub async fn most_runs_per_user(conn: Data<DatabaseConnection>) -> impl Responder {
+pub async fn most_runs_per_user(user: User, conn: Data<DatabaseConnection>) -> impl Responder {
     info!("Reached handler for /v1/ui/kpis/most-runs-per-user route");
+    if let Err(response) = access::authorize_any(&user, AccessRole::Viewer) {
+        return response;
+    }
     match get_most_runs_per_user(&conn).await {
         Ok(kpi) => HttpResponse::Ok().json(kpi),
         Err(e) => {
@@ -146,8 +161,14 @@ async fn get_pipelines_per_completed_state(
 }
 
 #[get("/v1/ui/kpis/pipelines-per-completed-state")]
-pub async fn pipelines_per_completed_state(conn: Data<DatabaseConnection>) -> impl Responder {
+pub async fn pipelines_per_completed_state(
+    user: User,
+    conn: Data<DatabaseConnection>,
+) -> impl Responder {
     info!("Reached handler for /v1/ui/kpis/pipelines-per-completed-state route");
+    if let Err(response) = access::authorize_any(&user, AccessRole::Viewer) {
+        return response;
+    }
     match get_pipelines_per_completed_state(&conn).await {
         Ok(kpi) => HttpResponse::Ok().json(kpi),
         Err(e) => {
@@ -171,8 +192,11 @@ async fn get_pipeline_runs_per_month(
 }
 
 #[get("/v1/ui/kpis/pipeline-runs-per-month")]
-pub async fn pipeline_runs_per_month(conn: Data<DatabaseConnection>) -> impl Responder {
+pub async fn pipeline_runs_per_month(user: User, conn: Data<DatabaseConnection>) -> impl Responder {
     info!("Reached handler for /v1/ui/kpis/pipeline-runs-per-month route");
+    if let Err(response) = access::authorize_any(&user, AccessRole::Viewer) {
+        return response;
+    }
     match get_pipeline_runs_per_month(&conn).await {
         Ok(kpi) => HttpResponse::Ok().json(kpi),
         Err(e) => {
@@ -1,27 +1,83 @@
+use crate::access::UserAccess;
 use actix_web::dev::Payload;
 use actix_web::error::ErrorUnauthorized;
 use actix_web::http::header::HeaderValue;
 use actix_web::web::Data;
 use actix_web::{Error, FromRequest, HttpRequest};
 use anyhow::{Result, anyhow, bail};
-use bld_config::{Auth, BldConfig, UserInfoProperty};
+use bld_config::{Auth, BldConfig, OpenIdInfo, UserInfoProperty};
+use bld_models::dtos::AccessRole;
 use futures::Future;
 use futures_util::future::FutureExt;
-use openidconnect::AccessToken;
-use openidconnect::core::{CoreClient, CoreUserInfoClaims};
+use openidconnect::core::{CoreClient, CoreGenderClaim};
 use openidconnect::reqwest::async_http_client;
+use openidconnect::{AccessToken, AdditionalClaims, UserInfoClaims};
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
 use std::pin::Pin;
 
-#[derive(Debug)]
+/// The claims of the user info response that aren't standard, among which is the claim
+/// with the groups of the user.
+#[derive(Debug, Serialize, Deserialize)]
+struct UserInfoAdditionalClaims {
+    #[serde(flatten)]
+    claims: HashMap<String, Value>,
+}
+
+impl AdditionalClaims for UserInfoAdditionalClaims {}
+
+type UserInfoResponse = UserInfoClaims<UserInfoAdditionalClaims, CoreGenderClaim>;
+
+#[derive(Debug, Default)]
 pub struct User {
     pub name: String,
+    pub access: UserAccess,
 }
 
 impl User {
     pub fn new(name: &str) -> Self {
         Self {
             name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn authorize(&self, role: AccessRole, pipeline: &str) -> Result<()> {
+        if self.access.role(pipeline).is_some_and(|r| r >= role) {
+            return Ok(());
+        }
+        bail!(
+            "user {} doesn't have the {role} role for pipeline {pipeline}",
+            self.name
+        )
+    }
+
+    /// Checks the role of the user for the whole server, such as for managing its secrets.
+    pub fn authorize_server(&self, role: AccessRole) -> Result<()> {
+        if self.access.role("").is_some_and(|r| r >= role) {
+            return Ok(());
         }
+        bail!(
+            "user {} doesn't have the {role} role for the server",
+            self.name
+        )
+    }
+
+    /// Checks that the user has the role for at least one of the pipelines.
+    pub fn authorize_any(&self, role: AccessRole) -> Result<()> {
+        if self.access.highest_role().is_some_and(|r| r >= role) {
+            return Ok(());
+        }
+        bail!(
+            "user {} doesn't have the {role} role for any pipeline",
+            self.name
+        )
+    }
+
+    pub fn can_view(&self, pipeline: &str) -> bool {
+        self.authorize(AccessRole::Viewer, pipeline).is_ok()
     }
 }
 
@@ -32,13 +88,15 @@ impl FromRequest for User {
     fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
         let config = req.app_data::<Data<BldConfig>>().cloned();
         let client = req.app_data::<Data<Option<CoreClient>>>().cloned();
+        let conn = req.app_data::<Data<DatabaseConnection>>().cloned();
         let access_token = get_access_token(req);
 
         async move {
             let config = config.unwrap();
             let client = client.unwrap();
+            let conn = conn.unwrap();
             if let Some(Auth::OpenId(openid)) = &config.get_ref().local.server.auth {
-                return openid_validate(client.as_ref(), access_token, &openid.user_property)
+                return openid_validate(client.as_ref(), conn.get_ref(), access_token, openid)
                     .await
                     .map_err(|e| ErrorUnauthorized(e.to_string()));
             }
@@ -74,27 +132,44 @@ fn get_access_token(request: &HttpRequest) -> AccessToken {
     AccessToken::new(bearer)
 }
 
+/// Reads the groups of the user from the claim, which can either be a list of groups or
+/// a single one.
+fn groups(res: &UserInfoResponse, claim: &str) -> Vec<String> {
+    match res.additional_claims().claims.get(claim) {
+        Some(Value::Array(groups)) => groups
+            .iter()
+            .filter_map(|g| g.as_str().map(|g| g.to_owned()))
+            .collect(),
+        Some(Value::String(group)) => vec![group.to_owned()],
+        _ => vec![],
+    }
+}
+
 async fn openid_validate(
     client: &Option<CoreClient>,
+    conn: &DatabaseConnection,
     access_token: AccessToken,
-    user_prop: &UserInfoProperty,
+    openid: &OpenIdInfo,
 ) -> Result<User> {
     let Some(client) = client else {
         bail!("openid core client not registered");
     };
 
-    let res: CoreUserInfoClaims = client
+    let res: UserInfoResponse = client
         .user_info(access_token, None)?
         .request_async(async_http_client)
         .await?;
 
-    let user = match user_prop {
+    let user = match openid.user_property {
         UserInfoProperty::Name => res.name().and_then(|x| x.get(None).map(|n| n.as_str())),
         UserInfoProperty::Email => res.email().map(|e| e.as_str()),
     };
 
-    let user = user
-        .ok_or_else(|| anyhow!("couldn't retrieve the user property for the user info response"))?;
+    let name = user
+        .ok_or_else(|| anyhow!("couldn't retrieve the user property for the user info response"))?
+        .to_owned();
+    let groups = groups(&res, &openid.groups_claim);
+    let access = UserAccess::load(conn, openid, &name, &groups).await?;
 
-    Ok(User::new(user))
+    Ok(User { name, access })
 }
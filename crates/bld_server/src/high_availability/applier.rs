@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{error, info};
+
+use super::{LogEntry, RaftStateMachine, RaftStorage, Snapshot};
+
+/// A committed entry of the raft log along with the sender of the write that appended
+/// it, if the write was made to this member.
+pub struct CommittedEntry {
+    pub entry: LogEntry,
+    pub resp_tx: Option<oneshot::Sender<Result<()>>>,
+}
+
+pub enum ApplierMessage {
+    Apply(Box<CommittedEntry>),
+    /// Replaces the state of the server with the one of a snapshot sent by the leader.
+    Restore(Arc<Snapshot>),
+}
+
+/// Applies the committed entries of the raft log to the state machine in the order that
+/// they were committed. It runs apart from the node of the member, so that commands that
+/// take long to apply, like a push that fetches the dependencies of a pipeline, don't
+/// delay the heartbeats and the votes of the raft group. Every time the threshold of
+/// entries is applied, it takes a snapshot of the state and sends it to the node so that
+/// it can remove the entries up to it from the log.
+pub struct RaftApplier<S: RaftStateMachine> {
+    storage: RaftStorage,
+    state_machine: Arc<S>,
+    snapshot_threshold: i32,
+    snapshot_index: i32,
+    snapshots: mpsc::UnboundedSender<Snapshot>,
+    rx: mpsc::UnboundedReceiver<ApplierMessage>,
+}
+
+impl<S: RaftStateMachine> RaftApplier<S> {
+    pub fn new(
+        storage: RaftStorage,
+        state_machine: Arc<S>,
+        snapshot_threshold: i32,
+        snapshot_index: i32,
+        snapshots: mpsc::UnboundedSender<Snapshot>,
+        rx: mpsc::UnboundedReceiver<ApplierMessage>,
+    ) -> Self {
+        Self {
+            storage,
+            state_machine,
+            snapshot_threshold,
+            snapshot_index,
+            snapshots,
+            rx,
+        }
+    }
+
+    pub async fn run(mut self) {
+        while let Some(message) = self.rx.recv().await {
+            let result = match message {
+                ApplierMessage::Apply(committed) => self.apply(*committed).await,
+                ApplierMessage::Restore(snapshot) => self.restore(&snapshot).await,
+            };
+            if let Err(e) = result {
+                error!("{e}");
+            }
+        }
+    }
+
+    async fn apply(&mut self, committed: CommittedEntry) -> Result<()> {
+        let CommittedEntry { entry, resp_tx } = committed;
+        let index = entry.index;
+        let result = match entry.command {
+            Some(command) => self.state_machine.apply(command).await,
+            None => Ok(()),
+        };
+        if let Err(e) = &result {
+            error!("unable to apply entry {index} of the raft log, {e}");
+        }
+        if let Some(resp_tx) = resp_tx {
+            let _ = resp_tx.send(result);
+        }
+        self.storage.save_last_applied(index).await?;
+
+        if index - self.snapshot_index >= self.snapshot_threshold {
+            let data = self.state_machine.snapshot().await?;
+            self.snapshot_index = index;
+            let snapshot = Snapshot {
+                index,
+                term: entry.term,
+                data,
+            };
+            let _ = self.snapshots.send(snapshot);
+        }
+        Ok(())
+    }
+
+    async fn restore(&mut self, snapshot: &Snapshot) -> Result<()> {
+        info!(
+            "restoring the snapshot of the raft log up to entry {}",
+            snapshot.index
+        );
+        self.state_machine.restore(&snapshot.data).await?;
+        self.storage.save_last_applied(snapshot.index).await?;
+        self.snapshot_index = snapshot.index;
+        Ok(())
+    }
+}
@@ -0,0 +1,428 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use bld_core::fs::FileSystem;
+use bld_models::{
+    access_grants::{self, InsertAccessGrant},
+    completion_triggers,
+    dtos::{
+        AccessGrantResponse, AddAccessGrantRequest, AddJobRequest, AddTriggerRequest,
+        CronJobResponse, JobFiltersParams, PipelinePathRequest, PushInfo, SecretRequest,
+        UpdateJobRequest, UpdateTriggerRequest,
+    },
+    pipeline, pipeline_run_environment_variables, pipeline_run_inputs,
+    pipeline_runs::{
+        self, InsertPipelineRun, PR_STATE_INITIAL, PR_STATE_QUEUED, PR_STATE_RUNNING,
+        PR_STATE_WAITING_APPROVAL, RunTrigger,
+    },
+    secrets, webhook_triggers,
+};
+use bld_pkg::PackageManager;
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use uuid::Uuid;
+
+use crate::{
+    cron::CronScheduler,
+    endpoints::{push, remove, secrets as secrets_endpoint, triggers},
+};
+
+use super::RaftStatus;
+
+/// The states of the runs that haven't completed yet.
+pub const OPEN_RUN_STATES: [&str; 4] = [
+    PR_STATE_INITIAL,
+    PR_STATE_QUEUED,
+    PR_STATE_RUNNING,
+    PR_STATE_WAITING_APPROVAL,
+];
+
+/// A change to the pipelines, the cron jobs, the access grants, the secrets, the triggers
+/// or the runs of the server. The ids of the cron jobs, the grants, the triggers and the
+/// runs are generated before the change is replicated so that every member uses the same.
+/// The secrets and the secrets of the webhook triggers are encrypted before they are
+/// replicated, so every member of a raft group must have the same secrets key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum HaCommand {
+    Push {
+        info: PushInfo,
+        cron_job_id: String,
+        completion_trigger_id: String,
+    },
+    Remove {
+        pipeline: String,
+    },
+    Move(PipelinePathRequest),
+    Copy(PipelinePathRequest),
+    AddCronJob {
+        id: String,
+        job: AddJobRequest,
+    },
+    UpdateCronJob(UpdateJobRequest),
+    RemoveCronJob {
+        id: String,
+    },
+    AddAccessGrant {
+        id: String,
+        grant: AddAccessGrantRequest,
+    },
+    RemoveAccessGrant {
+        id: String,
+    },
+    UpsertSecret(SecretRequest),
+    RemoveSecret {
+        name: String,
+    },
+    AddTrigger {
+        id: String,
+        trigger: AddTriggerRequest,
+    },
+    UpdateTrigger(UpdateTriggerRequest),
+    RemoveTrigger {
+        name: String,
+    },
+    /// A run that has been created by the leader, which only the leader sends to its
+    /// supervisor.
+    EnqueueRun {
+        run: InsertPipelineRun,
+    },
+    /// The state of a run on the leader, which the leader itself doesn't apply since its
+    /// workers may have already moved the run on to another state.
+    UpdateRunState {
+        run_id: String,
+        state: String,
+    },
+}
+
+/// Applies the commands that have been committed to the raft log, and takes and restores
+/// snapshots of the state that they have produced so that the log can be compacted.
+pub trait RaftStateMachine: 'static {
+    fn apply(&self, command: HaCommand) -> impl Future<Output = Result<()>>;
+
+    fn snapshot(&self) -> impl Future<Output = Result<String>>;
+
+    fn restore(&self, data: &str) -> impl Future<Output = Result<()>>;
+}
+
+/// The state of the server that is replicated by the raft log.
+#[derive(Serialize, Deserialize)]
+struct StateSnapshot {
+    pipelines: Vec<PipelineSnapshot>,
+    cron_jobs: Vec<CronJobResponse>,
+    access_grants: Vec<AccessGrantResponse>,
+    secrets: Vec<SecretRequest>,
+    triggers: Vec<TriggerSnapshot>,
+    runs: Vec<RunSnapshot>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PipelineSnapshot {
+    info: PushInfo,
+    completion_trigger_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TriggerSnapshot {
+    id: String,
+    trigger: AddTriggerRequest,
+}
+
+/// A run that hasn't completed yet along with its state.
+#[derive(Serialize, Deserialize)]
+struct RunSnapshot {
+    run: InsertPipelineRun,
+    state: String,
+}
+
+pub struct StateMachine {
+    fs: Arc<FileSystem>,
+    conn: Arc<DatabaseConnection>,
+    package_manager: Arc<PackageManager>,
+    cron: Arc<CronScheduler>,
+    status: watch::Receiver<RaftStatus>,
+}
+
+impl StateMachine {
+    pub fn new(
+        fs: Arc<FileSystem>,
+        conn: Arc<DatabaseConnection>,
+        package_manager: Arc<PackageManager>,
+        cron: Arc<CronScheduler>,
+        status: watch::Receiver<RaftStatus>,
+    ) -> Self {
+        Self {
+            fs,
+            conn,
+            package_manager,
+            cron,
+            status,
+        }
+    }
+
+    /// Records a run created by the leader, unless the member already has it.
+    async fn insert_run(&self, run: InsertPipelineRun) -> Result<()> {
+        let conn = self.conn.as_ref();
+        if pipeline_runs::select_by_id(conn, &run.id).await.is_ok() {
+            return Ok(());
+        }
+        pipeline_runs::insert(conn, run).await
+    }
+
+    async fn update_run_state(&self, run_id: &str, state: &str) -> Result<()> {
+        if self.status.borrow().is_leader() {
+            return Ok(());
+        }
+        let conn = self.conn.as_ref();
+        let run = pipeline_runs::select_by_id(conn, run_id).await?;
+        if run.state == state {
+            return Ok(());
+        }
+        pipeline_runs::update_replicated_state(conn, run_id, state).await
+    }
+
+    async fn snapshot_runs(&self) -> Result<Vec<RunSnapshot>> {
+        let conn = self.conn.as_ref();
+        let mut runs = vec![];
+        for run in pipeline_runs::select_by_states(conn, &OPEN_RUN_STATES).await? {
+            let inputs = pipeline_run_inputs::select_by_run_id(conn, &run.id)
+                .await?
+                .into_iter()
+                .map(|x| (x.name, x.value))
+                .collect();
+            let env = pipeline_run_environment_variables::select_by_run_id(conn, &run.id)
+                .await?
+                .into_iter()
+                .map(|x| (x.name, x.value))
+                .collect();
+            let trigger = RunTrigger {
+                trigger_type: run.trigger_type.unwrap_or_default(),
+                trigger_id: run.trigger_id,
+            };
+            let insert = InsertPipelineRun {
+                id: run.id,
+                name: run.name,
+                app_user: run.app_user,
+                trigger,
+                inputs,
+                env,
+            };
+            runs.push(RunSnapshot {
+                run: insert,
+                state: run.state,
+            });
+        }
+        Ok(runs)
+    }
+
+    /// Records the open runs of the snapshot, while the runs that are still open on the
+    /// member but not in the snapshot have completed on the leader in the entries that
+    /// the member missed, so their final state is unknown and they are set as faulted.
+    async fn restore_runs(&self, runs: Vec<RunSnapshot>) -> Result<()> {
+        let conn = self.conn.as_ref();
+        for run in pipeline_runs::select_by_states(conn, &OPEN_RUN_STATES).await? {
+            if !runs.iter().any(|r| r.run.id == run.id) {
+                let reason = "the run completed while the member was behind the raft group";
+                pipeline_runs::update_as_faulted(conn, &run.id, reason).await?;
+            }
+        }
+        for RunSnapshot { run, state } in runs {
+            let run_id = run.id.to_owned();
+            self.insert_run(run).await?;
+            pipeline_runs::update_replicated_state(conn, &run_id, &state).await?;
+        }
+        Ok(())
+    }
+}
+
+impl RaftStateMachine for StateMachine {
+    async fn apply(&self, command: HaCommand) -> Result<()> {
+        match command {
+            HaCommand::Push {
+                info,
+                cron_job_id,
+                completion_trigger_id,
+            } => {
+                let fs = self.fs.as_ref();
+                let package_manager = self.package_manager.as_ref();
+                let cron = self.cron.as_ref();
+                let conn = self.conn.as_ref();
+                push::do_push(
+                    fs,
+                    package_manager,
+                    cron,
+                    conn,
+                    &info,
+                    &cron_job_id,
+                    &completion_trigger_id,
+                )
+                .await
+            }
+            HaCommand::Remove { pipeline } => {
+                remove::do_remove(&self.fs, &self.cron, &pipeline).await
+            }
+            HaCommand::Move(request) => self.fs.mv(&request.pipeline, &request.target).await,
+            HaCommand::Copy(request) => self.fs.copy(&request.pipeline, &request.target).await,
+            HaCommand::AddCronJob { id, job } => self.cron.add(&id, &job).await,
+            HaCommand::UpdateCronJob(job) => self.cron.update(&job).await,
+            HaCommand::RemoveCronJob { id } => self.cron.remove(&id).await,
+            HaCommand::AddAccessGrant { id, grant } => {
+                let model = InsertAccessGrant {
+                    id,
+                    role: grant.role.to_string(),
+                    subject_type: grant.subject_type.to_string(),
+                    subject: grant.subject,
+                    pipeline: grant.pipeline,
+                };
+                access_grants::insert(self.conn.as_ref(), model).await
+            }
+            HaCommand::RemoveAccessGrant { id } => {
+                access_grants::delete_by_id(self.conn.as_ref(), &id).await
+            }
+            HaCommand::UpsertSecret(secret) => {
+                secrets_endpoint::do_upsert(self.conn.as_ref(), secret).await
+            }
+            HaCommand::RemoveSecret { name } => {
+                secrets::delete_by_name(self.conn.as_ref(), &name).await
+            }
+            HaCommand::AddTrigger { id, trigger } => {
+                triggers::do_add(self.conn.as_ref(), &id, &trigger).await
+            }
+            HaCommand::UpdateTrigger(trigger) => {
+                triggers::do_update(self.conn.as_ref(), trigger).await
+            }
+            HaCommand::RemoveTrigger { name } => {
+                webhook_triggers::delete_by_name(self.conn.as_ref(), &name).await
+            }
+            HaCommand::EnqueueRun { run } => self.insert_run(run).await,
+            HaCommand::UpdateRunState { run_id, state } => {
+                self.update_run_state(&run_id, &state).await
+            }
+        }
+    }
+
+    async fn snapshot(&self) -> Result<String> {
+        let conn = self.conn.as_ref();
+        let mut pipelines = vec![];
+        for name in self.fs.list().await? {
+            let content = self.fs.read(&name).await?;
+            let completion_trigger_id = match pipeline::select_by_name(conn, &name).await {
+                Ok(pipeline) => completion_triggers::select_by_pipeline(conn, &pipeline.id)
+                    .await?
+                    .map(|trigger| trigger.id),
+                Err(_) => None,
+            };
+            pipelines.push(PipelineSnapshot {
+                info: PushInfo { name, content },
+                completion_trigger_id,
+            });
+        }
+        let cron_jobs = self.cron.get(&JobFiltersParams::default()).await?;
+        let access_grants = access_grants::select_all(conn)
+            .await?
+            .into_iter()
+            .map(AccessGrantResponse::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        let secrets = secrets::select_all(conn)
+            .await?
+            .into_iter()
+            .map(|secret| SecretRequest::new(secret.name, secret.value))
+            .collect();
+        let triggers = triggers::do_export(conn)
+            .await?
+            .into_iter()
+            .map(|(id, trigger)| TriggerSnapshot { id, trigger })
+            .collect();
+        let runs = self.snapshot_runs().await?;
+
+        let snapshot = StateSnapshot {
+            pipelines,
+            cron_jobs,
+            access_grants,
+            secrets,
+            triggers,
+            runs,
+        };
+        Ok(serde_json::to_string(&snapshot)?)
+    }
+
+    /// Replaces the pipelines, the cron jobs, the access grants, the secrets and the
+    /// triggers of the server with the ones of the snapshot and records its open runs.
+    async fn restore(&self, data: &str) -> Result<()> {
+        let snapshot: StateSnapshot = serde_json::from_str(data)?;
+        let fs = self.fs.as_ref();
+        let package_manager = self.package_manager.as_ref();
+        let cron = self.cron.as_ref();
+        let conn = self.conn.as_ref();
+
+        for name in fs.list().await? {
+            if !snapshot.pipelines.iter().any(|p| p.info.name == name) {
+                remove::do_remove(fs, cron, &name).await?;
+            }
+        }
+        for pipeline in &snapshot.pipelines {
+            let cron_job_id = Uuid::new_v4().to_string();
+            let completion_trigger_id = pipeline
+                .completion_trigger_id
+                .to_owned()
+                .unwrap_or_else(|| Uuid::new_v4().to_string());
+            push::do_push(
+                fs,
+                package_manager,
+                cron,
+                conn,
+                &pipeline.info,
+                &cron_job_id,
+                &completion_trigger_id,
+            )
+            .await?;
+        }
+
+        // the pushes add the default cron jobs of the pipelines with new ids, so every job
+        // is replaced by the ones of the snapshot.
+        for job in cron.get(&JobFiltersParams::default()).await? {
+            cron.remove(&job.id).await?;
+        }
+        for job in snapshot.cron_jobs {
+            let add_job = AddJobRequest::new(
+                job.schedule,
+                job.pipeline,
+                job.inputs,
+                job.env,
+                job.is_default,
+                job.priority,
+            );
+            cron.add(&job.id, &add_job).await?;
+        }
+
+        for grant in access_grants::select_all(conn).await? {
+            access_grants::delete_by_id(conn, &grant.id).await?;
+        }
+        for grant in snapshot.access_grants {
+            let model = InsertAccessGrant {
+                id: grant.id,
+                role: grant.role.to_string(),
+                subject_type: grant.subject_type.to_string(),
+                subject: grant.subject,
+                pipeline: grant.pipeline,
+            };
+            access_grants::insert(conn, model).await?;
+        }
+
+        for secret in secrets::select_all(conn).await? {
+            secrets::delete_by_name(conn, &secret.name).await?;
+        }
+        for secret in snapshot.secrets {
+            secrets_endpoint::do_upsert(conn, secret).await?;
+        }
+
+        for trigger in webhook_triggers::select_all(conn).await? {
+            webhook_triggers::delete_by_name(conn, &trigger.name).await?;
+        }
+        for TriggerSnapshot { id, trigger } in snapshot.triggers {
+            triggers::do_add(conn, &id, &trigger).await?;
+        }
+
+        self.restore_runs(snapshot.runs).await
+    }
+}
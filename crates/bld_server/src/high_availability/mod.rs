@@ -0,0 +1,323 @@
+mod applier;
+mod command;
+mod network;
+mod node;
+mod relay;
+mod rpc;
+mod storage;
+
+pub use applier::*;
+pub use command::*;
+pub use network::*;
+pub use node::*;
+pub use relay::*;
+pub use rpc::*;
+pub use storage::*;
+
+use std::{sync::Arc, time::Duration};
+
+use actix_web::{
+    Error, HttpRequest, HttpResponse,
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::LOCATION,
+    middleware::Next,
+    web::Data,
+};
+use anyhow::{Result, bail};
+use bld_config::{BldConfig, BldHaMemberConfig};
+use bld_utils::crypto::constant_time_eq;
+use sea_orm::DatabaseConnection;
+use tokio::sync::watch;
+
+/// How the server applies the changes to its replicated state. A standalone
+/// server applies them directly while a member of a raft group writes them to the
+/// log of the group, which can only be done by its leader.
+pub enum HighAvailability {
+    Standalone(Arc<StateMachine>),
+    Raft {
+        node: RaftSender,
+        members: Vec<BldHaMemberConfig>,
+        secret: String,
+    },
+}
+
+/// Creates the channel with the status of the server in its raft group. A standalone
+/// server is always the leader.
+pub fn status_channel(
+    config: &BldConfig,
+) -> (watch::Sender<RaftStatus>, watch::Receiver<RaftStatus>) {
+    let status = match &config.local.server.ha {
+        Some(_) => RaftStatus::default(),
+        None => RaftStatus::standalone(),
+    };
+    watch::channel(status)
+}
+
+impl HighAvailability {
+    pub async fn new(
+        config: &BldConfig,
+        conn: Arc<DatabaseConnection>,
+        state_machine: Arc<StateMachine>,
+        status: watch::Sender<RaftStatus>,
+    ) -> Result<Self> {
+        let Some(ha) = &config.local.server.ha else {
+            return Ok(Self::Standalone(state_machine));
+        };
+
+        if ha.secret.is_empty() {
+            bail!("the secret of the raft group can't be empty");
+        }
+        if ha.snapshot_threshold < 1 {
+            bail!("the snapshot threshold of the raft group must be greater than zero");
+        }
+
+        let peers = ha.members.iter().map(|m| m.id).collect();
+        let options = RaftOptions {
+            election_timeout: Duration::from_millis(ha.election_timeout),
+            heartbeat_interval: Duration::from_millis(ha.heartbeat_interval),
+            snapshot_threshold: ha.snapshot_threshold,
+        };
+        let storage = RaftStorage::new(conn);
+        let network = Arc::new(HttpRaftNetwork::new(ha.members.clone(), ha.secret.clone()));
+        let node = RaftSender::spawn(
+            ha.id,
+            peers,
+            options,
+            storage,
+            network,
+            state_machine,
+            status,
+        )
+        .await?;
+
+        Ok(Self::Raft {
+            node,
+            members: ha.members.clone(),
+            secret: ha.secret.clone(),
+        })
+    }
+
+    /// Applies the command once it's committed to the log of the raft group.
+    pub async fn apply(&self, command: HaCommand) -> Result<()> {
+        match self {
+            Self::Standalone(state_machine) => state_machine.apply(command).await,
+            Self::Raft { node, .. } => node.write(command).await,
+        }
+    }
+
+    /// Whether the server is the leader of its raft group, which a standalone server
+    /// always is.
+    pub fn is_leader(&self) -> bool {
+        match self {
+            Self::Standalone(_) => true,
+            Self::Raft { node, .. } => node.status().is_leader(),
+        }
+    }
+
+    /// Checks that a request of another member carries the secret of the raft group, which
+    /// is compared in constant time.
+    pub fn verify_secret(&self, secret: Option<&str>) -> Result<()> {
+        let Self::Raft {
+            secret: expected, ..
+        } = self
+        else {
+            bail!("the server is not a member of a raft group");
+        };
+        if !secret.is_some_and(|secret| constant_time_eq(expected, secret)) {
+            bail!("invalid secret for the raft group");
+        }
+        Ok(())
+    }
+
+    fn node(&self) -> Result<&RaftSender> {
+        let Self::Raft { node, .. } = self else {
+            bail!("the server is not a member of a raft group");
+        };
+        Ok(node)
+    }
+
+    pub async fn append_entries(
+        &self,
+        request: AppendEntriesRequest,
+    ) -> Result<AppendEntriesResponse> {
+        self.node()?.append_entries(request).await
+    }
+
+    pub async fn request_vote(&self, request: VoteRequest) -> Result<VoteResponse> {
+        self.node()?.request_vote(request).await
+    }
+
+    pub async fn install_snapshot(
+        &self,
+        request: InstallSnapshotRequest,
+    ) -> Result<InstallSnapshotResponse> {
+        self.node()?.install_snapshot(request).await
+    }
+
+    /// The response that sends a request for the api to the leader of the raft group when
+    /// the server isn't the leader, or that the api is unavailable during an election.
+    pub fn redirect(&self, req: &HttpRequest) -> Option<HttpResponse> {
+        let Self::Raft { node, members, .. } = self else {
+            return None;
+        };
+
+        let path = req.path();
+        if !path.starts_with("/v1/") || path.starts_with("/v1/ha/") {
+            return None;
+        }
+
+        let status = node.status();
+        if status.is_leader() {
+            return None;
+        }
+
+        let leader = status
+            .leader
+            .and_then(|id| members.iter().find(|m| m.id == id));
+        let response = match leader {
+            Some(leader) => {
+                let path_and_query = req
+                    .uri()
+                    .path_and_query()
+                    .map(|p| p.as_str())
+                    .unwrap_or(path);
+                let location = format!("{}{path_and_query}", leader.base_url_http());
+                HttpResponse::TemporaryRedirect()
+                    .insert_header((LOCATION, location))
+                    .finish()
+            }
+            None => HttpResponse::ServiceUnavailable()
+                .body("the raft group is electing a leader, try again later"),
+        };
+        Some(response)
+    }
+}
+
+/// Middleware that redirects the requests of a member of a raft group to its leader.
+pub async fn follow_leader(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let redirect = req
+        .app_data::<Data<HighAvailability>>()
+        .and_then(|ha| ha.redirect(req.request()));
+
+    match redirect {
+        Some(response) => Ok(req.into_response(response).map_into_right_body()),
+        None => next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_left_body),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        App, HttpResponse,
+        http::{StatusCode, header::LOCATION},
+        middleware::from_fn,
+        test::{TestRequest, call_service, init_service},
+        web::{Data, get},
+    };
+    use bld_config::BldHaMemberConfig;
+    use tokio::sync::watch;
+
+    use super::{HighAvailability, RaftRole, RaftSender, RaftStatus, follow_leader};
+
+    fn member(status: watch::Receiver<RaftStatus>) -> HighAvailability {
+        let members = [1, 2, 3]
+            .into_iter()
+            .map(|id| BldHaMemberConfig {
+                id,
+                host: format!("member-{id}"),
+                port: 6080,
+                tls: false,
+            })
+            .collect();
+        HighAvailability::Raft {
+            node: RaftSender::detached(status),
+            members,
+            secret: "secret".to_owned(),
+        }
+    }
+
+    fn status(role: RaftRole, leader: Option<i32>) -> RaftStatus {
+        RaftStatus {
+            role,
+            term: 1,
+            leader,
+        }
+    }
+
+    #[test]
+    fn redirect_sends_the_requests_of_a_follower_to_the_leader() {
+        let (status_tx, status_rx) = watch::channel(status(RaftRole::Follower, Some(2)));
+        let ha = member(status_rx);
+
+        let req = TestRequest::get()
+            .uri("/v1/hist?state=running&limit=10")
+            .to_http_request();
+        let response = ha.redirect(&req).unwrap();
+        assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(
+            response.headers().get(LOCATION).unwrap(),
+            "http://member-2:6080/v1/hist?state=running&limit=10"
+        );
+
+        let req = TestRequest::post()
+            .uri("/v1/ha/append-entries")
+            .to_http_request();
+        assert!(ha.redirect(&req).is_none());
+        let req = TestRequest::get().uri("/").to_http_request();
+        assert!(ha.redirect(&req).is_none());
+
+        status_tx.send_replace(status(RaftRole::Leader, Some(1)));
+        let req = TestRequest::get().uri("/v1/hist").to_http_request();
+        assert!(ha.redirect(&req).is_none());
+    }
+
+    #[actix_web::test]
+    async fn follow_leader_redirects_until_the_member_becomes_the_leader() {
+        let (status_tx, status_rx) = watch::channel(status(RaftRole::Follower, Some(3)));
+        let app = App::new()
+            .app_data(Data::new(member(status_rx)))
+            .wrap(from_fn(follow_leader))
+            .route("/v1/hist", get().to(HttpResponse::Ok));
+        let app = init_service(app).await;
+
+        let req = TestRequest::get().uri("/v1/hist").to_request();
+        let response = call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(
+            response.headers().get(LOCATION).unwrap(),
+            "http://member-3:6080/v1/hist"
+        );
+
+        status_tx.send_replace(status(RaftRole::Leader, Some(1)));
+        let req = TestRequest::get().uri("/v1/hist").to_request();
+        let response = call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn redirect_is_unavailable_while_the_group_has_no_leader() {
+        let (_status_tx, status_rx) = watch::channel(status(RaftRole::Candidate, None));
+        let ha = member(status_rx);
+
+        let req = TestRequest::get().uri("/v1/hist").to_http_request();
+        let response = ha.redirect(&req).unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[test]
+    fn verify_secret_rejects_requests_without_the_secret_of_the_group() {
+        let (_status_tx, status_rx) = watch::channel(RaftStatus::default());
+        let ha = member(status_rx);
+        assert!(ha.verify_secret(Some("secret")).is_ok());
+        assert!(ha.verify_secret(Some("other")).is_err());
+        assert!(ha.verify_secret(Some("")).is_err());
+        assert!(ha.verify_secret(None).is_err());
+    }
+}
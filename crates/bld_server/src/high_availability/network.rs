@@ -0,0 +1,81 @@
+use anyhow::{Result, anyhow};
+use bld_config::BldHaMemberConfig;
+use bld_http::Request;
+
+use super::{
+    AppendEntriesRequest, AppendEntriesResponse, InstallSnapshotRequest, InstallSnapshotResponse,
+    VoteRequest, VoteResponse,
+};
+
+/// The header with the secret that the members of a raft group share.
+pub const HA_SECRET_HEADER: &str = "X-Bld-Ha-Secret";
+
+/// Sends the requests of a member of a raft group to the rest of the members.
+pub trait RaftNetwork: 'static {
+    fn append_entries(
+        &self,
+        target: i32,
+        request: AppendEntriesRequest,
+    ) -> impl Future<Output = Result<AppendEntriesResponse>>;
+
+    fn request_vote(
+        &self,
+        target: i32,
+        request: VoteRequest,
+    ) -> impl Future<Output = Result<VoteResponse>>;
+
+    fn install_snapshot(
+        &self,
+        target: i32,
+        request: InstallSnapshotRequest,
+    ) -> impl Future<Output = Result<InstallSnapshotResponse>>;
+}
+
+pub struct HttpRaftNetwork {
+    members: Vec<BldHaMemberConfig>,
+    secret: String,
+}
+
+impl HttpRaftNetwork {
+    pub fn new(members: Vec<BldHaMemberConfig>, secret: String) -> Self {
+        Self { members, secret }
+    }
+
+    fn request(&self, target: i32, route: &str) -> Result<Request> {
+        let member = self
+            .members
+            .iter()
+            .find(|m| m.id == target)
+            .ok_or_else(|| anyhow!("member {target} of the raft group not found in config"))?;
+        let url = format!("{}/v1/ha/{route}", member.base_url_http());
+        Ok(Request::post(&url).header(HA_SECRET_HEADER, &self.secret))
+    }
+}
+
+impl RaftNetwork for HttpRaftNetwork {
+    async fn append_entries(
+        &self,
+        target: i32,
+        request: AppendEntriesRequest,
+    ) -> Result<AppendEntriesResponse> {
+        self.request(target, "append-entries")?
+            .json_with_data(&request)
+            .await
+    }
+
+    async fn request_vote(&self, target: i32, request: VoteRequest) -> Result<VoteResponse> {
+        self.request(target, "request-vote")?
+            .json_with_data(&request)
+            .await
+    }
+
+    async fn install_snapshot(
+        &self,
+        target: i32,
+        request: InstallSnapshotRequest,
+    ) -> Result<InstallSnapshotResponse> {
+        self.request(target, "install-snapshot")?
+            .json_with_data(&request)
+            .await
+    }
+}
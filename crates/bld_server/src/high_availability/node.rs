@@ -0,0 +1,1199 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use actix_web::rt::spawn;
+use anyhow::{Result, anyhow};
+use tokio::{
+    sync::{mpsc, oneshot, watch},
+    time::{Instant, interval},
+};
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use super::{
+    AppendEntriesRequest, AppendEntriesResponse, ApplierMessage, CommittedEntry, HaCommand,
+    InstallSnapshotRequest, InstallSnapshotResponse, LogEntry, RaftApplier, RaftNetwork,
+    RaftStateMachine, RaftStorage, Snapshot, VoteRequest, VoteResponse,
+};
+
+/// The most entries that the leader sends to a member with a single request.
+const MAX_ENTRIES_PER_REQUEST: usize = 64;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RaftRole {
+    #[default]
+    Follower,
+    Candidate,
+    Leader,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RaftStatus {
+    pub role: RaftRole,
+    pub term: i32,
+    pub leader: Option<i32>,
+}
+
+impl RaftStatus {
+    /// The status of a server that isn't a member of a raft group, which always leads.
+    pub fn standalone() -> Self {
+        Self {
+            role: RaftRole::Leader,
+            term: 0,
+            leader: None,
+        }
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.role == RaftRole::Leader
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RaftOptions {
+    pub election_timeout: Duration,
+    pub heartbeat_interval: Duration,
+    /// The entries applied since the last snapshot after which a new one is taken.
+    pub snapshot_threshold: i32,
+}
+
+enum RaftMessage {
+    AppendEntries {
+        request: AppendEntriesRequest,
+        resp_tx: oneshot::Sender<Result<AppendEntriesResponse>>,
+    },
+    RequestVote {
+        request: VoteRequest,
+        resp_tx: oneshot::Sender<Result<VoteResponse>>,
+    },
+    InstallSnapshot {
+        request: InstallSnapshotRequest,
+        resp_tx: oneshot::Sender<Result<InstallSnapshotResponse>>,
+    },
+    Write {
+        command: HaCommand,
+        resp_tx: oneshot::Sender<Result<()>>,
+    },
+    Replicated {
+        peer: i32,
+        term: i32,
+        last_index: i32,
+        response: AppendEntriesResponse,
+    },
+    Voted {
+        peer: i32,
+        term: i32,
+        response: VoteResponse,
+    },
+    Installed {
+        peer: i32,
+        term: i32,
+        index: i32,
+        response: Result<InstallSnapshotResponse>,
+    },
+}
+
+/// A member of a raft group. The members elect a leader that appends the commands
+/// written to it to its log and replicates them to the rest of the members, and once
+/// a majority of them has an entry it's committed and handed to the applier of every
+/// member, which applies it to the state machine in the background. A leader
+/// that hasn't heard from a majority of the members within the election timeout steps
+/// down, so that a partitioned leader stops accepting writes. The responses of the
+/// requests to the members are sent back to the node as messages so that it never waits
+/// on the network.
+///
+/// The log only holds the entries after the latest snapshot of the applier, and a member
+/// that is missing entries that have been compacted is sent the snapshot instead.
+struct RaftNode<N: RaftNetwork> {
+    id: i32,
+    peers: Vec<i32>,
+    options: RaftOptions,
+    storage: RaftStorage,
+    network: Arc<N>,
+    applier: mpsc::UnboundedSender<ApplierMessage>,
+    snapshots: mpsc::UnboundedReceiver<Snapshot>,
+    role: RaftRole,
+    term: i32,
+    voted_for: Option<i32>,
+    leader: Option<i32>,
+    snapshot: Option<Arc<Snapshot>>,
+    log: Vec<LogEntry>,
+    commit_index: i32,
+    last_dispatched: i32,
+    next_index: HashMap<i32, i32>,
+    match_index: HashMap<i32, i32>,
+    votes: HashSet<i32>,
+    installing: HashSet<i32>,
+    last_contact: HashMap<i32, Instant>,
+    election_deadline: Instant,
+    pending: HashMap<i32, (i32, oneshot::Sender<Result<()>>)>,
+    status: watch::Sender<RaftStatus>,
+    tx: mpsc::Sender<RaftMessage>,
+    rx: mpsc::Receiver<RaftMessage>,
+}
+
+impl<N: RaftNetwork> RaftNode<N> {
+    async fn run(mut self) {
+        self.reset_election_deadline();
+        self.publish();
+
+        let mut ticker = interval(self.options.heartbeat_interval);
+        loop {
+            let result = tokio::select! {
+                Some(message) = self.rx.recv() => self.handle(message).await,
+                Some(snapshot) = self.snapshots.recv() => self.compact(snapshot).await,
+                _ = ticker.tick() => self.tick().await,
+            };
+            if let Err(e) = result {
+                error!("error in member {} of the raft group, {e}", self.id);
+            }
+        }
+    }
+
+    async fn handle(&mut self, message: RaftMessage) -> Result<()> {
+        match message {
+            RaftMessage::AppendEntries { request, resp_tx } => {
+                let result = self.append_entries(request).await;
+                resp_tx.send(result).map_err(oneshot_send_err)?;
+            }
+            RaftMessage::RequestVote { request, resp_tx } => {
+                let result = self.request_vote(request).await;
+                resp_tx.send(result).map_err(oneshot_send_err)?;
+            }
+            RaftMessage::InstallSnapshot { request, resp_tx } => {
+                let result = self.install_snapshot(request).await;
+                resp_tx.send(result).map_err(oneshot_send_err)?;
+            }
+            RaftMessage::Write { command, resp_tx } => self.write(command, resp_tx).await?,
+            RaftMessage::Replicated {
+                peer,
+                term,
+                last_index,
+                response,
+            } => self.replicated(peer, term, last_index, response).await?,
+            RaftMessage::Voted {
+                peer,
+                term,
+                response,
+            } => self.voted(peer, term, response).await?,
+            RaftMessage::Installed {
+                peer,
+                term,
+                index,
+                response,
+            } => self.installed(peer, term, index, response).await?,
+        }
+        Ok(())
+    }
+
+    async fn tick(&mut self) -> Result<()> {
+        match self.role {
+            RaftRole::Leader if !self.has_quorum(self.contacted()) => self.step_down().await,
+            RaftRole::Leader => {
+                self.replicate();
+                Ok(())
+            }
+            _ if Instant::now() >= self.election_deadline => self.start_election().await,
+            _ => Ok(()),
+        }
+    }
+
+    fn snapshot_index(&self) -> i32 {
+        self.snapshot.as_ref().map(|s| s.index).unwrap_or(0)
+    }
+
+    fn snapshot_term(&self) -> i32 {
+        self.snapshot.as_ref().map(|s| s.term).unwrap_or(0)
+    }
+
+    /// The position in the log of the entry with the index, if it hasn't been compacted.
+    fn position(&self, index: i32) -> Option<usize> {
+        let offset = index - self.snapshot_index() - 1;
+        usize::try_from(offset).ok()
+    }
+
+    fn entry(&self, index: i32) -> Option<&LogEntry> {
+        self.position(index).and_then(|p| self.log.get(p))
+    }
+
+    fn last_index(&self) -> i32 {
+        self.log
+            .last()
+            .map(|e| e.index)
+            .unwrap_or_else(|| self.snapshot_index())
+    }
+
+    fn last_term(&self) -> i32 {
+        self.log
+            .last()
+            .map(|e| e.term)
+            .unwrap_or_else(|| self.snapshot_term())
+    }
+
+    fn term_at(&self, index: i32) -> i32 {
+        if index == self.snapshot_index() {
+            return self.snapshot_term();
+        }
+        self.entry(index).map(|e| e.term).unwrap_or(0)
+    }
+
+    fn has_quorum(&self, count: usize) -> bool {
+        count * 2 > self.peers.len() + 1
+    }
+
+    /// The number of members, including this one, that have responded to the leader
+    /// within the election timeout.
+    fn contacted(&self) -> usize {
+        let timeout = self.options.election_timeout;
+        let peers = self
+            .last_contact
+            .values()
+            .filter(|instant| instant.elapsed() < timeout)
+            .count();
+        peers + 1
+    }
+
+    fn publish(&self) {
+        let status = RaftStatus {
+            role: self.role,
+            term: self.term,
+            leader: self.leader,
+        };
+        self.status.send_if_modified(|current| {
+            let modified = *current != status;
+            *current = status;
+            modified
+        });
+    }
+
+    /// The election timeout is randomized so that the members don't start their
+    /// elections at the same time and split the votes.
+    fn reset_election_deadline(&mut self) {
+        let timeout = self.options.election_timeout;
+        let jitter = Uuid::new_v4().as_u128() % timeout.as_millis().max(1);
+        self.election_deadline = Instant::now() + timeout + Duration::from_millis(jitter as u64);
+    }
+
+    async fn append_to_log(&mut self, entries: Vec<LogEntry>) -> Result<()> {
+        self.storage.append(&entries).await?;
+        self.log.extend(entries);
+        Ok(())
+    }
+
+    async fn become_follower(&mut self, term: i32) -> Result<()> {
+        if term > self.term {
+            self.term = term;
+            self.voted_for = None;
+            self.leader = None;
+            self.storage.save_hard_state(term, None).await?;
+        }
+        if self.role == RaftRole::Leader {
+            info!("member {} lost the leadership of the raft group", self.id);
+            for (_, (_, resp_tx)) in self.pending.drain() {
+                let _ = resp_tx.send(Err(anyhow!(
+                    "the leadership of the raft group was lost before the write was committed"
+                )));
+            }
+        }
+        self.role = RaftRole::Follower;
+        self.publish();
+        Ok(())
+    }
+
+    async fn step_down(&mut self) -> Result<()> {
+        info!(
+            "member {} hasn't heard from a majority of the raft group, stepping down",
+            self.id
+        );
+        self.leader = None;
+        self.reset_election_deadline();
+        self.become_follower(self.term).await
+    }
+
+    async fn become_leader(&mut self) -> Result<()> {
+        info!(
+            "member {} became the leader of the raft group for term {}",
+            self.id, self.term
+        );
+        self.role = RaftRole::Leader;
+        self.leader = Some(self.id);
+        let next_index = self.last_index() + 1;
+        self.next_index = self.peers.iter().map(|p| (*p, next_index)).collect();
+        self.match_index = self.peers.iter().map(|p| (*p, 0)).collect();
+        let now = Instant::now();
+        self.last_contact = self.peers.iter().map(|p| (*p, now)).collect();
+        self.installing.clear();
+        self.publish();
+
+        let entry = LogEntry {
+            index: next_index,
+            term: self.term,
+            command: None,
+        };
+        self.append_to_log(vec![entry]).await?;
+        self.replicate();
+        self.advance_commit().await
+    }
+
+    async fn start_election(&mut self) -> Result<()> {
+        self.role = RaftRole::Candidate;
+        self.term += 1;
+        self.voted_for = Some(self.id);
+        self.leader = None;
+        self.storage
+            .save_hard_state(self.term, self.voted_for)
+            .await?;
+        self.votes = HashSet::from([self.id]);
+        self.reset_election_deadline();
+        self.publish();
+        debug!(
+            "member {} started an election for term {}",
+            self.id, self.term
+        );
+
+        if self.has_quorum(self.votes.len()) {
+            return self.become_leader().await;
+        }
+
+        let request = VoteRequest {
+            term: self.term,
+            candidate_id: self.id,
+            last_log_index: self.last_index(),
+            last_log_term: self.last_term(),
+        };
+        for peer in self.peers.iter().copied() {
+            let network = self.network.clone();
+            let tx = self.tx.clone();
+            let request = request.clone();
+            let term = self.term;
+            spawn(async move {
+                match network.request_vote(peer, request).await {
+                    Ok(response) => {
+                        let message = RaftMessage::Voted {
+                            peer,
+                            term,
+                            response,
+                        };
+                        let _ = tx.send(message).await;
+                    }
+                    Err(e) => debug!("unable to request the vote of member {peer}, {e}"),
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn voted(&mut self, peer: i32, term: i32, response: VoteResponse) -> Result<()> {
+        if response.term > self.term {
+            return self.become_follower(response.term).await;
+        }
+        if self.role != RaftRole::Candidate || term != self.term || !response.vote_granted {
+            return Ok(());
+        }
+        self.votes.insert(peer);
+        if self.has_quorum(self.votes.len()) {
+            self.become_leader().await?;
+        }
+        Ok(())
+    }
+
+    /// Sends the entries that each member is missing, or an empty request that acts as a
+    /// heartbeat if it has all of them. A member that is missing entries that have been
+    /// compacted is sent the snapshot, unless it's already being sent one.
+    fn replicate(&mut self) {
+        for peer in self.peers.clone() {
+            let next_index = self.next_index.get(&peer).copied().unwrap_or(1);
+            if next_index <= self.snapshot_index() {
+                self.send_snapshot(peer);
+                continue;
+            }
+
+            let prev_log_index = next_index - 1;
+            let start = self.position(next_index).unwrap_or_default();
+            let end = (start + MAX_ENTRIES_PER_REQUEST).min(self.log.len());
+            let entries = self
+                .log
+                .get(start..end)
+                .map(<[LogEntry]>::to_vec)
+                .unwrap_or_default();
+            let last_index = prev_log_index + entries.len() as i32;
+            let request = AppendEntriesRequest {
+                term: self.term,
+                leader_id: self.id,
+                prev_log_index,
+                prev_log_term: self.term_at(prev_log_index),
+                entries,
+                leader_commit: self.commit_index,
+            };
+
+            let network = self.network.clone();
+            let tx = self.tx.clone();
+            let term = self.term;
+            spawn(async move {
+                match network.append_entries(peer, request).await {
+                    Ok(response) => {
+                        let message = RaftMessage::Replicated {
+                            peer,
+                            term,
+                            last_index,
+                            response,
+                        };
+                        let _ = tx.send(message).await;
+                    }
+                    Err(e) => debug!("unable to append entries to member {peer}, {e}"),
+                }
+            });
+        }
+    }
+
+    fn send_snapshot(&mut self, peer: i32) {
+        let Some(snapshot) = self.snapshot.as_ref() else {
+            return;
+        };
+        if !self.installing.insert(peer) {
+            return;
+        }
+
+        let index = snapshot.index;
+        let request = InstallSnapshotRequest {
+            term: self.term,
+            leader_id: self.id,
+            snapshot: snapshot.as_ref().clone(),
+        };
+        let network = self.network.clone();
+        let tx = self.tx.clone();
+        let term = self.term;
+        spawn(async move {
+            let response = network.install_snapshot(peer, request).await;
+            let message = RaftMessage::Installed {
+                peer,
+                term,
+                index,
+                response,
+            };
+            let _ = tx.send(message).await;
+        });
+    }
+
+    async fn installed(
+        &mut self,
+        peer: i32,
+        term: i32,
+        index: i32,
+        response: Result<InstallSnapshotResponse>,
+    ) -> Result<()> {
+        self.installing.remove(&peer);
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                debug!("unable to install the snapshot to member {peer}, {e}");
+                return Ok(());
+            }
+        };
+        if response.term > self.term {
+            return self.become_follower(response.term).await;
+        }
+        if self.role != RaftRole::Leader || term != self.term {
+            return Ok(());
+        }
+        self.last_contact.insert(peer, Instant::now());
+
+        let match_index = self.match_index.entry(peer).or_insert(0);
+        *match_index = (*match_index).max(index);
+        let next_index = *match_index + 1;
+        self.next_index.insert(peer, next_index);
+        self.advance_commit().await
+    }
+
+    async fn replicated(
+        &mut self,
+        peer: i32,
+        term: i32,
+        last_index: i32,
+        response: AppendEntriesResponse,
+    ) -> Result<()> {
+        if response.term > self.term {
+            return self.become_follower(response.term).await;
+        }
+        if self.role != RaftRole::Leader || term != self.term {
+            return Ok(());
+        }
+        self.last_contact.insert(peer, Instant::now());
+
+        if response.success {
+            let match_index = self.match_index.entry(peer).or_insert(0);
+            *match_index = (*match_index).max(last_index);
+            let next_index = *match_index + 1;
+            self.next_index.insert(peer, next_index);
+            self.advance_commit().await
+        } else {
+            let next_index = self.next_index.entry(peer).or_insert(1);
+            *next_index = (*next_index - 1).min(response.last_log_index + 1).max(1);
+            Ok(())
+        }
+    }
+
+    /// Commits the latest entry of the current term that a majority of the members has.
+    async fn advance_commit(&mut self) -> Result<()> {
+        let mut index = self.last_index();
+        while index > self.commit_index {
+            if self.term_at(index) == self.term {
+                let replicas = 1 + self.match_index.values().filter(|m| **m >= index).count();
+                if self.has_quorum(replicas) {
+                    self.commit_index = index;
+                    break;
+                }
+            }
+            index -= 1;
+        }
+        self.dispatch()
+    }
+
+    /// Hands the committed entries that haven't been applied yet to the applier, along
+    /// with the pending writes that appended them.
+    fn dispatch(&mut self) -> Result<()> {
+        while self.last_dispatched < self.commit_index {
+            let index = self.last_dispatched + 1;
+            let entry = self
+                .entry(index)
+                .cloned()
+                .ok_or_else(|| anyhow!("entry {index} of the raft log not found"))?;
+            let resp_tx = match self.pending.remove(&index) {
+                Some((term, resp_tx)) if term == entry.term => Some(resp_tx),
+                Some((_, resp_tx)) => {
+                    let _ = resp_tx.send(Err(anyhow!(
+                        "the write was replaced by the entry of another leader"
+                    )));
+                    None
+                }
+                None => None,
+            };
+            self.applier
+                .send(ApplierMessage::Apply(Box::new(CommittedEntry {
+                    entry,
+                    resp_tx,
+                })))
+                .map_err(|_| anyhow!("the applier of the raft log has stopped"))?;
+            self.last_dispatched = index;
+        }
+        Ok(())
+    }
+
+    /// Saves a snapshot taken by the applier and removes the entries up to it from the log.
+    async fn compact(&mut self, snapshot: Snapshot) -> Result<()> {
+        if snapshot.index <= self.snapshot_index() {
+            return Ok(());
+        }
+        debug!(
+            "member {} compacting the raft log up to entry {}",
+            self.id, snapshot.index
+        );
+        self.storage.save_snapshot(&snapshot).await?;
+        self.log.retain(|e| e.index > snapshot.index);
+        self.snapshot = Some(Arc::new(snapshot));
+        Ok(())
+    }
+
+    async fn write(
+        &mut self,
+        command: HaCommand,
+        resp_tx: oneshot::Sender<Result<()>>,
+    ) -> Result<()> {
+        if self.role != RaftRole::Leader {
+            let _ = resp_tx.send(Err(anyhow!(
+                "the server is not the leader of the raft group"
+            )));
+            return Ok(());
+        }
+
+        let entry = LogEntry {
+            index: self.last_index() + 1,
+            term: self.term,
+            command: Some(command),
+        };
+        let index = entry.index;
+        if let Err(e) = self.append_to_log(vec![entry]).await {
+            let _ = resp_tx.send(Err(e));
+            return Ok(());
+        }
+        self.pending.insert(index, (self.term, resp_tx));
+        self.replicate();
+        self.advance_commit().await
+    }
+
+    fn append_response(&self, success: bool) -> AppendEntriesResponse {
+        AppendEntriesResponse {
+            term: self.term,
+            success,
+            last_log_index: self.last_index(),
+        }
+    }
+
+    async fn append_entries(
+        &mut self,
+        request: AppendEntriesRequest,
+    ) -> Result<AppendEntriesResponse> {
+        if request.term < self.term {
+            return Ok(self.append_response(false));
+        }
+        if request.term > self.term || self.role != RaftRole::Follower {
+            self.become_follower(request.term).await?;
+        }
+        self.leader = Some(request.leader_id);
+        self.reset_election_deadline();
+        self.publish();
+
+        let mut prev_log_index = request.prev_log_index;
+        let mut prev_log_term = request.prev_log_term;
+        let mut request_entries = request.entries;
+        let snapshot_index = self.snapshot_index();
+        if prev_log_index < snapshot_index {
+            // the entries up to the snapshot are committed, so they match the ones of the
+            // leader.
+            request_entries.retain(|e| e.index > snapshot_index);
+            prev_log_index = snapshot_index;
+            prev_log_term = self.snapshot_term();
+        }
+
+        if prev_log_index > self.last_index() {
+            return Ok(self.append_response(false));
+        }
+        if self.term_at(prev_log_index) != prev_log_term {
+            return Ok(AppendEntriesResponse {
+                term: self.term,
+                success: false,
+                last_log_index: prev_log_index - 1,
+            });
+        }
+
+        let last_new_index = prev_log_index + request_entries.len() as i32;
+        let mut entries = vec![];
+        for entry in request_entries {
+            if entry.index <= self.last_index() {
+                if self.term_at(entry.index) == entry.term {
+                    continue;
+                }
+                self.storage.truncate(entry.index).await?;
+                let position = self.position(entry.index).unwrap_or_default();
+                self.log.truncate(position);
+            }
+            entries.push(entry);
+        }
+        self.append_to_log(entries).await?;
+
+        let commit_index = request.leader_commit.min(last_new_index);
+        self.commit_index = self.commit_index.max(commit_index);
+        self.dispatch()?;
+
+        Ok(self.append_response(true))
+    }
+
+    /// Replaces the state of the member with the snapshot of the leader, keeping the
+    /// entries of the log after it if the log has the last entry of the snapshot.
+    async fn install_snapshot(
+        &mut self,
+        request: InstallSnapshotRequest,
+    ) -> Result<InstallSnapshotResponse> {
+        if request.term < self.term {
+            return Ok(InstallSnapshotResponse { term: self.term });
+        }
+        if request.term > self.term || self.role != RaftRole::Follower {
+            self.become_follower(request.term).await?;
+        }
+        self.leader = Some(request.leader_id);
+        self.reset_election_deadline();
+        self.publish();
+
+        let snapshot = request.snapshot;
+        if snapshot.index <= self.commit_index {
+            return Ok(InstallSnapshotResponse { term: self.term });
+        }
+        info!(
+            "member {} installing the snapshot of the leader up to entry {}",
+            self.id, snapshot.index
+        );
+
+        let has_entry = self.term_at(snapshot.index) == snapshot.term;
+        self.storage.save_snapshot(&snapshot).await?;
+        if has_entry {
+            self.log.retain(|e| e.index > snapshot.index);
+        } else {
+            self.storage.truncate(snapshot.index + 1).await?;
+            self.log.clear();
+        }
+
+        let snapshot = Arc::new(snapshot);
+        self.commit_index = snapshot.index;
+        self.last_dispatched = snapshot.index;
+        self.snapshot = Some(Arc::clone(&snapshot));
+        self.applier
+            .send(ApplierMessage::Restore(snapshot))
+            .map_err(|_| anyhow!("the applier of the raft log has stopped"))?;
+
+        Ok(InstallSnapshotResponse { term: self.term })
+    }
+
+    async fn request_vote(&mut self, request: VoteRequest) -> Result<VoteResponse> {
+        if request.term > self.term {
+            self.become_follower(request.term).await?;
+        }
+
+        let up_to_date = (request.last_log_term, request.last_log_index)
+            >= (self.last_term(), self.last_index());
+        let can_vote = self.voted_for.is_none_or(|id| id == request.candidate_id);
+        let vote_granted = request.term == self.term && can_vote && up_to_date;
+
+        if vote_granted {
+            self.voted_for = Some(request.candidate_id);
+            self.storage
+                .save_hard_state(self.term, self.voted_for)
+                .await?;
+            self.reset_election_deadline();
+        }
+
+        Ok(VoteResponse {
+            term: self.term,
+            vote_granted,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct RaftSender {
+    tx: mpsc::Sender<RaftMessage>,
+    status: watch::Receiver<RaftStatus>,
+}
+
+impl RaftSender {
+    /// Loads the state of the member from the storage and starts its node in the background.
+    pub async fn spawn<N: RaftNetwork, S: RaftStateMachine>(
+        id: i32,
+        peers: Vec<i32>,
+        options: RaftOptions,
+        storage: RaftStorage,
+        network: Arc<N>,
+        state_machine: Arc<S>,
+        status: watch::Sender<RaftStatus>,
+    ) -> Result<Self> {
+        let (term, voted_for) = storage.hard_state().await?;
+        let snapshot = storage.snapshot().await?.map(Arc::new);
+        let snapshot_index = snapshot.as_ref().map(|s| s.index).unwrap_or(0);
+        let log = storage.entries(snapshot_index).await?;
+        let last_applied = storage
+            .last_applied()
+            .await?
+            .min(snapshot_index + log.len() as i32);
+        let (tx, rx) = mpsc::channel(4096);
+        let (applier_tx, applier_rx) = mpsc::unbounded_channel();
+        let (snapshots_tx, snapshots_rx) = mpsc::unbounded_channel();
+        let applier = RaftApplier::new(
+            storage.clone(),
+            state_machine,
+            options.snapshot_threshold,
+            snapshot_index,
+            snapshots_tx,
+            applier_rx,
+        );
+
+        // the server hasn't applied the entries up to the snapshot, so it's restored
+        // before any of the entries after it.
+        if let Some(snapshot) = snapshot.as_ref().filter(|_| last_applied < snapshot_index) {
+            let message = ApplierMessage::Restore(Arc::clone(snapshot));
+            applier_tx
+                .send(message)
+                .map_err(|_| anyhow!("the applier of the raft log has stopped"))?;
+        }
+        let last_applied = last_applied.max(snapshot_index);
+
+        let sender = Self {
+            tx: tx.clone(),
+            status: status.subscribe(),
+        };
+
+        let node = RaftNode {
+            id,
+            peers,
+            options,
+            storage,
+            network,
+            applier: applier_tx,
+            snapshots: snapshots_rx,
+            role: RaftRole::Follower,
+            term,
+            voted_for,
+            leader: None,
+            snapshot,
+            log,
+            commit_index: last_applied,
+            last_dispatched: last_applied,
+            next_index: HashMap::new(),
+            match_index: HashMap::new(),
+            votes: HashSet::new(),
+            installing: HashSet::new(),
+            last_contact: HashMap::new(),
+            election_deadline: Instant::now(),
+            pending: HashMap::new(),
+            status,
+            tx,
+            rx,
+        };
+        spawn(applier.run());
+        spawn(node.run());
+
+        Ok(sender)
+    }
+
+    pub fn status(&self) -> RaftStatus {
+        self.status.borrow().clone()
+    }
+
+    pub async fn append_entries(
+        &self,
+        request: AppendEntriesRequest,
+    ) -> Result<AppendEntriesResponse> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let message = RaftMessage::AppendEntries { request, resp_tx };
+        self.tx.send(message).await.map_err(|e| anyhow!(e))?;
+        resp_rx.await?
+    }
+
+    pub async fn request_vote(&self, request: VoteRequest) -> Result<VoteResponse> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let message = RaftMessage::RequestVote { request, resp_tx };
+        self.tx.send(message).await.map_err(|e| anyhow!(e))?;
+        resp_rx.await?
+    }
+
+    pub async fn install_snapshot(
+        &self,
+        request: InstallSnapshotRequest,
+    ) -> Result<InstallSnapshotResponse> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let message = RaftMessage::InstallSnapshot { request, resp_tx };
+        self.tx.send(message).await.map_err(|e| anyhow!(e))?;
+        resp_rx.await?
+    }
+
+    /// Writes the command to the log and waits until it's committed and applied.
+    pub async fn write(&self, command: HaCommand) -> Result<()> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let message = RaftMessage::Write { command, resp_tx };
+        self.tx.send(message).await.map_err(|e| anyhow!(e))?;
+        resp_rx.await?
+    }
+}
+
+#[cfg(test)]
+impl RaftSender {
+    /// A sender without a running node, whose status is the one of the watch channel.
+    pub fn detached(status: watch::Receiver<RaftStatus>) -> Self {
+        let (tx, _) = mpsc::channel(1);
+        Self { tx, status }
+    }
+}
+
+fn oneshot_send_err<T>(_: T) -> anyhow::Error {
+    anyhow!("oneshot response sender dropped")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use anyhow::bail;
+    use bld_config::BldConfig;
+    use bld_models::new_connection_pool;
+    use tokio::time::{sleep, timeout};
+
+    use super::*;
+
+    const MEMBERS: [i32; 3] = [1, 2, 3];
+    const SLOW_COMMAND: &str = "slow";
+    const SNAPSHOT_THRESHOLD: i32 = 4;
+
+    #[derive(Default)]
+    struct Cluster {
+        nodes: Mutex<HashMap<i32, RaftSender>>,
+        storages: Mutex<HashMap<i32, RaftStorage>>,
+        down: Mutex<HashSet<i32>>,
+    }
+
+    impl Cluster {
+        fn node(&self, id: i32) -> Result<RaftSender> {
+            if self.down.lock().unwrap().contains(&id) {
+                bail!("member {id} is disconnected");
+            }
+            self.nodes
+                .lock()
+                .unwrap()
+                .get(&id)
+                .cloned()
+                .ok_or_else(|| anyhow!("member {id} not found"))
+        }
+
+        fn status(&self, id: i32) -> RaftStatus {
+            self.nodes.lock().unwrap()[&id].status()
+        }
+
+        fn leader(&self) -> Option<i32> {
+            MEMBERS
+                .into_iter()
+                .filter(|id| !self.down.lock().unwrap().contains(id))
+                .find(|id| self.status(*id).is_leader())
+        }
+
+        fn storage(&self, id: i32) -> RaftStorage {
+            self.storages.lock().unwrap()[&id].clone()
+        }
+
+        fn disconnect(&self, id: i32) {
+            self.down.lock().unwrap().insert(id);
+        }
+
+        fn reconnect(&self, id: i32) {
+            self.down.lock().unwrap().remove(&id);
+        }
+    }
+
+    /// Delivers the requests between the members of the cluster in the same process and
+    /// fails them if either of the members is disconnected.
+    struct MemoryNetwork {
+        source: i32,
+        cluster: Arc<Cluster>,
+    }
+
+    impl RaftNetwork for MemoryNetwork {
+        async fn append_entries(
+            &self,
+            target: i32,
+            request: AppendEntriesRequest,
+        ) -> Result<AppendEntriesResponse> {
+            self.cluster.node(self.source)?;
+            self.cluster.node(target)?.append_entries(request).await
+        }
+
+        async fn request_vote(&self, target: i32, request: VoteRequest) -> Result<VoteResponse> {
+            self.cluster.node(self.source)?;
+            self.cluster.node(target)?.request_vote(request).await
+        }
+
+        async fn install_snapshot(
+            &self,
+            target: i32,
+            request: InstallSnapshotRequest,
+        ) -> Result<InstallSnapshotResponse> {
+            self.cluster.node(self.source)?;
+            self.cluster.node(target)?.install_snapshot(request).await
+        }
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        applied: Mutex<Vec<String>>,
+    }
+
+    impl Recorder {
+        fn applied(&self) -> Vec<String> {
+            self.applied.lock().unwrap().clone()
+        }
+    }
+
+    impl RaftStateMachine for Recorder {
+        async fn apply(&self, command: HaCommand) -> Result<()> {
+            if let HaCommand::RemoveCronJob { id } = command {
+                if id == SLOW_COMMAND {
+                    sleep(Duration::from_secs(1)).await;
+                }
+                self.applied.lock().unwrap().push(id);
+            }
+            Ok(())
+        }
+
+        async fn snapshot(&self) -> Result<String> {
+            Ok(serde_json::to_string(&self.applied())?)
+        }
+
+        async fn restore(&self, data: &str) -> Result<()> {
+            *self.applied.lock().unwrap() = serde_json::from_str(data)?;
+            Ok(())
+        }
+    }
+
+    fn command(id: &str) -> HaCommand {
+        HaCommand::RemoveCronJob { id: id.to_owned() }
+    }
+
+    async fn spawn_member(id: i32, cluster: &Arc<Cluster>) -> Arc<Recorder> {
+        let mut config = BldConfig::default();
+        config.local.server.db = Some("sqlite::memory:".to_owned());
+        let conn = new_connection_pool(Arc::new(config)).await.unwrap();
+
+        let peers = MEMBERS.into_iter().filter(|p| *p != id).collect();
+        let options = RaftOptions {
+            election_timeout: Duration::from_millis(150),
+            heartbeat_interval: Duration::from_millis(30),
+            snapshot_threshold: SNAPSHOT_THRESHOLD,
+        };
+        let network = Arc::new(MemoryNetwork {
+            source: id,
+            cluster: Arc::clone(cluster),
+        });
+        let recorder = Arc::new(Recorder::default());
+        let (status, _) = watch::channel(RaftStatus::default());
+        let storage = RaftStorage::new(Arc::new(conn));
+        cluster.storages.lock().unwrap().insert(id, storage.clone());
+
+        let node = RaftSender::spawn(
+            id,
+            peers,
+            options,
+            storage,
+            network,
+            Arc::clone(&recorder),
+            status,
+        )
+        .await
+        .unwrap();
+        cluster.nodes.lock().unwrap().insert(id, node);
+
+        recorder
+    }
+
+    async fn wait_for(condition: impl Fn() -> bool) {
+        for _ in 0..250 {
+            if condition() {
+                return;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+        panic!("timed out waiting for the raft group");
+    }
+
+    #[actix_web::test]
+    async fn a_new_leader_is_elected_when_the_leader_fails_and_the_old_leader_catches_up() {
+        let cluster = Arc::new(Cluster::default());
+        let mut recorders = HashMap::new();
+        for id in MEMBERS {
+            recorders.insert(id, spawn_member(id, &cluster).await);
+        }
+
+        wait_for(|| cluster.leader().is_some()).await;
+        let leader = cluster.leader().unwrap();
+        cluster
+            .node(leader)
+            .unwrap()
+            .write(command("first"))
+            .await
+            .unwrap();
+        wait_for(|| recorders.values().all(|r| r.applied() == ["first"])).await;
+
+        cluster.disconnect(leader);
+        wait_for(|| cluster.leader().is_some()).await;
+        let new_leader = cluster.leader().unwrap();
+        assert_ne!(new_leader, leader);
+
+        cluster
+            .node(new_leader)
+            .unwrap()
+            .write(command("second"))
+            .await
+            .unwrap();
+        wait_for(|| {
+            recorders
+                .iter()
+                .filter(|(id, _)| **id != leader)
+                .all(|(_, r)| r.applied() == ["first", "second"])
+        })
+        .await;
+        assert_eq!(recorders[&leader].applied(), ["first"]);
+
+        cluster.reconnect(leader);
+        wait_for(|| recorders[&leader].applied() == ["first", "second"]).await;
+        wait_for(|| !cluster.status(leader).is_leader()).await;
+        assert!(cluster.status(leader).term >= cluster.status(new_leader).term);
+    }
+
+    #[actix_web::test]
+    async fn a_partitioned_leader_steps_down_and_fails_its_pending_writes() {
+        let cluster = Arc::new(Cluster::default());
+        for id in MEMBERS {
+            spawn_member(id, &cluster).await;
+        }
+
+        wait_for(|| cluster.leader().is_some()).await;
+        let leader = cluster.leader().unwrap();
+        let node = cluster.node(leader).unwrap();
+        cluster.disconnect(leader);
+
+        let write = timeout(Duration::from_secs(5), node.write(command("partitioned")))
+            .await
+            .expect("the write of the partitioned leader never completed");
+        assert!(write.is_err());
+        wait_for(|| !cluster.status(leader).is_leader()).await;
+        assert_eq!(cluster.status(leader).leader, None);
+
+        wait_for(|| cluster.leader().is_some_and(|id| id != leader)).await;
+    }
+
+    #[actix_web::test]
+    async fn a_command_that_is_slow_to_apply_does_not_hold_up_the_raft_group() {
+        let cluster = Arc::new(Cluster::default());
+        let mut recorders = HashMap::new();
+        for id in MEMBERS {
+            recorders.insert(id, spawn_member(id, &cluster).await);
+        }
+
+        wait_for(|| cluster.leader().is_some()).await;
+        let leader = cluster.leader().unwrap();
+        let term = cluster.status(leader).term;
+        let node = cluster.node(leader).unwrap();
+        node.write(command(SLOW_COMMAND)).await.unwrap();
+        node.write(command("after")).await.unwrap();
+
+        assert_eq!(cluster.leader(), Some(leader));
+        assert_eq!(cluster.status(leader).term, term);
+        wait_for(|| {
+            recorders
+                .values()
+                .all(|r| r.applied() == [SLOW_COMMAND, "after"])
+        })
+        .await;
+    }
+
+    #[actix_web::test]
+    async fn a_member_that_falls_behind_the_compacted_log_catches_up_from_the_snapshot() {
+        let cluster = Arc::new(Cluster::default());
+        let mut recorders = HashMap::new();
+        for id in MEMBERS {
+            recorders.insert(id, spawn_member(id, &cluster).await);
+        }
+
+        wait_for(|| cluster.leader().is_some()).await;
+        let leader = cluster.leader().unwrap();
+        let follower = MEMBERS.into_iter().find(|id| *id != leader).unwrap();
+        cluster.disconnect(follower);
+
+        let node = cluster.node(leader).unwrap();
+        let commands: Vec<String> = (0..SNAPSHOT_THRESHOLD * 3)
+            .map(|i| format!("command-{i}"))
+            .collect();
+        for id in &commands {
+            node.write(command(id)).await.unwrap();
+        }
+
+        let storage = cluster.storage(leader);
+        let snapshot = storage.snapshot().await.unwrap().unwrap();
+        assert!(snapshot.index >= SNAPSHOT_THRESHOLD * 2);
+        let entries = storage.entries(0).await.unwrap();
+        assert!(entries.iter().all(|e| e.index > snapshot.index));
+
+        cluster.reconnect(follower);
+        wait_for(|| recorders[&follower].applied() == commands).await;
+        let snapshot = cluster.storage(follower).snapshot().await.unwrap();
+        assert!(snapshot.is_some());
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use actix_web::rt::spawn;
+use anyhow::Result;
+use bld_core::fs::FileSystem;
+use bld_models::pipeline_runs::{
+    self, PR_STATE_INITIAL, PR_STATE_QUEUED, PR_STATE_RUNNING, PR_STATE_WAITING_APPROVAL,
+    is_completed,
+};
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use sea_orm::DatabaseConnection;
+use tokio::{sync::watch, task::JoinHandle, time::sleep};
+use tracing::{error, info};
+
+use crate::supervisor::{channel::SupervisorMessageSender, helpers::requeue_worker};
+
+use super::{HaCommand, HighAvailability, RaftStatus};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Replicates the queue of the leader of a raft group, whose runs are only moved through
+/// their states by the workers of its own supervisor. The leader polls the runs whose state
+/// has changed and writes their new state to the log of the group. When a member gains the
+/// leadership it sends the runs that are still queued to its supervisor and faults the ones
+/// that were in progress on the previous leader, since their workers are gone along with it.
+///
+/// Runs are started at least once, since a queued run of a leader that restarts may also be
+/// started by its supervisor. A requeued run loses its sensitive environment variables,
+/// which are never persisted, and its priority unless it was started by a cron job.
+pub struct QueueRelay {
+    _task: JoinHandle<()>,
+}
+
+impl QueueRelay {
+    pub fn new(
+        fs: Arc<FileSystem>,
+        conn: Arc<DatabaseConnection>,
+        supervisor: Arc<SupervisorMessageSender>,
+        ha: Arc<HighAvailability>,
+        status: watch::Receiver<RaftStatus>,
+    ) -> Self {
+        let mut relay = Relay {
+            fs,
+            conn,
+            supervisor,
+            ha,
+            status,
+            leading: false,
+            since: Utc::now().naive_utc(),
+            relayed: HashMap::new(),
+            owned: HashSet::new(),
+        };
+        let task = spawn(async move {
+            loop {
+                relay.poll().await;
+                sleep(POLL_INTERVAL).await;
+            }
+        });
+
+        Self { _task: task }
+    }
+}
+
+struct Relay {
+    fs: Arc<FileSystem>,
+    conn: Arc<DatabaseConnection>,
+    supervisor: Arc<SupervisorMessageSender>,
+    ha: Arc<HighAvailability>,
+    status: watch::Receiver<RaftStatus>,
+    leading: bool,
+    /// The date after which the updated runs are polled, which lags a poll behind so that
+    /// no update is missed.
+    since: NaiveDateTime,
+    /// The state that each run updated during the last poll was relayed with.
+    relayed: HashMap<String, String>,
+    /// The runs in progress on the workers of the server.
+    owned: HashSet<String>,
+}
+
+impl Relay {
+    async fn poll(&mut self) {
+        if !self.status.borrow().is_leader() {
+            self.leading = false;
+            return;
+        }
+
+        if !self.leading {
+            self.since = Utc::now().naive_utc();
+            self.relayed.clear();
+            if let Err(e) = self.take_over().await {
+                error!("unable to take over the queue of the raft group due to: {e}");
+                return;
+            }
+            self.leading = true;
+        }
+
+        if let Err(e) = self.relay().await {
+            error!("unable to relay the state of the runs due to: {e}");
+        }
+    }
+
+    async fn take_over(&mut self) -> Result<()> {
+        let conn = self.conn.as_ref();
+
+        let states = [PR_STATE_RUNNING, PR_STATE_WAITING_APPROVAL];
+        for run in pipeline_runs::select_by_states(conn, &states).await? {
+            if !self.owned.contains(&run.id) {
+                info!("run {} was in progress on the previous leader", run.id);
+                let reason = "the leader of the raft group changed while the run was in progress";
+                pipeline_runs::update_as_faulted(conn, &run.id, reason).await?;
+            }
+        }
+
+        let states = [PR_STATE_INITIAL, PR_STATE_QUEUED];
+        for run in pipeline_runs::select_by_states(conn, &states).await? {
+            info!("requeueing run {} of the previous leader", run.id);
+            let supervisor = self.supervisor.as_ref();
+            if let Err(e) = requeue_worker(&self.fs, conn, supervisor, &run).await {
+                error!("unable to requeue run {} due to: {e}", run.id);
+                let reason =
+                    "the run couldn't be requeued after the leader of the raft group changed";
+                pipeline_runs::update_as_faulted(conn, &run.id, reason).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn relay(&mut self) -> Result<()> {
+        let now = Utc::now().naive_utc();
+        let runs = pipeline_runs::select_updated_since(self.conn.as_ref(), self.since).await?;
+
+        let mut relayed = HashMap::new();
+        for run in runs {
+            if self.relayed.get(&run.id) != Some(&run.state) {
+                let command = HaCommand::UpdateRunState {
+                    run_id: run.id.to_owned(),
+                    state: run.state.to_owned(),
+                };
+                self.ha.apply(command).await?;
+            }
+            if is_completed(&run.state) {
+                self.owned.remove(&run.id);
+            } else if run.state == PR_STATE_RUNNING || run.state == PR_STATE_WAITING_APPROVAL {
+                self.owned.insert(run.id.to_owned());
+            }
+            relayed.insert(run.id, run.state);
+        }
+
+        self.relayed = relayed;
+        self.since = now - TimeDelta::from_std(POLL_INTERVAL)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+use super::HaCommand;
+
+/// An entry of the raft log. Entries without a command are appended by a new leader
+/// in order to commit the entries of the previous terms.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub index: i32,
+    pub term: i32,
+    pub command: Option<HaCommand>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppendEntriesRequest {
+    pub term: i32,
+    pub leader_id: i32,
+    pub prev_log_index: i32,
+    pub prev_log_term: i32,
+    pub entries: Vec<LogEntry>,
+    pub leader_commit: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AppendEntriesResponse {
+    pub term: i32,
+    pub success: bool,
+    /// The index up to which the log of the member may match the one of the leader.
+    pub last_log_index: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoteRequest {
+    pub term: i32,
+    pub candidate_id: i32,
+    pub last_log_index: i32,
+    pub last_log_term: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VoteResponse {
+    pub term: i32,
+    pub vote_granted: bool,
+}
+
+/// The state of the server up to an entry of the raft log, which replaces the entries
+/// up to it once they have been applied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub index: i32,
+    pub term: i32,
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallSnapshotRequest {
+    pub term: i32,
+    pub leader_id: i32,
+    pub snapshot: Snapshot,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InstallSnapshotResponse {
+    pub term: i32,
+}
@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use bld_models::{
+    ha_hard_state::{self, InsertHighAvailHardState},
+    ha_log::{self, BLANK, HighAvailLog, InsertHighAvailLog, NORMAL},
+    ha_snapshot::{self, InsertHighAvailSnapshot},
+    ha_state_machine,
+};
+use sea_orm::DatabaseConnection;
+
+use super::{LogEntry, Snapshot};
+
+const HARD_STATE_ID: i32 = 1;
+const STATE_MACHINE_ID: i32 = 1;
+
+/// Persists the state of a member of a raft group to the high availability tables, which
+/// are the current term and vote, the latest snapshot, the entries of the log after it
+/// and the index of the last entry applied to the server.
+#[derive(Clone)]
+pub struct RaftStorage {
+    conn: Arc<DatabaseConnection>,
+}
+
+impl RaftStorage {
+    pub fn new(conn: Arc<DatabaseConnection>) -> Self {
+        Self { conn }
+    }
+
+    pub async fn hard_state(&self) -> Result<(i32, Option<i32>)> {
+        let conn = self.conn.as_ref();
+        if let Ok(state) = ha_hard_state::select_by_id(conn, HARD_STATE_ID).await {
+            return Ok((state.current_term, state.voted_for));
+        }
+        let model = InsertHighAvailHardState::new(HARD_STATE_ID, 0, None);
+        ha_hard_state::insert(conn, model).await?;
+        Ok((0, None))
+    }
+
+    pub async fn save_hard_state(&self, term: i32, voted_for: Option<i32>) -> Result<()> {
+        ha_hard_state::update(self.conn.as_ref(), HARD_STATE_ID, term, voted_for).await
+    }
+
+    pub async fn last_applied(&self) -> Result<i32> {
+        let conn = self.conn.as_ref();
+        if let Ok(state_machine) = ha_state_machine::select_by_id(conn, STATE_MACHINE_ID).await {
+            return Ok(state_machine.last_applied_log);
+        }
+        ha_state_machine::insert(conn, STATE_MACHINE_ID, 0).await?;
+        Ok(0)
+    }
+
+    pub async fn save_last_applied(&self, index: i32) -> Result<()> {
+        ha_state_machine::update(self.conn.as_ref(), STATE_MACHINE_ID, index).await
+    }
+
+    /// Loads the entries of the log after the index, which is the one of the snapshot.
+    pub async fn entries(&self, after: i32) -> Result<Vec<LogEntry>> {
+        ha_log::select_after_id(self.conn.as_ref(), after)
+            .await?
+            .into_iter()
+            .map(Self::into_entry)
+            .collect()
+    }
+
+    pub async fn append(&self, entries: &[LogEntry]) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let models = entries
+            .iter()
+            .map(Self::into_model)
+            .collect::<Result<Vec<_>>>()?;
+        ha_log::insert_many(self.conn.as_ref(), models).await
+    }
+
+    /// Removes the entries of the log starting from the index.
+    pub async fn truncate(&self, index: i32) -> Result<()> {
+        ha_log::delete_from_id(self.conn.as_ref(), index).await
+    }
+
+    pub async fn snapshot(&self) -> Result<Option<Snapshot>> {
+        let Ok(model) = ha_snapshot::select_last(self.conn.as_ref()).await else {
+            return Ok(None);
+        };
+        Ok(Some(Snapshot {
+            index: model.id,
+            term: model.term,
+            data: String::from_utf8(model.data)?,
+        }))
+    }
+
+    /// Saves the snapshot in place of the previous ones and removes the entries of the log
+    /// up to it.
+    pub async fn save_snapshot(&self, snapshot: &Snapshot) -> Result<()> {
+        let conn = self.conn.as_ref();
+        let model = InsertHighAvailSnapshot::new(
+            snapshot.index,
+            snapshot.term,
+            snapshot.data.as_bytes().to_vec(),
+        );
+        ha_snapshot::insert(conn, model).await?;
+        ha_snapshot::delete_until_id(conn, snapshot.index).await?;
+        ha_log::delete_until_id(conn, snapshot.index + 1).await
+    }
+
+    fn into_entry(model: HighAvailLog) -> Result<LogEntry> {
+        let command = match model.payload_type.as_str() {
+            NORMAL => Some(serde_json::from_str(&model.payload)?),
+            _ => None,
+        };
+        Ok(LogEntry {
+            index: model.id,
+            term: model.term,
+            command,
+        })
+    }
+
+    fn into_model(entry: &LogEntry) -> Result<InsertHighAvailLog> {
+        let model = match &entry.command {
+            Some(command) => {
+                let payload = serde_json::to_string(command)?;
+                InsertHighAvailLog::new(entry.index, entry.term, NORMAL, Some(&payload))
+            }
+            None => InsertHighAvailLog::new(entry.index, entry.term, BLANK, None),
+        };
+        Ok(model)
+    }
+}
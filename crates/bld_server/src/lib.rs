@@ -1,9 +1,13 @@
+pub mod access;
 mod cleanup;
+mod completions;
 pub mod cron;
 pub mod endpoints;
 pub mod extractors;
+pub mod high_availability;
 mod server;
 pub mod sockets;
 mod supervisor;
+mod webhooks;
 
 pub use server::*;
@@ -1,27 +1,36 @@
 use crate::cleanup::CleanupWorker;
+use crate::completions::CompletionTriggerWorker;
 use crate::cron::CronScheduler;
 use crate::endpoints::auth::WebCoreClient;
 use crate::endpoints::{
-    artifacts, auth, check, copy, cron, deps, hist, home, list, r#move, print, pull, push, remove,
-    run, stop, ui,
+    access, approve, artifacts, auth, check, copy, cron, deps, ha, hist, home, hooks, list, r#move,
+    print, pull, push, remove, run, secrets, stop, timeline, triggers, ui,
+};
+use crate::high_availability::{
+    HighAvailability, QueueRelay, StateMachine, follow_leader, status_channel,
 };
 use crate::sockets::{exec, login, monit};
 use crate::supervisor::channel::SupervisorMessageSender;
 use actix_cors::Cors;
 use actix_web::{
-    App, HttpServer, middleware,
+    App, HttpServer,
+    middleware::{self, from_fn},
     web::{get, resource},
 };
 use anyhow::Result;
 use bld_config::BldConfig;
 use bld_core::fs::FileSystem;
-use bld_models::new_connection_pool;
+use bld_models::{
+    new_connection_pool,
+    pipeline_runs::{self, PR_STATE_INITIAL},
+};
 use bld_pkg::PackageManager;
 use bld_utils::{
     sync::IntoData,
     tls::{load_server_certificate, load_server_private_key},
 };
 use rustls::ServerConfig;
+use sea_orm::DatabaseConnection;
 use std::{env::set_var, sync::Arc};
 use tracing::info;
 
@@ -35,17 +44,55 @@ pub async fn start(config: BldConfig, host: String, port: i64) -> Result<()> {
     let conn = new_connection_pool(Arc::clone(&config)).await?;
     let supervisor_sender = SupervisorMessageSender::new(Arc::clone(&config)).into_data();
     let pool = conn.into_data();
+    // the runs of a raft group are taken over by its leader instead.
+    if config.local.server.ha.is_none() {
+        reconcile_initial_runs(pool.as_ref()).await?;
+    }
     // Cleanup worker run in the background, ignore the variable until server exits and the worker is dropped.
     let _cleanup_worker = CleanupWorker::new(Arc::clone(&pool), Arc::clone(&config));
     let fs = FileSystem::server(Arc::clone(&config), Arc::clone(&pool)).into_data();
     let package_manager = PackageManager::new(Arc::clone(&config)).into_data();
+    let (status_tx, status_rx) = status_channel(&config);
     let cron = CronScheduler::new(
         Arc::clone(&fs),
         Arc::clone(&pool),
         Arc::clone(&supervisor_sender),
+        status_rx.clone(),
+    )
+    .await?
+    .into_data();
+    let state_machine = StateMachine::new(
+        Arc::clone(&fs),
+        Arc::clone(&pool),
+        Arc::clone(&package_manager),
+        Arc::clone(&cron),
+        status_rx.clone(),
+    );
+    let ha = HighAvailability::new(
+        &config,
+        Arc::clone(&pool),
+        Arc::new(state_machine),
+        status_tx,
     )
     .await?
     .into_data();
+    cron.set_high_availability(ha.clone().into_inner());
+    // Runs started by the completion of other runs, dropped along with the server like the cleanup worker.
+    let _completion_worker = CompletionTriggerWorker::new(
+        Arc::clone(&fs),
+        Arc::clone(&pool),
+        Arc::clone(&supervisor_sender),
+        ha.clone().into_inner(),
+    );
+    let _queue_relay = config.local.server.ha.as_ref().map(|_| {
+        QueueRelay::new(
+            Arc::clone(&fs),
+            Arc::clone(&pool),
+            Arc::clone(&supervisor_sender),
+            ha.clone().into_inner(),
+            status_rx,
+        )
+    });
 
     unsafe {
         set_var("RUST_LOG", "actix_server=info,actix_web=debug");
@@ -65,6 +112,8 @@ pub async fn start(config: BldConfig, host: String, port: i64) -> Result<()> {
             .app_data(fs.clone())
             .app_data(package_manager.clone())
             .app_data(cron.clone())
+            .app_data(ha.clone())
+            .wrap(from_fn(follow_leader))
             .wrap(middleware::Logger::default())
             .wrap(cors)
             .service(auth::available)
@@ -82,6 +131,7 @@ pub async fn start(config: BldConfig, host: String, port: i64) -> Result<()> {
             .service(deps::get)
             .service(pull::get)
             .service(stop::post)
+            .service(approve::post)
             .service(r#move::patch)
             .service(print::get)
             .service(cron::get)
@@ -91,6 +141,21 @@ pub async fn start(config: BldConfig, host: String, port: i64) -> Result<()> {
             .service(artifacts::get)
             .service(artifacts::download)
             .service(artifacts::delete)
+            .service(timeline::get)
+            .service(secrets::get)
+            .service(secrets::post)
+            .service(secrets::delete)
+            .service(access::get)
+            .service(access::post)
+            .service(access::delete)
+            .service(triggers::get)
+            .service(triggers::post)
+            .service(triggers::patch)
+            .service(triggers::delete)
+            .service(hooks::post)
+            .service(ha::append_entries)
+            .service(ha::request_vote)
+            .service(ha::install_snapshot)
             .service(ui::queued_pipelines)
             .service(ui::running_pipelines)
             .service(ui::completed_pipelines)
@@ -120,3 +185,14 @@ pub async fn start(config: BldConfig, host: String, port: i64) -> Result<()> {
     server.run().await?;
     Ok(())
 }
+
+/// Runs are created in the initial state right before being sent to the supervisor, so any
+/// run still in that state when the server starts was lost while being enqueued.
+async fn reconcile_initial_runs(conn: &DatabaseConnection) -> Result<()> {
+    for run in pipeline_runs::select_by_states(conn, &[PR_STATE_INITIAL]).await? {
+        info!("run {} was not enqueued before the server stopped", run.id);
+        let reason = "the server stopped before the run was enqueued";
+        pipeline_runs::update_as_faulted(conn, &run.id, reason).await?;
+    }
+    Ok(())
+}
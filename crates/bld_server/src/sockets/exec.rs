@@ -1,5 +1,7 @@
 use crate::{
+    access,
     extractors::User,
+    high_availability::HighAvailability,
     supervisor::{channel::SupervisorMessageSender, helpers::enqueue_worker},
 };
 use actix_web::{
@@ -14,7 +16,7 @@ use bld_config::BldConfig;
 use bld_core::{fs::FileSystem, scanner::FileScanner};
 use bld_models::{
     dtos::{ExecClientMessage, ExecServerMessage},
-    pipeline_runs::{self, PR_STATE_FAULTED, PR_STATE_FINISHED, PR_STATE_QUEUED},
+    pipeline_runs::{self, PR_STATE_FAULTED, PR_STATE_FINISHED, PR_STATE_QUEUED, RunTrigger},
 };
 use bld_sock::session::{self, WebSocketMessage};
 use sea_orm::DatabaseConnection;
@@ -27,6 +29,7 @@ const STATE_CHECK_INTERVAL_MS: u64 = 1000;
 struct ExecWebsocket {
     config: Data<BldConfig>,
     supervisor: Data<SupervisorMessageSender>,
+    ha: Data<HighAvailability>,
     conn: Data<DatabaseConnection>,
     fs: Data<FileSystem>,
     user: User,
@@ -38,6 +41,7 @@ impl ExecWebsocket {
     pub fn new(
         config: Data<BldConfig>,
         supervisor: Data<SupervisorMessageSender>,
+        ha: Data<HighAvailability>,
         conn: Data<DatabaseConnection>,
         fs: Data<FileSystem>,
         user: User,
@@ -45,6 +49,7 @@ impl ExecWebsocket {
         Self {
             config,
             supervisor,
+            ha,
             conn,
             fs,
             user,
@@ -101,13 +106,18 @@ impl ExecWebsocket {
 
     pub async fn handle_message(&mut self, session: &mut Session, message: &str) -> Result<()> {
         let message: ExecClientMessage = serde_json::from_str(message)?;
+        let pipeline = access::exec_pipeline(self.conn.as_ref(), &message).await?;
+        self.user
+            .authorize(access::exec_role(&message), &pipeline)?;
         let username = self.user.name.to_owned();
         let fs = self.fs.clone().into_inner();
         let pool = self.conn.clone().into_inner();
         let supervisor = self.supervisor.clone().into_inner();
+        let ha = self.ha.clone().into_inner();
 
         debug!("enqueueing run");
-        let run_id = enqueue_worker(&username, fs, pool, supervisor, message).await?;
+        let trigger = RunTrigger::user();
+        let run_id = enqueue_worker(&username, fs, pool, supervisor, ha, message, trigger).await?;
         self.scanner
             .replace(FileScanner::new(self.config.as_ref(), &run_id));
         self.run_id.replace(run_id.to_owned());
@@ -135,33 +145,19 @@ async fn send_log(session: &mut Session, content: String) {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::encode_log;
-    use bld_models::dtos::ExecServerMessage;
-
-    #[test]
-    fn encode_log_round_trips_as_exec_server_message() {
-        let data = encode_log("hello world".to_string()).unwrap();
-        let message: ExecServerMessage = serde_json::from_str(&data).unwrap();
-        match message {
-            ExecServerMessage::Log { content } => assert_eq!(content, "hello world"),
-            _ => panic!("expected ExecServerMessage::Log variant"),
-        }
-    }
-}
-
+#[allow(clippy::too_many_arguments)]
 pub async fn ws(
     user: Option<User>,
     req: HttpRequest,
     body: Payload,
     config: Data<BldConfig>,
     supervisor: Data<SupervisorMessageSender>,
+    ha: Data<HighAvailability>,
     conn: Data<DatabaseConnection>,
     fs: Data<FileSystem>,
 ) -> actix_web::Result<impl Responder> {
     let user = user.ok_or_else(|| ErrorUnauthorized(""))?;
-    let mut socket = ExecWebsocket::new(config, supervisor, conn, fs, user);
+    let mut socket = ExecWebsocket::new(config, supervisor, ha, conn, fs, user);
     let (response, mut handler) = session::handle(&req, body)?;
 
     spawn(async move {
@@ -206,3 +202,19 @@ pub async fn ws(
 
     Ok(response)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::encode_log;
+    use bld_models::dtos::ExecServerMessage;
+
+    #[test]
+    fn encode_log_round_trips_as_exec_server_message() {
+        let data = encode_log("hello world".to_string()).unwrap();
+        let message: ExecServerMessage = serde_json::from_str(&data).unwrap();
+        match message {
+            ExecServerMessage::Log { content } => assert_eq!(content, "hello world"),
+            _ => panic!("expected ExecServerMessage::Log variant"),
+        }
+    }
+}
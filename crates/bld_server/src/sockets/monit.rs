@@ -10,7 +10,7 @@ use anyhow::{Result, bail};
 use bld_config::BldConfig;
 use bld_core::scanner::FileScanner;
 use bld_models::{
-    dtos::MonitInfo,
+    dtos::{AccessRole, MonitInfo},
     pipeline_runs::{self, PR_STATE_FAULTED, PR_STATE_FINISHED},
 };
 use bld_sock::session::{self, WebSocketMessage};
@@ -24,15 +24,17 @@ pub struct MonitorPipelineSocket {
     id: Option<String>,
     conn: Data<DatabaseConnection>,
     config: Data<BldConfig>,
+    user: User,
     scanner: Option<FileScanner>,
 }
 
 impl MonitorPipelineSocket {
-    pub fn new(conn: Data<DatabaseConnection>, config: Data<BldConfig>) -> Self {
+    pub fn new(conn: Data<DatabaseConnection>, config: Data<BldConfig>, user: User) -> Self {
         Self {
             id: None,
             conn,
             config,
+            user,
             scanner: None,
         }
     }
@@ -83,8 +85,12 @@ impl MonitorPipelineSocket {
         } else {
             bail!("file not found");
         }?;
+        self.user.authorize(AccessRole::Viewer, &run.name)?;
         debug!("starting scan for run with id {}", run.id);
-        self.scanner = Some(FileScanner::new(self.config.as_ref(), &run.id));
+        self.scanner = Some(match data.job.as_deref() {
+            Some(job) => FileScanner::job(self.config.as_ref(), &run.id, job),
+            None => FileScanner::new(self.config.as_ref(), &run.id),
+        });
         self.id.replace(run.id);
         Ok(())
     }
@@ -97,8 +103,8 @@ pub async fn ws(
     conn: Data<DatabaseConnection>,
     config: Data<BldConfig>,
 ) -> actix_web::Result<impl Responder> {
-    user.ok_or_else(|| ErrorUnauthorized(""))?;
-    let mut socket = MonitorPipelineSocket::new(conn, config);
+    let user = user.ok_or_else(|| ErrorUnauthorized(""))?;
+    let mut socket = MonitorPipelineSocket::new(conn, config, user);
     let (response, mut handler) = session::handle(&req, body)?;
 
     spawn(async move {
@@ -151,20 +151,8 @@ impl SupervisorMessageSender {
         }
     }
 
-    pub async fn enqueue(
-        &self,
-        pipeline: String,
-        run_id: String,
-        variables: Option<Vec<String>>,
-        environment: Option<Vec<String>>,
-    ) -> Result<()> {
-        let message = ServerMessages::Enqueue {
-            pipeline,
-            run_id,
-            inputs: variables,
-            env: environment,
-        };
-
+    /// Sends the enqueue message of a run to the supervisor.
+    pub async fn enqueue(&self, message: ServerMessages) -> Result<()> {
         self.tx.send(message).await.map_err(|e| anyhow!(e))
     }
 
@@ -1,28 +1,52 @@
-use crate::supervisor::channel::SupervisorMessageSender;
+use crate::{
+    high_availability::{HaCommand, HighAvailability},
+    supervisor::channel::SupervisorMessageSender,
+};
 use anyhow::{Result, bail};
 use bld_core::fs::FileSystem;
 use bld_models::{
-    dtos::ExecClientMessage,
-    pipeline_runs::{self, InsertPipelineRun},
+    cron_jobs,
+    dtos::{ExecClientMessage, ServerMessages},
+    pipeline_run_environment_variables, pipeline_run_inputs,
+    pipeline_runs::{self, InsertPipelineRun, PR_TRIGGER_CRON, PipelineRuns, RunTrigger},
 };
+use bld_runner::VersionedFile;
 use bld_utils::fs::IsYaml;
 use sea_orm::DatabaseConnection;
 use std::{collections::HashMap, sync::Arc};
 use tracing::{debug, error};
 use uuid::Uuid;
 
+/// Records the run through the raft group, so that every member has it, and then sends
+/// it to the supervisor of the server.
 pub async fn enqueue_worker(
     user_name: &str,
     fs: Arc<FileSystem>,
     conn: Arc<DatabaseConnection>,
     supervisor_sender: Arc<SupervisorMessageSender>,
+    ha: Arc<HighAvailability>,
     data: ExecClientMessage,
+    trigger: RunTrigger,
 ) -> Result<String> {
-    let ExecClientMessage::EnqueueRun {
-        name,
-        env: environment,
-        inputs: variables,
-    } = data;
+    let (name, environment, variables, trigger, priority) = match data {
+        ExecClientMessage::EnqueueRun {
+            name,
+            env,
+            inputs,
+            parent_run_id,
+            priority,
+        } => {
+            let trigger = match parent_run_id {
+                Some(parent_run_id) => RunTrigger::parent_run(&parent_run_id),
+                None => trigger,
+            };
+            (name, env, inputs, trigger, priority.unwrap_or_default())
+        }
+        ExecClientMessage::Rerun { run_id } => {
+            let (name, env, inputs) = rerun_parameters(conn.as_ref(), &run_id).await?;
+            (name, Some(env), Some(inputs), RunTrigger::rerun(&run_id), 0)
+        }
+    };
 
     let path = fs.path(&name).await?;
     if !path.is_yaml() {
@@ -30,18 +54,34 @@ pub async fn enqueue_worker(
     }
 
     let run_id = Uuid::new_v4().to_string();
-    let model = InsertPipelineRun {
+
+    let file: VersionedFile = serde_yaml_ng::from_str(&fs.read(&name).await?)?;
+    let message = enqueue_message(
+        &fs,
+        &file,
+        &name,
+        &run_id,
+        user_name,
+        variables.clone(),
+        environment.clone(),
+        priority,
+    )?;
+
+    let mut inputs = file.inputs_map();
+    inputs.extend(variables.unwrap_or_default());
+
+    let run = InsertPipelineRun {
         id: run_id.to_owned(),
-        name: name.to_owned(),
+        name,
         app_user: user_name.to_owned(),
+        trigger,
+        inputs,
+        env: environment.unwrap_or_default(),
     };
-    pipeline_runs::insert(conn.as_ref(), model).await?;
-
-    let variables = variables.map(hash_map_to_var_string);
-    let environment = environment.map(hash_map_to_var_string);
+    ha.apply(HaCommand::EnqueueRun { run }).await?;
 
     supervisor_sender
-        .enqueue(name, run_id.to_owned(), variables, environment)
+        .enqueue(message)
         .await
         .map(|_| {
             debug!("sent message to supervisor receiver");
@@ -53,6 +93,88 @@ pub async fn enqueue_worker(
         })
 }
 
+/// Sends a run that has already been recorded to the supervisor of the server, which is
+/// how a new leader of a raft group takes over the runs that were still queued when the
+/// previous one failed. The sensitive environment variables of the run are lost, since
+/// they were never persisted, and so is its priority unless it was started by a cron job.
+pub async fn requeue_worker(
+    fs: &FileSystem,
+    conn: &DatabaseConnection,
+    supervisor_sender: &SupervisorMessageSender,
+    run: &PipelineRuns,
+) -> Result<()> {
+    let (name, env, inputs) = rerun_parameters(conn, &run.id).await?;
+    let file: VersionedFile = serde_yaml_ng::from_str(&fs.read(&name).await?)?;
+    let priority = match (run.trigger_type.as_deref(), run.trigger_id.as_deref()) {
+        (Some(PR_TRIGGER_CRON), Some(job_id)) => cron_jobs::select_by_id(conn, job_id)
+            .await
+            .map(|job| job.priority)
+            .unwrap_or_default(),
+        _ => 0,
+    };
+    let message = enqueue_message(
+        fs,
+        &file,
+        &name,
+        &run.id,
+        &run.app_user,
+        Some(inputs),
+        Some(env),
+        priority,
+    )?;
+    supervisor_sender.enqueue(message).await
+}
+
+#[allow(clippy::too_many_arguments)]
+fn enqueue_message(
+    fs: &FileSystem,
+    file: &VersionedFile,
+    name: &str,
+    run_id: &str,
+    user_name: &str,
+    variables: Option<HashMap<String, String>>,
+    environment: Option<HashMap<String, String>>,
+    priority: i32,
+) -> Result<ServerMessages> {
+    let concurrency = file.concurrency_group(
+        fs.config().clone(),
+        variables.clone().unwrap_or_default(),
+        environment.clone().unwrap_or_default(),
+        run_id,
+    )?;
+
+    Ok(ServerMessages::Enqueue {
+        pipeline: name.to_owned(),
+        run_id: run_id.to_owned(),
+        inputs: variables.map(hash_map_to_var_string),
+        env: environment.map(hash_map_to_var_string),
+        concurrency,
+        labels: file.labels(),
+        user: user_name.to_owned(),
+        priority,
+    })
+}
+
+/// Loads the pipeline, inputs and environment of a previous run, meaning everything
+/// but the sensitive environment variables that were never persisted.
+async fn rerun_parameters(
+    conn: &DatabaseConnection,
+    run_id: &str,
+) -> Result<(String, HashMap<String, String>, HashMap<String, String>)> {
+    let run = pipeline_runs::select_by_id(conn, run_id).await?;
+    let env = pipeline_run_environment_variables::select_by_run_id(conn, run_id)
+        .await?
+        .into_iter()
+        .map(|x| (x.name, x.value))
+        .collect();
+    let inputs = pipeline_run_inputs::select_by_run_id(conn, run_id)
+        .await?
+        .into_iter()
+        .map(|x| (x.name, x.value))
+        .collect();
+    Ok((run.name, env, inputs))
+}
+
 fn hash_map_to_var_string(hmap: HashMap<String, String>) -> Vec<String> {
     hmap.iter().map(|(k, v)| format!("{k}={v}")).collect()
 }
@@ -0,0 +1,218 @@
+use anyhow::{Result, anyhow, bail};
+use glob::Pattern;
+use ring::hmac;
+use serde_json::Value;
+
+/// The header with the hex encoded HMAC-SHA256 signature of the payload, optionally
+/// prefixed by `sha256=`, as sent by GitHub, Gitea and Forgejo.
+pub const SIGNATURE_HEADER: &str = "X-Hub-Signature-256";
+
+/// The header with the type of a GitHub event, used to ignore the ping sent when a
+/// webhook is created.
+pub const GITHUB_EVENT_HEADER: &str = "X-GitHub-Event";
+
+const BRANCH_REF_PREFIX: &str = "refs/heads/";
+
+/// Trigger names are part of the webhook url so they are limited to the characters
+/// that don't need to be encoded.
+pub fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        bail!("trigger name cannot be empty");
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        bail!("trigger name can only contain alphanumeric characters, '_' and '-'");
+    }
+    Ok(())
+}
+
+pub fn validate_patterns(patterns: &[String]) -> Result<()> {
+    for pattern in patterns {
+        Pattern::new(pattern).map_err(|e| anyhow!("invalid pattern {pattern}, {e}"))?;
+    }
+    Ok(())
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.is_ascii() || !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&value[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Verifies that the signature is the HMAC-SHA256 of the payload using the secret of
+/// the trigger as the key.
+pub fn verify_signature(secret: &str, payload: &[u8], signature: &str) -> Result<()> {
+    let signature = signature.strip_prefix("sha256=").unwrap_or(signature);
+    let tag = decode_hex(signature).ok_or_else(|| anyhow!("invalid payload signature"))?;
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    hmac::verify(&key, payload, &tag).map_err(|_| anyhow!("invalid payload signature"))
+}
+
+/// Looks up a dot separated path of the payload, such as `repository.clone_url` or
+/// `commits.0.id`. Strings are returned as they are and any other value as JSON.
+pub fn lookup(payload: &Value, path: &str) -> Option<String> {
+    let value = path
+        .split('.')
+        .try_fold(payload, |value, segment| match value {
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => value.get(segment),
+        })?;
+
+    match value {
+        Value::Null => None,
+        Value::String(value) => Some(value.to_owned()),
+        value => Some(value.to_string()),
+    }
+}
+
+fn branch(payload: &Value) -> Option<&str> {
+    payload
+        .get("ref")?
+        .as_str()?
+        .strip_prefix(BRANCH_REF_PREFIX)
+}
+
+fn changed_paths(payload: &Value) -> Vec<&str> {
+    payload
+        .get("commits")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .flat_map(|commit| {
+            ["added", "modified", "removed"]
+                .into_iter()
+                .filter_map(|key| commit.get(key).and_then(Value::as_array))
+                .flatten()
+        })
+        .filter_map(Value::as_str)
+        .collect()
+}
+
+fn matches_any(patterns: &[String], value: &str) -> bool {
+    patterns
+        .iter()
+        .filter_map(|pattern| Pattern::new(pattern).ok())
+        .any(|pattern| pattern.matches(value))
+}
+
+/// Checks the payload against the branch and path filters of a trigger, returning the
+/// reason that no run should be started when it doesn't pass them. The branch is read
+/// from the pushed `ref` and the paths from the files changed by its `commits`.
+pub fn filter(branches: &[String], paths: &[String], payload: &Value) -> Option<String> {
+    if !branches.is_empty() {
+        let Some(branch) = branch(payload) else {
+            return Some("payload is not a push to a branch".to_owned());
+        };
+        if !matches_any(branches, branch) {
+            return Some(format!("branch {branch} doesn't match the branch filters"));
+        }
+    }
+
+    if !paths.is_empty()
+        && !changed_paths(payload)
+            .into_iter()
+            .any(|path| matches_any(paths, path))
+    {
+        return Some("no changed path matches the path filters".to_owned());
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sign(secret: &str, payload: &[u8]) -> String {
+        let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+        let tag = hmac::sign(&key, payload);
+        let hex: String = tag.as_ref().iter().map(|b| format!("{b:02x}")).collect();
+        format!("sha256={hex}")
+    }
+
+    fn push_payload() -> Value {
+        json!({
+            "ref": "refs/heads/release/1.0",
+            "after": "0d1a26e67d8f5eaf1f6ba5c57fc3c7d91ac0fd1c",
+            "repository": { "clone_url": "https://example.com/org/repo.git" },
+            "commits": [
+                { "added": ["docs/index.md"], "modified": [], "removed": [] },
+                { "added": [], "modified": ["src/main.rs"], "removed": ["old.txt"] }
+            ]
+        })
+    }
+
+    #[test]
+    fn verify_signature_accepts_payload_signed_with_the_secret() {
+        let payload = br#"{"ref":"refs/heads/main"}"#;
+        let signature = sign("secret", payload);
+
+        assert!(verify_signature("secret", payload, &signature).is_ok());
+        assert!(
+            verify_signature("secret", payload, signature.trim_start_matches("sha256=")).is_ok()
+        );
+    }
+
+    #[test]
+    fn verify_signature_rejects_other_secret_or_tampered_payload() {
+        let payload = br#"{"ref":"refs/heads/main"}"#;
+        let signature = sign("secret", payload);
+
+        assert!(verify_signature("other", payload, &signature).is_err());
+        assert!(verify_signature("secret", br#"{"ref":"refs/heads/dev"}"#, &signature).is_err());
+        assert!(verify_signature("secret", payload, "sha256=not-hex").is_err());
+    }
+
+    #[test]
+    fn lookup_reads_nested_fields_and_array_items() {
+        let payload = push_payload();
+
+        assert_eq!(
+            lookup(&payload, "repository.clone_url").as_deref(),
+            Some("https://example.com/org/repo.git")
+        );
+        assert_eq!(
+            lookup(&payload, "commits.1.removed.0").as_deref(),
+            Some("old.txt")
+        );
+        assert_eq!(
+            lookup(&payload, "commits.0.modified").as_deref(),
+            Some("[]")
+        );
+        assert_eq!(lookup(&payload, "repository.missing"), None);
+    }
+
+    #[test]
+    fn filter_passes_matching_branch_and_paths() {
+        let payload = push_payload();
+        let branches = vec!["main".to_owned(), "release/*".to_owned()];
+        let paths = vec!["src/**".to_owned()];
+
+        assert_eq!(filter(&branches, &paths, &payload), None);
+        assert_eq!(filter(&[], &[], &payload), None);
+    }
+
+    #[test]
+    fn filter_rejects_other_branches_tags_and_paths() {
+        let payload = push_payload();
+        let tag_payload = json!({ "ref": "refs/tags/v1.0" });
+
+        assert!(filter(&["main".to_owned()], &[], &payload).is_some());
+        assert!(filter(&["*".to_owned()], &[], &tag_payload).is_some());
+        assert!(filter(&[], &["tests/**".to_owned()], &payload).is_some());
+    }
+
+    #[test]
+    fn validate_name_rejects_characters_that_need_encoding() {
+        assert!(validate_name("github-push_1").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("with/slash").is_err());
+    }
+}
@@ -0,0 +1,55 @@
+use anyhow::{Result, anyhow};
+use awc::ws::Frame;
+use bld_config::BldConfig;
+use bld_http::WebSock;
+use bld_models::dtos::{ServerMessages, WorkerMessages};
+use std::sync::Arc;
+use tracing::debug;
+
+pub struct AgentClient {
+    sock: WebSock,
+}
+
+impl AgentClient {
+    /// Connects to the supervisor and registers the agent with its labels and capacity,
+    /// using the token that the supervisor accepts agents with.
+    pub async fn connect(
+        config: Arc<BldConfig>,
+        name: &str,
+        token: &str,
+        labels: &[String],
+        capacity: usize,
+    ) -> Result<Self> {
+        let url = format!("{}/v1/ws-agent/", config.local.supervisor.base_url_ws());
+        debug!("establishing web socket connection on {}", url);
+        let mut sock = WebSock::connect(&url, None).await?;
+        sock.binary(&WorkerMessages::Ack).await?;
+        sock.binary(&WorkerMessages::Register {
+            name: name.to_owned(),
+            token: token.to_owned(),
+            labels: labels.to_vec(),
+            capacity,
+        })
+        .await?;
+        Ok(Self { sock })
+    }
+
+    pub async fn send(&mut self, message: &WorkerMessages) -> Result<()> {
+        self.sock.binary(message).await
+    }
+
+    /// Waits for the next message of the supervisor, returning none when it has closed
+    /// the connection.
+    pub async fn next(&mut self) -> Result<Option<ServerMessages>> {
+        loop {
+            match self.sock.next().await? {
+                Frame::Binary(bytes) => return Ok(Some(serde_json::from_slice(&bytes)?)),
+                Frame::Text(bytes) => {
+                    return Err(anyhow!("{}", String::from_utf8_lossy(&bytes)));
+                }
+                Frame::Close(_) => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+}
@@ -1,9 +1,11 @@
+mod agent_ws_client;
 mod enqueue_ws_client;
 mod exec_ws_client;
 mod login_ws_client;
 mod monit_ws_client;
 mod worker_ws_client;
 
+pub use agent_ws_client::*;
 pub use enqueue_ws_client::*;
 pub use exec_ws_client::*;
 pub use login_ws_client::*;
@@ -1,24 +1,29 @@
 use anyhow::Result;
 use awc::ws::Frame;
-use bld_config::BldConfig;
+use bld_config::{BldConfig, definitions::WORKER_HEARTBEAT_INTERVAL};
 use bld_core::logger::Logger;
 use bld_http::WebSock;
 use bld_models::dtos::WorkerMessages;
-use std::sync::Arc;
-use tokio::sync::mpsc::Receiver;
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::mpsc::Receiver, time::interval};
 use tracing::debug;
 
 pub struct WorkerClient {
+    run_id: String,
     logger: Logger,
     sock: WebSock,
 }
 
 impl WorkerClient {
-    pub async fn connect(config: Arc<BldConfig>, logger: Logger) -> Result<Self> {
+    pub async fn connect(config: Arc<BldConfig>, run_id: &str, logger: Logger) -> Result<Self> {
         let url = format!("{}/v1/ws-worker/", config.local.supervisor.base_url_ws());
         debug!("establishing web socket connection on {}", url);
         let sock = WebSock::connect(&url, None).await?;
-        Ok(Self { logger, sock })
+        Ok(Self {
+            run_id: run_id.to_owned(),
+            logger,
+            sock,
+        })
     }
 
     pub async fn run(mut self, mut worker_rx: Receiver<WorkerMessages>) -> Result<()> {
@@ -26,11 +31,17 @@ impl WorkerClient {
         self.sock
             .binary(&WorkerMessages::WhoAmI {
                 pid: std::process::id(),
+                run_id: self.run_id.to_owned(),
             })
             .await?;
 
+        let mut heartbeat = interval(Duration::from_secs(WORKER_HEARTBEAT_INTERVAL));
+
         loop {
             tokio::select! {
+                _ = heartbeat.tick() => {
+                    self.sock.binary(&WorkerMessages::Heartbeat).await?;
+                }
                 msg = worker_rx.recv() => {
                     // a closed channel means the runner is done, exit instead of
                     // keeping the process alive until the supervisor closes the socket
@@ -0,0 +1,126 @@
+use crate::queues::worker_command;
+use anyhow::{Result, bail};
+use bld_config::BldConfig;
+use bld_core::workers::Worker;
+use bld_models::dtos::{ServerMessages, WorkerMessages};
+use bld_sock::AgentClient;
+use bld_utils::sync::IntoArc;
+use std::time::Duration;
+use tokio::time::{interval, sleep};
+use tracing::{error, info};
+
+const RETRY_DELAY: u64 = 5000;
+const EXIT_CHECK_INTERVAL: u64 = 1000;
+
+/// Starts an agent that connects to the supervisor and spawns the workers of the runs that
+/// are routed to it, reconnecting whenever the connection is lost. Only the routing of the
+/// runs goes through the supervisor. The workers read the pipelines and the state of the
+/// runs and write their logs directly, so an agent only works where it has the .bld
+/// directory, logs path and database of the server.
+pub async fn start(
+    config: BldConfig,
+    name: String,
+    labels: Vec<String>,
+    capacity: usize,
+) -> Result<()> {
+    let Some(token) = config.local.supervisor.agent_token.clone() else {
+        bail!("the supervisor config has no agent_token to register the agent with");
+    };
+    let config = config.into_arc();
+    loop {
+        match AgentClient::connect(config.clone(), &name, &token, &labels, capacity).await {
+            Ok(client) => {
+                info!("agent {name} connected to the supervisor");
+                if let Err(e) = run(client).await {
+                    error!("{e}");
+                }
+            }
+            Err(e) => error!("connection to supervisor web socket failed due to {e}"),
+        }
+        info!("reconnecting to the supervisor in {RETRY_DELAY}ms");
+        sleep(Duration::from_millis(RETRY_DELAY)).await;
+    }
+}
+
+async fn run(mut client: AgentClient) -> Result<()> {
+    let mut workers: Vec<Worker> = vec![];
+    let mut exit_check = interval(Duration::from_millis(EXIT_CHECK_INTERVAL));
+
+    let result = loop {
+        tokio::select! {
+            msg = client.next() => match msg {
+                Ok(Some(msg)) => {
+                    if let Err(e) = handle_message(msg, &mut workers, &mut client).await {
+                        break Err(e);
+                    }
+                }
+                Ok(None) => break Ok(()),
+                Err(e) => break Err(e),
+            },
+            _ = exit_check.tick() => {
+                if let Err(e) = report_exited(&mut workers, &mut client).await {
+                    break Err(e);
+                }
+            }
+        }
+    };
+
+    // the supervisor faults the runs of an agent that disconnects, so their
+    // workers are stopped instead of being left to run without it.
+    for worker in workers.iter_mut() {
+        info!("stopping worker of run {}", worker.get_run_id());
+        let _ = worker.stop().await.inspect_err(|e| error!("{e}"));
+        let _ = worker.cleanup().await.inspect_err(|e| error!("{e}"));
+    }
+
+    result
+}
+
+/// Lets the supervisor know about the workers whose process has exited, so that it can
+/// route another run to the agent.
+async fn report_exited(workers: &mut Vec<Worker>, client: &mut AgentClient) -> Result<()> {
+    let exited: Vec<Worker> = workers.extract_if(.., |w| w.completed()).collect();
+    for worker in exited {
+        let run_id = worker.get_run_id().to_owned();
+        info!("worker of run {run_id} has exited");
+        client.send(&WorkerMessages::Exited { run_id }).await?;
+    }
+    Ok(())
+}
+
+async fn handle_message(
+    msg: ServerMessages,
+    workers: &mut Vec<Worker>,
+    client: &mut AgentClient,
+) -> Result<()> {
+    match msg {
+        ServerMessages::Ack => {}
+
+        ServerMessages::Enqueue {
+            pipeline,
+            run_id,
+            inputs,
+            env,
+            ..
+        } => {
+            info!("supervisor routed run {run_id} of pipeline: {pipeline}");
+            let command = worker_command(&pipeline, &run_id, inputs.as_deref(), env.as_deref())?;
+            let mut worker = Worker::new(run_id.to_owned(), None, command);
+            match worker.spawn() {
+                Ok(_) => workers.push(worker),
+                Err(e) => {
+                    error!("unable to spawn worker of run {run_id}. {e}");
+                    client.send(&WorkerMessages::Exited { run_id }).await?;
+                }
+            }
+        }
+
+        ServerMessages::Stop { run_id } => {
+            info!("supervisor sent a stop message for run_id: {run_id}");
+            if let Some(worker) = workers.iter_mut().find(|w| w.has_run_id(&run_id)) {
+                worker.stop().await?;
+            }
+        }
+    }
+    Ok(())
+}
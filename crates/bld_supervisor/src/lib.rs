@@ -1,3 +1,4 @@
+pub mod agent;
 mod queues;
 pub mod sockets;
 pub mod supervisor;
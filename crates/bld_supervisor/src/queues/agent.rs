@@ -0,0 +1,35 @@
+use bld_models::dtos::ServerMessages;
+use tokio::sync::mpsc;
+
+/// An agent that has connected to the supervisor in order to spawn the workers of the runs
+/// that need its labels.
+#[derive(Debug)]
+pub struct Agent {
+    pub id: String,
+    pub name: String,
+    pub labels: Vec<String>,
+    pub capacity: usize,
+    pub tx: mpsc::Sender<ServerMessages>,
+}
+
+impl Agent {
+    pub fn new(
+        id: String,
+        name: String,
+        labels: Vec<String>,
+        capacity: usize,
+        tx: mpsc::Sender<ServerMessages>,
+    ) -> Self {
+        Self {
+            id,
+            name,
+            labels,
+            capacity,
+            tx,
+        }
+    }
+
+    pub fn has_labels(&self, labels: &[String]) -> bool {
+        labels.iter().all(|label| self.labels.contains(label))
+    }
+}
@@ -1,3 +1,7 @@
+mod agent;
+mod worker;
 mod worker_queue;
 
+pub use agent::*;
+pub use worker::*;
 pub use worker_queue::*;
@@ -0,0 +1,60 @@
+use anyhow::{Result, bail};
+use bld_core::workers::Worker;
+use bld_models::dtos::ServerMessages;
+use std::env::current_exe;
+use tokio::process::Command;
+use tracing::error;
+
+/// The command that spawns the worker process of a run on the current host.
+pub fn worker_command(
+    pipeline: &str,
+    run_id: &str,
+    inputs: Option<&[String]>,
+    env: Option<&[String]>,
+) -> Result<Command> {
+    let exe = current_exe().map_err(|e| {
+        error!("could not get the current executable. {e}");
+        e
+    })?;
+    let mut command = Command::new(exe);
+    command.arg("worker");
+    command.arg("--pipeline");
+    command.arg(pipeline);
+    command.arg("--run-id");
+    command.arg(run_id);
+    for entry in inputs.unwrap_or_default() {
+        command.arg("--input");
+        command.arg(entry);
+    }
+    for entry in env.unwrap_or_default() {
+        command.arg("--environment");
+        command.arg(entry);
+    }
+    Ok(command)
+}
+
+/// The worker of an enqueue message, which is spawned locally unless the run needs labels
+/// in which case it's spawned by an agent.
+pub fn enqueued_worker(message: &ServerMessages) -> Result<Worker> {
+    let ServerMessages::Enqueue {
+        pipeline,
+        run_id,
+        inputs,
+        env,
+        concurrency,
+        labels,
+        user,
+        priority,
+    } = message
+    else {
+        bail!("only enqueue messages have a worker");
+    };
+    let (run_id, concurrency) = (run_id.to_owned(), concurrency.clone());
+    let worker = if labels.is_empty() {
+        let command = worker_command(pipeline, &run_id, inputs.as_deref(), env.as_deref())?;
+        Worker::new(run_id, concurrency, command)
+    } else {
+        Worker::remote(run_id, concurrency, labels.clone(), message.clone())
+    };
+    Ok(worker.queued_as(pipeline, user, *priority))
+}
@@ -1,17 +1,33 @@
 use actix_web::{rt::spawn, web::Data};
 use anyhow::{Error, Result, anyhow};
-use bld_config::BldConfig;
+use bld_config::{BldConfig, definitions::WORKER_HEARTBEAT_INTERVAL};
 use bld_core::{platform::docker, workers::Worker};
 use bld_models::{
+    dtos::ServerMessages,
     pipeline_run_containers::{self, PRC_STATE_REMOVED},
-    pipeline_runs::{self, PR_STATE_FAULTED, PR_STATE_FINISHED, PR_STATE_QUEUED},
+    pipeline_run_queue::{self, InsertPipelineRunQueue},
+    pipeline_runs::{
+        self, PR_STATE_FAULTED, PR_STATE_QUEUED, PR_STATE_RUNNING, PR_STATE_WAITING_APPROVAL,
+    },
 };
 use bld_utils::sync::IntoArc;
 use bollard::{Docker, container::RemoveContainerOptions, errors::Error as BollardError};
 use sea_orm::DatabaseConnection;
-use std::{collections::VecDeque, sync::Arc};
-use tokio::sync::{mpsc, oneshot};
+use std::{
+    cmp::Reverse,
+    collections::{HashSet, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    fs::remove_dir_all,
+    sync::{mpsc, oneshot},
+    time::interval,
+};
 use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use super::{Agent, enqueued_worker};
 
 fn oneshot_send_err<T>(_: T) -> Error {
     anyhow!("oneshot receiver dropped")
@@ -20,11 +36,11 @@ fn oneshot_send_err<T>(_: T) -> Error {
 #[derive(Debug)]
 pub enum WorkerQueueMessage {
     Enqueue {
-        worker: Box<Worker>,
+        message: Box<ServerMessages>,
         resp_tx: oneshot::Sender<Result<()>>,
     },
     Dequeue {
-        pid: u32,
+        run_id: String,
         resp_tx: oneshot::Sender<Result<()>>,
     },
     Stop {
@@ -32,18 +48,43 @@ pub enum WorkerQueueMessage {
         resp_tx: oneshot::Sender<Result<()>>,
     },
     Contains {
-        pid: u32,
+        run_id: String,
         resp_tx: oneshot::Sender<bool>,
     },
+    AddAgent {
+        agent: Box<Agent>,
+        resp_tx: oneshot::Sender<Result<()>>,
+    },
+    RemoveAgent {
+        id: String,
+        resp_tx: oneshot::Sender<Result<()>>,
+    },
+    Heartbeat {
+        run_id: String,
+        resp_tx: oneshot::Sender<Result<()>>,
+    },
 }
 
 /// The WorkerQueueReceiver is initialized with a capacity of active workers.
 /// If there are more workers than the specified capacity, the queue manager
-/// will add them to a backlog based on when they were enqueued.
+/// will add them to a backlog that is persisted in the database, so that it can be
+/// rebuilt when the supervisor starts. Workers leave the backlog by their priority, which
+/// rises every time another worker leaves ahead of them, and then by how few runs their
+/// user and pipeline already have active, so that a pipeline that is enqueued often
+/// doesn't starve the rest, and finally by when they were enqueued. Workers that belong
+/// to a concurrency group also wait in the backlog while another worker of the same
+/// group is active. Workers of runs waiting for approval stay active, since their process
+/// keeps running while it polls for the decision. Workers of runs that need labels are spawned by a
+/// connected agent with those labels instead, and take up the capacity of that agent.
+/// Workers whose process exits without completing their run, or that stop sending
+/// heartbeats, are removed and their runs are marked as faulted.
 struct WorkerQueueReceiver {
     capacity: usize,
+    heartbeat_timeout: Duration,
+    agents: Vec<Agent>,
     active: Vec<Worker>,
     backlog: VecDeque<Worker>,
+    config: Data<BldConfig>,
     conn: Data<DatabaseConnection>,
     docker: Arc<Docker>,
     rx: mpsc::Receiver<WorkerQueueMessage>,
@@ -57,19 +98,15 @@ impl WorkerQueueReceiver {
         rx: mpsc::Receiver<WorkerQueueMessage>,
     ) -> Result<Self> {
         let docker = docker(config.as_ref(), None)?.into_arc();
-        let docker_clone = docker.clone();
-        let conn_clone = conn.clone();
-
-        spawn(async move {
-            if let Err(e) = try_cleanup_containers(docker_clone, conn_clone).await {
-                error!("error while cleaning up containers, {e}");
-            }
-        });
+        let heartbeat_timeout = Duration::from_secs(config.local.supervisor.heartbeat_timeout);
 
         Ok(Self {
             capacity,
+            heartbeat_timeout,
+            agents: vec![],
             active: Vec::with_capacity(capacity),
             backlog: VecDeque::new(),
+            config,
             conn,
             docker,
             rx,
@@ -77,31 +114,105 @@ impl WorkerQueueReceiver {
     }
 
     pub async fn receive(mut self) -> Result<()> {
-        while let Some(msg) = self.rx.recv().await {
-            match msg {
-                WorkerQueueMessage::Enqueue { worker, resp_tx } => {
-                    let result = self.enqueue(worker).await;
-                    resp_tx.send(result).map_err(oneshot_send_err)?;
-                }
-                WorkerQueueMessage::Dequeue { pid, resp_tx } => {
-                    let result = self.dequeue(pid).await;
-                    resp_tx.send(result).map_err(oneshot_send_err)?;
-                }
-                WorkerQueueMessage::Stop { run_id, resp_tx } => {
-                    let result = self.stop(run_id).await;
-                    resp_tx.send(result).map_err(oneshot_send_err)?;
+        // the runs of a raft group are replicated to every member, so they are reconciled
+        // by the leader of the group when it takes over the queue instead.
+        if self.config.local.server.ha.is_none()
+            && let Err(e) = self.reconcile().await
+        {
+            error!("error while reconciling the runs in progress, {e}");
+        }
+
+        if let Err(e) = self.restore().await {
+            error!("error while restoring the queued runs, {e}");
+        }
+
+        let docker = self.docker.clone();
+        let conn = self.conn.clone();
+        spawn(async move {
+            if let Err(e) = try_cleanup_containers(docker, conn).await {
+                error!("error while cleaning up containers, {e}");
+            }
+        });
+
+        let mut reaper = interval(Duration::from_secs(WORKER_HEARTBEAT_INTERVAL));
+
+        loop {
+            tokio::select! {
+                msg = self.rx.recv() => {
+                    let Some(msg) = msg else {
+                        break;
+                    };
+                    self.handle(msg).await?;
                 }
-                WorkerQueueMessage::Contains { pid, resp_tx } => {
-                    let result = self.contains(pid);
-                    resp_tx.send(result).map_err(oneshot_send_err)?;
+                _ = reaper.tick() => {
+                    if let Err(e) = self.reap().await {
+                        error!("error while removing the workers that are gone, {e}");
+                    }
                 }
             }
         }
         Ok(())
     }
 
-    fn activate(&mut self, mut worker: Worker) -> Result<()> {
-        worker.spawn().map_err(|e| {
+    async fn handle(&mut self, msg: WorkerQueueMessage) -> Result<()> {
+        match msg {
+            WorkerQueueMessage::Enqueue { message, resp_tx } => {
+                let result = self.enqueue(*message).await;
+                resp_tx.send(result).map_err(oneshot_send_err)?;
+            }
+            WorkerQueueMessage::Dequeue { run_id, resp_tx } => {
+                let result = self.dequeue(&run_id).await;
+                resp_tx.send(result).map_err(oneshot_send_err)?;
+            }
+            WorkerQueueMessage::Stop { run_id, resp_tx } => {
+                let result = self.stop(run_id).await;
+                resp_tx.send(result).map_err(oneshot_send_err)?;
+            }
+            WorkerQueueMessage::Contains { run_id, resp_tx } => {
+                let result = self.contains(&run_id);
+                resp_tx.send(result).map_err(oneshot_send_err)?;
+            }
+            WorkerQueueMessage::AddAgent { agent, resp_tx } => {
+                let result = self.add_agent(*agent).await;
+                resp_tx.send(result).map_err(oneshot_send_err)?;
+            }
+            WorkerQueueMessage::RemoveAgent { id, resp_tx } => {
+                let result = self.remove_agent(&id).await;
+                resp_tx.send(result).map_err(oneshot_send_err)?;
+            }
+            WorkerQueueMessage::Heartbeat { run_id, resp_tx } => {
+                self.heartbeat(&run_id);
+                resp_tx.send(Ok(())).map_err(oneshot_send_err)?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn activate(&mut self, mut worker: Worker) -> Result<()> {
+        if worker.get_concurrency().is_some() {
+            pipeline_runs::update_waiting_on_group(self.conn.as_ref(), worker.get_run_id(), None)
+                .await?;
+        }
+        let spawned = if worker.is_remote() {
+            match self.available_agent(&worker) {
+                Some(agent) => {
+                    info!(
+                        "routing run {} to agent {}",
+                        worker.get_run_id(),
+                        agent.name
+                    );
+                    let (id, tx) = (agent.id.to_owned(), agent.tx.clone());
+                    worker.spawn_on(&id, tx).await
+                }
+                None => Err(anyhow!(
+                    "no agent available for run {}",
+                    worker.get_run_id()
+                )),
+            }
+        } else {
+            worker.spawn()
+        };
+        spawned.map_err(|e| {
             error!("{e}");
             e
         })?;
@@ -109,20 +220,96 @@ impl WorkerQueueReceiver {
         Ok(())
     }
 
-    async fn add_backlog(&mut self, worker: Box<Worker>) -> Result<()> {
-        pipeline_runs::update_state(self.conn.as_ref(), worker.get_run_id(), PR_STATE_QUEUED)
-            .await?;
-        self.backlog.push_back(*worker);
-        Ok(())
+    /// Whether the worker can become active, meaning that there is capacity left either
+    /// on the supervisor or on an agent with the labels of the worker.
+    fn has_slot(&self, worker: &Worker) -> bool {
+        if worker.is_remote() {
+            self.available_agent(worker).is_some()
+        } else {
+            local_load(self.active.iter()) < self.capacity
+        }
     }
 
-    fn after_removal(&mut self) -> Result<()> {
-        for _ in 0..(self.capacity - self.active.len()) {
-            if let Some(worker) = self.backlog.pop_front() {
-                self.activate(worker)?;
+    /// The agent with the labels of the worker that has the fewest active workers, as
+    /// long as it has capacity left.
+    fn available_agent(&self, worker: &Worker) -> Option<&Agent> {
+        self.agents
+            .iter()
+            .filter(|agent| agent.has_labels(worker.get_labels()))
+            .map(|agent| {
+                let load = self
+                    .active
+                    .iter()
+                    .filter(|w| w.get_agent() == Some(agent.id.as_str()))
+                    .count();
+                (agent, load)
+            })
+            .filter(|(agent, load)| *load < agent.capacity)
+            .min_by_key(|(_, load)| *load)
+            .map(|(agent, _)| agent)
+    }
+
+    /// Rebuilds the backlog from the runs that were still queued when the supervisor
+    /// stopped, dropping the ones that have since left the queued state.
+    async fn restore(&mut self) -> Result<()> {
+        let conn = self.conn.as_ref();
+        let entries = pipeline_run_queue::select_all(conn).await?;
+        info!("found {} queued runs to restore", entries.len());
+
+        for entry in entries {
+            let run = pipeline_runs::select_by_id(conn, &entry.run_id).await;
+            if run.is_ok_and(|r| r.state != PR_STATE_QUEUED) {
+                pipeline_run_queue::delete_by_run_id(conn, &entry.run_id).await?;
+                continue;
+            }
+            let worker = serde_json::from_str(&entry.message)
+                .map_err(|e| anyhow!(e))
+                .and_then(|message| enqueued_worker(&message));
+            match worker {
+                Ok(worker) => self.backlog.push_back(worker),
+                Err(e) => {
+                    error!("unable to restore queued run {}, {e}", entry.run_id);
+                    pipeline_run_queue::delete_by_run_id(conn, &entry.run_id).await?;
+                    pipeline_runs::update_state(conn, &entry.run_id, PR_STATE_FAULTED).await?;
+                }
             }
         }
 
+        self.fill_slots().await
+    }
+
+    async fn add_backlog(&mut self, worker: Worker, message: &ServerMessages) -> Result<()> {
+        let conn = self.conn.as_ref();
+        let run_id = worker.get_run_id();
+        let model = InsertPipelineRunQueue {
+            id: Uuid::new_v4().to_string(),
+            run_id: run_id.to_owned(),
+            priority: worker.get_priority(),
+            message: serde_json::to_string(message)?,
+        };
+        pipeline_run_queue::insert(conn, model).await?;
+        pipeline_runs::update_state(conn, run_id, PR_STATE_QUEUED).await?;
+
+        if let Some(group) = waiting_on_group(&worker, self.active.iter(), &self.backlog) {
+            info!("run {run_id} is waiting on concurrency group {group}");
+            pipeline_runs::update_waiting_on_group(conn, run_id, Some(group)).await?;
+        }
+
+        let labels = worker.get_labels();
+        if worker.is_remote() && !self.agents.iter().any(|a| a.has_labels(labels)) {
+            info!(
+                "run {run_id} is waiting for an agent with labels: {}",
+                labels.join(", ")
+            );
+        }
+
+        self.backlog.push_back(worker);
+        Ok(())
+    }
+
+    async fn after_removal(&mut self) -> Result<()> {
+        self.fill_slots().await?;
+
         let docker = self.docker.clone();
         let conn = self.conn.clone();
         spawn(async move {
@@ -134,30 +321,108 @@ impl WorkerQueueReceiver {
         Ok(())
     }
 
+    /// Activates the workers of the backlog for as long as there are slots for them.
+    async fn fill_slots(&mut self) -> Result<()> {
+        while let Some(i) = next_in_backlog(self.active.iter(), &self.backlog, |w| self.has_slot(w))
+        {
+            if let Some(worker) = self.backlog.remove(i) {
+                self.backlog.iter_mut().for_each(Worker::pass_over);
+                pipeline_run_queue::delete_by_run_id(self.conn.as_ref(), worker.get_run_id())
+                    .await?;
+                self.activate(worker).await?;
+            }
+        }
+        Ok(())
+    }
+
     /// Used to spawn the child process of the worker and add it to the active workers vector.
-    async fn enqueue(&mut self, item: Box<Worker>) -> Result<()> {
-        if self.active.len() < self.capacity {
-            self.activate(*item)?;
+    /// A worker whose concurrency group cancels the runs in progress first stops every other
+    /// worker of the same group.
+    async fn enqueue(&mut self, message: ServerMessages) -> Result<()> {
+        let item = enqueued_worker(&message)?;
+        // a new leader of a raft group requeues the runs that the supervisor may have
+        // already restored.
+        if self.contains(item.get_run_id()) {
+            debug!("run {} is already in the queue", item.get_run_id());
+            return Ok(());
+        }
+        if let Some(concurrency) = item.get_concurrency().filter(|c| c.cancel_in_progress) {
+            let group = concurrency.name.to_owned();
+            self.cancel_group(&group).await?;
+        }
+
+        let has_capacity = self.has_slot(&item);
+        let waiting = waiting_on_group(&item, self.active.iter(), &self.backlog).is_some();
+        if has_capacity && !waiting {
+            self.activate(item).await?;
         } else {
-            self.add_backlog(item).await?;
+            self.add_backlog(item, &message).await?;
+        }
+        Ok(())
+    }
+
+    /// Stops the active workers of the group and drops the ones waiting in the backlog,
+    /// marking their runs as faulted.
+    async fn cancel_group(&mut self, group: &str) -> Result<()> {
+        let mut cancelled = vec![];
+        let mut i = 0;
+
+        while i < self.active.len() {
+            if self.active[i].in_group(group) {
+                cancelled.push(self.active.remove(i));
+            } else {
+                i += 1;
+            }
+        }
+
+        let reason = format!("cancelled by a newer run of concurrency group {group}");
+        for entry in cancelled.iter_mut() {
+            info!(
+                "cancelling run {} of concurrency group {group}",
+                entry.get_run_id()
+            );
+            if let Err(e) = entry.stop().await {
+                error!("error while stopping worker process: {e}");
+            }
+            if let Err(e) = self.cleanup_process(entry, &reason).await {
+                error!("error while cleaning up worker process, {e}");
+            }
+        }
+
+        let (waiting, backlog) = self.backlog.drain(..).partition(|w| w.in_group(group));
+        self.backlog = backlog;
+
+        for entry in waiting.iter() {
+            info!(
+                "cancelling queued run {} of concurrency group {group}",
+                entry.get_run_id()
+            );
+            let _ =
+                pipeline_run_queue::delete_by_run_id(self.conn.as_ref(), entry.get_run_id()).await;
+            let _ = pipeline_runs::update_state(
+                self.conn.as_ref(),
+                entry.get_run_id(),
+                PR_STATE_FAULTED,
+            )
+            .await;
+        }
+
+        if !cancelled.is_empty() {
+            self.after_removal().await?;
         }
+
         Ok(())
     }
 
     /// This method will check for a worker that have finished executing and will remove them from
     /// the active workers collection. It will pop the appropriate amount of workers from the
     /// backlog vector, spawn them and add them as active.
-    async fn dequeue(&mut self, pid: u32) -> Result<()> {
+    async fn dequeue(&mut self, run_id: &str) -> Result<()> {
         let mut cleanup = vec![];
         let mut i = 0;
 
         while i < self.active.len() {
-            if self.active[i]
-                .get_pid()
-                .as_ref()
-                .map(|wpid| pid == *wpid)
-                .unwrap_or(false)
-            {
+            if self.active[i].has_run_id(run_id) {
                 let worker = self.active.remove(i);
                 cleanup.push(worker);
             } else {
@@ -166,12 +431,13 @@ impl WorkerQueueReceiver {
         }
 
         for entry in cleanup.iter_mut() {
-            if let Err(e) = try_cleanup_process(self.conn.clone(), entry).await {
+            let reason = "the worker exited without completing the run";
+            if let Err(e) = self.cleanup_process(entry, reason).await {
                 error!("error while cleaning up worker process, {e}");
             }
         }
 
-        self.after_removal()?;
+        self.after_removal().await?;
         Ok(())
     }
 
@@ -194,27 +460,223 @@ impl WorkerQueueReceiver {
             if let Err(e) = entry.stop().await {
                 error!("error while stopping worker process: {e}");
             }
-            if let Err(e) = try_cleanup_process(self.conn.clone(), entry).await {
+            if let Err(e) = self.cleanup_process(entry, "the run was stopped").await {
                 error!("error while cleaning up worker process, {e}");
             }
         }
 
         if found_in_active {
-            self.after_removal()?;
-        } else {
+            self.after_removal().await?;
+        } else if self.backlog.iter().any(|w| w.has_run_id(&run_id)) {
             self.backlog.retain(|w| !w.has_run_id(&run_id));
+            pipeline_run_queue::delete_by_run_id(self.conn.as_ref(), &run_id).await?;
         }
 
         Ok(())
     }
 
-    fn contains(&mut self, pid: u32) -> bool {
+    fn contains(&mut self, run_id: &str) -> bool {
         self.active
             .iter()
-            .find(|w| w.has_pid(pid))
-            .or_else(|| self.backlog.iter().find(|w| w.has_pid(pid)))
-            .is_some()
+            .chain(self.backlog.iter())
+            .any(|w| w.has_run_id(run_id))
+    }
+
+    fn heartbeat(&mut self, run_id: &str) {
+        if let Some(worker) = self.active.iter_mut().find(|w| w.has_run_id(run_id)) {
+            worker.heartbeat();
+        }
+    }
+
+    /// Removes the workers whose process has exited without completing their run, which
+    /// is the case when it crashed before connecting to the supervisor, and kills the ones
+    /// that have stopped sending heartbeats.
+    async fn reap(&mut self) -> Result<()> {
+        let timeout = self.heartbeat_timeout;
+
+        let mut exited: Vec<Worker> = self.active.extract_if(.., |w| w.completed()).collect();
+
+        let mut hung: Vec<Worker> = self.active.extract_if(.., |w| w.is_hung(timeout)).collect();
+
+        for entry in exited.iter_mut() {
+            info!("worker of run {} has exited", entry.get_run_id());
+            let reason = "the worker exited without completing the run";
+            if let Err(e) = self.cleanup_process(entry, reason).await {
+                error!("error while cleaning up worker process, {e}");
+            }
+        }
+
+        for entry in hung.iter_mut() {
+            info!(
+                "worker of run {} stopped sending heartbeats, killing it",
+                entry.get_run_id()
+            );
+            if let Err(e) = entry.kill().await {
+                error!("error while killing worker process: {e}");
+            }
+            let reason = "the worker stopped sending heartbeats and was killed";
+            if let Err(e) = self.cleanup_process(entry, reason).await {
+                error!("error while cleaning up worker process, {e}");
+            }
+        }
+
+        if !exited.is_empty() || !hung.is_empty() {
+            self.after_removal().await?;
+        }
+
+        Ok(())
     }
+
+    /// Faults the runs that were in progress when the supervisor stopped, since their
+    /// workers are gone along with it, as well as the queued runs that are missing from
+    /// the persisted queue.
+    async fn reconcile(&self) -> Result<()> {
+        let conn = self.conn.as_ref();
+
+        let states = [PR_STATE_RUNNING, PR_STATE_WAITING_APPROVAL];
+        for run in pipeline_runs::select_by_states(conn, &states).await? {
+            info!("run {} was in progress when the supervisor stopped", run.id);
+            let reason = "the supervisor stopped while the run was in progress";
+            self.cleanup_run(&run.id, reason).await?;
+        }
+
+        let queued: HashSet<String> = pipeline_run_queue::select_all(conn)
+            .await?
+            .into_iter()
+            .map(|entry| entry.run_id)
+            .collect();
+        for run in pipeline_runs::select_by_states(conn, &[PR_STATE_QUEUED]).await? {
+            if !queued.contains(&run.id) {
+                info!("run {} is missing from the queue", run.id);
+                let reason = "the run was lost from the queue of the supervisor";
+                self.cleanup_run(&run.id, reason).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Calls the clean up method of the worker and then cleans up its run.
+    async fn cleanup_process(&self, worker: &mut Worker, reason: &str) -> Result<()> {
+        debug!("starting worker process cleanup");
+
+        if let Err(e) = worker.cleanup().await {
+            error!("error when trying to cleanup the worker process, {e}");
+        }
+
+        self.cleanup_run(worker.get_run_id(), reason).await
+    }
+
+    /// If the run isn't faulted or finished then its worker did not complete it, so it will
+    /// be set to faulted with the reason. Its containers are set as faulted in order to be
+    /// cleaned up later and the temporary directory of its machine platform is removed. The
+    /// directories of its ssh platforms can't be removed from here, since the supervisor has
+    /// no credentials for their hosts, so the next ssh platform that connects to the same host
+    /// removes them once it finds the run completed.
+    async fn cleanup_run(&self, run_id: &str, reason: &str) -> Result<()> {
+        let conn = self.conn.as_ref();
+
+        pipeline_runs::update_as_faulted(conn, run_id, reason).await?;
+
+        let _ = pipeline_run_containers::update_running_containers_to_faulted(conn, run_id).await;
+
+        let tmp_dir = self.config.tmp_full_path(run_id);
+        if tmp_dir.is_dir() {
+            let _ = remove_dir_all(&tmp_dir)
+                .await
+                .inspect_err(|e| error!("could not remove {}, {e}", tmp_dir.display()));
+        }
+
+        Ok(())
+    }
+
+    async fn add_agent(&mut self, agent: Agent) -> Result<()> {
+        info!(
+            "agent {} connected with labels: {} and capacity: {}",
+            agent.name,
+            agent.labels.join(", "),
+            agent.capacity
+        );
+        self.agents.push(agent);
+        self.fill_slots().await
+    }
+
+    /// Removes an agent that has disconnected. The supervisor can no longer stop the
+    /// workers that the agent has spawned so their runs are cleaned up as faulted.
+    async fn remove_agent(&mut self, id: &str) -> Result<()> {
+        self.agents.retain(|a| a.id != id);
+
+        let mut removed: Vec<Worker> = self
+            .active
+            .extract_if(.., |w| w.get_agent() == Some(id))
+            .collect();
+
+        for entry in removed.iter_mut() {
+            info!(
+                "agent of run {} disconnected, cleaning up the run",
+                entry.get_run_id()
+            );
+            let reason = "the agent of the run disconnected";
+            if let Err(e) = self.cleanup_process(entry, reason).await {
+                error!("error while cleaning up worker process, {e}");
+            }
+        }
+
+        if !removed.is_empty() {
+            self.after_removal().await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The number of workers that take up the capacity of the supervisor, which are the
+/// ones it spawned itself rather than through an agent.
+fn local_load<'a>(workers: impl IntoIterator<Item = &'a Worker>) -> usize {
+    workers.into_iter().filter(|w| !w.is_remote()).count()
+}
+
+/// The concurrency group that the worker has to wait on, which is the case while another
+/// worker of the same group is either active or ahead of it in the backlog.
+fn waiting_on_group<'a, 'b>(
+    worker: &'a Worker,
+    active: impl IntoIterator<Item = &'b Worker>,
+    backlog: &'b VecDeque<Worker>,
+) -> Option<&'a str> {
+    let group = worker.get_concurrency()?.name.as_str();
+    active
+        .into_iter()
+        .chain(backlog.iter())
+        .any(|w| w.in_group(group))
+        .then_some(group)
+}
+
+/// The position of the worker in the backlog that becomes active next, skipping the ones
+/// that have no slot available and the ones whose concurrency group already has an active
+/// worker. Of the rest, the one with the highest aged priority goes first, then the one
+/// whose user and then pipeline have the fewest active workers and then the one enqueued
+/// first.
+fn next_in_backlog<'a>(
+    active: impl IntoIterator<Item = &'a Worker> + Clone,
+    backlog: &VecDeque<Worker>,
+    has_slot: impl Fn(&Worker) -> bool,
+) -> Option<usize> {
+    let count = |f: &dyn Fn(&Worker) -> bool| active.clone().into_iter().filter(|w| f(w)).count();
+    backlog
+        .iter()
+        .enumerate()
+        .filter(|(_, worker)| {
+            has_slot(worker)
+                && worker
+                    .get_concurrency()
+                    .is_none_or(|c| !active.clone().into_iter().any(|w| w.in_group(&c.name)))
+        })
+        .min_by_key(|(i, worker)| {
+            let user = count(&|w| w.get_user() == worker.get_user());
+            let pipeline = count(&|w| w.get_pipeline() == worker.get_pipeline());
+            (Reverse(worker.get_aged_priority()), user, pipeline, *i)
+        })
+        .map(|(i, _)| i)
 }
 
 pub struct WorkerQueueSender {
@@ -226,18 +688,24 @@ impl WorkerQueueSender {
         Self { tx }
     }
 
-    pub async fn enqueue(&self, worker: Box<Worker>) -> Result<()> {
+    pub async fn enqueue(&self, message: ServerMessages) -> Result<()> {
         let (resp_tx, resp_rx) = oneshot::channel();
-        let message = WorkerQueueMessage::Enqueue { worker, resp_tx };
+        let message = WorkerQueueMessage::Enqueue {
+            message: Box::new(message),
+            resp_tx,
+        };
 
         self.tx.send(message).await.map_err(|e| anyhow!(e))?;
 
         resp_rx.await?
     }
 
-    pub async fn dequeue(&self, pid: u32) -> Result<()> {
+    pub async fn dequeue(&self, run_id: &str) -> Result<()> {
         let (resp_tx, resp_rx) = oneshot::channel();
-        let message = WorkerQueueMessage::Dequeue { pid, resp_tx };
+        let message = WorkerQueueMessage::Dequeue {
+            run_id: run_id.to_owned(),
+            resp_tx,
+        };
 
         self.tx.send(message).await.map_err(|e| anyhow!(e))?;
 
@@ -256,14 +724,53 @@ impl WorkerQueueSender {
         resp_rx.await?
     }
 
-    pub async fn contains(&self, pid: u32) -> Result<bool> {
+    pub async fn contains(&self, run_id: &str) -> Result<bool> {
         let (resp_tx, resp_rx) = oneshot::channel();
-        let message = WorkerQueueMessage::Contains { pid, resp_tx };
+        let message = WorkerQueueMessage::Contains {
+            run_id: run_id.to_owned(),
+            resp_tx,
+        };
 
         self.tx.send(message).await.map_err(|e| anyhow!(e))?;
 
         resp_rx.await.map_err(|e| anyhow!(e))
     }
+
+    pub async fn add_agent(&self, agent: Agent) -> Result<()> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let message = WorkerQueueMessage::AddAgent {
+            agent: Box::new(agent),
+            resp_tx,
+        };
+
+        self.tx.send(message).await.map_err(|e| anyhow!(e))?;
+
+        resp_rx.await?
+    }
+
+    pub async fn heartbeat(&self, run_id: &str) -> Result<()> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let message = WorkerQueueMessage::Heartbeat {
+            run_id: run_id.to_owned(),
+            resp_tx,
+        };
+
+        self.tx.send(message).await.map_err(|e| anyhow!(e))?;
+
+        resp_rx.await?
+    }
+
+    pub async fn remove_agent(&self, id: &str) -> Result<()> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let message = WorkerQueueMessage::RemoveAgent {
+            id: id.to_owned(),
+            resp_tx,
+        };
+
+        self.tx.send(message).await.map_err(|e| anyhow!(e))?;
+
+        resp_rx.await?
+    }
 }
 
 pub async fn worker_queue_channel(
@@ -283,31 +790,6 @@ pub async fn worker_queue_channel(
     Ok(WorkerQueueSender::new(tx))
 }
 
-/// This function will call the clean up method for the worker and check
-/// the current state of the run id. If the state isn't faulted or finished then
-/// the worker did not complete successfully so it will be set to faulted and all
-/// of its associated containers will be set as faulted in order to be cleaned up later.
-async fn try_cleanup_process(conn: Data<DatabaseConnection>, worker: &mut Worker) -> Result<()> {
-    debug!("starting worker process cleanup");
-
-    let conn = conn.as_ref();
-
-    if let Err(e) = worker.cleanup().await {
-        error!("error when trying to cleanup the worker process, {e}");
-    }
-
-    let run_id = worker.get_run_id();
-    let run = pipeline_runs::select_by_id(conn, run_id).await?;
-
-    if run.state != PR_STATE_FINISHED && run.state != PR_STATE_FAULTED {
-        let _ = pipeline_runs::update_state(conn, run_id, PR_STATE_FAULTED).await;
-    }
-
-    let _ = pipeline_run_containers::update_running_containers_to_faulted(conn, run_id).await;
-
-    Ok(())
-}
-
 /// This function will fetch all containers with faulted state or those in active state
 /// with runs that have either finished or faulted, and try to stop and remove them using the docker
 /// engine API and then set their state as removed.
@@ -359,3 +841,166 @@ pub async fn try_cleanup_containers(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{local_load, next_in_backlog, waiting_on_group};
+    use crate::queues::Agent;
+    use bld_core::workers::Worker;
+    use bld_models::dtos::{ConcurrencyGroup, ServerMessages};
+    use std::{collections::VecDeque, thread::sleep, time::Duration};
+    use tokio::{process::Command, sync::mpsc::channel};
+
+    fn worker(run_id: &str, group: Option<&str>) -> Worker {
+        let concurrency = group.map(|name| ConcurrencyGroup {
+            name: name.to_string(),
+            cancel_in_progress: false,
+        });
+        Worker::new(run_id.to_string(), concurrency, Command::new("true"))
+    }
+
+    #[test]
+    fn worker_waits_on_its_group_while_another_run_is_active_or_queued() {
+        let active = vec![worker("1", Some("deploy"))];
+        let backlog = VecDeque::from([worker("2", Some("release"))]);
+
+        let deploy = worker("3", Some("deploy"));
+        assert_eq!(waiting_on_group(&deploy, &active, &backlog), Some("deploy"));
+
+        let release = worker("4", Some("release"));
+        assert_eq!(
+            waiting_on_group(&release, &active, &backlog),
+            Some("release")
+        );
+
+        let other = worker("5", Some("other"));
+        assert_eq!(waiting_on_group(&other, &active, &backlog), None);
+
+        let no_group = worker("6", None);
+        assert_eq!(waiting_on_group(&no_group, &active, &backlog), None);
+    }
+
+    #[test]
+    fn workers_spawned_by_agents_dont_count_against_the_capacity() {
+        let message = ServerMessages::Stop {
+            run_id: "3".to_string(),
+        };
+        let remote = Worker::remote("3".to_string(), None, vec![], message);
+        let active = [worker("1", None), remote, worker("2", None)];
+
+        assert_eq!(local_load(active.iter()), 2);
+    }
+
+    #[test]
+    fn next_in_backlog_skips_workers_of_active_groups() {
+        let active = vec![worker("1", Some("deploy"))];
+        let backlog = VecDeque::from([
+            worker("2", Some("deploy")),
+            worker("3", Some("deploy")),
+            worker("4", None),
+            worker("5", Some("release")),
+        ]);
+        assert_eq!(next_in_backlog(&active, &backlog, |_| true), Some(2));
+
+        let backlog = VecDeque::from([worker("2", Some("deploy")), worker("5", Some("release"))]);
+        assert_eq!(next_in_backlog(&active, &backlog, |_| true), Some(1));
+
+        let backlog = VecDeque::from([worker("2", Some("deploy"))]);
+        assert_eq!(next_in_backlog(&active, &backlog, |_| true), None);
+        assert_eq!(next_in_backlog(&[], &backlog, |_| true), Some(0));
+    }
+
+    #[test]
+    fn next_in_backlog_skips_workers_without_a_slot() {
+        let labels = vec!["linux".to_string()];
+        let message = ServerMessages::Stop {
+            run_id: "1".to_string(),
+        };
+        let remote = Worker::remote("1".to_string(), None, labels, message);
+        let backlog = VecDeque::from([remote, worker("2", None)]);
+
+        assert_eq!(next_in_backlog(&[], &backlog, |w| !w.is_remote()), Some(1));
+        assert_eq!(next_in_backlog(&[], &backlog, |_| false), None);
+    }
+
+    #[test]
+    fn next_in_backlog_picks_the_highest_priority_first() {
+        let backlog = VecDeque::from([
+            worker("1", None),
+            worker("2", None).queued_as("", "", 10),
+            worker("3", None).queued_as("", "", -5),
+            worker("4", None).queued_as("", "", 10),
+        ]);
+        assert_eq!(next_in_backlog(&[], &backlog, |_| true), Some(1));
+
+        let backlog = VecDeque::from([worker("1", None), worker("3", None).queued_as("", "", -5)]);
+        assert_eq!(next_in_backlog(&[], &backlog, |_| true), Some(0));
+    }
+
+    #[test]
+    fn next_in_backlog_eventually_picks_a_lower_priority_worker() {
+        let mut backlog = VecDeque::from([worker("manual", None).queued_as("", "admin", 0)]);
+
+        for i in 0..=10 {
+            let cron = worker(&format!("cron-{i}"), None).queued_as("nightly.yaml", "Cron", 10);
+            backlog.push_back(cron);
+            let next = next_in_backlog(&[], &backlog, |_| true).unwrap();
+            let picked = backlog.remove(next).unwrap();
+            backlog.iter_mut().for_each(Worker::pass_over);
+            if picked.has_run_id("manual") {
+                return;
+            }
+        }
+
+        panic!("the manual run was starved by the cron runs");
+    }
+
+    #[test]
+    fn next_in_backlog_shares_the_slots_between_users_and_pipelines() {
+        let active = vec![worker("1", None).queued_as("nightly.yaml", "Cron", 0)];
+        let backlog = VecDeque::from([
+            worker("2", None).queued_as("nightly.yaml", "Cron", 0),
+            worker("3", None).queued_as("weekly.yaml", "Cron", 0),
+            worker("4", None).queued_as("nightly.yaml", "admin", 0),
+            worker("5", None).queued_as("deploy.yaml", "admin", 0),
+        ]);
+        assert_eq!(next_in_backlog(&active, &backlog, |_| true), Some(3));
+
+        let backlog = VecDeque::from([
+            worker("2", None).queued_as("nightly.yaml", "Cron", 0),
+            worker("3", None).queued_as("weekly.yaml", "Cron", 0),
+        ]);
+        assert_eq!(next_in_backlog(&active, &backlog, |_| true), Some(1));
+
+        let backlog = VecDeque::from([
+            worker("2", None).queued_as("nightly.yaml", "Cron", 1),
+            worker("5", None).queued_as("deploy.yaml", "admin", 0),
+        ]);
+        assert_eq!(next_in_backlog(&active, &backlog, |_| true), Some(0));
+    }
+
+    #[test]
+    fn agent_has_labels_when_it_has_every_one_of_them() {
+        let (tx, _rx) = channel(1);
+        let labels = vec!["linux".to_string(), "docker".to_string()];
+        let agent = Agent::new("1".to_string(), "agent".to_string(), labels, 1, tx);
+
+        assert!(agent.has_labels(&[]));
+        assert!(agent.has_labels(&["docker".to_string()]));
+        assert!(agent.has_labels(&["linux".to_string(), "docker".to_string()]));
+        assert!(!agent.has_labels(&["linux".to_string(), "arm64".to_string()]));
+    }
+
+    #[test]
+    fn worker_is_hung_once_its_heartbeats_stop_for_longer_than_the_timeout() {
+        let timeout = Duration::from_millis(50);
+        let mut worker = worker("1", None);
+        assert!(!worker.is_hung(timeout));
+
+        sleep(Duration::from_millis(100));
+        assert!(worker.is_hung(timeout));
+
+        worker.heartbeat();
+        assert!(!worker.is_hung(timeout));
+    }
+}
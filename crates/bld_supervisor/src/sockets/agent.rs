@@ -0,0 +1,209 @@
+use crate::queues::{Agent, WorkerQueueSender};
+use actix_web::{
+    HttpRequest, Responder,
+    rt::spawn,
+    web::{self, Bytes, Data},
+};
+use anyhow::{Result, bail};
+use bld_config::BldConfig;
+use bld_models::dtos::{ServerMessages, WorkerMessages};
+use bld_sock::session::{self, WebSocketMessage};
+use bld_utils::crypto::constant_time_eq;
+use tokio::sync::mpsc::{Sender, channel};
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+async fn handle_message(
+    bytes: &Bytes,
+    agent_token: Option<&str>,
+    agent_id: &mut Option<String>,
+    agent_tx: &Sender<ServerMessages>,
+    worker_queue_tx: &WorkerQueueSender,
+) -> Result<()> {
+    let msg: WorkerMessages = serde_json::from_slice(&bytes[..])?;
+    match msg {
+        WorkerMessages::Ack => info!("a new agent connection was acknowledged"),
+
+        WorkerMessages::Register {
+            name,
+            token,
+            labels,
+            capacity,
+        } => {
+            if agent_id.is_some() {
+                bail!("agent {name} has already been registered");
+            }
+            let Some(agent_token) = agent_token else {
+                bail!("agent {name} rejected since the supervisor has no agent_token configured");
+            };
+            if !constant_time_eq(agent_token, &token) {
+                bail!("agent {name} rejected due to an invalid token");
+            }
+            let id = Uuid::new_v4().to_string();
+            let agent = Agent::new(id.clone(), name, labels, capacity, agent_tx.clone());
+            worker_queue_tx.add_agent(agent).await?;
+            agent_id.replace(id);
+        }
+
+        WorkerMessages::Exited { run_id } => {
+            if agent_id.is_none() {
+                bail!("exited message sent before the agent was registered");
+            }
+            info!("worker of run {run_id} exited on its agent");
+            worker_queue_tx.dequeue(&run_id).await?;
+        }
+
+        _ => bail!("worker message sent on an agent connection"),
+    }
+    Ok(())
+}
+
+/// The connection of an agent, which receives the enqueue and stop messages of the runs
+/// that the worker queue routes to it once it has registered with the agent token of the
+/// supervisor config.
+pub async fn ws(
+    req: HttpRequest,
+    body: web::Payload,
+    config: Data<BldConfig>,
+    worker_queue_tx: Data<WorkerQueueSender>,
+) -> actix_web::Result<impl Responder> {
+    let (response, mut handler) = session::handle(&req, body)?;
+    let mut session = handler.session().clone();
+
+    spawn(async move {
+        let (agent_tx, mut agent_rx) = channel::<ServerMessages>(4096);
+        let mut agent_id: Option<String> = None;
+
+        loop {
+            tokio::select! {
+                msg = handler.next() => match msg {
+                    WebSocketMessage::Binary(bytes) => {
+                        debug!("received binary message from agent");
+                        let agent_token = config.local.supervisor.agent_token.as_deref();
+                        let result = handle_message(
+                            &bytes,
+                            agent_token,
+                            &mut agent_id,
+                            &agent_tx,
+                            &worker_queue_tx,
+                        )
+                        .await;
+                        if let Err(e) = result {
+                            let _ = session
+                                .text("internal server error")
+                                .await
+                                .inspect_err(|e| error!("{e}"));
+                            error!("handling message error. {e}");
+                            handler.error();
+                            break;
+                        }
+                    }
+                    WebSocketMessage::Continue => {}
+                    _ => break,
+                },
+                Some(msg) = agent_rx.recv() => {
+                    let Ok(data) = serde_json::to_vec(&msg).inspect_err(|e| error!("{e}")) else {
+                        continue;
+                    };
+                    if let Err(e) = session.binary(data).await {
+                        error!("unable to send message to agent. {e}");
+                        break;
+                    }
+                }
+            }
+        }
+
+        if let Some(id) = agent_id {
+            debug!("removing agent with id: {id}");
+            let _ = worker_queue_tx
+                .remove_agent(&id)
+                .await
+                .inspect_err(|e| error!("{e}"));
+        }
+
+        handler.cleanup().await;
+    });
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::handle_message;
+    use crate::queues::{WorkerQueueMessage, WorkerQueueSender};
+    use actix_web::web::Bytes;
+    use bld_models::dtos::{ServerMessages, WorkerMessages};
+    use tokio::sync::mpsc::{Receiver, Sender, channel};
+
+    fn register(token: &str) -> Bytes {
+        let message = WorkerMessages::Register {
+            name: "agent".to_string(),
+            token: token.to_string(),
+            labels: vec![],
+            capacity: 1,
+        };
+        Bytes::from(serde_json::to_vec(&message).unwrap())
+    }
+
+    fn queue() -> (WorkerQueueSender, Receiver<WorkerQueueMessage>) {
+        let (tx, rx) = channel(1);
+        (WorkerQueueSender::new(tx), rx)
+    }
+
+    fn agent_tx() -> Sender<ServerMessages> {
+        channel(1).0
+    }
+
+    #[tokio::test]
+    async fn handle_message_register_without_a_configured_token_errors() {
+        let mut agent_id = None;
+        let (queue, _rx) = queue();
+        let result =
+            handle_message(&register("token"), None, &mut agent_id, &agent_tx(), &queue).await;
+        assert!(result.is_err());
+        assert_eq!(agent_id, None);
+    }
+
+    #[tokio::test]
+    async fn handle_message_register_with_an_invalid_token_errors() {
+        let mut agent_id = None;
+        let (queue, _rx) = queue();
+        let bytes = register("other");
+        let result =
+            handle_message(&bytes, Some("token"), &mut agent_id, &agent_tx(), &queue).await;
+        assert!(result.is_err());
+        assert_eq!(agent_id, None);
+    }
+
+    #[tokio::test]
+    async fn handle_message_register_with_the_token_adds_the_agent() {
+        let mut agent_id = None;
+        let (queue, mut rx) = queue();
+        let handle = tokio::spawn(async move {
+            let Some(WorkerQueueMessage::AddAgent { resp_tx, .. }) = rx.recv().await else {
+                panic!("expected an add agent message");
+            };
+            resp_tx.send(Ok(())).unwrap();
+        });
+
+        let bytes = register("token");
+        handle_message(&bytes, Some("token"), &mut agent_id, &agent_tx(), &queue)
+            .await
+            .unwrap();
+        handle.await.unwrap();
+        assert!(agent_id.is_some());
+    }
+
+    #[tokio::test]
+    async fn handle_message_exited_before_register_errors() {
+        let mut agent_id = None;
+        let (queue, _rx) = queue();
+        let message = WorkerMessages::Exited {
+            run_id: "run".to_string(),
+        };
+        let bytes = Bytes::from(serde_json::to_vec(&message).unwrap());
+        let result =
+            handle_message(&bytes, Some("token"), &mut agent_id, &agent_tx(), &queue).await;
+        assert!(result.is_err());
+    }
+}
@@ -4,11 +4,9 @@ use actix_web::{
     web::{self, Bytes, Data},
 };
 use anyhow::Result;
-use bld_core::workers::Worker;
 use bld_models::dtos::ServerMessages;
 use bld_sock::session::{self, WebSocketMessage};
-use std::env::current_exe;
-use tokio::{process::Command, sync::mpsc};
+use tokio::sync::mpsc;
 use tracing::{debug, error, info};
 
 async fn handle_message(worker_queue_tx: &Data<WorkerQueueSender>, bytes: &Bytes) -> Result<()> {
@@ -16,39 +14,11 @@ async fn handle_message(worker_queue_tx: &Data<WorkerQueueSender>, bytes: &Bytes
     match msg {
         ServerMessages::Ack => info!("a new server connection was acknowledged"),
 
-        ServerMessages::Enqueue {
-            pipeline,
-            run_id,
-            inputs,
-            env,
-        } => {
+        ServerMessages::Enqueue { ref pipeline, .. } => {
             info!("server sent an enqueue message for pipeline: {pipeline}");
-            let exe = current_exe().map_err(|e| {
-                error!("could not get the current executable. {e}");
-                e
-            })?;
-            let mut command = Command::new(exe);
-            command.arg("worker");
-            command.arg("--pipeline");
-            command.arg(&pipeline);
-            command.arg("--run-id");
-            command.arg(&run_id);
-            if let Some(inputs) = inputs {
-                for entry in inputs {
-                    command.arg("--input");
-                    command.arg(entry);
-                }
-            }
-            if let Some(env) = env {
-                for entry in env {
-                    command.arg("--environment");
-                    command.arg(entry);
-                }
-            }
-
-            let worker = Box::new(Worker::new(run_id, command));
+            let pipeline = pipeline.to_owned();
             worker_queue_tx
-                .enqueue(worker)
+                .enqueue(msg)
                 .await
                 .inspect(|_| info!("worker for pipeline: {pipeline} has been queued"))?;
         }
@@ -4,78 +4,45 @@ use actix_web::{
     rt::spawn,
     web::{self, Bytes, Data},
 };
-use anyhow::Result;
+use anyhow::{Result, bail};
 use bld_models::dtos::WorkerMessages;
 use bld_sock::session::{self, WebSocketMessage};
 use tracing::{debug, error, info};
 
-async fn handle_message(bytes: &Bytes, worker_pid: &mut Option<u32>) -> Result<bool> {
+async fn handle_message(
+    bytes: &Bytes,
+    worker_run_id: &mut Option<String>,
+    worker_queue_tx: &WorkerQueueSender,
+) -> Result<bool> {
     let msg: WorkerMessages = serde_json::from_slice(&bytes[..])?;
     let completed = match msg {
         WorkerMessages::Ack => {
             info!("a new worker connection was acknowledged");
             false
         }
-        WorkerMessages::WhoAmI { pid } => {
-            info!("worker with pid: {pid} sent a whoami message");
-            worker_pid.replace(pid);
+        WorkerMessages::WhoAmI { pid, run_id } => {
+            info!("worker with pid: {pid} of run {run_id} sent a whoami message");
+            worker_run_id.replace(run_id);
             false
         }
         WorkerMessages::Completed => {
             info!("worker just completed, starting cleanup");
             true
         }
+        WorkerMessages::Heartbeat => {
+            if let Some(run_id) = worker_run_id {
+                debug!("worker of run {run_id} sent a heartbeat");
+                worker_queue_tx.heartbeat(run_id).await?;
+            }
+            false
+        }
+        WorkerMessages::Register { .. } | WorkerMessages::Exited { .. } => {
+            bail!("agent message sent on a worker connection")
+        }
     };
     Ok(completed)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::handle_message;
-    use actix_web::web::Bytes;
-    use bld_models::dtos::WorkerMessages;
-
-    fn to_bytes(msg: &WorkerMessages) -> Bytes {
-        Bytes::from(serde_json::to_vec(msg).unwrap())
-    }
-
-    #[tokio::test]
-    async fn handle_message_ack_does_not_set_pid_and_is_not_completed() {
-        let mut pid = None;
-        let completed = handle_message(&to_bytes(&WorkerMessages::Ack), &mut pid)
-            .await
-            .unwrap();
-        assert!(!completed);
-        assert_eq!(pid, None);
-    }
-
-    #[tokio::test]
-    async fn handle_message_who_am_i_sets_pid() {
-        let mut pid = None;
-        let completed = handle_message(&to_bytes(&WorkerMessages::WhoAmI { pid: 42 }), &mut pid)
-            .await
-            .unwrap();
-        assert!(!completed);
-        assert_eq!(pid, Some(42));
-    }
-
-    #[tokio::test]
-    async fn handle_message_completed_signals_completion() {
-        let mut pid = None;
-        let completed = handle_message(&to_bytes(&WorkerMessages::Completed), &mut pid)
-            .await
-            .unwrap();
-        assert!(completed);
-    }
-
-    #[tokio::test]
-    async fn handle_message_invalid_payload_errors() {
-        let mut pid = None;
-        let result = handle_message(&Bytes::from_static(b"not json"), &mut pid).await;
-        assert!(result.is_err());
-    }
-}
-
 pub async fn ws(
     req: HttpRequest,
     body: web::Payload,
@@ -84,13 +51,13 @@ pub async fn ws(
     let (response, mut handler) = session::handle(&req, body)?;
 
     spawn(async move {
-        let mut worker_pid: Option<u32> = None;
+        let mut worker_run_id: Option<String> = None;
 
         loop {
             match handler.next().await {
                 WebSocketMessage::Binary(bytes) => {
                     debug!("received binary message");
-                    match handle_message(&bytes, &mut worker_pid).await {
+                    match handle_message(&bytes, &mut worker_run_id, &worker_queue_tx).await {
                         Ok(true) => break,
                         Ok(false) => {}
                         Err(e) => {
@@ -110,10 +77,10 @@ pub async fn ws(
             }
         }
 
-        if let Some(pid) = worker_pid {
-            debug!("dequeue of worker with pid: {}", pid);
+        if let Some(run_id) = worker_run_id {
+            debug!("dequeue of worker of run {run_id}");
             let _ = worker_queue_tx
-                .dequeue(pid)
+                .dequeue(&run_id)
                 .await
                 .inspect_err(|e| error!("{e}"));
         }
@@ -123,3 +90,94 @@ pub async fn ws(
 
     Ok(response)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::handle_message;
+    use crate::queues::{WorkerQueueMessage, WorkerQueueSender};
+    use actix_web::web::Bytes;
+    use bld_models::dtos::WorkerMessages;
+    use tokio::sync::mpsc::{Receiver, channel};
+
+    fn to_bytes(msg: &WorkerMessages) -> Bytes {
+        Bytes::from(serde_json::to_vec(msg).unwrap())
+    }
+
+    fn queue() -> (WorkerQueueSender, Receiver<WorkerQueueMessage>) {
+        let (tx, rx) = channel(1);
+        (WorkerQueueSender::new(tx), rx)
+    }
+
+    #[tokio::test]
+    async fn handle_message_ack_does_not_set_run_id_and_is_not_completed() {
+        let mut run_id = None;
+        let (queue, _rx) = queue();
+        let completed = handle_message(&to_bytes(&WorkerMessages::Ack), &mut run_id, &queue)
+            .await
+            .unwrap();
+        assert!(!completed);
+        assert_eq!(run_id, None);
+    }
+
+    #[tokio::test]
+    async fn handle_message_who_am_i_sets_run_id() {
+        let mut run_id = None;
+        let (queue, _rx) = queue();
+        let message = WorkerMessages::WhoAmI {
+            pid: 42,
+            run_id: "run".to_string(),
+        };
+        let completed = handle_message(&to_bytes(&message), &mut run_id, &queue)
+            .await
+            .unwrap();
+        assert!(!completed);
+        assert_eq!(run_id.as_deref(), Some("run"));
+    }
+
+    #[tokio::test]
+    async fn handle_message_completed_signals_completion() {
+        let mut run_id = None;
+        let (queue, _rx) = queue();
+        let completed = handle_message(&to_bytes(&WorkerMessages::Completed), &mut run_id, &queue)
+            .await
+            .unwrap();
+        assert!(completed);
+    }
+
+    #[tokio::test]
+    async fn handle_message_invalid_payload_errors() {
+        let mut run_id = None;
+        let (queue, _rx) = queue();
+        let result = handle_message(&Bytes::from_static(b"not json"), &mut run_id, &queue).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn handle_message_agent_message_errors() {
+        let mut run_id = None;
+        let (queue, _rx) = queue();
+        let message = WorkerMessages::Exited {
+            run_id: "run".to_string(),
+        };
+        let result = handle_message(&to_bytes(&message), &mut run_id, &queue).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn handle_message_heartbeat_is_sent_to_the_queue() {
+        let mut run_id = Some("run".to_string());
+        let (queue, mut rx) = queue();
+        let handle = tokio::spawn(async move {
+            let Some(WorkerQueueMessage::Heartbeat { run_id, resp_tx }) = rx.recv().await else {
+                panic!("expected a heartbeat message");
+            };
+            resp_tx.send(Ok(())).unwrap();
+            run_id
+        });
+
+        let bytes = to_bytes(&WorkerMessages::Heartbeat);
+        let completed = handle_message(&bytes, &mut run_id, &queue).await.unwrap();
+        assert!(!completed);
+        assert_eq!(handle.await.unwrap(), "run");
+    }
+}
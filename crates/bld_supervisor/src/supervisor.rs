@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use crate::queues::worker_queue_channel;
-use crate::sockets::{server, worker};
+use crate::sockets::{agent, server, worker};
 use actix_web::web::{get, resource};
 use actix_web::{App, HttpServer};
 use anyhow::{Result, anyhow};
@@ -34,6 +34,7 @@ pub async fn start(config: BldConfig) -> Result<()> {
             .app_data(worker_queue_sender.clone())
             .service(resource("/v1/ws-server/").route(get().to(server::ws)))
             .service(resource("/v1/ws-worker/").route(get().to(worker::ws)))
+            .service(resource("/v1/ws-agent/").route(get().to(agent::ws)))
     });
 
     server = match &config.local.supervisor.tls {
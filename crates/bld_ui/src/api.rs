@@ -1,10 +1,11 @@
 use anyhow::{Result, anyhow, bail};
 use bld_models::dtos::{
-    AddJobRequest, ArtifactResponse, ArtifactsQueryParams, AuthTokens, CompletedPipelinesKpi,
-    CronJobResponse, ExecClientMessage, HistQueryParams, HistoryEntry, JobFiltersParams,
-    ListResponse, PipelineInfoQueryParams, PipelinePathRequest, PipelinePerCompletedStateKpi,
-    PipelineQueryParams, PipelineRunsPerMonthKpi, QueuedPipelinesKpi, RunningPipelinesKpi,
-    RunsPerUserKpi, UpdateJobRequest,
+    AccessGrantResponse, AddAccessGrantRequest, AddJobRequest, ApprovalRequest, ArtifactResponse,
+    ArtifactsQueryParams, AuthTokens, CompletedPipelinesKpi, CronJobResponse, ExecClientMessage,
+    HistQueryParams, HistoryEntry, JobFiltersParams, ListResponse, PipelineInfoQueryParams,
+    PipelinePathRequest, PipelinePerCompletedStateKpi, PipelineQueryParams,
+    PipelineRunsPerMonthKpi, QueuedPipelinesKpi, RunningPipelinesKpi, RunsPerUserKpi,
+    TimelineJobResponse, TimelineQueryParams, UpdateJobRequest,
 };
 use leptos::leptos_dom::logging;
 use leptos_router::{NavigateOptions, use_navigate};
@@ -214,6 +215,21 @@ pub async fn stop(id: String) -> Result<()> {
     }
 }
 
+pub async fn approve(id: String, approved: bool) -> Result<()> {
+    let url = build_url("/v1/approve")?;
+    let request = add_authorization_header(Client::builder().build()?.post(&url))?;
+    let response = request
+        .json(&ApprovalRequest::new(id, None, approved))
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        handle_error(status, response.text().await?)
+    } else {
+        Ok(())
+    }
+}
+
 pub async fn queued_pipelines() -> Result<QueuedPipelinesKpi> {
     let url = build_url("/v1/ui/kpis/queued-pipelines")?;
     let request = add_authorization_header(Client::builder().build()?.get(&url))?;
@@ -334,6 +350,42 @@ pub async fn cron_delete(id: String) -> Result<()> {
     }
 }
 
+pub async fn access() -> Result<Vec<AccessGrantResponse>> {
+    let url = build_url("/v1/access")?;
+    let request = add_authorization_header(Client::builder().build()?.get(&url))?;
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        handle_error(status, response.text().await?)
+    } else {
+        Ok(response.json().await?)
+    }
+}
+
+pub async fn access_insert(data: AddAccessGrantRequest) -> Result<()> {
+    let url = build_url("/v1/access")?;
+    let request = add_authorization_header(Client::builder().build()?.post(&url))?;
+    let response = request.json(&data).send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        handle_error(status, response.text().await?)
+    } else {
+        Ok(())
+    }
+}
+
+pub async fn access_delete(id: String) -> Result<()> {
+    let url = build_url(format!("/v1/access/{id}"))?;
+    let request = add_authorization_header(Client::builder().build()?.delete(&url))?;
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        handle_error(status, response.text().await?)
+    } else {
+        Ok(())
+    }
+}
+
 pub async fn list() -> Result<Vec<ListResponse>> {
     let url = build_url("/v1/list")?;
     let request = add_authorization_header(Client::builder().build()?.get(&url))?;
@@ -418,6 +470,18 @@ pub async fn artifacts(params: ArtifactsQueryParams) -> Result<Vec<ArtifactRespo
     }
 }
 
+pub async fn timeline(params: TimelineQueryParams) -> Result<Vec<TimelineJobResponse>> {
+    let url = build_url("/v1/timeline")?;
+    let request = add_authorization_header(Client::builder().build()?.get(&url))?;
+    let response = request.query(&params).send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        handle_error(status, response.text().await?)
+    } else {
+        Ok(response.json().await?)
+    }
+}
+
 pub async fn artifact_delete(id: String) -> Result<()> {
     let url = build_url(format!("/v1/artifacts/{id}"))?;
     let request = add_authorization_header(Client::builder().build()?.delete(&url))?;
@@ -2,8 +2,8 @@ use crate::{
     context::{AppDialog, AppDialogContent},
     pages::{
         home::{
-            CronJobInsert, CronJobUpdate, CronJobs, Dashboard, History, Home, Monit, PipelineInfo,
-            Pipelines, RunPipeline,
+            AccessGrants, CronJobInsert, CronJobUpdate, CronJobs, Dashboard, History, Home, Monit,
+            PipelineInfo, Pipelines, RunPipeline,
         },
         login::Login,
         not_found::NotFound,
@@ -39,6 +39,7 @@ pub fn App() -> impl IntoView {
                             <Route path="/cron" view=CronJobs />
                             <Route path="/cron/insert" view=CronJobInsert />
                             <Route path="/cron/update" view=CronJobUpdate />
+                            <Route path="/access" view=AccessGrants />
                             <Route path="/monit" view=Monit />
                         </Route>
                         <Route path="/login" view=Login />
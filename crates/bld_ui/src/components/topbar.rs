@@ -31,6 +31,7 @@ pub fn Topbar(children: Children) -> impl IntoView {
                 <TopbarItem icon="iconoir-book" text="History" url="/history" />
                 <TopbarItem icon="iconoir-wrench" text="Pipelines" url="/pipelines" />
                 <TopbarItem icon="iconoir-clock-rotate-right" text="Cron jobs" url="/cron" />
+                <TopbarItem icon="iconoir-lock" text="Access" url="/access" />
             </nav>
             <div class="flex items-center gap-2">{children()}</div>
         </header>
@@ -15,6 +15,15 @@ impl RefreshCronJobs {
     }
 }
 
+#[derive(Copy, Clone)]
+pub struct RefreshAccessGrants(pub RwSignal<()>);
+
+impl RefreshAccessGrants {
+    pub fn set(&self) {
+        self.0.set(());
+    }
+}
+
 #[derive(Copy, Clone)]
 pub struct RefreshHistory(pub RwSignal<()>);
 
@@ -0,0 +1,121 @@
+use crate::{
+    api,
+    components::{
+        button::Button,
+        colors::Colors,
+        input::{Input, Select, SelectItem},
+    },
+    context::RefreshAccessGrants,
+    error::SmallError,
+};
+use anyhow::{Result, anyhow};
+use bld_models::dtos::{AccessRole, AccessSubject, AddAccessGrantRequest};
+use leptos::*;
+
+type AddActionArgs = (
+    Option<String>,
+    Option<String>,
+    String,
+    String,
+    RwSignal<Option<String>>,
+    Option<RefreshAccessGrants>,
+);
+
+async fn add(
+    role: Option<String>,
+    subject_type: Option<String>,
+    subject: String,
+    pipeline: String,
+) -> Result<()> {
+    let role: AccessRole = role
+        .unwrap_or_default()
+        .parse()
+        .map_err(|e| anyhow!("{e}"))?;
+    let subject_type: AccessSubject = subject_type
+        .unwrap_or_default()
+        .parse()
+        .map_err(|e| anyhow!("{e}"))?;
+    let data = AddAccessGrantRequest::new(role, subject_type, subject, pipeline);
+    api::access_insert(data).await
+}
+
+#[component]
+pub fn AccessGrantForm() -> impl IntoView {
+    let refresh = use_context::<RefreshAccessGrants>();
+    let role = create_rw_signal(Some(AccessRole::Viewer.to_string()));
+    let subject_type = create_rw_signal(Some(AccessSubject::User.to_string()));
+    let subject = create_rw_signal(String::new());
+    let pipeline = create_rw_signal(String::new());
+    let error = create_rw_signal(None);
+
+    let (roles, _set_roles) = create_signal(
+        AccessRole::all()
+            .into_iter()
+            .map(|role| SelectItem {
+                value: role.to_string(),
+                label: role.to_string(),
+            })
+            .collect::<Vec<SelectItem>>(),
+    );
+    let (subject_types, _set_subject_types) = create_signal(
+        [AccessSubject::User, AccessSubject::Group]
+            .into_iter()
+            .map(|subject| SelectItem {
+                value: subject.to_string(),
+                label: subject.to_string(),
+            })
+            .collect::<Vec<SelectItem>>(),
+    );
+
+    let add_action = create_action(|args: &AddActionArgs| {
+        let (role, subject_type, subject, pipeline, error, refresh) = args.clone();
+        async move {
+            match add(role, subject_type, subject, pipeline).await {
+                Ok(_) => {
+                    error.set(None);
+                    let _ = refresh.map(|x| x.set());
+                }
+                Err(e) => error.set(Some(e.to_string())),
+            }
+        }
+    });
+
+    view! {
+        <div class="flex flex-col gap-3">
+            <div class="flex gap-4">
+                <div class="min-w-[140px]">
+                    <Select items=roles value=role />
+                </div>
+                <div class="min-w-[120px]">
+                    <Select items=subject_types value=subject_type />
+                </div>
+                <div class="grow">
+                    <Input placeholder="User or group" value=subject />
+                </div>
+                <div class="grow">
+                    <Input placeholder="Pipeline prefix" value=pipeline />
+                </div>
+                <Button
+                    color=Colors::Violet
+                    class="w-20"
+                    on:click=move |_| {
+                        add_action
+                            .dispatch((
+                                role.get(),
+                                subject_type.get(),
+                                subject.get(),
+                                pipeline.get(),
+                                error,
+                                refresh,
+                            ));
+                    }
+                >
+                    "Add"
+                </Button>
+            </div>
+            <Show when=move || error.get().is_some() fallback=|| view! {}>
+                <SmallError error=move || error.get().unwrap() />
+            </Show>
+        </div>
+    }
+}
@@ -0,0 +1,33 @@
+mod delete;
+mod form;
+mod table;
+
+use crate::context::RefreshAccessGrants;
+use form::AccessGrantForm;
+use leptos::*;
+
+pub use table::AccessGrantsTable;
+
+#[component]
+pub fn AccessGrants() -> impl IntoView {
+    provide_context(RefreshAccessGrants(create_rw_signal(())));
+
+    view! {
+        <div class="flex flex-col min-h-full">
+            <div class="px-6 py-5 border-b border-zinc-800 flex items-center gap-4">
+                <div class="grow">
+                    <div class="text-lg font-semibold text-white">"Access"</div>
+                    <div class="text-xs text-zinc-500 mt-0.5">
+                        "Roles granted to users and groups for the pipelines of the server"
+                    </div>
+                </div>
+            </div>
+            <div class="px-6 py-3 border-b border-zinc-800/60">
+                <AccessGrantForm />
+            </div>
+            <div class="px-6 py-5">
+                <AccessGrantsTable />
+            </div>
+        </div>
+    }
+}
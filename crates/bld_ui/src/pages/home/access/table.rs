@@ -0,0 +1,66 @@
+use super::delete::AccessGrantDeleteButton;
+use crate::{
+    api,
+    components::table::{Body, Cell, Header, Headers, Row, Table},
+    context::RefreshAccessGrants,
+    error::Error,
+};
+use leptos::{leptos_dom::logging, *};
+
+#[component]
+pub fn AccessGrantsTable() -> impl IntoView {
+    let refresh = use_context::<RefreshAccessGrants>();
+
+    let data = create_resource(
+        || (),
+        |_| async move { api::access().await.map_err(|e| e.to_string()) },
+    );
+
+    let _ = watch(
+        move || {
+            if let Some(RefreshAccessGrants(refresh)) = refresh {
+                refresh.get();
+            } else {
+                logging::console_error("Refresh access grants signal not found in context");
+            }
+        },
+        move |_, _, _| data.refetch(),
+        false,
+    );
+
+    view! {
+        <Show when=move || matches!(data.get(), Some(Err(_))) fallback=|| view! {}>
+            <Error error=move || data.get().unwrap().unwrap_err() />
+        </Show>
+        <Show when=move || matches!(data.get(), Some(Ok(_))) fallback=|| view! {}>
+            <Table>
+                <Headers>
+                    <Header>"Role"</Header>
+                    <Header>"Subject type"</Header>
+                    <Header>"Subject"</Header>
+                    <Header>"Pipeline prefix"</Header>
+                    <Header>"Date created"</Header>
+                    <Header>"Actions"</Header>
+                </Headers>
+                <Body>
+                    <For
+                        each=move || data.get().unwrap().unwrap().into_iter()
+                        key=move |e| e.id.clone()
+                        let:child
+                    >
+                        <Row>
+                            <Cell>{child.role.to_string()}</Cell>
+                            <Cell>{child.subject_type.to_string()}</Cell>
+                            <Cell>{child.subject}</Cell>
+                            <Cell>{child.pipeline}</Cell>
+                            <Cell>{child.date_created}</Cell>
+                            <Cell>
+                                <AccessGrantDeleteButton id=child.id />
+                            </Cell>
+                        </Row>
+                    </For>
+                </Body>
+            </Table>
+        </Show>
+    }
+}
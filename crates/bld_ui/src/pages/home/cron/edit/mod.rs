@@ -14,6 +14,7 @@ pub type SaveCronJob = (
     String,
     HashMap<String, RwSignal<String>>,
     HashMap<String, RwSignal<String>>,
+    i32,
 );
 
 #[component]
@@ -26,7 +27,11 @@ pub fn CronJobsEdit(
     let schedule = create_rw_signal(String::new());
     let variables = create_rw_signal(HashMap::new());
     let environment = create_rw_signal(HashMap::new());
-    let save_data = move || (schedule.get(), variables.get(), environment.get());
+    let priority = create_rw_signal(String::new());
+    let save_data = move || {
+        let priority = priority.get().trim().parse().unwrap_or_default();
+        (schedule.get(), variables.get(), environment.get(), priority)
+    };
 
     create_effect(move |_| {
         let (Some(cron), Some(pipeline)) = (cron.get(), pipeline.get()) else {
@@ -34,6 +39,7 @@ pub fn CronJobsEdit(
         };
 
         schedule.set(cron.schedule);
+        priority.set(cron.priority.to_string());
         let (vars, env) = pipeline.into_variables();
 
         if let Some(vars) = vars {
@@ -57,7 +63,7 @@ pub fn CronJobsEdit(
                     delete=delete
                 />
                 <div class="px-6 py-5 flex flex-col gap-4">
-                    <CronJobsEditSchedule schedule=schedule />
+                    <CronJobsEditSchedule schedule=schedule priority=priority />
                     <Show when=move || !variables.get().is_empty() fallback=|| view! {}>
                         <RunPipelineVariables
                             title="Variables"
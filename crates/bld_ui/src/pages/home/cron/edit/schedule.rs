@@ -2,7 +2,10 @@ use crate::components::{card::Card, input::Input, link::Link};
 use leptos::*;
 
 #[component]
-pub fn CronJobsEditSchedule(#[prop(into)] schedule: RwSignal<String>) -> impl IntoView {
+pub fn CronJobsEditSchedule(
+    #[prop(into)] schedule: RwSignal<String>,
+    #[prop(into)] priority: RwSignal<String>,
+) -> impl IntoView {
     view! {
         <Card>
             <div class="flex flex-col px-6 py-5 gap-4">
@@ -20,6 +23,12 @@ pub fn CronJobsEditSchedule(#[prop(into)] schedule: RwSignal<String>) -> impl In
                         <Input value=schedule />
                     </div>
                 </div>
+                <div class="grid grid-cols-3 items-center gap-4">
+                    <div class="text-sm text-zinc-400">"Priority"</div>
+                    <div class="col-span-2">
+                        <Input value=priority />
+                    </div>
+                </div>
             </div>
         </Card>
     }
@@ -38,13 +38,12 @@ pub fn CronJobInsert() -> impl IntoView {
         }
     };
 
-    let data = create_resource(
-        move || name(),
-        |name| async move { get_pipeline(name).await.map_err(|e| e.to_string()) },
-    );
+    let data = create_resource(name, |name| async move {
+        get_pipeline(name).await.map_err(|e| e.to_string())
+    });
 
     let save_action = create_action(|args: &SaveActionArgs| {
-        let (name, dialog, dialog_content, (schedule, vars, env)) = args.clone();
+        let (name, dialog, dialog_content, (schedule, vars, env, priority)) = args.clone();
         let vars = hash_map_strings(vars);
         let env = hash_map_strings(env);
 
@@ -52,7 +51,14 @@ pub fn CronJobInsert() -> impl IntoView {
             let Some(name) = name else {
                 return;
             };
-            let data = AddJobRequest::new(schedule, name.to_string(), Some(vars), Some(env), false);
+            let data = AddJobRequest::new(
+                schedule,
+                name.to_string(),
+                Some(vars),
+                Some(env),
+                false,
+                priority,
+            );
             match api::cron_insert(data).await {
                 Ok(_) => {
                     let nav = use_navigate();
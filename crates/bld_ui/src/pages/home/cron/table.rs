@@ -52,6 +52,7 @@ pub fn CronJobsTable(#[prop(into)] params: Signal<Option<JobFiltersParams>>) ->
                     <Header>"Pipeline"</Header>
                     <Header>"Schedule"</Header>
                     <Header>"Default"</Header>
+                    <Header>"Priority"</Header>
                     <Header>"Date created"</Header>
                     <Header>"Date updated"</Header>
                     <Header>"Actions"</Header>
@@ -74,6 +75,7 @@ pub fn CronJobsTable(#[prop(into)] params: Signal<Option<JobFiltersParams>>) ->
                                     <Cell>{child.pipeline}</Cell>
                                     <Cell>{child.schedule}</Cell>
                                     <Cell>{child.is_default}</Cell>
+                                    <Cell>{child.priority}</Cell>
                                     <Cell>{child.date_created}</Cell>
                                     <Cell>{child.date_updated.unwrap_or_default()}</Cell>
                                     <Cell>
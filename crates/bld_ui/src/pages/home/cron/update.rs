@@ -30,15 +30,12 @@ pub fn CronJobUpdate() -> impl IntoView {
     let app_dialog = use_context::<AppDialog>();
     let app_dialog_content = use_context::<AppDialogContent>();
 
-    let data = create_resource(
-        move || id(),
-        |id| async move {
-            let cron = get_cron(id).await.map_err(|e| e.to_string())?;
-            let name = cron.pipeline.clone();
-            let pipeline = get_pipeline(Some(name)).await.map_err(|e| e.to_string())?;
-            Ok::<(CronJobResponse, VersionedFile), String>((cron, pipeline))
-        },
-    );
+    let data = create_resource(id, |id| async move {
+        let cron = get_cron(id).await.map_err(|e| e.to_string())?;
+        let name = cron.pipeline.clone();
+        let pipeline = get_pipeline(Some(name)).await.map_err(|e| e.to_string())?;
+        Ok::<(CronJobResponse, VersionedFile), String>((cron, pipeline))
+    });
 
     let cron = move || match data.get() {
         Some(Ok((cron, _))) => Some(cron),
@@ -51,7 +48,7 @@ pub fn CronJobUpdate() -> impl IntoView {
     };
 
     let save_action = create_action(|args: &UpdateActionArgs| {
-        let (id, dialog, content, (schedule, vars, env)) = args.clone();
+        let (id, dialog, content, (schedule, vars, env, priority)) = args.clone();
         let vars = hash_map_strings(vars);
         let env = hash_map_strings(env);
 
@@ -59,7 +56,13 @@ pub fn CronJobUpdate() -> impl IntoView {
             let Some(id) = id else {
                 return;
             };
-            let data = UpdateJobRequest::new(id.to_string(), schedule, Some(vars), Some(env));
+            let data = UpdateJobRequest::new(
+                id.to_string(),
+                schedule,
+                Some(vars),
+                Some(env),
+                Some(priority),
+            );
             match api::cron_update(data).await {
                 Ok(_) => {
                     let nav = use_navigate();
@@ -65,7 +65,7 @@ pub fn DashboardRunsPerMonth() -> impl IntoView {
                 </Show>
                 <div class="grow">
                     <Chart
-                        aspect_ratio=Signal::from(move || aspect_ratio())
+                        aspect_ratio=Signal::from(aspect_ratio)
                         series=series
                         data=move || chart_data()
                         left=TickLabels::aligned_floats()
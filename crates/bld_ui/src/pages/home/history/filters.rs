@@ -24,6 +24,10 @@ pub fn HistoryFilters(
             value: "running".to_string(),
             label: "Running".to_string(),
         },
+        SelectItem {
+            value: "waiting-for-approval".to_string(),
+            label: "Waiting for approval".to_string(),
+        },
         SelectItem {
             value: "finished".to_string(),
             label: "Finished".to_string(),
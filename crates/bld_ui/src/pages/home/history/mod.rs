@@ -16,6 +16,7 @@ fn get_params(state: Option<String>, limit: String, pipeline: String) -> Option<
         },
         state: state.filter(|x| x != "all"),
         limit: limit.parse::<u64>().unwrap_or(100),
+        details: false,
     };
     Some(params)
 }
@@ -20,21 +20,31 @@ async fn get_hist(params: Option<HistQueryParams>) -> Result<Vec<HistoryEntry>>
 }
 
 #[component]
-pub fn HistoryEntryState(#[prop(into)] state: String) -> impl IntoView {
+pub fn HistoryEntryState(
+    #[prop(into)] state: String,
+    #[prop(into)] waiting_on_group: Option<String>,
+    #[prop(into)] fault_reason: Option<String>,
+) -> impl IntoView {
     let (icon, label, class) = match state.as_str() {
         "initial" => ("iconoir-running", "Intial", "bg-yellow-600"),
         "queued" => ("iconoir-clock", "Queued", ""),
         "running" => ("iconoir-running", "Running", ""),
+        "waiting-for-approval" => ("iconoir-pause", "Waiting for approval", "bg-yellow-600"),
         "finished" => ("iconoir-check-circle", "Finished", "bg-emerable-600"),
         "faulted" => ("iconoir-minus-circle", "Faulted", "bg-red-600"),
         _ => ("", "Unknown", "bg-black"),
     };
 
     let icon = format!("{icon} mr-2");
+    let label = match waiting_on_group {
+        Some(group) if state == "queued" => format!("Waiting on group {group}"),
+        _ => label.to_string(),
+    };
+    let reason = fault_reason.filter(|_| state == "faulted");
 
     view! {
         <Badge class=class>
-            <div class="flex items-center">
+            <div class="flex items-center" title=reason>
                 <i class=icon></i>
                 {label}
             </div>
@@ -74,6 +84,7 @@ pub fn HistoryTable(#[prop(into)] params: Signal<Option<HistQueryParams>>) -> im
                     <Header>"Id"</Header>
                     <Header>"Name"</Header>
                     <Header>"User"</Header>
+                    <Header>"Trigger"</Header>
                     <Header>"Start Date"</Header>
                     <Header>"End Date"</Header>
                     <Header>"State"</Header>
@@ -82,23 +93,31 @@ pub fn HistoryTable(#[prop(into)] params: Signal<Option<HistQueryParams>>) -> im
                     <For
                         each=move || data.get().unwrap().unwrap().into_iter()
                         key=move |e| e.id.clone()
-                        let:child
-                    >
-                        <Row>
-                            <Cell>
-                                <Link href=format!("/monit?id={}", child.id)>{child.id}</Link>
-                            </Cell>
-                            <Cell>{child.name}</Cell>
-                            <Cell>
-                                <UserPill name=move || child.user.clone() />
-                            </Cell>
-                            <Cell>{child.start_date_time.unwrap_or_default()}</Cell>
-                            <Cell>{child.end_date_time.unwrap_or_default()}</Cell>
-                            <Cell>
-                                <HistoryEntryState state=child.state />
-                            </Cell>
-                        </Row>
-                    </For>
+                        children=move |child| {
+                            let trigger = child.display_trigger();
+                            view! {
+                                <Row>
+                                    <Cell>
+                                        <Link href=format!("/monit?id={}", child.id)>{child.id}</Link>
+                                    </Cell>
+                                    <Cell>{child.name}</Cell>
+                                    <Cell>
+                                        <UserPill name=move || child.user.clone() />
+                                    </Cell>
+                                    <Cell>{trigger}</Cell>
+                                    <Cell>{child.start_date_time.unwrap_or_default()}</Cell>
+                                    <Cell>{child.end_date_time.unwrap_or_default()}</Cell>
+                                    <Cell>
+                                        <HistoryEntryState
+                                            state=child.state
+                                            waiting_on_group=child.waiting_on_group
+                                            fault_reason=child.fault_reason
+                                        />
+                                    </Cell>
+                                </Row>
+                            }
+                        }
+                    />
                 </Body>
             </Table>
         </Show>
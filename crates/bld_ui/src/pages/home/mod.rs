@@ -1,9 +1,11 @@
+mod access;
 mod cron;
 mod dashboard;
 mod history;
 mod monit;
 mod pipelines;
 
+pub use access::*;
 pub use cron::*;
 pub use dashboard::*;
 pub use history::*;
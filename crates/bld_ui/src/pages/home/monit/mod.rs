@@ -1,5 +1,6 @@
 mod artifacts;
 mod logs;
+mod timeline;
 
 use crate::{
     api::{self, build_ws_url, get_access_token},
@@ -11,7 +12,7 @@ use crate::{
     context::{AppDialog, AppDialogContent, RefreshArtifacts},
     error::ErrorDialog,
 };
-use bld_models::dtos::MonitInfo;
+use bld_models::dtos::{ExecClientMessage, MonitInfo};
 use codee::string::FromToStringCodec;
 use leptos::{html::Dialog, leptos_dom::logging, *};
 use leptos_router::*;
@@ -20,15 +21,18 @@ use leptos_use::{
     use_websocket_with_options,
 };
 
-use {artifacts::MonitArtifacts, logs::MonitLogs};
+use {artifacts::MonitArtifacts, logs::MonitLogs, timeline::MonitTimeline};
 
 type StopActionArgs = (String, NodeRef<Dialog>, RwSignal<Option<View>>);
+type RerunActionArgs = (String, NodeRef<Dialog>, RwSignal<Option<View>>);
+type ApproveActionArgs = (String, bool, NodeRef<Dialog>, RwSignal<Option<View>>);
 
 #[derive(Clone, Default, Eq, PartialEq)]
 enum MenuItem {
     #[default]
     Logs,
     Artifacts,
+    Timeline,
 }
 
 #[component]
@@ -113,6 +117,52 @@ pub fn Monit() -> impl IntoView {
         }
     });
 
+    let rerun_action = create_action(|args: &RerunActionArgs| {
+        let (run_id, dialog, content) = args.clone();
+        async move {
+            match api::run(ExecClientMessage::Rerun { run_id }).await {
+                // the page is reloaded so that the socket monitors the new run from the start.
+                Ok(id) => {
+                    let _ = window().location().set_href(&format!("/monit?id={id}"));
+                }
+                Err(e) => {
+                    content.set(Some(
+                        view! { <ErrorDialog dialog=dialog error=move || e.to_string() /> },
+                    ));
+                    let _ = dialog.get().map(|x| x.show_modal());
+                }
+            }
+        }
+    });
+
+    let approve_action = create_action(|args: &ApproveActionArgs| {
+        let (id, approved, dialog, content) = args.clone();
+        async move {
+            if let Err(e) = api::approve(id, approved).await {
+                content.set(Some(
+                    view! { <ErrorDialog dialog=dialog error=move || e.to_string() /> },
+                ));
+                let _ = dialog.get().map(|x| x.show_modal());
+            }
+        }
+    });
+
+    let approve = move |approved: bool| {
+        let Some(id) = id() else {
+            logging::console_error("Pipeline run id not provided in url");
+            return;
+        };
+        let Some(AppDialog(dialog)) = app_dialog else {
+            logging::console_error("App dialog context not found");
+            return;
+        };
+        let Some(AppDialogContent(content)) = app_dialog_content else {
+            logging::console_error("App dialog context not found");
+            return;
+        };
+        approve_action.dispatch((id, approved, dialog, content));
+    };
+
     view! {
         <div class="flex flex-col min-h-full">
             <div class="px-6 py-5 border-b border-zinc-800 grid grid-cols-4 items-center gap-4">
@@ -136,10 +186,46 @@ pub fn Monit() -> impl IntoView {
                         >
                             "Artifacts"
                         </Tab>
+                        <Tab
+                            is_selected=move || selected.get() == MenuItem::Timeline
+                            on:click=move |_| selected.set(MenuItem::Timeline)
+                        >
+                            "Timeline"
+                        </Tab>
                     </Tabs>
                 </div>
                 <div class="flex items-center justify-end gap-4">
                     <div class="text-xs text-zinc-500">"Socket: " {move || socket_state()}</div>
+                    <div class="w-24 shrink-0">
+                        <Button on:click=move |_| approve(true)>"Approve"</Button>
+                    </div>
+                    <div class="w-24 shrink-0">
+                        <Button ghost=true on:click=move |_| approve(false)>
+                            "Reject"
+                        </Button>
+                    </div>
+                    <div class="w-24 shrink-0">
+                        <Button
+                            ghost=true
+                            on:click=move |_| {
+                                let Some(id) = id() else {
+                                    logging::console_error("Pipeline run id not provided in url");
+                                    return;
+                                };
+                                let Some(AppDialog(dialog)) = app_dialog else {
+                                    logging::console_error("App dialog context not found");
+                                    return;
+                                };
+                                let Some(AppDialogContent(content)) = app_dialog_content else {
+                                    logging::console_error("App dialog context not found");
+                                    return;
+                                };
+                                rerun_action.dispatch((id, dialog, content));
+                            }
+                        >
+                            "Re-run"
+                        </Button>
+                    </div>
                     <div class="w-24 shrink-0">
                         <Button
                             color=Colors::Red
@@ -174,6 +260,12 @@ pub fn Monit() -> impl IntoView {
                 >
                     <MonitArtifacts run_id=Signal::derive(id) />
                 </Show>
+                <Show
+                    when=move || matches!(selected.get(), MenuItem::Timeline)
+                    fallback=|| view! {}
+                >
+                    <MonitTimeline run_id=Signal::derive(id) />
+                </Show>
             </div>
         </div>
     }
@@ -0,0 +1,169 @@
+use crate::{
+    api,
+    components::{button::IconButton, colors::Colors},
+    error::Error,
+};
+use anyhow::{Result, anyhow};
+use bld_models::dtos::{TimelineJobResponse, TimelineQueryParams};
+use leptos::*;
+
+async fn get_timeline(run_id: Option<String>) -> Result<Vec<TimelineJobResponse>> {
+    let run_id = run_id.ok_or_else(|| anyhow!("Run id not provided"))?;
+    api::timeline(TimelineQueryParams { run_id }).await
+}
+
+fn state_class(state: &str) -> &'static str {
+    match state {
+        "running" => "bg-violet-600",
+        "completed" => "bg-emerald-600",
+        "continued-on-error" => "bg-yellow-600",
+        "failed" => "bg-red-600",
+        "cancelled" => "bg-zinc-600",
+        _ => "bg-black",
+    }
+}
+
+fn label(name: &str, matrix: Option<&String>) -> String {
+    match matrix {
+        Some(matrix) => format!("{name} {matrix}"),
+        None => name.to_string(),
+    }
+}
+
+fn duration(start: Option<i64>, end: i64) -> String {
+    let Some(start) = start else {
+        return String::new();
+    };
+    let seconds = (end - start).max(0) / 1000;
+    format!("{}m {}s", seconds / 60, seconds % 60)
+}
+
+/// The range that all bars are placed in, from the earliest start to the latest end of
+/// the jobs. Entries that are still running are considered to end now.
+fn bounds(jobs: &[TimelineJobResponse], now: i64) -> (i64, i64) {
+    let start = jobs
+        .iter()
+        .filter_map(|x| x.start_millis)
+        .min()
+        .unwrap_or(now);
+    let end = jobs
+        .iter()
+        .map(|x| x.end_millis.unwrap_or(now))
+        .max()
+        .unwrap_or(now);
+    (start, end.max(start + 1))
+}
+
+#[component]
+fn TimelineBar(
+    #[prop(into)] name: String,
+    #[prop(into)] state: String,
+    #[prop(into)] error: Option<String>,
+    start_millis: Option<i64>,
+    end_millis: Option<i64>,
+    bounds: (i64, i64),
+    now: i64,
+    #[prop(default = false)] nested: bool,
+) -> impl IntoView {
+    let (min, max) = bounds;
+    let end = end_millis.unwrap_or(now);
+    let start = start_millis.unwrap_or(end);
+    let total = (max - min) as f64;
+    let left = (start - min) as f64 / total * 100.0;
+    let width = ((end - start) as f64 / total * 100.0).max(0.5);
+    let bar_style = format!("left: {left:.2}%; width: {width:.2}%;");
+    let bar_class = format!("absolute h-4 rounded {}", state_class(&state));
+    let name_class = if nested {
+        "w-64 shrink-0 truncate pl-6 text-zinc-400"
+    } else {
+        "w-64 shrink-0 truncate font-semibold text-white"
+    };
+    let title = match error {
+        Some(error) => format!("{state}: {error}"),
+        None => state,
+    };
+
+    view! {
+        <div class="flex items-center gap-4 text-sm" title=title>
+            <div class=name_class>{name}</div>
+            <div class="relative grow h-4 bg-zinc-800 rounded">
+                <div class=bar_class style=bar_style></div>
+            </div>
+            <div class="w-20 shrink-0 text-right text-zinc-500">
+                {duration(start_millis, end)}
+            </div>
+        </div>
+    }
+}
+
+#[component]
+pub fn MonitTimeline(#[prop(into)] run_id: Signal<Option<String>>) -> impl IntoView {
+    let data = create_resource(
+        move || run_id.get(),
+        |run_id| async move { get_timeline(run_id).await.map_err(|e| e.to_string()) },
+    );
+
+    view! {
+        <div class="px-6 py-5 grow flex flex-col gap-4">
+            <div class="flex justify-end">
+                <IconButton
+                    icon="iconoir-refresh-double"
+                    ghost=true
+                    color=Colors::Violet
+                    on:click=move |_| data.refetch()
+                />
+            </div>
+            <Show when=move || matches!(data.get(), Some(Err(_))) fallback=|| view! {}>
+                <Error error=move || data.get().unwrap().unwrap_err() />
+            </Show>
+            <Show when=move || matches!(data.get(), Some(Ok(_))) fallback=|| view! {}>
+                {move || {
+                    let jobs = data.get().unwrap().unwrap();
+                    let now = js_sys::Date::now() as i64;
+                    let bounds = bounds(&jobs, now);
+                    view! {
+                        <div class="flex flex-col gap-2">
+                            <For
+                                each=move || jobs.clone().into_iter()
+                                key=|e| e.id.clone()
+                                children=move |job| {
+                                    let steps = job.steps.clone();
+                                    view! {
+                                        <TimelineBar
+                                            name=label(&job.name, job.matrix.as_ref())
+                                            state=job.state
+                                            error=job.error
+                                            start_millis=job.start_millis
+                                            end_millis=job.end_millis
+                                            bounds=bounds
+                                            now=now
+                                        />
+                                        <For
+                                            each=move || steps.clone().into_iter()
+                                            key=|e| e.id.clone()
+                                            children=move |step| {
+                                                let name = step.name.as_deref().unwrap_or(&step.step_id);
+                                                view! {
+                                                    <TimelineBar
+                                                        name=label(name, step.matrix.as_ref())
+                                                        state=step.state.clone()
+                                                        error=step.error.clone()
+                                                        start_millis=step.start_millis
+                                                        end_millis=step.end_millis
+                                                        bounds=bounds
+                                                        now=now
+                                                        nested=true
+                                                    />
+                                                }
+                                            }
+                                        />
+                                    }
+                                }
+                            />
+                        </div>
+                    }
+                }}
+            </Show>
+        </div>
+    }
+}
@@ -13,6 +13,7 @@ pub fn PipelineHist(#[prop(into)] name: Signal<Option<String>>) -> impl IntoView
             name: Some(n),
             state: None,
             limit: 10000,
+            details: false,
         })
     };
 
@@ -28,10 +28,9 @@ pub fn PipelineInfo() -> impl IntoView {
     let id = move || params.with(|p| p.get("id").cloned());
     let name = move || params.with(|p| p.get("name").cloned());
 
-    let data = create_resource(
-        move || id(),
-        |id| async move { get_pipeline(id).await.map_err(|e| e.to_string()) },
-    );
+    let data = create_resource(id, |id| async move {
+        get_pipeline(id).await.map_err(|e| e.to_string())
+    });
     let selected_menu_item = create_rw_signal(menu::MenuItem::RawFile);
 
     provide_context(RefreshHistory(create_rw_signal(())));
@@ -46,6 +46,8 @@ async fn start_run(
         name: name.to_string(),
         inputs: Some(vars),
         env: Some(env),
+        parent_run_id: None,
+        priority: None,
     };
     api::run(data).await
 }
@@ -74,10 +76,9 @@ pub fn RunPipeline() -> impl IntoView {
     let app_dialog = use_context::<AppDialog>();
     let app_dialog_content = use_context::<AppDialogContent>();
 
-    let data = create_resource(
-        move || id(),
-        |id| async move { get_pipeline(id).await.map_err(|e| e.to_string()) },
-    );
+    let data = create_resource(id, |id| async move {
+        get_pipeline(id).await.map_err(|e| e.to_string())
+    });
 
     let start_run = create_action(|args: &RequestInterRepr| {
         let (name, vars, env, dialog, dialog_content) = args.clone();
@@ -0,0 +1,20 @@
+use subtle::ConstantTimeEq;
+
+/// Compares two secrets in constant time, so that the time of a failed comparison
+/// doesn't reveal how much of the expected value was matched.
+pub fn constant_time_eq(left: &str, right: &str) -> bool {
+    left.as_bytes().ct_eq(right.as_bytes()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::constant_time_eq;
+
+    #[test]
+    pub fn constant_time_eq_success() {
+        assert!(constant_time_eq("secret", "secret"));
+        assert!(!constant_time_eq("secret", "secreT"));
+        assert!(!constant_time_eq("secret", "secret2"));
+        assert!(!constant_time_eq("secret", ""));
+    }
+}
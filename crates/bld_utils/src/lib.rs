@@ -1,3 +1,4 @@
+pub mod crypto;
 pub mod fs;
 pub mod shell;
 pub mod sync;